@@ -1,6 +1,8 @@
 #![warn(clippy::all)]
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use zkp_elliptic_curve::{mul, Affine, Jacobian};
+use zkp_elliptic_curve::{
+    double_scalar_mul, mul, mul_jacobian, Affine, Jacobian, ModifiedJacobian, GENERATOR,
+};
 use zkp_macros_decl::u256h;
 use zkp_primefield::FieldElement;
 use zkp_u256::U256;
@@ -176,6 +178,15 @@ fn jacobian_mul_affine(crit: &mut Criterion) {
     });
 }
 
+fn jacobian_mul_ct(crit: &mut Criterion) {
+    let b = u256h!("014023b44fbb1e6f2a79c929c6da775be3c4b9e043d439385b5050fdc69177e3");
+    crit.bench_function("Jacobian mul ct", move |bench| {
+        bench.iter(|| {
+            black_box(Jacobian::mul_ct(black_box(&GENERATOR), black_box(&b)));
+        })
+    });
+}
+
 fn wnaf_mul_affine(crit: &mut Criterion) {
     let a = Affine::Point {
         x: FieldElement::from_montgomery(u256h!(
@@ -193,6 +204,88 @@ fn wnaf_mul_affine(crit: &mut Criterion) {
     });
 }
 
+fn modified_jacobian_add_affine(crit: &mut Criterion) {
+    let a = ModifiedJacobian::from(&Affine::Point {
+        x: FieldElement::from_montgomery(u256h!(
+            "04f50f81bf91b7ada9de33eeec4ae787bc39f520fbb5c8fa4620fecfca4d7cf5"
+        )),
+        y: FieldElement::from_montgomery(u256h!(
+            "0176a4c00d1ce6b642176e460624b1699da148593f701cac4df2280c2edb163f"
+        )),
+    });
+    let b = Affine::Point {
+        x: FieldElement::from_montgomery(u256h!(
+            "03722d346a64345ec69b4a36c97247fa924bedfbd371d5bdedeb7db3fcf32a78"
+        )),
+        y: FieldElement::from_montgomery(u256h!(
+            "07444fb1e7e4751935707758c5b9bb6bc270056bc12a00d1f5b82ba217a20876"
+        )),
+    };
+    crit.bench_function("Modified Jacobian add affine", move |bench| {
+        bench.iter(|| {
+            let mut r = black_box(&a).clone();
+            r += black_box(&b);
+            black_box(r);
+        })
+    });
+}
+
+fn modified_jacobian_dbl(crit: &mut Criterion) {
+    let a = ModifiedJacobian::from(&Affine::Point {
+        x: FieldElement::from_montgomery(u256h!(
+            "04f50f81bf91b7ada9de33eeec4ae787bc39f520fbb5c8fa4620fecfca4d7cf5"
+        )),
+        y: FieldElement::from_montgomery(u256h!(
+            "0176a4c00d1ce6b642176e460624b1699da148593f701cac4df2280c2edb163f"
+        )),
+    });
+    crit.bench_function("Modified Jacobian dbl", move |bench| {
+        bench.iter(|| {
+            black_box(black_box(&a).double());
+        })
+    });
+}
+
+fn wnaf_mul_jacobian(crit: &mut Criterion) {
+    let a = Affine::Point {
+        x: FieldElement::from_montgomery(u256h!(
+            "04f50f81bf91b7ada9de33eeec4ae787bc39f520fbb5c8fa4620fecfca4d7cf5"
+        )),
+        y: FieldElement::from_montgomery(u256h!(
+            "0176a4c00d1ce6b642176e460624b1699da148593f701cac4df2280c2edb163f"
+        )),
+    };
+    let b = u256h!("014023b44fbb1e6f2a79c929c6da775be3c4b9e043d439385b5050fdc69177e3");
+    crit.bench_function("Wnaf mul jacobian", move |bench| {
+        bench.iter(|| {
+            black_box(mul_jacobian(black_box(&a), black_box(&b)));
+        })
+    });
+}
+
+fn wnaf_double_scalar_mul(crit: &mut Criterion) {
+    let a = Affine::Point {
+        x: FieldElement::from_montgomery(u256h!(
+            "04f50f81bf91b7ada9de33eeec4ae787bc39f520fbb5c8fa4620fecfca4d7cf5"
+        )),
+        y: FieldElement::from_montgomery(u256h!(
+            "0176a4c00d1ce6b642176e460624b1699da148593f701cac4df2280c2edb163f"
+        )),
+    };
+    let b = u256h!("014023b44fbb1e6f2a79c929c6da775be3c4b9e043d439385b5050fdc69177e3");
+    let c = u256h!("07374b7d69dc9825fc758b28913c8d2a27be5e7c32412f612b20c9c97afbe4dd");
+    crit.bench_function("Wnaf double scalar mul", move |bench| {
+        bench.iter(|| {
+            black_box(double_scalar_mul(
+                black_box(&b),
+                black_box(&a),
+                black_box(&c),
+                black_box(&GENERATOR),
+            ));
+        })
+    });
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     curve_add(c);
     curve_dbl(c);
@@ -202,8 +295,13 @@ fn criterion_benchmark(c: &mut Criterion) {
     jacobian_dbl(c);
     jacobian_mul(c);
     jacobian_mul_affine(c);
+    jacobian_mul_ct(c);
     jacobian_to_affine(c);
+    modified_jacobian_add_affine(c);
+    modified_jacobian_dbl(c);
     wnaf_mul_affine(c);
+    wnaf_mul_jacobian(c);
+    wnaf_double_scalar_mul(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
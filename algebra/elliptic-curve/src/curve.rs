@@ -1,32 +1,74 @@
-use crate::BETA;
+use crate::{jacobian::JacobianPoint, CurveParameters, StarkCurve};
 use std::{
     ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
     prelude::v1::*,
 };
 use zkp_primefield::FieldElement;
-use zkp_u256::{commutative_binop, noncommutative_binop, U256};
+use zkp_u256::U256;
 
+/// A point in affine coordinates on the curve `C`.
+///
+/// Note: All curves are currently over the `FieldElement` base field.
 #[derive(PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub enum Affine {
+pub enum AffinePoint<C: CurveParameters> {
     Zero, // Neutral element, point at infinity, additive identity, etc.
-    Point { x: FieldElement, y: FieldElement },
+    Point { x: C::Field, y: C::Field },
 }
 
-impl Affine {
+/// A point in affine coordinates on the Stark curve.
+pub type Affine = AffinePoint<StarkCurve>;
+
+/// Reasons for rejecting a point in `AffinePoint::try_new`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointError {
+    NotOnCurve,
+    NotInSubgroup,
+}
+
+impl<C> AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     pub const ZERO: Self = Self::Zero;
 
     pub fn new(x: FieldElement, y: FieldElement) -> Self {
         Self::Point { x, y }
     }
 
+    /// Create a point from untrusted coordinates, checking `is_valid`.
+    pub fn try_new(x: FieldElement, y: FieldElement) -> Result<Self, PointError> {
+        let point = Self::Point { x, y };
+        point.validate()?;
+        Ok(point)
+    }
+
     pub fn on_curve(&self) -> bool {
         match self {
             Self::Zero => true,
-            Self::Point { x, y } => y * y == x * x * x + x + BETA,
+            Self::Point { x, y } => y * y == x * x * x + C::mul_a(x) + C::B,
         }
     }
 
+    /// Check that the point is on the curve and in the subgroup generated by
+    /// `C::GENERATOR`.
+    ///
+    /// Points from untrusted sources must be checked before use.
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    pub fn validate(&self) -> Result<(), PointError> {
+        if !self.on_curve() {
+            return Err(PointError::NotOnCurve);
+        }
+        // With a cofactor of one every point on the curve is in the subgroup.
+        if C::COFACTOR != U256::ONE && self * &C::ORDER != Self::Zero {
+            return Err(PointError::NotInSubgroup);
+        }
+        Ok(())
+    }
+
     pub fn double_assign(&mut self) {
         *self = self.double();
     }
@@ -38,7 +80,7 @@ impl Affine {
                 if *y == FieldElement::ZERO {
                     Self::Zero
                 } else {
-                    let m = ((x + x + x) * x + FieldElement::ONE) / (y + y);
+                    let m = ((x + x + x) * x + C::A) / (y + y);
                     let nx = &m * &m - x - x;
                     let ny = m * (x - &nx) - y;
                     Self::Point { x: nx, y: ny }
@@ -55,20 +97,26 @@ impl Affine {
     }
 }
 
-impl Default for Affine {
+impl<C> Default for AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     fn default() -> Self {
         Self::ZERO
     }
 }
 
-impl Neg for &Affine {
-    type Output = Affine;
+impl<C> Neg for &AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    type Output = AffinePoint<C>;
 
     fn neg(self) -> Self::Output {
         match self {
-            Affine::Zero => Affine::Zero,
-            Affine::Point { x, y } => {
-                Affine::Point {
+            AffinePoint::Zero => AffinePoint::Zero,
+            AffinePoint::Point { x, y } => {
+                AffinePoint::Point {
                     x: x.clone(),
                     y: y.neg(),
                 }
@@ -77,7 +125,22 @@ impl Neg for &Affine {
     }
 }
 
-impl AddAssign<&Affine> for Affine {
+impl<C> Neg for AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        self.neg_assign();
+        self
+    }
+}
+
+impl<C> AddAssign<&AffinePoint<C>> for AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     fn add_assign(&mut self, rhs: &Self) {
         match self {
             Self::Zero => *self = rhs.clone(),
@@ -104,45 +167,108 @@ impl AddAssign<&Affine> for Affine {
     }
 }
 
+/// Implement infix operator for a point type using the assignment version.
+///
+/// Like `commutative_binop!` from `zkp-u256`, but for types that are generic
+/// over the curve.
+#[macro_export]
+macro_rules! curve_binop {
+    ($type:ident, $trait:ident, $trait_fn:ident, $inplace:ident, $inplace_fn:ident) => {
+        impl<C> $inplace<$type<C>> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
+            fn $inplace_fn(&mut self, rhs: Self) {
+                self.$inplace_fn(&rhs)
+            }
+        }
+
+        impl<C> $trait<$type<C>> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
+            type Output = Self;
+
+            fn $trait_fn(self, rhs: Self) -> Self {
+                self.$trait_fn(&rhs)
+            }
+        }
+
+        impl<C> $trait<&$type<C>> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
+            type Output = Self;
+
+            fn $trait_fn(mut self, rhs: &Self) -> Self {
+                self.$inplace_fn(rhs);
+                self
+            }
+        }
+
+        // Note: a clone is necessary
+        impl<C> $trait<$type<C>> for &$type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
+            type Output = $type<C>;
+
+            fn $trait_fn(self, rhs: $type<C>) -> $type<C> {
+                self.clone().$trait_fn(&rhs)
+            }
+        }
+
+        // Note: a clone is necessary
+        impl<C> $trait<&$type<C>> for &$type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
+            type Output = $type<C>;
+
+            fn $trait_fn(self, rhs: &$type<C>) -> $type<C> {
+                self.clone().$trait_fn(rhs)
+            }
+        }
+    };
+}
+
 // TODO: This can be more elegantly done using traits
+/// Implements subtraction and the scalar multiplication variants, assuming
+/// `Neg for &Type`, `AddAssign<&Type>` and `Mul<&U256> for &Type`.
 #[macro_export]
 macro_rules! curve_operations {
     ($type:ident) => {
-        impl SubAssign<&$type> for $type {
+        impl<C> SubAssign<&$type<C>> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
             fn sub_assign(&mut self, rhs: &Self) {
                 *self += &rhs.neg()
             }
         }
 
-        impl Mul<&U256> for &$type {
-            type Output = $type;
-
-            fn mul(self, scalar: &U256) -> $type {
-                // OPT: Use WNAF
-                let mut r = self.clone();
-                for i in (0..scalar.msb()).rev() {
-                    r.double_assign();
-                    if scalar.bit(i) {
-                        r += self;
-                    }
-                }
-                r
-            }
-        }
-
-        impl MulAssign<&U256> for $type {
+        impl<C> MulAssign<&U256> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
             fn mul_assign(&mut self, scalar: &U256) {
                 *self = &*self * scalar;
             }
         }
 
-        impl MulAssign<U256> for $type {
+        impl<C> MulAssign<U256> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
             fn mul_assign(&mut self, scalar: U256) {
                 *self *= &scalar;
             }
         }
 
-        impl Mul<U256> for $type {
+        impl<C> Mul<U256> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
             type Output = Self;
 
             fn mul(self, scalar: U256) -> Self {
@@ -150,7 +276,10 @@ macro_rules! curve_operations {
             }
         }
 
-        impl Mul<&U256> for $type {
+        impl<C> Mul<&U256> for $type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
             type Output = Self;
 
             fn mul(self, scalar: &U256) -> Self {
@@ -158,10 +287,13 @@ macro_rules! curve_operations {
             }
         }
 
-        impl Mul<U256> for &$type {
-            type Output = $type;
+        impl<C> Mul<U256> for &$type<C>
+        where
+            C: $crate::CurveParameters<Field = FieldElement>,
+        {
+            type Output = $type<C>;
 
-            fn mul(self, scalar: U256) -> $type {
+            fn mul(self, scalar: U256) -> $type<C> {
                 self * &scalar
             }
         }
@@ -170,24 +302,36 @@ macro_rules! curve_operations {
     };
 }
 
-curve_operations!(Affine);
-commutative_binop!(Affine, Add, add, AddAssign, add_assign);
-noncommutative_binop!(Affine, Sub, sub, SubAssign, sub_assign);
+impl<C> Mul<&U256> for &AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    type Output = AffinePoint<C>;
 
-#[cfg(test)]
+    fn mul(self, scalar: &U256) -> AffinePoint<C> {
+        // Accumulate in Jacobian coordinates to avoid an inversion per step.
+        AffinePoint::from(&JacobianPoint::<C>::mul(self, scalar))
+    }
+}
+
+curve_operations!(AffinePoint);
+curve_binop!(AffinePoint, Add, add, AddAssign, add_assign);
+curve_binop!(AffinePoint, Sub, sub, SubAssign, sub_assign);
+
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::{Arbitrary, Gen};
 
-#[cfg(test)]
-impl Arbitrary for Affine {
+/// Random points in the subgroup generated by `C::GENERATOR`.
+#[cfg(any(test, feature = "quickcheck"))]
+impl<C> Arbitrary for AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement> + 'static,
+{
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         if u8::arbitrary(g) < 50 {
             Self::Zero
         } else {
-            // TODO: Make sure it is on the curve
-            Self::Point {
-                x: FieldElement::arbitrary(g),
-                y: FieldElement::arbitrary(g),
-            }
+            &C::GENERATOR * &(U256::arbitrary(g) % &C::ORDER)
         }
     }
 }
@@ -199,7 +343,7 @@ impl Arbitrary for Affine {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ORDER;
+    use crate::{GENERATOR, ORDER};
     use quickcheck_macros::quickcheck;
     use zkp_macros_decl::u256h;
     use zkp_u256::U256;
@@ -277,6 +421,25 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_try_new() {
+        let (x, y) = match GENERATOR {
+            Affine::Point { x, y } => (x, y),
+            Affine::Zero => unreachable!(),
+        };
+        assert_eq!(Affine::try_new(x.clone(), y.clone()), Ok(GENERATOR));
+        assert_eq!(
+            Affine::try_new(x, y + FieldElement::ONE),
+            Err(PointError::NotOnCurve)
+        );
+        assert!(Affine::Zero.is_valid());
+    }
+
+    #[quickcheck]
+    fn multiples_are_valid(scalar: U256) -> bool {
+        (&GENERATOR * scalar).is_valid()
+    }
+
     #[allow(clippy::eq_op)]
     #[quickcheck]
     fn add_commutative(a: Affine, b: Affine) -> bool {
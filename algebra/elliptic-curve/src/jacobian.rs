@@ -1,22 +1,29 @@
-use crate::{curve::Affine, curve_operations};
+use crate::{curve::AffinePoint, curve_binop, curve_operations, CurveParameters, StarkCurve};
 use std::{
     ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
     prelude::v1::*,
 };
 use zkp_primefield::FieldElement;
-use zkp_u256::{commutative_binop, noncommutative_binop, U256};
+use zkp_u256::U256;
 
 // See http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html
 
+/// A point in Jacobian coordinates on the curve `C`.
 #[derive(Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Jacobian {
-    pub x: FieldElement,
-    pub y: FieldElement,
-    pub z: FieldElement,
+pub struct JacobianPoint<C: CurveParameters> {
+    pub x: C::Field,
+    pub y: C::Field,
+    pub z: C::Field,
 }
 
-impl Jacobian {
+/// A point in Jacobian coordinates on the Stark curve.
+pub type Jacobian = JacobianPoint<StarkCurve>;
+
+impl<C> JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     pub const ZERO: Self = Self {
         x: FieldElement::ONE,
         y: FieldElement::ONE,
@@ -25,7 +32,7 @@ impl Jacobian {
 
     pub fn on_curve(&self) -> bool {
         // TODO: Compute without inverting Z
-        Affine::from(self).on_curve()
+        AffinePoint::<C>::from(self).on_curve()
     }
 
     pub fn double_assign(&mut self) {
@@ -40,7 +47,7 @@ impl Jacobian {
         let yyyy = yy.square();
         let zz = self.z.square();
         let s = ((&self.x + &yy).square() - &xx - &yyyy).double();
-        let m = xx.triple() + /* ALPHA * */ zz.square();
+        let m = xx.triple() + C::mul_a(&zz.square());
         self.z = (&self.y + &self.z).square() - yy - zz;
         self.x = m.square() - s.double();
         self.y = m * (s - &self.x) - yyyy.double().double().double(); // TODO: .octuple()
@@ -57,7 +64,10 @@ impl Jacobian {
     }
 
     // Multiply Affine point using Jacobian accumulator
-    pub fn mul(p: &Affine, scalar: &U256) -> Self {
+    pub fn mul(p: &AffinePoint<C>, scalar: &U256) -> Self {
+        if scalar.is_zero() {
+            return Self::ZERO;
+        }
         let mut r = Self::from(p);
         for i in (0..scalar.msb()).rev() {
             r.double_assign();
@@ -67,26 +77,97 @@ impl Jacobian {
         }
         r
     }
+
+    /// Multiply an Affine point by a scalar in constant time.
+    ///
+    /// Uses a Montgomery ladder over a fixed number of bits, with
+    /// branch-free conditional swaps. Use this instead of `mul` when the
+    /// scalar is secret. The scalar must be less than `C::ORDER`.
+    ///
+    /// Adding multiples of `C::ORDER` to the scalar is only a no-op for points
+    /// in the prime order subgroup, so this requires a curve with cofactor one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `C::COFACTOR` is not one.
+    ///
+    /// Note: The field arithmetic and the exceptional cases of the addition
+    /// formula (which only occur for a negligible fraction of scalars) are
+    /// not guaranteed to be constant time.
+    pub fn mul_ct(p: &AffinePoint<C>, scalar: &U256) -> Self {
+        assert_eq!(C::COFACTOR, U256::ONE, "mul_ct requires cofactor one");
+        debug_assert!(scalar < &C::ORDER);
+        // Add one or two times the order, so the top bit is always set at
+        // the same position. This does not change the result.
+        let bits = C::ORDER.bits() + 1;
+        let mut k = scalar + &C::ORDER;
+        let mut k2 = &k + &C::ORDER;
+        let top = k.bit(bits - 1);
+        conditional_swap_u256(&mut k, &mut k2, !top);
+
+        // Invariant: r1 = r0 + p
+        let mut r0 = Self::from(p);
+        let mut r1 = r0.double();
+        for i in (0..bits - 1).rev() {
+            let bit = k.bit(i);
+            r0.conditional_swap(&mut r1, bit);
+            r1 += &r0;
+            r0.double_assign();
+            r0.conditional_swap(&mut r1, bit);
+        }
+        r0
+    }
+
+    fn conditional_swap(&mut self, other: &mut Self, swap: bool) {
+        conditional_swap_field(&mut self.x, &mut other.x, swap);
+        conditional_swap_field(&mut self.y, &mut other.y, swap);
+        conditional_swap_field(&mut self.z, &mut other.z, swap);
+    }
 }
 
-impl PartialEq for Jacobian {
+/// Swap `a` and `b` if `swap` is set, without branching on `swap`.
+fn conditional_swap_u256(a: &mut U256, b: &mut U256, swap: bool) {
+    let mask = 0_u64.wrapping_sub(u64::from(swap));
+    let t = (&*a ^ &*b) & U256::from_limbs(mask, mask, mask, mask);
+    *a ^= &t;
+    *b ^= &t;
+}
+
+fn conditional_swap_field(a: &mut FieldElement, b: &mut FieldElement, swap: bool) {
+    let mut x = a.as_montgomery().clone();
+    let mut y = b.as_montgomery().clone();
+    conditional_swap_u256(&mut x, &mut y, swap);
+    *a = FieldElement::from_montgomery(x);
+    *b = FieldElement::from_montgomery(y);
+}
+
+impl<C> PartialEq for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     fn eq(&self, rhs: &Self) -> bool {
         // TODO: without inverting Z
-        Affine::from(self) == Affine::from(rhs)
+        AffinePoint::<C>::from(self) == AffinePoint::<C>::from(rhs)
     }
 }
 
-impl Default for Jacobian {
+impl<C> Default for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     fn default() -> Self {
         Self::ZERO
     }
 }
 
-impl From<&Affine> for Jacobian {
-    fn from(other: &Affine) -> Self {
+impl<C> From<&AffinePoint<C>> for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: &AffinePoint<C>) -> Self {
         match other {
-            Affine::Zero => Self::ZERO,
-            Affine::Point { x, y } => {
+            AffinePoint::Zero => Self::ZERO,
+            AffinePoint::Point { x, y } => {
                 Self {
                     x: x.clone(),
                     y: y.clone(),
@@ -97,11 +178,14 @@ impl From<&Affine> for Jacobian {
     }
 }
 
-impl From<Affine> for Jacobian {
-    fn from(other: Affine) -> Self {
+impl<C> From<AffinePoint<C>> for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: AffinePoint<C>) -> Self {
         match other {
-            Affine::Zero => Self::ZERO,
-            Affine::Point { x, y } => {
+            AffinePoint::Zero => Self::ZERO,
+            AffinePoint::Point { x, y } => {
                 Self {
                     x,
                     y,
@@ -112,8 +196,11 @@ impl From<Affine> for Jacobian {
     }
 }
 
-impl From<&Jacobian> for Affine {
-    fn from(other: &Jacobian) -> Self {
+impl<C> From<&JacobianPoint<C>> for AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: &JacobianPoint<C>) -> Self {
         match other.z.inv() {
             None => Self::ZERO,
             Some(zi) => {
@@ -128,17 +215,35 @@ impl From<&Jacobian> for Affine {
     }
 }
 
-impl Neg for &Jacobian {
-    type Output = Jacobian;
+impl<C> Neg for &JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    type Output = JacobianPoint<C>;
 
-    fn neg(self) -> Jacobian {
+    fn neg(self) -> JacobianPoint<C> {
         let mut r = self.clone();
         r.neg_assign();
         r
     }
 }
 
-impl AddAssign<&Jacobian> for Jacobian {
+impl<C> Neg for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        self.neg_assign();
+        self
+    }
+}
+
+impl<C> AddAssign<&JacobianPoint<C>> for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     // We want to use the variable naming convention from the source
     #[allow(clippy::many_single_char_names)]
     // We need multiplications to implement addition
@@ -178,15 +283,18 @@ impl AddAssign<&Jacobian> for Jacobian {
     }
 }
 
-impl AddAssign<&Affine> for Jacobian {
+impl<C> AddAssign<&AffinePoint<C>> for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     // We want to use the variable naming convention from the source
     #[allow(clippy::many_single_char_names)]
     // We need multiplications to implement addition
     #[allow(clippy::suspicious_op_assign_impl)]
-    fn add_assign(&mut self, rhs: &Affine) {
+    fn add_assign(&mut self, rhs: &AffinePoint<C>) {
         match rhs {
-            Affine::Zero => { /* Do nothing */ }
-            Affine::Point { x, y } => {
+            AffinePoint::Zero => { /* Do nothing */ }
+            AffinePoint::Point { x, y } => {
                 if self.z == FieldElement::ZERO {
                     self.x = x.clone();
                     self.y = y.clone();
@@ -199,7 +307,7 @@ impl AddAssign<&Affine> for Jacobian {
                 let u2 = x * &z1z1;
                 let s2 = y * &self.z * &z1z1;
                 if self.x == u2 {
-                    return if self.x == s2 {
+                    return if self.y == s2 {
                         self.double_assign()
                     } else {
                         *self = Self::ZERO
@@ -219,36 +327,171 @@ impl AddAssign<&Affine> for Jacobian {
     }
 }
 
-// TODO: Various Add implementations mixing Affine and Jacobian values and refs.
-impl Add<&Affine> for &Jacobian {
-    type Output = Jacobian;
-
-    fn add(self, rhs: &Affine) -> Jacobian {
-        let mut r = self.clone();
-        r += rhs;
-        r
+impl<C> SubAssign<&AffinePoint<C>> for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn sub_assign(&mut self, rhs: &AffinePoint<C>) {
+        self.add_assign(&rhs.neg())
     }
 }
 
-impl SubAssign<&Affine> for Jacobian {
-    fn sub_assign(&mut self, rhs: &Affine) {
-        self.add_assign(&rhs.neg())
+/// Implement an operator between `Jacobian` and `Affine` values and
+/// references in either order, using `Jacobian: OpAssign<&Affine>`.
+///
+/// With the `Affine` on the left, the result is `rhs <op> lhs`, negated when
+/// the operator is not commutative.
+macro_rules! mixed_binop {
+    ($trait:ident, $trait_fn:ident, $inplace:ident, $inplace_fn:ident, $negate:expr) => {
+        impl<C> $inplace<AffinePoint<C>> for JacobianPoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            fn $inplace_fn(&mut self, rhs: AffinePoint<C>) {
+                self.$inplace_fn(&rhs)
+            }
+        }
+
+        impl<C> $trait<&AffinePoint<C>> for JacobianPoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = Self;
+
+            fn $trait_fn(mut self, rhs: &AffinePoint<C>) -> Self {
+                self.$inplace_fn(rhs);
+                self
+            }
+        }
+
+        impl<C> $trait<AffinePoint<C>> for JacobianPoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = Self;
+
+            fn $trait_fn(self, rhs: AffinePoint<C>) -> Self {
+                self.$trait_fn(&rhs)
+            }
+        }
+
+        // Note: a clone is necessary
+        impl<C> $trait<&AffinePoint<C>> for &JacobianPoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = JacobianPoint<C>;
+
+            fn $trait_fn(self, rhs: &AffinePoint<C>) -> JacobianPoint<C> {
+                self.clone().$trait_fn(rhs)
+            }
+        }
+
+        // Note: a clone is necessary
+        impl<C> $trait<AffinePoint<C>> for &JacobianPoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = JacobianPoint<C>;
+
+            fn $trait_fn(self, rhs: AffinePoint<C>) -> JacobianPoint<C> {
+                self.clone().$trait_fn(&rhs)
+            }
+        }
+
+        impl<C> $trait<JacobianPoint<C>> for &AffinePoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = JacobianPoint<C>;
+
+            fn $trait_fn(self, rhs: JacobianPoint<C>) -> JacobianPoint<C> {
+                let mut r = rhs.$trait_fn(self);
+                if $negate {
+                    r.neg_assign();
+                }
+                r
+            }
+        }
+
+        impl<C> $trait<JacobianPoint<C>> for AffinePoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = JacobianPoint<C>;
+
+            fn $trait_fn(self, rhs: JacobianPoint<C>) -> JacobianPoint<C> {
+                (&self).$trait_fn(rhs)
+            }
+        }
+
+        // Note: a clone is necessary
+        impl<C> $trait<&JacobianPoint<C>> for &AffinePoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = JacobianPoint<C>;
+
+            fn $trait_fn(self, rhs: &JacobianPoint<C>) -> JacobianPoint<C> {
+                self.$trait_fn(rhs.clone())
+            }
+        }
+
+        // Note: a clone is necessary
+        impl<C> $trait<&JacobianPoint<C>> for AffinePoint<C>
+        where
+            C: CurveParameters<Field = FieldElement>,
+        {
+            type Output = JacobianPoint<C>;
+
+            fn $trait_fn(self, rhs: &JacobianPoint<C>) -> JacobianPoint<C> {
+                (&self).$trait_fn(rhs.clone())
+            }
+        }
+    };
+}
+
+mixed_binop!(Add, add, AddAssign, add_assign, false);
+mixed_binop!(Sub, sub, SubAssign, sub_assign, true);
+
+impl<C> Mul<&U256> for &JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    type Output = JacobianPoint<C>;
+
+    fn mul(self, scalar: &U256) -> JacobianPoint<C> {
+        // OPT: Use WNAF
+        if scalar.is_zero() {
+            return JacobianPoint::ZERO;
+        }
+        let mut r = self.clone();
+        for i in (0..scalar.msb()).rev() {
+            r.double_assign();
+            if scalar.bit(i) {
+                r += self;
+            }
+        }
+        r
     }
 }
 
-curve_operations!(Jacobian);
-commutative_binop!(Jacobian, Add, add, AddAssign, add_assign);
-noncommutative_binop!(Jacobian, Sub, sub, SubAssign, sub_assign);
+curve_operations!(JacobianPoint);
+curve_binop!(JacobianPoint, Add, add, AddAssign, add_assign);
+curve_binop!(JacobianPoint, Sub, sub, SubAssign, sub_assign);
 
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::{Arbitrary, Gen};
 
-#[cfg(test)]
-impl Arbitrary for Jacobian {
+#[cfg(any(test, feature = "quickcheck"))]
+impl<C> Arbitrary for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement> + 'static,
+{
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         // To force Z to be non trivial we add two points.
-        let mut r = Self::from(Affine::arbitrary(g));
-        r += &Affine::arbitrary(g);
+        let mut r = Self::from(AffinePoint::<C>::arbitrary(g));
+        r += &AffinePoint::<C>::arbitrary(g);
         r
     }
 }
@@ -260,7 +503,7 @@ impl Arbitrary for Jacobian {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ORDER;
+    use crate::{Affine, GENERATOR, ORDER};
     use quickcheck_macros::quickcheck;
     use zkp_macros_decl::u256h;
 
@@ -342,6 +585,46 @@ mod tests {
         &a + &b == &b + &a
     }
 
+    #[quickcheck]
+    fn mixed_add_sub(a: Affine, b: Jacobian) -> bool {
+        let a_jacobian = Jacobian::from(&a);
+        &b + &a == &b + &a_jacobian
+            && &a + &b == &a_jacobian + &b
+            && &b - &a == &b - &a_jacobian
+            && &a - &b == &a_jacobian - &b
+            && a.clone() - b.clone() == -(b - a)
+    }
+
+    #[test]
+    fn test_mixed_add_double() {
+        // Adding a point to itself in mixed coordinates should double.
+        let a = crate::GENERATOR;
+        let b = Jacobian::from(&a);
+        assert_eq!(&b + &a, b.double());
+        assert_eq!(&b - &a, Jacobian::ZERO);
+        assert_eq!(&b * U256::ZERO, Jacobian::ZERO);
+    }
+
+    #[quickcheck]
+    fn mul_ct_matches_mul(seed: U256, mut scalar: U256) -> bool {
+        // The point needs to be in the subgroup of order `ORDER`
+        let p = Affine::from(&Jacobian::mul(&GENERATOR, &seed));
+        scalar %= &ORDER;
+        Jacobian::mul_ct(&p, &scalar) == Jacobian::mul(&p, &scalar)
+    }
+
+    #[test]
+    fn test_mul_ct_edge_cases() {
+        let p = GENERATOR;
+        assert_eq!(Jacobian::mul_ct(&p, &U256::ZERO), Jacobian::ZERO);
+        assert_eq!(Jacobian::mul_ct(&p, &U256::ONE), Jacobian::from(&p));
+        assert_eq!(
+            Jacobian::mul_ct(&p, &(&ORDER - &U256::ONE)),
+            Jacobian::from(-&p)
+        );
+        assert_eq!(Jacobian::mul_ct(&Affine::Zero, &U256::ONE), Jacobian::ZERO);
+    }
+
     #[quickcheck]
     fn distributivity(p: Jacobian, mut a: U256, mut b: U256) -> bool {
         a %= &ORDER;
@@ -39,20 +39,27 @@
 
 mod curve;
 mod jacobian;
+mod modified_jacobian;
+mod parameters;
 mod wnaf;
 
 #[cfg(not(feature = "std"))]
 extern crate no_std_compat as std;
 
-pub use curve::Affine;
-pub use jacobian::Jacobian;
-pub use wnaf::{base_mul, double_base_mul, double_mul, mul, window_table_affine};
+pub use curve::{Affine, AffinePoint, PointError};
+pub use jacobian::{Jacobian, JacobianPoint};
+pub use modified_jacobian::{ModifiedJacobian, ModifiedJacobianPoint};
+pub use parameters::{CurveParameters, StarkCurve};
+pub use wnaf::{
+    base_mul, double_base_mul, double_mul, double_scalar_mul, mul, mul_jacobian,
+    window_table_affine,
+};
 
 use zkp_macros_decl::u256h;
 use zkp_primefield::FieldElement;
 use zkp_u256::U256;
 
-// Curve parameters
+// Stark curve parameters, see also `StarkCurve`
 
 // Alpha = 1
 // Beta  = 0x06f21413efbe40de150e596d72f7a8c5609ad26c15c915c1f4cdfcb99cee9e89
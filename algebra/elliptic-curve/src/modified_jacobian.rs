@@ -0,0 +1,247 @@
+use crate::{curve::AffinePoint, jacobian::JacobianPoint, CurveParameters, StarkCurve};
+use std::{
+    ops::{AddAssign, SubAssign},
+    prelude::v1::*,
+};
+use zkp_primefield::FieldElement;
+
+// See http://www.hyperelliptic.org/EFD/g1p/auto-shortw-modified.html
+
+/// Modified Jacobian coordinates.
+///
+/// A Jacobian point on the curve `C` that additionally caches
+/// `t = alpha * z^4`. This makes
+/// doubling cheaper, because the `alpha * zz^2` term no longer needs to be
+/// recomputed, at the cost of two extra squarings per mixed addition. It is
+/// the better accumulator when doublings dominate, as in scalar
+/// multiplication.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ModifiedJacobianPoint<C: CurveParameters> {
+    pub x: C::Field,
+    pub y: C::Field,
+    pub z: C::Field,
+    pub t: C::Field,
+}
+
+/// A point in modified Jacobian coordinates on the Stark curve.
+pub type ModifiedJacobian = ModifiedJacobianPoint<StarkCurve>;
+
+impl<C> ModifiedJacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    pub const ZERO: Self = Self {
+        x: FieldElement::ONE,
+        y: FieldElement::ONE,
+        z: FieldElement::ZERO,
+        t: FieldElement::ZERO,
+    };
+
+    pub fn on_curve(&self) -> bool {
+        AffinePoint::<C>::from(self).on_curve()
+    }
+
+    pub fn double_assign(&mut self) {
+        if self.y == FieldElement::ZERO {
+            *self = Self::ZERO;
+            return;
+        }
+        // See http://www.hyperelliptic.org/EFD/g1p/auto-shortw-modified.html#doubling-mdbl-2009-bl
+        // with `z3 = 2 y z` because we do not keep `zz` around.
+        let xx = self.x.square();
+        let yy = self.y.square();
+        let yyyy = yy.square();
+        let s = ((&self.x + &yy).square() - &xx - &yyyy).double();
+        let m = xx.triple() + &self.t;
+        let u = yyyy.double().double().double(); // TODO: .octuple()
+        self.z = (&self.y * &self.z).double();
+        self.x = m.square() - s.double();
+        self.y = m * (s - &self.x) - &u;
+        self.t = (u * &self.t).double();
+    }
+
+    pub fn neg_assign(&mut self) {
+        self.y.neg_assign();
+    }
+
+    pub fn double(&self) -> Self {
+        let mut r = self.clone();
+        r.double_assign();
+        r
+    }
+}
+
+impl<C> PartialEq for ModifiedJacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn eq(&self, rhs: &Self) -> bool {
+        AffinePoint::<C>::from(self) == AffinePoint::<C>::from(rhs)
+    }
+}
+
+impl<C> Default for ModifiedJacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl<C> From<&AffinePoint<C>> for ModifiedJacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: &AffinePoint<C>) -> Self {
+        match other {
+            AffinePoint::Zero => Self::ZERO,
+            AffinePoint::Point { x, y } => {
+                Self {
+                    x: x.clone(),
+                    y: y.clone(),
+                    z: FieldElement::ONE,
+                    t: C::mul_a(&FieldElement::ONE),
+                }
+            }
+        }
+    }
+}
+
+impl<C> From<&JacobianPoint<C>> for ModifiedJacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: &JacobianPoint<C>) -> Self {
+        Self {
+            x: other.x.clone(),
+            y: other.y.clone(),
+            z: other.z.clone(),
+            t: C::mul_a(&other.z.square().square()),
+        }
+    }
+}
+
+impl<C> From<ModifiedJacobianPoint<C>> for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: ModifiedJacobianPoint<C>) -> Self {
+        Self {
+            x: other.x,
+            y: other.y,
+            z: other.z,
+        }
+    }
+}
+
+impl<C> From<&ModifiedJacobianPoint<C>> for JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: &ModifiedJacobianPoint<C>) -> Self {
+        Self {
+            x: other.x.clone(),
+            y: other.y.clone(),
+            z: other.z.clone(),
+        }
+    }
+}
+
+impl<C> From<&ModifiedJacobianPoint<C>> for AffinePoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn from(other: &ModifiedJacobianPoint<C>) -> Self {
+        Self::from(&JacobianPoint::<C>::from(other))
+    }
+}
+
+impl<C> AddAssign<&AffinePoint<C>> for ModifiedJacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    // We want to use the variable naming convention from the source
+    #[allow(clippy::many_single_char_names)]
+    // We need multiplications to implement addition
+    #[allow(clippy::suspicious_op_assign_impl)]
+    fn add_assign(&mut self, rhs: &AffinePoint<C>) {
+        match rhs {
+            AffinePoint::Zero => { /* Do nothing */ }
+            AffinePoint::Point { x, y } => {
+                if self.z == FieldElement::ZERO {
+                    *self = Self::from(rhs);
+                    return;
+                }
+                // Same as the Jacobian mixed addition, followed by updating t.
+                // See http://www.hyperelliptic.org/EFD/g1p/auto-shortw-jacobian.html#addition-madd-2007-bl
+                let z1z1 = self.z.square();
+                let u2 = x * &z1z1;
+                let s2 = y * &self.z * &z1z1;
+                if self.x == u2 {
+                    return if self.y == s2 {
+                        self.double_assign()
+                    } else {
+                        *self = Self::ZERO
+                    };
+                }
+                let h = u2 - &self.x;
+                let hh = h.square();
+                let i = hh.double().double(); // TODO .quadruple()
+                let j = &h * &i;
+                let r = (s2 - &self.y).double();
+                let v = &self.x * i;
+                self.x = r.square() - &j - v.double();
+                self.y = r * (v - &self.x) - (&self.y * j).double();
+                self.z = (&self.z + h).square() - z1z1 - hh;
+                self.t = C::mul_a(&self.z.square().square());
+            }
+        }
+    }
+}
+
+impl<C> SubAssign<&AffinePoint<C>> for ModifiedJacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    fn sub_assign(&mut self, rhs: &AffinePoint<C>) {
+        self.add_assign(&-rhs)
+    }
+}
+
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Affine, Jacobian};
+    use quickcheck_macros::quickcheck;
+    use zkp_u256::U256;
+
+    #[quickcheck]
+    fn double_matches_jacobian(a: Jacobian) -> bool {
+        Jacobian::from(ModifiedJacobian::from(&a).double()) == a.double()
+    }
+
+    #[quickcheck]
+    fn add_matches_jacobian(a: Jacobian, b: Affine) -> bool {
+        let mut r = ModifiedJacobian::from(&a);
+        r += &b;
+        r.double_assign();
+        r -= &b;
+        Jacobian::from(r) == (a + &b).double() - &b
+    }
+
+    #[quickcheck]
+    fn ladder_matches_jacobian(p: Affine, scalar: U256) -> bool {
+        let mut r = ModifiedJacobian::ZERO;
+        for i in (0..256).rev() {
+            r.double_assign();
+            if scalar.bit(i) {
+                r += &p;
+            }
+        }
+        Jacobian::from(r) == Jacobian::mul(&p, &scalar)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{curve::AffinePoint, BETA, GENERATOR, ORDER};
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Parameters of a short Weierstrass curve `y^2 = x^3 + a x + b`.
+///
+/// The point types `AffinePoint`, `JacobianPoint` and
+/// `ModifiedJacobianPoint` are generic over these parameters.
+///
+/// Note: The arithmetic is currently only implemented for curves over the
+/// `FieldElement` base field.
+pub trait CurveParameters: Sized + Clone + PartialEq + Eq {
+    /// The base field of the curve.
+    type Field: Clone + PartialEq + Eq;
+
+    /// The coefficient `a` of the curve equation.
+    const A: Self::Field;
+
+    /// The coefficient `b` of the curve equation.
+    const B: Self::Field;
+
+    /// A generator of the prime order subgroup.
+    const GENERATOR: AffinePoint<Self>;
+
+    /// The order of the subgroup generated by `GENERATOR`.
+    const ORDER: U256;
+
+    /// The number of points on the curve divided by `ORDER`.
+    const COFACTOR: U256;
+
+    /// Multiply a field element by `a`.
+    ///
+    /// This exists so curves with small `a` (such as `0`, `1` or `-3`) can
+    /// avoid a full multiplication in the doubling formulas.
+    fn mul_a(x: &Self::Field) -> Self::Field;
+}
+
+/// The Stark curve `y^2 = x^3 + x + beta`.
+#[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct StarkCurve;
+
+impl CurveParameters for StarkCurve {
+    type Field = FieldElement;
+
+    const A: FieldElement = FieldElement::ONE;
+    const B: FieldElement = BETA;
+    const COFACTOR: U256 = U256::ONE;
+    const GENERATOR: AffinePoint<Self> = GENERATOR;
+    const ORDER: U256 = ORDER;
+
+    fn mul_a(x: &FieldElement) -> FieldElement {
+        // Alpha = 1
+        x.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generator_on_curve() {
+        assert!(StarkCurve::GENERATOR.on_curve());
+        assert_eq!(
+            &StarkCurve::GENERATOR * &StarkCurve::ORDER,
+            AffinePoint::Zero
+        );
+    }
+}
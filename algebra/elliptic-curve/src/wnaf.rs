@@ -1,13 +1,19 @@
-use crate::{curve::Affine, jacobian::Jacobian};
+use crate::{
+    curve::AffinePoint, jacobian::JacobianPoint, modified_jacobian::ModifiedJacobianPoint,
+    CurveParameters,
+};
 use itertools::izip;
 use std::prelude::v1::*;
 use zkp_primefield::FieldElement;
 use zkp_u256::U256;
 
-pub(crate) fn window_table(p: &Affine, naf: &mut [Jacobian]) {
+pub(crate) fn window_table<C>(p: &AffinePoint<C>, naf: &mut [JacobianPoint<C>])
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     // naf = P, 3P, 5P, ... 15P
     // OPT: Optimal window size
-    naf[0] = Jacobian::from(p);
+    naf[0] = JacobianPoint::from(p);
     let p2 = naf[0].double();
     for i in 1..naf.len() {
         naf[i] = &naf[i - 1] + &p2;
@@ -15,7 +21,10 @@ pub(crate) fn window_table(p: &Affine, naf: &mut [Jacobian]) {
     // OPT: Use batch inversion to convert to Affine
 }
 
-pub fn window_table_affine(p: &Affine, naf: &mut [Affine]) {
+pub fn window_table_affine<C>(p: &AffinePoint<C>, naf: &mut [AffinePoint<C>])
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     // naf = P, 3P, 5P, ... 15P
     // OPT: Optimal window size
     naf[0] = p.clone();
@@ -26,7 +35,10 @@ pub fn window_table_affine(p: &Affine, naf: &mut [Affine]) {
 }
 
 // TODO: https://link.springer.com/content/pdf/10.1007/3-540-36400-5_41.pdf
-pub(crate) fn batch_convert(jacobians: &[Jacobian], affines: &mut [Affine]) {
+pub(crate) fn batch_convert<C>(jacobians: &[JacobianPoint<C>], affines: &mut [AffinePoint<C>])
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     debug_assert!(jacobians.len() == affines.len());
 
     // Intermediate values
@@ -56,7 +68,7 @@ pub(crate) fn batch_convert(jacobians: &[Jacobian], affines: &mut [Affine]) {
         // Compute affine point
         let zi2 = zi.square();
         let zi3 = zi * &zi2;
-        *aff = Affine::Point {
+        *aff = AffinePoint::Point {
             x: &jac.x * zi2,
             y: &jac.y * zi3,
         }
@@ -105,20 +117,37 @@ pub(crate) fn non_adjacent_form(mut scalar: U256, window: usize) -> [i16; 257] {
     snaf
 }
 
-// Multiply Affine point using Jacobian accumulator
+// Multiply Affine point using a modified Jacobian accumulator
 // See https://doc-internal.dalek.rs/curve25519_dalek/traits/trait.VartimeMultiscalarMul.html
+// The modified Jacobian accumulator has cheaper doublings, which outweighs
+// its more expensive additions with a window of 5. See the
+// `Wnaf mul` and `Wnaf mul jacobian` benchmarks.
 // Signs are explicitly handled
 #[allow(clippy::cast_sign_loss)]
-pub fn mul(p: &Affine, scalar: &U256) -> Jacobian {
+// Rebind naf_table after affine batch conversion
+#[allow(clippy::shadow_unrelated)]
+pub fn mul<C>(p: &AffinePoint<C>, scalar: &U256) -> JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    if *p == AffinePoint::<C>::ZERO {
+        return JacobianPoint::ZERO;
+    }
+
     // Precomputed odd multiples
-    let mut naf_table: [Jacobian; 8] = Default::default();
+    let mut naf_table: [JacobianPoint<C>; 8] = Default::default();
     window_table(p, &mut naf_table);
 
+    // Batch convert to affine so we can use mixed additions
+    let mut temp: [AffinePoint<C>; 8] = Default::default();
+    batch_convert(&naf_table, &mut temp);
+    let naf_table = temp;
+
     // Get SNAF
     let snaf_expansion = non_adjacent_form(scalar.clone(), 5);
 
     // Algorithm 3.36 of Guide to Elliptic Curve Cryptography
-    let mut r = Jacobian::ZERO;
+    let mut r = ModifiedJacobianPoint::<C>::ZERO;
     for i in (0..snaf_expansion.len()).rev() {
         // OPT: Avoid doubling zeros
         // OPT: Use A + A -> J formula for first
@@ -129,32 +158,86 @@ pub fn mul(p: &Affine, scalar: &U256) -> Jacobian {
             r -= &naf_table[(-snaf_expansion[i] >> 1) as usize];
         }
     }
-    r
+    r.into()
 }
 
+// Multiply Affine point using a Jacobian accumulator
+// This is the reference for `mul` and only used in benchmarks and tests.
 // Signs are explicitly handled
 #[allow(clippy::cast_sign_loss)]
-pub fn double_mul(point_a: &Affine, scalar_a: U256, point_b: &Affine, scalar_b: U256) -> Jacobian {
+pub fn mul_jacobian<C>(p: &AffinePoint<C>, scalar: &U256) -> JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     // Precomputed odd multiples
-    let mut naf_table_a: [Jacobian; 8] = Default::default();
-    let mut naf_table_b: [Jacobian; 8] = Default::default();
-    window_table(point_a, &mut naf_table_a);
-    window_table(point_b, &mut naf_table_b);
+    let mut naf_table: [JacobianPoint<C>; 8] = Default::default();
+    window_table(p, &mut naf_table);
 
     // Get SNAF
-    let snaf_expansion_a = non_adjacent_form(scalar_a, 5);
-    let snaf_expansion_b = non_adjacent_form(scalar_b, 5);
+    let snaf_expansion = non_adjacent_form(scalar.clone(), 5);
 
     // Algorithm 3.36 of Guide to Elliptic Curve Cryptography
-    let mut r = Jacobian::ZERO;
+    let mut r = JacobianPoint::<C>::ZERO;
+    for i in (0..snaf_expansion.len()).rev() {
+        // OPT: Avoid doubling zeros
+        // OPT: Use A + A -> J formula for first
+        r.double_assign();
+        if snaf_expansion[i] > 0 {
+            r += &naf_table[(snaf_expansion[i] >> 1) as usize];
+        } else if snaf_expansion[i] < 0 {
+            r -= &naf_table[(-snaf_expansion[i] >> 1) as usize];
+        }
+    }
+    r
+}
+
+// Compute `a * p + b * q` using interleaved wNAF (Shamir's trick)
+// The doublings are shared between both scalars, so this costs about as
+// much as a single multiplication plus the additions of the second.
+// Signs are explicitly handled
+#[allow(clippy::cast_sign_loss)]
+// Rebind naf_table after affine batch conversion
+#[allow(clippy::shadow_unrelated)]
+pub fn double_scalar_mul<C>(
+    a: &U256,
+    p: &AffinePoint<C>,
+    b: &U256,
+    q: &AffinePoint<C>,
+) -> JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    // Batch conversion can not handle the point at infinity
+    if *p == AffinePoint::<C>::ZERO {
+        return mul(q, b);
+    }
+    if *q == AffinePoint::<C>::ZERO {
+        return mul(p, a);
+    }
+
+    // Precomputed odd multiples of both points
+    let mut naf_table: [JacobianPoint<C>; 16] = Default::default();
+    window_table(p, &mut naf_table[..8]);
+    window_table(q, &mut naf_table[8..]);
+
+    // Batch convert to affine so we can use mixed additions
+    let mut temp: [AffinePoint<C>; 16] = Default::default();
+    batch_convert(&naf_table, &mut temp);
+    let (naf_table_a, naf_table_b) = temp.split_at(8);
+
+    // Get SNAF
+    let snaf_expansion_a = non_adjacent_form(a.clone(), 5);
+    let snaf_expansion_b = non_adjacent_form(b.clone(), 5);
+
+    // Algorithm 3.36 of Guide to Elliptic Curve Cryptography
+    let mut r = ModifiedJacobianPoint::<C>::ZERO;
     debug_assert_eq!(snaf_expansion_a.len(), snaf_expansion_b.len());
     for i in (0..snaf_expansion_a.len()).rev() {
         // OPT: Avoid doubling zeros
-        // OPT: Use A + A -> J formular for first
         r.double_assign();
         if snaf_expansion_a[i] > 0 {
             r += &naf_table_a[(snaf_expansion_a[i] >> 1) as usize];
-        } else if snaf_expansion_b[i] < 0 {
+        } else if snaf_expansion_a[i] < 0 {
             r -= &naf_table_a[(-snaf_expansion_a[i] >> 1) as usize];
         }
         if snaf_expansion_b[i] > 0 {
@@ -163,17 +246,32 @@ pub fn double_mul(point_a: &Affine, scalar_a: U256, point_b: &Affine, scalar_b:
             r -= &naf_table_b[(-snaf_expansion_b[i] >> 1) as usize];
         }
     }
-    r
+    r.into()
+}
+
+pub fn double_mul<C>(
+    point_a: &AffinePoint<C>,
+    scalar_a: U256,
+    point_b: &AffinePoint<C>,
+    scalar_b: U256,
+) -> JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
+    double_scalar_mul(&scalar_a, point_a, &scalar_b, point_b)
 }
 
 // Signs are explicitly handled
 #[allow(clippy::cast_sign_loss)]
-pub fn base_mul(naf_table: &[Affine], s: U256) -> Jacobian {
+pub fn base_mul<C>(naf_table: &[AffinePoint<C>], s: U256) -> JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     // Get SNAF
     let snaf_expansion = non_adjacent_form(s, 7);
 
     // Algorithm 3.36 of Guide to Elliptic Curve Cryptography
-    let mut r = Jacobian::ZERO;
+    let mut r = ModifiedJacobianPoint::<C>::ZERO;
     for i in (0..snaf_expansion.len()).rev() {
         // OPT: Avoid doubling zeros
         // OPT: Use A + A -> J formular for first
@@ -184,27 +282,30 @@ pub fn base_mul(naf_table: &[Affine], s: U256) -> Jacobian {
             r -= &naf_table[(-snaf_expansion[i] >> 1) as usize];
         }
     }
-    r
+    r.into()
 }
 
 // Signs are explicitly handled
 #[allow(clippy::cast_sign_loss)]
 // Rebind naf_table after affine batch conversion
 #[allow(clippy::shadow_unrelated)]
-pub fn double_base_mul(
-    naf_table_a: &[Affine],
+pub fn double_base_mul<C>(
+    naf_table_a: &[AffinePoint<C>],
     scalar_a: U256,
-    point_b: &Affine,
+    point_b: &AffinePoint<C>,
     scalar_b: U256,
-) -> Jacobian {
+) -> JacobianPoint<C>
+where
+    C: CurveParameters<Field = FieldElement>,
+{
     // Precomputed odd multiples
-    let mut naf_table_b: [Jacobian; 8] = Default::default();
+    let mut naf_table_b: [JacobianPoint<C>; 8] = Default::default();
     window_table(point_b, &mut naf_table_b);
 
     // Batch convert to affine
     // OPT: Right now this doesn't hurt or improve performance. It should be
     // better with more points.
-    let mut temp: [Affine; 8] = Default::default();
+    let mut temp: [AffinePoint<C>; 8] = Default::default();
     batch_convert(&naf_table_b, &mut temp);
     let naf_table_b = temp;
 
@@ -213,7 +314,7 @@ pub fn double_base_mul(
     let snaf_expansion_b = non_adjacent_form(scalar_b, 5);
 
     // Algorithm 3.36 of Guide to Elliptic Curve Cryptography
-    let mut r = Jacobian::ZERO;
+    let mut r = JacobianPoint::<C>::ZERO;
     debug_assert_eq!(snaf_expansion_a.len(), snaf_expansion_b.len());
     for i in (0..snaf_expansion_a.len()).rev() {
         // OPT: Avoid doubling zeros
@@ -237,11 +338,14 @@ pub fn double_base_mul(
 
 // TODO: Replace literals with u256h!
 #[allow(clippy::unreadable_literal)]
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Affine, Jacobian, GENERATOR, ORDER};
+    use quickcheck_macros::quickcheck;
     use zkp_macros_decl::u256h;
-    use zkp_primefield::FieldElement;
 
     #[test]
     fn test_mul() {
@@ -264,5 +368,23 @@ mod tests {
         });
         let result = mul(&p, &c);
         assert_eq!(result, expected);
+        assert_eq!(mul_jacobian(&p, &c), expected);
+        assert_eq!(mul(&Affine::ZERO, &c), Jacobian::ZERO);
+    }
+
+    #[quickcheck]
+    fn double_scalar_mul_matches_mul(a: U256, p: Affine, b: U256, q: Affine) -> bool {
+        double_scalar_mul(&a, &p, &b, &q) == mul(&p, &a) + mul(&q, &b)
+    }
+
+    #[test]
+    fn test_double_scalar_mul_zero() {
+        let p = GENERATOR;
+        let a = u256h!("07374b7d69dc9825fc758b28913c8d2a27be5e7c32412f612b20c9c97afbe4dd");
+        let expected = mul(&p, &a);
+        assert_eq!(double_scalar_mul(&a, &p, &a, &Affine::ZERO), expected);
+        assert_eq!(double_scalar_mul(&U256::ZERO, &p, &a, &p), expected);
+        assert_eq!(double_scalar_mul(&a, &p, &ORDER, &p), expected);
+        assert_eq!(double_mul(&Affine::ZERO, a.clone(), &p, a), expected);
     }
 }
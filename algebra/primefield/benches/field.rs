@@ -52,4 +52,23 @@ fn field_inv(crit: &mut Criterion) {
     });
 }
 
-criterion_group!(field, field_add, field_mul, field_inv, field_sqrt);
+fn field_mul_slice(crit: &mut Criterion) {
+    let a: Vec<_> = (0..1024_usize).map(FieldElement::from).collect();
+    let b: Vec<_> = (1024..2048_usize).map(FieldElement::from).collect();
+    crit.bench_function("Field mul slice 1024", move |bench| {
+        let mut copy = a.clone();
+        bench.iter(|| {
+            copy.clone_from_slice(&a);
+            FieldElement::mul_slice(black_box(&mut copy), black_box(&b));
+        })
+    });
+}
+
+criterion_group!(
+    field,
+    field_add,
+    field_mul,
+    field_mul_slice,
+    field_inv,
+    field_sqrt
+);
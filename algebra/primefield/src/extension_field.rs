@@ -0,0 +1,475 @@
+use crate::{fft::fft, FieldElement};
+#[cfg(feature = "std")]
+use std::fmt;
+use std::{
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    prelude::v1::*,
+};
+use zkp_macros_decl::field_element;
+use zkp_u256::{commutative_binop, noncommutative_binop, U256};
+
+/// A finite extension of the base field.
+///
+/// Elements are polynomials of degree less than [`DEGREE`] over
+/// [`FieldElement`], reduced by a fixed irreducible polynomial.
+///
+/// [`DEGREE`]: #associatedconstant.DEGREE
+/// [`FieldElement`]: struct.FieldElement.html
+pub trait ExtensionField: Sized + Clone + PartialEq {
+    /// Degree of the extension over the base field.
+    const DEGREE: usize;
+
+    /// Embed a base field element.
+    fn from_base(element: FieldElement) -> Self;
+
+    /// The element with `coefficients`, lowest degree first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are not exactly `DEGREE` coefficients.
+    fn from_coefficients(coefficients: &[FieldElement]) -> Self;
+
+    /// The coefficients, lowest degree first.
+    fn coefficients(&self) -> Vec<FieldElement>;
+
+    /// The Frobenius automorphism $x \mapsto x^p$.
+    fn frobenius(&self) -> Self;
+
+    /// The product of all conjugates, which is in the base field.
+    fn norm(&self) -> FieldElement;
+}
+
+/// FFT of extension field values over the roots of unity of the base field.
+///
+/// Since the roots are in the base field, this is the FFT of each
+/// coefficient. The result is not permuted, as with [`fft`].
+///
+/// [`fft`]: fft/fn.fft.html
+pub fn fft_extension<E: ExtensionField>(values: &[E]) -> Vec<E> {
+    let columns: Vec<Vec<FieldElement>> = (0..E::DEGREE)
+        .map(|i| {
+            let column: Vec<_> = values
+                .iter()
+                .map(|value| value.coefficients()[i].clone())
+                .collect();
+            fft(&column)
+        })
+        .collect();
+    (0..values.len())
+        .map(|j| {
+            let coefficients: Vec<_> = columns.iter().map(|column| column[j].clone()).collect();
+            E::from_coefficients(&coefficients)
+        })
+        .collect()
+}
+
+/// The quadratic extension $\mathbb{F}_p[u] / (u^2 - 3)$.
+///
+/// Three generates the multiplicative group of the base field, so it is not a
+/// square and the polynomial is irreducible.
+#[derive(PartialEq, Eq, Clone)]
+pub struct Fp2 {
+    pub c0: FieldElement,
+    pub c1: FieldElement,
+}
+
+/// The cubic extension $\mathbb{F}_p[v] / (v^3 + v - 1)$.
+///
+/// Every element of the base field is a cube since $3 \nmid p - 1$, so
+/// there is no irreducible binomial and a trinomial is used instead.
+#[derive(PartialEq, Eq, Clone)]
+pub struct Fp3 {
+    pub c0: FieldElement,
+    pub c1: FieldElement,
+    pub c2: FieldElement,
+}
+
+const NON_RESIDUE: FieldElement = field_element!("03");
+
+// $v^p$ and $v^{2p}$ in `Fp3`.
+const FROBENIUS_V: Fp3 = Fp3 {
+    c0: field_element!("064fd4d697458c1b11e6903f2d997629644f5262385dc048be3ca56675638b66"),
+    c1: field_element!("06339ee2d45c7b2be846c48e269949dd21b2795cfed2f0a3ac087426881ff9a4"),
+    c2: field_element!("0177bf41e2e852179ad9d85ec466313e1676fb93548ca06d1d5af819b0155118"),
+};
+const FROBENIUS_V2: Fp3 = Fp3 {
+    c0: field_element!("068840be1d17adf9652627a13b99cec1e989046cab735f92e2a507e64feaaee8"),
+    c1: field_element!("0327ea6b4ba2c60d88f3481f96ccbb14b227a9311c2ee0245f1e52b33ab1c5b3"),
+    c2: field_element!("01cc611d2ba384e517b93b71d966b622de4d86a3012d0f5c53f78bd977e0065c"),
+};
+
+impl Fp2 {
+    pub const ONE: Self = Self {
+        c0: FieldElement::ONE,
+        c1: FieldElement::ZERO,
+    };
+    pub const ZERO: Self = Self {
+        c0: FieldElement::ZERO,
+        c1: FieldElement::ZERO,
+    };
+
+    pub fn new(c0: FieldElement, c1: FieldElement) -> Self {
+        Self { c0, c1 }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero()
+    }
+
+    pub fn scale(&self, factor: &FieldElement) -> Self {
+        Self::new(&self.c0 * factor, &self.c1 * factor)
+    }
+
+    pub fn square(&self) -> Self {
+        let product = &self.c0 * &self.c1;
+        Self::new(
+            self.c0.square() + NON_RESIDUE * self.c1.square(),
+            product.double(),
+        )
+    }
+
+    pub fn inv(&self) -> Option<Self> {
+        let inverse = self.norm().inv()?;
+        Some(Self::new(&self.c0 * &inverse, -&(&self.c1 * inverse)))
+    }
+
+    pub fn pow<T: Into<U256>>(&self, exponent: T) -> Self {
+        let mut result = Self::ONE;
+        let mut square = self.clone();
+        let mut remaining_exponent: U256 = exponent.into();
+        while !remaining_exponent.is_zero() {
+            if remaining_exponent.is_odd() {
+                result *= &square;
+            }
+            remaining_exponent >>= 1;
+            square = square.square();
+        }
+        result
+    }
+}
+
+impl ExtensionField for Fp2 {
+    const DEGREE: usize = 2;
+
+    fn from_base(element: FieldElement) -> Self {
+        Self::new(element, FieldElement::ZERO)
+    }
+
+    fn from_coefficients(coefficients: &[FieldElement]) -> Self {
+        assert_eq!(coefficients.len(), Self::DEGREE);
+        Self::new(coefficients[0].clone(), coefficients[1].clone())
+    }
+
+    fn coefficients(&self) -> Vec<FieldElement> {
+        vec![self.c0.clone(), self.c1.clone()]
+    }
+
+    // $u^p = 3^{(p - 1) / 2} u = -u$
+    fn frobenius(&self) -> Self {
+        Self::new(self.c0.clone(), -&self.c1)
+    }
+
+    fn norm(&self) -> FieldElement {
+        self.c0.square() - NON_RESIDUE * self.c1.square()
+    }
+}
+
+impl Fp3 {
+    pub const ONE: Self = Self {
+        c0: FieldElement::ONE,
+        c1: FieldElement::ZERO,
+        c2: FieldElement::ZERO,
+    };
+    pub const ZERO: Self = Self {
+        c0: FieldElement::ZERO,
+        c1: FieldElement::ZERO,
+        c2: FieldElement::ZERO,
+    };
+
+    pub fn new(c0: FieldElement, c1: FieldElement, c2: FieldElement) -> Self {
+        Self { c0, c1, c2 }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.c0.is_zero() && self.c1.is_zero() && self.c2.is_zero()
+    }
+
+    pub fn scale(&self, factor: &FieldElement) -> Self {
+        Self::new(&self.c0 * factor, &self.c1 * factor, &self.c2 * factor)
+    }
+
+    pub fn square(&self) -> Self {
+        self * self
+    }
+
+    pub fn inv(&self) -> Option<Self> {
+        // The product of the other conjugates is the norm over `self`.
+        let conjugate = self.frobenius();
+        let others = &conjugate * conjugate.frobenius();
+        let inverse = (self * &others).c0.inv()?;
+        Some(others.scale(&inverse))
+    }
+
+    pub fn pow<T: Into<U256>>(&self, exponent: T) -> Self {
+        let mut result = Self::ONE;
+        let mut square = self.clone();
+        let mut remaining_exponent: U256 = exponent.into();
+        while !remaining_exponent.is_zero() {
+            if remaining_exponent.is_odd() {
+                result *= &square;
+            }
+            remaining_exponent >>= 1;
+            square = square.square();
+        }
+        result
+    }
+}
+
+impl ExtensionField for Fp3 {
+    const DEGREE: usize = 3;
+
+    fn from_base(element: FieldElement) -> Self {
+        Self::new(element, FieldElement::ZERO, FieldElement::ZERO)
+    }
+
+    fn from_coefficients(coefficients: &[FieldElement]) -> Self {
+        assert_eq!(coefficients.len(), Self::DEGREE);
+        Self::new(
+            coefficients[0].clone(),
+            coefficients[1].clone(),
+            coefficients[2].clone(),
+        )
+    }
+
+    fn coefficients(&self) -> Vec<FieldElement> {
+        vec![self.c0.clone(), self.c1.clone(), self.c2.clone()]
+    }
+
+    fn frobenius(&self) -> Self {
+        Self::from_base(self.c0.clone())
+            + FROBENIUS_V.scale(&self.c1)
+            + FROBENIUS_V2.scale(&self.c2)
+    }
+
+    fn norm(&self) -> FieldElement {
+        let conjugate = self.frobenius();
+        (self * &conjugate * conjugate.frobenius()).c0
+    }
+}
+
+impl From<FieldElement> for Fp2 {
+    fn from(element: FieldElement) -> Self {
+        Self::from_base(element)
+    }
+}
+
+impl From<FieldElement> for Fp3 {
+    fn from(element: FieldElement) -> Self {
+        Self::from_base(element)
+    }
+}
+
+impl Neg for &Fp2 {
+    type Output = Fp2;
+
+    fn neg(self) -> Self::Output {
+        Fp2::new(-&self.c0, -&self.c1)
+    }
+}
+
+impl Neg for &Fp3 {
+    type Output = Fp3;
+
+    fn neg(self) -> Self::Output {
+        Fp3::new(-&self.c0, -&self.c1, -&self.c2)
+    }
+}
+
+impl AddAssign<&Fp2> for Fp2 {
+    fn add_assign(&mut self, rhs: &Self) {
+        self.c0 += &rhs.c0;
+        self.c1 += &rhs.c1;
+    }
+}
+
+impl AddAssign<&Fp3> for Fp3 {
+    fn add_assign(&mut self, rhs: &Self) {
+        self.c0 += &rhs.c0;
+        self.c1 += &rhs.c1;
+        self.c2 += &rhs.c2;
+    }
+}
+
+impl SubAssign<&Fp2> for Fp2 {
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.c0 -= &rhs.c0;
+        self.c1 -= &rhs.c1;
+    }
+}
+
+impl SubAssign<&Fp3> for Fp3 {
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.c0 -= &rhs.c0;
+        self.c1 -= &rhs.c1;
+        self.c2 -= &rhs.c2;
+    }
+}
+
+impl MulAssign<&Fp2> for Fp2 {
+    fn mul_assign(&mut self, rhs: &Self) {
+        let c0 = &self.c0 * &rhs.c0 + NON_RESIDUE * (&self.c1 * &rhs.c1);
+        let c1 = &self.c0 * &rhs.c1 + &self.c1 * &rhs.c0;
+        *self = Self::new(c0, c1);
+    }
+}
+
+impl MulAssign<&Fp3> for Fp3 {
+    fn mul_assign(&mut self, rhs: &Self) {
+        let (a, b) = (self, rhs);
+        let d0 = &a.c0 * &b.c0;
+        let d1 = &a.c0 * &b.c1 + &a.c1 * &b.c0;
+        let d2 = &a.c0 * &b.c2 + &a.c1 * &b.c1 + &a.c2 * &b.c0;
+        let d3 = &a.c1 * &b.c2 + &a.c2 * &b.c1;
+        let d4 = &a.c2 * &b.c2;
+        // Reduce with $v^3 = 1 - v$ and $v^4 = v - v^2$
+        *a = Fp3::new(d0 + &d3, d1 - d3 + &d4, d2 - d4);
+    }
+}
+
+impl DivAssign<&Fp2> for Fp2 {
+    fn div_assign(&mut self, rhs: &Self) {
+        *self *= rhs.inv().unwrap();
+    }
+}
+
+impl DivAssign<&Fp3> for Fp3 {
+    fn div_assign(&mut self, rhs: &Self) {
+        *self *= rhs.inv().unwrap();
+    }
+}
+
+commutative_binop!(Fp2, Add, add, AddAssign, add_assign);
+commutative_binop!(Fp2, Mul, mul, MulAssign, mul_assign);
+noncommutative_binop!(Fp2, Sub, sub, SubAssign, sub_assign);
+noncommutative_binop!(Fp2, Div, div, DivAssign, div_assign);
+commutative_binop!(Fp3, Add, add, AddAssign, add_assign);
+commutative_binop!(Fp3, Mul, mul, MulAssign, mul_assign);
+noncommutative_binop!(Fp3, Sub, sub, SubAssign, sub_assign);
+noncommutative_binop!(Fp3, Div, div, DivAssign, div_assign);
+
+#[cfg(feature = "std")]
+impl fmt::Debug for Fp2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fp2({:?}, {:?})", self.c0, self.c1)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for Fp3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fp3({:?}, {:?}, {:?})", self.c0, self.c1, self.c2)
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+use quickcheck::{Arbitrary, Gen};
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for Fp2 {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Self::new(FieldElement::arbitrary(g), FieldElement::arbitrary(g))
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for Fp3 {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Self::new(
+            FieldElement::arbitrary(g),
+            FieldElement::arbitrary(g),
+            FieldElement::arbitrary(g),
+        )
+    }
+}
+
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn non_residue_test() {
+        let half_order = (FieldElement::MODULUS - U256::ONE) >> 1;
+        assert_eq!(NON_RESIDUE.pow(half_order), FieldElement::NEGATIVE_ONE);
+        let v = Fp3::new(FieldElement::ZERO, FieldElement::ONE, FieldElement::ZERO);
+        assert_eq!(v.pow(3) + &v, Fp3::ONE);
+        assert_eq!(v.pow(FieldElement::MODULUS), FROBENIUS_V);
+    }
+
+    #[quickcheck]
+    fn fp2_field_axioms(a: Fp2, b: Fp2, c: Fp2) -> bool {
+        &a * (&b + &c) == &a * &b + &a * &c
+            && (&a * &b) * &c == &a * (&b * &c)
+            && &a * &b == &b * &a
+            && a.square() == &a * &a
+            && &a - &a == Fp2::ZERO
+    }
+
+    #[quickcheck]
+    fn fp3_field_axioms(a: Fp3, b: Fp3, c: Fp3) -> bool {
+        &a * (&b + &c) == &a * &b + &a * &c
+            && (&a * &b) * &c == &a * (&b * &c)
+            && &a * &b == &b * &a
+            && &a - &a == Fp3::ZERO
+    }
+
+    #[quickcheck]
+    fn fp2_inverse(a: Fp2) -> bool {
+        match a.inv() {
+            None => a.is_zero(),
+            Some(inverse) => a * inverse == Fp2::ONE,
+        }
+    }
+
+    #[quickcheck]
+    fn fp3_inverse(a: Fp3) -> bool {
+        match a.inv() {
+            None => a.is_zero(),
+            Some(inverse) => a * inverse == Fp3::ONE,
+        }
+    }
+
+    #[quickcheck]
+    fn fp2_frobenius(a: Fp2) -> bool {
+        a.frobenius() == a.pow(FieldElement::MODULUS) && a.frobenius().frobenius() == a
+    }
+
+    #[quickcheck]
+    fn fp3_frobenius(a: Fp3) -> bool {
+        a.frobenius() == a.pow(FieldElement::MODULUS) && a.frobenius().frobenius().frobenius() == a
+    }
+
+    #[quickcheck]
+    fn fp3_norm(a: Fp3, b: Fp3) -> bool {
+        (&a * &b).norm() == a.norm() * b.norm()
+    }
+
+    #[quickcheck]
+    fn fft_extension_test(values: Vec<Fp2>) -> bool {
+        if values.is_empty() {
+            return true;
+        }
+        let values = &values[..(values.len() + 1).next_power_of_two() / 2];
+        let root = FieldElement::root(values.len()).unwrap();
+        fft_extension(values).iter().enumerate().all(|(i, result)| {
+            let x = root.pow(i);
+            let expected = values
+                .iter()
+                .rev()
+                .fold(Fp2::ZERO, |acc, value| acc.scale(&x) + value);
+            *result == expected
+        })
+    }
+}
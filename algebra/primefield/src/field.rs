@@ -1,10 +1,12 @@
 use crate::{montgomery::*, square_root::square_root};
+#[cfg(all(feature = "asm", target_arch = "x86_64"))]
+use crate::{montgomery_adx, montgomery_avx512};
 use std::{
     ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
     prelude::v1::*,
 };
 use zkp_macros_decl::u256h;
-use zkp_u256::{commutative_binop, noncommutative_binop, U256};
+use zkp_u256::{commutative_binop, noncommutative_binop, I256, U256};
 // TODO: Implement Serde
 #[cfg(feature = "std")]
 use std::fmt;
@@ -34,6 +36,25 @@ impl FieldElement {
         Self(to_montgomery_const(n))
     }
 
+    /// Parse a hexadecimal number in a `const` context and reduce it to a
+    /// field element.
+    ///
+    /// This allows declaring constants without the `field_element!` macro:
+    ///
+    /// ```
+    /// # use zkp_primefield::FieldElement;
+    /// const TWO: FieldElement = FieldElement::from_hex("0x2");
+    /// assert_eq!(TWO, FieldElement::from(2_u64));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics, or fails to compile when used in a constant, if `s` is not a
+    /// hexadecimal number that fits in 256 bits.
+    pub const fn from_hex(s: &str) -> Self {
+        Self::from_u256_const(&U256::from_hex(s))
+    }
+
     pub const fn from_montgomery(n: U256) -> Self {
         // TODO: Uncomment assertion when support in `const fn` is enabled.
         // See https://github.com/rust-lang/rust/issues/57563
@@ -41,9 +62,11 @@ impl FieldElement {
         Self(n)
     }
 
-    #[cfg(feature = "std")]
+    /// # Panics
+    ///
+    /// Panics if `s` is not a hexadecimal number that fits in 256 bits.
     pub fn from_hex_str(s: &str) -> Self {
-        Self::from(U256::from_hex_str(s))
+        Self::from(U256::from_hex_str(s).expect("Invalid hexadecimal number"))
     }
 
     pub fn as_montgomery(&self) -> &U256 {
@@ -80,6 +103,17 @@ impl FieldElement {
         square_root(self)
     }
 
+    /// Legendre symbol, `1` for non-zero squares, `-1` for non-squares and
+    /// `0` for zero.
+    pub fn legendre(&self) -> i8 {
+        U256::from(self).jacobi(&Self::MODULUS)
+    }
+
+    /// Returns `true` if the element has a square root, which includes zero.
+    pub fn is_quadratic_residue(&self) -> bool {
+        self.legendre() != -1
+    }
+
     pub fn neg_assign(&mut self) {
         *self = self.neg()
     }
@@ -111,6 +145,76 @@ impl FieldElement {
         }
         Some(Self::GENERATOR.pow(q))
     }
+
+    /// Pointwise `a[i] += b[i]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices differ in length.
+    pub fn add_slice(a: &mut [Self], b: &[Self]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter_mut().zip(b) {
+            *a += b;
+        }
+    }
+
+    /// Pointwise `a[i] -= b[i]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices differ in length.
+    pub fn sub_slice(a: &mut [Self], b: &[Self]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter_mut().zip(b) {
+            *a -= b;
+        }
+    }
+
+    /// Pointwise `a[i] *= b[i]`.
+    ///
+    /// With the `asm` feature on x86_64 CPUs with AVX-512 IFMA, eight
+    /// elements are multiplied at once using 52-bit limbs. AVX2 has no wide
+    /// enough multiplication to beat the scalar code. Otherwise the elements
+    /// are processed in groups of four independent multiplications to keep
+    /// the multiplier busy.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices differ in length.
+    pub fn mul_slice(a: &mut [Self], b: &[Self]) {
+        assert_eq!(a.len(), b.len());
+        #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+        {
+            if montgomery_avx512::is_supported() {
+                #[allow(unsafe_code)]
+                return unsafe { montgomery_avx512::mul_slice(a, b) };
+            }
+            if montgomery_adx::is_supported() {
+                #[allow(unsafe_code)]
+                return unsafe { montgomery_adx::mul_slice(a, b) };
+            }
+        }
+        let mut a_chunks = a.chunks_exact_mut(4);
+        let mut b_chunks = b.chunks_exact(4);
+        for (a, b) in (&mut a_chunks).zip(&mut b_chunks) {
+            let products = [
+                mul_redc(&a[0].0, &b[0].0),
+                mul_redc(&a[1].0, &b[1].0),
+                mul_redc(&a[2].0, &b[2].0),
+                mul_redc(&a[3].0, &b[3].0),
+            ];
+            for (a, product) in a.iter_mut().zip(products.iter()) {
+                a.0 = product.clone();
+            }
+        }
+        for (a, b) in a_chunks
+            .into_remainder()
+            .iter_mut()
+            .zip(b_chunks.remainder())
+        {
+            *a *= b;
+        }
+    }
 }
 
 pub fn invert_batch_src_dst(source: &[FieldElement], destination: &mut [FieldElement]) {
@@ -246,6 +350,19 @@ impl FieldElement {
     to_int!(to_isize, as_isize, isize);
 }
 
+impl FieldElement {
+    /// Signed representative in the range `-(p - 1) / 2 ..= (p - 1) / 2`.
+    pub fn to_i256(&self) -> I256 {
+        let n = U256::from(self);
+        let half = Self::MODULUS >> 1;
+        if n <= half {
+            I256::from_bits(n)
+        } else {
+            I256::from_sign_magnitude(true, Self::MODULUS - n)
+        }
+    }
+}
+
 impl From<U256> for FieldElement {
     fn from(n: U256) -> Self {
         (&n).into()
@@ -258,6 +375,19 @@ impl From<&U256> for FieldElement {
     }
 }
 
+/// Negative numbers map to `p - |n|`.
+impl From<I256> for FieldElement {
+    fn from(n: I256) -> Self {
+        (&n).into()
+    }
+}
+
+impl From<&I256> for FieldElement {
+    fn from(n: &I256) -> Self {
+        Self::from(n.rem_euclid(&Self::MODULUS))
+    }
+}
+
 impl From<FieldElement> for U256 {
     fn from(n: FieldElement) -> Self {
         (&n).into()
@@ -332,6 +462,27 @@ impl Arbitrary for FieldElement {
     }
 }
 
+#[cfg(feature = "rand")]
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+/// Uniformly distributed over the field.
+#[cfg(feature = "rand")]
+impl Distribution<FieldElement> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> FieldElement {
+        // Rejection sample the Montgomery form, which is then uniform as well.
+        let shift = FieldElement::MODULUS.leading_zeros();
+        loop {
+            let candidate = U256::from_limbs(rng.gen(), rng.gen(), rng.gen(), rng.gen()) >> shift;
+            if candidate < FieldElement::MODULUS {
+                return FieldElement::from_montgomery(candidate);
+            }
+        }
+    }
+}
+
 #[allow(unused_macros)]
 macro_rules! field_h {
     (- $e:expr) => {
@@ -423,6 +574,31 @@ mod tests {
         assert_eq!(a / b, c);
     }
 
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random() {
+        use rand::{rngs::mock::StepRng, Rng};
+        let mut rng = StepRng::new(1, 0x9e37_79b9_7f4a_7c15);
+        let samples: Vec<FieldElement> = (0..100).map(|_| rng.gen()).collect();
+        assert!(samples
+            .iter()
+            .all(|sample| sample.as_montgomery() < &FieldElement::MODULUS));
+        assert_ne!(samples[0], samples[1]);
+    }
+
+    #[quickcheck]
+    fn slice_ops(a: Vec<FieldElement>, b: Vec<FieldElement>) -> bool {
+        let n = std::cmp::min(a.len(), b.len());
+        let (a, b) = (&a[..n], &b[..n]);
+        let (mut sum, mut difference, mut product) = (a.to_vec(), a.to_vec(), a.to_vec());
+        FieldElement::add_slice(&mut sum, b);
+        FieldElement::sub_slice(&mut difference, b);
+        FieldElement::mul_slice(&mut product, b);
+        (0..n).all(|i| {
+            sum[i] == &a[i] + &b[i] && difference[i] == &a[i] - &b[i] && product[i] == &a[i] * &b[i]
+        })
+    }
+
     #[quickcheck]
     fn test_batch_inv(x: Vec<FieldElement>) -> bool {
         if x.iter().any(FieldElement::is_zero) {
@@ -445,6 +621,47 @@ mod tests {
         FieldElement::from(n).to_i128() == n
     }
 
+    #[quickcheck]
+    fn from_to_i256(a: FieldElement) -> bool {
+        FieldElement::from(a.to_i256()) == a
+    }
+
+    #[test]
+    fn test_from_hex_const() {
+        const A: FieldElement = FieldElement::from_hex(
+            "0x03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb",
+        );
+        assert_eq!(
+            A,
+            field_element!("03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb")
+        );
+        // Values beyond the modulus are reduced
+        const B: FieldElement = FieldElement::from_hex(
+            "0800000000000011000000000000000000000000000000000000000000000003",
+        );
+        assert_eq!(B, FieldElement::from(2_u64));
+        // Small values take the path that adds back the modulus
+        const C: FieldElement = FieldElement::from_hex("0x05");
+        assert_eq!(C, FieldElement::from(5_u64));
+        const D: FieldElement = FieldElement::from_hex(
+            "0800000000000011000000000000000000000000000000000000000000000001",
+        );
+        assert_eq!(D, FieldElement::ZERO);
+    }
+
+    #[test]
+    fn test_from_i256() {
+        assert_eq!(
+            FieldElement::from(I256::MINUS_ONE),
+            FieldElement::NEGATIVE_ONE
+        );
+        assert_eq!(FieldElement::NEGATIVE_ONE.to_i256(), I256::MINUS_ONE);
+        assert_eq!(
+            FieldElement::from(I256::MIN),
+            -&FieldElement::from(U256::ONE << 255)
+        );
+    }
+
     #[quickcheck]
     fn add_identity(a: FieldElement) -> bool {
         &a + FieldElement::ZERO == a
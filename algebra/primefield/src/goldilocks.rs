@@ -0,0 +1,375 @@
+//! The 64-bit prime field with modulus $p = 2^{64} - 2^{32} + 1$.
+//!
+//! Elements fit a machine word and products reduce with a few word
+//! operations using $2^{64} \equiv 2^{32} - 1$ and $2^{96} \equiv -1$. The
+//! multiplicative group has order divisible by $2^{32}$, so FFTs of up to
+//! $2^{32}$ elements are supported.
+use crate::fft::{permute, permute_index};
+#[cfg(feature = "std")]
+use std::fmt;
+use std::{
+    ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    prelude::v1::*,
+};
+use zkp_u256::{commutative_binop, noncommutative_binop};
+
+/// $2^{64} - p = 2^{32} - 1$.
+const EPSILON: u64 = 0xffff_ffff;
+
+/// An element of the Goldilocks field in canonical form.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Hash)]
+pub struct Goldilocks(u64);
+
+impl Goldilocks {
+    /// Generator of the multiplicative group.
+    pub const GENERATOR: Self = Self(7);
+    pub const MODULUS: u64 = 0xffff_ffff_0000_0001;
+    pub const NEGATIVE_ONE: Self = Self(Self::MODULUS - 1);
+    pub const ONE: Self = Self(1);
+    /// Largest $k$ such that $2^k$ divides $p - 1$.
+    pub const TWO_ADICITY: usize = 32;
+    pub const ZERO: Self = Self(0);
+
+    /// Construct from a value that is already reduced.
+    pub const fn from_canonical(value: u64) -> Self {
+        // TODO: Uncomment assertion when support in `const fn` is enabled.
+        // debug_assert!(value < Self::MODULUS);
+        Self(value)
+    }
+
+    /// The representative in `0..MODULUS`.
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.0 == 1
+    }
+
+    pub fn inv(&self) -> Option<Self> {
+        if self.is_zero() {
+            None
+        } else {
+            Some(self.pow(Self::MODULUS - 2))
+        }
+    }
+
+    pub fn double(&self) -> Self {
+        *self + self
+    }
+
+    pub fn square(&self) -> Self {
+        *self * self
+    }
+
+    pub fn pow(&self, exponent: u64) -> Self {
+        let mut result = Self::ONE;
+        let mut square = *self;
+        let mut remaining_exponent = exponent;
+        while remaining_exponent != 0 {
+            if remaining_exponent & 1 == 1 {
+                result *= &square;
+            }
+            remaining_exponent >>= 1;
+            square = square.square();
+        }
+        result
+    }
+
+    /// A primitive `n`-th root of unity, if it exists.
+    pub fn root(n: u64) -> Option<Self> {
+        if n == 0 {
+            return Some(Self::ONE);
+        }
+        if (Self::MODULUS - 1) % n != 0 {
+            return None;
+        }
+        Some(Self::GENERATOR.pow((Self::MODULUS - 1) / n))
+    }
+}
+
+/// Reduce a 128-bit value modulo $p$.
+fn reduce128(x: u128) -> u64 {
+    // Truncations are intentional
+    #[allow(clippy::cast_possible_truncation)]
+    let (low, high) = (x as u64, (x >> 64) as u64);
+    let (high_high, high_low) = (high >> 32, high & EPSILON);
+
+    // low - high_high * 2^96, where 2^96 = -1
+    let (mut result, borrow) = low.overflowing_sub(high_high);
+    if borrow {
+        // Adding p modulo 2^64, can not underflow since high_high < 2^32
+        result -= EPSILON;
+    }
+
+    // + high_low * 2^64, where 2^64 = EPSILON. The product is less than 2^64.
+    let (result, carry) = result.overflowing_add(high_low * EPSILON);
+    let result = if carry {
+        // Can not overflow since the sum wrapped
+        result + EPSILON
+    } else {
+        result
+    };
+    if result >= Goldilocks::MODULUS {
+        result - Goldilocks::MODULUS
+    } else {
+        result
+    }
+}
+
+impl From<u64> for Goldilocks {
+    fn from(n: u64) -> Self {
+        if n >= Self::MODULUS {
+            Self(n - Self::MODULUS)
+        } else {
+            Self(n)
+        }
+    }
+}
+
+impl From<u32> for Goldilocks {
+    fn from(n: u32) -> Self {
+        Self(n.into())
+    }
+}
+
+impl From<usize> for Goldilocks {
+    fn from(n: usize) -> Self {
+        Self::from(n as u64)
+    }
+}
+
+impl From<Goldilocks> for u64 {
+    fn from(n: Goldilocks) -> Self {
+        n.0
+    }
+}
+
+impl Neg for &Goldilocks {
+    type Output = Goldilocks;
+
+    fn neg(self) -> Self::Output {
+        if self.is_zero() {
+            *self
+        } else {
+            Goldilocks(Goldilocks::MODULUS - self.0)
+        }
+    }
+}
+
+impl AddAssign<&Goldilocks> for Goldilocks {
+    fn add_assign(&mut self, rhs: &Self) {
+        let (sum, carry) = self.0.overflowing_add(rhs.0);
+        self.0 = if carry || sum >= Self::MODULUS {
+            sum.wrapping_sub(Self::MODULUS)
+        } else {
+            sum
+        };
+    }
+}
+
+impl SubAssign<&Goldilocks> for Goldilocks {
+    fn sub_assign(&mut self, rhs: &Self) {
+        let (difference, borrow) = self.0.overflowing_sub(rhs.0);
+        self.0 = if borrow {
+            difference.wrapping_add(Self::MODULUS)
+        } else {
+            difference
+        };
+    }
+}
+
+impl MulAssign<&Goldilocks> for Goldilocks {
+    fn mul_assign(&mut self, rhs: &Self) {
+        self.0 = reduce128(u128::from(self.0) * u128::from(rhs.0));
+    }
+}
+
+impl DivAssign<&Goldilocks> for Goldilocks {
+    fn div_assign(&mut self, rhs: &Self) {
+        *self *= rhs.inv().unwrap();
+    }
+}
+
+commutative_binop!(Goldilocks, Add, add, AddAssign, add_assign);
+commutative_binop!(Goldilocks, Mul, mul, MulAssign, mul_assign);
+noncommutative_binop!(Goldilocks, Sub, sub, SubAssign, sub_assign);
+noncommutative_binop!(Goldilocks, Div, div, DivAssign, div_assign);
+
+#[cfg(feature = "std")]
+impl fmt::Debug for Goldilocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Goldilocks({})", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Goldilocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Out-of-place FFT with non-permuted result.
+pub fn fft(a: &[Goldilocks]) -> Vec<Goldilocks> {
+    let mut result = a.to_owned();
+    fft_permuted(&mut result);
+    permute(&mut result);
+    result
+}
+
+/// Out-of-place inverse FFT with non-permuted result.
+pub fn ifft(a: &[Goldilocks]) -> Vec<Goldilocks> {
+    let mut result = a.to_owned();
+    ifft_permuted(&mut result);
+    permute(&mut result);
+    result
+}
+
+/// In-place permuted FFT.
+pub fn fft_permuted(x: &mut [Goldilocks]) {
+    let root = Goldilocks::root(x.len() as u64).expect("No root of unity for input length");
+    fft_permuted_root(root, x);
+}
+
+/// In-place permuted inverse FFT.
+pub fn ifft_permuted(x: &mut [Goldilocks]) {
+    let inverse_root = Goldilocks::root(x.len() as u64)
+        .expect("No root of unity for input length")
+        .inv()
+        .expect("No inverse for Goldilocks::ZERO");
+    let inverse_length = Goldilocks::from(x.len())
+        .inv()
+        .expect("No inverse length for empty list");
+    fft_permuted_root(inverse_root, x);
+    for e in x {
+        *e *= &inverse_length;
+    }
+}
+
+fn fft_permuted_root(root: Goldilocks, coefficients: &mut [Goldilocks]) {
+    let n_elements = coefficients.len();
+    debug_assert!(n_elements.is_power_of_two());
+    debug_assert!(root.pow(n_elements as u64).is_one());
+    for layer in 0..n_elements.trailing_zeros() {
+        let n_blocks = 1_usize << layer;
+        let mut twiddle_factor = Goldilocks::ONE;
+        let block_size = n_elements >> (layer + 1);
+        let twiddle_factor_update = root.pow(block_size as u64);
+        for block in 0..n_blocks {
+            let block_start = 2 * permute_index(n_blocks, block) * block_size;
+            for i in block_start..block_start + block_size {
+                let j = i + block_size;
+                let left = coefficients[i];
+                let right = coefficients[j] * twiddle_factor;
+                coefficients[i] = left + right;
+                coefficients[j] = left - right;
+            }
+            twiddle_factor *= &twiddle_factor_update;
+        }
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+use quickcheck::{Arbitrary, Gen};
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for Goldilocks {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Self::from(u64::arbitrary(g))
+    }
+}
+
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    fn reference_mul(a: Goldilocks, b: Goldilocks) -> Goldilocks {
+        let product = u128::from(a.0) * u128::from(b.0) % u128::from(Goldilocks::MODULUS);
+        Goldilocks(product as u64)
+    }
+
+    #[test]
+    fn test_reduce() {
+        let max = Goldilocks::NEGATIVE_ONE;
+        assert_eq!(max * max, Goldilocks::ONE);
+        assert_eq!(
+            u128::from(reduce128(u128::max_value())),
+            u128::max_value() % u128::from(Goldilocks::MODULUS)
+        );
+        assert_eq!(Goldilocks::ONE + Goldilocks::NEGATIVE_ONE, Goldilocks::ZERO);
+        assert_eq!(-&Goldilocks::ZERO, Goldilocks::ZERO);
+        assert_eq!(Goldilocks::from(u64::max_value()).as_u64(), EPSILON - 1);
+    }
+
+    #[test]
+    fn test_root() {
+        let root = Goldilocks::root(1_u64 << 32).unwrap();
+        assert_eq!(root.pow(1_u64 << 31), Goldilocks::NEGATIVE_ONE);
+        assert_eq!(Goldilocks::root(3).unwrap().pow(3), Goldilocks::ONE);
+        assert_eq!(Goldilocks::root(7), None);
+        // The generator is not a square
+        assert_eq!(
+            Goldilocks::GENERATOR.pow((Goldilocks::MODULUS - 1) / 2),
+            Goldilocks::NEGATIVE_ONE
+        );
+    }
+
+    #[quickcheck]
+    fn mul_matches_reference(a: Goldilocks, b: Goldilocks) -> bool {
+        a * b == reference_mul(a, b)
+    }
+
+    #[quickcheck]
+    fn reduce_matches_reference(x: u128) -> bool {
+        u128::from(reduce128(x)) == x % u128::from(Goldilocks::MODULUS)
+    }
+
+    #[quickcheck]
+    fn add_sub(a: Goldilocks, b: Goldilocks) -> bool {
+        a + b - b == a && a - b == a + -&b
+    }
+
+    #[quickcheck]
+    fn distributivity(a: Goldilocks, b: Goldilocks, c: Goldilocks) -> bool {
+        a * (b + c) == a * b + a * c
+    }
+
+    #[quickcheck]
+    fn inverse(a: Goldilocks) -> bool {
+        match a.inv() {
+            None => a.is_zero(),
+            Some(inverse) => a * inverse == Goldilocks::ONE,
+        }
+    }
+
+    #[quickcheck]
+    fn fft_ifft(values: Vec<Goldilocks>) -> bool {
+        if values.is_empty() {
+            return true;
+        }
+        let values = &values[..(values.len() + 1).next_power_of_two() / 2];
+        ifft(&fft(values)) == values
+    }
+
+    #[test]
+    fn test_fft() {
+        let values: Vec<_> = (0..8_u64).map(Goldilocks::from).collect();
+        let root = Goldilocks::root(8).unwrap();
+        for (i, result) in fft(&values).iter().enumerate() {
+            let x = root.pow(i as u64);
+            let expected = values
+                .iter()
+                .rev()
+                .fold(Goldilocks::ZERO, |acc, value| acc * x + value);
+            assert_eq!(*result, expected);
+        }
+    }
+}
@@ -3,7 +3,8 @@
 // For clippy lints see: https://rust-lang.github.io/rust-clippy/master
 // For rustc lints see: https://doc.rust-lang.org/rustc/lints/index.html
 #![cfg_attr(not(feature = "std"), no_std)]
-#![forbid(unsafe_code)]
+// The `asm` backend uses CPU intrinsics, which need `unsafe`
+#![cfg_attr(not(feature = "asm"), forbid(unsafe_code))]
 #![warn(
     // Enable sets of warnings
     clippy::all,
@@ -37,13 +38,23 @@
 )]
 #![cfg_attr(feature = "std", warn(missing_debug_implementations,))]
 
+mod extension_field;
 pub mod fft;
 mod field;
 pub mod geometric_series;
+pub mod goldilocks;
 mod montgomery;
+#[cfg(all(feature = "asm", target_arch = "x86_64"))]
+mod montgomery_adx;
+#[cfg(all(feature = "asm", target_arch = "x86_64"))]
+mod montgomery_avx512;
+mod polynomial;
 mod square_root;
 
+pub use extension_field::{fft_extension, ExtensionField, Fp2, Fp3};
 pub use field::FieldElement;
+pub use goldilocks::Goldilocks;
+pub use polynomial::{Polynomial, SparsePolynomial};
 
 // TODO: Make member functions of FieldElement?
 pub use field::{invert_batch, invert_batch_src_dst};
@@ -1,6 +1,8 @@
 // Names `from` and `to` are not very meaningful on their own
 #![allow(clippy::module_name_repetitions)]
 use crate::field::FieldElement;
+#[cfg(all(feature = "asm", target_arch = "x86_64"))]
+use crate::montgomery_adx;
 use zkp_macros_decl::u256h;
 use zkp_u256::{
     utils::{adc, mac, sbb},
@@ -75,12 +77,12 @@ pub(crate) const fn to_montgomery_const(x: &U256) -> U256 {
     let (a2, borrow) = sbb(a2, FieldElement::MODULUS.c2, borrow);
     let (a3, borrow) = sbb(a3, FieldElement::MODULUS.c3, borrow);
     // Now we may have accidentally subtracted where we shouldn't.
-    // If this is the case `borrow == 1` and else `borrow = 0`. We can
-    // use  this to conditionally add back a modulus.
-    let (a0, carry) = adc(a0, borrow * FieldElement::MODULUS.c0, 0);
-    let (a1, carry) = adc(a1, borrow * FieldElement::MODULUS.c1, carry);
-    let (a2, carry) = adc(a2, borrow * FieldElement::MODULUS.c2, carry);
-    let (a3, _) = adc(a3, borrow * FieldElement::MODULUS.c3, carry);
+    // If this is the case all bits of `borrow` are set and else `borrow = 0`.
+    // We can use this as a mask to conditionally add back a modulus.
+    let (a0, carry) = adc(a0, borrow & FieldElement::MODULUS.c0, 0);
+    let (a1, carry) = adc(a1, borrow & FieldElement::MODULUS.c1, carry);
+    let (a2, carry) = adc(a2, borrow & FieldElement::MODULUS.c2, carry);
+    let (a3, _) = adc(a3, borrow & FieldElement::MODULUS.c3, carry);
     // Return the now reduced result
     U256::from_limbs(a0, a1, a2, a3)
 }
@@ -123,9 +125,23 @@ pub(crate) fn redc(lo: &U256, hi: &U256) -> U256 {
     r
 }
 
+/// Montgomery multiplication, using the `mulx`/`adcx` backend if the CPU
+/// supports it.
+#[inline]
+pub(crate) fn mul_redc(x: &U256, y: &U256) -> U256 {
+    #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+    {
+        if montgomery_adx::is_supported() {
+            #[allow(unsafe_code)]
+            return unsafe { montgomery_adx::mul_redc(x, y) };
+        }
+    }
+    mul_redc_portable(x, y)
+}
+
 // We rebind variables for readability
 #[allow(clippy::shadow_unrelated)]
-pub(crate) fn mul_redc(x: &U256, y: &U256) -> U256 {
+pub(crate) fn mul_redc_portable(x: &U256, y: &U256) -> U256 {
     // TODO: This might not be faster than:
     // let (lo, hi) = x.mul_full(y);
     // return redc(&lo, &hi);
@@ -183,6 +199,13 @@ pub(crate) fn mul_redc(x: &U256, y: &U256) -> U256 {
 }
 
 pub(crate) fn sqr_redc(a: &U256) -> U256 {
+    #[cfg(all(feature = "asm", target_arch = "x86_64"))]
+    {
+        if montgomery_adx::is_supported() {
+            #[allow(unsafe_code)]
+            return unsafe { montgomery_adx::mul_redc(a, a) };
+        }
+    }
     let (lo, hi) = a.sqr_full();
     redc(&lo, &hi)
 }
@@ -219,6 +242,7 @@ mod tests {
         let b = u256h!("024385f6bebc1c496e09955db534ef4b1eaff9a78e27d4093cfa8f7c8f886f6b");
         let c = u256h!("012b854fc6321976d374ad069cfdec8bb7b2bd184259dae8f530cbb28f0805b4");
         assert_eq!(mul_redc(&a, &b), c);
+        assert_eq!(mul_redc_portable(&a, &b), c);
     }
 
     #[quickcheck]
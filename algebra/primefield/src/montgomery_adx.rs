@@ -0,0 +1,142 @@
+// Intrinsics are inherently unsafe, the caller checks CPU support.
+#![allow(unsafe_code)]
+use crate::field::FieldElement;
+use core::{
+    arch::x86_64::{_addcarryx_u64, _mulx_u64},
+    sync::atomic::{AtomicU8, Ordering},
+};
+use zkp_u256::U256;
+
+const UNKNOWN: u8 = 0;
+const UNSUPPORTED: u8 = 1;
+const SUPPORTED: u8 = 2;
+
+// Result of the CPU feature detection, so multiplications only pay for a
+// relaxed load.
+static SUPPORT: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+/// Whether the CPU supports the `mulx` and `adcx`/`adox` instructions.
+///
+/// The features are detected on the first call only.
+#[inline]
+pub(crate) fn is_supported() -> bool {
+    match SUPPORT.load(Ordering::Relaxed) {
+        UNKNOWN => {
+            let supported = is_x86_feature_detected!("bmi2") && is_x86_feature_detected!("adx");
+            SUPPORT.store(
+                if supported { SUPPORTED } else { UNSUPPORTED },
+                Ordering::Relaxed,
+            );
+            supported
+        }
+        support => support == SUPPORTED,
+    }
+}
+
+#[inline(always)]
+unsafe fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let mut hi = 0;
+    let lo = _mulx_u64(b, c, &mut hi);
+    let mut result = 0;
+    let carry_1 = _addcarryx_u64(0, lo, a, &mut result);
+    let carry_2 = _addcarryx_u64(0, result, carry, &mut result);
+    // Can not overflow as the full result is less than 2^128
+    (result, hi + u64::from(carry_1) + u64::from(carry_2))
+}
+
+#[inline(always)]
+unsafe fn adc(a: u64, b: u64, carry: u8) -> (u64, u8) {
+    let mut result = 0;
+    let carry = _addcarryx_u64(carry, a, b, &mut result);
+    (result, carry)
+}
+
+/// Montgomery multiplication using `mulx` and `adcx`.
+///
+/// The reduction is specialized for the modulus, which has
+/// `M64 = -1` and limbs `[1, 0, 0, c3]`,  so adding a multiple `k` of the
+/// modulus takes a single multiplication per round.
+///
+/// # Safety
+///
+/// The CPU must support the `bmi2` and `adx` features, see
+/// [`is_supported`](fn.is_supported.html).
+// We rebind variables for readability
+#[allow(clippy::shadow_unrelated)]
+#[inline]
+#[target_feature(enable = "bmi2,adx")]
+pub(crate) unsafe fn mul_redc(x: &U256, y: &U256) -> U256 {
+    const M3: u64 = FieldElement::MODULUS.c3;
+    let (mut a0, mut a1, mut a2, mut a3) = (0, 0, 0, 0);
+    for &xi in &[x.c0, x.c1, x.c2, x.c3] {
+        let (b0, carry) = mac(a0, xi, y.c0, 0);
+        let (b1, carry) = mac(a1, xi, y.c1, carry);
+        let (b2, carry) = mac(a2, xi, y.c2, carry);
+        let (b3, b4) = mac(a3, xi, y.c3, carry);
+        // Add k * MODULUS with k = -b0, which clears the lowest limb.
+        let k = b0.wrapping_neg();
+        let (b1, carry) = adc(b1, 0, (b0 != 0) as u8);
+        let (b2, carry) = adc(b2, 0, carry);
+        let (b3, high) = mac(b3, k, M3, u64::from(carry));
+        // Can not overflow as the intermediate is less than twice the modulus
+        let b4 = b4 + high;
+        a0 = b1;
+        a1 = b2;
+        a2 = b3;
+        a3 = b4;
+    }
+
+    // Final reduction
+    let mut r = U256::from_limbs(a0, a1, a2, a3);
+    if r >= FieldElement::MODULUS {
+        r -= &FieldElement::MODULUS;
+    }
+    r
+}
+
+/// Element-wise Montgomery multiplication `a[i] = a[i] * b[i]`.
+///
+/// Dispatching once per slice lets [`mul_redc`](fn.mul_redc.html) inline
+/// into the loop, which a call per element can not.
+///
+/// # Safety
+///
+/// The CPU must support the `bmi2` and `adx` features, see
+/// [`is_supported`](fn.is_supported.html).
+#[target_feature(enable = "bmi2,adx")]
+pub(crate) unsafe fn mul_slice(a: &mut [FieldElement], b: &[FieldElement]) {
+    for (a, b) in a.iter_mut().zip(b) {
+        *a = FieldElement::from_montgomery(mul_redc(a.as_montgomery(), b.as_montgomery()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::montgomery::mul_redc_portable;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn mul_redc_matches_portable(a: FieldElement, b: FieldElement) -> bool {
+        if !is_supported() {
+            return true;
+        }
+        let (a, b) = (a.as_montgomery(), b.as_montgomery());
+        unsafe { mul_redc(a, b) == mul_redc_portable(a, b) }
+    }
+
+    #[test]
+    fn test_mul_redc_edge_cases() {
+        if !is_supported() {
+            return;
+        }
+        let max = FieldElement::MODULUS - U256::ONE;
+        for (a, b) in &[
+            (U256::ZERO, max.clone()),
+            (max.clone(), max.clone()),
+            (U256::ONE, max.clone()),
+        ] {
+            assert_eq!(unsafe { mul_redc(a, b) }, mul_redc_portable(a, b));
+        }
+    }
+}
@@ -0,0 +1,241 @@
+// Intrinsics are inherently unsafe, the caller checks CPU support.
+#![allow(unsafe_code)]
+use crate::field::FieldElement;
+use core::{
+    arch::x86_64::{
+        __m512i, _mm512_add_epi64, _mm512_and_si512, _mm512_loadu_si512, _mm512_madd52hi_epu64,
+        _mm512_madd52lo_epu64, _mm512_set1_epi64, _mm512_setzero_si512, _mm512_srli_epi64,
+        _mm512_storeu_si512, _mm512_sub_epi64,
+    },
+    sync::atomic::{AtomicU8, Ordering},
+};
+use zkp_u256::U256;
+
+const UNKNOWN: u8 = 0;
+const UNSUPPORTED: u8 = 1;
+const SUPPORTED: u8 = 2;
+
+// Result of the CPU feature detection, so slices only pay for a relaxed load.
+static SUPPORT: AtomicU8 = AtomicU8::new(UNKNOWN);
+
+/// Number of elements processed at once, one per 64-bit lane.
+const LANES: usize = 8;
+
+const MASK: u64 = (1 << 52) - 1;
+
+/// The non-zero 52-bit limbs of the modulus besides the lowest, which is one.
+///
+/// The modulus is `2^251 + 17 * 2^192 + 1`, and `192 = 3 * 52 + 36`,
+/// `251 = 4 * 52 + 43`.
+const M3: u64 = 17 << 36;
+const M4: u64 = 1 << 43;
+
+/// Whether the CPU supports the AVX-512 foundation and integer fused
+/// multiply-add instructions.
+///
+/// The features are detected on the first call only.
+#[inline]
+pub(crate) fn is_supported() -> bool {
+    match SUPPORT.load(Ordering::Relaxed) {
+        UNKNOWN => {
+            let supported =
+                is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512ifma");
+            SUPPORT.store(
+                if supported { SUPPORTED } else { UNSUPPORTED },
+                Ordering::Relaxed,
+            );
+            supported
+        }
+        support => support == SUPPORTED,
+    }
+}
+
+/// Split `x << shift` in five 52-bit limbs.
+fn to_limbs(x: &U256, shift: usize) -> [u64; 5] {
+    let words = [x.c0, x.c1, x.c2, x.c3, 0];
+    let mut result = [0; 5];
+    for (i, result) in result.iter_mut().enumerate() {
+        let bit = 52 * i;
+        *result = if bit < shift {
+            words[0] << (shift - bit)
+        } else {
+            let (word, offset) = ((bit - shift) / 64, (bit - shift) % 64);
+            if offset == 0 {
+                words[word]
+            } else {
+                (words[word] >> offset) | (words[word + 1] << (64 - offset))
+            }
+        } & MASK;
+    }
+    result
+}
+
+/// Combine five unnormalized 52-bit limbs, each less than `2^60`.
+#[allow(clippy::cast_possible_truncation)]
+fn from_limbs(limbs: [u64; 5]) -> U256 {
+    let mut acc = u128::from(limbs[0]) + (u128::from(limbs[1]) << 52);
+    let c0 = acc as u64;
+    acc = (acc >> 64) + (u128::from(limbs[2]) << 40);
+    let c1 = acc as u64;
+    acc = (acc >> 64) + (u128::from(limbs[3]) << 28);
+    let c2 = acc as u64;
+    acc = (acc >> 64) + (u128::from(limbs[4]) << 16);
+    let c3 = acc as u64;
+    U256::from_limbs(c0, c1, c2, c3)
+}
+
+/// The limbs of eight numbers, with limb `i` of every number in vector `i`.
+///
+/// The numbers are multiplied by `2^shift`.
+#[inline]
+#[target_feature(enable = "avx512f")]
+#[allow(clippy::cast_ptr_alignment)]
+unsafe fn transpose(x: &[FieldElement], shift: usize) -> [__m512i; 5] {
+    let mut limbs = [[0_u64; LANES]; 5];
+    for (lane, x) in x.iter().enumerate() {
+        for (i, limb) in to_limbs(x.as_montgomery(), shift).iter().enumerate() {
+            limbs[i][lane] = *limb;
+        }
+    }
+    let mut result = [_mm512_setzero_si512(); 5];
+    for (result, limbs) in result.iter_mut().zip(limbs.iter()) {
+        *result = _mm512_loadu_si512(limbs.as_ptr().cast::<__m512i>());
+    }
+    result
+}
+
+/// Montgomery multiplication of eight pairs of numbers with `R = 2^260`.
+///
+/// The 52-bit limb products accumulate in the 64-bit lanes without carry
+/// propagation. The reduction uses that `-1/M mod 2^52 = -1` and that the
+/// modulus has only three non-zero limbs.
+///
+/// The result limbs are not normalized.
+#[inline]
+#[target_feature(enable = "avx512f,avx512ifma")]
+#[allow(clippy::needless_range_loop, clippy::cast_possible_wrap)]
+unsafe fn mul_redc_x8(x: &[__m512i; 5], y: &[__m512i; 5]) -> [__m512i; 5] {
+    let mask = _mm512_set1_epi64(MASK as i64);
+    let m3 = _mm512_set1_epi64(M3 as i64);
+    let m4 = _mm512_set1_epi64(M4 as i64);
+    let zero = _mm512_setzero_si512();
+
+    // Each limb receives at most ten products, so they stay below 2^56.
+    let mut t = [zero; 10];
+    for i in 0..5 {
+        for j in 0..5 {
+            t[i + j] = _mm512_madd52lo_epu64(t[i + j], x[i], y[j]);
+            t[i + j + 1] = _mm512_madd52hi_epu64(t[i + j + 1], x[i], y[j]);
+        }
+    }
+
+    // Add m * MODULUS with m = -t[i], which clears limb `i`.
+    for i in 0..5 {
+        let m = _mm512_and_si512(_mm512_sub_epi64(zero, t[i]), mask);
+        let carry = _mm512_srli_epi64::<52>(_mm512_add_epi64(t[i], m));
+        t[i + 1] = _mm512_add_epi64(t[i + 1], carry);
+        t[i + 3] = _mm512_madd52lo_epu64(t[i + 3], m, m3);
+        t[i + 4] = _mm512_madd52hi_epu64(t[i + 4], m, m3);
+        t[i + 4] = _mm512_madd52lo_epu64(t[i + 4], m, m4);
+        t[i + 5] = _mm512_madd52hi_epu64(t[i + 5], m, m4);
+    }
+    [t[5], t[6], t[7], t[8], t[9]]
+}
+
+/// Element-wise Montgomery multiplication `a[i] = a[i] * b[i]`, eight
+/// elements at a time.
+///
+/// The field uses `R = 2^256`, so one of the operands is multiplied by
+/// `2^4` before the multiplication with `R = 2^260`. The product is then
+/// less than `(2^4 M^2 + 2^260 M) / 2^260 < 2 M`.
+///
+/// # Safety
+///
+/// The CPU must support the `avx512f` and `avx512ifma` features, see
+/// [`is_supported`](fn.is_supported.html).
+#[target_feature(enable = "avx512f,avx512ifma")]
+#[allow(clippy::cast_ptr_alignment)]
+pub(crate) unsafe fn mul_slice(a: &mut [FieldElement], b: &[FieldElement]) {
+    let mut a_chunks = a.chunks_exact_mut(LANES);
+    let mut b_chunks = b.chunks_exact(LANES);
+    for (a, b) in (&mut a_chunks).zip(&mut b_chunks) {
+        let product = mul_redc_x8(&transpose(a, 4), &transpose(b, 0));
+        let mut limbs = [[0_u64; LANES]; 5];
+        for (limbs, product) in limbs.iter_mut().zip(product.iter()) {
+            _mm512_storeu_si512(limbs.as_mut_ptr().cast::<__m512i>(), *product);
+        }
+        for (lane, a) in a.iter_mut().enumerate() {
+            let mut r = from_limbs([
+                limbs[0][lane],
+                limbs[1][lane],
+                limbs[2][lane],
+                limbs[3][lane],
+                limbs[4][lane],
+            ]);
+            if r >= FieldElement::MODULUS {
+                r -= &FieldElement::MODULUS;
+            }
+            *a = FieldElement::from_montgomery(r);
+        }
+    }
+    for (a, b) in a_chunks
+        .into_remainder()
+        .iter_mut()
+        .zip(b_chunks.remainder())
+    {
+        *a *= b;
+    }
+}
+
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn mul_slice_matches_portable(a: Vec<FieldElement>, b: Vec<FieldElement>) -> bool {
+        if !is_supported() {
+            return true;
+        }
+        let n = a.len().min(b.len());
+        let mut product = a[..n].to_vec();
+        unsafe { mul_slice(&mut product, &b[..n]) };
+        (0..n).all(|i| product[i] == &a[i] * &b[i])
+    }
+
+    #[test]
+    fn test_mul_slice_edge_cases() {
+        if !is_supported() {
+            return;
+        }
+        let max = FieldElement::from_montgomery(FieldElement::MODULUS - U256::ONE);
+        let one = FieldElement::from_montgomery(U256::ONE);
+        let a = vec![
+            FieldElement::ZERO,
+            max.clone(),
+            one.clone(),
+            max.clone(),
+            max.clone(),
+            one.clone(),
+            FieldElement::ONE,
+            max.clone(),
+        ];
+        let b = vec![
+            max.clone(),
+            max.clone(),
+            max.clone(),
+            FieldElement::ONE,
+            one.clone(),
+            one,
+            FieldElement::ZERO,
+            FieldElement::NEGATIVE_ONE,
+        ];
+        let mut product = a.clone();
+        unsafe { mul_slice(&mut product, &b) };
+        for i in 0..LANES {
+            assert_eq!(product[i], &a[i] * &b[i]);
+        }
+    }
+}
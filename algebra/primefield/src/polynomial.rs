@@ -0,0 +1,388 @@
+// We want these types to be called `...Polynomial`
+#![allow(clippy::module_name_repetitions)]
+use crate::{
+    fft::{fft, ifft},
+    FieldElement,
+};
+use std::{
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    prelude::v1::*,
+};
+use zkp_u256::{commutative_binop, noncommutative_binop};
+
+/// Below this many coefficients in the product, `mul` uses the schoolbook
+/// method instead of FFTs.
+const FFT_THRESHOLD: usize = 64;
+
+/// Univariate polynomial stored as its coefficients.
+///
+/// Coefficients are in order of ascending degree and the leading coefficient
+/// is never zero. The zero polynomial has no coefficients.
+#[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Polynomial(Vec<FieldElement>);
+
+/// Univariate polynomial stored as its non-zero terms.
+///
+/// Terms are `(degree, coefficient)` pairs in order of ascending degree.
+#[derive(Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SparsePolynomial(Vec<(usize, FieldElement)>);
+
+impl Polynomial {
+    /// Construct from coefficients in order of ascending degree.
+    pub fn new(mut coefficients: Vec<FieldElement>) -> Self {
+        while coefficients.last() == Some(&FieldElement::ZERO) {
+            let _ = coefficients.pop();
+        }
+        Self(coefficients)
+    }
+
+    pub fn zero() -> Self {
+        Self(Vec::new())
+    }
+
+    pub fn constant(value: FieldElement) -> Self {
+        Self::new(vec![value])
+    }
+
+    /// The polynomial `X^size - 1` that is zero on the subgroup of order
+    /// `size`.
+    pub fn vanishing_subgroup(size: usize) -> Self {
+        Self::from(&SparsePolynomial::vanishing_subgroup(size))
+    }
+
+    /// The monic polynomial that is zero exactly on `points`.
+    pub fn vanishing(points: &[FieldElement]) -> Self {
+        // OPT: Use a product tree with FFT multiplications.
+        let mut coefficients = Vec::with_capacity(points.len() + 1);
+        coefficients.push(FieldElement::ONE);
+        for point in points {
+            // Multiply by (X - point)
+            coefficients.push(FieldElement::ZERO);
+            for i in (1..coefficients.len()).rev() {
+                coefficients[i] = &coefficients[i - 1] - point * &coefficients[i];
+            }
+            coefficients[0] = -&(point * &coefficients[0]);
+        }
+        Self::new(coefficients)
+    }
+
+    pub fn coefficients(&self) -> &[FieldElement] {
+        &self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.0.len().checked_sub(1)
+    }
+
+    pub fn evaluate(&self, x: &FieldElement) -> FieldElement {
+        let mut result = FieldElement::ZERO;
+        for coefficient in self.0.iter().rev() {
+            result *= x;
+            result += coefficient;
+        }
+        result
+    }
+
+    /// Schoolbook multiplication.
+    pub fn mul_naive(&self, other: &Self) -> Self {
+        if self.is_zero() || other.is_zero() {
+            return Self::zero();
+        }
+        let mut result = vec![FieldElement::ZERO; self.0.len() + other.0.len() - 1];
+        for (i, a) in self.0.iter().enumerate() {
+            for (j, b) in other.0.iter().enumerate() {
+                result[i + j] += a * b;
+            }
+        }
+        Self::new(result)
+    }
+
+    /// Multiplication using FFTs.
+    pub fn mul_fft(&self, other: &Self) -> Self {
+        if self.is_zero() || other.is_zero() {
+            return Self::zero();
+        }
+        let size = (self.0.len() + other.0.len() - 1).next_power_of_two();
+        let mut a = self.0.clone();
+        let mut b = other.0.clone();
+        a.resize(size, FieldElement::ZERO);
+        b.resize(size, FieldElement::ZERO);
+        let product = fft(&a)
+            .iter()
+            .zip(fft(&b).iter())
+            .map(|(a, b)| a * b)
+            .collect::<Vec<_>>();
+        Self::new(ifft(&product))
+    }
+
+    /// Polynomial long division.
+    ///
+    /// Returns the quotient and remainder, or `None` if `divisor` is zero.
+    pub fn div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        let leading_inv = divisor.0.last()?.inv()?;
+        if self.0.len() < divisor.0.len() {
+            return Some((Self::zero(), self.clone()));
+        }
+        let mut remainder = self.0.clone();
+        let mut quotient = vec![FieldElement::ZERO; self.0.len() - divisor.0.len() + 1];
+        for i in (0..quotient.len()).rev() {
+            let factor = &remainder[i + divisor.0.len() - 1] * &leading_inv;
+            for (j, coefficient) in divisor.0.iter().enumerate() {
+                remainder[i + j] -= &factor * coefficient;
+            }
+            quotient[i] = factor;
+        }
+        remainder.truncate(divisor.0.len() - 1);
+        Some((Self::new(quotient), Self::new(remainder)))
+    }
+}
+
+impl From<&SparsePolynomial> for Polynomial {
+    fn from(sparse: &SparsePolynomial) -> Self {
+        let mut coefficients = vec![FieldElement::ZERO; sparse.degree().map_or(0, |d| d + 1)];
+        for (degree, coefficient) in &sparse.0 {
+            coefficients[*degree] = coefficient.clone();
+        }
+        Self(coefficients)
+    }
+}
+
+impl AddAssign<&Polynomial> for Polynomial {
+    fn add_assign(&mut self, rhs: &Self) {
+        if self.0.len() < rhs.0.len() {
+            self.0.resize(rhs.0.len(), FieldElement::ZERO);
+        }
+        for (a, b) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *a += b;
+        }
+        *self = Self::new(std::mem::take(&mut self.0));
+    }
+}
+
+impl SubAssign<&Polynomial> for Polynomial {
+    fn sub_assign(&mut self, rhs: &Self) {
+        if self.0.len() < rhs.0.len() {
+            self.0.resize(rhs.0.len(), FieldElement::ZERO);
+        }
+        for (a, b) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *a -= b;
+        }
+        *self = Self::new(std::mem::take(&mut self.0));
+    }
+}
+
+impl MulAssign<&Polynomial> for Polynomial {
+    fn mul_assign(&mut self, rhs: &Self) {
+        *self = if self.0.len() + rhs.0.len() < FFT_THRESHOLD {
+            self.mul_naive(rhs)
+        } else {
+            self.mul_fft(rhs)
+        };
+    }
+}
+
+impl MulAssign<&FieldElement> for Polynomial {
+    fn mul_assign(&mut self, rhs: &FieldElement) {
+        if rhs == &FieldElement::ZERO {
+            self.0.clear();
+        }
+        for coefficient in &mut self.0 {
+            *coefficient *= rhs;
+        }
+    }
+}
+
+impl Neg for &Polynomial {
+    type Output = Polynomial;
+
+    fn neg(self) -> Polynomial {
+        Polynomial(self.0.iter().map(Neg::neg).collect())
+    }
+}
+
+commutative_binop!(Polynomial, Add, add, AddAssign, add_assign);
+commutative_binop!(Polynomial, Mul, mul, MulAssign, mul_assign);
+noncommutative_binop!(Polynomial, Sub, sub, SubAssign, sub_assign);
+
+impl SparsePolynomial {
+    /// Construct from `(degree, coefficient)` terms in any order.
+    ///
+    /// Terms of the same degree are added together.
+    pub fn new(mut terms: Vec<(usize, FieldElement)>) -> Self {
+        terms.sort_by_key(|(degree, _)| *degree);
+        let mut result: Vec<(usize, FieldElement)> = Vec::with_capacity(terms.len());
+        for (degree, coefficient) in terms {
+            match result.last_mut() {
+                Some((last, sum)) if *last == degree => *sum += coefficient,
+                _ => result.push((degree, coefficient)),
+            }
+        }
+        result.retain(|(_, coefficient)| coefficient != &FieldElement::ZERO);
+        Self(result)
+    }
+
+    /// The polynomial `X^size - 1` that is zero on the subgroup of order
+    /// `size`.
+    pub fn vanishing_subgroup(size: usize) -> Self {
+        Self::new(vec![(0, -&FieldElement::ONE), (size, FieldElement::ONE)])
+    }
+
+    pub fn terms(&self) -> &[(usize, FieldElement)] {
+        &self.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.0.last().map(|(degree, _)| *degree)
+    }
+
+    pub fn evaluate(&self, x: &FieldElement) -> FieldElement {
+        let mut result = FieldElement::ZERO;
+        let mut power = FieldElement::ONE;
+        let mut power_degree = 0;
+        for (degree, coefficient) in &self.0 {
+            power *= x.pow(degree - power_degree);
+            power_degree = *degree;
+            result += coefficient * &power;
+        }
+        result
+    }
+}
+
+impl AddAssign<&SparsePolynomial> for SparsePolynomial {
+    fn add_assign(&mut self, rhs: &Self) {
+        let mut terms = std::mem::take(&mut self.0);
+        terms.extend_from_slice(&rhs.0);
+        *self = Self::new(terms);
+    }
+}
+
+impl SubAssign<&SparsePolynomial> for SparsePolynomial {
+    fn sub_assign(&mut self, rhs: &Self) {
+        let mut terms = std::mem::take(&mut self.0);
+        terms.extend(
+            rhs.0
+                .iter()
+                .map(|(degree, coefficient)| (*degree, -coefficient)),
+        );
+        *self = Self::new(terms);
+    }
+}
+
+impl MulAssign<&SparsePolynomial> for SparsePolynomial {
+    fn mul_assign(&mut self, rhs: &Self) {
+        let mut terms = Vec::with_capacity(self.0.len() * rhs.0.len());
+        for (i, a) in &self.0 {
+            for (j, b) in &rhs.0 {
+                terms.push((i + j, a * b));
+            }
+        }
+        *self = Self::new(terms);
+    }
+}
+
+commutative_binop!(SparsePolynomial, Add, add, AddAssign, add_assign);
+commutative_binop!(SparsePolynomial, Mul, mul, MulAssign, mul_assign);
+noncommutative_binop!(SparsePolynomial, Sub, sub, SubAssign, sub_assign);
+
+#[cfg(any(test, feature = "quickcheck"))]
+use quickcheck::{Arbitrary, Gen};
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for Polynomial {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Self::new(Vec::<FieldElement>::arbitrary(g))
+    }
+}
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for SparsePolynomial {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let terms = Vec::<(u8, FieldElement)>::arbitrary(g);
+        Self::new(
+            terms
+                .into_iter()
+                .map(|(degree, coefficient)| (usize::from(degree), coefficient))
+                .collect(),
+        )
+    }
+}
+
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_normalized() {
+        let p = Polynomial::new(vec![FieldElement::ONE, FieldElement::ZERO]);
+        assert_eq!(p.degree(), Some(0));
+        assert_eq!(Polynomial::new(vec![FieldElement::ZERO]).degree(), None);
+        assert_eq!(&p - &p, Polynomial::zero());
+    }
+
+    #[test]
+    fn test_vanishing_subgroup() {
+        let root = FieldElement::root(16).unwrap();
+        let points = (0..16_usize).map(|i| root.pow(i)).collect::<Vec<_>>();
+        let dense = Polynomial::vanishing_subgroup(16);
+        assert_eq!(Polynomial::vanishing(&points), dense);
+        for point in &points {
+            assert_eq!(dense.evaluate(point), FieldElement::ZERO);
+        }
+    }
+
+    #[quickcheck]
+    fn vanishing_is_zero_on_points(points: Vec<FieldElement>, x: FieldElement) -> bool {
+        let vanishing = Polynomial::vanishing(&points);
+        let expected = points
+            .iter()
+            .fold(FieldElement::ONE, |product, point| product * (&x - point));
+        vanishing.degree() == Some(points.len()) && vanishing.evaluate(&x) == expected
+    }
+
+    #[quickcheck]
+    fn add_sub_evaluate(a: Polynomial, b: Polynomial, x: FieldElement) -> bool {
+        (&a + &b).evaluate(&x) == a.evaluate(&x) + b.evaluate(&x)
+            && (&a - &b).evaluate(&x) == a.evaluate(&x) - b.evaluate(&x)
+            && (&a + &b - &b) == a
+    }
+
+    #[quickcheck]
+    fn mul_fft_matches_naive(a: Polynomial, b: Polynomial, x: FieldElement) -> bool {
+        let product = a.mul_naive(&b);
+        product == a.mul_fft(&b) && product.evaluate(&x) == a.evaluate(&x) * b.evaluate(&x)
+    }
+
+    #[quickcheck]
+    fn div_rem_reconstructs(a: Polynomial, b: Polynomial) -> bool {
+        match a.div_rem(&b) {
+            None => b.is_zero(),
+            Some((quotient, remainder)) => {
+                remainder.degree() < b.degree() && quotient * &b + remainder == a
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn sparse_matches_dense(a: SparsePolynomial, b: SparsePolynomial, x: FieldElement) -> bool {
+        let dense = |p: &SparsePolynomial| Polynomial::from(p);
+        a.evaluate(&x) == dense(&a).evaluate(&x)
+            && dense(&(&a + &b)) == dense(&a) + dense(&b)
+            && dense(&(&a - &b)) == dense(&a) - dense(&b)
+            && dense(&(&a * &b)) == dense(&a) * dense(&b)
+    }
+}
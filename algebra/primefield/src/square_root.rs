@@ -3,18 +3,13 @@ use zkp_macros_decl::u256h;
 use zkp_u256::U256;
 
 pub(crate) fn square_root(a: &FieldElement) -> Option<FieldElement> {
-    if is_quadratic_residue(a) {
+    if a.is_quadratic_residue() {
         Some(tonelli_shanks(a))
     } else {
         None
     }
 }
 
-// Returns the result of (a/p) != -1, where (a/p) is the Legendre symbol.
-fn is_quadratic_residue(a: &FieldElement) -> bool {
-    a.pow(FieldElement::MODULUS >> 1) != FieldElement::NEGATIVE_ONE
-}
-
 // These two constants are chosen so that 1 + SIGNIFICAND << BINARY_EXPONENT ==
 // MODULUS.
 const BINARY_EXPONENT: usize = 3 * 4 * 16;
@@ -83,13 +78,24 @@ mod tests {
 
     #[quickcheck]
     fn squares_are_quadratic_residues(x: FieldElement) -> bool {
-        is_quadratic_residue(&x.square())
+        x.square().is_quadratic_residue()
+    }
+
+    #[quickcheck]
+    fn legendre_matches_euler(x: FieldElement) -> bool {
+        let power = x.pow(FieldElement::MODULUS >> 1);
+        match x.legendre() {
+            0 => x.is_zero(),
+            1 => power.is_one(),
+            -1 => power == FieldElement::NEGATIVE_ONE,
+            _ => false,
+        }
     }
 
     #[quickcheck]
     fn inverse(x: FieldElement) -> bool {
         match square_root(&x) {
-            None => !is_quadratic_residue(&x),
+            None => !x.is_quadratic_residue(),
             Some(result) => result.square() == x,
         }
     }
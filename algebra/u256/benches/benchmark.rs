@@ -1,7 +1,7 @@
 #![warn(clippy::all)]
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use zkp_macros_decl::u256h;
-use zkp_u256::U256;
+use zkp_u256::{MulModContext, U256};
 
 fn u256_add(crit: &mut Criterion) {
     let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
@@ -34,6 +34,22 @@ fn u256_invmod(crit: &mut Criterion) {
     });
 }
 
+fn u256_gcd(crit: &mut Criterion) {
+    let a = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+    let b = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+    crit.bench_function("U256 gcd", move |bench| {
+        bench.iter(|| black_box(&a).gcd(black_box(&b)))
+    });
+}
+
+fn u256_extended_gcd(crit: &mut Criterion) {
+    let a = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+    let b = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+    crit.bench_function("U256 extended gcd", move |bench| {
+        bench.iter(|| black_box(&a).extended_gcd(black_box(&b)))
+    });
+}
+
 fn u256_divrem(crit: &mut Criterion) {
     let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
     let b = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
@@ -51,13 +67,70 @@ fn u256_mulmod(crit: &mut Criterion) {
     });
 }
 
+fn u256_mulmod_context(crit: &mut Criterion) {
+    let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
+    let b = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+    let m = u256h!("d2f9a8d8b8f33d1c91e231a9efb0d295e93c1af395f9a7d3f9c7d0f0e2a5e6b3");
+    let context = MulModContext::new(&m).unwrap();
+    crit.bench_function("U256 mulmod context odd", move |bench| {
+        bench.iter(|| black_box(context.mulmod(black_box(&a), black_box(&b))))
+    });
+    let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
+    let b = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+    let m = u256h!("d2f9a8d8b8f33d1c91e231a9efb0d295e93c1af395f9a7d3f9c7d0f0e2a5e6b4");
+    let context = MulModContext::new(&m).unwrap();
+    crit.bench_function("U256 mulmod context even", move |bench| {
+        bench.iter(|| black_box(context.mulmod(black_box(&a), black_box(&b))))
+    });
+}
+
+fn u256_powmod_context(crit: &mut Criterion) {
+    let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
+    let e = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+    let m = u256h!("d2f9a8d8b8f33d1c91e231a9efb0d295e93c1af395f9a7d3f9c7d0f0e2a5e6b3");
+    let context = MulModContext::new(&m).unwrap();
+    crit.bench_function("U256 powmod context", move |bench| {
+        bench.iter(|| black_box(context.powmod(black_box(&a), black_box(&e))))
+    });
+}
+
+fn u256_powmod(crit: &mut Criterion) {
+    let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
+    let e = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+    let m = u256h!("d2f9a8d8b8f33d1c91e231a9efb0d295e93c1af395f9a7d3f9c7d0f0e2a5e6b3");
+    crit.bench_function("U256 powmod", move |bench| {
+        bench.iter(|| black_box(black_box(&a).powmod(black_box(&e), black_box(&m))))
+    });
+}
+
+fn u256_from_decimal_str(crit: &mut Criterion) {
+    let s = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+    crit.bench_function("U256 from decimal", move |bench| {
+        bench.iter(|| black_box(U256::from_decimal_str(black_box(s))))
+    });
+}
+
+fn u256_to_decimal_str(crit: &mut Criterion) {
+    let n = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+    crit.bench_function("U256 to decimal", move |bench| {
+        bench.iter(|| black_box(black_box(&n).to_decimal_str()))
+    });
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     u256_add(c);
     u256_mul(c);
     u256_invmod256(c);
     u256_invmod(c);
+    u256_gcd(c);
+    u256_extended_gcd(c);
     u256_divrem(c);
     u256_mulmod(c);
+    u256_mulmod_context(c);
+    u256_powmod_context(c);
+    u256_powmod(c);
+    u256_from_decimal_str(c);
+    u256_to_decimal_str(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -52,8 +52,14 @@ fn div_3by2(n: &[u64; 3], d: &[u64; 2]) -> u64 {
     // The highest bit of d needs to be set
     debug_assert!(d[1] >> 63 == 1);
 
-    // The quotient needs to fit u64. For this we need [n2 n1] < [d1 d0]
-    debug_assert!(val_2(n[1], n[2]) < val_2(d[0], d[1]));
+    // The quotient needs to fit u64. For this we need [n2 n1] < [d1 d0].
+    // Long division can arrive at [n2 n1] = [d1 d0] when the lower limbs of
+    // the divisor make the true quotient digit fit. The estimate is then
+    // clamped to the largest digit, which is at most one too large.
+    debug_assert!(val_2(n[1], n[2]) <= val_2(d[0], d[1]));
+    if val_2(n[1], n[2]) == val_2(d[0], d[1]) {
+        return 0xffff_ffff_ffff_ffff_u64;
+    }
 
     if n[2] == d[1] {
         // From [n2 n1] < [d1 d0] and n2 = d1 it follows that n[1] < d[0].
@@ -184,6 +190,8 @@ mod tests {
         // Test cases where n[2] == d[1]
         assert_eq!(div_3by2(&[FULL, FULL - 1, HALF], &[FULL, HALF]), FULL);
         assert_eq!(div_3by2(&[0, 0, HALF], &[FULL, HALF]), FULL - 1);
+        // Test case where [n2 n1] == [d1 d0]
+        assert_eq!(div_3by2(&[0, FULL, HALF], &[FULL, HALF]), FULL);
     }
 
     #[test]
@@ -199,6 +207,15 @@ mod tests {
         assert_eq!(quotient, expected_quotient);
     }
 
+    #[test]
+    fn test_divrem_4by3_clamped_estimate() {
+        let numerator = U256::from_limbs(0x12, 0x17, 0x3d, 0x62);
+        let divisor = U256::from_limbs(0x25, 0x3d, 0x62, 0);
+        let (quotient, remainder) = numerator.divrem(&divisor).unwrap();
+        assert_eq!(quotient, U256::from(u64::max_value()));
+        assert_eq!(remainder, U256::from_limbs(0x37, 0x2f, 0x62, 0));
+    }
+
     #[allow(clippy::unreadable_literal)]
     #[test]
     fn test_divrem_8by4() {
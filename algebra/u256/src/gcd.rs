@@ -473,6 +473,31 @@ pub(crate) fn inv_mod(modulus: &U256, num: &U256) -> Option<U256> {
     }
 }
 
+impl U256 {
+    /// Greatest common divisor of `self` and `other`.
+    ///
+    /// Uses Lehmer's algorithm, see [`gcd_extended`] for details. The GCD of
+    /// zero and zero is zero.
+    pub fn gcd(&self, other: &Self) -> Self {
+        gcd(self.clone(), other.clone())
+    }
+
+    /// Extended greatest common divisor of `self` and `other`.
+    ///
+    /// Returns `(gcd, u, v, even)` with cofactors `u` and `v` such that
+    ///
+    /// ```text
+    ///   gcd = u * self - v * other   if even
+    ///   gcd = v * other - u * self   otherwise
+    /// ```
+    ///
+    /// The signs are returned separately because the cofactors do not fit
+    /// a signed 256-bit integer in general.
+    pub fn extended_gcd(&self, other: &Self) -> (Self, Self, Self, bool) {
+        gcd_extended(self.clone(), other.clone())
+    }
+}
+
 // We don't mind large number literals here.
 #[allow(clippy::unreadable_literal)]
 #[cfg(test)]
@@ -718,6 +743,22 @@ mod tests {
             && gcd == if even { u * a - v * b } else { v * b - u * a }
     }
 
+    #[quickcheck]
+    fn test_gcd_method(a: U256, b: U256) -> bool {
+        let (gcd, u, v, even) = a.extended_gcd(&b);
+        a.gcd(&b) == gcd
+            && b.gcd(&a) == gcd
+            && gcd == if even { u * &a - v * &b } else { v * &b - u * &a }
+    }
+
+    #[test]
+    fn test_gcd_method_small() {
+        assert_eq!(U256::ZERO.gcd(&U256::ZERO), U256::ZERO);
+        assert_eq!(U256::from(12_u64).gcd(&U256::ZERO), U256::from(12_u64));
+        assert_eq!(U256::from(12_u64).gcd(&U256::from(18_u64)), U256::from(6_u64));
+        assert_eq!(U256::MAX.gcd(&(U256::ONE << 200)), U256::ONE);
+    }
+
     #[quickcheck]
     fn test_inv_lehmer(mut a: U256) -> bool {
         const MODULUS: U256 =
@@ -0,0 +1,406 @@
+use crate::{commutative_binop, noncommutative_binop, U256};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    ops::{
+        Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+    },
+    prelude::v1::*,
+};
+
+#[cfg(feature = "std")]
+use std::fmt;
+
+/// Signed 256-bit integer in two's complement over `U256`.
+///
+/// Addition, subtraction, multiplication and negation wrap silently like
+/// their `U256` counterparts. Division and remainder round towards zero like
+/// the primitive signed integers.
+#[derive(PartialEq, Eq, Clone, Default)]
+pub struct I256(U256);
+
+impl I256 {
+    pub const MAX: Self = Self(U256::from_limbs(
+        u64::max_value(),
+        u64::max_value(),
+        u64::max_value(),
+        u64::max_value() >> 1,
+    ));
+    pub const MIN: Self = Self(U256::from_limbs(0, 0, 0, 1_u64 << 63));
+    pub const MINUS_ONE: Self = Self(U256::MAX);
+    pub const ONE: Self = Self(U256::ONE);
+    pub const ZERO: Self = Self(U256::ZERO);
+
+    /// Interpret the bits of a `U256` as a two's complement number.
+    pub const fn from_bits(bits: U256) -> Self {
+        Self(bits)
+    }
+
+    /// The two's complement bits of the number.
+    pub fn as_bits(&self) -> &U256 {
+        &self.0
+    }
+
+    /// Construct from a sign and a magnitude.
+    ///
+    /// The result wraps, so a negative magnitude of `2^255` gives `I256::MIN`.
+    pub fn from_sign_magnitude(negative: bool, magnitude: U256) -> Self {
+        if negative {
+            Self(U256::ZERO - magnitude)
+        } else {
+            Self(magnitude)
+        }
+    }
+
+    /// Split into a sign and a magnitude, the inverse of
+    /// `from_sign_magnitude`.
+    pub fn to_sign_magnitude(&self) -> (bool, U256) {
+        (self.is_negative(), self.unsigned_abs())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub const fn is_negative(&self) -> bool {
+        self.0.c3 >> 63 == 1
+    }
+
+    pub const fn is_positive(&self) -> bool {
+        self.0.c3 >> 63 == 0 && (self.0.c0 | self.0.c1 | self.0.c2 | self.0.c3) != 0
+    }
+
+    /// Returns `-1`, `0` or `1` depending on the sign.
+    pub fn signum(&self) -> i8 {
+        if self.is_negative() {
+            -1
+        } else if self.is_zero() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Absolute value as an unsigned number. Unlike `abs` this does not
+    /// overflow on `I256::MIN`.
+    pub fn unsigned_abs(&self) -> U256 {
+        if self.is_negative() {
+            U256::ZERO - &self.0
+        } else {
+            self.0.clone()
+        }
+    }
+
+    /// Absolute value, wrapping `I256::MIN` to itself.
+    pub fn abs(&self) -> Self {
+        Self(self.unsigned_abs())
+    }
+
+    pub fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        let result = self + rhs;
+        // Overflow iff both operands have the same sign and the result not.
+        if self.is_negative() == rhs.is_negative() && result.is_negative() != self.is_negative()
+        {
+            None
+        } else {
+            Some(result)
+        }
+    }
+
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        let result = self - rhs;
+        // Overflow iff the operands have different signs and the result has
+        // the sign of `rhs`.
+        if self.is_negative() != rhs.is_negative() && result.is_negative() != self.is_negative()
+        {
+            None
+        } else {
+            Some(result)
+        }
+    }
+
+    pub fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        let negative = self.is_negative() != rhs.is_negative();
+        let magnitude = self.unsigned_abs().checked_mul(&rhs.unsigned_abs())?;
+        let limit = if negative {
+            Self::MIN.0
+        } else {
+            Self::MAX.0
+        };
+        if magnitude > limit {
+            None
+        } else {
+            Some(Self::from_sign_magnitude(negative, magnitude))
+        }
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        if *self == Self::MIN {
+            None
+        } else {
+            Some(-self)
+        }
+    }
+
+    /// Quotient and remainder rounding towards zero, `None` if `rhs` is zero.
+    ///
+    /// The remainder has the sign of `self`. `I256::MIN / -1` wraps.
+    pub fn divrem(&self, rhs: &Self) -> Option<(Self, Self)> {
+        let (quotient, remainder) = self.unsigned_abs().divrem(&rhs.unsigned_abs())?;
+        Some((
+            Self::from_sign_magnitude(self.is_negative() != rhs.is_negative(), quotient),
+            Self::from_sign_magnitude(self.is_negative(), remainder),
+        ))
+    }
+
+    /// Remainder in the range `0..modulus`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `modulus` is zero.
+    pub fn rem_euclid(&self, modulus: &U256) -> U256 {
+        let remainder = self.unsigned_abs() % modulus;
+        if self.is_negative() && !remainder.is_zero() {
+            modulus - remainder
+        } else {
+            remainder
+        }
+    }
+
+    pub fn to_decimal_str(&self) -> String {
+        if self.is_negative() {
+            let mut result = "-".to_string();
+            result.push_str(&self.unsigned_abs().to_decimal_str());
+            result
+        } else {
+            self.0.to_decimal_str()
+        }
+    }
+}
+
+macro_rules! impl_from_int {
+    ($t:ty) => {
+        impl From<$t> for I256 {
+            fn from(n: $t) -> Self {
+                // U256 sign-extends signed primitives and zero-extends
+                // unsigned ones, which is what we want here.
+                Self(U256::from(n))
+            }
+        }
+    };
+}
+
+impl_from_int!(u8);
+impl_from_int!(u16);
+impl_from_int!(u32);
+impl_from_int!(u64);
+impl_from_int!(u128);
+impl_from_int!(usize);
+impl_from_int!(i8);
+impl_from_int!(i16);
+impl_from_int!(i32);
+impl_from_int!(i64);
+impl_from_int!(i128);
+impl_from_int!(isize);
+
+/// Fails if the number exceeds `I256::MAX`.
+impl TryFrom<U256> for I256 {
+    type Error = U256;
+
+    fn try_from(n: U256) -> Result<Self, U256> {
+        if n > I256::MAX.0 {
+            Err(n)
+        } else {
+            Ok(Self(n))
+        }
+    }
+}
+
+/// Fails if the number is negative.
+impl TryFrom<I256> for U256 {
+    type Error = I256;
+
+    fn try_from(n: I256) -> Result<Self, I256> {
+        if n.is_negative() {
+            Err(n)
+        } else {
+            Ok(n.0)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad_integral(!self.is_negative(), "", &self.unsigned_abs().to_decimal_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for I256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "I256({})", self)
+    }
+}
+
+impl PartialOrd for I256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for I256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            // Two's complement preserves order within a sign.
+            _ => self.0.cmp(&other.0),
+        }
+    }
+}
+
+impl Neg for I256 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(U256::ZERO - self.0)
+    }
+}
+
+impl Neg for &I256 {
+    type Output = I256;
+
+    fn neg(self) -> I256 {
+        I256(U256::ZERO - &self.0)
+    }
+}
+
+impl AddAssign<&I256> for I256 {
+    fn add_assign(&mut self, rhs: &Self) {
+        self.0 += &rhs.0;
+    }
+}
+
+impl SubAssign<&I256> for I256 {
+    fn sub_assign(&mut self, rhs: &Self) {
+        self.0 -= &rhs.0;
+    }
+}
+
+impl MulAssign<&I256> for I256 {
+    fn mul_assign(&mut self, rhs: &Self) {
+        // The low 256 bits of a product do not depend on the signs.
+        self.0 *= &rhs.0;
+    }
+}
+
+impl DivAssign<&I256> for I256 {
+    fn div_assign(&mut self, rhs: &Self) {
+        let (q, _r) = self.divrem(rhs).unwrap();
+        *self = q;
+    }
+}
+
+impl RemAssign<&I256> for I256 {
+    fn rem_assign(&mut self, rhs: &Self) {
+        let (_q, r) = self.divrem(rhs).unwrap();
+        *self = r;
+    }
+}
+
+commutative_binop!(I256, Add, add, AddAssign, add_assign);
+commutative_binop!(I256, Mul, mul, MulAssign, mul_assign);
+noncommutative_binop!(I256, Sub, sub, SubAssign, sub_assign);
+noncommutative_binop!(I256, Div, div, DivAssign, div_assign);
+noncommutative_binop!(I256, Rem, rem, RemAssign, rem_assign);
+
+#[cfg(any(test, feature = "quickcheck"))]
+use quickcheck::{Arbitrary, Gen};
+
+#[cfg(any(test, feature = "quickcheck"))]
+impl Arbitrary for I256 {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Self(U256::arbitrary(g))
+    }
+}
+
+// Quickcheck requires pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(I256::MAX + I256::ONE, I256::MIN);
+        assert_eq!(I256::MINUS_ONE, I256::from(-1_i64));
+        assert_eq!(-I256::ONE, I256::MINUS_ONE);
+        assert_eq!(-&I256::MIN, I256::MIN);
+        assert!(I256::MIN < I256::MINUS_ONE);
+        assert!(I256::MINUS_ONE < I256::ZERO);
+        assert!(I256::ZERO < I256::MAX);
+        assert_eq!(I256::MIN.unsigned_abs(), U256::ONE << 255);
+    }
+
+    #[test]
+    fn test_division_rounding() {
+        let seven = I256::from(7_i64);
+        let two = I256::from(2_i64);
+        assert_eq!(&seven / &two, I256::from(3_i64));
+        assert_eq!(-&seven / &two, I256::from(-3_i64));
+        assert_eq!(&seven % -&two, I256::from(1_i64));
+        assert_eq!(-&seven % &two, I256::from(-1_i64));
+        assert_eq!((-&seven).rem_euclid(&U256::from(2_u64)), U256::ONE);
+        assert_eq!(I256::ONE.divrem(&I256::ZERO), None);
+    }
+
+    #[test]
+    fn test_checked() {
+        assert_eq!(I256::MAX.checked_add(&I256::ONE), None);
+        assert_eq!(I256::MIN.checked_sub(&I256::ONE), None);
+        assert_eq!(I256::MIN.checked_neg(), None);
+        assert_eq!(I256::MIN.checked_mul(&I256::MINUS_ONE), None);
+        assert_eq!(
+            I256::from_sign_magnitude(true, U256::ONE << 254).checked_mul(&I256::from(2_i64)),
+            Some(I256::MIN)
+        );
+        assert_eq!(I256::MINUS_ONE.checked_add(&I256::MIN), None);
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(I256::try_from(U256::MAX), Err(U256::MAX));
+        assert_eq!(I256::try_from(U256::ONE), Ok(I256::ONE));
+        assert_eq!(U256::try_from(I256::MINUS_ONE), Err(I256::MINUS_ONE));
+        assert_eq!(I256::from(u128::max_value()).signum(), 1);
+        assert_eq!(I256::from(-5_i64).to_decimal_str(), "-5");
+        assert_eq!(format!("{:+}", I256::from(5_i64)), "+5");
+    }
+
+    #[quickcheck]
+    fn sign_magnitude_round_trip(a: I256) -> bool {
+        let (negative, magnitude) = a.to_sign_magnitude();
+        I256::from_sign_magnitude(negative, magnitude) == a
+    }
+
+    #[quickcheck]
+    fn matches_i64(a: i64, b: i64) -> bool {
+        let (ia, ib) = (I256::from(a), I256::from(b));
+        let (a, b) = (i128::from(a), i128::from(b));
+        ia.cmp(&ib) == a.cmp(&b)
+            && &ia + &ib == I256::from(a + b)
+            && &ia - &ib == I256::from(a - b)
+            && &ia * &ib == I256::from(a * b)
+            && (b == 0 || (&ia / &ib == I256::from(a / b) && &ia % &ib == I256::from(a % b)))
+    }
+
+    #[quickcheck]
+    fn divrem_identity(a: I256, b: I256) -> bool {
+        match a.divrem(&b) {
+            None => b.is_zero(),
+            Some((q, r)) => q * &b + &r == a && r.unsigned_abs() < b.unsigned_abs(),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::U256;
+use core::mem::swap;
+
+impl U256 {
+    /// Jacobi symbol $\left(\frac{\mathtt{self}}{n}\right)$.
+    ///
+    /// Returns `1`, `-1` or `0`. For a prime `n` this is the Legendre symbol,
+    /// which is `1` for non-zero quadratic residues, `-1` for non-residues
+    /// and `0` for multiples of `n`.
+    ///
+    /// Uses the binary algorithm based on quadratic reciprocity (Cohen,
+    /// algorithm 1.4.10).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is even.
+    pub fn jacobi(&self, n: &Self) -> i8 {
+        assert!(n.is_odd(), "Jacobi symbol requires an odd modulus");
+        let mut a = self % n;
+        let mut n = n.clone();
+        let mut result = 1;
+        while !a.is_zero() {
+            // Remove factors of two using (2/n) = -1 iff n = ±3 mod 8.
+            let twos = a.trailing_zeros();
+            a >>= twos;
+            if twos % 2 == 1 && (&n & 7 == 3 || &n & 7 == 5) {
+                result = -result;
+            }
+            // Quadratic reciprocity for odd a and n.
+            if &a & 3 == 3 && &n & 3 == 3 {
+                result = -result;
+            }
+            swap(&mut a, &mut n);
+            a %= &n;
+        }
+        if n == Self::ONE {
+            result
+        } else {
+            0
+        }
+    }
+}
+
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use zkp_macros_decl::u256h;
+
+    /// Euler's criterion, for prime moduli only.
+    fn euler(a: &U256, p: &U256) -> i8 {
+        let power = a.powmod(&(p.clone() >> 1), p);
+        if power.is_zero() {
+            0
+        } else if power == U256::ONE {
+            1
+        } else {
+            -1
+        }
+    }
+
+    #[test]
+    fn test_jacobi() {
+        // Residues modulo 7 are 1, 2 and 4.
+        let symbols: Vec<i8> = (0..8_u64)
+            .map(|a| U256::from(a).jacobi(&U256::from(7_u64)))
+            .collect();
+        assert_eq!(symbols, vec![0, 1, 1, -1, 1, -1, -1, 0]);
+        // Composite moduli: (2/15) = 1 while 2 is not a square mod 15.
+        assert_eq!(U256::from(2_u64).jacobi(&U256::from(15_u64)), 1);
+        assert_eq!(U256::from(7_u64).jacobi(&U256::from(15_u64)), -1);
+        assert_eq!(U256::from(6_u64).jacobi(&U256::from(15_u64)), 0);
+        assert_eq!(U256::from(5_u64).jacobi(&U256::ONE), 1);
+        assert_eq!(U256::MAX.jacobi(&U256::MAX), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_jacobi_even() {
+        let _ = U256::ONE.jacobi(&U256::from(4_u64));
+    }
+
+    #[quickcheck]
+    fn jacobi_matches_euler(a: U256) -> bool {
+        let p = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+        let q = u256h!("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+        a.jacobi(&p) == euler(&a, &p) && a.jacobi(&q) == euler(&a, &q)
+    }
+
+    #[quickcheck]
+    fn jacobi_multiplicative(a: U256, b: U256, n: U256) -> bool {
+        let n = n | U256::ONE;
+        a.jacobi(&n) * b.jacobi(&n) == a.mulmod(&b, &n).jacobi(&n)
+    }
+}
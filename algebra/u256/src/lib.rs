@@ -40,12 +40,22 @@
 mod binops;
 mod division;
 mod gcd;
+mod i256;
+mod jacobi;
+mod mulmod_context;
+mod prime;
+#[cfg(feature = "rand")]
+mod random;
 mod u256;
 
 // TODO: This seems out of scope for U256 to export.
 pub mod utils;
 
 pub use crate::u256::U256;
+pub use i256::I256;
+pub use mulmod_context::MulModContext;
+#[cfg(feature = "rand")]
+pub use random::UniformU256;
 
 // TODO: Make member functions of U256?
 pub use gcd::{gcd, gcd_extended};
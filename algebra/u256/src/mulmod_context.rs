@@ -0,0 +1,430 @@
+use crate::{
+    division::{divrem_nby1, divrem_nbym},
+    utils::{adc, mac, sbb},
+    U256,
+};
+
+/// Precomputed constants for modular arithmetic with a fixed modulus.
+///
+/// Odd moduli use Montgomery multiplication and even moduli use Barrett
+/// reduction. Both replace the long division in [`U256::mulmod`] by
+/// multiplications, which makes repeated operations with the same modulus
+/// substantially faster.
+///
+/// Inputs can be any `U256`, results are always reduced.
+///
+/// [`U256::mulmod`]: struct.U256.html#method.mulmod
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct MulModContext {
+    modulus:   U256,
+    reduction: Reduction,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+enum Reduction {
+    /// Montgomery reduction with $R = 2^{256}$.
+    Montgomery {
+        /// $-m^{-1} \mod 2^{64}$
+        m64: u64,
+        /// $R^2 \mod m$
+        r2:  U256,
+    },
+    /// Barrett reduction in base $b = 2^{64}$ for a modulus of $k$ limbs.
+    Barrett {
+        limbs: usize,
+        /// $\floor{b^{2k} / m}$, which has at most $k + 2$ limbs.
+        mu:    [u64; 6],
+    },
+}
+
+impl MulModContext {
+    /// Precompute the reduction constants, returns `None` if the modulus is
+    /// zero.
+    pub fn new(modulus: &U256) -> Option<Self> {
+        if modulus.is_zero() {
+            return None;
+        }
+        let reduction = if modulus.is_odd() {
+            let m64 = modulus.invmod256().unwrap().c0.wrapping_neg();
+            let r1 = (U256::ZERO - modulus) % modulus;
+            let r2 = r1.mulmod(&r1, modulus);
+            Reduction::Montgomery { m64, r2 }
+        } else {
+            let limbs = (modulus.bits() + 63) / 64;
+            let mut numerator = [0_u64; 10];
+            numerator[2 * limbs] = 1;
+            let mut mu = [0_u64; 6];
+            if limbs == 1 {
+                let _remainder = divrem_nby1(&mut numerator[..3], modulus.c0);
+                mu[..3].copy_from_slice(&numerator[..3]);
+            } else {
+                let mut divisor = [modulus.c0, modulus.c1, modulus.c2, modulus.c3];
+                divrem_nbym(&mut numerator[..2 * limbs + 2], &mut divisor[..limbs]);
+                mu[..limbs + 2].copy_from_slice(&numerator[limbs..2 * limbs + 2]);
+            }
+            Reduction::Barrett { limbs, mu }
+        };
+        Some(Self {
+            modulus: modulus.clone(),
+            reduction,
+        })
+    }
+
+    pub fn modulus(&self) -> &U256 {
+        &self.modulus
+    }
+
+    pub fn reduce(&self, a: &U256) -> U256 {
+        if *a < self.modulus {
+            a.clone()
+        } else {
+            a % &self.modulus
+        }
+    }
+
+    pub fn addmod(&self, a: &U256, b: &U256) -> U256 {
+        let (sum, carry) = self.reduce(a).overflowing_add(&self.reduce(b));
+        if carry || sum >= self.modulus {
+            sum.wrapping_sub(&self.modulus)
+        } else {
+            sum
+        }
+    }
+
+    pub fn mulmod(&self, a: &U256, b: &U256) -> U256 {
+        let product = self.reduce(a).mul_full(&self.reduce(b));
+        match &self.reduction {
+            Reduction::Montgomery { m64, r2 } => {
+                // Compute a b R^-1 and correct for the factor R^-1.
+                let reduced = self.redc(*m64, &product);
+                self.redc(*m64, &reduced.mul_full(r2))
+            }
+            Reduction::Barrett { limbs, mu } => self.barrett(*limbs, mu, &product),
+        }
+    }
+
+    /// Modular exponentiation.
+    ///
+    /// Exponents of more than 64 bits are processed in windows of four bits
+    /// using a table of small powers of the base, smaller exponents use plain
+    /// square and multiply.
+    ///
+    /// Zero to the power zero is one.
+    pub fn powmod(&self, base: &U256, exponent: &U256) -> U256 {
+        let base = self.reduce(base);
+        match &self.reduction {
+            Reduction::Montgomery { m64, r2 } => {
+                // Work on Montgomery forms, a R mod m.
+                let base = self.redc(*m64, &base.mul_full(r2));
+                let one = self.redc(*m64, &(r2.clone(), U256::ZERO));
+                let result = pow_windowed(
+                    one,
+                    &base,
+                    exponent,
+                    |a| self.redc(*m64, &a.sqr_full()),
+                    |a, b| self.redc(*m64, &a.mul_full(b)),
+                );
+                self.redc(*m64, &(result, U256::ZERO))
+            }
+            Reduction::Barrett { limbs, mu } => {
+                pow_windowed(
+                    self.reduce(&U256::ONE),
+                    &base,
+                    exponent,
+                    |a| self.barrett(*limbs, mu, &a.sqr_full()),
+                    |a, b| self.barrett(*limbs, mu, &a.mul_full(b)),
+                )
+            }
+        }
+    }
+
+    /// Montgomery reduction, computes $x R^{-1} \mod m$ for $x < m R$.
+    // We shadow carry for readability
+    #[allow(clippy::shadow_unrelated)]
+    fn redc(&self, m64: u64, (lo, hi): &(U256, U256)) -> U256 {
+        let m = &self.modulus;
+        let mut t = [lo.c0, lo.c1, lo.c2, lo.c3, hi.c0, hi.c1, hi.c2, hi.c3];
+        // Carry into limb i + 4 from the previous round
+        let mut overflow = 0;
+        for i in 0..4 {
+            // Add k * m to clear limb i
+            let k = t[i].wrapping_mul(m64);
+            let (_, carry) = mac(t[i], k, m.c0, 0);
+            let (t1, carry) = mac(t[i + 1], k, m.c1, carry);
+            let (t2, carry) = mac(t[i + 2], k, m.c2, carry);
+            let (t3, carry) = mac(t[i + 3], k, m.c3, carry);
+            let (t4, carry) = adc(t[i + 4], carry, overflow);
+            t[i + 1] = t1;
+            t[i + 2] = t2;
+            t[i + 3] = t3;
+            t[i + 4] = t4;
+            overflow = carry;
+        }
+        // The result is less than 2m
+        let result = U256::from_limbs(t[4], t[5], t[6], t[7]);
+        if overflow != 0 || result >= self.modulus {
+            result.wrapping_sub(&self.modulus)
+        } else {
+            result
+        }
+    }
+
+    /// Barrett reduction, computes $x \mod m$ for $x < b^{2k}$.
+    ///
+    /// See Handbook of Applied Cryptography, algorithm 14.42.
+    fn barrett(&self, limbs: usize, mu: &[u64; 6], (lo, hi): &(U256, U256)) -> U256 {
+        let k = limbs;
+        let m = [
+            self.modulus.c0,
+            self.modulus.c1,
+            self.modulus.c2,
+            self.modulus.c3,
+            0,
+        ];
+        let x = [lo.c0, lo.c1, lo.c2, lo.c3, hi.c0, hi.c1, hi.c2, hi.c3];
+
+        // Estimate the quotient, q3 = floor(floor(x / b^(k-1)) mu / b^(k+1)).
+        // Products below limb k - 1 are left out, see note 14.44.
+        let mut q2 = [0_u64; 12];
+        mul_limbs_high(&x[k - 1..2 * k], &mu[..mu_limbs(k, mu)], k - 1, &mut q2);
+        let q3 = &q2[k + 1..2 * k + 2];
+
+        // r = x - q3 m mod b^(k+1)
+        let mut product = [0_u64; 5];
+        mul_limbs_low(q3, &m[..k], &mut product[..=k]);
+        let mut r = [0_u64; 5];
+        let mut borrow = 0;
+        for i in 0..=k {
+            let (limb, next) = sbb(x[i], product[i], borrow);
+            r[i] = limb;
+            borrow = next;
+        }
+
+        // The estimate is at most a few less than the quotient
+        while !less_than(&r[..=k], &m[..=k]) {
+            let mut borrow = 0;
+            for i in 0..=k {
+                let (limb, next) = sbb(r[i], m[i], borrow);
+                r[i] = limb;
+                borrow = next;
+            }
+        }
+        U256::from_limbs(r[0], r[1], r[2], r[3])
+    }
+}
+
+/// Number of limbs of `mu`, which only has `k + 2` limbs for $m = b^{k-1}$.
+fn mu_limbs(k: usize, mu: &[u64; 6]) -> usize {
+    if mu[k + 1] == 0 {
+        k + 1
+    } else {
+        k + 2
+    }
+}
+
+/// Schoolbook multiplication leaving out the products below limb `min`.
+///
+/// The result can be slightly less than the full product because carries
+/// from the omitted products are lost. `out` must be zero and fit the
+/// product.
+fn mul_limbs_high(a: &[u64], b: &[u64], min: usize, out: &mut [u64]) {
+    for (i, &ai) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, &bj) in b.iter().enumerate().skip(min.saturating_sub(i)) {
+            let (limb, next) = mac(out[i + j], ai, bj, carry);
+            out[i + j] = limb;
+            carry = next;
+        }
+        out[i + b.len()] = carry;
+    }
+}
+
+/// Schoolbook multiplication modulo the base to the length of `out`, which
+/// must be zero.
+fn mul_limbs_low(a: &[u64], b: &[u64], out: &mut [u64]) {
+    for (i, &ai) in a.iter().enumerate().take(out.len()) {
+        let mut carry = 0;
+        for (j, &bj) in b.iter().enumerate().take(out.len() - i) {
+            let (limb, next) = mac(out[i + j], ai, bj, carry);
+            out[i + j] = limb;
+            carry = next;
+        }
+        if i + b.len() < out.len() {
+            out[i + b.len()] = carry;
+        }
+    }
+}
+
+/// Compare little-endian limbs of equal length.
+fn less_than(a: &[u64], b: &[u64]) -> bool {
+    for (ai, bi) in a.iter().zip(b.iter()).rev() {
+        if ai != bi {
+            return ai < bi;
+        }
+    }
+    false
+}
+
+// Quickcheck requires pass by value
+#[allow(clippy::needless_pass_by_value)]
+/// Number of exponent bits processed at once by `pow_windowed`.
+const POWMOD_WINDOW: usize = 4;
+
+/// Computes `base` to the power `exponent` given the unit `one` and the
+/// squaring and multiplication operations.
+fn pow_windowed<S, M>(one: U256, base: &U256, exponent: &U256, square: S, mul: M) -> U256
+where
+    S: Fn(&U256) -> U256,
+    M: Fn(&U256, &U256) -> U256,
+{
+    let bits = exponent.bits();
+    let mut result = one;
+    if bits <= 64 {
+        for i in (0..bits).rev() {
+            result = square(&result);
+            if exponent.bit(i) {
+                result = mul(&result, base);
+            }
+        }
+        return result;
+    }
+
+    // Table of base^i for i < 2^POWMOD_WINDOW
+    let mut table: [U256; 1 << POWMOD_WINDOW] = Default::default();
+    table[0] = result.clone();
+    table[1] = base.clone();
+    for i in 2..table.len() {
+        table[i] = mul(&table[i - 1], base);
+    }
+
+    let windows = (bits + POWMOD_WINDOW - 1) / POWMOD_WINDOW;
+    for window in (0..windows).rev() {
+        if window + 1 < windows {
+            for _ in 0..POWMOD_WINDOW {
+                result = square(&result);
+            }
+        }
+        let digit = (0..POWMOD_WINDOW)
+            .filter(|j| exponent.bit(window * POWMOD_WINDOW + j))
+            .fold(0, |digit, j| digit | (1 << j));
+        if digit != 0 {
+            result = mul(&result, &table[digit]);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use zkp_macros_decl::u256h;
+
+    /// Moduli of any bit length
+    fn modulus(m: U256, shift: u8) -> Option<MulModContext> {
+        MulModContext::new(&(m >> usize::from(shift)))
+    }
+
+    fn naive_powmod(base: &U256, exponent: u64, modulus: &U256) -> U256 {
+        let mut result = U256::ONE % modulus;
+        for _ in 0..exponent {
+            result = result.mulmod(base, modulus);
+        }
+        result
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(MulModContext::new(&U256::ZERO), None);
+        let one = MulModContext::new(&U256::ONE).unwrap();
+        assert_eq!(one.mulmod(&U256::MAX, &U256::MAX), U256::ZERO);
+        assert_eq!(one.powmod(&U256::ZERO, &U256::ZERO), U256::ZERO);
+    }
+
+    #[test]
+    fn test_powers_of_two() {
+        let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
+        let b = u256h!("07717a21e77894e8d82120c54277c73ee1062290709829411717f47973471ed5");
+        for &shift in &[1, 63, 64, 65, 128, 192, 255] {
+            let modulus = U256::ONE << shift;
+            let context = MulModContext::new(&modulus).unwrap();
+            assert_eq!(context.mulmod(&a, &b), a.mulmod(&b, &modulus));
+        }
+    }
+
+    #[test]
+    fn test_fermat() {
+        let p = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+        let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
+        let context = MulModContext::new(&p).unwrap();
+        assert_eq!(context.powmod(&a, &(p.clone() - U256::ONE)), U256::ONE);
+        assert_eq!(context.powmod(&a, &U256::ZERO), U256::ONE);
+        assert_eq!(context.powmod(&U256::ZERO, &U256::ZERO), U256::ONE);
+        assert_eq!(
+            context.mulmod(&context.powmod(&a, &(p.clone() - U256::from(2_u64))), &a),
+            U256::ONE
+        );
+    }
+
+    #[quickcheck]
+    fn mulmod_matches(a: U256, b: U256, m: U256, shift: u8) -> bool {
+        match modulus(m, shift) {
+            None => true,
+            Some(context) => context.mulmod(&a, &b) == a.mulmod(&b, context.modulus()),
+        }
+    }
+
+    #[quickcheck]
+    fn mulmod_matches_even(a: U256, b: U256, m: U256, shift: u8) -> bool {
+        match modulus(m.clone() - m.clone() % U256::from(2_u64), shift) {
+            None => true,
+            Some(context) => context.mulmod(&a, &b) == a.mulmod(&b, context.modulus()),
+        }
+    }
+
+    #[quickcheck]
+    fn addmod_matches(a: U256, b: U256, m: U256, shift: u8) -> bool {
+        match modulus(m, shift) {
+            None => true,
+            Some(context) => {
+                let m = context.modulus();
+                let sum = context.addmod(&a, &b);
+                let (a, b) = (&a % m, &b % m);
+                // Subtracting b again gives a
+                sum < *m
+                    && if sum >= b {
+                        sum - b == a
+                    } else {
+                        m - &(b - sum) == a
+                    }
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn powmod_matches(base: U256, exponent: u8, m: U256, shift: u8) -> bool {
+        match modulus(m, shift) {
+            None => true,
+            Some(context) => {
+                context.powmod(&base, &U256::from(exponent))
+                    == naive_powmod(&base, u64::from(exponent), context.modulus())
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn powmod_windowed(base: U256, e1: U256, e2: U256, m: U256, shift: u8) -> bool {
+        // Large exponents with e1 + e2 < 2^256
+        let e1 = e1 >> 1;
+        let e2 = e2 >> 1;
+        match modulus(m, shift) {
+            None => true,
+            Some(context) => {
+                context.powmod(&base, &(e1.clone() + &e2))
+                    == context.mulmod(&context.powmod(&base, &e1), &context.powmod(&base, &e2))
+            }
+        }
+    }
+}
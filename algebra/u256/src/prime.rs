@@ -0,0 +1,155 @@
+use crate::{MulModContext, U256};
+
+/// The primes below 256, used for trial division and as Miller-Rabin bases.
+const SMALL_PRIMES: [u64; 54] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// Number of Miller-Rabin rounds used by [`U256::next_prime`].
+///
+/// [`U256::next_prime`]: struct.U256.html#method.next_prime
+const NEXT_PRIME_ROUNDS: usize = 32;
+
+impl U256 {
+    /// Miller-Rabin probabilistic primality test.
+    ///
+    /// After trial division by the primes below 256 the number is tested
+    /// against the first `rounds` of those primes as Miller-Rabin bases, at
+    /// most 54. The bases are fixed, so the result is deterministic: a prime
+    /// is always reported as prime, and a composite is reported as prime with
+    /// probability at most $4^{-\mathtt{rounds}}$ if it is chosen at random.
+    /// Composites constructed to fool these specific bases exist, so do not
+    /// use this on untrusted inputs.
+    pub fn is_probable_prime(&self, rounds: usize) -> bool {
+        if *self < U256::from(2_u64) {
+            return false;
+        }
+        for &prime in SMALL_PRIMES.iter() {
+            if *self == U256::from(prime) {
+                return true;
+            }
+            if self.divrem_u64(prime).unwrap().1 == 0 {
+                return false;
+            }
+        }
+        // Without factors below 256 any number below 256^2 is prime.
+        if *self < U256::from(256_u64 * 256) {
+            return true;
+        }
+
+        // Write self - 1 = d 2^s with d odd.
+        let minus_one = self - &U256::ONE;
+        let s = minus_one.trailing_zeros();
+        let d = minus_one.clone() >> s;
+        let context = MulModContext::new(self).unwrap();
+        'witness: for &base in SMALL_PRIMES.iter().take(rounds) {
+            let mut x = context.powmod(&U256::from(base), &d);
+            if x == U256::ONE || x == minus_one {
+                continue;
+            }
+            for _ in 1..s {
+                x = context.mulmod(&x, &x);
+                if x == minus_one {
+                    continue 'witness;
+                }
+                if x == U256::ONE {
+                    return false;
+                }
+            }
+            return false;
+        }
+        true
+    }
+
+    /// The smallest probable prime larger than `self`, or `None` if there is
+    /// none below $2^{256}$.
+    ///
+    /// Candidates are tested with [`is_probable_prime`] using 32 rounds.
+    ///
+    /// [`is_probable_prime`]: #method.is_probable_prime
+    pub fn next_prime(&self) -> Option<Self> {
+        let two = U256::from(2_u64);
+        if *self < two {
+            return Some(two);
+        }
+        // Smallest odd number larger than self.
+        let mut candidate = if self.is_odd() {
+            self.checked_add(&two)?
+        } else {
+            self + &U256::ONE
+        };
+        while !candidate.is_probable_prime(NEXT_PRIME_ROUNDS) {
+            candidate = candidate.checked_add(&two)?;
+        }
+        Some(candidate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use zkp_macros_decl::u256h;
+
+    #[test]
+    fn test_small() {
+        let primes: Vec<u64> = (0..1000)
+            .filter(|&n| U256::from(n).is_probable_prime(1))
+            .collect();
+        assert_eq!(primes.len(), 168);
+        assert_eq!(&primes[..8], &[2, 3, 5, 7, 11, 13, 17, 19]);
+        assert_eq!(primes.last(), Some(&997));
+    }
+
+    #[test]
+    fn test_large() {
+        // The Stark field modulus and the secp256k1 group order
+        let p = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+        let n = u256h!("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+        assert!(p.is_probable_prime(20));
+        assert!(n.is_probable_prime(20));
+        assert!(!(p.clone() * &U256::from(3_u64)).is_probable_prime(20));
+        assert!(!U256::from(0xffff_fffb_u64 * 0xffff_ffef_u64).is_probable_prime(20));
+        // Carmichael numbers
+        assert!(!U256::from(561_u64).is_probable_prime(1));
+        assert!(!U256::from(9_746_347_772_161_u64).is_probable_prime(1));
+        // Strong pseudoprimes to the first eleven and twelve prime bases
+        let psi_11 = U256::from(3_825_123_056_546_413_051_u64);
+        let psi_12 = U256::from(318_665_857_834_031_151_167_461_u128);
+        assert!(psi_11.is_probable_prime(11));
+        assert!(!psi_11.is_probable_prime(12));
+        assert!(psi_12.is_probable_prime(12));
+        assert!(!psi_12.is_probable_prime(13));
+    }
+
+    #[test]
+    fn test_next_prime() {
+        assert_eq!(U256::ZERO.next_prime(), Some(U256::from(2_u64)));
+        assert_eq!(U256::from(2_u64).next_prime(), Some(U256::from(3_u64)));
+        assert_eq!(U256::from(3_u64).next_prime(), Some(U256::from(5_u64)));
+        assert_eq!(
+            U256::from(1000_u64).next_prime(),
+            Some(U256::from(1009_u64))
+        );
+        // 2^64 + 13 and 2^256 - 189 are prime
+        assert_eq!(
+            U256::from(u64::max_value()).next_prime(),
+            Some(U256::from_limbs(13, 1, 0, 0))
+        );
+        let largest = U256::MAX - U256::from(188_u64);
+        assert_eq!(
+            (largest.clone() - U256::ONE).next_prime(),
+            Some(largest.clone())
+        );
+        assert_eq!(largest.next_prime(), None);
+    }
+
+    #[quickcheck]
+    fn product_is_composite(a: u64, b: u64) -> bool {
+        let a = U256::from(a | 1).next_prime().unwrap();
+        let b = U256::from(b | 1).next_prime().unwrap();
+        a.is_probable_prime(10) && !(a * b).is_probable_prime(10)
+    }
+}
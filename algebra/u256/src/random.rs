@@ -0,0 +1,143 @@
+//! Random sampling of `U256` using the `rand` crate.
+use crate::U256;
+use rand::{
+    distributions::{
+        uniform::{SampleBorrow, SampleUniform, UniformSampler},
+        Distribution, Standard,
+    },
+    Rng,
+};
+
+/// Uniformly distributed over all 256 bit numbers.
+impl Distribution<U256> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> U256 {
+        U256::from_limbs(rng.gen(), rng.gen(), rng.gen(), rng.gen())
+    }
+}
+
+impl U256 {
+    /// Uniformly distributed number in `0..modulus`.
+    ///
+    /// Uses rejection sampling on numbers with the bit length of the modulus,
+    /// so on average less than two samples are drawn.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `modulus` is zero.
+    pub fn random_below<R: Rng + ?Sized>(rng: &mut R, modulus: &Self) -> Self {
+        assert!(!modulus.is_zero(), "Can not sample below zero.");
+        let shift = modulus.leading_zeros();
+        loop {
+            let candidate = rng.gen::<Self>() >> shift;
+            if candidate < *modulus {
+                return candidate;
+            }
+        }
+    }
+}
+
+/// Sampler for `U256` in a range, used by `rand::distributions::Uniform`.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct UniformU256 {
+    low:   U256,
+    /// Size of the range, zero means all 2^256 numbers.
+    range: U256,
+}
+
+impl UniformSampler for UniformU256 {
+    type X = U256;
+
+    fn new<B1, B2>(low: B1, high: B2) -> Self
+    where
+        B1: SampleBorrow<Self::X> + Sized,
+        B2: SampleBorrow<Self::X> + Sized,
+    {
+        let (low, high) = (low.borrow(), high.borrow());
+        assert!(low < high, "Uniform::new called with `low >= high`");
+        Self {
+            low:   low.clone(),
+            range: high - low,
+        }
+    }
+
+    fn new_inclusive<B1, B2>(low: B1, high: B2) -> Self
+    where
+        B1: SampleBorrow<Self::X> + Sized,
+        B2: SampleBorrow<Self::X> + Sized,
+    {
+        let (low, high) = (low.borrow(), high.borrow());
+        assert!(
+            low <= high,
+            "Uniform::new_inclusive called with `low > high`"
+        );
+        Self {
+            low:   low.clone(),
+            range: (high - low).wrapping_add(&U256::ONE),
+        }
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Self::X {
+        if self.range.is_zero() {
+            rng.gen()
+        } else {
+            &self.low + U256::random_below(rng, &self.range)
+        }
+    }
+}
+
+impl SampleUniform for U256 {
+    type Sampler = UniformU256;
+}
+
+// Quickcheck requires pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use rand::{distributions::Uniform, rngs::mock::StepRng};
+
+    fn rng(seed: u64) -> StepRng {
+        StepRng::new(seed, 0x9e37_79b9_7f4a_7c15)
+    }
+
+    #[test]
+    fn test_random_below() {
+        let mut rng = rng(1);
+        for _ in 0..10 {
+            assert_eq!(U256::random_below(&mut rng, &U256::ONE), U256::ZERO);
+        }
+        let full = Uniform::new_inclusive(U256::ZERO, U256::MAX);
+        let _ = full.sample(&mut rng);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_random_below_zero() {
+        let _ = U256::random_below(&mut rng(1), &U256::ZERO);
+    }
+
+    #[quickcheck]
+    fn random_below(seed: u64, modulus: U256, shift: u8) -> bool {
+        let modulus = modulus >> usize::from(shift);
+        if modulus.is_zero() {
+            return true;
+        }
+        let mut rng = rng(seed);
+        (0..10).all(|_| U256::random_below(&mut rng, &modulus) < modulus)
+    }
+
+    #[quickcheck]
+    fn uniform_range(seed: u64, a: U256, b: U256) -> bool {
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+        let mut rng = rng(seed);
+        let sample = Uniform::new_inclusive(&low, &high).sample(&mut rng);
+        let in_inclusive = low <= sample && sample <= high;
+        if low == high {
+            return in_inclusive;
+        }
+        let sample = rng.gen_range(&low, &high);
+        in_inclusive && low <= sample && sample < high
+    }
+}
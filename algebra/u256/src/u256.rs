@@ -4,25 +4,30 @@ use crate::{
     gcd::inv_mod,
     noncommutative_binop,
     utils::{adc, div_2_1, mac, sbb},
+    MulModContext,
 };
 use std::{
     cmp::Ordering,
     num::Wrapping,
     ops::{
-        Add, AddAssign, BitAnd, BitAndAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Shl,
-        ShlAssign, Shr, ShrAssign, Sub, SubAssign,
+        Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div,
+        DivAssign, Mul, MulAssign, Not, Rem, RemAssign, Shl, ShlAssign, Shr, ShrAssign, Sub,
+        SubAssign,
     },
     prelude::v1::*,
+    str::FromStr,
     u64,
 };
 
 #[cfg(feature = "std")]
-use std::{fmt, format};
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseError {
     Empty,
     Overflow,
+    InvalidDigit,
+    InvalidRadix,
     InnerError(core::num::ParseIntError),
 }
 
@@ -32,6 +37,33 @@ impl From<core::num::ParseIntError> for ParseError {
     }
 }
 
+/// The number of decimal digits that always fit a `u64`.
+const DECIMAL_CHUNK_DIGITS: usize = 19;
+
+/// `10^i` for `i` up to and including `DECIMAL_CHUNK_DIGITS`.
+const POWERS_OF_TEN: [u64; DECIMAL_CHUNK_DIGITS + 1] = [
+    1,
+    10,
+    100,
+    1_000,
+    10_000,
+    100_000,
+    1_000_000,
+    10_000_000,
+    100_000_000,
+    1_000_000_000,
+    10_000_000_000,
+    100_000_000_000,
+    1_000_000_000_000,
+    10_000_000_000_000,
+    100_000_000_000_000,
+    1_000_000_000_000_000,
+    10_000_000_000_000_000,
+    100_000_000_000_000_000,
+    1_000_000_000_000_000_000,
+    10_000_000_000_000_000_000,
+];
+
 #[derive(PartialEq, Eq, Clone, Default)]
 pub struct U256 {
     pub c0: u64,
@@ -79,33 +111,62 @@ impl U256 {
         *self == Self::ZERO
     }
 
-    // Can not use Self inside the macro
-    #[allow(clippy::use_self)]
-    pub fn from_decimal_str(s: &str) -> Result<Self, ParseError> {
-        // ceil(2^256 / 10)
-        let max10: Self = Self::from_limbs(
-            0x9999_9999_9999_999a_u64,
-            0x9999_9999_9999_9999_u64,
-            0x9999_9999_9999_9999_u64,
-            0x1999_9999_9999_9999_u64,
-        );
+    /// Parse a string of digits in the given radix.
+    ///
+    /// Like `u64::from_str_radix` the radix can be from 2 to 36 and digits
+    /// beyond 9 can be upper or lower case letters. There is no prefix or
+    /// sign.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseError> {
+        if radix < 2 || radix > 36 {
+            return Err(ParseError::InvalidRadix);
+        }
         if s.is_empty() {
             return Err(ParseError::Empty);
         }
-        // TODO: Support other radices
-        // TODO: Implement as trait
-        // OPT: Convert 19 digits at a time using u64.
+        if radix == 10 {
+            return Self::from_decimal_str(s);
+        }
         let mut result = Self::ZERO;
-        for (i, _c) in s.chars().enumerate() {
-            if result > max10 {
-                return Err(ParseError::Overflow);
+        if radix.is_power_of_two() {
+            let bits = radix.trailing_zeros() as usize;
+            for c in s.chars() {
+                let digit = c.to_digit(radix).ok_or(ParseError::InvalidDigit)?;
+                if result.leading_zeros() < bits {
+                    return Err(ParseError::Overflow);
+                }
+                result <<= bits;
+                result.c0 |= u64::from(digit);
             }
-            result *= Self::from(10_u64);
-            let digit = Self::from(u64::from_str_radix(&s[i..=i], 10)?);
-            if &result + &digit < result {
-                return Err(ParseError::Overflow);
+        } else {
+            let base = Self::from(radix);
+            for c in s.chars() {
+                let digit = c.to_digit(radix).ok_or(ParseError::InvalidDigit)?;
+                result = result
+                    .checked_mul(&base)
+                    .and_then(|result| result.checked_add(&Self::from(digit)))
+                    .ok_or(ParseError::Overflow)?;
             }
-            result += digit;
+        }
+        Ok(result)
+    }
+
+    pub fn from_decimal_str(s: &str) -> Result<Self, ParseError> {
+        if s.is_empty() {
+            return Err(ParseError::Empty);
+        }
+        // Convert 19 digits at a time using u64.
+        let mut result = Self::ZERO;
+        for chunk in s.as_bytes().chunks(DECIMAL_CHUNK_DIGITS) {
+            let mut value = 0_u64;
+            for &c in chunk {
+                if !c.is_ascii_digit() {
+                    return Err(ParseError::InvalidDigit);
+                }
+                value = 10 * value + u64::from(c - b'0');
+            }
+            result = result
+                .checked_mul_add_u64(POWERS_OF_TEN[chunk.len()], value)
+                .ok_or(ParseError::Overflow)?;
         }
         Ok(result)
     }
@@ -114,27 +175,114 @@ impl U256 {
         if *self == Self::ZERO {
             return "0".to_string();
         }
-        let mut result = String::new();
+        // Convert 19 digits at a time using u64, least significant first.
+        let mut digits = Vec::with_capacity(78);
         let mut copy = self.clone();
-        while copy > Self::ZERO {
-            // OPT: Convert 19 digits at a time using u64.
-            let digit = (&copy % Self::from(10_u64)).c0;
-            result.push_str(&digit.to_string());
-            copy /= Self::from(10_u64);
+        while !copy.is_zero() {
+            let (quotient, mut chunk) = copy
+                .divrem_u64(POWERS_OF_TEN[DECIMAL_CHUNK_DIGITS])
+                .unwrap();
+            copy = quotient;
+            for _ in 0..DECIMAL_CHUNK_DIGITS {
+                // Truncation is intentional, the digit is less than 10.
+                #[allow(clippy::cast_possible_truncation)]
+                digits.push(b'0' + (chunk % 10) as u8);
+                chunk /= 10;
+            }
         }
-        // Reverse digits
-        // Note: Chars are safe here instead of graphemes, because all graphemes
-        // are a single codepoint.
-        result.chars().rev().collect()
+        // Strip the padding of the most significant chunk and reverse digits
+        while digits.last() == Some(&b'0') {
+            let _ = digits.pop();
+        }
+        digits.reverse();
+        String::from_utf8(digits).unwrap()
     }
 
+    /// Computes `self * factor + addend`, returning `None` on overflow.
+    // We shadow carry for readability
+    #[allow(clippy::shadow_unrelated)]
+    fn checked_mul_add_u64(&self, factor: u64, addend: u64) -> Option<Self> {
+        let (c0, carry) = mac(addend, self.c0, factor, 0);
+        let (c1, carry) = mac(0, self.c1, factor, carry);
+        let (c2, carry) = mac(0, self.c2, factor, carry);
+        let (c3, carry) = mac(0, self.c3, factor, carry);
+        if carry == 0 {
+            Some(Self::from_limbs(c0, c1, c2, c3))
+        } else {
+            None
+        }
+    }
+
+    /// Parse hexadecimal digits with an optional `0x` prefix.
+    pub fn from_hex_str(s: &str) -> Result<Self, ParseError> {
+        if s.starts_with("0x") {
+            Self::from_str_radix(&s[2..], 16)
+        } else {
+            Self::from_str_radix(s, 16)
+        }
+    }
+
+    /// Parse hexadecimal digits with an optional `0x` prefix in a `const`
+    /// context.
+    ///
+    /// This allows declaring constants without the `u256h!` macro:
+    ///
+    /// ```
+    /// # use zkp_u256::U256;
+    /// const N: U256 = U256::from_hex("0x0800000000000011");
+    /// assert_eq!(N, U256::from(0x0800_0000_0000_0011_u64));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics, or fails to compile when used in a constant, if `s` is empty,
+    /// contains a non-hexadecimal digit or has more than 64 digits.
+    // `u64::from` is not available in a `const fn`.
+    #[allow(clippy::cast_lossless)]
+    pub const fn from_hex(s: &str) -> Self {
+        let bytes = s.as_bytes();
+        let mut i = if bytes.len() >= 2 && bytes[0] == b'0' && bytes[1] == b'x' {
+            2
+        } else {
+            0
+        };
+        assert!(i < bytes.len(), "Empty hexadecimal number");
+        assert!(bytes.len() - i <= 64, "Hexadecimal number exceeds 256 bits");
+        let mut limbs = [0_u64; 4];
+        while i < bytes.len() {
+            let digit = match bytes[i] {
+                b'0'..=b'9' => bytes[i] - b'0',
+                b'a'..=b'f' => bytes[i] - b'a' + 10,
+                b'A'..=b'F' => bytes[i] - b'A' + 10,
+                _ => panic!("Invalid hexadecimal digit"),
+            };
+            // Shift all limbs left by one digit
+            limbs[3] = (limbs[3] << 4) | (limbs[2] >> 60);
+            limbs[2] = (limbs[2] << 4) | (limbs[1] >> 60);
+            limbs[1] = (limbs[1] << 4) | (limbs[0] >> 60);
+            limbs[0] = (limbs[0] << 4) | digit as u64;
+            i += 1;
+        }
+        Self::from_limbs(limbs[0], limbs[1], limbs[2], limbs[3])
+    }
+
+    /// Digits in radix `2^bits`, most significant first.
     #[cfg(feature = "std")]
-    pub fn from_hex_str(s: &str) -> Self {
-        let byte_string = format!("{:0>64}", s.trim_start_matches("0x"));
-        let bytes = hex::decode(byte_string).unwrap();
-        let mut array = [0_u8; 32];
-        array.copy_from_slice(&bytes[..32]);
-        Self::from_bytes_be(&array)
+    fn to_pow2_radix_str(&self, bits: usize) -> String {
+        if self.is_zero() {
+            return "0".to_string();
+        }
+        let mask = (1_u64 << bits) - 1;
+        let mut result = String::new();
+        let mut copy = self.clone();
+        while !copy.is_zero() {
+            // Truncation is intentional, the digit is less than 32.
+            #[allow(clippy::cast_possible_truncation)]
+            let digit = (copy.c0 & mask) as u32;
+            result.push(core::char::from_digit(digit, 1 << bits).unwrap());
+            copy >>= bits;
+        }
+        result.chars().rev().collect()
     }
 
     pub const fn is_even(&self) -> bool {
@@ -181,6 +329,25 @@ impl U256 {
         }
     }
 
+    pub fn count_ones(&self) -> usize {
+        (self.c0.count_ones() + self.c1.count_ones() + self.c2.count_ones() + self.c3.count_ones())
+            as usize
+    }
+
+    /// Shifts the bits to the left by `n` modulo 256, wrapping the truncated
+    /// bits to the end.
+    pub fn rotate_left(&self, n: usize) -> Self {
+        let n = n % 256;
+        // Note: `>> 256` results in zero.
+        (self.clone() << n) | (self.clone() >> (256 - n))
+    }
+
+    /// Shifts the bits to the right by `n` modulo 256, wrapping the truncated
+    /// bits to the beginning.
+    pub fn rotate_right(&self, n: usize) -> Self {
+        self.rotate_left(256 - n % 256)
+    }
+
     pub fn trailing_zeros(&self) -> usize {
         if self.c0 > 0 {
             self.c0.trailing_zeros() as usize
@@ -369,6 +536,105 @@ impl U256 {
             Some(result)
         }
     }
+
+    /// Computes `self` to the power `exponent` modulo `modulus`.
+    ///
+    /// Zero to the power zero is one. For repeated exponentiations with the
+    /// same modulus use a [`MulModContext`] directly.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the modulus is zero.
+    ///
+    /// [`MulModContext`]: struct.MulModContext.html
+    pub fn powmod(&self, exponent: &Self, modulus: &Self) -> Self {
+        MulModContext::new(modulus)
+            .expect("Modulus is zero")
+            .powmod(self, exponent)
+    }
+}
+
+// Overflow aware arithmetic, mirroring the methods on the primitive integers.
+// Note: The `+`, `-` and `*` operators wrap silently.
+impl U256 {
+    /// Calculates `self + rhs`, returning the wrapped result and whether an
+    /// overflow occurred.
+    // We shadow carry for readability
+    #[allow(clippy::shadow_unrelated)]
+    pub fn overflowing_add(&self, rhs: &Self) -> (Self, bool) {
+        let (c0, carry) = adc(self.c0, rhs.c0, 0);
+        let (c1, carry) = adc(self.c1, rhs.c1, carry);
+        let (c2, carry) = adc(self.c2, rhs.c2, carry);
+        let (c3, carry) = adc(self.c3, rhs.c3, carry);
+        (Self::from_limbs(c0, c1, c2, c3), carry != 0)
+    }
+
+    /// Calculates `self - rhs`, returning the wrapped result and whether an
+    /// underflow occurred.
+    // We shadow borrow for readability
+    #[allow(clippy::shadow_unrelated)]
+    pub fn overflowing_sub(&self, rhs: &Self) -> (Self, bool) {
+        let (c0, borrow) = sbb(self.c0, rhs.c0, 0);
+        let (c1, borrow) = sbb(self.c1, rhs.c1, borrow);
+        let (c2, borrow) = sbb(self.c2, rhs.c2, borrow);
+        let (c3, borrow) = sbb(self.c3, rhs.c3, borrow);
+        (Self::from_limbs(c0, c1, c2, c3), borrow != 0)
+    }
+
+    /// Calculates `self * rhs`, returning the wrapped result and whether an
+    /// overflow occurred.
+    pub fn overflowing_mul(&self, rhs: &Self) -> (Self, bool) {
+        let (lo, hi) = self.mul_full(rhs);
+        (lo, !hi.is_zero())
+    }
+
+    pub fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        match self.overflowing_add(rhs) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        match self.overflowing_sub(rhs) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    pub fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+        match self.overflowing_mul(rhs) {
+            (result, false) => Some(result),
+            (_, true) => None,
+        }
+    }
+
+    /// Same as `self + rhs`, but explicit about the wrapping.
+    pub fn wrapping_add(&self, rhs: &Self) -> Self {
+        self.overflowing_add(rhs).0
+    }
+
+    /// Same as `self - rhs`, but explicit about the wrapping.
+    pub fn wrapping_sub(&self, rhs: &Self) -> Self {
+        self.overflowing_sub(rhs).0
+    }
+
+    /// Same as `self * rhs`, but explicit about the wrapping.
+    pub fn wrapping_mul(&self, rhs: &Self) -> Self {
+        self.clone() * rhs
+    }
+
+    pub fn saturating_add(&self, rhs: &Self) -> Self {
+        self.checked_add(rhs).unwrap_or(Self::MAX)
+    }
+
+    pub fn saturating_sub(&self, rhs: &Self) -> Self {
+        self.checked_sub(rhs).unwrap_or(Self::ZERO)
+    }
+
+    pub fn saturating_mul(&self, rhs: &Self) -> Self {
+        self.checked_mul(rhs).unwrap_or(Self::MAX)
+    }
 }
 
 macro_rules! impl_from_uint {
@@ -504,6 +770,45 @@ impl fmt::Debug for U256 {
     }
 }
 
+macro_rules! impl_fmt_radix {
+    ($trait:ident, $bits:expr, $prefix:expr, $uppercase:expr) => {
+        #[cfg(feature = "std")]
+        impl fmt::$trait for U256 {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let digits = self.to_pow2_radix_str($bits);
+                if $uppercase {
+                    f.pad_integral(true, $prefix, &digits.to_uppercase())
+                } else {
+                    f.pad_integral(true, $prefix, &digits)
+                }
+            }
+        }
+    };
+}
+
+impl_fmt_radix!(LowerHex, 4, "0x", false);
+impl_fmt_radix!(UpperHex, 4, "0x", true);
+impl_fmt_radix!(Octal, 3, "0o", false);
+impl_fmt_radix!(Binary, 1, "0b", false);
+
+/// Parses hexadecimal, octal and binary with a `0x`, `0o` and `0b` prefix
+/// respectively, and decimal otherwise.
+impl FromStr for U256 {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        if s.starts_with("0x") {
+            Self::from_str_radix(&s[2..], 16)
+        } else if s.starts_with("0o") {
+            Self::from_str_radix(&s[2..], 8)
+        } else if s.starts_with("0b") {
+            Self::from_str_radix(&s[2..], 2)
+        } else {
+            Self::from_str_radix(s, 10)
+        }
+    }
+}
+
 impl PartialOrd for U256 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -546,6 +851,40 @@ impl BitAndAssign<&U256> for U256 {
     }
 }
 
+impl BitOrAssign<&U256> for U256 {
+    fn bitor_assign(&mut self, rhs: &Self) {
+        self.c0 |= rhs.c0;
+        self.c1 |= rhs.c1;
+        self.c2 |= rhs.c2;
+        self.c3 |= rhs.c3;
+    }
+}
+
+impl BitXorAssign<&U256> for U256 {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        self.c0 ^= rhs.c0;
+        self.c1 ^= rhs.c1;
+        self.c2 ^= rhs.c2;
+        self.c3 ^= rhs.c3;
+    }
+}
+
+impl Not for U256 {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self::from_limbs(!self.c0, !self.c1, !self.c2, !self.c3)
+    }
+}
+
+impl Not for &U256 {
+    type Output = U256;
+
+    fn not(self) -> U256 {
+        U256::from_limbs(!self.c0, !self.c1, !self.c2, !self.c3)
+    }
+}
+
 impl ShlAssign<usize> for U256 {
     fn shl_assign(&mut self, rhs: usize) {
         // Note: If RHS is a compile time constant then inlining will allow
@@ -743,6 +1082,8 @@ impl RemAssign<&U256> for U256 {
 commutative_binop!(U256, Add, add, AddAssign, add_assign);
 commutative_binop!(U256, Mul, mul, MulAssign, mul_assign);
 commutative_binop!(U256, BitAnd, bitand, BitAndAssign, bitand_assign);
+commutative_binop!(U256, BitOr, bitor, BitOrAssign, bitor_assign);
+commutative_binop!(U256, BitXor, bitxor, BitXorAssign, bitxor_assign);
 noncommutative_binop!(U256, Sub, sub, SubAssign, sub_assign);
 noncommutative_binop!(U256, Div, div, DivAssign, div_assign);
 noncommutative_binop!(U256, Rem, rem, RemAssign, rem_assign);
@@ -910,6 +1251,31 @@ mod tests {
         assert!(U256::from_decimal_str("12a3").is_err());
     }
 
+    #[test]
+    fn test_decimal_chunks() {
+        for &digits in &[18, 19, 20, 37, 38, 39, 57, 76, 77] {
+            let s = "9".repeat(digits);
+            let expected = U256::from(10_u64).pow(digits as u64).unwrap() - U256::ONE;
+            assert_eq!(U256::from_decimal_str(&s), Ok(expected.clone()));
+            assert_eq!(expected.to_decimal_str(), s);
+            let power = format!("1{}", "0".repeat(digits));
+            assert_eq!(
+                U256::from_decimal_str(&power).unwrap().to_decimal_str(),
+                power
+            );
+        }
+        assert_eq!(
+            U256::from_decimal_str(&"2".repeat(78)),
+            Err(ParseError::Overflow)
+        );
+        assert_eq!(
+            U256::from_decimal_str("1234567890123456789x"),
+            Err(ParseError::InvalidDigit)
+        );
+        assert_eq!(U256::from_decimal_str("+1"), Err(ParseError::InvalidDigit));
+        assert_eq!(U256::from_decimal_str("１"), Err(ParseError::InvalidDigit));
+    }
+
     #[quickcheck]
     fn test_decimal_to_from(n: U256) -> bool {
         let decimal = n.clone().to_decimal_str();
@@ -1056,6 +1422,30 @@ mod tests {
         assert_eq!(rhi, ehi);
     }
 
+    #[test]
+    fn test_from_hex_const() {
+        const A: U256 =
+            U256::from_hex("0800000000000011000000000000000000000000000000000000000000000001");
+        assert_eq!(
+            A,
+            u256h!("0800000000000011000000000000000000000000000000000000000000000001")
+        );
+        assert_eq!(U256::from_hex("0xFfA"), U256::from(0xffa_u64));
+        assert_eq!(U256::from_hex(&"f".repeat(64)), U256::MAX);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_hex_const_overflow() {
+        let _ = U256::from_hex(&"1".repeat(65));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_hex_const_invalid() {
+        let _ = U256::from_hex("0x12g4");
+    }
+
     #[test]
     fn test_invmod256() {
         let a = U256::from_limbs(
@@ -1107,6 +1497,27 @@ mod tests {
         assert_eq!(i, r);
     }
 
+    #[test]
+    fn test_powmod() {
+        let p = u256h!("0800000000000011000000000000000000000000000000000000000000000001");
+        let a = u256h!("01c9e043b135fa21471cec503f1181884ef3d9c2cb44b6a3531bb3056443bc99");
+        let inverse = a.powmod(&(p.clone() - U256::from(2_u64)), &p);
+        assert_eq!(inverse, a.invmod(&p).unwrap());
+        assert_eq!(U256::ZERO.powmod(&U256::ZERO, &p), U256::ONE);
+        assert_eq!(a.powmod(&U256::MAX, &U256::ONE), U256::ZERO);
+        let m = U256::ONE << 200;
+        assert_eq!(
+            U256::from(3_u64).powmod(&U256::from(1000_u64), &m),
+            U256::from(3_u64).pow(1000).unwrap() % &m
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Modulus is zero")]
+    fn test_powmod_zero_modulus() {
+        let _ = U256::ONE.powmod(&U256::ONE, &U256::ZERO);
+    }
+
     #[test]
     fn test_mulmod() {
         let a = U256::from_limbs(
@@ -1181,4 +1592,165 @@ mod tests {
     fn square(a: U256) -> bool {
         a.sqr_full() == a.mul_full(&a)
     }
+
+    #[test]
+    fn test_overflowing() {
+        assert_eq!(U256::MAX.overflowing_add(&U256::ONE), (U256::ZERO, true));
+        assert_eq!(U256::ZERO.overflowing_sub(&U256::ONE), (U256::MAX, true));
+        assert_eq!(
+            U256::MAX.overflowing_mul(&U256::from(2_u64)),
+            (U256::MAX - U256::ONE, true)
+        );
+        let half = U256::ONE << 128;
+        assert_eq!(half.overflowing_mul(&half), (U256::ZERO, true));
+        assert_eq!(
+            (half.clone() - U256::ONE).checked_mul(&half),
+            Some(U256::MAX - (half.clone() - U256::ONE))
+        );
+        assert_eq!(U256::MAX.saturating_add(&half), U256::MAX);
+        assert_eq!(U256::ONE.saturating_sub(&half), U256::ZERO);
+        assert_eq!(half.saturating_mul(&half), U256::MAX);
+    }
+
+    #[quickcheck]
+    fn overflowing_add(a: U256, b: U256) -> bool {
+        let (sum, overflow) = a.overflowing_add(&b);
+        sum == a.wrapping_add(&b) && sum == &a + &b && overflow == (sum < a)
+    }
+
+    #[quickcheck]
+    fn overflowing_sub(a: U256, b: U256) -> bool {
+        let (difference, overflow) = a.overflowing_sub(&b);
+        difference == a.wrapping_sub(&b) && difference == &a - &b && overflow == (b > a)
+    }
+
+    #[quickcheck]
+    fn overflowing_mul(a: U256, b: U256) -> bool {
+        let (lo, hi) = a.mul_full(&b);
+        a.overflowing_mul(&b) == (lo.clone(), hi != U256::ZERO) && a.wrapping_mul(&b) == lo
+    }
+
+    #[quickcheck]
+    fn checked_saturating(a: U256, b: U256) -> bool {
+        let b = b >> 128;
+        a.checked_add(&b)
+            .map_or(a.saturating_add(&b) == U256::MAX, |sum| {
+                sum == a.saturating_add(&b)
+            })
+            && a.checked_sub(&b)
+                .map_or(a.saturating_sub(&b) == U256::ZERO, |diff| {
+                    diff == a.saturating_sub(&b)
+                })
+            && a.checked_mul(&b)
+                .map_or(a.saturating_mul(&b) == U256::MAX, |product| {
+                    product == a.saturating_mul(&b)
+                })
+    }
+
+    #[test]
+    fn test_bitwise() {
+        let a = u256h!("ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00");
+        let b = u256h!("0ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff0");
+        assert_eq!(
+            &a | &b,
+            u256h!("fff0fff0fff0fff0fff0fff0fff0fff0fff0fff0fff0fff0fff0fff0fff0fff0")
+        );
+        assert_eq!(
+            &a ^ &b,
+            u256h!("f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0f0")
+        );
+        assert_eq!(
+            !&a,
+            u256h!("00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff")
+        );
+        assert_eq!(
+            a.rotate_left(4),
+            u256h!("f00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00ff00f")
+        );
+        assert_eq!(a.rotate_right(8), !&a);
+        assert_eq!(a.count_ones(), 128);
+        assert_eq!(U256::MAX.count_ones(), 256);
+    }
+
+    #[quickcheck]
+    fn bitwise_identities(a: U256, b: U256) -> bool {
+        (&a ^ &b) == (&a | &b) & !(&a & &b)
+            && !!a.clone() == a
+            && (&a | !&a) == U256::MAX
+            && (&a ^ &a) == U256::ZERO
+            && a.count_ones() + (!&a).count_ones() == 256
+    }
+
+    #[quickcheck]
+    fn rotate(a: U256, n: usize) -> bool {
+        a.rotate_left(n).rotate_right(n) == a
+            && a.rotate_left(n).count_ones() == a.count_ones()
+            && a.rotate_left(n % 256 + 256) == a.rotate_left(n)
+            && a.rotate_left(1) == (a.clone() << 1) | U256::from(u64::from(a.bit(255)))
+    }
+
+    #[test]
+    fn test_from_str_radix() {
+        assert_eq!(U256::from_str_radix("", 16), Err(ParseError::Empty));
+        assert_eq!(U256::from_str_radix("1", 1), Err(ParseError::InvalidRadix));
+        assert_eq!(U256::from_str_radix("1", 37), Err(ParseError::InvalidRadix));
+        assert_eq!(U256::from_str_radix("12", 2), Err(ParseError::InvalidDigit));
+        assert_eq!(U256::from_str_radix("zz", 36), Ok(U256::from(1295_u64)));
+        assert_eq!(U256::from_str_radix("777", 8), Ok(U256::from(511_u64)));
+        assert_eq!(U256::from_str_radix(&"f".repeat(64), 16), Ok(U256::MAX));
+        assert_eq!(
+            U256::from_str_radix(&"1".repeat(257), 2),
+            Err(ParseError::Overflow)
+        );
+        assert_eq!(U256::from_str_radix(&"1".repeat(256), 2), Ok(U256::MAX));
+        assert_eq!(
+            U256::from_hex_str(
+                "0x0800000000000011000000000000000000000000000000000000000000000001"
+            ),
+            Ok(u256h!(
+                "0800000000000011000000000000000000000000000000000000000000000001"
+            ))
+        );
+        assert_eq!(
+            U256::from_hex_str("0xABCdef"),
+            Ok(U256::from(0x00ab_cdef_u64))
+        );
+        assert_eq!(U256::from_hex_str("0xg"), Err(ParseError::InvalidDigit));
+        assert_eq!(
+            U256::from_hex_str(&"1".repeat(65)),
+            Err(ParseError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("0x1f".parse(), Ok(U256::from(31_u64)));
+        assert_eq!("0o17".parse(), Ok(U256::from(15_u64)));
+        assert_eq!("0b101".parse(), Ok(U256::from(5_u64)));
+        assert_eq!("0101".parse(), Ok(U256::from(101_u64)));
+        assert_eq!("0x".parse::<U256>(), Err(ParseError::Empty));
+    }
+
+    #[test]
+    fn test_fmt_radix() {
+        let n = U256::from(0x00de_adbe_efu64);
+        assert_eq!(format!("{:x}", n), "deadbeef");
+        assert_eq!(format!("{:X}", n), "DEADBEEF");
+        assert_eq!(format!("{:#x}", n), "0xdeadbeef");
+        assert_eq!(format!("{:#012x}", n), "0x00deadbeef");
+        assert_eq!(format!("{:o}", U256::from(8_u64)), "10");
+        assert_eq!(format!("{:#b}", U256::from(5_u64)), "0b101");
+        assert_eq!(format!("{:x}", U256::ZERO), "0");
+        assert_eq!(format!("{:x}", U256::MAX), "f".repeat(64));
+        assert_eq!(format!("{:o}", U256::MAX), format!("1{}", "7".repeat(85)));
+    }
+
+    #[quickcheck]
+    fn fmt_parse_roundtrip(n: U256) -> bool {
+        format!("{:#x}", n).parse() == Ok(n.clone())
+            && format!("{:#X}", n).parse() == Ok(n.clone())
+            && format!("{:#o}", n).parse() == Ok(n.clone())
+            && format!("{:#b}", n).parse() == Ok(n.clone())
+            && U256::from_str_radix(&format!("{:x}", n), 16) == Ok(n)
+    }
 }
@@ -0,0 +1,18 @@
+#![warn(clippy::all)]
+use criterion::criterion_main;
+
+mod curve;
+mod fft;
+mod field;
+mod merkle;
+mod stark;
+mod u256;
+
+criterion_main!(
+    u256::u256,
+    field::field,
+    fft::fft,
+    curve::curve,
+    merkle::merkle,
+    stark::stark
+);
@@ -0,0 +1,22 @@
+use criterion::{black_box, criterion_group, Criterion};
+use zkp_elliptic_curve::{Affine, Jacobian};
+use zkp_macros_decl::u256h;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+fn jacobian_mul(crit: &mut Criterion) {
+    let a = Jacobian::from(Affine::Point {
+        x: FieldElement::from_montgomery(u256h!(
+            "04f50f81bf91b7ada9de33eeec4ae787bc39f520fbb5c8fa4620fecfca4d7cf5"
+        )),
+        y: FieldElement::from_montgomery(u256h!(
+            "0176a4c00d1ce6b642176e460624b1699da148593f701cac4df2280c2edb163f"
+        )),
+    });
+    let b = u256h!("014023b44fbb1e6f2a79c929c6da775be3c4b9e043d439385b5050fdc69177e3");
+    crit.bench_function("Jacobian mul", move |bench| {
+        bench.iter(|| black_box(black_box(&a) * black_box(&b)))
+    });
+}
+
+criterion_group!(curve, jacobian_mul);
@@ -0,0 +1,32 @@
+use criterion::{black_box, criterion_group, Criterion};
+use zkp_criterion_utils::log_size_bench;
+use zkp_macros_decl::field_element;
+use zkp_primefield::{fft::fft_cofactor_permuted, FieldElement};
+use zkp_u256::U256;
+
+/// Sizes from $2^{10}$ to $2^{24}$. The largest input takes half a gigabyte.
+const SIZES: [usize; 8] = [
+    1 << 10,
+    1 << 12,
+    1 << 14,
+    1 << 16,
+    1 << 18,
+    1 << 20,
+    1 << 22,
+    1 << 24,
+];
+
+fn fft_size(crit: &mut Criterion) {
+    log_size_bench(crit, "FFT size", &SIZES, move |bench, size| {
+        let cofactor =
+            field_element!("0142c45e5d743d10eae7ebb70f1526c65de7dbcdb65b322b6ddc36a812591e8f");
+        let leaves: Vec<_> = (0..size).map(FieldElement::from).collect();
+        let mut copy = leaves.clone();
+        bench.iter(|| {
+            copy.clone_from_slice(&leaves);
+            fft_cofactor_permuted(black_box(&cofactor), black_box(&mut copy))
+        })
+    });
+}
+
+criterion_group!(fft, fft_size);
@@ -0,0 +1,59 @@
+use criterion::{black_box, criterion_group, Criterion};
+use zkp_macros_decl::u256h;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+fn operands() -> (FieldElement, FieldElement) {
+    let a = FieldElement::from_montgomery(u256h!(
+        "03f9b5d66dd1e8ef70ead1370f862cc9c29e319a176e9f5b7f10c24c4de29f0f"
+    ));
+    let b = FieldElement::from_montgomery(u256h!(
+        "0560d4ae8cd8a5974b122d8cf65967e5c83911ed0c74f02899727b3f2e916e23"
+    ));
+    (a, b)
+}
+
+fn field_add(crit: &mut Criterion) {
+    let (a, b) = operands();
+    crit.bench_function("Field add", move |bench| {
+        bench.iter(|| black_box(black_box(&a) + black_box(&b)))
+    });
+}
+
+fn field_mul(crit: &mut Criterion) {
+    let (a, b) = operands();
+    crit.bench_function("Field mul", move |bench| {
+        bench.iter(|| black_box(black_box(&a) * black_box(&b)))
+    });
+}
+
+fn field_square(crit: &mut Criterion) {
+    let (a, _) = operands();
+    crit.bench_function("Field square", move |bench| {
+        bench.iter(|| black_box(black_box(&a).square()))
+    });
+}
+
+fn field_inv(crit: &mut Criterion) {
+    let (a, _) = operands();
+    crit.bench_function("Field inv", move |bench| {
+        bench.iter(|| black_box(black_box(&a).inv()))
+    });
+}
+
+fn field_pow(crit: &mut Criterion) {
+    let (a, b) = operands();
+    let exponent = U256::from(&b);
+    crit.bench_function("Field pow", move |bench| {
+        bench.iter(|| black_box(black_box(&a).pow(black_box(exponent.clone()))))
+    });
+}
+
+criterion_group!(
+    field,
+    field_add,
+    field_mul,
+    field_square,
+    field_inv,
+    field_pow
+);
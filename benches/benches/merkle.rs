@@ -0,0 +1,28 @@
+use criterion::{black_box, criterion_group, Criterion};
+use zkp_criterion_utils::log_size_bench;
+use zkp_hash::Blake3;
+use zkp_merkle_tree::Tree;
+use zkp_primefield::FieldElement;
+
+const SIZES: [usize; 5] = [1 << 10, 1 << 12, 1 << 14, 1 << 16, 1 << 18];
+
+fn merkle_tree_size(crit: &mut Criterion) {
+    log_size_bench(crit, "Merkle tree size", &SIZES, move |bench, size| {
+        let leaves: Vec<_> = (0..size).map(FieldElement::from).collect();
+        bench.iter(|| black_box(Tree::<_>::from_leaves(black_box(leaves.clone()))))
+    });
+}
+
+fn merkle_tree_blake3_size(crit: &mut Criterion) {
+    log_size_bench(
+        crit,
+        "Merkle tree Blake3 size",
+        &SIZES,
+        move |bench, size| {
+            let leaves: Vec<_> = (0..size).map(FieldElement::from).collect();
+            bench.iter(|| black_box(Tree::<_, Blake3>::from_leaves(black_box(leaves.clone()))))
+        },
+    );
+}
+
+criterion_group!(merkle, merkle_tree_size, merkle_tree_blake3_size);
@@ -0,0 +1,58 @@
+use criterion::{black_box, criterion_group, Criterion};
+use zkp_criterion_utils::log_size_bench;
+use zkp_macros_decl::field_element;
+use zkp_primefield::FieldElement;
+use zkp_stark::{
+    components::{fibonacci, range_check},
+    Provable, Verifiable,
+};
+use zkp_u256::U256;
+
+const SIZES: [usize; 4] = [1 << 8, 1 << 10, 1 << 12, 1 << 14];
+
+/// The Fibonacci claim with its last element at row `size - 1`.
+fn fibonacci_claim(size: usize, witness: &fibonacci::Witness) -> fibonacci::Claim {
+    let mut claim = fibonacci::Claim {
+        index: size - 1,
+        value: FieldElement::ZERO,
+    };
+    claim.value = claim.trace(witness)[(size - 1, 0)].clone();
+    claim
+}
+
+fn fibonacci_prove(crit: &mut Criterion) {
+    log_size_bench(crit, "Fibonacci prove", &SIZES, move |bench, size| {
+        let witness = fibonacci::Witness {
+            secret: field_element!("cafebabe"),
+        };
+        let claim = fibonacci_claim(size, &witness);
+        bench.iter(|| black_box(claim.prove(black_box(&witness)).unwrap()))
+    });
+}
+
+fn fibonacci_verify(crit: &mut Criterion) {
+    log_size_bench(crit, "Fibonacci verify", &SIZES, move |bench, size| {
+        let witness = fibonacci::Witness {
+            secret: field_element!("cafebabe"),
+        };
+        let claim = fibonacci_claim(size, &witness);
+        let proof = claim.prove(&witness).unwrap();
+        bench.iter(|| black_box(claim.verify(black_box(&proof))))
+    });
+}
+
+fn range_check_prove(crit: &mut Criterion) {
+    let claim = range_check::Claim {
+        bits:  250,
+        value: field_element!("0142c45e5d743d10eae7ebb70f1526c65de7dbcdb65b322b6ddc36a812591e8f"),
+    };
+    crit.bench_function("Range check prove", move |bench| {
+        bench.iter(|| black_box(claim.prove(()).unwrap()))
+    });
+}
+
+criterion_group! {
+   name = stark;
+   config = Criterion::default().sample_size(10);
+   targets = fibonacci_prove, fibonacci_verify, range_check_prove
+}
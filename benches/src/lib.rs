@@ -0,0 +1,5 @@
+//! Criterion benchmarks across the OpenZKP workspace.
+//!
+//! The benchmarks live in `benches/`, this library is intentionally empty.
+#![forbid(unsafe_code)]
+#![warn(clippy::all)]
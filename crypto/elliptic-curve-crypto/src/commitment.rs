@@ -0,0 +1,273 @@
+use std::prelude::v1::*;
+use tiny_keccak::sha3_256;
+use zkp_elliptic_curve::{double_scalar_mul, mul, Affine, Jacobian, BETA, ORDER};
+use zkp_merkle_tree::CommitmentScheme;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Deterministically map a message to a curve point.
+///
+/// Uses try-and-increment: the `x` coordinate is derived by hashing the
+/// domain, message and a counter until it is on the curve. Of the two
+/// possible `y` coordinates the one with even canonical representation is
+/// used. Nobody knows the discrete logarithm of the result with respect to
+/// any other point.
+///
+/// This is not constant time in the message, so it should only be used on
+/// public inputs.
+pub fn hash_to_curve(domain: &[u8], message: &[u8]) -> Affine {
+    for counter in 0_u64.. {
+        let digest = sha3_256(&[domain, message, &counter.to_be_bytes()].concat());
+        // Take 251 bits so the value is always less than the modulus
+        let x = FieldElement::from(U256::from_bytes_be(&digest) >> 5);
+        let y_squared = x.square() * &x + &x + BETA;
+        if let Some(y) = y_squared.square_root() {
+            let y = if U256::from(&y).is_even() { y } else { -&y };
+            return Affine::new(x, y);
+        }
+    }
+    unreachable!()
+}
+
+/// Pedersen commitments `value * G + blinding * H`.
+///
+/// The generators are derived with [`hash_to_curve`] from a domain separator,
+/// so nobody knows their relative discrete logarithm. Commitments are
+/// additively homomorphic: the sum of two commitments, using `Affine`
+/// addition, opens to the sum of the values and blindings.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PedersenCommitment {
+    value_generator:    Affine,
+    blinding_generator: Affine,
+}
+
+impl PedersenCommitment {
+    /// Derive a commitment scheme from a domain separator.
+    ///
+    /// Commitments with different domains are unrelated.
+    pub fn new(domain: &[u8]) -> Self {
+        Self {
+            value_generator:    hash_to_curve(domain, b"value"),
+            blinding_generator: hash_to_curve(domain, b"blinding"),
+        }
+    }
+
+    pub fn value_generator(&self) -> &Affine {
+        &self.value_generator
+    }
+
+    pub fn blinding_generator(&self) -> &Affine {
+        &self.blinding_generator
+    }
+
+    /// Commit to `value` using the secret random `blinding`.
+    ///
+    /// Both are taken modulo the curve order.
+    // TODO (SECURITY): The reduction is not constant time
+    pub fn commit(&self, value: &U256, blinding: &U256) -> Affine {
+        let value = Jacobian::mul_ct(&self.value_generator, &(value % ORDER));
+        let blinding = Jacobian::mul_ct(&self.blinding_generator, &(blinding % ORDER));
+        Affine::from(&(value + blinding))
+    }
+
+    /// Check that `commitment` opens to `value` with `blinding`.
+    pub fn verify_opening(&self, commitment: &Affine, value: &U256, blinding: &U256) -> bool {
+        let expected = double_scalar_mul(
+            &(value % ORDER),
+            &self.value_generator,
+            &(blinding % ORDER),
+            &self.blinding_generator,
+        );
+        *commitment == Affine::from(&expected)
+    }
+}
+
+impl Default for PedersenCommitment {
+    fn default() -> Self {
+        Self::new(b"OpenZKP Pedersen commitment")
+    }
+}
+
+/// Binding vector commitment `n L + sum_i v_i G_i` to a vector of length `n`.
+///
+/// The generators `L` and `G_i` are derived with [`hash_to_curve`]. The
+/// length term binds the length, so appending zeros changes the commitment.
+/// Values must be less than the curve order, larger values are rejected
+/// instead of reduced. The commitment is not hiding, and openings are not
+/// succinct: the proof contains the whole vector.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PedersenVectorCommitment;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpeningError {
+    IndexOutOfRange,
+    ValueOutOfRange,
+    LeafMismatch,
+    CommitmentMismatch,
+}
+
+impl PedersenVectorCommitment {
+    const DOMAIN: &'static [u8] = b"OpenZKP Pedersen vector commitment";
+
+    pub fn generator(index: usize) -> Affine {
+        hash_to_curve(Self::DOMAIN, &U256::from(index).to_bytes_be())
+    }
+
+    /// The generator `L` multiplied by the length of the vector.
+    pub fn length_generator() -> Affine {
+        hash_to_curve(Self::DOMAIN, b"length")
+    }
+
+    fn commit_values(values: &[U256]) -> Result<Affine, OpeningError> {
+        if values.iter().any(|value| *value >= ORDER) {
+            return Err(OpeningError::ValueOutOfRange);
+        }
+        let length = mul(&Self::length_generator(), &U256::from(values.len()));
+        let sum = values
+            .iter()
+            .enumerate()
+            .fold(length, |sum, (index, value)| {
+                sum + mul(&Self::generator(index), value)
+            });
+        Ok(Affine::from(&sum))
+    }
+}
+
+impl CommitmentScheme for PedersenVectorCommitment {
+    type Commitment = Affine;
+    type Decommitment = Vec<U256>;
+    type Error = OpeningError;
+    type Leaf = U256;
+    type Proof = Vec<U256>;
+    type Vector = Vec<U256>;
+
+    fn commit(vector: Vec<U256>) -> Result<(Affine, Vec<U256>), OpeningError> {
+        Ok((Self::commit_values(&vector)?, vector))
+    }
+
+    fn batch_open(vector: &Vec<U256>, indices: &[usize]) -> Result<Vec<U256>, OpeningError> {
+        if indices.iter().any(|&index| index >= vector.len()) {
+            return Err(OpeningError::IndexOutOfRange);
+        }
+        Ok(vector.clone())
+    }
+
+    fn verify(
+        commitment: &Affine,
+        leaves: &[(usize, U256)],
+        proof: &Vec<U256>,
+    ) -> Result<(), OpeningError> {
+        for (index, leaf) in leaves {
+            match proof.get(*index) {
+                None => return Err(OpeningError::IndexOutOfRange),
+                Some(value) if value != leaf => return Err(OpeningError::LeafMismatch),
+                Some(_) => {}
+            }
+        }
+        if Self::commit_values(proof)? != *commitment {
+            return Err(OpeningError::CommitmentMismatch);
+        }
+        Ok(())
+    }
+}
+
+// Quickcheck needs pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn test_generators() {
+        let scheme = PedersenCommitment::default();
+        assert!(scheme.value_generator().is_valid());
+        assert!(scheme.blinding_generator().is_valid());
+        assert_ne!(scheme.value_generator(), scheme.blinding_generator());
+        assert_ne!(scheme, PedersenCommitment::new(b"other"));
+        assert_eq!(scheme, PedersenCommitment::default());
+    }
+
+    #[quickcheck]
+    fn test_opening(value: U256, blinding: U256) -> bool {
+        let scheme = PedersenCommitment::default();
+        let commitment = scheme.commit(&value, &blinding);
+        scheme.verify_opening(&commitment, &value, &blinding)
+            && !scheme.verify_opening(&commitment, &value.wrapping_add(&U256::ONE), &blinding)
+    }
+
+    #[quickcheck]
+    fn test_homomorphic(a: U256, r_a: U256, b: U256, r_b: U256) -> bool {
+        let scheme = PedersenCommitment::default();
+        let (a, r_a, b, r_b) = (a % ORDER, r_a % ORDER, b % ORDER, r_b % ORDER);
+        let sum = scheme.commit(&a, &r_a) + scheme.commit(&b, &r_b);
+        scheme.verify_opening(&sum, &(a + b), &(r_a + r_b))
+    }
+
+    #[test]
+    fn test_vector_commitment() {
+        type Scheme = PedersenVectorCommitment;
+        let vector: Vec<U256> = (0..5_u64).map(|i| U256::from(i * i + 1)).collect();
+        let (commitment, decommitment) = Scheme::commit(vector.clone()).unwrap();
+        let proof = Scheme::batch_open(&decommitment, &[1, 3]).unwrap();
+        let leaves = [(1, vector[1].clone()), (3, vector[3].clone())];
+        assert_eq!(Scheme::verify(&commitment, &leaves, &proof), Ok(()));
+        assert_eq!(
+            Scheme::verify(&commitment, &[(1, vector[2].clone())], &proof),
+            Err(OpeningError::LeafMismatch)
+        );
+        assert_eq!(
+            Scheme::open(&decommitment, 5),
+            Err(OpeningError::IndexOutOfRange)
+        );
+
+        // A proof for a different vector does not match the commitment
+        let mut other = vector.clone();
+        other[0] = U256::from(42_u64);
+        assert_eq!(
+            Scheme::verify(&commitment, &leaves, &other),
+            Err(OpeningError::CommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn test_vector_commitment_value_range() {
+        type Scheme = PedersenVectorCommitment;
+        let vector: Vec<U256> = (0..3_u64).map(U256::from).collect();
+        let (commitment, decommitment) = Scheme::commit(vector.clone()).unwrap();
+        let proof = Scheme::open(&decommitment, 1).unwrap();
+
+        // The same value plus the order would otherwise open the commitment
+        let mut shifted = proof.clone();
+        shifted[1] += &ORDER;
+        assert_eq!(
+            Scheme::verify(&commitment, &[(1, shifted[1].clone())], &shifted),
+            Err(OpeningError::ValueOutOfRange)
+        );
+        let mut too_large = vector;
+        too_large[2] = ORDER;
+        assert_eq!(
+            Scheme::commit(too_large),
+            Err(OpeningError::ValueOutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_vector_commitment_length() {
+        type Scheme = PedersenVectorCommitment;
+        let vector: Vec<U256> = (1..4_u64).map(U256::from).collect();
+        let (commitment, _) = Scheme::commit(vector.clone()).unwrap();
+
+        // Trailing zeros do not contribute to the sum, but change the length
+        let mut padded = vector.clone();
+        padded.push(U256::ZERO);
+        let leaves = [(0, vector[0].clone())];
+        assert_eq!(
+            Scheme::verify(&commitment, &leaves, &padded),
+            Err(OpeningError::CommitmentMismatch)
+        );
+        assert_eq!(Scheme::verify(&commitment, &leaves, &vector), Ok(()));
+    }
+}
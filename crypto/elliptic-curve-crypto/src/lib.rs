@@ -37,14 +37,22 @@
 )]
 #![cfg_attr(feature = "std", warn(missing_debug_implementations,))]
 
+mod commitment;
+mod pedersen;
+mod pedersen_points;
+
 use lazy_static::*;
 use std::prelude::v1::*;
 use tiny_keccak::sha3_256;
 use zkp_elliptic_curve::{
-    base_mul, double_base_mul, window_table_affine, Affine, GENERATOR, ORDER,
+    double_base_mul, window_table_affine, Affine, Jacobian, GENERATOR, ORDER,
 };
 use zkp_u256::U256;
 
+pub use commitment::{hash_to_curve, OpeningError, PedersenCommitment, PedersenVectorCommitment};
+pub use pedersen::{pedersen_hash, pedersen_hash_chain, Pedersen};
+pub use pedersen_points::{PEDERSEN_POINTS, SHIFT_POINT};
+
 #[cfg(not(feature = "std"))]
 extern crate no_std_compat as std;
 
@@ -56,9 +64,9 @@ lazy_static! {
     };
 }
 
-// TODO (SECURITY): Use side-channel-resistant math
+// TODO (SECURITY): The reduction is not constant time
 pub fn private_to_public(private_key: &U256) -> Affine {
-    Affine::from(&base_mul(&*GENERATOR_TABLE, private_key % ORDER))
+    Affine::from(&Jacobian::mul_ct(&GENERATOR, &(private_key % ORDER)))
 }
 
 fn divmod(a: &U256, b: &U256) -> Option<U256> {
@@ -80,7 +88,7 @@ pub fn sign(msg_hash: &U256, private_key: &U256) -> (U256, U256) {
         if k == U256::ZERO || k.bits() > 251 {
             continue;
         }
-        match Affine::from(&base_mul(&*GENERATOR_TABLE, k.clone())) {
+        match Affine::from(&Jacobian::mul_ct(&GENERATOR, &k)) {
             Affine::Zero => continue,
             Affine::Point { x, .. } => {
                 let r = U256::from(x);
@@ -103,7 +111,9 @@ pub fn verify(msg_hash: &U256, r: &U256, w: &U256, public_key: &Affine) -> bool
     assert!(r.bits() <= 251);
     assert!(w != &U256::ZERO);
     assert!(w.bits() <= 251);
-    assert!(public_key.on_curve());
+    if *public_key == Affine::Zero || !public_key.is_valid() {
+        return false;
+    }
 
     match Affine::from(&double_base_mul(
         &*GENERATOR_TABLE,
@@ -172,6 +182,30 @@ mod tests {
         assert!(verify(&message_hash, &r, &w, &public_key));
     }
 
+    #[test]
+    fn test_verify_invalid_public_key() {
+        let message_hash =
+            u256h!("01e542e2da71b3f5d7b4e9d329b4d30ac0b5d6f266ebef7364bf61c39aac35d0");
+        let public_key = Affine::Point {
+            x: FieldElement::from(u256h!(
+                "077a3b314db07c45076d11f62b6f9e748a39790441823307743cf00d6597ea43"
+            )),
+            y: FieldElement::ONE,
+        };
+        let r = u256h!("01ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca");
+        let w = u256h!("07656a287e3be47c6e9a29482aecc10cd8b1ae4797b4b956a3573b425d1e66c9");
+        assert!(!verify(&message_hash, &r, &w, &public_key));
+    }
+
+    #[test]
+    fn test_verify_zero_public_key() {
+        let message_hash =
+            u256h!("01e542e2da71b3f5d7b4e9d329b4d30ac0b5d6f266ebef7364bf61c39aac35d0");
+        let r = u256h!("01ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca");
+        let w = u256h!("07656a287e3be47c6e9a29482aecc10cd8b1ae4797b4b956a3573b425d1e66c9");
+        assert!(!verify(&message_hash, &r, &w, &Affine::Zero));
+    }
+
     #[quickcheck]
     fn test_ecdsa(mut message_hash: U256, private_key: U256) -> bool {
         message_hash >>= 5; // Need message_hash <= 2**251
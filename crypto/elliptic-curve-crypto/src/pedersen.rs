@@ -0,0 +1,147 @@
+use crate::pedersen_points::{PEDERSEN_POINTS, SHIFT_POINT};
+use std::prelude::v1::*;
+use zkp_elliptic_curve::{Affine, Jacobian};
+use zkp_hash::{bytes_to_field_elements, Buffer, Hash, Hasher};
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Number of low bits of each input that go with the first point.
+const LOW_BITS: usize = 248;
+
+/// StarkWare's Pedersen hash of two field elements.
+///
+/// The hash is the `x` coordinate of
+///
+/// $$
+/// P_0 + a_{\text{low}} P_1 + a_{\text{high}} P_2 + b_{\text{low}} P_3 +
+/// b_{\text{high}} P_4
+/// $$
+///
+/// where $a_{\text{low}}$ are the low 248 bits of `a` and $a_{\text{high}}$
+/// the remaining four, likewise for `b`. $P_0$ is the [`SHIFT_POINT`] and
+/// $P_1, \dots, P_4$ are `PEDERSEN_POINTS[2..6]`. This matches
+/// `pedersen_hash` in StarkWare's `cairo-lang` and `starknet-crypto`.
+///
+/// # Panics
+///
+/// Panics in the (cryptographically unlikely) event that the sum is the point
+/// at infinity.
+pub fn pedersen_hash(a: &FieldElement, b: &FieldElement) -> FieldElement {
+    let mut point = Jacobian::from(&SHIFT_POINT);
+    add_element(&mut point, &U256::from(a), &PEDERSEN_POINTS[2..4]);
+    add_element(&mut point, &U256::from(b), &PEDERSEN_POINTS[4..6]);
+    match Affine::from(&point) {
+        Affine::Zero => panic!("Pedersen hash is the point at infinity"),
+        Affine::Point { x, .. } => x,
+    }
+}
+
+/// Pedersen hash of a sequence of field elements.
+///
+/// This matches StarkWare's `compute_hash_on_elements`: the elements are
+/// folded from the left starting with zero and the final result is hashed
+/// with the number of elements.
+pub fn pedersen_hash_chain(elements: &[FieldElement]) -> FieldElement {
+    let result = elements.iter().fold(FieldElement::ZERO, |acc, element| {
+        pedersen_hash(&acc, element)
+    });
+    pedersen_hash(&result, &FieldElement::from(elements.len()))
+}
+
+/// Merkle tree and channel hashing with [`pedersen_hash`].
+///
+/// Like [`zkp_hash::Poseidon`], hashes are interpreted as field elements in
+/// Montgomery form. Nodes are hashed with a single [`pedersen_hash`], longer
+/// inputs with [`pedersen_hash_chain`].
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Pedersen;
+
+impl Hasher for Pedersen {
+    type State = Buffer<Self>;
+
+    const ID: u8 = 3;
+
+    fn digest(parts: &[&[u8]]) -> [u8; 32] {
+        U256::from(pedersen_hash_chain(&bytes_to_field_elements(parts))).to_bytes_be()
+    }
+
+    fn hash_many(hashes: &[Hash]) -> Hash {
+        let elements: Vec<FieldElement> = hashes.iter().map(Hash::to_field_element).collect();
+        Hash::from(&pedersen_hash_chain(&elements))
+    }
+
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        Hash::from(&pedersen_hash(
+            &left.to_field_element(),
+            &right.to_field_element(),
+        ))
+    }
+}
+
+fn add_element(point: &mut Jacobian, value: &U256, points: &[Affine]) {
+    let high = value.clone() >> LOW_BITS;
+    let mut low = value.clone();
+    low -= &(high.clone() << LOW_BITS);
+    *point += &Jacobian::mul(&points[0], &low);
+    *point += &Jacobian::mul(&points[1], &high);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkp_macros_decl::field_element;
+
+    // Test vectors from StarkWare's `starknet-crypto`.
+    #[test]
+    fn pedersen_hash_test() {
+        assert_eq!(
+            pedersen_hash(
+                &field_element!("03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb"),
+                &field_element!("0208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a")
+            ),
+            field_element!("030e480bed5fe53fa909cc0f8c4d99b8f9f2c016be4c41e13a4848797979c662")
+        );
+        assert_eq!(
+            pedersen_hash(
+                &field_element!("058f580910a6ca59b28927c08fe6c43e2e303ca384badc365795fc645d479d45"),
+                &field_element!("078734f65a067be9bdb39de18434d71e79f7b6466a4b66bbd979ab9e7515fe0b")
+            ),
+            field_element!("068cc0b76cddd1dd4ed2301ada9b7c872b23875d5ff837b3a87993e0d9996b87")
+        );
+    }
+
+    #[test]
+    fn pedersen_hash_chain_test() {
+        let elements = [
+            FieldElement::from(1),
+            FieldElement::from(2),
+            FieldElement::from(3),
+        ];
+        let expected = pedersen_hash(
+            &pedersen_hash(
+                &pedersen_hash(
+                    &pedersen_hash(&FieldElement::ZERO, &elements[0]),
+                    &elements[1],
+                ),
+                &elements[2],
+            ),
+            &FieldElement::from(3),
+        );
+        assert_eq!(pedersen_hash_chain(&elements), expected);
+        assert_eq!(
+            pedersen_hash_chain(&[]),
+            pedersen_hash(&FieldElement::ZERO, &FieldElement::ZERO)
+        );
+    }
+
+    #[test]
+    fn pedersen_hasher_test() {
+        let a = FieldElement::from(1);
+        let b = FieldElement::from(2);
+        assert_eq!(
+            Pedersen::hash_node(&Hash::from(&a), &Hash::from(&b)),
+            Hash::from(&pedersen_hash(&a, &b))
+        );
+    }
+}
@@ -0,0 +1,37 @@
+#![warn(clippy::all)]
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use zkp_hash::{Blake2s, Blake3, Hash, Hasher, MaskedKeccak, Poseidon, Rescue};
+use zkp_macros_decl::hex;
+
+fn hash_node<H: Hasher>(crit: &mut Criterion, name: &str) {
+    let left = Hash::new(hex!(
+        "03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb"
+    ));
+    let right = Hash::new(hex!(
+        "0208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a"
+    ));
+    crit.bench_function(&format!("{} node", name), move |bench| {
+        bench.iter(|| black_box(H::hash_node(black_box(&left), black_box(&right))))
+    });
+}
+
+fn digest<H: Hasher>(crit: &mut Criterion, name: &str) {
+    let data = vec![0x5a_u8; 1024];
+    crit.bench_function(&format!("{} digest 1 KiB", name), move |bench| {
+        bench.iter(|| black_box(H::digest(&[black_box(&data)])))
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    hash_node::<MaskedKeccak>(c, "MaskedKeccak");
+    hash_node::<Blake2s>(c, "Blake2s");
+    hash_node::<Blake3>(c, "Blake3");
+    hash_node::<Poseidon>(c, "Poseidon");
+    hash_node::<Rescue>(c, "Rescue");
+    digest::<MaskedKeccak>(c, "MaskedKeccak");
+    digest::<Blake2s>(c, "Blake2s");
+    digest::<Blake3>(c, "Blake3");
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,208 @@
+use crate::{digest::DigestState, hash::Hash, hasher::Hasher};
+
+/// Blake2s-256 (RFC 7693).
+///
+/// Blake2s is considerably faster than Keccak in software and is well suited
+/// for deployments where proofs are not verified on Ethereum.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Blake2s {
+    state:  [u32; 8],
+    buffer: [u8; 64],
+    length: usize,
+    count:  u64,
+}
+
+const IV: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+const SIGMA: [[usize; 16]; 10] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],
+    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],
+    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],
+    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],
+    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],
+    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],
+    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],
+    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],
+    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
+];
+
+// The mixing function G.
+#[allow(clippy::many_single_char_names)]
+fn mix(v: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, x: u32, y: u32) {
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(12);
+    v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);
+    v[d] = (v[d] ^ v[a]).rotate_right(8);
+    v[c] = v[c].wrapping_add(v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(7);
+}
+
+impl Blake2s {
+    pub fn new() -> Self {
+        let mut state = IV;
+        // Parameter block: 32 byte digest, no key, fanout and depth one.
+        state[0] ^= 0x0101_0020;
+        Self {
+            state,
+            buffer: [0; 64],
+            length: 0,
+            count: 0,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        for &byte in input {
+            // The last block is compressed in `finalize`, so only compress a
+            // full buffer when more input arrives.
+            if self.length == 64 {
+                self.count += 64;
+                self.compress(false);
+                self.length = 0;
+            }
+            self.buffer[self.length] = byte;
+            self.length += 1;
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 32] {
+        // Length is at most 64
+        #[allow(clippy::cast_possible_truncation)]
+        let length = self.length as u64;
+        self.count += length;
+        for byte in self.buffer[self.length..].iter_mut() {
+            *byte = 0;
+        }
+        self.compress(true);
+        let mut result = [0; 32];
+        for (chunk, word) in result.chunks_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        result
+    }
+
+    // Truncation of the counter is intended
+    #[allow(clippy::cast_possible_truncation)]
+    fn compress(&mut self, last: bool) {
+        let mut message = [0_u32; 16];
+        for (word, chunk) in message.iter_mut().zip(self.buffer.chunks(4)) {
+            *word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        let mut v = [0_u32; 16];
+        v[..8].copy_from_slice(&self.state);
+        v[8..].copy_from_slice(&IV);
+        v[12] ^= self.count as u32;
+        v[13] ^= (self.count >> 32) as u32;
+        if last {
+            v[14] = !v[14];
+        }
+        for s in &SIGMA {
+            mix(&mut v, 0, 4, 8, 12, message[s[0]], message[s[1]]);
+            mix(&mut v, 1, 5, 9, 13, message[s[2]], message[s[3]]);
+            mix(&mut v, 2, 6, 10, 14, message[s[4]], message[s[5]]);
+            mix(&mut v, 3, 7, 11, 15, message[s[6]], message[s[7]]);
+            mix(&mut v, 0, 5, 10, 15, message[s[8]], message[s[9]]);
+            mix(&mut v, 1, 6, 11, 12, message[s[10]], message[s[11]]);
+            mix(&mut v, 2, 7, 8, 13, message[s[12]], message[s[13]]);
+            mix(&mut v, 3, 4, 9, 14, message[s[14]], message[s[15]]);
+        }
+        for i in 0..8 {
+            self.state[i] ^= v[i] ^ v[i + 8];
+        }
+    }
+}
+
+impl Default for Blake2s {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DigestState for Blake2s {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Self::update(self, input);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        Self::finalize(self)
+    }
+}
+
+impl Hasher for Blake2s {
+    type State = Self;
+
+    const ID: u8 = 2;
+
+    fn digest(parts: &[&[u8]]) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut hasher = Self::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize()
+    }
+
+    fn hash_many(hashes: &[Hash]) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut hasher = Self::new();
+        for hash in hashes {
+            hasher.update(hash.as_bytes());
+        }
+        Hash::new(hasher.finalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkp_macros_decl::hex;
+
+    #[test]
+    fn blake2s_test_vectors() {
+        assert_eq!(
+            Blake2s::digest(&[]),
+            hex!("69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9")
+        );
+        assert_eq!(
+            Blake2s::digest(&[b"abc"]),
+            hex!("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982")
+        );
+    }
+
+    #[test]
+    fn blake2s_multi_block() {
+        let input: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        let mut hasher = Blake2s::new();
+        for chunk in input.chunks(7) {
+            hasher.update(chunk);
+        }
+        assert_eq!(
+            hasher.finalize(),
+            Blake2s::digest(&[&input[..64], &input[64..]])
+        );
+        assert_ne!(
+            Blake2s::digest(&[&input[..64]]),
+            Blake2s::digest(&[&input[..65]])
+        );
+    }
+}
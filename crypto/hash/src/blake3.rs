@@ -0,0 +1,123 @@
+use crate::{digest::DigestState, hash::Hash, hasher::Hasher};
+
+/// Blake3 with 256 bit output, computed by the `blake3` crate.
+///
+/// Blake3 uses the Blake2s mixing function with fewer rounds and arranges
+/// the input in a binary tree of 1 KiB chunks. Merkle tree nodes are 64 bytes
+/// and take a single compression. The `blake3` crate selects the fastest
+/// SIMD implementation the CPU supports at runtime. As with the other hashes,
+/// Merkle trees hash their leaf layer in parallel.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Blake3;
+
+impl DigestState for ::blake3::Hasher {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        let _ = Self::update(self, input);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        *Self::finalize(&self).as_bytes()
+    }
+}
+
+impl Hasher for Blake3 {
+    type State = ::blake3::Hasher;
+
+    const ID: u8 = 4;
+
+    fn digest(parts: &[&[u8]]) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut hasher = ::blake3::Hasher::new();
+        for part in parts {
+            let _ = hasher.update(part);
+        }
+        *::blake3::Hasher::finalize(&hasher).as_bytes()
+    }
+
+    fn hash_many(hashes: &[Hash]) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut hasher = ::blake3::Hasher::new();
+        for hash in hashes {
+            let _ = hasher.update(hash.as_bytes());
+        }
+        Hash::new(*::blake3::Hasher::finalize(&hasher).as_bytes())
+    }
+
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut block = [0_u8; 64];
+        block[..32].copy_from_slice(left.as_bytes());
+        block[32..].copy_from_slice(right.as_bytes());
+        Hash::new(*::blake3::hash(&block).as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::prelude::v1::*;
+    use zkp_macros_decl::hex;
+
+    // Input of the official test vectors
+    fn input(length: usize) -> Vec<u8> {
+        (0..length).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn blake3_test_vectors() {
+        assert_eq!(
+            Blake3::digest(&[]),
+            hex!("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262")
+        );
+        assert_eq!(
+            Blake3::digest(&[b"abc"]),
+            hex!("6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85")
+        );
+        assert_eq!(
+            Blake3::digest(&[&input(1)]),
+            hex!("2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213")
+        );
+        assert_eq!(
+            Blake3::digest(&[&input(1024)]),
+            hex!("42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7")
+        );
+        assert_eq!(
+            Blake3::digest(&[&input(1025)]),
+            hex!("d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444")
+        );
+        assert_eq!(
+            Blake3::digest(&[&input(2048)]),
+            hex!("e776b6028c7cd22a4d0ba182a8bf62205d2ef576467e838ed6f2529b85fba24a")
+        );
+    }
+
+    #[test]
+    fn blake3_streaming() {
+        let input = input(5000);
+        let mut hasher = <Blake3 as Hasher>::State::new();
+        for chunk in input.chunks(100) {
+            DigestState::update(&mut hasher, chunk);
+        }
+        assert_eq!(DigestState::finalize(hasher), Blake3::digest(&[&input]));
+    }
+
+    #[test]
+    fn blake3_hash_node() {
+        let left = Hash::new([1; 32]);
+        let right = Hash::new([2; 32]);
+        assert_eq!(
+            Blake3::hash_node(&left, &right),
+            Blake3::hash_many(&[left, right])
+        );
+    }
+}
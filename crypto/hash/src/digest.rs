@@ -0,0 +1,142 @@
+use crate::hasher::Hasher;
+use std::{marker::PhantomData, prelude::v1::*};
+
+/// Streaming state of a [`Hasher::digest`], see [`Hasher::State`].
+///
+/// [`Hasher::digest`]: trait.Hasher.html#tymethod.digest
+/// [`Hasher::State`]: trait.Hasher.html#associatedtype.State
+pub trait DigestState: Clone {
+    fn new() -> Self;
+
+    fn update(&mut self, input: &[u8]);
+
+    fn finalize(self) -> [u8; 32];
+}
+
+/// State that keeps all input until [`finalize`].
+///
+/// Hashes over field elements encode the total length of the input first,
+/// see [`bytes_to_field_elements`], so they can not stream it.
+///
+/// [`finalize`]: trait.DigestState.html#tymethod.finalize
+/// [`bytes_to_field_elements`]: fn.bytes_to_field_elements.html
+pub struct Buffer<H: Hasher> {
+    buffer: Vec<u8>,
+    hasher: PhantomData<H>,
+}
+
+impl<H: Hasher> Clone for Buffer<H> {
+    fn clone(&self) -> Self {
+        Self {
+            buffer: self.buffer.clone(),
+            hasher: PhantomData,
+        }
+    }
+}
+
+impl<H: Hasher> DigestState for Buffer<H> {
+    fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            hasher: PhantomData,
+        }
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        self.buffer.extend_from_slice(input);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        H::digest(&[&self.buffer])
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: Hasher> std::fmt::Debug for Buffer<H> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "Buffer(len = {})", self.buffer.len())
+    }
+}
+
+/// Incremental form of [`Hasher::digest`].
+///
+/// Byte oriented hashes process the input as it arrives. Hashes over field
+/// elements buffer it, see [`Buffer`].
+///
+/// [`Hasher::digest`]: trait.Hasher.html#tymethod.digest
+/// [`Buffer`]: struct.Buffer.html
+pub struct Digest<H: Hasher> {
+    state: H::State,
+}
+
+impl<H: Hasher> Digest<H> {
+    pub fn new() -> Self {
+        Self {
+            state: H::State::new(),
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.state.update(input);
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        self.state.finalize()
+    }
+}
+
+impl<H: Hasher> Clone for Digest<H> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<H: Hasher> Default for Digest<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The states of the underlying hashes are not all printable.
+#[cfg(feature = "std")]
+impl<H: Hasher> std::fmt::Debug for Digest<H> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "Digest(id = {})", H::ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "blake3")]
+    use crate::Blake3;
+    use crate::{Blake2s, MaskedKeccak, Poseidon, Rescue};
+
+    fn check<H: Hasher>() {
+        let mut digest = Digest::<H>::new();
+        digest.update(b"split ");
+        digest.update(b"");
+        digest.update(b"input");
+        assert_eq!(digest.finalize(), H::digest(&[b"split input"]));
+
+        // Input across several blocks
+        let input: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut digest = Digest::<H>::new();
+        for chunk in input.chunks(77) {
+            digest.update(chunk);
+        }
+        assert_eq!(digest.finalize(), H::digest(&[&input]));
+    }
+
+    #[test]
+    fn digest_test() {
+        check::<MaskedKeccak>();
+        check::<Blake2s>();
+        #[cfg(feature = "blake3")]
+        check::<Blake3>();
+        check::<Poseidon>();
+        check::<Rescue>();
+    }
+}
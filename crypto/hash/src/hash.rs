@@ -1,10 +1,12 @@
 #[cfg(feature = "std")]
 use hex;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
 
 #[cfg(feature = "std")]
 use std::fmt;
 
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Hash([u8; 32]);
 
 impl Hash {
@@ -15,6 +17,22 @@ impl Hash {
     pub fn as_bytes(&self) -> &[u8] {
         &self.0
     }
+
+    /// Interpret the hash as a field element in Montgomery form.
+    ///
+    /// This inverts `Hashable` for field elements. Other values are reduced
+    /// modulo the field order.
+    pub fn to_field_element(&self) -> FieldElement {
+        let mut value = U256::from_bytes_be(&self.0);
+        value %= &FieldElement::MODULUS;
+        FieldElement::from_montgomery(value)
+    }
+}
+
+impl From<&FieldElement> for Hash {
+    fn from(element: &FieldElement) -> Self {
+        Self::new(element.as_montgomery().to_bytes_be())
+    }
 }
 
 #[cfg(feature = "std")]
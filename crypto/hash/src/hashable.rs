@@ -1,10 +1,34 @@
-use crate::{hash::Hash, masked_keccak::MaskedKeccak};
+use crate::{hash::Hash, hasher::Hasher, masked_keccak::MaskedKeccak};
 use std::prelude::v1::*;
 use zkp_primefield::FieldElement;
 use zkp_u256::U256;
 
+/// Values that can be committed to, for example as Merkle tree leaves.
+///
+/// Atomic values are encoded as a single 32 byte word, which is their hash:
+/// integers and [`U256`] big-endian, [`FieldElement`]s in Montgomery form.
+/// Compound values, slices and tuples, hash the sequence of the hashes of
+/// their components with [`Hasher::hash_many`], except for a single component
+/// which is its own hash. A record type can implement the trait by hashing
+/// a tuple of its fields, for example
+/// `(self.index, &self.value, self.flags).hash_with::<H>()`.
+///
+/// The number of components is not part of the encoding. Leaves of one tree
+/// should have the same shape, but different trees can use different leaf
+/// sizes.
+///
+/// [`U256`]: zkp_u256::U256
+/// [`FieldElement`]: zkp_primefield::FieldElement
+/// [`Hasher::hash_many`]: crate::Hasher::hash_many
 pub trait Hashable {
     fn hash(&self) -> Hash;
+
+    /// Hash using `H` to combine the hashes of compound values.
+    ///
+    /// Atomic values are their own hash regardless of the hasher.
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        self.hash()
+    }
 }
 
 impl Hashable for Hash {
@@ -24,6 +48,24 @@ impl Hashable for U256 {
     }
 }
 
+impl Hashable for u64 {
+    fn hash(&self) -> Hash {
+        U256::from(*self).hash()
+    }
+}
+
+impl Hashable for usize {
+    fn hash(&self) -> Hash {
+        U256::from(*self).hash()
+    }
+}
+
+impl Hashable for bool {
+    fn hash(&self) -> Hash {
+        U256::from(u64::from(*self)).hash()
+    }
+}
+
 impl Hashable for FieldElement {
     fn hash(&self) -> Hash {
         // We hash as U256 in Montgomery form (which is identity-hashed)
@@ -35,20 +77,25 @@ impl<T: Hashable> Hashable for &T {
     fn hash(&self) -> Hash {
         (*self).hash()
     }
+
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        (*self).hash_with::<H>()
+    }
 }
 
 impl<T: Hashable> Hashable for &[T] {
     fn hash(&self) -> Hash {
+        self.hash_with::<MaskedKeccak>()
+    }
+
+    fn hash_with<H: Hasher>(&self) -> Hash {
         if self.len() == 1 {
             // For a single element, return its hash.
-            self[0].hash()
+            self[0].hash_with::<H>()
         } else {
-            // Concatenate the element hashes and hash the result.
-            let mut hasher = MaskedKeccak::new();
-            for value in self.iter() {
-                hasher.update(value.hash().as_bytes());
-            }
-            hasher.hash()
+            // Combine the element hashes.
+            let hashes: Vec<Hash> = self.iter().map(Hashable::hash_with::<H>).collect();
+            H::hash_many(&hashes)
         }
     }
 }
@@ -57,4 +104,51 @@ impl<T: Hashable> Hashable for Vec<T> {
     fn hash(&self) -> Hash {
         self.as_slice().hash()
     }
+
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        self.as_slice().hash_with::<H>()
+    }
+}
+
+macro_rules! hashable_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Hashable),+> Hashable for ($($name,)+) {
+            fn hash(&self) -> Hash {
+                self.hash_with::<MaskedKeccak>()
+            }
+
+            fn hash_with<H: Hasher>(&self) -> Hash {
+                H::hash_many(&[$(self.$index.hash_with::<H>()),+])
+            }
+        }
+    };
+}
+
+hashable_tuple!(A 0, B 1);
+hashable_tuple!(A 0, B 1, C 2);
+hashable_tuple!(A 0, B 1, C 2, D 3);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Blake2s;
+
+    #[test]
+    fn test_tuple_hash() {
+        let a = FieldElement::from(3_usize);
+        let b = FieldElement::from(5_usize);
+        assert_eq!(
+            (a.clone(), b.clone()).hash(),
+            vec![a.clone(), b.clone()].hash()
+        );
+        assert_eq!(
+            (a.clone(), b.clone()).hash_with::<Blake2s>(),
+            vec![a, b].hash_with::<Blake2s>()
+        );
+        assert_eq!(
+            (7_usize, true).hash(),
+            vec![U256::from(7_u64), U256::ONE].hash()
+        );
+        assert_ne!((7_usize, true).hash(), (7_usize, false).hash());
+    }
 }
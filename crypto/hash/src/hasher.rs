@@ -0,0 +1,143 @@
+use crate::{digest::DigestState, hash::Hash, masked_keccak::MaskedKeccak};
+use std::prelude::v1::*;
+use tiny_keccak::Keccak;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Hash function used for commitments and the proof channel.
+///
+/// Implementations provide a byte oriented digest, used to derive the random
+/// challenges of the channel, and a way to combine hashes, used in Merkle
+/// trees. The identifier is recorded in proofs so the verifier knows which
+/// hash was used. The built-in identifiers are
+///
+/// | Hash                        | `ID` |
+/// |-----------------------------|------|
+/// | [`MaskedKeccak`]            | 0    |
+/// | [`Poseidon`](crate::Poseidon) | 1    |
+/// | [`Blake2s`](crate::Blake2s)   | 2    |
+/// | Pedersen                    | 3    |
+/// | Blake3 (`blake3` feature)   | 4    |
+/// | [`Rescue`](crate::Rescue)     | 5    |
+pub trait Hasher {
+    /// Identifier of the hash function.
+    const ID: u8;
+
+    /// Input block size in bytes, used by [`Hmac`](crate::Hmac).
+    ///
+    /// Hashes over field elements have no byte blocks and use the default.
+    const BLOCK_SIZE: usize = 64;
+
+    /// Streaming state for [`Digest`](crate::Digest).
+    ///
+    /// Hashes over field elements use [`Buffer`](crate::Buffer).
+    type State: DigestState;
+
+    /// 256-bit digest of the concatenation of `parts`.
+    fn digest(parts: &[&[u8]]) -> [u8; 32];
+
+    /// Hash of a sequence of hashes.
+    fn hash_many(hashes: &[Hash]) -> Hash;
+
+    /// Hash of a Merkle tree node from its children.
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        Self::hash_many(&[left.clone(), right.clone()])
+    }
+}
+
+/// Encode bytes as field elements for algebraic hashes.
+///
+/// The first element is the number of bytes, followed by the bytes in
+/// big-endian chunks of 31 bytes. The last chunk may be shorter.
+pub fn bytes_to_field_elements(parts: &[&[u8]]) -> Vec<FieldElement> {
+    const CHUNK: usize = 31;
+    let bytes: Vec<u8> = parts.iter().flat_map(|part| part.iter().cloned()).collect();
+    let mut result = Vec::with_capacity(1 + (bytes.len() + CHUNK - 1) / CHUNK);
+    result.push(FieldElement::from(bytes.len()));
+    for chunk in bytes.chunks(CHUNK) {
+        let mut padded = [0_u8; 32];
+        padded[32 - chunk.len()..].copy_from_slice(chunk);
+        result.push(FieldElement::from(U256::from_bytes_be(&padded)));
+    }
+    result
+}
+
+/// Merkle tree hashes are truncated to 160 bits as in StarkWare's EVM
+/// verifier. The channel digest is the full Keccak-256.
+impl Hasher for MaskedKeccak {
+    type State = Keccak;
+
+    // The rate of Keccak-256
+    const BLOCK_SIZE: usize = 136;
+    const ID: u8 = 0;
+
+    fn digest(parts: &[&[u8]]) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut keccak = Keccak::new_keccak256();
+        for part in parts {
+            keccak.update(part);
+        }
+        let mut result = [0; 32];
+        keccak.finalize(&mut result);
+        result
+    }
+
+    fn hash_many(hashes: &[Hash]) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut hasher = Self::new();
+        for hash in hashes {
+            hasher.update(hash.as_bytes());
+        }
+        hasher.hash()
+    }
+
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut hasher = Self::new();
+        hasher.update(left.as_bytes());
+        hasher.update(right.as_bytes());
+        hasher.hash()
+    }
+}
+
+impl DigestState for Keccak {
+    fn new() -> Self {
+        Self::new_keccak256()
+    }
+
+    fn update(&mut self, input: &[u8]) {
+        Self::update(self, input);
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let mut result = [0; 32];
+        Self::finalize(self, &mut result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_to_field_elements_test() {
+        assert_eq!(bytes_to_field_elements(&[]), vec![FieldElement::ZERO]);
+        let elements = bytes_to_field_elements(&[&[1; 20], &[2; 20]]);
+        assert_eq!(elements.len(), 3);
+        assert_eq!(elements[0], FieldElement::from(40));
+        assert_eq!(bytes_to_field_elements(&[&[0, 1]]), vec![
+            FieldElement::from(2),
+            FieldElement::from(1)
+        ]);
+        assert_ne!(
+            bytes_to_field_elements(&[&[1]]),
+            bytes_to_field_elements(&[&[0, 1]])
+        );
+    }
+}
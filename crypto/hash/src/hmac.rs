@@ -0,0 +1,135 @@
+//! HMAC (RFC 2104) and HKDF (RFC 5869) over a [`Hasher`].
+//!
+//! The output length of the hashes is 32 bytes and the block size is
+//! [`Hasher::BLOCK_SIZE`]. With [`Blake2s`](crate::Blake2s) the results match
+//! the usual HMAC-BLAKE2s and HKDF-BLAKE2s.
+//!
+//! [`Hasher`]: crate::Hasher
+//! [`Hasher::BLOCK_SIZE`]: crate::Hasher::BLOCK_SIZE
+use crate::{digest::Digest, hasher::Hasher};
+use std::prelude::v1::*;
+
+/// Incremental HMAC with the hash `H`.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Hmac<H: Hasher> {
+    inner:     Digest<H>,
+    outer_pad: Vec<u8>,
+}
+
+impl<H: Hasher> Hmac<H> {
+    pub fn new(key: &[u8]) -> Self {
+        // Keys longer than a block are hashed first
+        let mut block = vec![0_u8; H::BLOCK_SIZE];
+        if key.len() > H::BLOCK_SIZE {
+            block[..32].copy_from_slice(&H::digest(&[key]));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let inner_pad: Vec<u8> = block.iter().map(|byte| byte ^ 0x36).collect();
+        let outer_pad: Vec<u8> = block.iter().map(|byte| byte ^ 0x5c).collect();
+        let mut inner = Digest::new();
+        inner.update(&inner_pad);
+        Self { inner, outer_pad }
+    }
+
+    pub fn update(&mut self, input: &[u8]) {
+        self.inner.update(input);
+    }
+
+    pub fn finalize(self) -> [u8; 32] {
+        H::digest(&[&self.outer_pad, &self.inner.finalize()])
+    }
+}
+
+/// HMAC of the concatenation of `parts` with `key`.
+pub fn hmac<H: Hasher>(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut mac = Hmac::<H>::new(key);
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize()
+}
+
+/// Pseudorandom key from the input key material `ikm`.
+///
+/// An empty `salt` is the same as 32 zero bytes.
+pub fn hkdf_extract<H: Hasher>(salt: &[u8], ikm: &[u8]) -> [u8; 32] {
+    hmac::<H>(salt, &[ikm])
+}
+
+/// Fill `output` with key material derived from the pseudorandom key `prk`
+/// for the purpose `info`.
+///
+/// # Panics
+///
+/// Panics if `output` is longer than 255 hashes.
+pub fn hkdf_expand<H: Hasher>(prk: &[u8], info: &[u8], output: &mut [u8]) {
+    assert!(output.len() <= 255 * 32, "Output too long for HKDF");
+    let mut previous: Vec<u8> = Vec::new();
+    for (counter, chunk) in (1..=255_u8).zip(output.chunks_mut(32)) {
+        let block = hmac::<H>(prk, &[&previous, info, &[counter]]);
+        chunk.copy_from_slice(&block[..chunk.len()]);
+        previous = block.to_vec();
+    }
+}
+
+/// Extract and expand in one step.
+///
+/// # Panics
+///
+/// Panics if `output` is longer than 255 hashes.
+pub fn hkdf<H: Hasher>(salt: &[u8], ikm: &[u8], info: &[u8], output: &mut [u8]) {
+    hkdf_expand::<H>(&hkdf_extract::<H>(salt, ikm), info, output);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Blake2s, MaskedKeccak};
+    use zkp_macros_decl::hex;
+
+    #[test]
+    fn hmac_blake2s_test() {
+        assert_eq!(
+            hmac::<Blake2s>(b"key", &[
+                b"The quick brown fox ",
+                b"jumps over the lazy dog"
+            ]),
+            hex!("f93215bb90d4af4c3061cd932fb169fb8bb8a91d0b4022baea1271e1323cd9a0")
+        );
+        assert_eq!(
+            hmac::<Blake2s>(&[b'k'; 100], &[b"message"]),
+            hex!("a13ba17e4e17ff37d880c23fa2b68ceedd52ae33eeab90f65020ba2911b2be93")
+        );
+    }
+
+    // The inputs of test case 1 of RFC 5869
+    #[test]
+    fn hkdf_blake2s_test() {
+        let ikm = [0x0b_u8; 22];
+        let salt = hex!("000102030405060708090a0b0c");
+        let info = hex!("f0f1f2f3f4f5f6f7f8f9");
+        assert_eq!(
+            hkdf_extract::<Blake2s>(&salt, &ikm),
+            hex!("57e878130679f9ea85900980b52df2643d043b82f290eb7dd62175dbb04cca4e")
+        );
+        let mut okm = [0_u8; 42];
+        hkdf::<Blake2s>(&salt, &ikm, &info, &mut okm);
+        assert_eq!(
+            okm[..],
+            hex!(
+                "1472c31f2ff768c71b19f8803683ee3b13c1a5fb3ea59c0c3bf0d44a4a40dcd4329d9cd85bbe35a1b3e7"
+            )[..]
+        );
+    }
+
+    #[test]
+    fn hkdf_info_test() {
+        let mut a = [0_u8; 32];
+        let mut b = [0_u8; 32];
+        hkdf::<MaskedKeccak>(b"salt", b"secret", b"nonce", &mut a);
+        hkdf::<MaskedKeccak>(b"salt", b"secret", b"key", &mut b);
+        assert_ne!(a, b);
+    }
+}
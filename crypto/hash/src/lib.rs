@@ -40,8 +40,30 @@
 #[cfg(not(feature = "std"))]
 extern crate no_std_compat as std;
 
+mod blake2s;
+#[cfg(feature = "blake3")]
+mod blake3;
+mod digest;
 mod hash;
 mod hashable;
+mod hasher;
+pub mod hmac;
 mod masked_keccak;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+mod poseidon;
+mod rescue;
 
-pub use crate::{hash::Hash, hashable::Hashable, masked_keccak::MaskedKeccak};
+#[cfg(feature = "blake3")]
+pub use crate::blake3::Blake3;
+pub use crate::{
+    blake2s::Blake2s,
+    digest::{Buffer, Digest, DigestState},
+    hash::Hash,
+    hashable::Hashable,
+    hasher::{bytes_to_field_elements, Hasher},
+    hmac::Hmac,
+    masked_keccak::MaskedKeccak,
+    poseidon::Poseidon,
+    rescue::Rescue,
+};
@@ -0,0 +1,18 @@
+//! Hash counters, enabled with the `metrics` feature.
+//!
+//! The counter is global and shared by all threads. It counts calls to the
+//! [`Hasher`](../trait.Hasher.html) functions, values that are their own
+//! hash are not counted.
+use core::sync::atomic::{AtomicU64, Ordering};
+
+static HASHES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of hashes computed since the start of the process.
+pub fn hash_count() -> u64 {
+    HASHES.load(Ordering::Relaxed)
+}
+
+#[inline(always)]
+pub(crate) fn record_hash() {
+    let _ = HASHES.fetch_add(1, Ordering::Relaxed);
+}
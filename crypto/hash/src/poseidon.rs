@@ -0,0 +1,245 @@
+use crate::{
+    digest::Buffer,
+    hash::Hash,
+    hasher::{bytes_to_field_elements, Hasher},
+};
+use lazy_static::lazy_static;
+use std::prelude::v1::*;
+use tiny_keccak::Keccak;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Poseidon permutation over the Stark field.
+///
+/// The permutation has a state of three field elements (rate two, capacity
+/// one) and uses the $x^3$ S-box, which is a permutation because
+/// $\gcd(3, p - 1) = 1$. There are eight full rounds split around 83 partial
+/// rounds. The round constants are derived by hashing a counter with Keccak,
+/// the MDS matrix is
+///
+/// $$
+/// \begin{bmatrix} 3 & 1 & 1 \\\\ 1 & -1 & 1 \\\\ 1 & 1 & -2 \end{bmatrix}
+/// $$
+///
+/// Being an algebraic hash, Poseidon is cheap to evaluate inside a STARK,
+/// which makes it the hash of choice for proofs that are verified
+/// recursively.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Poseidon {
+    state:  [FieldElement; Poseidon::WIDTH],
+    offset: usize,
+}
+
+lazy_static! {
+    static ref ROUND_CONSTANTS: Vec<[FieldElement; Poseidon::WIDTH]> = (0..Poseidon::ROUNDS)
+        .map(|round| {
+            let index = round * Poseidon::WIDTH;
+            [
+                round_constant(b"Poseidon", index),
+                round_constant(b"Poseidon", index + 1),
+                round_constant(b"Poseidon", index + 2),
+            ]
+        })
+        .collect();
+}
+
+// Keccak of `tag` and the big-endian `index`, reduced to a field element.
+pub(crate) fn round_constant(tag: &[u8], index: usize) -> FieldElement {
+    let mut keccak = Keccak::new_keccak256();
+    keccak.update(tag);
+    keccak.update(&(index as u64).to_be_bytes());
+    let mut bytes = [0; 32];
+    keccak.finalize(&mut bytes);
+    let mut value = U256::from_bytes_be(&bytes);
+    value %= &FieldElement::MODULUS;
+    FieldElement::from(value)
+}
+
+impl Poseidon {
+    pub const FULL_ROUNDS: usize = 8;
+    pub const PARTIAL_ROUNDS: usize = 83;
+    const RATE: usize = 2;
+    pub const ROUNDS: usize = Self::FULL_ROUNDS + Self::PARTIAL_ROUNDS;
+    pub const WIDTH: usize = 3;
+
+    pub fn new() -> Self {
+        Self {
+            state:  [FieldElement::ZERO, FieldElement::ZERO, FieldElement::ZERO],
+            offset: 0,
+        }
+    }
+
+    /// The round constants, added at the start of each round.
+    pub fn round_constants() -> &'static [[FieldElement; Self::WIDTH]] {
+        &ROUND_CONSTANTS
+    }
+
+    /// Whether `round` applies the S-box to the full state.
+    ///
+    /// The other rounds only apply it to the last element.
+    pub fn is_full_round(round: usize) -> bool {
+        let half_full = Self::FULL_ROUNDS / 2;
+        round < half_full || round >= half_full + Self::PARTIAL_ROUNDS
+    }
+
+    /// Multiply `state` by the MDS matrix.
+    pub fn mix(state: &mut [FieldElement; Self::WIDTH]) {
+        mix(state)
+    }
+
+    /// Apply the Poseidon permutation to `state`.
+    pub fn permute(state: &mut [FieldElement; Self::WIDTH]) {
+        for (round, constants) in ROUND_CONSTANTS.iter().enumerate() {
+            for (element, constant) in state.iter_mut().zip(constants.iter()) {
+                *element += constant;
+            }
+            if Self::is_full_round(round) {
+                for element in state.iter_mut() {
+                    *element = element.square() * &*element;
+                }
+            } else {
+                state[Self::WIDTH - 1] = state[Self::WIDTH - 1].square() * &state[Self::WIDTH - 1];
+            }
+            mix(state);
+        }
+    }
+
+    /// Hash of two field elements.
+    ///
+    /// Permutes `[a, b, 2]` and returns the first element. The constant in the
+    /// capacity separates this from [`Poseidon::hash_elements`].
+    pub fn hash_pair(a: &FieldElement, b: &FieldElement) -> FieldElement {
+        let mut state = [a.clone(), b.clone(), FieldElement::from(2)];
+        Self::permute(&mut state);
+        state[0].clone()
+    }
+
+    /// Sponge hash of a sequence of field elements.
+    pub fn hash_elements(elements: &[FieldElement]) -> FieldElement {
+        let mut sponge = Self::new();
+        for element in elements {
+            sponge.update(element);
+        }
+        sponge.finalize()
+    }
+
+    /// Absorb a field element into the sponge.
+    pub fn update(&mut self, element: &FieldElement) {
+        self.state[self.offset] += element;
+        self.offset += 1;
+        if self.offset == Self::RATE {
+            Self::permute(&mut self.state);
+            self.offset = 0;
+        }
+    }
+
+    /// Pad the input with a one and squeeze out the first element.
+    pub fn finalize(mut self) -> FieldElement {
+        self.update(&FieldElement::ONE);
+        if self.offset != 0 {
+            Self::permute(&mut self.state);
+        }
+        self.state[0].clone()
+    }
+}
+
+impl Default for Poseidon {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Multiply by the MDS matrix.
+pub(crate) fn mix(state: &mut [FieldElement; Poseidon::WIDTH]) {
+    let [a, b, c] = state.clone();
+    let sum = a.clone() + &b + &c;
+    state[0] = sum.clone() + a.double();
+    state[1] = sum.clone() - b.double();
+    state[2] = sum - c.triple();
+}
+
+/// Merkle tree hashing with Poseidon.
+///
+/// Hashes are interpreted as field elements in Montgomery form, which is
+/// how `Hashable` encodes field elements. Results are encoded the same way.
+///
+/// The channel digest hashes the bytes packed into field elements and returns
+/// the result as a big-endian integer.
+impl Hasher for Poseidon {
+    type State = Buffer<Self>;
+
+    const ID: u8 = 1;
+
+    fn digest(parts: &[&[u8]]) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        U256::from(Self::hash_elements(&bytes_to_field_elements(parts))).to_bytes_be()
+    }
+
+    fn hash_many(hashes: &[Hash]) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let elements: Vec<FieldElement> = hashes.iter().map(Hash::to_field_element).collect();
+        Hash::from(&Self::hash_elements(&elements))
+    }
+
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        Hash::from(&Self::hash_pair(
+            &left.to_field_element(),
+            &right.to_field_element(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Hashable;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn mds_test() {
+        let mut state = [
+            FieldElement::from(1),
+            FieldElement::from(2),
+            FieldElement::from(3),
+        ];
+        mix(&mut state);
+        assert_eq!(state, [
+            FieldElement::from(8),
+            FieldElement::from(2),
+            FieldElement::from(-3),
+        ]);
+    }
+
+    #[test]
+    fn sponge_test() {
+        let elements = [
+            FieldElement::from(1),
+            FieldElement::from(2),
+            FieldElement::from(3),
+        ];
+        let mut state = [
+            FieldElement::from(1),
+            FieldElement::from(2),
+            FieldElement::ZERO,
+        ];
+        Poseidon::permute(&mut state);
+        state[0] += &FieldElement::from(3);
+        state[1] += &FieldElement::ONE;
+        Poseidon::permute(&mut state);
+        assert_eq!(Poseidon::hash_elements(&elements), state[0]);
+        assert_ne!(
+            Poseidon::hash_elements(&elements[..2]),
+            Poseidon::hash_pair(&elements[0], &elements[1])
+        );
+    }
+
+    #[quickcheck]
+    fn hash_node_matches_pair(a: FieldElement, b: FieldElement) -> bool {
+        Poseidon::hash_node(&a.hash(), &b.hash()) == Poseidon::hash_pair(&a, &b).hash()
+    }
+}
@@ -0,0 +1,222 @@
+use crate::{
+    digest::Buffer,
+    hash::Hash,
+    hasher::{bytes_to_field_elements, Hasher},
+    poseidon::{mix, round_constant},
+};
+use lazy_static::lazy_static;
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Rescue-Prime permutation over the Stark field.
+///
+/// The permutation has a state of three field elements (rate two, capacity
+/// one). Each of the eighteen rounds applies the $x^3$ S-box, the MDS matrix
+/// and round constants, followed by the inverse S-box $x^{1/3}$, the MDS
+/// matrix and round constants again. The MDS matrix is the one of
+/// [`Poseidon`](crate::Poseidon) and the round constants are derived the same
+/// way, so this instance is not interoperable with other Rescue-Prime
+/// implementations. The number of rounds is a conservative choice above the
+/// Gröbner basis estimate in the Rescue-Prime paper.
+///
+/// Every round is a low degree relation between consecutive states, so a
+/// permutation takes a single trace row per round to prove. See
+/// `zkp_stark::components::rescue`.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Rescue {
+    state:  [FieldElement; Rescue::WIDTH],
+    offset: usize,
+}
+
+// The inverse of three modulo `p - 1`.
+const ALPHA_INV: U256 =
+    U256::from_hex("0x0555555555555560aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaab");
+
+lazy_static! {
+    static ref ROUND_CONSTANTS: Vec<[FieldElement; Rescue::WIDTH]> = (0..2 * Rescue::ROUNDS)
+        .map(|step| {
+            let index = step * Rescue::WIDTH;
+            [
+                round_constant(b"Rescue", index),
+                round_constant(b"Rescue", index + 1),
+                round_constant(b"Rescue", index + 2),
+            ]
+        })
+        .collect();
+}
+
+impl Rescue {
+    const RATE: usize = 2;
+    pub const ROUNDS: usize = 18;
+    pub const WIDTH: usize = 3;
+
+    pub fn new() -> Self {
+        Self {
+            state:  [FieldElement::ZERO, FieldElement::ZERO, FieldElement::ZERO],
+            offset: 0,
+        }
+    }
+
+    /// The round constants, two per round.
+    ///
+    /// Round `i` adds constants `2 i` after the S-box and `2 i + 1` after the
+    /// inverse S-box.
+    pub fn round_constants() -> &'static [[FieldElement; Self::WIDTH]] {
+        &ROUND_CONSTANTS
+    }
+
+    /// The inverse S-box, `x^(1/3)`.
+    pub fn inverse_sbox(element: &FieldElement) -> FieldElement {
+        element.pow(ALPHA_INV)
+    }
+
+    /// Multiply `state` by the MDS matrix.
+    pub fn mix(state: &mut [FieldElement; Self::WIDTH]) {
+        mix(state)
+    }
+
+    /// Apply the Rescue-Prime permutation to `state`.
+    pub fn permute(state: &mut [FieldElement; Self::WIDTH]) {
+        for constants in ROUND_CONSTANTS.chunks(2) {
+            for element in state.iter_mut() {
+                *element = element.square() * &*element;
+            }
+            mix(state);
+            for (element, constant) in state.iter_mut().zip(constants[0].iter()) {
+                *element += constant;
+            }
+            for element in state.iter_mut() {
+                *element = Self::inverse_sbox(element);
+            }
+            mix(state);
+            for (element, constant) in state.iter_mut().zip(constants[1].iter()) {
+                *element += constant;
+            }
+        }
+    }
+
+    /// Hash of two field elements.
+    ///
+    /// Permutes `[a, b, 2]` and returns the first element. The constant in the
+    /// capacity separates this from [`Rescue::hash_elements`].
+    pub fn hash_pair(a: &FieldElement, b: &FieldElement) -> FieldElement {
+        let mut state = [a.clone(), b.clone(), FieldElement::from(2)];
+        Self::permute(&mut state);
+        state[0].clone()
+    }
+
+    /// Sponge hash of a sequence of field elements.
+    pub fn hash_elements(elements: &[FieldElement]) -> FieldElement {
+        let mut sponge = Self::new();
+        for element in elements {
+            sponge.update(element);
+        }
+        sponge.finalize()
+    }
+
+    /// Absorb a field element into the sponge.
+    pub fn update(&mut self, element: &FieldElement) {
+        self.state[self.offset] += element;
+        self.offset += 1;
+        if self.offset == Self::RATE {
+            Self::permute(&mut self.state);
+            self.offset = 0;
+        }
+    }
+
+    /// Pad the input with a one and squeeze out the first element.
+    pub fn finalize(mut self) -> FieldElement {
+        self.update(&FieldElement::ONE);
+        if self.offset != 0 {
+            Self::permute(&mut self.state);
+        }
+        self.state[0].clone()
+    }
+}
+
+impl Default for Rescue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Merkle tree hashing with Rescue-Prime.
+///
+/// Hashes are encoded as field elements the same way as for
+/// [`Poseidon`](crate::Poseidon).
+impl Hasher for Rescue {
+    type State = Buffer<Self>;
+
+    const ID: u8 = 5;
+
+    fn digest(parts: &[&[u8]]) -> [u8; 32] {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        U256::from(Self::hash_elements(&bytes_to_field_elements(parts))).to_bytes_be()
+    }
+
+    fn hash_many(hashes: &[Hash]) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        let elements: Vec<FieldElement> = hashes.iter().map(Hash::to_field_element).collect();
+        Hash::from(&Self::hash_elements(&elements))
+    }
+
+    fn hash_node(left: &Hash, right: &Hash) -> Hash {
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_hash();
+        Hash::from(&Self::hash_pair(
+            &left.to_field_element(),
+            &right.to_field_element(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hashable, Poseidon};
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn inverse_sbox(x: FieldElement) -> bool {
+        Rescue::inverse_sbox(&(x.square() * &x)) == x
+    }
+
+    #[test]
+    fn round_constants_test() {
+        let constants = Rescue::round_constants();
+        assert_eq!(constants.len(), 2 * Rescue::ROUNDS);
+        assert_ne!(constants[0], constants[1]);
+    }
+
+    #[test]
+    fn sponge_test() {
+        let elements = [
+            FieldElement::from(1),
+            FieldElement::from(2),
+            FieldElement::from(3),
+        ];
+        let mut state = [
+            FieldElement::from(1),
+            FieldElement::from(2),
+            FieldElement::ZERO,
+        ];
+        Rescue::permute(&mut state);
+        state[0] += &FieldElement::from(3);
+        state[1] += &FieldElement::ONE;
+        Rescue::permute(&mut state);
+        assert_eq!(Rescue::hash_elements(&elements), state[0]);
+        assert_ne!(
+            Rescue::hash_elements(&elements),
+            Poseidon::hash_elements(&elements)
+        );
+    }
+
+    #[quickcheck]
+    fn hash_node_matches_pair(a: FieldElement, b: FieldElement) -> bool {
+        Rescue::hash_node(&a.hash(), &b.hash()) == Rescue::hash_pair(&a, &b).hash()
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(feature = "prover")]
+use crate::{Error, Proof, Tree, VectorCommitment};
+#[cfg(feature = "prover")]
+use std::marker::PhantomData;
+use std::prelude::v1::*;
+#[cfg(feature = "prover")]
+use zkp_error_utils::require;
+#[cfg(feature = "prover")]
+use zkp_hash::{Hasher, MaskedKeccak};
+
+/// A scheme to commit to a vector and later open it at chosen indices.
+///
+/// Abstracts over the Merkle tree commitments used in the proof system and
+/// alternatives such as algebraic vector commitments, so the prover can be
+/// written against the scheme instead of a concrete tree.
+pub trait CommitmentScheme {
+    /// The committed vector.
+    type Vector;
+
+    /// A single value of the vector.
+    type Leaf;
+
+    /// The short commitment sent to the verifier.
+    type Commitment;
+
+    /// Prover side data needed to produce openings.
+    type Decommitment;
+
+    /// Proof that opened values are consistent with a commitment.
+    type Proof;
+
+    type Error;
+
+    fn commit(vector: Self::Vector) -> Result<(Self::Commitment, Self::Decommitment), Self::Error>;
+
+    /// Open the values at `indices` in a single proof.
+    fn batch_open(
+        decommitment: &Self::Decommitment,
+        indices: &[usize],
+    ) -> Result<Self::Proof, Self::Error>;
+
+    fn open(decommitment: &Self::Decommitment, index: usize) -> Result<Self::Proof, Self::Error> {
+        Self::batch_open(decommitment, &[index])
+    }
+
+    /// Check that `leaves` are the values at their indices in the vector
+    /// committed to by `commitment`.
+    fn verify(
+        commitment: &Self::Commitment,
+        leaves: &[(usize, Self::Leaf)],
+        proof: &Self::Proof,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Merkle tree commitments over a [`VectorCommitment`] hashed with `H`.
+#[cfg(feature = "prover")]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct MerkleScheme<Container: VectorCommitment, H: Hasher = MaskedKeccak> {
+    container: PhantomData<Container>,
+    hasher:    PhantomData<H>,
+}
+
+#[cfg(feature = "prover")]
+impl<Container: VectorCommitment, H: Hasher> CommitmentScheme for MerkleScheme<Container, H> {
+    type Commitment = crate::Commitment;
+    type Decommitment = Tree<Container, H>;
+    type Error = Error;
+    type Leaf = Container::Leaf;
+    type Proof = Proof;
+    type Vector = Container;
+
+    fn commit(vector: Container) -> Result<(Self::Commitment, Self::Decommitment), Error> {
+        vector.commit_with::<H>()
+    }
+
+    fn batch_open(tree: &Self::Decommitment, indices: &[usize]) -> Result<Proof, Error> {
+        tree.open(indices)
+    }
+
+    fn verify(
+        commitment: &Self::Commitment,
+        leaves: &[(usize, Self::Leaf)],
+        proof: &Proof,
+    ) -> Result<(), Error> {
+        require!(
+            proof.commitment().size() == commitment.size()
+                && proof.commitment().hash() == commitment.hash(),
+            Error::RootHashMismatch
+        );
+        proof.verify_with::<H, _>(leaves)
+    }
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkp_hash::Hash;
+
+    type Scheme = MerkleScheme<Vec<Hash>>;
+
+    fn leaves() -> Vec<Hash> {
+        (0..8_u8).map(|i| Hash::new([i; 32])).collect()
+    }
+
+    #[test]
+    fn test_open_verify() {
+        let (commitment, tree) = Scheme::commit(leaves()).unwrap();
+        let proof = Scheme::batch_open(&tree, &[1, 5, 6]).unwrap();
+        let opened: Vec<_> = [1, 5, 6]
+            .iter()
+            .map(|&i| (i, leaves()[i].clone()))
+            .collect();
+        assert_eq!(Scheme::verify(&commitment, &opened, &proof), Ok(()));
+
+        let proof = Scheme::open(&tree, 3).unwrap();
+        assert_eq!(
+            Scheme::verify(&commitment, &[(3, leaves()[3].clone())], &proof),
+            Ok(())
+        );
+        assert_eq!(
+            Scheme::verify(&commitment, &[(3, leaves()[2].clone())], &proof),
+            Err(Error::RootHashMismatch)
+        );
+    }
+
+    #[test]
+    fn test_wrong_commitment() {
+        let (_, tree) = Scheme::commit(leaves()).unwrap();
+        let mut other = leaves();
+        other[0] = Hash::new([9; 32]);
+        let (commitment, _) = Scheme::commit(other).unwrap();
+        let proof = Scheme::open(&tree, 3).unwrap();
+        assert_eq!(
+            Scheme::verify(&commitment, &[(3, leaves()[3].clone())], &proof),
+            Err(Error::RootHashMismatch)
+        );
+    }
+}
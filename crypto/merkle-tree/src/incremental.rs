@@ -0,0 +1,324 @@
+use crate::{Error, Result};
+use std::{marker::PhantomData, prelude::v1::*};
+use zkp_error_utils::require;
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
+
+/// Append-only Merkle tree with consistency proofs.
+///
+/// The tree can have any number of leaves. As in [RFC 6962], a tree of $n$
+/// leaves is split into a left subtree of the largest power of two smaller
+/// than $n$ leaves and a right subtree with the remainder. For a power of two
+/// number of leaves the root is the same as that of a [`Tree`].
+///
+/// The roots of all complete subtrees are cached, so appending a leaf and
+/// computing the root take $O(\log n)$ hashes.
+///
+/// [RFC 6962]: https://tools.ietf.org/html/rfc6962#section-2.1
+/// [`Tree`]: struct.Tree.html
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct IncrementalMerkleTree<H: Hasher = MaskedKeccak> {
+    // `layers[k][i]` is the root of the complete subtree of `2^k` leaves
+    // starting at leaf `i * 2^k`.
+    layers: Vec<Vec<Hash>>,
+    hasher: PhantomData<H>,
+}
+
+/// Proof that a tree is an extension of a smaller tree.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ConsistencyProof {
+    old_size: usize,
+    new_size: usize,
+    hashes:   Vec<Hash>,
+}
+
+impl<H: Hasher> IncrementalMerkleTree<H> {
+    pub fn new() -> Self {
+        Self {
+            layers: vec![Vec::new()],
+            hasher: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn leaf(&self, index: usize) -> Option<&Hash> {
+        self.layers[0].get(index)
+    }
+
+    /// Append a leaf to the tree.
+    pub fn append<Leaf: Hashable>(&mut self, leaf: &Leaf) {
+        self.layers[0].push(leaf.hash_with::<H>());
+        // Complete the subtrees that end in the new leaf
+        let mut depth = 0;
+        while self.layers[depth].len() % 2 == 0 {
+            let layer = &self.layers[depth];
+            let node = H::hash_node(&layer[layer.len() - 2], &layer[layer.len() - 1]);
+            depth += 1;
+            if depth == self.layers.len() {
+                self.layers.push(Vec::new());
+            }
+            self.layers[depth].push(node);
+        }
+    }
+
+    /// The root hash, or `None` if the tree is empty.
+    pub fn root(&self) -> Option<Hash> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.subtree_root(0, self.len()))
+        }
+    }
+
+    /// The root hash of the tree formed by the first `size` leaves.
+    pub fn prefix_root(&self, size: usize) -> Result<Hash> {
+        require!(size > 0 && size <= self.len(), Error::IndexOutOfRange);
+        Ok(self.subtree_root(0, size))
+    }
+
+    /// Proof that the current tree extends the tree of the first `old_size`
+    /// leaves.
+    pub fn consistency_proof(&self, old_size: usize) -> Result<ConsistencyProof> {
+        require!(
+            old_size > 0 && old_size <= self.len(),
+            Error::IndexOutOfRange
+        );
+        let mut hashes = Vec::new();
+        self.subproof(old_size, 0, self.len(), true, &mut hashes);
+        Ok(ConsistencyProof {
+            old_size,
+            new_size: self.len(),
+            hashes,
+        })
+    }
+
+    // Root of the leaves `start..start + size`. The range is either a cached
+    // complete subtree or is split like the tree itself.
+    fn subtree_root(&self, start: usize, size: usize) -> Hash {
+        if size.is_power_of_two() && start % size == 0 {
+            let depth = size.trailing_zeros() as usize;
+            self.layers[depth][start >> depth].clone()
+        } else {
+            let split = split(size);
+            H::hash_node(
+                &self.subtree_root(start, split),
+                &self.subtree_root(start + split, size - split),
+            )
+        }
+    }
+
+    // The `SUBPROOF` algorithm from RFC 6962 on the leaves `start..start +
+    // size`.
+    fn subproof(
+        &self,
+        old_size: usize,
+        start: usize,
+        size: usize,
+        complete: bool,
+        hashes: &mut Vec<Hash>,
+    ) {
+        if old_size == size {
+            if !complete {
+                hashes.push(self.subtree_root(start, size));
+            }
+        } else {
+            let split = split(size);
+            if old_size <= split {
+                self.subproof(old_size, start, split, complete, hashes);
+                hashes.push(self.subtree_root(start + split, size - split));
+            } else {
+                self.subproof(old_size - split, start + split, size - split, false, hashes);
+                hashes.push(self.subtree_root(start, split));
+            }
+        }
+    }
+}
+
+impl<H: Hasher> Default for IncrementalMerkleTree<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsistencyProof {
+    pub fn from_hashes(old_size: usize, new_size: usize, hashes: &[Hash]) -> Result<Self> {
+        require!(old_size > 0 && old_size <= new_size, Error::IndexOutOfRange);
+        Ok(Self {
+            old_size,
+            new_size,
+            hashes: hashes.to_vec(),
+        })
+    }
+
+    pub fn old_size(&self) -> usize {
+        self.old_size
+    }
+
+    pub fn new_size(&self) -> usize {
+        self.new_size
+    }
+
+    pub fn hashes(&self) -> &[Hash] {
+        &self.hashes
+    }
+
+    pub fn verify(&self, old_root: &Hash, new_root: &Hash) -> Result<()> {
+        self.verify_with::<MaskedKeccak>(old_root, new_root)
+    }
+
+    /// Verify the proof for trees hashed with `H`.
+    ///
+    /// This is the verification algorithm of [RFC 9162].
+    ///
+    /// [RFC 9162]: https://tools.ietf.org/html/rfc9162#section-2.1.4.2
+    pub fn verify_with<H: Hasher>(&self, old_root: &Hash, new_root: &Hash) -> Result<()> {
+        if self.old_size == self.new_size {
+            require!(self.hashes.is_empty(), Error::RootHashMismatch);
+            require!(old_root == new_root, Error::RootHashMismatch);
+            return Ok(());
+        }
+
+        // If the old tree is a complete subtree, its root is the start of the
+        // path.
+        let mut hashes = self.hashes.iter();
+        let first = if self.old_size.is_power_of_two() {
+            old_root
+        } else {
+            hashes.next().ok_or(Error::NotEnoughHashes)?
+        };
+
+        // Skip the levels where the old tree is the right-most node
+        let mut old_node = self.old_size - 1;
+        let mut new_node = self.new_size - 1;
+        while old_node % 2 == 1 {
+            old_node >>= 1;
+            new_node >>= 1;
+        }
+
+        let mut old_hash = first.clone();
+        let mut new_hash = first.clone();
+        for hash in hashes {
+            require!(new_node != 0, Error::RootHashMismatch);
+            if old_node % 2 == 1 || old_node == new_node {
+                old_hash = H::hash_node(hash, &old_hash);
+                new_hash = H::hash_node(hash, &new_hash);
+                while old_node % 2 == 0 && old_node != 0 {
+                    old_node >>= 1;
+                    new_node >>= 1;
+                }
+            } else {
+                new_hash = H::hash_node(&new_hash, hash);
+            }
+            old_node >>= 1;
+            new_node >>= 1;
+        }
+        require!(new_node == 0, Error::NotEnoughHashes);
+        require!(
+            old_hash == *old_root && new_hash == *new_root,
+            Error::RootHashMismatch
+        );
+        Ok(())
+    }
+}
+
+// Largest power of two strictly smaller than `size`.
+fn split(size: usize) -> usize {
+    debug_assert!(size > 1);
+    1 << (0_usize.leading_zeros() - (size - 1).leading_zeros() - 1)
+}
+
+// Quickcheck requires pass by value
+#[allow(clippy::needless_pass_by_value)]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tree;
+    use quickcheck_macros::quickcheck;
+    use zkp_u256::U256;
+
+    // Reference implementation of RFC 6962's `MTH` without caching.
+    fn naive_root(leaves: &[U256]) -> Hash {
+        if leaves.len() == 1 {
+            leaves[0].hash()
+        } else {
+            let split = split(leaves.len());
+            MaskedKeccak::hash_node(&naive_root(&leaves[..split]), &naive_root(&leaves[split..]))
+        }
+    }
+
+    fn tree(size: usize) -> IncrementalMerkleTree {
+        let mut tree = IncrementalMerkleTree::new();
+        for i in 0..size {
+            tree.append(&U256::from(i));
+        }
+        tree
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split(2), 1);
+        assert_eq!(split(3), 2);
+        assert_eq!(split(4), 2);
+        assert_eq!(split(5), 4);
+        assert_eq!(split(8), 4);
+        assert_eq!(split(9), 8);
+    }
+
+    #[test]
+    fn test_matches_tree() {
+        let leaves: Vec<_> = (0..16).map(U256::from).collect();
+        let root = Tree::<_>::from_leaves(leaves)
+            .unwrap()
+            .commitment()
+            .hash()
+            .clone();
+        assert_eq!(tree(16).root(), Some(root));
+        assert_eq!(tree(0).root(), None);
+    }
+
+    #[quickcheck]
+    fn test_root(size: usize) -> bool {
+        let size = size % 100 + 1;
+        let leaves: Vec<_> = (0..size).map(U256::from).collect();
+        tree(size).root() == Some(naive_root(&leaves))
+    }
+
+    #[quickcheck]
+    fn test_consistency(old_size: usize, new_size: usize) {
+        let new_size = new_size % 100 + 1;
+        let old_size = old_size % new_size + 1;
+        let tree = tree(new_size);
+        let old_root = tree.prefix_root(old_size).unwrap();
+        let new_root = tree.root().unwrap();
+        let proof = tree.consistency_proof(old_size).unwrap();
+        proof.verify(&old_root, &new_root).unwrap();
+        if old_size < new_size {
+            assert_eq!(
+                proof.verify(&naive_root(&[U256::from(1000)]), &new_root),
+                Err(Error::RootHashMismatch)
+            );
+            assert_eq!(
+                proof.verify(&old_root, &old_root),
+                Err(Error::RootHashMismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn test_consistency_proof_size() {
+        let tree = tree(7);
+        assert_eq!(tree.consistency_proof(7).unwrap().hashes().len(), 0);
+        assert_eq!(tree.consistency_proof(4).unwrap().hashes().len(), 1);
+        assert_eq!(tree.consistency_proof(3).unwrap().hashes().len(), 4);
+        assert_eq!(tree.consistency_proof(8), Err(Error::IndexOutOfRange));
+        assert_eq!(tree.consistency_proof(0), Err(Error::IndexOutOfRange));
+    }
+}
@@ -41,16 +41,20 @@
 extern crate no_std_compat as std;
 
 mod commitment;
+mod commitment_scheme;
 /// Implements Vector Commitments using Merkle Trees.
 ///
 /// <https://eprint.iacr.org/2011/495.pdf>
 // TODO: Spin of to it's own crate.
 // TODO: Implement sparse Merkle trees.
-// TODO: Generalize over hash implementations.
+mod incremental;
 mod index;
 mod node;
+mod path;
 mod proof;
 mod result;
+mod salted;
+mod verified;
 
 #[cfg(feature = "prover")]
 mod tree;
@@ -59,11 +63,20 @@ mod tree;
 mod vector_commitment;
 
 pub use commitment::Commitment;
+pub use commitment_scheme::CommitmentScheme;
+pub use incremental::{ConsistencyProof, IncrementalMerkleTree};
+pub use path::{root_from_path, verify_path};
 pub use proof::Proof;
 pub use result::{Error, Result};
+pub use salted::Salted;
+pub use verified::VerifiedNodes;
 
 #[cfg(feature = "prover")]
-pub use tree::Tree;
+pub use commitment_scheme::MerkleScheme;
+#[cfg(feature = "prover")]
+pub use salted::SaltedVec;
+#[cfg(feature = "prover")]
+pub use tree::{sequential, Tree};
 
 #[cfg(feature = "prover")]
 pub use vector_commitment::VectorCommitment;
@@ -1,4 +1,4 @@
-use zkp_hash::{Hash, Hashable, MaskedKeccak};
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
 
 #[derive(Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
@@ -6,9 +6,10 @@ pub(crate) struct Node<'a>(pub(crate) &'a Hash, pub(crate) &'a Hash);
 
 impl Hashable for Node<'_> {
     fn hash(&self) -> Hash {
-        let mut hasher = MaskedKeccak::new();
-        hasher.update(self.0.as_bytes());
-        hasher.update(self.1.as_bytes());
-        hasher.hash()
+        self.hash_with::<MaskedKeccak>()
+    }
+
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        H::hash_node(self.0, self.1)
     }
 }
@@ -0,0 +1,111 @@
+use crate::{Commitment, Error, Index, Node, Result};
+use zkp_error_utils::require;
+use zkp_hash::{Hash, Hashable, Hasher};
+
+/// Recompute the root hash from the hash of leaf `index` in a tree of `size`
+/// leaves and the sibling hashes on its path, from the leaf up.
+///
+/// The iterator must yield exactly `log2(size)` siblings, which are taken one
+/// at a time. Together with an iterator that reads hashes from the proof stream
+/// this verifies a path in constant memory. For a single index the hashes of a
+/// [`Proof`](struct.Proof.html) are exactly this path.
+pub fn root_from_path<H, I>(size: usize, index: usize, leaf_hash: Hash, siblings: I) -> Result<Hash>
+where
+    H: Hasher,
+    I: IntoIterator<Item = Hash>,
+{
+    let mut current = Index::from_size_offset(size, index)?;
+    let mut hash = leaf_hash;
+    let mut siblings = siblings.into_iter();
+    while let Some(parent) = current.parent() {
+        let sibling = siblings.next().ok_or(Error::NotEnoughHashes)?;
+        hash = if current.is_left() {
+            Node(&hash, &sibling).hash_with::<H>()
+        } else {
+            Node(&sibling, &hash).hash_with::<H>()
+        };
+        current = parent;
+    }
+    require!(siblings.next().is_none(), Error::TooManyHashes);
+    Ok(hash)
+}
+
+/// Verify that `leaf` is at `index` in the tree committed to by `commitment`
+/// using the sibling hashes on its path, see [`root_from_path`].
+pub fn verify_path<H, Leaf, I>(
+    commitment: &Commitment,
+    index: usize,
+    leaf: &Leaf,
+    siblings: I,
+) -> Result<()>
+where
+    H: Hasher,
+    Leaf: Hashable,
+    I: IntoIterator<Item = Hash>,
+{
+    let root = root_from_path::<H, _>(commitment.size(), index, leaf.hash_with::<H>(), siblings)?;
+    require!(root == *commitment.hash(), Error::RootHashMismatch);
+    Ok(())
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VectorCommitment;
+    use quickcheck_macros::quickcheck;
+    use zkp_hash::{Blake3, MaskedKeccak};
+    use zkp_u256::U256;
+
+    #[quickcheck]
+    fn path_matches_proof(depth: usize, index: usize, seed: U256) {
+        let depth = depth % 9;
+        let size = 1_usize << depth;
+        let index = index % size;
+        let leaves: Vec<_> = (0..size).map(|i| &seed + U256::from(i)).collect();
+        let (commitment, tree) = leaves.clone().commit().unwrap();
+        let proof = tree.open(&[index]).unwrap();
+        let siblings = proof.hashes().iter().cloned();
+        assert_eq!(
+            verify_path::<MaskedKeccak, _, _>(&commitment, index, &leaves[index], siblings),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn path_errors() {
+        let leaves: Vec<_> = (0..16_u64).map(U256::from).collect();
+        let (commitment, tree) = leaves.clone().commit().unwrap();
+        let siblings = tree.open(&[5]).unwrap().hashes().to_vec();
+        let verify = |index, leaf: &U256, siblings: &[Hash]| {
+            verify_path::<MaskedKeccak, _, _>(&commitment, index, leaf, siblings.iter().cloned())
+        };
+        assert_eq!(verify(5, &leaves[5], &siblings), Ok(()));
+        assert_eq!(
+            verify(5, &leaves[6], &siblings),
+            Err(Error::RootHashMismatch)
+        );
+        assert_eq!(
+            verify(4, &leaves[5], &siblings),
+            Err(Error::RootHashMismatch)
+        );
+        assert_eq!(
+            verify(5, &leaves[5], &siblings[1..]),
+            Err(Error::NotEnoughHashes)
+        );
+        let mut extra = siblings.clone();
+        extra.push(Hash::new([0; 32]));
+        assert_eq!(verify(5, &leaves[5], &extra), Err(Error::TooManyHashes));
+        assert_eq!(
+            verify(16, &leaves[5], &siblings),
+            Err(Error::IndexOutOfRange)
+        );
+
+        let tree = crate::Tree::<_, Blake3>::from_leaves(leaves.clone()).unwrap();
+        let siblings = tree.open(&[5]).unwrap().hashes().to_vec();
+        assert_eq!(
+            verify_path::<Blake3, _, _>(tree.commitment(), 5, &leaves[5], siblings),
+            Ok(())
+        );
+    }
+}
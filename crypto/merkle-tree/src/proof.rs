@@ -1,9 +1,14 @@
-use crate::{Commitment, Error, Index, Node, Result};
+use crate::{Commitment, Error, Index, Node, Result, VerifiedNodes};
 use itertools::Itertools;
 use std::{collections::VecDeque, prelude::v1::*};
 use zkp_error_utils::require;
-use zkp_hash::{Hash, Hashable};
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
 
+/// Merkle multiproof for a set of leaves.
+///
+/// Contains the sibling hashes needed to reconstruct the root from the
+/// opened leaves, in the order they are consumed by [`Proof::verify`]. Hashes
+/// shared between paths are included only once.
 // Note: we can merge and split proofs. Based on indices we can
 // compute which values are redundant.
 #[derive(Clone)]
@@ -33,11 +38,58 @@ impl Proof {
         })
     }
 
+    pub fn commitment(&self) -> &Commitment {
+        &self.commitment
+    }
+
     pub fn hashes(&self) -> &[Hash] {
         &self.hashes
     }
 
     pub fn verify<Leaf: Hashable>(&self, leafs: &[(usize, Leaf)]) -> Result<()> {
+        self.verify_with::<MaskedKeccak, Leaf>(leafs)
+    }
+
+    /// Verify the proof for a tree hashed with `H`.
+    pub fn verify_with<H: Hasher, Leaf: Hashable>(&self, leafs: &[(usize, Leaf)]) -> Result<()> {
+        self.reconstruct::<H, _, _>(leafs, |_, left, right| Node(left, right).hash_with::<H>())
+    }
+
+    /// Verify the proof like [`verify_with`], taking nodes authenticated by
+    /// earlier proofs from `verified` instead of hashing them.
+    ///
+    /// If the proof is valid its nodes are added to `verified`.
+    ///
+    /// [`verify_with`]: #method.verify_with
+    pub fn verify_cached_with<H: Hasher, Leaf: Hashable>(
+        &self,
+        leafs: &[(usize, Leaf)],
+        verified: &VerifiedNodes,
+    ) -> Result<()> {
+        let root = self.commitment.hash();
+        let mut nodes = Vec::new();
+        self.reconstruct::<H, _, _>(leafs, |parent, left, right| {
+            let hash = verified
+                .parent(root, parent, left, right)
+                .unwrap_or_else(|| Node(left, right).hash_with::<H>());
+            nodes.push((parent.left_child(), left.clone()));
+            nodes.push((parent.right_child(), right.clone()));
+            nodes.push((parent, hash.clone()));
+            hash
+        })?;
+        verified.extend(root, nodes);
+        Ok(())
+    }
+
+    // Reconstruct the root from the leafs and the proof hashes and compare it
+    // to the commitment. Parent nodes are computed by `node` from the parent
+    // index and the child hashes.
+    fn reconstruct<H, Leaf, F>(&self, leafs: &[(usize, Leaf)], mut node: F) -> Result<()>
+    where
+        H: Hasher,
+        Leaf: Hashable,
+        F: FnMut(Index, &Hash, &Hash) -> Hash,
+    {
         // TODO: Pass leafs by reference?
         // TODO: Check if the indices line up.
 
@@ -46,7 +98,7 @@ impl Proof {
             .iter()
             .map(|(index, leaf)| {
                 (Index::from_size_offset(self.commitment.size(), *index)
-                    .map(|index| (index, leaf.hash())))
+                    .map(|index| (index, leaf.hash_with::<H>())))
             })
             .collect::<Result<Vec<_>>>()?;
         nodes.sort_unstable_by_key(|(index, _)| *index);
@@ -69,29 +121,29 @@ impl Proof {
         while let Some((current, hash)) = nodes.pop_front() {
             if let Some(parent) = current.parent() {
                 // Reconstruct the parent node
-                let node = if current.is_left() {
+                let hash = if current.is_left() {
                     if let Some((next, next_hash)) = nodes.front() {
                         // TODO: Find a better way to satisfy the borrow checker.
                         let next_hash = next_hash.clone();
                         if current.sibling().unwrap() == *next {
                             // Merge left with next
                             let _ = nodes.pop_front();
-                            Node(&hash, &next_hash).hash()
+                            node(parent, &hash, &next_hash)
                         } else {
                             // Left not merged with next
                             // TODO: Find a way to merge this branch with the next.
-                            Node(&hash, pop()?).hash()
+                            node(parent, &hash, pop()?)
                         }
                     } else {
                         // Left not merged with next
-                        Node(&hash, pop()?).hash()
+                        node(parent, &hash, pop()?)
                     }
                 } else {
                     // Right not merged with previous (or we would have skipped)
-                    Node(pop()?, &hash).hash()
+                    node(parent, pop()?, &hash)
                 };
                 // Queue the new parent node for the next iteration
-                nodes.push_back((parent, node))
+                nodes.push_back((parent, hash))
             } else {
                 // Root node has no parent, we are done
                 require!(hash == *self.commitment.hash(), Error::RootHashMismatch);
@@ -11,6 +11,7 @@ pub enum Error {
     IndicesUnsortedOrDuplicate,
     DuplicateLeafMismatch,
     NotEnoughHashes,
+    TooManyHashes,
     RootHashMismatch,
 }
 
@@ -24,6 +25,7 @@ impl fmt::Display for Error {
             IndicesUnsortedOrDuplicate => write!(f, "Indices are unsorted or duplicate"),
             DuplicateLeafMismatch => write!(f, "Duplicate leaf mismatch"),
             NotEnoughHashes => write!(f, "Not enough hashes to verify proof"),
+            TooManyHashes => write!(f, "More hashes than needed to verify proof"),
             RootHashMismatch => write!(f, "Verification failed since root hashes don't match"),
         }
     }
@@ -0,0 +1,130 @@
+#[cfg(feature = "prover")]
+use crate::VectorCommitment;
+#[cfg(feature = "prover")]
+use std::prelude::v1::*;
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
+
+/// A leaf committed together with a salt.
+///
+/// The leaf hash is `H(salt ‖ hash(value))`. As long as the salt is secret
+/// and random, the hash reveals nothing about the value, even when the value
+/// has low entropy. Opening the leaf reveals both the salt and the value.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Salted<T> {
+    pub salt:  Hash,
+    pub value: T,
+}
+
+impl<T: Hashable> Hashable for Salted<T> {
+    fn hash(&self) -> Hash {
+        self.hash_with::<MaskedKeccak>()
+    }
+
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        H::hash_many(&[self.salt.clone(), self.value.hash_with::<H>()])
+    }
+}
+
+/// A vector commitment with a salt for every leaf.
+///
+/// The prover keeps the salts and they are included in the leaves, so
+/// decommitments contain them. See [`Salted`].
+#[cfg(feature = "prover")]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SaltedVec<C> {
+    inner: C,
+    salts: Vec<Hash>,
+}
+
+#[cfg(feature = "prover")]
+impl<C: VectorCommitment> SaltedVec<C> {
+    /// Salt the leaves of `inner` with `salts`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not exactly one salt per leaf.
+    pub fn new(inner: C, salts: Vec<Hash>) -> Self {
+        assert_eq!(inner.len(), salts.len());
+        Self { inner, salts }
+    }
+
+    /// Salt the leaves of `inner` with salts derived from a secret `seed`.
+    ///
+    /// Salt `i` is `H(seed ‖ i)` with `i` as a big-endian 64 bit number.
+    pub fn from_seed<H: Hasher>(inner: C, seed: &[u8; 32]) -> Self {
+        let salts = (0..inner.len())
+            .map(|i| Hash::new(H::digest(&[seed, &(i as u64).to_be_bytes()])))
+            .collect();
+        Self { inner, salts }
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn salts(&self) -> &[Hash] {
+        &self.salts
+    }
+}
+
+#[cfg(feature = "prover")]
+impl<C: VectorCommitment> VectorCommitment for SaltedVec<C> {
+    type Leaf = Salted<C::Leaf>;
+
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn leaf(&self, index: usize) -> Self::Leaf {
+        Salted {
+            salt:  self.salts[index].clone(),
+            value: self.inner.leaf(index),
+        }
+    }
+
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        H::hash_many(&[self.salts[index].clone(), self.inner.leaf_hash::<H>(index)])
+    }
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkp_u256::U256;
+
+    fn bits() -> Vec<U256> {
+        (0..16_u64).map(|i| U256::from(i % 2)).collect()
+    }
+
+    #[test]
+    fn test_open_salted() {
+        let leaves = SaltedVec::from_seed::<MaskedKeccak>(bits(), &[7; 32]);
+        let (commitment, tree) = leaves.commit().unwrap();
+        let indices = vec![1, 6, 7, 12];
+        let proof = tree.open(&indices).unwrap();
+        let opened: Vec<_> = indices.iter().map(|&i| (i, tree.leaf(i))).collect();
+        assert_eq!(proof.commitment().hash(), commitment.hash());
+        assert_eq!(proof.verify(&opened), Ok(()));
+        assert_eq!(opened[1].1.value, U256::ZERO);
+
+        // The salt is part of the leaf
+        let mut tampered = opened.clone();
+        tampered[0].1.salt = Hash::new([0; 32]);
+        assert!(proof.verify(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_salts_hide_values() {
+        let (plain, _) = bits().commit().unwrap();
+        let (first, _) = SaltedVec::from_seed::<MaskedKeccak>(bits(), &[1; 32])
+            .commit()
+            .unwrap();
+        let (second, _) = SaltedVec::from_seed::<MaskedKeccak>(bits(), &[2; 32])
+            .commit()
+            .unwrap();
+        assert_ne!(plain.hash(), first.hash());
+        assert_ne!(first.hash(), second.hash());
+    }
+}
@@ -1,36 +1,167 @@
 use crate::{Commitment, Error, Index, Node, Proof, Result, VectorCommitment};
-use std::collections::VecDeque;
+use std::{collections::VecDeque, iter::FromIterator, marker::PhantomData};
 use zkp_error_utils::require;
-use zkp_hash::{Hash, Hashable};
-use zkp_mmap_vec::MmapVec;
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
+use zkp_mmap_vec::{MmapVec, Storage};
 
 #[cfg(feature = "std")]
 use rayon::prelude::*;
+#[cfg(feature = "std")]
+use std::cell::Cell;
+
+#[cfg(feature = "std")]
+thread_local! {
+    // Set on threads running inside `sequential`
+    static SEQUENTIAL: Cell<bool> = Cell::new(false);
+}
+
+/// Run `job` with all trees built on the calling thread, without Rayon.
+///
+/// Outside of `job`, tree construction is spread over the current Rayon
+/// pool.
+#[cfg(feature = "std")]
+pub fn sequential<R, F>(job: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    // Restores the previous value, also when `job` panics
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SEQUENTIAL.with(|sequential| sequential.set(self.0));
+        }
+    }
+    let _restore = Restore(SEQUENTIAL.with(|sequential| sequential.replace(true)));
+    job()
+}
+
+/// Run `job`. Without `std` trees are always built on the calling thread.
+#[cfg(not(feature = "std"))]
+pub fn sequential<R, F>(job: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    job()
+}
 
-// Utility function to parallelize iff on std
+// Utility function to parallelize iff on std and not in `sequential`
 fn for_each<F>(slice: &mut [Hash], f: F)
 where
     F: Fn((usize, &mut Hash)) -> () + Sync + Send,
 {
     #[cfg(feature = "std")]
-    slice.par_iter_mut().enumerate().for_each(f);
+    {
+        if SEQUENTIAL.with(Cell::get) {
+            slice.iter_mut().enumerate().for_each(f);
+        } else {
+            slice.par_iter_mut().enumerate().for_each(f);
+        }
+    }
 
     #[cfg(not(feature = "std"))]
     slice.iter_mut().enumerate().for_each(f);
 }
 
+// Utility function to parallelize iff on std and not in `sequential`
+fn for_each_owned<T, F>(items: Vec<T>, f: F)
+where
+    T: Send,
+    F: Fn((usize, T)) -> () + Sync + Send,
+{
+    #[cfg(feature = "std")]
+    {
+        if SEQUENTIAL.with(Cell::get) {
+            items.into_iter().enumerate().for_each(f);
+        } else {
+            items.into_par_iter().enumerate().for_each(f);
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    items.into_iter().enumerate().for_each(f);
+}
+
+// Layers below this depth are hashed as independent subtrees, one task per
+// subtree. With 256 subtrees there is enough slack for work stealing on large
+// machines, and the 255 nodes above are cheap to hash serially.
+const SUBTREE_DEPTH: usize = 8;
+
+// Utility function to hash all the nodes up to `depth`, which are stored by
+// `Index` in `nodes`.
+// TODO: This makes assumptions about how Index works.
+fn hash_nodes<C: VectorCommitment, H: Hasher>(leaves: &C, nodes: &mut [Hash], depth: usize) {
+    let split = std::cmp::min(depth, SUBTREE_DEPTH);
+    {
+        // Layer `split + k` is divided in `2^split` chunks of `2^k` nodes
+        // and chunk `j` belongs to the subtree under node `(split, j)`.
+        let mut subtrees: Vec<Vec<&mut [Hash]>> = (0..1 << split)
+            .map(|_| Vec::with_capacity(depth - split + 1))
+            .collect();
+        let (_, mut rest) =
+            nodes.split_at_mut(Index::from_depth_offset(split, 0).unwrap().as_index());
+        for k in 0..=depth - split {
+            let (layer, tail) = rest.split_at_mut(1 << (split + k));
+            rest = tail;
+            for (subtree, chunk) in subtrees.iter_mut().zip(layer.chunks_mut(1 << k)) {
+                subtree.push(chunk);
+            }
+        }
+        for_each_owned(subtrees, |(j, mut layers)| {
+            let height = layers.len() - 1;
+            for (i, hash) in layers[height].iter_mut().enumerate() {
+                let offset = (j << height) + i;
+                *hash = compute::<_, H>(leaves, Index::from_depth_offset(depth, offset).unwrap());
+            }
+            for k in (0..height).rev() {
+                let (upper, lower) = layers.split_at_mut(k + 1);
+                let previous = &lower[0];
+                for (i, hash) in upper[k].iter_mut().enumerate() {
+                    *hash = Node(&previous[i << 1], &previous[i << 1 | 1]).hash_with::<H>();
+                }
+            }
+        });
+    }
+    // Layers above the subtrees
+    for depth in (0..split).rev() {
+        let (tree, previous) =
+            nodes.split_at_mut(Index::from_depth_offset(depth + 1, 0).unwrap().as_index());
+        let current = &mut tree[Index::layer_range(depth)];
+        for_each(current, |(i, hash)| {
+            *hash = Node(&previous[i << 1], &previous[i << 1 | 1]).hash_with::<H>()
+        });
+    }
+}
+
+// Utility function to allocate storage for all but the lowest `skip_layers`
+fn allocate_nodes(leaf_depth: usize, skip_layers: usize, storage: Storage) -> MmapVec<Hash> {
+    if leaf_depth >= skip_layers {
+        // The array size is the largest index + 1
+        let depth = leaf_depth - skip_layers;
+        let max_index = Index::from_depth_offset(depth, Index::size_at_depth(depth) - 1)
+            .unwrap()
+            .as_index();
+        let mut nodes = MmapVec::with_capacity_in(max_index + 1, storage);
+        for _ in 0..=max_index {
+            nodes.push(Hash::default());
+        }
+        nodes
+    } else {
+        MmapVec::with_capacity_in(0, storage)
+    }
+}
+
 // Utility function to compute the first layer of the tree from the leaves
-fn compute<C: VectorCommitment>(leaves: &C, index: Index) -> Hash {
+fn compute<C: VectorCommitment, H: Hasher>(leaves: &C, index: Index) -> Hash {
     let leaf_depth = Index::depth_for_size(leaves.len());
     assert!(index.depth() <= leaf_depth);
     if index.depth() == leaf_depth {
-        leaves.leaf_hash(index.offset())
+        leaves.leaf_hash::<H>(index.offset())
     } else {
         Node(
-            &compute(leaves, index.left_child()),
-            &compute(leaves, index.right_child()),
+            &compute::<C, H>(leaves, index.left_child()),
+            &compute::<C, H>(leaves, index.right_child()),
         )
-        .hash()
+        .hash_with::<H>()
     }
 }
 
@@ -39,30 +170,39 @@ fn compute<C: VectorCommitment>(leaves: &C, index: Index) -> Hash {
 /// The tree will become the owner of the `Container`. This is necessary because
 /// when low layer-omission is implemented we need immutable access to the
 /// leaves. If shared ownership is required the `Container` can be an `Rc<_>`.
+///
+/// Leaves and nodes are combined using the hash function `H`.
 // OPT: Do not store leaf hashes but re-create.
 // OPT: Allow up to `n` lower layers to be skipped.
-// TODO: Make hash depend on type.
 #[cfg_attr(feature = "std", derive(Debug))]
-pub struct Tree<Container: VectorCommitment> {
+pub struct Tree<Container: VectorCommitment, H: Hasher = MaskedKeccak> {
     commitment: Commitment,
     nodes:      MmapVec<Hash>,
     leaves:     Container,
+    hasher:     PhantomData<H>,
 }
 
-impl<Container: VectorCommitment> Tree<Container> {
+impl<Container: VectorCommitment, H: Hasher> Tree<Container, H> {
     pub fn from_leaves(leaves: Container) -> Result<Self> {
         Self::from_leaves_skip_layers(leaves, 1)
     }
 
     pub fn from_leaves_skip_layers(leaves: Container, skip_layers: usize) -> Result<Self> {
+        Self::from_leaves_in(leaves, skip_layers, Storage::default())
+    }
+
+    /// Like [`Tree::from_leaves_skip_layers`] with the nodes kept as selected
+    /// by `storage`.
+    pub fn from_leaves_in(leaves: Container, skip_layers: usize, storage: Storage) -> Result<Self> {
         let size = leaves.len();
         if size == 0 {
             return Ok(Self {
                 // TODO: Ideally give the empty tree a unique flag value.
                 // Size zero commitment always exists
                 commitment: Commitment::from_size_hash(size, &Hash::default()).unwrap(),
-                nodes: MmapVec::with_capacity(0),
+                nodes: MmapVec::with_capacity_in(0, storage),
                 leaves,
+                hasher: PhantomData,
             });
         }
         // TODO: Support non power of two sizes
@@ -71,44 +211,15 @@ impl<Container: VectorCommitment> Tree<Container> {
 
         // Allocate result
         let leaf_depth = Index::depth_for_size(size);
-        let mut nodes = if leaf_depth >= skip_layers {
-            // The array size is the largest index + 1
-            let depth = leaf_depth - skip_layers;
-            let max_index = Index::from_depth_offset(depth, Index::size_at_depth(depth) - 1)
-                .unwrap()
-                .as_index();
-            let mut nodes = MmapVec::with_capacity(max_index + 1);
-            for _ in 0..=max_index {
-                nodes.push(Hash::default());
-            }
-            nodes
-        } else {
-            MmapVec::with_capacity(0)
-        };
+        let mut nodes = allocate_nodes(leaf_depth, skip_layers, storage);
 
         // Hash the tree nodes
-        // OPT: Instead of layer at a time, have each thread compute a subtree.
         if leaf_depth >= skip_layers {
-            let depth = leaf_depth - skip_layers;
-            let leaf_layer = &mut nodes[Index::layer_range(depth)];
-            // First layer
-            for_each(leaf_layer, |(i, hash)| {
-                *hash = compute(&leaves, Index::from_depth_offset(depth, i).unwrap())
-            });
-            // Upper layers
-            for depth in (0..depth).rev() {
-                // TODO: This makes assumptions about how Index works.
-                let (tree, previous) =
-                    nodes.split_at_mut(Index::from_depth_offset(depth + 1, 0).unwrap().as_index());
-                let current = &mut tree[Index::layer_range(depth)];
-                for_each(current, |(i, hash)| {
-                    *hash = Node(&previous[i << 1], &previous[i << 1 | 1]).hash()
-                });
-            }
+            hash_nodes::<_, H>(&leaves, &mut nodes, leaf_depth - skip_layers);
         }
 
         let root_hash = if nodes.is_empty() {
-            compute(&leaves, Index::root())
+            compute::<_, H>(&leaves, Index::root())
         } else {
             nodes[0].clone()
         };
@@ -117,6 +228,71 @@ impl<Container: VectorCommitment> Tree<Container> {
             commitment,
             nodes,
             leaves,
+            hasher: PhantomData,
+        })
+    }
+
+    /// Build the tree from leaves produced in order by an iterator.
+    ///
+    /// This is for leaves that are expensive to hold or to access out of
+    /// order. Leaves are hashed as they arrive and combined with the previous
+    /// completed subtrees, so at most one pending hash per layer is held in
+    /// addition to the stored layers. The lowest `skip_layers` layers are not
+    /// stored. The leaves are collected in the `Container` for decommitments.
+    ///
+    /// Unlike [`Tree::from_leaves`], hashing is not parallelized.
+    pub fn from_leaves_iter<I>(leaves: I, skip_layers: usize) -> Result<Self>
+    where
+        I: IntoIterator<Item = Container::Leaf>,
+        I::IntoIter: ExactSizeIterator,
+        Container: FromIterator<Container::Leaf>,
+    {
+        let leaves = leaves.into_iter();
+        let size = leaves.len();
+        if size == 0 {
+            return Self::from_leaves_skip_layers(leaves.collect(), skip_layers);
+        }
+        require!(size.is_power_of_two(), Error::NumLeavesNotPowerOfTwo);
+        require!(size <= Index::max_size(), Error::TreeToLarge);
+        let leaf_depth = Index::depth_for_size(size);
+        let mut nodes = allocate_nodes(leaf_depth, skip_layers, Storage::default());
+
+        // Roots of the completed subtrees that are still missing a sibling,
+        // with their height above the leaves.
+        let mut stack: Vec<(usize, Hash)> = Vec::with_capacity(leaf_depth + 1);
+        let leaves: Container = leaves
+            .enumerate()
+            .map(|(offset, leaf)| {
+                let mut height = 0;
+                let mut hash = leaf.hash_with::<H>();
+                loop {
+                    if height >= skip_layers {
+                        let index = Index::from_depth_offset(leaf_depth - height, offset >> height)
+                            .unwrap();
+                        nodes[index.as_index()] = hash.clone();
+                    }
+                    match stack.last() {
+                        Some((left_height, _)) if *left_height == height => {
+                            let (_, left) = stack.pop().unwrap();
+                            hash = Node(&left, &hash).hash_with::<H>();
+                            height += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                stack.push((height, hash));
+                leaf
+            })
+            .collect();
+        require!(stack.len() == 1, Error::NumLeavesNotPowerOfTwo);
+
+        let (_, root_hash) = stack.pop().unwrap();
+        let commitment = Commitment::from_size_hash(size, &root_hash).unwrap();
+        Ok(Self {
+            commitment,
+            nodes,
+            leaves,
+            hasher: PhantomData,
         })
     }
 
@@ -142,17 +318,23 @@ impl<Container: VectorCommitment> Tree<Container> {
         } else {
             assert!(index.depth() <= self.leaf_depth());
             if index.depth() == self.leaf_depth() {
-                self.leaves.leaf_hash(index.offset())
+                self.leaves.leaf_hash::<H>(index.offset())
             } else {
                 Node(
                     &self.node_hash(index.left_child()),
                     &self.node_hash(index.right_child()),
                 )
-                .hash()
+                .hash_with::<H>()
             }
         }
     }
 
+    /// Open the leaves at `indices` in a single multiproof.
+    ///
+    /// Sibling hashes that are shared between the paths, or that can be
+    /// computed from other opened leaves, are only included once. Opening all
+    /// query indices in one call is therefore smaller than opening them one
+    /// by one.
     pub fn open(&self, indices: &[usize]) -> Result<Proof> {
         let indices = self.commitment().sort_indices(indices)?;
         let proof_indices: Vec<usize> = indices.iter().map(|i| i.offset()).collect();
@@ -191,6 +373,7 @@ impl<Container: VectorCommitment> Tree<Container> {
 mod tests {
     use super::*;
     use quickcheck_macros::quickcheck;
+    use zkp_hash::{Blake3, Poseidon};
     use zkp_macros_decl::hex;
     use zkp_u256::U256;
 
@@ -202,7 +385,7 @@ mod tests {
             .collect();
 
         // Build the tree
-        let tree = Tree::from_leaves(leaves).unwrap();
+        let tree = Tree::<_>::from_leaves(leaves).unwrap();
         let root = tree.commitment();
         assert_eq!(
             root.hash().as_bytes(),
@@ -251,7 +434,7 @@ mod tests {
         let indices: Vec<usize> = vec![];
         let leaves: Vec<U256> = vec![];
 
-        let tree = Tree::from_leaves(leaves).unwrap();
+        let tree = Tree::<_>::from_leaves(leaves).unwrap();
         let root = tree.commitment();
 
         // Open indices
@@ -276,7 +459,7 @@ mod tests {
             .collect();
 
         // Build the tree
-        let tree = Tree::from_leaves_skip_layers(leaves, skip).unwrap();
+        let tree = Tree::<_>::from_leaves_skip_layers(leaves, skip).unwrap();
         let root = tree.commitment();
 
         // Open indices
@@ -287,4 +470,140 @@ mod tests {
         let select_leaves: Vec<_> = indices.iter().map(|&i| (i, tree.leaf(i))).collect();
         proof.verify(&select_leaves).unwrap();
     }
+
+    #[quickcheck]
+    fn test_poseidon_tree(depth: usize, indices: Vec<usize>, seed: U256) {
+        let depth = depth % 7;
+        let num_leaves = 1_usize << depth;
+        let indices: Vec<_> = indices.iter().map(|&i| i % num_leaves).collect();
+        let leaves: Vec<_> = (0..num_leaves)
+            .map(|i| vec![&seed + U256::from(i), U256::from(i)])
+            .collect();
+
+        let tree = Tree::<_, Poseidon>::from_leaves(leaves.clone()).unwrap();
+        assert_ne!(
+            tree.commitment().hash(),
+            Tree::<_>::from_leaves(leaves).unwrap().commitment().hash()
+        );
+
+        let proof = tree.open(&indices).unwrap();
+        let select_leaves: Vec<_> = indices.iter().map(|&i| (i, tree.leaf(i))).collect();
+        proof.verify_with::<Poseidon, _>(&select_leaves).unwrap();
+        if !indices.is_empty() {
+            assert_eq!(proof.verify(&select_leaves), Err(Error::RootHashMismatch));
+        }
+    }
+
+    #[quickcheck]
+    fn test_blake3_tree(depth: usize, indices: Vec<usize>, seed: U256) {
+        let depth = depth % 9;
+        let num_leaves = 1_usize << depth;
+        let indices: Vec<_> = indices.iter().map(|&i| i % num_leaves).collect();
+        let leaves: Vec<_> = (0..num_leaves).map(|i| &seed + U256::from(i)).collect();
+
+        let tree = Tree::<_, Blake3>::from_leaves(leaves).unwrap();
+        let proof = tree.open(&indices).unwrap();
+        let select_leaves: Vec<_> = indices.iter().map(|&i| (i, tree.leaf(i))).collect();
+        proof.verify_with::<Blake3, _>(&select_leaves).unwrap();
+    }
+
+    #[test]
+    fn test_multiproof_size() {
+        let leaves: Vec<_> = (0..8).map(U256::from).collect();
+        let tree = Tree::<_>::from_leaves(leaves).unwrap();
+        let root = tree.commitment();
+        assert_eq!(root.proof_size(&[0]).unwrap(), 3);
+        assert_eq!(root.proof_size(&[0, 1]).unwrap(), 2);
+        assert_eq!(root.proof_size(&[0, 2]).unwrap(), 3);
+        assert_eq!(root.proof_size(&[0, 7]).unwrap(), 4);
+        assert_eq!(root.proof_size(&[0, 1, 2, 3, 4, 5, 6, 7]).unwrap(), 0);
+
+        let proof = tree.open(&[2, 0]).unwrap();
+        assert_eq!(proof.hashes().len(), 3);
+        proof
+            .verify(&[(0, tree.leaf(0)), (2, tree.leaf(2))])
+            .unwrap();
+    }
+
+    #[test]
+    fn test_record_leaves() {
+        // (index, value, flags) records committed directly
+        let leaves: Vec<_> = (0..16_usize)
+            .map(|i| (i, U256::from(i * i), i % 3 == 0))
+            .collect();
+        let tree = Tree::<_, Blake3>::from_leaves(leaves).unwrap();
+        let proof = tree.open(&[3, 10]).unwrap();
+        let opened = [(3, tree.leaf(3)), (10, tree.leaf(10))];
+        assert_eq!(proof.verify_with::<Blake3, _>(&opened), Ok(()));
+
+        let mut tampered = opened.clone();
+        tampered[1].1 .2 = true;
+        assert!(proof.verify_with::<Blake3, _>(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_leaf_widths() {
+        // Each tree has its own number of values per leaf
+        for &width in &[1, 3, 5] {
+            let leaves: Vec<Vec<U256>> = (0..8_usize)
+                .map(|i| (0..width).map(|j| U256::from(i * width + j)).collect())
+                .collect();
+            let tree = Tree::<_>::from_leaves(leaves).unwrap();
+            let proof = tree.open(&[5]).unwrap();
+            assert_eq!(proof.verify(&[(5, tree.leaf(5))]), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_subtrees() {
+        // Deep enough to have several layers in each subtree
+        let depth = SUBTREE_DEPTH + 3;
+        let leaves: Vec<_> = (0..1_usize << depth).map(U256::from).collect();
+        for &skip_layers in &[0, 1, 3, 4, depth] {
+            let expected = Tree::<Vec<_>>::from_leaves_iter(leaves.clone(), skip_layers).unwrap();
+            let tree = Tree::<_>::from_leaves_skip_layers(leaves.clone(), skip_layers).unwrap();
+            assert_eq!(tree.commitment().hash(), expected.commitment().hash());
+            assert_eq!(tree.nodes[..], expected.nodes[..]);
+        }
+    }
+
+    #[test]
+    fn test_storage() {
+        let leaves: Vec<_> = (0..64_usize).map(U256::from).collect();
+        let expected = Tree::<_>::from_leaves(leaves.clone()).unwrap();
+        let tree = Tree::<_>::from_leaves_in(leaves, 1, Storage::MEMORY).unwrap();
+        assert!(!tree.nodes.is_file_backed());
+        assert_eq!(tree.commitment().hash(), expected.commitment().hash());
+    }
+
+    #[test]
+    fn test_sequential() {
+        let leaves: Vec<_> = (0..1_usize << (SUBTREE_DEPTH + 2))
+            .map(U256::from)
+            .collect();
+        let expected = Tree::<_>::from_leaves(leaves.clone()).unwrap();
+        let tree = sequential(|| {
+            assert!(SEQUENTIAL.with(Cell::get));
+            Tree::<_>::from_leaves(leaves).unwrap()
+        });
+        assert!(!SEQUENTIAL.with(Cell::get));
+        assert_eq!(tree.commitment().hash(), expected.commitment().hash());
+    }
+
+    #[quickcheck]
+    fn test_from_leaves_iter(depth: usize, skip_layers: usize, indices: Vec<usize>, seed: U256) {
+        let depth = depth % 9;
+        let skip_layers = skip_layers % 11;
+        let num_leaves = 1_usize << depth;
+        let indices: Vec<_> = indices.iter().map(|&i| i % num_leaves).collect();
+        let leaves: Vec<_> = (0..num_leaves).map(|i| &seed + U256::from(i)).collect();
+
+        let expected = Tree::<_>::from_leaves(leaves.clone()).unwrap();
+        let tree = Tree::<Vec<_>>::from_leaves_iter(leaves, skip_layers).unwrap();
+        assert_eq!(tree.commitment().hash(), expected.commitment().hash());
+        assert_eq!(
+            tree.open(&indices).unwrap().hashes(),
+            expected.open(&indices).unwrap().hashes()
+        );
+    }
 }
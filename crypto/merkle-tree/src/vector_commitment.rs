@@ -1,9 +1,7 @@
 use crate::{Commitment, Result, Tree};
 use std::prelude::v1::*;
-use zkp_hash::{Hash, Hashable};
-
-#[cfg(feature = "mmap")]
-use crate::mmap_vec::MmapVec;
+use zkp_hash::{Hash, Hashable, Hasher};
+use zkp_mmap_vec::MmapVec;
 
 pub trait VectorCommitment
 where
@@ -20,12 +18,18 @@ where
 
     fn leaf(&self, index: usize) -> Self::Leaf;
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        self.leaf(index).hash()
+    /// Hash of a leaf, combining compound leaves with `H`.
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        self.leaf(index).hash_with::<H>()
     }
 
     fn commit(self) -> Result<(Commitment, Tree<Self>)> {
-        let tree = Tree::from_leaves(self)?;
+        self.commit_with()
+    }
+
+    /// Commit using a Merkle tree hashed with `H`.
+    fn commit_with<H: Hasher>(self) -> Result<(Commitment, Tree<Self, H>)> {
+        let tree = Tree::<Self, H>::from_leaves(self)?;
         let commitment = tree.commitment().clone();
         Ok((commitment, tree))
     }
@@ -44,12 +48,11 @@ impl<Leaf: Hashable + Clone + Sync> VectorCommitment for Vec<Leaf> {
         self[index].clone()
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        self[index].hash()
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        self[index].hash_with::<H>()
     }
 }
 
-#[cfg(feature = "mmap")]
 impl<Leaf: Hashable + Clone + Sync> VectorCommitment for MmapVec<Leaf> {
     type Leaf = Leaf;
 
@@ -61,7 +64,7 @@ impl<Leaf: Hashable + Clone + Sync> VectorCommitment for MmapVec<Leaf> {
         self[index].clone()
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        self[index].hash()
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        self[index].hash_with::<H>()
     }
 }
@@ -0,0 +1,191 @@
+use crate::Index;
+#[cfg(not(feature = "std"))]
+use std::cell::RefCell;
+#[cfg(feature = "std")]
+use std::sync::{PoisonError, RwLock};
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+};
+use zkp_hash::Hash;
+
+type Nodes = BTreeMap<(Hash, Index), Hash>;
+
+/// Tree nodes authenticated by earlier proofs, to share hashing between
+/// proofs against the same commitment.
+///
+/// A node is stored once a proof through it reconstructed the root, so it is
+/// part of the committed tree. [`Proof::verify_cached_with`] then takes the
+/// parent of two known nodes from the store instead of hashing them. This
+/// does not change the outcome of a verification, only the work it takes.
+/// With `std` the store can be shared between threads. It grows until it is
+/// dropped.
+///
+/// [`Proof::verify_cached_with`]: struct.Proof.html#method.verify_cached_with
+#[derive(Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VerifiedNodes {
+    #[cfg(feature = "std")]
+    nodes: RwLock<Nodes>,
+    #[cfg(not(feature = "std"))]
+    nodes: RefCell<Nodes>,
+}
+
+impl VerifiedNodes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of nodes stored, over all commitments.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The hash of `parent` in the tree with root `root`, if both its
+    /// children are stored with the given hashes.
+    pub(crate) fn parent(
+        &self,
+        root: &Hash,
+        parent: Index,
+        left: &Hash,
+        right: &Hash,
+    ) -> Option<Hash> {
+        let nodes = self.read();
+        let stored = |index| nodes.get(&(root.clone(), index));
+        if stored(parent.left_child()) == Some(left) && stored(parent.right_child()) == Some(right)
+        {
+            stored(parent).cloned()
+        } else {
+            None
+        }
+    }
+
+    /// Store nodes of the tree with root `root`.
+    pub(crate) fn extend<I>(&self, root: &Hash, nodes: I)
+    where
+        I: IntoIterator<Item = (Index, Hash)>,
+    {
+        let mut stored = self.write();
+        for (index, hash) in nodes {
+            let _ = stored.entry((root.clone(), index)).or_insert(hash);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn read(&self) -> impl Deref<Target = Nodes> + '_ {
+        self.nodes.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[cfg(feature = "std")]
+    fn write(&self) -> impl DerefMut<Target = Nodes> + '_ {
+        self.nodes.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn read(&self) -> impl Deref<Target = Nodes> + '_ {
+        self.nodes.borrow()
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn write(&self) -> impl DerefMut<Target = Nodes> + '_ {
+        self.nodes.borrow_mut()
+    }
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Commitment, Error, Proof, Tree, VectorCommitment};
+    use std::cell::Cell;
+    use zkp_hash::{Hasher, MaskedKeccak};
+    use zkp_u256::U256;
+
+    thread_local! {
+        static NODES_HASHED: Cell<usize> = Cell::new(0);
+    }
+
+    // `MaskedKeccak` that counts the nodes hashed on this thread
+    struct Counting;
+
+    impl Hasher for Counting {
+        type State = <MaskedKeccak as Hasher>::State;
+
+        const ID: u8 = MaskedKeccak::ID;
+
+        fn digest(parts: &[&[u8]]) -> [u8; 32] {
+            MaskedKeccak::digest(parts)
+        }
+
+        fn hash_many(hashes: &[Hash]) -> Hash {
+            MaskedKeccak::hash_many(hashes)
+        }
+
+        fn hash_node(left: &Hash, right: &Hash) -> Hash {
+            NODES_HASHED.with(|count| count.set(count.get() + 1));
+            MaskedKeccak::hash_node(left, right)
+        }
+    }
+
+    fn nodes_hashed(verify: impl FnOnce()) -> usize {
+        NODES_HASHED.with(|count| count.set(0));
+        verify();
+        NODES_HASHED.with(Cell::get)
+    }
+
+    #[test]
+    fn test_verify_cached() {
+        let leaves: Vec<_> = (0..16).map(U256::from).collect();
+        let tree = Tree::<_>::from_leaves(leaves).unwrap();
+        let opened =
+            |indices: &[usize]| -> Vec<_> { indices.iter().map(|&i| (i, tree.leaf(i))).collect() };
+        let verified = VerifiedNodes::new();
+
+        let proof = tree.open(&[3, 10]).unwrap();
+        let first = nodes_hashed(|| {
+            assert_eq!(
+                proof.verify_cached_with::<Counting, _>(&opened(&[3, 10]), &verified),
+                Ok(())
+            );
+        });
+        assert_eq!(first, 7);
+        assert_eq!(verified.len(), 15);
+
+        // A path through known nodes is not hashed again
+        let proof = tree.open(&[2, 10]).unwrap();
+        let second = nodes_hashed(|| {
+            assert_eq!(
+                proof.verify_cached_with::<Counting, _>(&opened(&[2, 10]), &verified),
+                Ok(())
+            );
+        });
+        assert_eq!(second, 0);
+
+        // Known nodes do not vouch for other values
+        let mut tampered = opened(&[2, 10]);
+        tampered[0].1 = U256::from(7);
+        assert_eq!(
+            proof.verify_cached_with::<Counting, _>(&tampered, &verified),
+            Err(Error::RootHashMismatch)
+        );
+        let mut hashes = proof.hashes().to_vec();
+        hashes[0] = Hash::new([1; 32]);
+        let forged = Proof::from_hashes(tree.commitment(), &[2, 10], &hashes).unwrap();
+        assert_eq!(
+            forged.verify_cached_with::<Counting, _>(&opened(&[2, 10]), &verified),
+            Err(Error::RootHashMismatch)
+        );
+
+        // Nodes are kept per commitment
+        let other = Commitment::from_size_hash(16, &Hash::new([2; 32])).unwrap();
+        let proof = Proof::from_hashes(&other, &[2, 10], proof.hashes()).unwrap();
+        assert_eq!(
+            proof.verify_cached_with::<Counting, _>(&opened(&[2, 10]), &verified),
+            Err(Error::RootHashMismatch)
+        );
+    }
+}
@@ -0,0 +1,63 @@
+#ifndef ZKP_STARK_H
+#define ZKP_STARK_H
+
+/* Generated with cbindgen, do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * The proof is valid for the claim.
+ */
+#define ZKP_OK 0
+
+/**
+ * The proof does not verify for the claim.
+ */
+#define ZKP_INVALID_PROOF 1
+
+/**
+ * One of the pointers is null.
+ */
+#define ZKP_NULL_POINTER -1
+
+/**
+ * The claim could not be decoded.
+ */
+#define ZKP_INVALID_CLAIM -2
+
+/**
+ * The proof could not be decoded.
+ */
+#define ZKP_INVALID_PROOF_ENCODING -3
+
+/**
+ * Verification panicked.
+ */
+#define ZKP_INTERNAL_ERROR -4
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Verify a serialized proof for a serialized claim.
+ *
+ * Returns `ZKP_OK` if the proof is valid, `ZKP_INVALID_PROOF` if it is not
+ * and a negative error code if the inputs can not be decoded.
+ *
+ * # Safety
+ *
+ * `claim_ptr` and `proof_ptr` must point to `claim_len` and `proof_len`
+ * readable bytes respectively. The buffers are only read during the call.
+ */
+int32_t zkp_verify(const uint8_t *claim_ptr,
+                   size_t claim_len,
+                   const uint8_t *proof_ptr,
+                   size_t proof_len);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* ZKP_STARK_H */
@@ -0,0 +1,200 @@
+// This sequence needs to be repeated in each project as a workaround.
+//       See https://github.com/rust-lang/cargo/issues/5034
+// For clippy lints see: https://rust-lang.github.io/rust-clippy/master
+// For rustc lints see: https://doc.rust-lang.org/rustc/lints/index.html
+// #![forbid(unsafe_code)] // We need unsafe to read from raw pointers
+#![warn(
+    // Enable sets of warnings
+    clippy::all,
+    clippy::pedantic,
+    clippy::cargo,
+    rust_2018_idioms,
+    future_incompatible,
+    unused,
+
+    // Additional unused warnings (not included in `unused`)
+    unused_lifetimes,
+    unused_qualifications,
+    unused_results,
+
+    // Additional misc. warnings
+    anonymous_parameters,
+    deprecated_in_future,
+    elided_lifetimes_in_paths,
+    explicit_outlives_requirements,
+    keyword_idents,
+    macro_use_extern_crate,
+    // missing_docs,
+    missing_doc_code_examples,
+    private_doc_tests,
+    single_use_lifetimes,
+    trivial_casts,
+    trivial_numeric_casts,
+    unreachable_pub,
+    variant_size_differences
+)]
+#![warn(missing_debug_implementations)]
+
+//! C interface to the Stark verifier.
+//!
+//! The library exposes a single function, `zkp_verify`, declared in
+//! `include/zkp_stark.h`. The header is generated with
+//!
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/zkp_stark.h
+//! ```
+//!
+//! A claim is encoded as a one byte tag selecting a component from
+//! [`zkp_stark::components`] followed by the [`ClaimBytes`] encoding of the
+//! claim:
+//!
+//! | Tag | Claim                   | Encoding                              |
+//! | --- | ----------------------- | ------------------------------------- |
+//! | `0` | `fibonacci::Claim`      | `index` as `u64`, `value`             |
+//! | `1` | `range_check::Claim`    | `bits` as `u64`, `value`              |
+//!
+//! Integers are big-endian and field elements are 32 bytes big-endian in
+//! Montgomery form. The proof is the serialized form of [`Proof::to_bytes`].
+//!
+//! [`ClaimBytes`]: ../zkp_stark/trait.ClaimBytes.html
+//! [`Proof::to_bytes`]: ../zkp_stark/struct.Proof.html#method.to_bytes
+
+use std::{convert::TryFrom, panic, slice};
+use zkp_primefield::FieldElement;
+use zkp_stark::{
+    components::{fibonacci, range_check},
+    verify, Constraints, Proof, Verifiable,
+};
+use zkp_u256::U256;
+
+/// The proof is valid for the claim.
+pub const ZKP_OK: i32 = 0;
+/// The proof does not verify for the claim.
+pub const ZKP_INVALID_PROOF: i32 = 1;
+/// One of the pointers is null.
+pub const ZKP_NULL_POINTER: i32 = -1;
+/// The claim could not be decoded.
+pub const ZKP_INVALID_CLAIM: i32 = -2;
+/// The proof could not be decoded.
+pub const ZKP_INVALID_PROOF_ENCODING: i32 = -3;
+/// Verification panicked.
+pub const ZKP_INTERNAL_ERROR: i32 = -4;
+
+const TAG_FIBONACCI: u8 = 0;
+const TAG_RANGE_CHECK: u8 = 1;
+
+/// Verify a serialized proof for a serialized claim.
+///
+/// Returns `ZKP_OK` if the proof is valid, `ZKP_INVALID_PROOF` if it is not
+/// and a negative error code if the inputs can not be decoded.
+///
+/// # Safety
+///
+/// `claim_ptr` and `proof_ptr` must point to `claim_len` and `proof_len`
+/// readable bytes respectively. The buffers are only read during the call.
+#[no_mangle]
+pub unsafe extern "C" fn zkp_verify(
+    claim_ptr: *const u8,
+    claim_len: usize,
+    proof_ptr: *const u8,
+    proof_len: usize,
+) -> i32 {
+    if claim_ptr.is_null() || proof_ptr.is_null() {
+        return ZKP_NULL_POINTER;
+    }
+    let claim = slice::from_raw_parts(claim_ptr, claim_len);
+    let proof = slice::from_raw_parts(proof_ptr, proof_len);
+
+    // Unwinding into C is undefined behaviour
+    panic::catch_unwind(|| verify_bytes(claim, proof)).unwrap_or(ZKP_INTERNAL_ERROR)
+}
+
+fn verify_bytes(claim: &[u8], proof: &[u8]) -> i32 {
+    let constraints = match decode_claim(claim) {
+        Some(constraints) => constraints,
+        None => return ZKP_INVALID_CLAIM,
+    };
+    let proof = match Proof::from_bytes_with_header(proof) {
+        Some(proof) => proof,
+        None => return ZKP_INVALID_PROOF_ENCODING,
+    };
+    match verify(&constraints, &proof) {
+        Ok(()) => ZKP_OK,
+        Err(_) => ZKP_INVALID_PROOF,
+    }
+}
+
+fn decode_claim(bytes: &[u8]) -> Option<Constraints> {
+    let (&tag, rest) = bytes.split_first()?;
+    if rest.len() != 40 {
+        return None;
+    }
+    let mut size = [0_u8; 8];
+    size.copy_from_slice(&rest[..8]);
+    let size = usize::try_from(u64::from_be_bytes(size)).ok()?;
+    let mut value = [0_u8; 32];
+    value.copy_from_slice(&rest[8..]);
+    let value = U256::from_bytes_be(&value);
+    if value >= FieldElement::MODULUS {
+        return None;
+    }
+    let value = FieldElement::from_montgomery(value);
+    match tag {
+        TAG_FIBONACCI => Some(fibonacci::Claim { index: size, value }.constraints()),
+        TAG_RANGE_CHECK => Some(range_check::Claim { bits: size, value }.constraints()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+    use zkp_stark::{fib_claim, fib_witness, prove, ClaimBytes, Provable};
+
+    fn claim_bytes(tag: u8, claim: &impl ClaimBytes) -> Vec<u8> {
+        let mut bytes = vec![tag];
+        claim.write_claim_bytes(&mut bytes);
+        bytes
+    }
+
+    fn call(claim: &[u8], proof: &[u8]) -> i32 {
+        unsafe { zkp_verify(claim.as_ptr(), claim.len(), proof.as_ptr(), proof.len()) }
+    }
+
+    #[test]
+    fn test_verify() {
+        let claim = fib_claim();
+        let proof = prove(&claim.constraints(), &claim.trace(&fib_witness()))
+            .unwrap()
+            .to_bytes();
+        let encoded = claim_bytes(TAG_FIBONACCI, &claim);
+        assert_eq!(call(&encoded, &proof), ZKP_OK);
+
+        let mut other = encoded.clone();
+        other[0] = TAG_RANGE_CHECK;
+        assert_eq!(call(&other, &proof), ZKP_INVALID_PROOF);
+        let mut tampered = proof.clone();
+        tampered[100] ^= 1;
+        assert_eq!(call(&encoded, &tampered), ZKP_INVALID_PROOF);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let claim = claim_bytes(TAG_FIBONACCI, &(1000_usize, FieldElement::ONE));
+        assert_eq!(call(&claim, &[]), ZKP_INVALID_PROOF_ENCODING);
+        assert_eq!(call(&claim[..20], &[0]), ZKP_INVALID_CLAIM);
+        assert_eq!(call(&[], &[0]), ZKP_INVALID_CLAIM);
+        assert_eq!(
+            call(&claim_bytes(7, &(1000_usize, FieldElement::ONE)), &[0]),
+            ZKP_INVALID_CLAIM
+        );
+        let mut unreduced = claim.clone();
+        unreduced[9..].copy_from_slice(&[0xff; 32]);
+        assert_eq!(call(&unreduced, &[0]), ZKP_INVALID_CLAIM);
+        assert_eq!(
+            unsafe { zkp_verify(ptr::null(), 0, claim.as_ptr(), claim.len()) },
+            ZKP_NULL_POINTER
+        );
+    }
+}
@@ -45,14 +45,11 @@ impl Verifiable for Claim {
 impl Provable<&Witness> for Claim {
     fn trace(&self, witness: &Witness) -> TraceTable {
         let trace_length = self.index.next_power_of_two();
-        let mut trace = TraceTable::new(trace_length, 2);
-        trace[(0, 0)] = 1.into();
-        trace[(0, 1)] = witness.secret.clone();
-        for i in 0..(trace_length - 1) {
-            trace[(i + 1, 0)] = trace[(i, 1)].clone();
-            trace[(i + 1, 1)] = &trace[(i, 0)] + &trace[(i, 1)];
-        }
-        trace
+        TraceTable::from_recurrence(
+            trace_length,
+            vec![1.into(), witness.secret.clone()],
+            |_, previous| vec![previous[1].clone(), &previous[0] + &previous[1]],
+        )
     }
 }
 
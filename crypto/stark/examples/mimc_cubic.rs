@@ -1,7 +1,7 @@
 use zkp_macros_decl::field_element;
-use zkp_primefield::{fft::ifft, FieldElement};
+use zkp_primefield::FieldElement;
 use zkp_stark::{
-    Constraints, DensePolynomial, Provable, RationalExpression, TraceTable, Verifiable,
+    ClaimBytes, Constraints, PeriodicColumn, Provable, RationalExpression, TraceTable, Verifiable,
 };
 use zkp_u256::U256;
 
@@ -33,14 +33,17 @@ pub struct Claim {
     after:  FieldElement,
 }
 
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.before.write_claim_bytes(bytes);
+        self.after.write_claim_bytes(bytes);
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.before.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.after.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = ROUNDS;
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -48,15 +51,9 @@ impl Verifiable for Claim {
         let on_row = |index| (X - g.pow(index)).inv();
         let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1.into());
 
-        let periodic = |coefficients| {
-            Polynomial(
-                DensePolynomial::new(coefficients),
-                Box::new(X.pow(trace_length / 16)),
-            )
-        };
-        let k_coef = periodic(&ifft(&K_COEF.to_vec()));
+        let k_coef = PeriodicColumn::from_values(&K_COEF).expression(trace_length);
 
-        Constraints::from_expressions((trace_length, 1), seed, vec![
+        Constraints::from_expressions((trace_length, 1), self.claim_bytes(), vec![
             // Says the next row for each row is current x_0^alpha + k
             (Trace(0, 1) - (Exp(Box::new(Trace(0, 0)), ALPHA) + k_coef.clone())) * every_row(),
             // Says the first x_0 is the before
@@ -70,13 +67,10 @@ impl Verifiable for Claim {
 
 impl Provable<()> for Claim {
     fn trace(&self, _witness: ()) -> TraceTable {
-        let mut trace = TraceTable::new(ROUNDS, 1);
-
-        let mut prev = self.before.clone();
-        for i in 0..ROUNDS {
-            trace[(i, 0)] = prev.clone();
-            prev = &prev.pow(ALPHA) + &K_COEF[i % 16];
-        }
+        let trace =
+            TraceTable::from_recurrence(ROUNDS, vec![self.before.clone()], |i, previous| {
+                vec![&previous[0].pow(ALPHA) + &K_COEF[(i - 1) % 16]]
+            });
         assert_eq!(trace[(ROUNDS - 1, 0)], self.after);
         trace
     }
@@ -1,7 +1,7 @@
 use zkp_macros_decl::field_element;
-use zkp_primefield::{fft::ifft, FieldElement};
+use zkp_primefield::FieldElement;
 use zkp_stark::{
-    Constraints, DensePolynomial, Provable, RationalExpression, TraceTable, Verifiable,
+    ClaimBytes, Constraints, PeriodicColumn, Provable, RationalExpression, TraceTable, Verifiable,
 };
 use zkp_u256::U256;
 
@@ -34,14 +34,17 @@ pub struct Claim {
     after:  FieldElement,
 }
 
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.before.write_claim_bytes(bytes);
+        self.after.write_claim_bytes(bytes);
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.before.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.after.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = ROUNDS;
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -49,15 +52,9 @@ impl Verifiable for Claim {
         let on_row = |index| (X - g.pow(index)).inv();
         let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1.into());
 
-        let periodic = |coefficients| {
-            Polynomial(
-                DensePolynomial::new(coefficients),
-                Box::new(X.pow(trace_length / 16)),
-            )
-        };
-        let k_coef = periodic(&ifft(&K_COEF.to_vec()));
+        let k_coef = PeriodicColumn::from_values(&K_COEF).expression(trace_length);
 
-        Constraints::from_expressions((trace_length, 3), seed, vec![
+        Constraints::from_expressions((trace_length, 3), self.claim_bytes(), vec![
             // Says x_1 = x_0^2
             (Trace(0, 0) * Trace(0, 0) - Trace(1, 0)) * every_row(),
             // Says x_2 = x_1*x_0
@@ -75,15 +72,14 @@ impl Verifiable for Claim {
 
 impl Provable<()> for Claim {
     fn trace(&self, _witness: ()) -> TraceTable {
-        let mut trace = TraceTable::new(ROUNDS, 3);
-
-        let mut prev = self.before.clone();
-        for i in 0..ROUNDS {
-            trace[(i, 0)] = prev.clone();
-            trace[(i, 1)] = (&trace[(i, 0)]).square();
-            trace[(i, 2)] = &trace[(i, 0)] * &trace[(i, 1)];
-            prev = &trace[(i, 2)] + &K_COEF[i % 16];
-        }
+        let row = |x: FieldElement| {
+            let square = x.square();
+            let cube = &x * &square;
+            vec![x, square, cube]
+        };
+        let trace = TraceTable::from_recurrence(ROUNDS, row(self.before.clone()), |i, previous| {
+            row(&previous[2] + &K_COEF[(i - 1) % 16])
+        });
         assert_eq!(trace[(ROUNDS - 1, 0)], self.after);
         trace
     }
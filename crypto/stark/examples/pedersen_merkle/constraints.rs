@@ -1,22 +1,25 @@
 use super::{
     inputs::Claim,
-    pedersen_points::SHIFT_POINT,
     periodic_columns::{
         LEFT_X_COEFFICIENTS, LEFT_Y_COEFFICIENTS, RIGHT_X_COEFFICIENTS, RIGHT_Y_COEFFICIENTS,
     },
 };
 use std::{prelude::v1::*, vec};
 use zkp_elliptic_curve::Affine;
+use zkp_elliptic_curve_crypto::SHIFT_POINT;
 use zkp_primefield::FieldElement;
-use zkp_stark::{Constraints, DensePolynomial, RationalExpression};
+use zkp_stark::{ClaimBytes, Constraints, PeriodicColumn, RationalExpression};
 
 // TODO: Naming
 #[allow(clippy::module_name_repetitions)]
 pub fn get_pedersen_merkle_constraints(claim: &Claim) -> Constraints {
     use RationalExpression::*;
 
-    let path_length = claim.path_length;
+    // Hashes are padded to a power of two, the root is checked at the end of
+    // the last real hash.
+    let path_length = claim.path_length.next_power_of_two();
     let trace_length = path_length * 256;
+    let root_row = claim.path_length * 256 - 1;
     let root = claim.root.clone();
     let leaf = claim.leaf.clone();
     let field_element_bits = 252;
@@ -27,12 +30,8 @@ pub fn get_pedersen_merkle_constraints(claim: &Claim) -> Constraints {
     };
 
     // Periodic columns
-    let periodic = |coefficients| {
-        Polynomial(
-            DensePolynomial::new(coefficients),
-            Box::new(X.pow(path_length)),
-        )
-    };
+    let periodic =
+        |coefficients| PeriodicColumn::from_coefficients(coefficients).expression(trace_length);
     let periodic_left_x = periodic(&LEFT_X_COEFFICIENTS);
     let periodic_left_y = periodic(&LEFT_Y_COEFFICIENTS);
     let periodic_right_x = periodic(&RIGHT_X_COEFFICIENTS);
@@ -42,7 +41,7 @@ pub fn get_pedersen_merkle_constraints(claim: &Claim) -> Constraints {
     // TODO: Clean this up
     let trace_generator = Constant(FieldElement::root(trace_length).unwrap());
     let on_first_row = |a: RationalExpression| a / (X - Constant(FieldElement::ONE));
-    let on_last_row = |a: RationalExpression| a / (X - trace_generator.pow(trace_length - 1));
+    let on_root_row = |a: RationalExpression| a / (X - trace_generator.pow(root_row));
     let on_hash_end_rows = |a: RationalExpression| {
         a * (X - trace_generator.pow(trace_length - 1))
             / (X.pow(path_length) - trace_generator.pow(path_length * (trace_length - 1)))
@@ -63,7 +62,7 @@ pub fn get_pedersen_merkle_constraints(claim: &Claim) -> Constraints {
     let left_bit = Trace(0, 0) - Trace(0, 1) * 2.into();
     let right_bit = Trace(4, 0) - Trace(4, 1) * 2.into();
 
-    Constraints::from_expressions((trace_length, 8), claim.into(), vec![
+    Constraints::from_expressions((trace_length, 8), claim.claim_bytes(), vec![
         Trace(0, 0),
         Trace(1, 0),
         Trace(2, 0),
@@ -75,7 +74,7 @@ pub fn get_pedersen_merkle_constraints(claim: &Claim) -> Constraints {
         on_first_row(
             (Constant(leaf.clone()) - Trace(0, 0)) * (Constant(leaf.clone()) - Trace(4, 0)),
         ),
-        on_last_row(Constant(root.clone()) - Trace(6, 0)),
+        on_root_row(Constant(root.clone()) - Trace(6, 0)),
         on_hash_end_rows(Trace(6, 0) - Trace(0, 1)) * (Trace(6, 0) - Trace(4, 1)),
         on_hash_start_rows(Trace(6, 0) - Constant(shift_point_x.clone())),
         on_hash_start_rows(Trace(7, 0) - Constant(shift_point_y.clone())),
@@ -143,6 +142,7 @@ mod tests {
         constraints.pow_bits = 0;
         constraints.num_queries = 13;
         constraints.fri_layout = vec![3, 2];
+        constraints.min_security_bits = 0;
 
         let trace = claim.trace(&witness);
         let proof = prove(&constraints, &trace).unwrap();
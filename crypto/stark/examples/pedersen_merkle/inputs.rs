@@ -1,43 +1,117 @@
-use super::{constraints::get_pedersen_merkle_constraints, trace_table::get_trace_table};
-use std::{prelude::v1::*, vec};
+use super::{
+    constraints::get_pedersen_merkle_constraints,
+    trace_table::{get_trace_table, hash},
+};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use zkp_macros_decl::StarkClaim;
 use zkp_primefield::FieldElement;
-use zkp_stark::{Constraints, Provable, TraceTable, Verifiable};
 
-#[derive(PartialEq, Clone)]
+/// Claim that `leaf` is in the Pedersen Merkle tree with the given `root`.
+///
+/// The trace has `256` rows per hash and is padded to a power of two number
+/// of hashes, so claims with path lengths between `2^(k - 1) + 1` and `2^k`
+/// share the same constraint layout and only differ in the row where the
+/// root is checked.
+///
+/// A 4-ary tree with nodes `H(H(a, b), H(c, d))` is a binary tree with two
+/// levels per 4-ary level and can be proven as such, with `H(c, d)` or
+/// `H(a, b)` as the sibling on the upper level.
+// The `order` attribute keeps the channel seed independent of field order.
+#[derive(PartialEq, Clone, StarkClaim)]
 #[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[stark(
+    constraints = "get_pedersen_merkle_constraints",
+    trace = "get_trace_table",
+    witness = "Witness",
+    order = "path_length, root, leaf"
+)]
 pub struct Claim {
     pub path_length: usize,
+    #[cfg_attr(feature = "json", serde(with = "zkp_stark::hex_field"))]
     pub leaf:        FieldElement,
+    #[cfg_attr(feature = "json", serde(with = "zkp_stark::hex_field"))]
     pub root:        FieldElement,
 }
 
+/// Authentication path from the leaf to the root.
+///
+/// With the `json` feature the claim and witness are JSON objects with the
+/// field names as keys and field elements as hexadecimal strings:
+///
+/// ```json
+/// { "path_length": 4, "root": "0x0720...0255", "leaf": "0x0000...0000" }
+/// { "directions": [true, false, true, true], "path": ["0x01", "0x02", "0x03", "0x04"] }
+/// ```
 #[derive(PartialEq, Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 pub struct Witness {
     pub directions: Vec<bool>,
+    #[cfg_attr(feature = "json", serde(with = "zkp_stark::hex_field::vec"))]
     pub path:       Vec<FieldElement>,
 }
 
-impl Verifiable for Claim {
-    fn constraints(&self) -> Constraints {
-        get_pedersen_merkle_constraints(self)
+impl Witness {
+    /// Claim and witness for the leaf at `index` in the Pedersen Merkle tree
+    /// over `leaves`.
+    ///
+    /// Nodes are the [`hash`] of their children. The path lists the siblings
+    /// from the leaf up to the root and a direction is `true` when the sibling
+    /// is the left child, i.e. when the corresponding bit of `index` is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of leaves is not a power of two greater than one
+    /// or if `index` is out of range.
+    // Used by the tests, the benchmark uses a fixed claim.
+    #[allow(dead_code)]
+    pub fn from_leaves(leaves: &[FieldElement], index: usize) -> (Claim, Self) {
+        assert!(leaves.len() > 1 && leaves.len().is_power_of_two());
+        assert!(index < leaves.len());
+        let mut layer = leaves.to_vec();
+        let mut position = index;
+        let mut directions = Vec::new();
+        let mut path = Vec::new();
+        while layer.len() > 1 {
+            directions.push(position % 2 == 1);
+            path.push(layer[position ^ 1].clone());
+            layer = layer
+                .chunks(2)
+                .map(|pair| hash(&pair[0], &pair[1]))
+                .collect();
+            position /= 2;
+        }
+        let claim = Claim {
+            path_length: path.len(),
+            root:        layer[0].clone(),
+            leaf:        leaves[index].clone(),
+        };
+        (claim, Self { directions, path })
     }
 }
 
-#[cfg(feature = "prover")]
-impl Provable<&Witness> for Claim {
-    fn trace(&self, witness: &Witness) -> TraceTable {
-        get_trace_table(self, witness)
+#[cfg(feature = "json")]
+impl Claim {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Claim serialization does not fail")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
     }
 }
 
-impl From<&Claim> for Vec<u8> {
-    fn from(claim: &Claim) -> Self {
-        let mut bytes: Self = vec![];
-        bytes.extend_from_slice(&claim.path_length.to_be_bytes());
-        bytes.extend_from_slice(&claim.root.as_montgomery().to_bytes_be());
-        bytes.extend_from_slice(&claim.leaf.as_montgomery().to_bytes_be());
-        bytes
+#[cfg(feature = "json")]
+impl Witness {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Witness serialization does not fail")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
     }
 }
 
@@ -77,9 +151,59 @@ pub fn short_witness() -> Witness {
 mod tests {
     use super::*;
     use zkp_macros_decl::hex;
+    use zkp_stark::{Provable, Verifiable};
 
     #[test]
     fn claim_writable_correct() {
         assert_eq!(Vec::from(&SHORT_CLAIM), hex!("0000000000000004062b7c2734c31d5b73119a5bfdb460c0411af12fafd42af8ca041fea5ec464d00000000000000000000000000000000000000000000000000000000000000000").to_vec());
     }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_roundtrip() {
+        let json = SHORT_CLAIM.to_json();
+        assert_eq!(Claim::from_json(&json).unwrap(), SHORT_CLAIM);
+        let witness = short_witness();
+        assert_eq!(Witness::from_json(&witness.to_json()).unwrap(), witness);
+    }
+
+    #[test]
+    fn from_leaves_directions() {
+        let mut leaves = vec![FieldElement::ZERO; 16];
+        leaves[0b1100] = field_element!("01");
+        let (claim, witness) = Witness::from_leaves(&leaves, 0b1101);
+        assert_eq!(witness.directions, vec![true, false, true, true]);
+        assert_eq!(witness.path[0], field_element!("01"));
+        assert_eq!(claim.path_length, 4);
+        assert_eq!(claim.leaf, FieldElement::ZERO);
+    }
+
+    #[test]
+    fn from_leaves_consistent() {
+        let leaves: Vec<FieldElement> = (0..16_usize).map(FieldElement::from).collect();
+        for &index in &[0, 6, 15] {
+            let (claim, witness) = Witness::from_leaves(&leaves, index);
+            let trace = get_trace_table(&claim, &witness);
+            assert_eq!(trace[(trace.num_rows() - 1, 6)], claim.root);
+        }
+    }
+
+    #[test]
+    fn padded_path() {
+        let leaves: Vec<FieldElement> = (0..8_usize).map(FieldElement::from).collect();
+        let (claim, witness) = Witness::from_leaves(&leaves, 5);
+        assert_eq!(claim.path_length, 3);
+        let constraints = claim.constraints();
+        assert_eq!(constraints.trace_nrows(), 1024);
+        assert_eq!(constraints.check_trace(&claim.trace(&witness)), Ok(()));
+
+        let wrong_root = Claim {
+            root: FieldElement::ONE,
+            ..claim
+        };
+        assert!(wrong_root
+            .constraints()
+            .check_trace(&wrong_root.trace(&witness))
+            .is_err());
+    }
 }
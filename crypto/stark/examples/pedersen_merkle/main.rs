@@ -1,6 +1,5 @@
 mod constraints;
 mod inputs;
-mod pedersen_points;
 mod periodic_columns;
 mod trace_table;
 
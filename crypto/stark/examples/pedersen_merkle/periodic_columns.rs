@@ -1037,8 +1037,9 @@ pub(crate) const RIGHT_Y_COEFFICIENTS: [FieldElement; 256] = [
 
 #[cfg(test)]
 mod tests {
-    use super::{super::pedersen_points::PEDERSEN_POINTS, *};
+    use super::*;
     use zkp_elliptic_curve::Affine;
+    use zkp_elliptic_curve_crypto::PEDERSEN_POINTS;
     use zkp_primefield::geometric_series::root_series;
     use zkp_stark::DensePolynomial;
 
@@ -1,9 +1,7 @@
-use super::{
-    inputs::{Claim, Witness},
-    pedersen_points::{PEDERSEN_POINTS, SHIFT_POINT},
-};
+use super::inputs::{Claim, Witness};
 use std::prelude::v1::*;
 use zkp_elliptic_curve::Affine;
+use zkp_elliptic_curve_crypto::{PEDERSEN_POINTS, SHIFT_POINT};
 use zkp_primefield::FieldElement;
 use zkp_stark::TraceTable;
 use zkp_u256::U256;
@@ -12,7 +10,7 @@ use zkp_u256::U256;
 #[allow(clippy::module_name_repetitions)]
 pub fn get_trace_table(claim: &Claim, witness: &Witness) -> TraceTable {
     let num_columns = 8;
-    let mut trace = TraceTable::new(claim.path_length * 256, num_columns);
+    let mut trace = TraceTable::new(claim.path_length.next_power_of_two() * 256, num_columns);
 
     let mut row: Row = Row::default();
     row.right.point = Affine::Point {
@@ -20,12 +18,13 @@ pub fn get_trace_table(claim: &Claim, witness: &Witness) -> TraceTable {
         y: FieldElement::ZERO,
     };
 
-    for path_index in 0..claim.path_length {
+    for path_index in 0..claim.path_length.next_power_of_two() {
         for bit_index in 0..256 {
             if bit_index % 256 == 0 {
-                let other_hash = U256::from(&witness.path[path_index]);
+                // Padding hashes continue the path with zero siblings
+                let other_hash = witness.path.get(path_index).map_or(U256::ZERO, U256::from);
                 let (x, _) = get_coordinates(&row.right.point);
-                if witness.directions[path_index] {
+                if witness.directions.get(path_index) == Some(&true) {
                     row = initialize_hash(other_hash, U256::from(x));
                 } else {
                     row = initialize_hash(U256::from(x), other_hash);
@@ -51,6 +50,21 @@ pub fn get_trace_table(claim: &Claim, witness: &Witness) -> TraceTable {
     trace
 }
 
+/// The Pedersen hash computed by the constraints.
+///
+/// This is the bitwise variant of the hash: bit `i` of `left` adds
+/// `PEDERSEN_POINTS[i + 1]` and bit `i` of `right` adds
+/// `PEDERSEN_POINTS[i + 253]` to the shift point. It differs from
+/// [`zkp_elliptic_curve_crypto::pedersen_hash`], which splits each input over
+/// two points.
+pub fn hash(left: &FieldElement, right: &FieldElement) -> FieldElement {
+    let row = (1..256).fold(
+        initialize_hash(U256::from(left), U256::from(right)),
+        |row, bit_index| hash_next_bit(&row, bit_index),
+    );
+    get_coordinates(&row.right.point).0.clone()
+}
+
 fn initialize_hash(left_source: U256, right_source: U256) -> Row {
     let mut row: Row = Row::default();
     row.left.source = left_source;
@@ -136,4 +150,20 @@ mod tests {
         let trace = get_trace_table(&SHORT_CLAIM, &short_witness());
         assert_eq!(trace[(trace.num_rows() - 1, 6)], SHORT_CLAIM.root);
     }
+
+    #[test]
+    fn short_inputs_match_hash() {
+        let witness = short_witness();
+        let root = witness.path.iter().zip(witness.directions.iter()).fold(
+            SHORT_CLAIM.leaf,
+            |node, (sibling, &direction)| {
+                if direction {
+                    hash(sibling, &node)
+                } else {
+                    hash(&node, sibling)
+                }
+            },
+        );
+        assert_eq!(root, SHORT_CLAIM.root);
+    }
 }
@@ -0,0 +1,359 @@
+use super::machine::{Instruction, State, MEMORY, REGISTERS};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+use zkp_stark::{
+    components::{
+        memory::{Access, Memory},
+        range_check::Decomposition,
+    },
+    ClaimBytes, Constraints, Provable, RationalExpression, TraceTable, Verifiable,
+};
+
+/// Claim that running `program` on `input` halts within `trace_length`
+/// steps with `output` in register `0`.
+///
+/// Register `1` starts with a secret value from the witness.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Claim {
+    pub program:      Vec<Instruction>,
+    pub trace_length: usize,
+    pub input:        FieldElement,
+    pub output:       FieldElement,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Witness {
+    pub secret: FieldElement,
+}
+
+// Trace layout
+const PC: usize = 0;
+const REGISTER: usize = PC + 1;
+// The memory access of the step: every step reads or writes one cell
+const ADDRESS: usize = REGISTER + REGISTERS;
+const VALUE: usize = ADDRESS + 1;
+const WRITE: usize = VALUE + 1;
+// Inverse of the register tested by `JumpIfZero`, or zero
+const INVERSE: usize = WRITE + 1;
+// One column per program line, one on the line that is executed, followed
+// by the memory columns
+const SELECTOR: usize = INVERSE + 1;
+
+impl Claim {
+    fn memory(&self) -> Memory {
+        // Gaps are at most the number of steps or cells
+        let gaps = self.trace_length.max(MEMORY).next_power_of_two();
+        Memory {
+            gap_bits:      gaps.trailing_zeros() as usize,
+            decomposition: Decomposition::Bits,
+            first_column:  SELECTOR + self.program.len(),
+        }
+    }
+
+    fn columns(&self) -> usize {
+        SELECTOR + self.program.len() + self.memory().columns()
+    }
+
+    // The memory access of a program line. Lines that do not use memory read
+    // cell zero.
+    fn access(instruction: &Instruction, state: &State) -> Access {
+        use Instruction::*;
+        match instruction {
+            Load(_, address) => {
+                Access {
+                    address: *address,
+                    value:   state.memory[*address].clone(),
+                    write:   false,
+                }
+            }
+            Store(address, register) => {
+                Access {
+                    address: *address,
+                    value:   state.registers[*register].clone(),
+                    write:   true,
+                }
+            }
+            _ => {
+                Access {
+                    address: 0,
+                    value:   state.memory[0].clone(),
+                    write:   false,
+                }
+            }
+        }
+    }
+
+    /// The constraints on a trace table of `trace_length` rows.
+    ///
+    /// Row `i` holds the state before step `i`. Fetching and decoding is done
+    /// by the selector columns: the program is public, so the constraints on
+    /// the next state are a sum over program lines of the selector times the
+    /// effect of that line. Memory is a log of one access per step, which the
+    /// [`Memory`] component checks with a permutation argument. Its running
+    /// product is added in [`constraints`](#method.constraints).
+    ///
+    /// [`Memory`]: ../zkp_stark/components/memory/struct.Memory.html
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        use Instruction::*;
+        use RationalExpression::*;
+
+        // Constraint repetitions
+        let trace_length = self.trace_length;
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator);
+        let on_row = |index| (X - g.pow(index)).inv();
+        let every_row = || (X.pow(trace_length) - 1.into()).inv();
+        let on_transition_rows = || (X - g.pow(trace_length - 1)) * every_row();
+
+        let one = || Constant(FieldElement::ONE);
+        let constant = |value: usize| Constant(FieldElement::from(value));
+        let selector = |line| Trace(SELECTOR + line, 0);
+        let register = |index| Trace(REGISTER + index, 0);
+        let is_zero = |index| one() - register(index) * Trace(INVERSE, 0);
+        let lines = || self.program.iter().enumerate();
+
+        let mut expressions = Vec::new();
+
+        // Selectors are one-hot and encode the program counter
+        expressions.extend(
+            (0..self.program.len())
+                .map(|line| selector(line) * (selector(line) - 1.into()) * every_row()),
+        );
+        expressions.push(
+            ((0..self.program.len())
+                .map(selector)
+                .sum::<RationalExpression>()
+                - 1.into())
+                * every_row(),
+        );
+        expressions.push(
+            (Trace(PC, 0)
+                - (0..self.program.len())
+                    .map(|line| selector(line) * constant(line))
+                    .sum())
+                * every_row(),
+        );
+
+        // The memory access is the one of the executed line
+        expressions.push(
+            (Trace(ADDRESS, 0)
+                - lines()
+                    .map(|(line, instruction)| {
+                        match instruction {
+                            Load(_, address) | Store(address, _) => {
+                                selector(line) * constant(*address)
+                            }
+                            _ => constant(0),
+                        }
+                    })
+                    .sum())
+                * every_row(),
+        );
+        expressions.push(
+            (Trace(WRITE, 0)
+                - lines()
+                    .filter(|(_, instruction)| matches!(instruction, Store(..)))
+                    .map(|(line, _)| selector(line))
+                    .sum())
+                * every_row(),
+        );
+        for (line, instruction) in lines() {
+            if let Store(_, index) = instruction {
+                expressions
+                    .push(selector(line) * (Trace(VALUE, 0) - register(*index)) * every_row());
+            }
+        }
+
+        // The inverse witnesses that a tested register is non-zero
+        for (line, instruction) in lines() {
+            if let JumpIfZero(index, _) = instruction {
+                expressions.push(selector(line) * register(*index) * is_zero(*index) * every_row());
+            }
+        }
+
+        // Transitions
+        let next_pc = lines()
+            .map(|(line, instruction)| {
+                selector(line)
+                    * match instruction {
+                        Jump(target) => constant(*target),
+                        JumpIfZero(index, target) => {
+                            is_zero(*index) * constant(*target)
+                                + (one() - is_zero(*index)) * constant(line + 1)
+                        }
+                        Halt => constant(line),
+                        _ => constant(line + 1),
+                    }
+            })
+            .sum::<RationalExpression>();
+        expressions.push((Trace(PC, 1) - next_pc) * on_transition_rows());
+        for index in 0..REGISTERS {
+            let next = lines()
+                .map(|(line, instruction)| {
+                    selector(line)
+                        * match instruction {
+                            Set(target, value) if *target == index => value.into(),
+                            Instruction::Add(target, left, right) if *target == index => {
+                                register(*left) + register(*right)
+                            }
+                            Instruction::Mul(target, left, right) if *target == index => {
+                                register(*left) * register(*right)
+                            }
+                            Load(target, _) if *target == index => Trace(VALUE, 0),
+                            _ => register(index),
+                        }
+                })
+                .sum::<RationalExpression>();
+            expressions.push((Trace(REGISTER + index, 1) - next) * on_transition_rows());
+        }
+
+        // Boundary constraints. Register 1 is left free for the secret.
+        expressions.push(Trace(PC, 0) * on_row(0));
+        expressions.push((register(0) - (&self.input).into()) * on_row(0));
+        for index in 2..REGISTERS {
+            expressions.push(register(index) * on_row(0));
+        }
+        let halted = lines()
+            .filter(|(_, instruction)| **instruction == Halt)
+            .map(|(line, _)| selector(line))
+            .sum::<RationalExpression>();
+        expressions.push((halted - 1.into()) * on_row(trace_length - 1));
+        expressions.push((register(0) - (&self.output).into()) * on_row(trace_length - 1));
+        expressions.extend(self.memory().expressions(trace_length));
+        expressions
+    }
+}
+
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.program.write_claim_bytes(bytes);
+        self.trace_length.write_claim_bytes(bytes);
+        self.input.write_claim_bytes(bytes);
+        self.output.write_claim_bytes(bytes);
+    }
+}
+
+impl Verifiable for Claim {
+    fn constraints(&self) -> Constraints {
+        use RationalExpression::*;
+        let mut constraints = Constraints::from_expressions(
+            (self.trace_length, self.columns()),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap();
+        let _ = constraints
+            .add_running_product(self.memory().running_product(
+                Trace(ADDRESS, 0),
+                Trace(VALUE, 0),
+                Trace(WRITE, 0),
+            ))
+            .unwrap();
+        constraints
+    }
+}
+
+impl Provable<&Witness> for Claim {
+    /// Execute the program for `trace_length - 1` steps. A program that has
+    /// not halted by then produces a trace that violates the constraints.
+    fn trace(&self, witness: &Witness) -> TraceTable {
+        let mut trace = TraceTable::new(self.trace_length, self.columns());
+        let mut state = State::new(&self.input, &witness.secret);
+        let mut accesses = Vec::with_capacity(self.trace_length);
+        for row in 0..self.trace_length {
+            trace[(row, PC)] = state.pc.into();
+            for (index, value) in state.registers.iter().enumerate() {
+                trace[(row, REGISTER + index)] = value.clone();
+            }
+            let access = Self::access(&self.program[state.pc], &state);
+            trace[(row, ADDRESS)] = access.address.into();
+            trace[(row, VALUE)] = access.value.clone();
+            trace[(row, WRITE)] = u64::from(access.write).into();
+            accesses.push(access);
+            if let Instruction::JumpIfZero(index, _) = self.program[state.pc] {
+                trace[(row, INVERSE)] = state.registers[index].inv().unwrap_or(FieldElement::ZERO);
+            }
+            trace[(row, SELECTOR + state.pc)] = FieldElement::ONE;
+            state.step(&self.program);
+        }
+        self.memory().fill(&mut trace, &accesses);
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine::power_program;
+    use zkp_stark::ProverError;
+
+    fn claim() -> Claim {
+        Claim {
+            program:      power_program(),
+            trace_length: 32,
+            input:        5.into(),
+            output:       243.into(),
+        }
+    }
+
+    #[test]
+    fn tiny_vm_test() {
+        let claim = claim();
+        let witness = Witness { secret: 3.into() };
+        let trace = claim.trace(&witness);
+        assert_eq!(claim.constraints().check_trace(&trace), Ok(()));
+        let proof = claim.prove(&witness).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+    }
+
+    #[test]
+    fn wrong_output() {
+        let claim = Claim {
+            output: 242.into(),
+            ..claim()
+        };
+        let witness = Witness { secret: 3.into() };
+        // The memory constraints follow the output constraint
+        let output = claim.expressions().len() - claim.memory().expressions(32).len() - 1;
+        assert_eq!(
+            claim.prove(&witness),
+            Err(ProverError::ConstraintUnsatisfied {
+                constraint: output,
+                row:        31,
+            })
+        );
+    }
+
+    #[test]
+    fn forged_load() {
+        let claim = Claim {
+            output: 242.into(),
+            ..claim()
+        };
+        let witness = Witness { secret: 3.into() };
+        let mut trace = claim.trace(&witness);
+        // Load a different value than was stored, which is consistent with
+        // the execution but not with the memory log
+        let load = (0..32).find(|&row| trace[(row, PC)] == 7.into()).unwrap();
+        trace[(load, VALUE)] = 242.into();
+        for row in load + 1..32 {
+            trace[(row, REGISTER)] = 242.into();
+        }
+        let constraints = claim.constraints();
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+        assert!(zkp_stark::prove(&constraints, &trace).is_err());
+    }
+
+    #[test]
+    fn not_halted() {
+        let claim = Claim {
+            trace_length: 16,
+            ..claim()
+        };
+        let witness = Witness { secret: 3.into() };
+        let trace = claim.trace(&witness);
+        assert!(claim.constraints().check_trace(&trace).is_err());
+    }
+}
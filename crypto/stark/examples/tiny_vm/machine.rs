@@ -0,0 +1,200 @@
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+use zkp_stark::ClaimBytes;
+
+/// Number of registers.
+pub const REGISTERS: usize = 4;
+
+/// Number of memory cells.
+pub const MEMORY: usize = 4;
+
+/// A TinyVM instruction.
+///
+/// Jump targets are instruction indices in the program and memory addresses
+/// are cell indices.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Instruction {
+    /// `Set(register, value)` sets `register` to `value`.
+    Set(usize, FieldElement),
+    /// `Add(register, left, right)` sets `register` to `left + right`.
+    Add(usize, usize, usize),
+    /// `Mul(register, left, right)` sets `register` to `left * right`.
+    Mul(usize, usize, usize),
+    /// `Load(register, address)` copies a memory cell to `register`.
+    Load(usize, usize),
+    /// `Store(address, register)` copies `register` to a memory cell.
+    Store(usize, usize),
+    /// `Jump(target)` continues at `target`.
+    Jump(usize),
+    /// `JumpIfZero(register, target)` continues at `target` if `register` is
+    /// zero and at the next instruction otherwise.
+    JumpIfZero(usize, usize),
+    /// `Halt` stops the machine. The state no longer changes.
+    Halt,
+}
+
+impl Instruction {
+    // Panics on operands that are out of range.
+    fn validate(&self, program_length: usize) {
+        use Instruction::*;
+        let registers = match self {
+            Set(register, _) | Load(register, _) | Store(_, register) | JumpIfZero(register, _) => {
+                vec![*register]
+            }
+            Add(register, left, right) | Mul(register, left, right) => {
+                vec![*register, *left, *right]
+            }
+            Jump(_) | Halt => vec![],
+        };
+        assert!(registers.iter().all(|register| *register < REGISTERS));
+        match self {
+            Load(_, address) | Store(address, _) => assert!(*address < MEMORY),
+            Jump(target) | JumpIfZero(_, target) => assert!(*target < program_length),
+            _ => {}
+        }
+    }
+}
+
+impl ClaimBytes for Instruction {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        use Instruction::*;
+        match self {
+            Set(register, value) => {
+                0_u8.write_claim_bytes(bytes);
+                register.write_claim_bytes(bytes);
+                value.write_claim_bytes(bytes);
+            }
+            Add(register, left, right) => {
+                1_u8.write_claim_bytes(bytes);
+                (*register, *left, *right).write_claim_bytes(bytes);
+            }
+            Mul(register, left, right) => {
+                2_u8.write_claim_bytes(bytes);
+                (*register, *left, *right).write_claim_bytes(bytes);
+            }
+            Load(register, address) => {
+                3_u8.write_claim_bytes(bytes);
+                (*register, *address).write_claim_bytes(bytes);
+            }
+            Store(address, register) => {
+                4_u8.write_claim_bytes(bytes);
+                (*address, *register).write_claim_bytes(bytes);
+            }
+            Jump(target) => {
+                5_u8.write_claim_bytes(bytes);
+                target.write_claim_bytes(bytes);
+            }
+            JumpIfZero(register, target) => {
+                6_u8.write_claim_bytes(bytes);
+                (*register, *target).write_claim_bytes(bytes);
+            }
+            Halt => 7_u8.write_claim_bytes(bytes),
+        }
+    }
+}
+
+/// Machine state between two instructions.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct State {
+    pub pc:        usize,
+    pub registers: [FieldElement; REGISTERS],
+    pub memory:    [FieldElement; MEMORY],
+}
+
+impl State {
+    /// The initial state with `public` in register `0`, `secret` in register
+    /// `1` and everything else zero.
+    pub fn new(public: &FieldElement, secret: &FieldElement) -> Self {
+        let mut registers = [
+            FieldElement::ZERO,
+            FieldElement::ZERO,
+            FieldElement::ZERO,
+            FieldElement::ZERO,
+        ];
+        registers[0] = public.clone();
+        registers[1] = secret.clone();
+        Self {
+            pc: 0,
+            registers,
+            memory: [
+                FieldElement::ZERO,
+                FieldElement::ZERO,
+                FieldElement::ZERO,
+                FieldElement::ZERO,
+            ],
+        }
+    }
+
+    /// Execute the instruction at `pc`.
+    pub fn step(&mut self, program: &[Instruction]) {
+        use Instruction::*;
+        let instruction = &program[self.pc];
+        instruction.validate(program.len());
+        self.pc += 1;
+        match instruction {
+            Set(register, value) => self.registers[*register] = value.clone(),
+            Add(register, left, right) => {
+                self.registers[*register] = &self.registers[*left] + &self.registers[*right]
+            }
+            Mul(register, left, right) => {
+                self.registers[*register] = &self.registers[*left] * &self.registers[*right]
+            }
+            Load(register, address) => self.registers[*register] = self.memory[*address].clone(),
+            Store(address, register) => self.memory[*address] = self.registers[*register].clone(),
+            Jump(target) => self.pc = *target,
+            JumpIfZero(register, target) => {
+                if self.registers[*register].is_zero() {
+                    self.pc = *target
+                }
+            }
+            Halt => self.pc -= 1,
+        }
+    }
+
+    pub fn is_halted(&self, program: &[Instruction]) -> bool {
+        program[self.pc] == Instruction::Halt
+    }
+}
+
+/// Compute `secret^public` with a loop, passing the result through memory
+/// before it ends up in register `0`.
+pub fn power_program() -> Vec<Instruction> {
+    use Instruction::*;
+    vec![
+        Set(2, FieldElement::ONE),
+        Set(3, -&FieldElement::ONE),
+        JumpIfZero(0, 6),
+        Mul(2, 2, 1),
+        Add(0, 0, 3),
+        Jump(2),
+        Store(0, 2),
+        Load(0, 0),
+        Halt,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_test() {
+        let program = power_program();
+        let mut state = State::new(&5.into(), &3.into());
+        let mut steps = 0;
+        while !state.is_halted(&program) {
+            state.step(&program);
+            steps += 1;
+        }
+        assert_eq!(steps, 25);
+        assert_eq!(state.registers[0], 243.into());
+        assert_eq!(state.memory[0], 243.into());
+
+        // Halting is a fixed point
+        let halted = state.clone();
+        state.step(&program);
+        assert_eq!(state, halted);
+    }
+}
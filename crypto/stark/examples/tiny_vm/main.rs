@@ -0,0 +1,64 @@
+//! TinyVM, a small register machine proven one instruction per row.
+//!
+//! The machine has four registers, four memory cells and a program counter.
+//! Every row of the trace holds the machine state before a step, and the
+//! public program is decoded by one selector column per program line. See
+//! [`machine::Instruction`] for the instruction set.
+//!
+//! Every step makes one memory access, and the accesses are checked with the
+//! [`Memory`] component: a sorted copy of the access log on which consistency
+//! is a local constraint, linked to the execution by a permutation argument
+//! on the interaction trace.
+//!
+//! [`Memory`]: ../zkp_stark/components/memory/struct.Memory.html
+#![warn(clippy::all)]
+mod air;
+mod machine;
+
+use air::{Claim, Witness};
+use env_logger;
+use log::info;
+use machine::{power_program, State};
+use std::time::Instant;
+use zkp_primefield::FieldElement;
+use zkp_stark::{Provable, Verifiable};
+
+fn main() {
+    env_logger::init();
+
+    info!("Constructing witness");
+    let witness = Witness { secret: 3.into() };
+
+    // Run the program once to size the trace and find the output
+    info!("Running program");
+    let program = power_program();
+    let input = FieldElement::from(5);
+    let mut state = State::new(&input, &witness.secret);
+    let mut steps = 0_usize;
+    while !state.is_halted(&program) {
+        state.step(&program);
+        steps += 1;
+    }
+
+    info!("Constructing claim");
+    let claim = Claim {
+        program,
+        trace_length: (steps + 1).next_power_of_two(),
+        input,
+        output: state.registers[0].clone(),
+    };
+    info!("Claim: {:?}", claim);
+
+    // Start timer
+    let start = Instant::now();
+
+    info!("Constructing proof...");
+    let proof = claim.prove(&witness).unwrap();
+
+    // Measure time
+    let duration = start.elapsed();
+    info!("Time elapsed in proof function is: {:?}", duration);
+
+    info!("Verifying proof...");
+    claim.verify(&proof).unwrap();
+}
@@ -4,7 +4,7 @@ use log::info;
 use std::time::Instant;
 use zkp_macros_decl::field_element;
 use zkp_primefield::FieldElement;
-use zkp_stark::{Constraints, Provable, RationalExpression, TraceTable, Verifiable};
+use zkp_stark::{ClaimBytes, Constraints, Provable, RationalExpression, TraceTable, Verifiable};
 use zkp_u256::U256;
 
 const R: FieldElement = field_element!("03");
@@ -17,16 +17,19 @@ struct Claim {
     pub c1_end:   FieldElement,
 }
 
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.c0_start.write_claim_bytes(bytes);
+        self.c1_start.write_claim_bytes(bytes);
+        self.c0_end.write_claim_bytes(bytes);
+        self.c1_end.write_claim_bytes(bytes);
+    }
+}
+
 impl Verifiable for Claim {
     fn constraints(&self) -> Constraints {
         use RationalExpression::*;
 
-        // Seed
-        let mut seed = self.c0_start.as_montgomery().to_bytes_be().to_vec();
-        seed.extend_from_slice(&self.c1_start.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.c0_end.as_montgomery().to_bytes_be());
-        seed.extend_from_slice(&self.c1_end.as_montgomery().to_bytes_be());
-
         // Constraint repetitions
         let trace_length = 1_048_576;
         let trace_generator = FieldElement::root(trace_length).unwrap();
@@ -34,7 +37,7 @@ impl Verifiable for Claim {
         let on_row = |index| (X - g.pow(index)).inv();
         let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1.into());
 
-        Constraints::from_expressions((trace_length, 4), seed, vec![
+        Constraints::from_expressions((trace_length, 4), self.claim_bytes(), vec![
             // Square (Trace(0,0), Trace(1, 0)) and check that it equals (Trace(2,0), Trace(3,0))
             ((Trace(0, 0) * Trace(0, 0) + Constant(R) * Trace(1, 0) * Trace(1, 0) - Trace(2, 0))
                 * every_row()),
@@ -0,0 +1,24 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use zkp_hash::{Hash, MaskedKeccak};
+use zkp_primefield::FieldElement;
+use zkp_stark::{HashChain, RandomGenerator, Replayable, VerifierChannel};
+use zkp_u256::U256;
+
+// Replays a mix of everything the verifier reads. Running out of transcript
+// must not panic.
+fuzz_target!(|data: &[u8]| {
+    let mut channel = VerifierChannel::<HashChain<MaskedKeccak>>::new(data.to_vec());
+    channel.initialize(b"fuzz");
+    let mut read = 0;
+    while !channel.at_end() && !channel.is_exhausted() {
+        let _: Hash = channel.replay();
+        let _: FieldElement = channel.get_random();
+        let _: FieldElement = channel.replay();
+        let _: Vec<FieldElement> = channel.replay_many(3);
+        let _: U256 = channel.replay();
+        let _: [u8; 32] = channel.replay();
+        read += 7 * 32;
+    }
+    assert_eq!(channel.is_exhausted(), read > data.len());
+});
@@ -0,0 +1,19 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use zkp_merkle_tree::{Commitment, Proof};
+use zkp_stark_fuzz::parse_merkle_input;
+
+fuzz_target!(|data: &[u8]| {
+    let input = match parse_merkle_input(data) {
+        Some(input) => input,
+        None => return,
+    };
+    let commitment = match Commitment::from_size_hash(input.size, &input.root) {
+        Ok(commitment) => commitment,
+        Err(_) => return,
+    };
+    let indices: Vec<usize> = input.leaves.iter().map(|(index, _)| *index).collect();
+    if let Ok(proof) = Proof::from_hashes(&commitment, &indices, &input.hashes) {
+        let _ = proof.verify(&input.leaves);
+    }
+});
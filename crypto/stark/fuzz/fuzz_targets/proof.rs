@@ -0,0 +1,10 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use zkp_stark::{verify, Proof};
+use zkp_stark_fuzz::constraints;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(proof) = Proof::from_bytes_with_header(data) {
+        let _ = verify(&constraints(), &proof);
+    }
+});
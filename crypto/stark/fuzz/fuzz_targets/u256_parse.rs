@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use zkp_u256::U256;
+
+fuzz_target!(|data: &[u8]| {
+    let string = match std::str::from_utf8(data) {
+        Ok(string) => string,
+        Err(_) => return,
+    };
+    if let Ok(n) = U256::from_decimal_str(string) {
+        assert_eq!(U256::from_decimal_str(&n.to_decimal_str()), Ok(n));
+    }
+    if let Ok(n) = U256::from_hex_str(string) {
+        assert_eq!(U256::from_hex_str(&format!("{:x}", n)), Ok(n));
+    }
+});
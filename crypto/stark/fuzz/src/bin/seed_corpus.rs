@@ -0,0 +1,55 @@
+//! Write valid inputs for each fuzz target to `corpus/<target>/`.
+use std::{fs, path::Path};
+use zkp_hash::{Blake2s, Blake3, Hasher, MaskedKeccak, Poseidon};
+use zkp_merkle_tree::VectorCommitment;
+use zkp_stark::{prove_with, Provable, Verifiable};
+use zkp_stark_fuzz::{claim, constraints, witness, write_merkle_input, MerkleInput};
+use zkp_u256::U256;
+
+fn write(target: &str, name: &str, bytes: &[u8]) {
+    let directory = Path::new("corpus").join(target);
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join(name), bytes).unwrap();
+}
+
+fn seed_proof<H: Hasher>(name: &str) {
+    let trace = claim().trace(&witness());
+    let proof = prove_with::<H>(&constraints(), &trace).unwrap();
+    assert!(claim().verify(&proof).is_ok());
+    write("proof", name, &proof.to_bytes());
+    write("channel", name, proof.as_bytes());
+}
+
+fn main() {
+    seed_proof::<MaskedKeccak>("fibonacci-keccak");
+    seed_proof::<Poseidon>("fibonacci-poseidon");
+    seed_proof::<Blake2s>("fibonacci-blake2s");
+    seed_proof::<Blake3>("fibonacci-blake3");
+
+    let leaves: Vec<U256> = (0..64_u64).map(|i| U256::from(i * i)).collect();
+    let (commitment, tree) = leaves.commit().unwrap();
+    for (name, indices) in &[("single", vec![5]), ("several", vec![0, 1, 17, 63])] {
+        let proof = tree.open(indices).unwrap();
+        let input = MerkleInput {
+            size:   leaves.len(),
+            root:   commitment.hash().clone(),
+            leaves: indices.iter().map(|&i| (i, leaves[i].clone())).collect(),
+            hashes: proof.hashes().to_vec(),
+        };
+        write("merkle_proof", name, &write_merkle_input(&input));
+    }
+
+    for (name, string) in &[
+        ("zero", "0"),
+        (
+            "decimal",
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935",
+        ),
+        (
+            "hex",
+            "0x0800000000000011000000000000000000000000000000000000000000000001",
+        ),
+    ] {
+        write("u256_parse", name, string.as_bytes());
+    }
+}
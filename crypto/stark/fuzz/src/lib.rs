@@ -0,0 +1,85 @@
+//! Shared inputs for the fuzz targets and the corpus seeds.
+use zkp_hash::Hash;
+use zkp_macros_decl::field_element;
+use zkp_stark::{
+    components::fibonacci::{Claim, Witness},
+    Constraints, Verifiable,
+};
+use zkp_u256::U256;
+
+/// The claim proofs are verified against.
+pub fn claim() -> Claim {
+    Claim {
+        index: 100,
+        value: field_element!("0f3a06a159eba017c7a42f79bc"),
+    }
+}
+
+/// A witness for [`claim`].
+pub fn witness() -> Witness {
+    Witness {
+        secret: field_element!("cafebabe"),
+    }
+}
+
+pub fn constraints() -> Constraints {
+    claim().constraints()
+}
+
+/// Split the Merkle fuzz input into its parts.
+///
+/// The layout is a byte with the binary logarithm of the tree size, a byte
+/// with the number of opened leaves, the 32 byte root, for each leaf a two
+/// byte big-endian index and the 32 byte leaf value, and finally the
+/// decommitment hashes.
+pub fn parse_merkle_input(data: &[u8]) -> Option<MerkleInput> {
+    let (&log_size, data) = data.split_first()?;
+    let (&count, data) = data.split_first()?;
+    if data.len() < 32 + usize::from(count) * 34 {
+        return None;
+    }
+    let (root, data) = data.split_at(32);
+    let (leaves, hashes) = data.split_at(usize::from(count) * 34);
+    Some(MerkleInput {
+        size:   1_usize << (log_size % 16),
+        root:   Hash::new(to_array(root)),
+        leaves: leaves
+            .chunks_exact(34)
+            .map(|chunk| {
+                let index = usize::from(u16::from_be_bytes([chunk[0], chunk[1]]));
+                (index, U256::from_bytes_be(&to_array(&chunk[2..])))
+            })
+            .collect(),
+        hashes: hashes
+            .chunks_exact(32)
+            .map(|chunk| Hash::new(to_array(chunk)))
+            .collect(),
+    })
+}
+
+/// Serialize a Merkle fuzz input, see [`parse_merkle_input`].
+pub fn write_merkle_input(input: &MerkleInput) -> Vec<u8> {
+    let mut result = vec![input.size.trailing_zeros() as u8, input.leaves.len() as u8];
+    result.extend_from_slice(input.root.as_bytes());
+    for (index, leaf) in &input.leaves {
+        result.extend_from_slice(&(*index as u16).to_be_bytes());
+        result.extend_from_slice(&leaf.to_bytes_be());
+    }
+    for hash in &input.hashes {
+        result.extend_from_slice(hash.as_bytes());
+    }
+    result
+}
+
+pub struct MerkleInput {
+    pub size:   usize,
+    pub root:   Hash,
+    pub leaves: Vec<(usize, U256)>,
+    pub hashes: Vec<Hash>,
+}
+
+fn to_array(slice: &[u8]) -> [u8; 32] {
+    let mut result = [0; 32];
+    result.copy_from_slice(slice);
+    result
+}
@@ -0,0 +1,503 @@
+//! Portable JSON description of a constraint system.
+//!
+//! The description contains everything that determines the proof: the trace
+//! dimensions, the channel seed and layout, the proof parameters and the
+//! constraint expressions. Periodic columns and boundary conditions are part
+//! of the expressions, as polynomials in `X` and as denominators.
+//!
+//! Expressions are objects tagged by `op`:
+//!
+//! ```json
+//! { "op": "add", "left": { "op": "trace", "column": 0, "offset": 1 },
+//!   "right": { "op": "neg", "value": { "op": "constant", "value": "0x..05" } } }
+//! ```
+//!
+//! The other operations are `x`, `challenge` (with an `index`), `polynomial`
+//! (with `coefficients`, lowest degree first, and an `argument`), `mul`, `inv`
+//! and `exp` (with `base` and `exponent`). Field elements are 64 digit
+//! hexadecimal strings of their canonical value.
+//!
+//! The running products of the interaction trace are listed with their
+//! `numerator` and `denominator` expressions. Their constraints are included
+//! in the constraint list.
+//!
+//! An external commitment to the leading trace columns has the number of
+//! `columns` and the hexadecimal Merkle `root`. Like the running products it
+//! is already part of the channel seed.
+use crate::{
+    channel::{ChannelLayout, CounterLayout, FieldEncoding},
+    constraints::{Constraints, Error as ConstraintError},
+    external_commitment::ExternalCommitment,
+    polynomial::DensePolynomial,
+    rational_expression::RationalExpression,
+    running_product::RunningProduct,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{convert::TryInto, fmt, prelude::v1::*};
+use zkp_hash::Hash;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+#[derive(Debug)]
+pub enum Error {
+    Json(serde_json::Error),
+    InvalidChannelSeed(hex::FromHexError),
+    InvalidRoot(hex::FromHexError),
+    Constraints(ConstraintError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match self {
+            Json(error) => write!(f, "Invalid AIR description: {}", error),
+            InvalidChannelSeed(error) => write!(f, "Invalid channel seed: {}", error),
+            InvalidRoot(error) => write!(f, "Invalid external commitment root: {}", error),
+            Constraints(error) => write!(f, "Invalid constraints: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<ConstraintError> for Error {
+    fn from(error: ConstraintError) -> Self {
+        Self::Constraints(error)
+    }
+}
+
+// Field element in canonical hexadecimal notation
+struct Hex(FieldElement);
+
+impl Serialize for Hex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let n = U256::from(&self.0);
+        serializer.serialize_str(&format!(
+            "0x{:016x}{:016x}{:016x}{:016x}",
+            n.c3, n.c2, n.c1, n.c0
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for Hex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let n = U256::from_hex_str(&string)
+            .map_err(|_| de::Error::custom("invalid hexadecimal number"))?;
+        if n >= FieldElement::MODULUS {
+            return Err(de::Error::custom("field element out of range"));
+        }
+        Ok(Self(FieldElement::from(n)))
+    }
+}
+
+/// Serde support for field elements as canonical hexadecimal strings.
+///
+/// Use `#[serde(with = "zkp_stark::hex_field")]` on `FieldElement` fields and
+/// `#[serde(with = "zkp_stark::hex_field::vec")]` on `Vec<FieldElement>`
+/// fields. Values are written as `0x` followed by 64 hexadecimal digits.
+/// Shorter strings are accepted when reading, values not less than the
+/// modulus are rejected.
+pub mod hex_field {
+    use super::Hex;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use zkp_primefield::FieldElement;
+
+    pub fn serialize<S: Serializer>(
+        value: &FieldElement,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(&Hex(value.clone()), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<FieldElement, D::Error> {
+        Hex::deserialize(deserializer).map(|hex| hex.0)
+    }
+
+    /// Serde support for sequences of field elements.
+    pub mod vec {
+        use super::Hex;
+        use serde::{Deserialize, Deserializer, Serializer};
+        use std::prelude::v1::*;
+        use zkp_primefield::FieldElement;
+
+        pub fn serialize<S: Serializer>(
+            values: &[FieldElement],
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(values.iter().cloned().map(Hex))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<FieldElement>, D::Error> {
+            let values = Vec::<Hex>::deserialize(deserializer)?;
+            Ok(values.into_iter().map(|hex| hex.0).collect())
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Expression {
+    X,
+    Constant {
+        value: Hex,
+    },
+    Trace {
+        column: usize,
+        offset: isize,
+    },
+    Challenge {
+        index: usize,
+    },
+    Polynomial {
+        coefficients: Vec<Hex>,
+        argument:     Box<Expression>,
+    },
+    Add {
+        left:  Box<Expression>,
+        right: Box<Expression>,
+    },
+    Neg {
+        value: Box<Expression>,
+    },
+    Mul {
+        left:  Box<Expression>,
+        right: Box<Expression>,
+    },
+    Inv {
+        value: Box<Expression>,
+    },
+    Exp {
+        base:     Box<Expression>,
+        exponent: usize,
+    },
+}
+
+impl From<&RationalExpression> for Expression {
+    fn from(expression: &RationalExpression) -> Self {
+        use RationalExpression::*;
+        let boxed = |expression: &RationalExpression| Box::new(Self::from(expression));
+        match expression {
+            X => Self::X,
+            Constant(value) => {
+                Self::Constant {
+                    value: Hex(value.clone()),
+                }
+            }
+            &Trace(column, offset) => Self::Trace { column, offset },
+            &Challenge(index) => Self::Challenge { index },
+            Polynomial(p, a) => {
+                Self::Polynomial {
+                    coefficients: p.coefficients().iter().cloned().map(Hex).collect(),
+                    argument:     boxed(a),
+                }
+            }
+            Add(a, b) => {
+                Self::Add {
+                    left:  boxed(a),
+                    right: boxed(b),
+                }
+            }
+            Neg(a) => Self::Neg { value: boxed(a) },
+            Mul(a, b) => {
+                Self::Mul {
+                    left:  boxed(a),
+                    right: boxed(b),
+                }
+            }
+            Inv(a) => Self::Inv { value: boxed(a) },
+            Exp(a, e) => {
+                Self::Exp {
+                    base:     boxed(a),
+                    exponent: *e,
+                }
+            }
+        }
+    }
+}
+
+impl From<&Expression> for RationalExpression {
+    fn from(expression: &Expression) -> Self {
+        use RationalExpression::*;
+        let boxed = |expression: &Expression| Box::new(Self::from(expression));
+        match expression {
+            Expression::X => X,
+            Expression::Constant { value } => Constant(value.0.clone()),
+            &Expression::Trace { column, offset } => Trace(column, offset),
+            &Expression::Challenge { index } => Challenge(index),
+            Expression::Polynomial {
+                coefficients,
+                argument,
+            } => {
+                // Dense polynomials have a power of two number of coefficients
+                let mut coefficients: Vec<_> = coefficients.iter().map(|c| c.0.clone()).collect();
+                let size = std::cmp::max(coefficients.len(), 1).next_power_of_two();
+                coefficients.resize(size, FieldElement::ZERO);
+                Polynomial(DensePolynomial::new(&coefficients), boxed(argument))
+            }
+            Expression::Add { left, right } => Add(boxed(left), boxed(right)),
+            Expression::Neg { value } => Neg(boxed(value)),
+            Expression::Mul { left, right } => Mul(boxed(left), boxed(right)),
+            Expression::Inv { value } => Inv(boxed(value)),
+            Expression::Exp { base, exponent } => Exp(boxed(base), *exponent),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Counter {
+    Padded,
+    Compact,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Encoding {
+    Montgomery,
+    Standard,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Layout {
+    counter:        Counter,
+    field_encoding: Encoding,
+    #[serde(default)]
+    labels:         bool,
+}
+
+impl From<ChannelLayout> for Layout {
+    fn from(layout: ChannelLayout) -> Self {
+        Self {
+            counter:        match layout.counter {
+                CounterLayout::Padded => Counter::Padded,
+                CounterLayout::Compact => Counter::Compact,
+            },
+            field_encoding: match layout.field_encoding {
+                FieldEncoding::Montgomery => Encoding::Montgomery,
+                FieldEncoding::Standard => Encoding::Standard,
+            },
+            labels:         layout.labels,
+        }
+    }
+}
+
+impl From<&Layout> for ChannelLayout {
+    fn from(layout: &Layout) -> Self {
+        Self {
+            counter:        match layout.counter {
+                Counter::Padded => CounterLayout::Padded,
+                Counter::Compact => CounterLayout::Compact,
+            },
+            field_encoding: match layout.field_encoding {
+                Encoding::Montgomery => FieldEncoding::Montgomery,
+                Encoding::Standard => FieldEncoding::Standard,
+            },
+            labels:         layout.labels,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Air {
+    trace_nrows:         usize,
+    trace_ncolumns:      usize,
+    channel_seed:        String,
+    channel_layout:      Layout,
+    blowup:              usize,
+    pow_bits:            usize,
+    num_queries:         usize,
+    fri_layout:          Vec<usize>,
+    min_security_bits:   usize,
+    constraints:         Vec<Expression>,
+    #[serde(default)]
+    running_products:    Vec<Product>,
+    #[serde(default)]
+    zero_knowledge:      bool,
+    #[serde(default)]
+    external_commitment: Option<External>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct External {
+    columns: usize,
+    root:    String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Product {
+    numerator:   Expression,
+    denominator: Expression,
+}
+
+impl Constraints {
+    /// The constraint system as a portable JSON AIR description.
+    pub fn to_json(&self) -> String {
+        let air = Air {
+            trace_nrows:         self.trace_nrows(),
+            trace_ncolumns:      self.trace_ncolumns(),
+            channel_seed:        hex::encode(self.channel_seed()),
+            channel_layout:      self.channel_layout.into(),
+            blowup:              self.blowup,
+            pow_bits:            self.pow_bits,
+            num_queries:         self.num_queries,
+            fri_layout:          self.fri_layout.clone(),
+            min_security_bits:   self.min_security_bits,
+            constraints:         self.expressions().iter().map(Expression::from).collect(),
+            running_products:    self
+                .running_products()
+                .iter()
+                .map(|product| {
+                    Product {
+                        numerator:   product.numerator().into(),
+                        denominator: product.denominator().into(),
+                    }
+                })
+                .collect(),
+            zero_knowledge:      self.zero_knowledge,
+            external_commitment: self.external_commitment().map(|external| {
+                External {
+                    columns: external.columns(),
+                    root:    hex::encode(external.root().as_bytes()),
+                }
+            }),
+        };
+        serde_json::to_string_pretty(&air).expect("AIR serialization does not fail")
+    }
+
+    /// Load constraints from a JSON AIR description created by
+    /// [`to_json`](#method.to_json).
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let air: Air = serde_json::from_str(json)?;
+        let channel_seed = hex::decode(&air.channel_seed).map_err(Error::InvalidChannelSeed)?;
+        let mut constraints = Self::from_expressions(
+            (air.trace_nrows, air.trace_ncolumns),
+            channel_seed,
+            air.constraints
+                .iter()
+                .map(RationalExpression::from)
+                .collect(),
+        )?;
+        constraints.channel_layout = (&air.channel_layout).into();
+        constraints.blowup = air.blowup;
+        constraints.pow_bits = air.pow_bits;
+        constraints.num_queries = air.num_queries;
+        constraints.fri_layout = air.fri_layout;
+        constraints.min_security_bits = air.min_security_bits;
+        constraints.zero_knowledge = air.zero_knowledge;
+        if let Some(external) = &air.external_commitment {
+            let root: [u8; 32] = hex::decode(&external.root)
+                .map_err(Error::InvalidRoot)?
+                .as_slice()
+                .try_into()
+                .map_err(|_| Error::InvalidRoot(hex::FromHexError::InvalidStringLength))?;
+            if external.columns == 0 || external.columns >= air.trace_ncolumns {
+                return Err(ConstraintError::InvalidExternalCommitment.into());
+            }
+            constraints.external_commitment =
+                Some(ExternalCommitment::new(external.columns, Hash::new(root)));
+        }
+        // Their constraints are already in the expressions
+        constraints.running_products = air
+            .running_products
+            .iter()
+            .map(|product| {
+                RunningProduct::new((&product.numerator).into(), (&product.denominator).into())
+            })
+            .collect();
+        Ok(constraints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        traits::tests::{fib_claim, Claim},
+        Verifiable,
+    };
+
+    #[test]
+    fn test_expression_format() {
+        use RationalExpression::*;
+        let expression = Trace(0, 1) - 5.into();
+        let json = serde_json::to_string(&Expression::from(&expression)).unwrap();
+        assert_eq!(
+            json,
+            "{\"op\":\"add\",\"left\":{\"op\":\"trace\",\"column\":0,\"offset\":1},\"right\":{\"\
+             op\":\"neg\",\"value\":{\"op\":\"constant\",\"value\":\"\
+             0x0000000000000000000000000000000000000000000000000000000000000005\"}}}"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let claim = fib_claim();
+        let mut constraints = claim.constraints();
+        constraints.channel_layout.counter = CounterLayout::Compact;
+        constraints.fri_layout = vec![2, 1];
+        constraints.zero_knowledge = true;
+        let json = constraints.to_json();
+        let loaded = Constraints::from_json(&json).unwrap();
+        assert_eq!(loaded.to_json(), json);
+        assert_eq!(loaded.expressions(), constraints.expressions());
+        assert_eq!(loaded.channel_seed(), constraints.channel_seed());
+        assert_eq!(loaded.channel_layout, constraints.channel_layout);
+        assert_eq!(loaded.fri_layout, constraints.fri_layout);
+        assert!(loaded.zero_knowledge);
+    }
+
+    #[test]
+    fn test_roundtrip_running_product() {
+        use RationalExpression::*;
+        let mut constraints = fib_claim().constraints();
+        let product = RunningProduct::new(Challenge(0) - Trace(0, 0), Challenge(0) - Trace(1, 0));
+        let _ = constraints.add_running_product(product).unwrap();
+        let json = constraints.to_json();
+        let loaded = Constraints::from_json(&json).unwrap();
+        assert_eq!(loaded.to_json(), json);
+        assert_eq!(loaded.expressions(), constraints.expressions());
+        assert_eq!(loaded.running_products(), constraints.running_products());
+    }
+
+    #[test]
+    fn test_roundtrip_external_commitment() {
+        let mut constraints = fib_claim().constraints();
+        let commitment = ExternalCommitment::new(1, Hash::new([7; 32]));
+        constraints.add_external_commitment(commitment).unwrap();
+        let json = constraints.to_json();
+        let loaded = Constraints::from_json(&json).unwrap();
+        assert_eq!(loaded.to_json(), json);
+        assert_eq!(loaded.channel_seed(), constraints.channel_seed());
+        assert_eq!(
+            loaded.external_commitment(),
+            constraints.external_commitment()
+        );
+        assert!(Constraints::from_json(&json.replace("\"columns\": 1", "\"columns\": 2")).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let json = Claim {
+            index: 1000,
+            value: FieldElement::ONE,
+        }
+        .constraints()
+        .to_json()
+        .replace(
+            "0x0000000000000000000000000000000000000000000000000000000000000001",
+            "0x0800000000000011000000000000000000000000000000000000000000000001",
+        );
+        assert!(Constraints::from_json(&json).is_err());
+    }
+}
@@ -1,7 +1,9 @@
 use crate::{
     polynomial::DensePolynomial, rational_expression::RationalExpression, trace_table::TraceTable,
 };
-use std::{cmp::min, ops::Neg, prelude::v1::*};
+#[cfg(feature = "codegen")]
+use std::sync::Arc;
+use std::{cmp::min, collections::BTreeMap, ops::Neg, prelude::v1::*};
 use tiny_keccak::Keccak;
 use zkp_primefield::{invert_batch_src_dst, FieldElement};
 use zkp_u256::U256;
@@ -56,6 +58,9 @@ pub(crate) struct AlgebraicGraph {
     /// Evaluation nodes in causal order.
     nodes: Vec<Node>,
 
+    /// Node index by hash, for hash-consing.
+    hashes: BTreeMap<U256, Index>,
+
     /// Current row
     row: usize,
 }
@@ -166,6 +171,7 @@ impl AlgebraicGraph {
             trace_blowup,
             seed: from_entropy(keccak),
             nodes: vec![],
+            hashes: BTreeMap::new(),
             row: 0,
         }
     }
@@ -295,9 +301,9 @@ impl AlgebraicGraph {
     /// returned instead.
     fn op(&mut self, operation: Operation) -> Index {
         let hash = self.hash(&operation);
-        if let Some(index) = self.nodes.iter().position(|n| n.hash == hash) {
+        if let Some(index) = self.hashes.get(hash.as_montgomery()) {
             // Return existing node index
-            Index(index)
+            *index
         } else {
             // Recognize expressions evaluating to zero or one. Simplify other
             // expressions.
@@ -308,7 +314,8 @@ impl AlgebraicGraph {
             };
 
             // Create new node
-            let index = self.nodes.len();
+            let index = Index(self.nodes.len());
+            let _ = self.hashes.insert(hash.as_montgomery().clone(), index);
             let period = self.period(&operation);
             self.nodes.push(Node {
                 op: operation,
@@ -317,12 +324,14 @@ impl AlgebraicGraph {
                 values: CHUNK_INIT,
                 note: FieldElement::ZERO,
             });
-            Index(index)
+            index
         }
     }
 
     /// Adds a rational expression to the graph and return the result node
     /// index.
+    ///
+    /// Challenges need to be replaced by their values first.
     pub(crate) fn expression(&mut self, expr: RationalExpression) -> Index {
         use Operation as Op;
         use RationalExpression as RE;
@@ -330,6 +339,7 @@ impl AlgebraicGraph {
             RE::X => self.op(Op::Coset(self.cofactor.clone(), self.coset_size)),
             RE::Constant(a) => self.op(Op::Constant(a)),
             RE::Trace(i, j) => self.op(Op::Trace(i, j)),
+            RE::Challenge(_) => panic!("Challenges must be replaced before evaluation"),
             RE::Polynomial(p, a) => {
                 let a = self.expression(*a);
                 self.op(Op::Poly(p, a))
@@ -434,6 +444,12 @@ impl AlgebraicGraph {
             i += 1;
             used[i - 1]
         });
+        self.hashes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.hash.as_montgomery().clone(), Index(i)))
+            .collect();
 
         numbers[tip.0]
     }
@@ -501,21 +517,19 @@ impl AlgebraicGraph {
             } = &mut current[0];
             match op {
                 Trace(c, o) => {
-                    // TODO: Handle all the casting more elegantly
+                    // Resolve the offset once per chunk so the inner loop
+                    // only does unsigned wrapping.
                     // Sizes are small enough
                     #[allow(clippy::cast_possible_wrap)]
                     let n = trace_table.num_rows() as isize;
+                    // Sizes are small enough
+                    #[allow(clippy::cast_possible_wrap)]
+                    let shift = (self.trace_blowup as isize * *o).rem_euclid(n);
+                    // `rem_euclid` is non-negative
+                    #[allow(clippy::cast_sign_loss)]
+                    let (n, shift) = (n as usize, shift as usize);
                     for i in 0..CHUNK_SIZE {
-                        // Sizes are small enough
-                        #[allow(clippy::cast_possible_wrap)]
-                        let trace_blowup = self.trace_blowup as isize;
-                        // Sizes are small enough
-                        #[allow(clippy::cast_possible_wrap)]
-                        let row = (self.row + i) as isize;
-                        let row = (n + row + trace_blowup * *o) % n;
-                        // Sizes are small enough
-                        #[allow(clippy::cast_sign_loss)]
-                        let row = row as usize;
+                        let row = (self.row + i + shift) % n;
                         values[i] = trace_table[(row, *c)].clone();
                     }
                 }
@@ -574,6 +588,263 @@ impl AlgebraicGraph {
     }
 }
 
+/// Values of a node for one chunk of rows.
+#[cfg(feature = "codegen")]
+type Values = [FieldElement; CHUNK_SIZE];
+
+/// Sets the values of a node for the chunk starting at a row.
+#[cfg(feature = "codegen")]
+type Init = Box<dyn Fn(&mut Values, usize) + Send + Sync>;
+
+/// Computes the values of a node for the chunk starting at a row from the
+/// values of the previous nodes and the trace table.
+#[cfg(feature = "codegen")]
+type Step = Box<dyn Fn(&[Values], &mut Values, &TraceTable, usize) + Send + Sync>;
+
+/// An [`AlgebraicGraph`] compiled to straight-line closures.
+///
+/// Each node becomes a closure specialized for its operation, with the
+/// operand indices, constants and trace offsets captured. Evaluating a chunk
+/// runs the closures in order without matching on the operations. Nodes that
+/// do not change between chunks, such as constants, short cosets and short
+/// lookup tables, only have an `Init` and are skipped during evaluation.
+///
+/// [`AlgebraicGraph`]: struct.AlgebraicGraph.html
+#[cfg(feature = "codegen")]
+#[derive(Clone)]
+pub(crate) struct CompiledGraph {
+    /// Node index and closure for the nodes that need initialization.
+    inits: Arc<Vec<(usize, Init)>>,
+
+    /// Node index and closure for the nodes evaluated on every chunk.
+    steps: Arc<Vec<(usize, Step)>>,
+
+    /// Values of all nodes for the current chunk.
+    values: Vec<Values>,
+
+    /// Current row
+    row: usize,
+}
+
+#[cfg(feature = "codegen")]
+impl AlgebraicGraph {
+    /// Compile the graph to closures.
+    ///
+    /// The result evaluates to the same values as [`next`]. Lookup tables
+    /// and tree shaking should be done before compiling.
+    ///
+    /// [`next`]: #method.next
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn compile(&self) -> CompiledGraph {
+        use Operation::*;
+        let mut inits: Vec<(usize, Init)> = Vec::new();
+        let mut steps: Vec<(usize, Step)> = Vec::new();
+        for (index, node) in self.nodes.iter().enumerate() {
+            match &node.op {
+                Constant(a) => {
+                    let a = a.clone();
+                    inits.push((
+                        index,
+                        Box::new(move |values, _| {
+                            for value in values.iter_mut() {
+                                *value = a.clone();
+                            }
+                        }),
+                    ));
+                }
+                Coset(c, s) => {
+                    let root = FieldElement::root(*s).unwrap();
+                    // Longer cosets are advanced by a factor every chunk,
+                    // including the first, so they start a chunk early.
+                    let (start, factor) = if *s > CHUNK_SIZE {
+                        let factor = root.pow(CHUNK_SIZE);
+                        (c / &factor, Some(factor))
+                    } else {
+                        (c.clone(), None)
+                    };
+                    inits.push((
+                        index,
+                        Box::new(move |values, row| {
+                            let mut acc = &start * root.pow(row);
+                            for value in values.iter_mut() {
+                                *value = acc.clone();
+                                acc *= &root;
+                            }
+                        }),
+                    ));
+                    if let Some(factor) = factor {
+                        steps.push((
+                            index,
+                            Box::new(move |_, values, _, _| {
+                                for value in values.iter_mut() {
+                                    *value *= &factor;
+                                }
+                            }),
+                        ));
+                    }
+                }
+                Trace(column, offset) => {
+                    let column = *column;
+                    // Sizes are small enough
+                    #[allow(clippy::cast_possible_wrap)]
+                    let offset = self.trace_blowup as isize * *offset;
+                    steps.push((
+                        index,
+                        Box::new(move |_, values, trace_table, row| {
+                            // Sizes are small enough
+                            #[allow(clippy::cast_possible_wrap)]
+                            let n = trace_table.num_rows() as isize;
+                            // `rem_euclid` is non-negative
+                            #[allow(clippy::cast_sign_loss)]
+                            let (n, shift) = (n as usize, offset.rem_euclid(n) as usize);
+                            for (i, value) in values.iter_mut().enumerate() {
+                                *value = trace_table[((row + i + shift) % n, column)].clone();
+                            }
+                        }),
+                    ));
+                }
+                Add(a, b) => {
+                    let (a, b) = (a.0, b.0);
+                    steps.push((
+                        index,
+                        Box::new(move |previous, values, _, _| {
+                            let (a, b) = (&previous[a], &previous[b]);
+                            for (value, (a, b)) in values.iter_mut().zip(a.iter().zip(b.iter())) {
+                                *value = a + b;
+                            }
+                        }),
+                    ));
+                }
+                Neg(a) => {
+                    let a = a.0;
+                    steps.push((
+                        index,
+                        Box::new(move |previous, values, _, _| {
+                            for (value, a) in values.iter_mut().zip(previous[a].iter()) {
+                                *value = a.neg();
+                            }
+                        }),
+                    ));
+                }
+                Mul(a, b) => {
+                    // Multiplications by a constant don't read its values.
+                    let constant = match (&self[*a].op, &self[*b].op) {
+                        (Constant(c), _) => Some((c.clone(), b.0)),
+                        (_, Constant(c)) => Some((c.clone(), a.0)),
+                        _ => None,
+                    };
+                    if let Some((c, a)) = constant {
+                        steps.push((
+                            index,
+                            Box::new(move |previous, values, _, _| {
+                                for (value, a) in values.iter_mut().zip(previous[a].iter()) {
+                                    *value = a * &c;
+                                }
+                            }),
+                        ));
+                    } else {
+                        let (a, b) = (a.0, b.0);
+                        steps.push((
+                            index,
+                            Box::new(move |previous, values, _, _| {
+                                let (a, b) = (&previous[a], &previous[b]);
+                                for (value, (a, b)) in values.iter_mut().zip(a.iter().zip(b.iter()))
+                                {
+                                    *value = a * b;
+                                }
+                            }),
+                        ));
+                    }
+                }
+                Inv(a) => {
+                    let a = a.0;
+                    steps.push((
+                        index,
+                        Box::new(move |previous, values, _, _| {
+                            invert_batch_src_dst(&previous[a], values);
+                        }),
+                    ));
+                }
+                Exp(a, 2) => {
+                    let a = a.0;
+                    steps.push((
+                        index,
+                        Box::new(move |previous, values, _, _| {
+                            for (value, a) in values.iter_mut().zip(previous[a].iter()) {
+                                *value = a.square();
+                            }
+                        }),
+                    ));
+                }
+                Exp(a, e) => {
+                    let (a, e) = (a.0, *e);
+                    steps.push((
+                        index,
+                        Box::new(move |previous, values, _, _| {
+                            for (value, a) in values.iter_mut().zip(previous[a].iter()) {
+                                *value = a.pow(e);
+                            }
+                        }),
+                    ));
+                }
+                Poly(p, a) => {
+                    let (p, a) = (p.clone(), a.0);
+                    steps.push((
+                        index,
+                        Box::new(move |previous, values, _, _| {
+                            for (value, a) in values.iter_mut().zip(previous[a].iter()) {
+                                *value = p.evaluate(a);
+                            }
+                        }),
+                    ));
+                }
+                Lookup(Table(table)) => {
+                    let (table, short) = (table.clone(), table.len() <= CHUNK_SIZE);
+                    let copy = move |values: &mut Values, row: usize| {
+                        for (i, value) in values.iter_mut().enumerate() {
+                            *value = table[(row + i) % table.len()].clone();
+                        }
+                    };
+                    if short {
+                        inits.push((index, Box::new(copy)));
+                    } else {
+                        steps.push((index, Box::new(move |_, values, _, row| copy(values, row))));
+                    }
+                }
+            }
+        }
+        CompiledGraph {
+            inits:  Arc::new(inits),
+            steps:  Arc::new(steps),
+            values: vec![CHUNK_INIT; self.nodes.len()],
+            row:    0,
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+impl CompiledGraph {
+    pub(crate) fn init(&mut self, start: usize) {
+        assert_eq!(start % CHUNK_SIZE, 0);
+        self.row = start;
+        for (index, init) in self.inits.iter() {
+            init(&mut self.values[*index], start);
+        }
+    }
+
+    pub(crate) fn next(&mut self, trace_table: &TraceTable) -> FieldElement {
+        if self.row % CHUNK_SIZE == 0 {
+            for (index, step) in self.steps.iter() {
+                let (previous, current) = self.values.split_at_mut(*index);
+                step(previous, &mut current[0], trace_table, self.row);
+            }
+        }
+        let result = self.values.last().unwrap()[self.row % CHUNK_SIZE].clone();
+        self.row += 1;
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -614,6 +885,56 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_shared_subexpressions() {
+        // Nodes a, b, a * b, a * b + a, (a * b + a) * (a * b) and the sum,
+        // where b * a is recognized as a * b.
+        let a = || RE::Trace(0, 0);
+        let b = || RE::Trace(1, 0);
+        let expression = (a() * b() + a()) * (a() * b()) + b() * a();
+        let mut dag = AlgebraicGraph::new(&FieldElement::GENERATOR, 1024, 2);
+        let tip = dag.expression(expression);
+        assert_eq!(dag.tree_shake(tip), Index(5));
+        assert_eq!(dag.nodes.len(), 6);
+
+        // Hash-consing still works after renumbering
+        assert_eq!(dag.expression(b() * a()), Index(2));
+        assert_eq!(dag.nodes.len(), 6);
+    }
+
+    #[cfg(feature = "codegen")]
+    #[test]
+    fn test_compiled_matches_interpreted() {
+        let trace = TraceTable::from_rows(256, 2, |i| {
+            vec![FieldElement::from(i * i + 3), FieldElement::from(5 * i + 1)]
+        });
+        let p = DensePolynomial::new(&[
+            FieldElement::from(3),
+            FieldElement::from(1),
+            FieldElement::from(4),
+            FieldElement::from(1),
+        ]);
+        let poly = |a: RE| RE::Polynomial(p.clone(), Box::new(a));
+        let expression = (RE::Trace(0, 0) * RE::Trace(1, -1) + RE::from(7) * RE::Trace(0, 1))
+            .pow(3)
+            / (RE::X - RE::from(1))
+            + poly(RE::X.pow(8))
+            + poly(RE::X.pow(64)) * RE::Trace(1, 0).pow(2)
+            - RE::X.pow(64);
+        let mut dag = AlgebraicGraph::new(&FieldElement::GENERATOR, 256, 4);
+        let tip = dag.expression(expression);
+        dag.lookup_tables();
+        let _ = dag.tree_shake(tip);
+        let mut compiled = dag.compile();
+        for start in &[0, 128] {
+            dag.init(*start);
+            compiled.init(*start);
+            for _ in *start..256 {
+                assert_eq!(compiled.next(&trace), dag.next(&trace));
+            }
+        }
+    }
+
     #[test]
     fn test_hash_x_is_seed() {
         let mut dag = AlgebraicGraph::new(&FieldElement::GENERATOR, 1024, 2);
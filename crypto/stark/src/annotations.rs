@@ -0,0 +1,220 @@
+//! Checking proofs against StarkWare proof annotations.
+//!
+//! StarkWare's prover can write an annotation file next to a proof that
+//! describes every channel message. Messages from the prover to the verifier
+//! include their byte range in the proof:
+//!
+//! ```text
+//! P->V[0:32]: /fibonacci/STARK/Original/Commit on Trace: Hash(0x4ef92de4d2d3594d35f0123ed8187d60542188f5)
+//! V->P: /fibonacci/STARK/Original/Constraint Coefficients: Field Element(0x4b8...)
+//! ```
+//!
+//! [`check_annotations`] compares our proof bytes with every `P->V` line, so
+//! a published proof of a statement doubles as a byte level compatibility
+//! test. Random values drawn by the verifier (`V->P`) are derived from the
+//! messages, they are not checked separately.
+use crate::channel::ChannelLayout;
+use std::{fmt, prelude::v1::*};
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The line is not a valid annotation.
+    Syntax { line: usize },
+    /// The byte range of the annotation is not in the proof.
+    OutOfRange { line: usize },
+    /// The proof bytes do not match the annotated value.
+    Mismatch { line: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match *self {
+            Syntax { line } => write!(f, "Line {} is not a valid annotation", line),
+            OutOfRange { line } => write!(f, "Annotation on line {} is not in the proof", line),
+            Mismatch { line } => write!(f, "Proof does not match annotation on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Check `proof` against the `P->V` lines of StarkWare's `annotations` and
+/// return the number of messages checked.
+///
+/// Hashes and other data are compared as bytes, left aligned in the range
+/// and padded with zeros. Field elements are compared by value after
+/// decoding the proof bytes with `layout`. Line numbers in errors start at
+/// one.
+pub fn check_annotations(
+    proof: &[u8],
+    layout: ChannelLayout,
+    annotations: &str,
+) -> Result<usize, Error> {
+    let mut checked = 0;
+    for (index, text) in annotations.lines().enumerate() {
+        let line = index + 1;
+        if !text.starts_with("P->V[") {
+            continue;
+        }
+        let (from, to, value) = parse_line(text).ok_or(Error::Syntax { line })?;
+        if from > to || to > proof.len() {
+            return Err(Error::OutOfRange { line });
+        }
+        let bytes = &proof[from..to];
+        let matches = match value {
+            Value::Bytes(expected) => {
+                expected.len() <= bytes.len()
+                    && bytes[..expected.len()] == expected[..]
+                    && bytes[expected.len()..].iter().all(|&byte| byte == 0)
+            }
+            Value::FieldElements(expected) => {
+                bytes.len() == 32 * expected.len()
+                    && bytes.chunks_exact(32).zip(&expected).all(|(chunk, value)| {
+                        let mut holder = [0_u8; 32];
+                        holder.copy_from_slice(chunk);
+                        layout.decode(&holder) == *value
+                    })
+            }
+        };
+        if !matches {
+            return Err(Error::Mismatch { line });
+        }
+        checked += 1;
+    }
+    Ok(checked)
+}
+
+enum Value {
+    Bytes(Vec<u8>),
+    FieldElements(Vec<FieldElement>),
+}
+
+// Parse `P->V[from:to]: /path: Kind(0x.., 0x..)`
+fn parse_line(text: &str) -> Option<(usize, usize, Value)> {
+    let text = &text["P->V[".len()..];
+    let (range, text) = text.split_at(text.find(']')?);
+    let (from, to) = range.split_at(range.find(':')?);
+    let from = from.parse().ok()?;
+    let to = to[1..].parse().ok()?;
+    let text = &text[text.rfind(": ")? + 2..];
+    let (kind, arguments) = text.split_at(text.find('(')?);
+    if !arguments.ends_with(')') {
+        return None;
+    }
+    let arguments = &arguments[1..arguments.len() - 1];
+    let value = if kind.starts_with("Field Element") {
+        let elements = arguments
+            .split(',')
+            .map(|argument| {
+                let number = U256::from_hex_str(argument.trim()).ok()?;
+                if number >= FieldElement::MODULUS {
+                    return None;
+                }
+                Some(FieldElement::from(number))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Value::FieldElements(elements)
+    } else {
+        let mut bytes = Vec::new();
+        for argument in arguments.split(',') {
+            let digits = argument.trim();
+            let digits = if digits.starts_with("0x") {
+                &digits[2..]
+            } else {
+                digits
+            };
+            if digits.len() % 2 == 1 {
+                bytes.extend(hex::decode(format!("0{}", digits)).ok()?);
+            } else {
+                bytes.extend(hex::decode(digits).ok()?);
+            }
+        }
+        Value::Bytes(bytes)
+    };
+    Some((from, to, value))
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prove,
+        traits::tests::{Claim, Witness},
+        Provable, Verifiable,
+    };
+    use zkp_macros_decl::field_element;
+
+    // The commitment hashes from
+    // solidity/test/fibonacci/proof/fibonacci_proof_annotations.txt in
+    // https://github.com/0xProject/evm-verifier/commit/9bf369139b0edc23ab7ab7e8db8164c5a05a83df,
+    // written as annotation lines. See also `prover::tests::starkware_fibonacci`.
+    const FIBONACCI_ANNOTATIONS: &str = "\
+P->V[0:32]: /fibonacci/Commit on Trace: Hash(0x4ef92de4d2d3594d35f0123ed8187d60542188f5)
+V->P: /fibonacci/Constraint Coefficients: Field Element(0x1)
+P->V[32:64]: /fibonacci/Commit on Composition: Hash(0xf2f6338add62aac3311361aa5d4cf2da2ae04fb6)
+P->V[224:256]: /fibonacci/FRI/Layer 1: Hash(0xe793b5a749cf7d10eb2d43faf4ab472f3ed20c1e)
+P->V[256:288]: /fibonacci/FRI/Layer 2: Hash(0x2333baba2fa0573e00bca54c2b5508f540a37781)
+";
+
+    fn fibonacci_proof() -> Vec<u8> {
+        let witness = Witness {
+            secret: field_element!("83d36de9"),
+        };
+        let claim = Claim {
+            index: 1000,
+            value: field_element!(
+                "04d5f1f669b34fb7252d5a9d0d9786b2638c27eaa04e820b38b088057960cca1"
+            ),
+        };
+        let mut constraints = claim.constraints();
+        constraints.blowup = 16;
+        constraints.pow_bits = 0;
+        constraints.num_queries = 20;
+        constraints.fri_layout = vec![3, 2];
+        let trace = claim.trace(&witness);
+        prove(&constraints, &trace).unwrap().as_bytes().to_vec()
+    }
+
+    #[test]
+    fn starkware_fibonacci_annotations() {
+        let proof = fibonacci_proof();
+        assert_eq!(
+            check_annotations(&proof, ChannelLayout::STARKWARE, FIBONACCI_ANNOTATIONS),
+            Ok(4)
+        );
+
+        let tampered = FIBONACCI_ANNOTATIONS.replace("0x2333baba", "0x2333babb");
+        assert_eq!(
+            check_annotations(&proof, ChannelLayout::STARKWARE, &tampered),
+            Err(Error::Mismatch { line: 5 })
+        );
+    }
+
+    #[test]
+    fn field_element_annotations() {
+        let value = field_element!("0123456789");
+        let mut proof = vec![0_u8; 32];
+        proof.copy_from_slice(&value.as_montgomery().to_bytes_be());
+        let annotation = "P->V[0:32]: /test/OODS values: Field Element(0x123456789)";
+        assert_eq!(
+            check_annotations(&proof, ChannelLayout::STARKWARE, annotation),
+            Ok(1)
+        );
+        assert_eq!(
+            check_annotations(&proof, ChannelLayout::STARKWARE, "P->V[0:32]: bogus"),
+            Err(Error::Syntax { line: 1 })
+        );
+        assert_eq!(
+            check_annotations(
+                &proof,
+                ChannelLayout::STARKWARE,
+                "P->V[0:64]: /x: Data(0x1)"
+            ),
+            Err(Error::OutOfRange { line: 1 })
+        );
+    }
+}
@@ -0,0 +1,65 @@
+//! Proving and verifying from async code.
+//!
+//! Proving is CPU bound and takes from milliseconds to minutes, so it should
+//! not run on the threads of an async executor. These functions move the
+//! work to the Tokio blocking thread pool. They take their arguments by
+//! value because the work may outlive the caller's borrows.
+use crate::{prove, verify, Constraints, Proof, ProverError, TraceTable, VerifierError};
+use tokio::task::spawn_blocking;
+
+/// [`prove`] on the blocking thread pool.
+///
+/// # Panics
+///
+/// Panics if the prover panics.
+pub async fn prove_async(
+    constraints: Constraints,
+    trace: TraceTable,
+) -> Result<Proof, ProverError> {
+    spawn_blocking(move || prove(&constraints, &trace))
+        .await
+        .expect("prover task failed")
+}
+
+/// [`verify`] on the blocking thread pool.
+///
+/// # Panics
+///
+/// Panics if the verifier panics.
+pub async fn verify_async(constraints: Constraints, proof: Proof) -> Result<(), VerifierError> {
+    spawn_blocking(move || verify(&constraints, &proof))
+        .await
+        .expect("verifier task failed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        traits::tests::{Claim, Witness},
+        Provable, Verifiable,
+    };
+    use tokio::runtime::Builder;
+
+    #[test]
+    fn async_test() {
+        let claim = Claim {
+            index: 100,
+            value: 0.into(),
+        };
+        let witness = Witness { secret: 1.into() };
+        let claim = Claim {
+            value: claim.trace(&witness)[(100, 0)].clone(),
+            ..claim
+        };
+        let mut runtime = Builder::new().basic_scheduler().build().unwrap();
+        let proof = runtime
+            .block_on(prove_async(claim.constraints(), claim.trace(&witness)))
+            .unwrap();
+        assert_eq!(proof, claim.prove(&witness).unwrap());
+        assert_eq!(
+            runtime.block_on(verify_async(claim.constraints(), proof)),
+            Ok(())
+        );
+    }
+}
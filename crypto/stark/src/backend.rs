@@ -0,0 +1,124 @@
+use crate::{parallelism::is_sequential, polynomial::DensePolynomial};
+use rayon::prelude::*;
+use std::prelude::v1::*;
+use zkp_hash::{Hash, Hashable, Hasher};
+use zkp_mmap_vec::{MmapVec, Storage};
+use zkp_primefield::FieldElement;
+
+/// Compute backend for the data parallel stages of the prover.
+///
+/// The prover hands the low degree extensions of the trace and constraint
+/// polynomials and the hashing of their Merkle leaves to the backend. The
+/// results are used to construct the Merkle trees on the CPU, so an
+/// implementation can offload the work to an accelerator such as a GPU and
+/// stream the results back. Results must be identical to [`CpuBackend`].
+///
+/// [`CpuBackend`]: struct.CpuBackend.html
+pub trait Backend: Sync {
+    /// Evaluate each polynomial on the coset of the evaluation domain, see
+    /// [`DensePolynomial::low_degree_extension`]. The results are kept as
+    /// selected by `storage`.
+    ///
+    /// [`DensePolynomial::low_degree_extension`]: struct.DensePolynomial.html#method.low_degree_extension
+    fn low_degree_extensions(
+        &self,
+        polynomials: &[DensePolynomial],
+        blowup: usize,
+        storage: Storage,
+    ) -> Vec<MmapVec<FieldElement>>;
+
+    /// Leaf hashes of the Merkle tree with in leaf `i` the `i`-th value of
+    /// each column.
+    ///
+    /// A leaf with a single value hashes to the hash of the value, otherwise
+    /// the value hashes are combined with [`Hasher::hash_many`].
+    fn leaf_hashes<H: Hasher>(&self, columns: &[MmapVec<FieldElement>]) -> Vec<Hash>;
+}
+
+/// The default backend, running on all cores using `rayon`.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CpuBackend;
+
+impl Backend for CpuBackend {
+    fn low_degree_extensions(
+        &self,
+        polynomials: &[DensePolynomial],
+        blowup: usize,
+        storage: Storage,
+    ) -> Vec<MmapVec<FieldElement>> {
+        let extend = |p: &DensePolynomial| p.low_degree_extension_in(blowup, storage);
+        if is_sequential() {
+            polynomials.iter().map(extend).collect()
+        } else {
+            polynomials.par_iter().map(extend).collect()
+        }
+    }
+
+    fn leaf_hashes<H: Hasher>(&self, columns: &[MmapVec<FieldElement>]) -> Vec<Hash> {
+        let size = columns.first().map_or(0, MmapVec::len);
+        let hash = |index| leaf_hash::<H>(columns, index);
+        if is_sequential() {
+            (0..size).map(hash).collect()
+        } else {
+            (0..size).into_par_iter().map(hash).collect()
+        }
+    }
+}
+
+pub(crate) fn leaf_hash<H: Hasher>(columns: &[MmapVec<FieldElement>], index: usize) -> Hash {
+    if columns.len() == 1 {
+        // For a single element, return its hash.
+        columns[0][index].hash()
+    } else {
+        // Combine the element hashes.
+        let hashes: Vec<Hash> = columns.iter().map(|value| value[index].hash()).collect();
+        H::hash_many(&hashes)
+    }
+}
+
+#[cfg(feature = "gpu")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prove, prove_with_backend,
+        traits::tests::{fib_claim, fib_witness},
+        Provable, Verifiable,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use zkp_hash::MaskedKeccak;
+
+    /// Backend that counts the leaves it hashes.
+    #[derive(Default)]
+    struct CountingBackend(AtomicUsize);
+
+    impl Backend for CountingBackend {
+        fn low_degree_extensions(
+            &self,
+            polynomials: &[DensePolynomial],
+            blowup: usize,
+            storage: Storage,
+        ) -> Vec<MmapVec<FieldElement>> {
+            CpuBackend.low_degree_extensions(polynomials, blowup, storage)
+        }
+
+        fn leaf_hashes<H: Hasher>(&self, columns: &[MmapVec<FieldElement>]) -> Vec<Hash> {
+            let hashes = CpuBackend.leaf_hashes::<H>(columns);
+            let _ = self.0.fetch_add(hashes.len(), Ordering::SeqCst);
+            hashes
+        }
+    }
+
+    #[test]
+    fn custom_backend_test() {
+        let claim = fib_claim();
+        let witness = fib_witness();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&witness);
+        let backend = CountingBackend::default();
+        let proof = prove_with_backend::<MaskedKeccak, _>(&constraints, &trace, &backend).unwrap();
+        assert_eq!(proof, prove(&constraints, &trace).unwrap());
+        let eval_domain_size = constraints.trace_nrows() * constraints.blowup;
+        assert_eq!(backend.0.load(Ordering::SeqCst), 2 * eval_domain_size);
+    }
+}
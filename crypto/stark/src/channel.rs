@@ -1,23 +1,25 @@
 // TODO: Naming?
 #![allow(clippy::module_name_repetitions)]
-use crate::proof_of_work;
-use std::prelude::v1::*;
-use tiny_keccak::Keccak;
-use zkp_hash::Hash;
+use crate::{proof_of_work, transcript_logger::TranscriptLogger};
+use std::{marker::PhantomData, prelude::v1::*};
+use zkp_hash::{Hash, Hasher, MaskedKeccak};
 use zkp_macros_decl::u256h;
 use zkp_merkle_tree;
 use zkp_primefield::FieldElement;
 use zkp_u256::U256;
 
-pub(crate) trait RandomGenerator<T> {
+/// Draw Fiat-Shamir randomness of type `T`.
+pub trait RandomGenerator<T> {
     fn get_random(&mut self) -> T;
 }
 
-pub(crate) trait Writable<T> {
+/// Append `T` to the proof and mix it into the randomness.
+pub trait Writable<T> {
     fn write(&mut self, data: T);
 }
 
-pub(crate) trait Replayable<T> {
+/// Read `T` from the proof and mix it into the randomness.
+pub trait Replayable<T> {
     fn replay(&mut self) -> T;
 
     fn replay_many(&mut self, count: usize) -> Vec<T> {
@@ -25,70 +27,330 @@ pub(crate) trait Replayable<T> {
     }
 }
 
-// TODO: Limit to crate
-#[derive(PartialEq, Eq, Clone, Default)]
+/// Position of the counter in the hash input used to draw random values.
+#[derive(Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub(crate) struct PublicCoin {
-    pub(crate) digest: [u8; 32],
-    counter:           u64,
+pub enum CounterLayout {
+    /// The digest followed by the counter as a big-endian 256 bit number.
+    Padded,
+    /// The digest followed by the counter as a big-endian 64 bit number.
+    Compact,
 }
 
-#[derive(PartialEq, Eq, Clone, Default)]
+/// Encoding of field elements in the transcript.
+#[derive(Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug))]
-pub(crate) struct ProverChannel {
-    pub(crate) coin:  PublicCoin,
-    pub(crate) proof: Vec<u8>,
+pub enum FieldEncoding {
+    /// The Montgomery representation as a 32 byte big-endian number.
+    Montgomery,
+    /// The canonical value as a 32 byte big-endian number.
+    Standard,
+}
+
+/// Version tag hashed into the seed of labeled channels.
+pub const PROTOCOL_VERSION: &[u8] = b"zkp-stark/1";
+
+/// Protocol step of a group of channel interactions.
+///
+/// With [`ChannelLayout::labels`] set, the prover and verifier mix the label
+/// into the channel before the interactions of each step, so randomness
+/// drawn in one step can not be confused with another step or protocol.
+///
+/// [`ChannelLayout::labels`]: struct.ChannelLayout.html#structfield.labels
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Label {
+    TraceCommitment,
+    /// Challenges for the interaction trace, see
+    /// `RationalExpression::Challenge`.
+    Challenges,
+    InteractionCommitment,
+    ConstraintCoefficients,
+    ConstraintCommitment,
+    OodsPoint,
+    OodsValues,
+    OodsCoefficients,
+    /// Commitment to FRI layer `i` and the folding coefficient after it.
+    FriLayer(usize),
+    FriLastLayer,
+    ProofOfWork,
+    QueryIndices,
+    Decommitments,
+}
+
+impl Label {
+    pub fn name(self) -> &'static str {
+        use Label::*;
+        match self {
+            TraceCommitment => "trace-commitment",
+            Challenges => "challenges",
+            InteractionCommitment => "interaction-commitment",
+            ConstraintCoefficients => "constraint-coefficients",
+            ConstraintCommitment => "constraint-commitment",
+            OodsPoint => "oods-point",
+            OodsValues => "oods-values",
+            OodsCoefficients => "oods-coefficients",
+            FriLayer(_) => "fri-layer",
+            FriLastLayer => "fri-last-layer",
+            ProofOfWork => "pow",
+            QueryIndices => "query-indices",
+            Decommitments => "decommitments",
+        }
+    }
+
+    // The name, followed by the layer index as a 64 bit big-endian number for
+    // FRI layers.
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = self.name().as_bytes().to_vec();
+        if let Self::FriLayer(index) = self {
+            bytes.extend_from_slice(&(index as u64).to_be_bytes());
+        }
+        bytes
+    }
+}
+
+/// Byte layout of the Fiat-Shamir channel.
+///
+/// This determines how random values are derived from the channel state and
+/// how field elements are encoded in the proof, which includes the values of
+/// decommitted Merkle leaves. The hashes of Merkle tree leaves do not depend
+/// on the layout. The hash function itself is selected with [`prove_with`].
+///
+/// [`ChannelLayout::STARKWARE`], the default, follows the channel of
+/// StarkWare's public verifier. Together with [`MaskedKeccak`] it reproduces
+/// the commitments of the Fibonacci example proof in 0x's `evm-verifier`,
+/// which the tests check. The rest of the proof is not compared against a
+/// reference proof, so verification by their contracts is not guaranteed.
+/// Their transcript has no domain separation, so [`ChannelLayout::LABELED`]
+/// is recommended where compatibility is not needed.
+///
+/// [`prove_with`]: fn.prove_with.html
+/// [`ChannelLayout::STARKWARE`]: #associatedconstant.STARKWARE
+/// [`ChannelLayout::LABELED`]: #associatedconstant.LABELED
+/// [`MaskedKeccak`]: ../zkp_hash/struct.MaskedKeccak.html
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ChannelLayout {
+    pub counter:        CounterLayout,
+    pub field_encoding: FieldEncoding,
+    /// Prefix the seed with [`PROTOCOL_VERSION`] and mix a [`Label`] into
+    /// the channel at the start of every protocol step.
+    ///
+    /// [`PROTOCOL_VERSION`]: constant.PROTOCOL_VERSION.html
+    /// [`Label`]: enum.Label.html
+    pub labels:         bool,
+}
+
+impl ChannelLayout {
+    /// The StarkWare layout with domain separation labels.
+    pub const LABELED: Self = Self {
+        labels: true,
+        ..Self::STARKWARE
+    };
+    pub const STARKWARE: Self = Self {
+        counter:        CounterLayout::Padded,
+        field_encoding: FieldEncoding::Montgomery,
+        labels:         false,
+    };
+
+    pub(crate) fn encode(self, element: &FieldElement) -> [u8; 32] {
+        match self.field_encoding {
+            FieldEncoding::Montgomery => element.as_montgomery().to_bytes_be(),
+            FieldEncoding::Standard => U256::from(element).to_bytes_be(),
+        }
+    }
+
+    pub(crate) fn decode(self, bytes: &[u8; 32]) -> FieldElement {
+        let number = U256::from_bytes_be(bytes);
+        match self.field_encoding {
+            FieldEncoding::Montgomery => FieldElement::from_montgomery(number),
+            FieldEncoding::Standard => FieldElement::from(number),
+        }
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Default)]
+impl Default for ChannelLayout {
+    fn default() -> Self {
+        Self::STARKWARE
+    }
+}
+
+/// Construction of the Fiat-Shamir randomness behind the channels.
+///
+/// The channels encode messages, labels and field elements as bytes, so a
+/// transcript only absorbs bytes and squeezes random bytes out of them. The
+/// prover and verifier must use the same transcript. [`HashChain`] is the
+/// default and the construction of StarkWare's verifier. Others, such as a
+/// sponge over an algebraic permutation for recursive verification, are used
+/// with [`prove_with_transcript`] and [`verify_with_transcript`].
+///
+/// [`HashChain`]: struct.HashChain.html
+/// [`prove_with_transcript`]: fn.prove_with_transcript.html
+/// [`verify_with_transcript`]: fn.verify_with_transcript.html
+pub trait Transcript: Default {
+    /// Reset the state to one derived from the concatenation of `parts`.
+    fn seed(&mut self, parts: &[&[u8]]);
+
+    /// Mix `data` into the state.
+    fn absorb(&mut self, data: &[u8]);
+
+    /// Draw 32 random bytes. Consecutive draws must differ.
+    ///
+    /// Transcripts without a counter can ignore `counter`.
+    fn squeeze(&mut self, counter: CounterLayout) -> [u8; 32];
+
+    /// Commitment to everything absorbed so far, used as the proof of work
+    /// challenge.
+    fn state(&self) -> [u8; 32];
+}
+
+/// Transcript that hashes every message together with the previous digest.
+///
+/// Random values are hashes of the digest and a counter that is reset on
+/// every message.
 #[cfg_attr(feature = "std", derive(Debug))]
-pub(crate) struct VerifierChannel {
-    pub(crate) coin:  PublicCoin,
-    pub(crate) proof: Vec<u8>,
-    proof_index:      usize,
+pub struct HashChain<H: Hasher = MaskedKeccak> {
+    digest:  [u8; 32],
+    counter: u64,
+    hasher:  PhantomData<H>,
 }
 
-impl PublicCoin {
-    pub(crate) fn new() -> Self {
+impl<H: Hasher> Default for HashChain<H> {
+    fn default() -> Self {
         Self {
             digest:  [0; 32],
             counter: 0,
+            hasher:  PhantomData,
         }
     }
+}
 
-    pub(crate) fn seed(&mut self, seed: &[u8]) {
-        let mut keccak = Keccak::new_keccak256();
-        keccak.update(seed);
-        keccak.finalize(&mut self.digest);
+impl<H: Hasher> Transcript for HashChain<H> {
+    fn seed(&mut self, parts: &[&[u8]]) {
+        self.digest = H::digest(parts);
         self.counter = 0;
     }
+
+    fn absorb(&mut self, data: &[u8]) {
+        // FIX: Hash counter into digest.
+        self.digest = H::digest(&[&self.digest, data]);
+        self.counter = 0;
+    }
+
+    fn squeeze(&mut self, counter: CounterLayout) -> [u8; 32] {
+        let result = match counter {
+            CounterLayout::Padded => {
+                H::digest(&[&self.digest, &[0_u8; 24], &self.counter.to_be_bytes()])
+            }
+            CounterLayout::Compact => H::digest(&[&self.digest, &self.counter.to_be_bytes()]),
+        };
+        self.counter += 1;
+        result
+    }
+
+    fn state(&self) -> [u8; 32] {
+        self.digest
+    }
+}
+
+/// Fiat-Shamir random oracle, instantiated with the transcript `T`.
+#[cfg_attr(feature = "std", derive(Debug))]
+pub(crate) struct PublicCoin<T: Transcript = HashChain> {
+    pub(crate) transcript: T,
+    pub(crate) layout:     ChannelLayout,
+    pub(crate) logger:     Option<TranscriptLogger>,
+}
+
+/// Transcript written by the prover.
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ProverChannel<T: Transcript = HashChain> {
+    pub(crate) coin:  PublicCoin<T>,
+    pub(crate) proof: Vec<u8>,
 }
 
-impl From<Vec<u8>> for ProverChannel {
-    fn from(proof_data: Vec<u8>) -> Self {
+/// Transcript read back by the verifier.
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VerifierChannel<T: Transcript = HashChain> {
+    pub(crate) coin:  PublicCoin<T>,
+    pub(crate) proof: Vec<u8>,
+    proof_index:      usize,
+}
+
+impl<T: Transcript> PublicCoin<T> {
+    pub(crate) fn new() -> Self {
         Self {
-            coin:  PublicCoin::new(),
-            proof: proof_data,
+            transcript: T::default(),
+            layout:     ChannelLayout::STARKWARE,
+            logger:     None,
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn digest(&self) -> [u8; 32] {
+        self.transcript.state()
+    }
+
+    pub(crate) fn seed(&mut self, seed: &[u8]) {
+        if self.layout.labels {
+            self.transcript.seed(&[PROTOCOL_VERSION, seed]);
+        } else {
+            self.transcript.seed(&[seed]);
+        }
+        self.log("seed", seed);
+    }
+
+    // Does nothing unless the layout has labels
+    pub(crate) fn label(&mut self, label: Label) {
+        if self.layout.labels {
+            let bytes = label.to_bytes();
+            self.transcript.absorb(&bytes);
+            self.log("label", &bytes);
+        }
+    }
+
+    fn log(&mut self, label: &'static str, bytes: &[u8]) {
+        if let Some(logger) = &mut self.logger {
+            logger.log(label, bytes, &self.transcript.state());
         }
     }
 }
 
 #[cfg(feature = "prover")]
-impl ProverChannel {
-    pub(crate) fn new() -> Self {
+impl<T: Transcript> ProverChannel<T> {
+    pub fn new() -> Self {
         Self {
             coin:  PublicCoin::new(),
             proof: Vec::new(),
         }
     }
 
-    pub(crate) fn initialize(&mut self, seed: &[u8]) {
+    /// Seed the randomness, usually with the public input.
+    pub fn initialize(&mut self, seed: &[u8]) {
         self.coin.seed(seed);
     }
+
+    /// The proof bytes written so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.proof
+    }
+
+    /// Start a protocol step, see [`Label`].
+    ///
+    /// [`Label`]: enum.Label.html
+    pub fn label(&mut self, label: Label) {
+        self.coin.label(label);
+    }
 }
 
-impl VerifierChannel {
-    pub(crate) fn new(proof: Vec<u8>) -> Self {
+#[cfg(feature = "prover")]
+impl<T: Transcript> Default for ProverChannel<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Transcript> VerifierChannel<T> {
+    pub fn new(proof: Vec<u8>) -> Self {
         Self {
             coin: PublicCoin::new(),
             proof,
@@ -96,43 +358,72 @@ impl VerifierChannel {
         }
     }
 
-    pub(crate) fn initialize(&mut self, seed: &[u8]) {
+    /// Seed the randomness, the same as [`ProverChannel::initialize`].
+    pub fn initialize(&mut self, seed: &[u8]) {
         self.coin.seed(seed);
     }
 
-    pub(crate) fn at_end(self) -> bool {
+    /// Start a protocol step, the same as [`ProverChannel::label`].
+    pub fn label(&mut self, label: Label) {
+        self.coin.label(label);
+    }
+
+    /// Whether all of the proof has been read.
+    pub fn at_end(&self) -> bool {
         self.proof_index == self.proof.len()
     }
+
+    /// Whether more was read than the proof contains.
+    ///
+    /// Reading past the end does not panic, it produces zeros. The verifier
+    /// rejects the proof afterwards.
+    pub fn is_exhausted(&self) -> bool {
+        self.proof_index > self.proof.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.proof.len().saturating_sub(self.proof_index)
+    }
+
+    // Fill `holder` with the next bytes of the proof, or zeros past the end.
+    fn read(&mut self, holder: &mut [u8]) {
+        // The index runs past the end once the proof is exhausted
+        let start = std::cmp::min(self.proof_index, self.proof.len());
+        let available = std::cmp::min(holder.len(), self.remaining());
+        holder[..available].copy_from_slice(&self.proof[start..start + available]);
+        for byte in &mut holder[available..] {
+            *byte = 0;
+        }
+        self.proof_index = self.proof_index.saturating_add(holder.len());
+    }
 }
 
-impl RandomGenerator<proof_of_work::ChallengeSeed> for PublicCoin {
+impl<T: Transcript> RandomGenerator<proof_of_work::ChallengeSeed> for PublicCoin<T> {
     fn get_random(&mut self) -> proof_of_work::ChallengeSeed {
-        self.counter += 1;
         // FIX: Use get_random::<[u8;32]>();
-        proof_of_work::ChallengeSeed::from_bytes(self.digest)
+        let digest = self.transcript.state();
+        self.log("random", &digest);
+        proof_of_work::ChallengeSeed::from_bytes(digest)
     }
 }
 
-impl Writable<proof_of_work::Response> for ProverChannel {
+impl<T: Transcript> Writable<proof_of_work::Response> for ProverChannel<T> {
     fn write(&mut self, data: proof_of_work::Response) {
         self.write(&data.nonce().to_be_bytes()[..]);
     }
 }
 
-impl Replayable<proof_of_work::Response> for VerifierChannel {
+impl<T: Transcript> Replayable<proof_of_work::Response> for VerifierChannel<T> {
     fn replay(&mut self) -> proof_of_work::Response {
         let mut holder = [0_u8; 8];
-        let from = self.proof_index;
-        let to = from + 8;
-        self.proof_index = to;
-        holder.copy_from_slice(&self.proof[from..to]);
+        self.read(&mut holder);
         self.coin.write(&holder[..]);
         let nonce = u64::from_be_bytes(holder);
         proof_of_work::Response::from_nonce(nonce)
     }
 }
 
-impl RandomGenerator<FieldElement> for PublicCoin {
+impl<T: Transcript> RandomGenerator<FieldElement> for PublicCoin<T> {
     fn get_random(&mut self) -> FieldElement {
         const MASK: U256 =
             u256h!("0FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF");
@@ -140,59 +431,48 @@ impl RandomGenerator<FieldElement> for PublicCoin {
             let number: U256 = self.get_random();
             let seed = number & MASK;
             if seed < FieldElement::MODULUS {
-                break FieldElement::from_montgomery(seed);
+                break self.layout.decode(&seed.to_bytes_be());
             }
         }
     }
 }
 
-impl RandomGenerator<U256> for PublicCoin {
+impl<T: Transcript> RandomGenerator<U256> for PublicCoin<T> {
     fn get_random(&mut self) -> U256 {
         U256::from_bytes_be(&self.get_random())
     }
 }
 
-impl RandomGenerator<[u8; 32]> for PublicCoin {
+impl<T: Transcript> RandomGenerator<[u8; 32]> for PublicCoin<T> {
     fn get_random(&mut self) -> [u8; 32] {
-        let mut result = [0; 32];
-        let mut keccak = Keccak::new_keccak256();
-        keccak.update(&self.digest);
-        keccak.update(&[0_u8; 24]);
-        keccak.update(&self.counter.to_be_bytes());
-        keccak.finalize(&mut result);
-        self.counter += 1;
+        let result = self.transcript.squeeze(self.layout.counter);
+        self.log("random", &result);
         result
     }
 }
 
-impl<T> RandomGenerator<T> for ProverChannel
+impl<T: Transcript, R> RandomGenerator<R> for ProverChannel<T>
 where
-    PublicCoin: RandomGenerator<T>,
+    PublicCoin<T>: RandomGenerator<R>,
 {
-    fn get_random(&mut self) -> T {
+    fn get_random(&mut self) -> R {
         self.coin.get_random()
     }
 }
 
-impl<T> RandomGenerator<T> for VerifierChannel
+impl<T: Transcript, R> RandomGenerator<R> for VerifierChannel<T>
 where
-    PublicCoin: RandomGenerator<T>,
+    PublicCoin<T>: RandomGenerator<R>,
 {
-    fn get_random(&mut self) -> T {
+    fn get_random(&mut self) -> R {
         self.coin.get_random()
     }
 }
 
-impl Writable<&[u8]> for PublicCoin {
+impl<T: Transcript> Writable<&[u8]> for PublicCoin<T> {
     fn write(&mut self, data: &[u8]) {
-        let mut result: [u8; 32] = [0; 32];
-        let mut keccak = Keccak::new_keccak256();
-        keccak.update(&self.digest);
-        keccak.update(data);
-        keccak.finalize(&mut result);
-        // FIX: Hash counter into digest.
-        self.digest = result;
-        self.counter = 0;
+        self.transcript.absorb(data);
+        self.log("write", data);
     }
 }
 
@@ -200,26 +480,26 @@ impl Writable<&[u8]> for PublicCoin {
 // the proof with the same encoding for the writing and the non writing. However
 // by writing directly to the coin, other writes for the channel could separate
 // encoding from random perturbation.
-impl Writable<&[u8]> for ProverChannel {
+impl<T: Transcript> Writable<&[u8]> for ProverChannel<T> {
     fn write(&mut self, data: &[u8]) {
         self.proof.extend_from_slice(data);
         self.coin.write(data);
     }
 }
 
-impl Writable<&Hash> for ProverChannel {
+impl<T: Transcript> Writable<&Hash> for ProverChannel<T> {
     fn write(&mut self, data: &Hash) {
         self.write(data.as_bytes());
     }
 }
 
-impl Writable<&zkp_merkle_tree::Commitment> for ProverChannel {
+impl<T: Transcript> Writable<&zkp_merkle_tree::Commitment> for ProverChannel<T> {
     fn write(&mut self, data: &zkp_merkle_tree::Commitment) {
         self.write(data.hash())
     }
 }
 
-impl Writable<&zkp_merkle_tree::Proof> for ProverChannel {
+impl<T: Transcript> Writable<&zkp_merkle_tree::Proof> for ProverChannel<T> {
     fn write(&mut self, data: &zkp_merkle_tree::Proof) {
         for hash in data.hashes() {
             self.write(hash)
@@ -228,98 +508,123 @@ impl Writable<&zkp_merkle_tree::Proof> for ProverChannel {
 }
 
 // OPT - Remove allocation of vectors
-impl Writable<&[FieldElement]> for ProverChannel {
+impl<T: Transcript> Writable<&[FieldElement]> for ProverChannel<T> {
     fn write(&mut self, data: &[FieldElement]) {
         let mut container = Vec::with_capacity(32 * data.len());
         for element in data {
-            for byte in &element.as_montgomery().to_bytes_be() {
-                container.push(byte.clone());
-            }
+            container.extend_from_slice(&self.coin.layout.encode(element));
         }
         self.write(container.as_slice());
     }
 }
 
-impl Writable<&FieldElement> for ProverChannel {
+impl<T: Transcript> Writable<&FieldElement> for ProverChannel<T> {
     fn write(&mut self, data: &FieldElement) {
-        // TODO: Avoid accessing FieldElement members directly
-        self.write(&data.as_montgomery().to_bytes_be()[..]);
+        let bytes = self.coin.layout.encode(data);
+        self.write(&bytes[..]);
     }
 }
 
 // Note -- This method of writing is distinct from the field element, and is
-// used in the decommitment when groups are decommited from the rows
-impl Writable<Vec<U256>> for ProverChannel {
+// used in the decommitment when groups are decommited from the rows. The
+// values are field elements in Montgomery form.
+impl<T: Transcript> Writable<Vec<U256>> for ProverChannel<T> {
     fn write(&mut self, data: Vec<U256>) {
         for element in data {
-            self.write(element)
+            let bytes = self
+                .coin
+                .layout
+                .encode(&FieldElement::from_montgomery(element));
+            self.write(&bytes[..]);
         }
     }
 }
 
-impl Writable<U256> for ProverChannel {
+impl<T: Transcript> Writable<U256> for ProverChannel<T> {
     fn write(&mut self, data: U256) {
         self.write(&data.to_bytes_be()[..]);
     }
 }
 
-impl Replayable<Hash> for VerifierChannel {
+impl<T: Transcript> Replayable<Hash> for VerifierChannel<T> {
     fn replay(&mut self) -> Hash {
         let hash: [u8; 32] = self.replay();
         Hash::new(hash)
     }
 }
 
-impl Replayable<[u8; 32]> for VerifierChannel {
+impl<T: Transcript> Replayable<[u8; 32]> for VerifierChannel<T> {
     fn replay(&mut self) -> [u8; 32] {
         let mut holder = [0_u8; 32];
-        let from = self.proof_index;
-        let to = from + 32;
-        self.proof_index = to;
-        // OPT: Use arrayref crate or similar to avoid copy
-        holder.copy_from_slice(&self.proof[from..to]);
+        self.read(&mut holder);
         self.coin.write(&holder[..]);
         holder
     }
 }
 
-impl Replayable<U256> for VerifierChannel {
+impl<T: Transcript> Replayable<U256> for VerifierChannel<T> {
     fn replay(&mut self) -> U256 {
         U256::from_bytes_be(&Replayable::replay(self))
     }
 }
 
-impl Replayable<FieldElement> for VerifierChannel {
+impl<T: Transcript> Replayable<FieldElement> for VerifierChannel<T> {
     fn replay(&mut self) -> FieldElement {
-        FieldElement::from_montgomery(Replayable::replay(self))
+        let bytes: [u8; 32] = self.replay();
+        self.coin.layout.decode(&bytes)
     }
 
     fn replay_many(&mut self, len: usize) -> Vec<FieldElement> {
-        let start_index = self.proof_index;
-        let mut ret = Vec::with_capacity(len);
-        for _ in 0..len {
-            let mut holder = [0_u8; 32];
-            let from = self.proof_index;
-            let to = from + 32;
-            self.proof_index = to;
-            holder.copy_from_slice(&self.proof[from..to]);
-            ret.push(FieldElement::from_montgomery(U256::from_bytes_be(&holder)));
-        }
-        self.coin.write(&self.proof[start_index..self.proof_index]);
+        let mut bytes = vec![0_u8; 32 * len];
+        self.read(&mut bytes);
+        let layout = self.coin.layout;
+        let ret = bytes
+            .chunks_exact(32)
+            .map(|chunk| {
+                let mut holder = [0_u8; 32];
+                holder.copy_from_slice(chunk);
+                layout.decode(&holder)
+            })
+            .collect();
+        self.coin.write(&bytes);
         ret
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use zkp_macros_decl::{hex, u256h};
 
+    // A duplex construction without a counter: every draw replaces the state.
+    #[derive(Default, Debug)]
+    pub(crate) struct Ratchet([u8; 32]);
+
+    impl Transcript for Ratchet {
+        fn seed(&mut self, parts: &[&[u8]]) {
+            self.0 = MaskedKeccak::digest(parts);
+        }
+
+        fn absorb(&mut self, data: &[u8]) {
+            self.0 = MaskedKeccak::digest(&[&self.0, data]);
+        }
+
+        fn squeeze(&mut self, _counter: CounterLayout) -> [u8; 32] {
+            let result = MaskedKeccak::digest(&[&self.0, b"squeeze"]);
+            self.0 = MaskedKeccak::digest(&[&self.0, b"ratchet"]);
+            result
+        }
+
+        fn state(&self) -> [u8; 32] {
+            self.0
+        }
+    }
+
     // Note - This test depends on the specific ordering of the subtests because of
     // the nature of the channel
     #[test]
     fn test_channel_get_random() {
-        let mut source = ProverChannel::new();
+        let mut source: ProverChannel = ProverChannel::new();
         source.initialize(hex!("0123456789abcded").to_vec().as_slice());
         let rand_bytes: [u8; 32] = source.get_random();
         assert_eq!(
@@ -344,24 +649,24 @@ mod tests {
     // the nature of the channel
     #[test]
     fn test_channel_write() {
-        let mut source = ProverChannel::new();
+        let mut source: ProverChannel = ProverChannel::new();
         source.initialize(&hex!("0123456789abcded"));
         let rand_bytes: [u8; 32] = source.get_random();
         source.write(&rand_bytes[..]);
         assert_eq!(
-            source.coin.digest,
+            source.coin.digest(),
             hex!("3174a00d031bc8deff799e24a78ee347b303295a6cb61986a49873d9b6f13a0d")
         );
         source.write(proof_of_work::Response::from_nonce(11_028_357_238_u64));
         assert_eq!(
-            source.coin.digest,
+            source.coin.digest(),
             hex!("21571e2a323daa1e6f2adda87ce912608e1325492d868e8fe41626633d6acb93")
         );
         source.write(&FieldElement::from_montgomery(u256h!(
             "0389a47fe0e1e5f9c05d8dcb27b069b67b1c7ec61a5c0a3f54d81aea83d2c8f0"
         )));
         assert_eq!(
-            source.coin.digest,
+            source.coin.digest(),
             hex!("34a12938f047c34da72b5949434950fa2b24220270fd26e6f64b6eb5e86c6626")
         );
         source.write(
@@ -376,14 +681,14 @@ mod tests {
             .as_slice(),
         );
         assert_eq!(
-            source.coin.digest,
+            source.coin.digest(),
             hex!("a748ff89e2c4322afb061ef3321e207b3fe32c35f181de0809300995dd9b92fd")
         );
     }
 
     #[test]
     fn verifier_channel_test() {
-        let mut source = ProverChannel::new();
+        let mut source: ProverChannel = ProverChannel::new();
         source.initialize(&hex!("0123456789abcded"));
         let rand_bytes: [u8; 32] = source.get_random();
         source.write(&rand_bytes[..]);
@@ -408,41 +713,101 @@ mod tests {
         ];
         source.write(written_big_int_vec.clone());
 
-        let mut verifier = VerifierChannel::new(source.proof.clone());
+        let mut verifier: VerifierChannel = VerifierChannel::new(source.proof.clone());
         verifier.initialize(&hex!("0123456789abcded"));
         let bytes_test: [u8; 32] = verifier.replay();
         assert_eq!(bytes_test, rand_bytes);
         assert_eq!(
-            verifier.coin.digest,
+            verifier.coin.digest(),
             hex!("3174a00d031bc8deff799e24a78ee347b303295a6cb61986a49873d9b6f13a0d")
         );
         let pow_response_test: proof_of_work::Response = verifier.replay();
         assert_eq!(pow_response_test.nonce(), 11_028_357_238_u64);
         assert_eq!(
-            verifier.coin.digest,
+            verifier.coin.digest(),
             hex!("21571e2a323daa1e6f2adda87ce912608e1325492d868e8fe41626633d6acb93")
         );
         let field_element_test: FieldElement = verifier.replay();
         assert_eq!(field_element_test, written_field_element);
         assert_eq!(
-            verifier.coin.digest,
+            verifier.coin.digest(),
             hex!("34a12938f047c34da72b5949434950fa2b24220270fd26e6f64b6eb5e86c6626")
         );
         let field_element_vec_test: Vec<FieldElement> = verifier.replay_many(2);
         assert_eq!(field_element_vec_test, written_field_element_vec);
         assert_eq!(
-            verifier.coin.digest,
+            verifier.coin.digest(),
             hex!("a748ff89e2c4322afb061ef3321e207b3fe32c35f181de0809300995dd9b92fd")
         );
         let bit_int_vec_test: Vec<U256> = verifier.replay_many(2);
         assert_eq!(bit_int_vec_test, written_big_int_vec);
-        assert_eq!(verifier.coin.digest, source.coin.digest);
+        assert_eq!(verifier.coin.digest(), source.coin.digest());
+    }
+
+    #[test]
+    fn test_read_past_end() {
+        let mut verifier: VerifierChannel = VerifierChannel::new(vec![1; 40]);
+        let first: [u8; 32] = verifier.replay();
+        assert_eq!(first, [1; 32]);
+        let second: [u8; 32] = verifier.replay();
+        assert_eq!(second[..8], [1; 8]);
+        assert_eq!(second[8..], [0; 24]);
+        assert!(verifier.is_exhausted());
+        // Further reads keep producing zeros
+        let third: [u8; 32] = verifier.replay();
+        assert_eq!(third, [0; 32]);
+    }
+
+    #[test]
+    fn test_labels() {
+        let mut plain: ProverChannel = ProverChannel::new();
+        plain.initialize(b"seed");
+        let digest = plain.coin.digest();
+        plain.label(Label::TraceCommitment);
+        assert_eq!(plain.coin.digest(), digest);
+
+        let mut labeled: ProverChannel = ProverChannel::new();
+        labeled.coin.layout = ChannelLayout::LABELED;
+        labeled.initialize(b"seed");
+        assert_ne!(labeled.coin.digest(), digest);
+        let mut other: ProverChannel = ProverChannel::new();
+        other.coin.layout = ChannelLayout::LABELED;
+        other.initialize(b"seed");
+        labeled.label(Label::FriLayer(1));
+        other.label(Label::FriLayer(2));
+        let a: [u8; 32] = labeled.get_random();
+        let b: [u8; 32] = other.get_random();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_transcript() {
+        let mut source: ProverChannel<Ratchet> = ProverChannel::new();
+        source.initialize(b"seed");
+        let mut chain: ProverChannel = ProverChannel::new();
+        chain.initialize(b"seed");
+        assert_eq!(source.coin.digest(), chain.coin.digest());
+        let a: [u8; 32] = source.get_random();
+        let b: [u8; 32] = source.get_random();
+        let c: [u8; 32] = chain.get_random();
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+
+        let element = FieldElement::from(42);
+        source.write(&element);
+        let mut verifier: VerifierChannel<Ratchet> = VerifierChannel::new(source.proof.clone());
+        verifier.initialize(b"seed");
+        let _: [u8; 32] = verifier.get_random();
+        let _: [u8; 32] = verifier.get_random();
+        let replayed: FieldElement = verifier.replay();
+        assert_eq!(replayed, element);
+        assert_eq!(verifier.coin.digest(), source.coin.digest());
     }
 
     #[test]
     fn test_challenge_seed_from_channel() {
         use crate::channel::*;
-        let mut rand_source = ProverChannel::new();
+        let mut rand_source: ProverChannel = ProverChannel::new();
         rand_source.initialize(&hex!("0123456789abcded"));
         // Verify that reading challenges does not depend on public coin counter.
         // FIX: Make it depend on public coin counter.
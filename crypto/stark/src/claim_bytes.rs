@@ -0,0 +1,143 @@
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// Canonical byte encoding of a claim.
+///
+/// The encoding is used to seed the proof channel, so prover and verifier
+/// must agree on it exactly. The encoding of the built-in types is
+///
+/// * integers are big-endian of their full width, `usize` and `isize` are
+///   always encoded as 64 bit integers regardless of the platform,
+/// * `bool` is a single byte `0` or `1`,
+/// * `U256` is 32 bytes big-endian,
+/// * `FieldElement` is its Montgomery representation as a `U256`,
+/// * slices and vectors are their length (as `usize`) followed by the elements,
+/// * tuples are the concatenation of their elements.
+///
+/// Claim structs implement this by writing their fields in order.
+pub trait ClaimBytes {
+    /// Append the encoding of `self` to `bytes`.
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>);
+
+    /// The encoding of `self`.
+    fn claim_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_claim_bytes(&mut bytes);
+        bytes
+    }
+}
+
+macro_rules! claim_bytes_int {
+    ($($type:ty),*) => {
+        $(
+            impl ClaimBytes for $type {
+                fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_be_bytes());
+                }
+            }
+        )*
+    };
+}
+
+claim_bytes_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl ClaimBytes for usize {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        (*self as u64).write_claim_bytes(bytes)
+    }
+}
+
+impl ClaimBytes for isize {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        (*self as i64).write_claim_bytes(bytes)
+    }
+}
+
+impl ClaimBytes for bool {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.push(u8::from(*self))
+    }
+}
+
+impl ClaimBytes for U256 {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_bytes_be())
+    }
+}
+
+impl ClaimBytes for FieldElement {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.as_montgomery().write_claim_bytes(bytes)
+    }
+}
+
+impl<T: ClaimBytes> ClaimBytes for [T] {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.len().write_claim_bytes(bytes);
+        for element in self {
+            element.write_claim_bytes(bytes);
+        }
+    }
+}
+
+impl<T: ClaimBytes> ClaimBytes for Vec<T> {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.as_slice().write_claim_bytes(bytes)
+    }
+}
+
+impl<T: ClaimBytes + ?Sized> ClaimBytes for &T {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        (*self).write_claim_bytes(bytes)
+    }
+}
+
+macro_rules! claim_bytes_tuple {
+    ($(($($name:ident),*)),*) => {
+        $(
+            impl<$($name: ClaimBytes),*> ClaimBytes for ($($name,)*) {
+                #[allow(non_snake_case)]
+                fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+                    let ($($name,)*) = self;
+                    $($name.write_claim_bytes(bytes);)*
+                }
+            }
+        )*
+    };
+}
+
+claim_bytes_tuple!((A, B), (A, B, C), (A, B, C, D));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkp_macros_decl::hex;
+
+    #[test]
+    fn integer_test() {
+        assert_eq!(5_u8.claim_bytes(), vec![5]);
+        assert_eq!(0x0102_u16.claim_bytes(), vec![1, 2]);
+        assert_eq!(5_usize.claim_bytes(), vec![0, 0, 0, 0, 0, 0, 0, 5]);
+        assert_eq!((-1_isize).claim_bytes(), vec![0xff; 8]);
+        assert_eq!(true.claim_bytes(), vec![1]);
+    }
+
+    #[test]
+    fn field_element_test() {
+        let value = FieldElement::from(5);
+        assert_eq!(
+            value.claim_bytes(),
+            value.as_montgomery().to_bytes_be().to_vec()
+        );
+    }
+
+    #[test]
+    fn nested_test() {
+        let claim = (3_u16, vec![vec![1_u8], vec![]]);
+        assert_eq!(
+            claim.claim_bytes(),
+            hex!("000300000000000000020000000000000001010000000000000000").to_vec()
+        );
+    }
+}
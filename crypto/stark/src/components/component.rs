@@ -0,0 +1,431 @@
+use crate::{
+    constraints::{Constraints, Error as ConstraintError},
+    rational_expression::RationalExpression,
+    trace_table::{TraceLayout, TraceTable},
+};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+
+/// A trace table fragment together with the constraints on it.
+///
+/// Components can be combined into larger components by placing them
+/// side-by-side using [`Component::horizontal`] or by interleaving their rows
+/// using [`Component::vertical`]. The constraints are relabeled to refer to
+/// the new column and row positions, so a component can be written once
+/// as if it owns the whole trace table.
+// TODO: Support components of different lengths.
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Component {
+    trace:       TraceTable,
+    constraints: Vec<RationalExpression>,
+}
+
+impl Component {
+    /// The trace is stored column-major, so the combinators can copy whole
+    /// columns.
+    pub fn new(trace: TraceTable, constraints: Vec<RationalExpression>) -> Self {
+        Self {
+            trace: trace.into_layout(TraceLayout::ColumnMajor),
+            constraints,
+        }
+    }
+
+    pub fn trace(&self) -> &TraceTable {
+        &self.trace
+    }
+
+    pub fn expressions(&self) -> &[RationalExpression] {
+        &self.constraints
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.trace.num_rows()
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.trace.num_columns()
+    }
+
+    /// Place two components side by side.
+    ///
+    /// The columns of `right` are placed after the columns of `left`. The
+    /// constraints of `left` come first, followed by those of `right`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the components do not have the same number of rows.
+    pub fn horizontal(left: &Self, right: &Self) -> Self {
+        use RationalExpression::*;
+        assert_eq!(left.num_rows(), right.num_rows());
+        let offset = left.num_columns();
+
+        let mut trace = TraceTable::with_layout(
+            left.num_rows(),
+            offset + right.num_columns(),
+            TraceLayout::ColumnMajor,
+        );
+        for (j, column) in left
+            .trace
+            .columns()
+            .chain(right.trace.columns())
+            .enumerate()
+        {
+            trace.column_mut(j).clone_from_slice(column);
+        }
+
+        let constraints = left
+            .constraints
+            .iter()
+            .cloned()
+            .chain(
+                right
+                    .constraints
+                    .iter()
+                    .map(|c| c.substitute(&X, &|i, j| Trace(i + offset, j))),
+            )
+            .collect();
+        Self { trace, constraints }
+    }
+
+    /// Interleave the rows of two components.
+    ///
+    /// Row `i` of `even` becomes row `2 i` and row `i` of `odd` becomes row
+    /// `2 i + 1`. The narrower component is padded with zero columns. The
+    /// constraints of `even` come first, followed by those of `odd`.
+    ///
+    /// Both components are expected to use the generator of their own trace
+    /// length in their constraints. For `even` these are already the correct
+    /// points in the new domain. For `odd` the domain is shifted by one row.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the components do not have the same number of rows.
+    pub fn vertical(even: &Self, odd: &Self) -> Self {
+        assert_eq!(even.num_rows(), odd.num_rows());
+        let num_rows = 2 * even.num_rows();
+        let num_columns = std::cmp::max(even.num_columns(), odd.num_columns());
+
+        let mut trace = TraceTable::with_layout(num_rows, num_columns, TraceLayout::ColumnMajor);
+        for (j, column) in even.trace.columns().enumerate() {
+            for (target, value) in trace.column_mut(j).iter_mut().step_by(2).zip(column) {
+                *target = value.clone();
+            }
+        }
+        for (j, column) in odd.trace.columns().enumerate() {
+            for (target, value) in trace
+                .column_mut(j)
+                .iter_mut()
+                .skip(1)
+                .step_by(2)
+                .zip(column)
+            {
+                *target = value.clone();
+            }
+        }
+
+        let constraints = vertical_expressions(num_rows, &even.constraints, &odd.constraints);
+        Self { trace, constraints }
+    }
+
+    /// Spread the rows of a component out over a longer trace.
+    ///
+    /// Row `i` of `component` becomes row `stride * i + offset`, all other
+    /// rows are zero. This lets a component run at a lower rate than the
+    /// components it is combined with using [`Component::horizontal`], for
+    /// example one row per hash next to a component that uses `stride` rows
+    /// per hash. The constraints are relabeled like in
+    /// [`Component::vertical`], which is the special case of two components
+    /// with stride two.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stride` is not a power of two or `offset` is not less than
+    /// `stride`.
+    pub fn strided(component: &Self, stride: usize, offset: usize) -> Self {
+        assert!(stride.is_power_of_two());
+        assert!(offset < stride);
+        let num_rows = stride * component.num_rows();
+        let mut trace =
+            TraceTable::with_layout(num_rows, component.num_columns(), TraceLayout::ColumnMajor);
+        for (j, column) in component.trace.columns().enumerate() {
+            for (target, value) in trace
+                .column_mut(j)
+                .iter_mut()
+                .skip(offset)
+                .step_by(stride)
+                .zip(column)
+            {
+                *target = value.clone();
+            }
+        }
+        let constraints = relabel_strided(num_rows, stride, offset, &component.constraints);
+        Self { trace, constraints }
+    }
+
+    /// The constraints of [`Component::strided`] for a component with
+    /// `num_rows` rows and the given constraints.
+    pub fn strided_expressions(
+        num_rows: usize,
+        stride: usize,
+        offset: usize,
+        expressions: &[RationalExpression],
+    ) -> Vec<RationalExpression> {
+        relabel_strided(stride * num_rows, stride, offset, expressions)
+    }
+
+    /// Combine a power of two number of components into one.
+    ///
+    /// This is used to prove many claims of the same constraint system in a
+    /// single proof. The components are interleaved using
+    /// [`Component::vertical`], so the trace has the width of a single
+    /// component. The constraints are those of each component in order. A
+    /// verifier can construct them without the traces using
+    /// [`Component::aggregate_expressions`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of components is not a power of two or if the
+    /// components do not have the same number of rows.
+    pub fn aggregate(components: &[Self]) -> Self {
+        assert!(components.len().is_power_of_two());
+        if let [component] = components {
+            return Self::new(component.trace.clone(), component.constraints.clone());
+        }
+        let (even, odd) = components.split_at(components.len() / 2);
+        Self::vertical(&Self::aggregate(even), &Self::aggregate(odd))
+    }
+
+    /// The constraints of [`Component::aggregate`] for components with
+    /// `num_rows` rows and the given constraints.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of components is not a power of two.
+    pub fn aggregate_expressions(
+        num_rows: usize,
+        expressions: &[Vec<RationalExpression>],
+    ) -> Vec<RationalExpression> {
+        assert!(expressions.len().is_power_of_two());
+        if let [expressions] = expressions {
+            return expressions.clone();
+        }
+        let (even, odd) = expressions.split_at(expressions.len() / 2);
+        vertical_expressions(
+            2 * even.len() * num_rows,
+            &Self::aggregate_expressions(num_rows, even),
+            &Self::aggregate_expressions(num_rows, odd),
+        )
+    }
+
+    /// Create the constraint system for this component.
+    pub fn to_constraints(&self, channel_seed: Vec<u8>) -> Result<Constraints, ConstraintError> {
+        Constraints::from_expressions(
+            (self.num_rows(), self.num_columns()),
+            channel_seed,
+            self.constraints.clone(),
+        )
+    }
+
+    /// The generator of the component's trace domain.
+    pub fn generator(&self) -> FieldElement {
+        self.trace.generator()
+    }
+}
+
+// Relabel constraints for `Component::vertical`, where `num_rows` is the
+// length of the combined trace.
+fn vertical_expressions(
+    num_rows: usize,
+    even: &[RationalExpression],
+    odd: &[RationalExpression],
+) -> Vec<RationalExpression> {
+    let mut expressions = relabel_strided(num_rows, 2, 0, even);
+    expressions.extend(relabel_strided(num_rows, 2, 1, odd));
+    expressions
+}
+
+// Relabel constraints for `Component::strided`, where `num_rows` is the
+// length of the strided trace.
+//
+// The constraints use the generator `g^stride` of the component's own trace
+// length, where `g` is the generator of the strided trace. Row `i` of the
+// component is at `x = g^(stride i + offset)`, so replacing `X` by
+// `X g^(-offset)` makes the component's points line up with its rows.
+fn relabel_strided(
+    num_rows: usize,
+    stride: usize,
+    offset: usize,
+    expressions: &[RationalExpression],
+) -> Vec<RationalExpression> {
+    use RationalExpression::*;
+    let generator = FieldElement::root(num_rows).expect("No generator for trace length.");
+    let shift = generator
+        .inv()
+        .expect("Generator is never zero.")
+        .pow(offset);
+    let x = if offset == 0 { X } else { X * Constant(shift) };
+    // Strides are small
+    #[allow(clippy::cast_possible_wrap)]
+    let stride = stride as isize;
+    let rows = |i, j| Trace(i, stride * j);
+    expressions
+        .iter()
+        .map(|c| c.substitute(&x, &rows))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{
+            fibonacci::{Claim, Witness},
+            range_check,
+        },
+        prove, verify, Provable,
+    };
+
+    fn fibonacci(index: usize, secret: usize) -> Component {
+        let claim = Claim {
+            index,
+            value: FieldElement::ZERO,
+        };
+        let witness = Witness {
+            secret: secret.into(),
+        };
+        let value = claim.trace(&witness)[(index, 0)].clone();
+        Claim { index, value }.component(&witness)
+    }
+
+    #[test]
+    fn horizontal_test() {
+        let left = fibonacci(13, 7);
+        let right = fibonacci(11, 42);
+        let component = Component::horizontal(&left, &right);
+        assert_eq!(component.num_rows(), 16);
+        assert_eq!(component.num_columns(), 4);
+        assert_eq!(component.expressions().len(), 8);
+        assert_eq!(component.trace()[(3, 2)], right.trace()[(3, 0)]);
+        let constraints = component.to_constraints(vec![]).unwrap();
+        assert_eq!(constraints.check_trace(component.trace()), Ok(()));
+
+        let proof = prove(&constraints, component.trace()).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn range_check_test() {
+        let range_check = range_check::Claim {
+            bits:  12,
+            value: 1234.into(),
+        };
+        let component = Component::horizontal(&fibonacci(13, 7), &range_check.component());
+        let constraints = component.to_constraints(vec![]).unwrap();
+        let proof = prove(&constraints, component.trace()).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn vertical_test() {
+        let even = fibonacci(13, 7);
+        let odd = Component::horizontal(&fibonacci(11, 42), &fibonacci(9, 3));
+        let component = Component::vertical(&even, &odd);
+        assert_eq!(component.num_rows(), 32);
+        assert_eq!(component.num_columns(), 4);
+        assert_eq!(component.expressions().len(), 12);
+        assert_eq!(component.trace()[(6, 1)], even.trace()[(3, 1)]);
+        assert_eq!(component.trace()[(7, 3)], odd.trace()[(3, 3)]);
+        assert_eq!(component.trace()[(6, 3)], FieldElement::ZERO);
+        let constraints = component.to_constraints(vec![]).unwrap();
+        assert_eq!(constraints.check_trace(component.trace()), Ok(()));
+
+        let proof = prove(&constraints, component.trace()).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn vertical_invalid_test() {
+        let component = Component::vertical(&fibonacci(13, 7), &fibonacci(11, 42));
+        let mut trace = component.trace().clone();
+        // Row 5 of the odd component
+        trace[(11, 0)] += FieldElement::ONE;
+        let constraints = component.to_constraints(vec![]).unwrap();
+        let rows = constraints
+            .check_trace(&trace)
+            .unwrap_err()
+            .iter()
+            .map(|violation| (violation.constraint, violation.row))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![(4, 9), (5, 11)]);
+    }
+
+    #[test]
+    fn aggregate_test() {
+        let claims: Vec<_> = (0..4)
+            .map(|i| {
+                let witness = Witness {
+                    secret: (i + 1).into(),
+                };
+                let claim = Claim {
+                    index: 10 + i,
+                    value: FieldElement::ZERO,
+                };
+                let value = claim.trace(&witness)[(10 + i, 0)].clone();
+                (
+                    Claim {
+                        index: 10 + i,
+                        value,
+                    },
+                    witness,
+                )
+            })
+            .collect();
+        let components: Vec<_> = claims
+            .iter()
+            .map(|(claim, witness)| claim.component(witness))
+            .collect();
+        let component = Component::aggregate(&components);
+        assert_eq!(component.num_rows(), 64);
+        assert_eq!(component.num_columns(), 2);
+        assert_eq!(
+            component.trace()[(4 * 5 + 1, 0)],
+            components[2].trace()[(5, 0)]
+        );
+
+        // The verifier only needs the claims
+        let expressions = Component::aggregate_expressions(
+            16,
+            &claims
+                .iter()
+                .map(|(claim, _)| claim.expressions())
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(expressions, component.expressions());
+        let constraints = Constraints::from_expressions((64, 2), vec![], expressions).unwrap();
+        let proof = prove(&constraints, component.trace()).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn strided_test() {
+        let range_check = range_check::Claim {
+            bits:  7,
+            value: 100.into(),
+        }
+        .component();
+        let strided = Component::strided(&range_check, 4, 1);
+        assert_eq!(strided.num_rows(), 32);
+        assert_eq!(strided.trace()[(4 * 3 + 1, 0)], range_check.trace()[(3, 0)]);
+        assert_eq!(strided.trace()[(4 * 3 + 2, 0)], FieldElement::ZERO);
+        assert_eq!(
+            strided.expressions(),
+            &Component::strided_expressions(8, 4, 1, range_check.expressions())[..]
+        );
+
+        let component = Component::horizontal(&fibonacci(20, 3), &strided);
+        let constraints = component.to_constraints(vec![]).unwrap();
+        assert_eq!(constraints.check_trace(component.trace()), Ok(()));
+        let proof = prove(&constraints, component.trace()).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+    }
+}
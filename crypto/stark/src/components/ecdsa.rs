@@ -0,0 +1,336 @@
+//! Knowledge of an ECDSA signature on the Stark curve.
+//!
+//! The claim is that the prover knows a signature `(r, w)`, with `w` the
+//! inverse of the usual `s`, such that
+//! `zkp_elliptic_curve_crypto::verify(message_hash, r, w, public_key)` holds.
+//! That is, the `x` coordinate of
+//!
+//! ```text
+//! R = w · (message_hash · G + r · Q)
+//! ```
+//!
+//! is `r`, where `Q` is the public key. The scalars are used as integers,
+//! which gives the same point as the reduced products in `verify` because the
+//! curve has prime order.
+//!
+//! The trace has eight columns and four phases of 256 rows. Each phase is a
+//! double-and-add scalar multiplication over 251 bits with the same
+//! constraints as the Pedersen hash: column `0` holds the remaining scalar
+//! `s` with bit `s_i - 2 s_(i+1)`, columns `1, 2` the accumulator, columns
+//! `3, 4` the doubled base point, and columns `5, 6, 7` the addition slope,
+//! the inverse of the `x` difference and the doubling slope. The accumulator
+//! starts at the Pedersen shift point, so the incomplete addition formulas
+//! never meet the point at infinity, and the inverse column rules out adding
+//! a point to itself.
+//!
+//! 1. `S + message_hash · G`
+//! 2. `S + message_hash · G + r · Q`, after which the shift is subtracted to
+//!    get the base point `B` of the next phase.
+//! 3. `S + w · B`, after which the shift is subtracted and the `x` coordinate
+//!    compared with `r` from the second phase.
+//! 4. Unused, a multiplication by zero.
+use crate::{ClaimBytes, Constraints, RationalExpression, Verifiable};
+#[cfg(feature = "prover")]
+use crate::{Component, Provable, TraceTable};
+use std::prelude::v1::*;
+use zkp_elliptic_curve::{Affine, CurveParameters, StarkCurve};
+use zkp_elliptic_curve_crypto::SHIFT_POINT;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Claim {
+    pub message_hash: U256,
+    pub public_key:   Affine,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Witness {
+    pub r: U256,
+    pub w: U256,
+}
+
+// Bits per scalar, as in `zkp_elliptic_curve_crypto::verify`
+const BITS: usize = 251;
+const PHASE_ROWS: usize = 256;
+const PHASES: usize = 4;
+const TRACE_LENGTH: usize = PHASES * PHASE_ROWS;
+const COLUMNS: usize = 8;
+
+fn coordinates(point: &Affine) -> (FieldElement, FieldElement) {
+    match point {
+        Affine::Zero => panic!("Point at infinity"),
+        Affine::Point { x, y } => (x.clone(), y.clone()),
+    }
+}
+
+// The row after the last bit of `phase`
+fn result_row(phase: usize) -> usize {
+    phase * PHASE_ROWS + BITS
+}
+
+impl Claim {
+    /// The constraints on a trace table of `trace_length` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message hash has more than 251 bits or the public key is
+    /// the point at infinity.
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+        assert!(self.message_hash.bits() <= BITS);
+
+        // Constraint repetitions
+        let trace_generator = FieldElement::root(TRACE_LENGTH).unwrap();
+        let g = Constant(trace_generator.clone());
+        let phase_generator = Constant(trace_generator.pow(PHASES));
+        let on_row = |index| (X - g.pow(index)).inv();
+        let on_step_rows = || {
+            (BITS..PHASE_ROWS)
+                .map(|row| X.pow(PHASES) - phase_generator.pow(row))
+                .fold(Constant(FieldElement::ONE), |product, factor| {
+                    product * factor
+                })
+                / (X.pow(TRACE_LENGTH) - 1.into())
+        };
+        let on_result_rows = || (X.pow(PHASES) - phase_generator.pow(BITS)).inv();
+
+        let (shift_x, shift_y) = coordinates(&SHIFT_POINT);
+        let (generator_x, generator_y) = coordinates(&StarkCurve::GENERATOR);
+        let (key_x, key_y) = coordinates(&self.public_key);
+        let constant = |value: &FieldElement| Constant(value.clone());
+        let one = || Constant(FieldElement::ONE);
+
+        // Double and add
+        let bit = Trace(0, 0) - Trace(0, 1) * 2.into();
+        let (acc_x, acc_y) = (Trace(1, 0), Trace(2, 0));
+        let (base_x, base_y) = (Trace(3, 0), Trace(4, 0));
+        let (add_slope, inverse, double_slope) = (Trace(5, 0), Trace(6, 0), Trace(7, 0));
+        let mut expressions = vec![
+            bit.clone() * (bit.clone() - one()) * on_step_rows(),
+            bit.clone()
+                * (add_slope.clone() * (base_x.clone() - acc_x.clone())
+                    - (base_y.clone() - acc_y.clone()))
+                * on_step_rows(),
+            bit.clone()
+                * (inverse.clone() * (base_x.clone() - acc_x.clone()) - one())
+                * on_step_rows(),
+            (Trace(1, 1)
+                - bit.clone()
+                    * (add_slope.clone() * add_slope.clone() - acc_x.clone() - base_x.clone())
+                - (one() - bit.clone()) * acc_x.clone())
+                * on_step_rows(),
+            (Trace(2, 1)
+                - bit.clone()
+                    * (add_slope.clone() * (acc_x.clone() - Trace(1, 1)) - acc_y.clone())
+                - (one() - bit) * acc_y.clone())
+                * on_step_rows(),
+            (double_slope.clone() * base_y.clone() * 2.into()
+                - base_x.clone() * base_x.clone() * 3.into()
+                - constant(&StarkCurve::A))
+                * on_step_rows(),
+            (Trace(3, 1) - double_slope.clone() * double_slope.clone() + base_x.clone() * 2.into())
+                * on_step_rows(),
+            (Trace(4, 1) - double_slope.clone() * (base_x.clone() - Trace(3, 1)) + base_y.clone())
+                * on_step_rows(),
+            Trace(0, 0) * on_result_rows(),
+        ];
+
+        // Subtracting the shift point from the accumulator on a result row
+        let unshift_slope = |row| {
+            (add_slope.clone() * (constant(&shift_x) - acc_x.clone())
+                + constant(&shift_y)
+                + acc_y.clone())
+                * on_row(row)
+        };
+        let unshift_inverse =
+            |row| (inverse.clone() * (constant(&shift_x) - acc_x.clone()) - one()) * on_row(row);
+        let unshift_x =
+            || add_slope.clone() * add_slope.clone() - acc_x.clone() - constant(&shift_x);
+        #[allow(clippy::cast_possible_wrap)]
+        let to_next_phase = (PHASE_ROWS - BITS) as isize;
+        #[allow(clippy::cast_possible_wrap)]
+        let to_r = -((result_row(2) - PHASE_ROWS) as isize);
+
+        expressions.extend(vec![
+            // Phase 1: message_hash · G
+            (Trace(0, 0) - constant(&FieldElement::from(&self.message_hash))) * on_row(0),
+            (acc_x.clone() - constant(&shift_x)) * on_row(0),
+            (acc_y.clone() - constant(&shift_y)) * on_row(0),
+            (base_x.clone() - constant(&generator_x)) * on_row(0),
+            (base_y.clone() - constant(&generator_y)) * on_row(0),
+            // Phase 2: r · Q
+            (Trace(1, to_next_phase) - acc_x.clone()) * on_row(result_row(0)),
+            (Trace(2, to_next_phase) - acc_y.clone()) * on_row(result_row(0)),
+            (base_x.clone() - constant(&key_x)) * on_row(PHASE_ROWS),
+            (base_y.clone() - constant(&key_y)) * on_row(PHASE_ROWS),
+            // Phase 3: w · B
+            unshift_slope(result_row(1)),
+            unshift_inverse(result_row(1)),
+            (Trace(3, to_next_phase) - unshift_x()) * on_row(result_row(1)),
+            (Trace(4, to_next_phase)
+                - add_slope.clone() * (acc_x.clone() - Trace(3, to_next_phase))
+                + acc_y.clone())
+                * on_row(result_row(1)),
+            (acc_x.clone() - constant(&shift_x)) * on_row(2 * PHASE_ROWS),
+            (acc_y.clone() - constant(&shift_y)) * on_row(2 * PHASE_ROWS),
+            // The x coordinate of R is r
+            unshift_slope(result_row(2)),
+            unshift_inverse(result_row(2)),
+            (unshift_x() - Trace(0, to_r)) * on_row(result_row(2)),
+        ]);
+        expressions
+    }
+
+    /// The trace and constraints as a component for building larger
+    /// constraint systems.
+    #[cfg(feature = "prover")]
+    pub fn component(&self, witness: &Witness) -> Component {
+        Component::new(self.trace(witness), self.expressions())
+    }
+}
+
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        let (x, y) = coordinates(&self.public_key);
+        self.message_hash.write_claim_bytes(bytes);
+        x.write_claim_bytes(bytes);
+        y.write_claim_bytes(bytes);
+    }
+}
+
+impl Verifiable for Claim {
+    fn constraints(&self) -> Constraints {
+        Constraints::from_expressions(
+            (TRACE_LENGTH, COLUMNS),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap()
+    }
+}
+
+// Write a scalar multiplication phase and return the accumulator.
+#[cfg(feature = "prover")]
+fn scalar_mul(
+    trace: &mut TraceTable,
+    phase: usize,
+    scalar: &U256,
+    accumulator: (FieldElement, FieldElement),
+    base: (FieldElement, FieldElement),
+) -> (FieldElement, FieldElement) {
+    let (mut acc_x, mut acc_y) = accumulator;
+    let (mut base_x, mut base_y) = base;
+    for i in 0..=BITS {
+        let row = phase * PHASE_ROWS + i;
+        trace[(row, 0)] = FieldElement::from(scalar.clone() >> i);
+        trace[(row, 1)] = acc_x.clone();
+        trace[(row, 2)] = acc_y.clone();
+        trace[(row, 3)] = base_x.clone();
+        trace[(row, 4)] = base_y.clone();
+        if i == BITS {
+            break;
+        }
+        if scalar.bit(i) {
+            let inverse = (&base_x - &acc_x).inv().expect("Adding a point to itself");
+            let slope = (&base_y - &acc_y) * &inverse;
+            let x = slope.square() - &acc_x - &base_x;
+            acc_y = &slope * (&acc_x - &x) - &acc_y;
+            acc_x = x;
+            trace[(row, 5)] = slope;
+            trace[(row, 6)] = inverse;
+        }
+        let slope = (base_x.square().triple() + &StarkCurve::A) / base_y.double();
+        let x = slope.square() - base_x.double();
+        base_y = &slope * (&base_x - &x) - &base_y;
+        base_x = x;
+        trace[(row, 7)] = slope;
+    }
+    (acc_x, acc_y)
+}
+
+// Subtract the shift point from the accumulator on a result row.
+#[cfg(feature = "prover")]
+fn unshift(
+    trace: &mut TraceTable,
+    row: usize,
+    accumulator: &(FieldElement, FieldElement),
+) -> (FieldElement, FieldElement) {
+    let (shift_x, shift_y) = coordinates(&SHIFT_POINT);
+    let (acc_x, acc_y) = accumulator;
+    let inverse = (&shift_x - acc_x).inv().expect("Result is the shift point");
+    let slope = (-&shift_y - acc_y) * &inverse;
+    let x = slope.square() - acc_x - &shift_x;
+    let y = &slope * (acc_x - &x) - acc_y;
+    trace[(row, 5)] = slope;
+    trace[(row, 6)] = inverse;
+    (x, y)
+}
+
+#[cfg(feature = "prover")]
+impl Provable<&Witness> for Claim {
+    fn trace(&self, witness: &Witness) -> TraceTable {
+        let mut trace = TraceTable::new(TRACE_LENGTH, COLUMNS);
+        let shift = coordinates(&SHIFT_POINT);
+        let generator = coordinates(&StarkCurve::GENERATOR);
+        let accumulator = scalar_mul(
+            &mut trace,
+            0,
+            &self.message_hash,
+            shift.clone(),
+            generator.clone(),
+        );
+        let accumulator = scalar_mul(
+            &mut trace,
+            1,
+            &witness.r,
+            accumulator,
+            coordinates(&self.public_key),
+        );
+        let base = unshift(&mut trace, result_row(1), &accumulator);
+        let accumulator = scalar_mul(&mut trace, 2, &witness.w, shift.clone(), base);
+        let _ = unshift(&mut trace, result_row(2), &accumulator);
+        let _ = scalar_mul(&mut trace, 3, &U256::ZERO, shift, generator);
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverError;
+    use zkp_elliptic_curve_crypto::{private_to_public, sign};
+    use zkp_macros_decl::u256h;
+
+    #[test]
+    fn ecdsa_test() {
+        let private_key =
+            u256h!("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc");
+        let message_hash =
+            u256h!("01e542e2da71b3f5d7b4e9d329b4d30ac0b5d6f266ebef7364bf61c39aac35d0");
+        let (r, w) = sign(&message_hash, &private_key);
+        let claim = Claim {
+            message_hash,
+            public_key: private_to_public(&private_key),
+        };
+        let witness = Witness { r, w };
+        let trace = claim.trace(&witness);
+        assert_eq!(claim.constraints().check_trace(&trace), Ok(()));
+        let proof = claim.prove(&witness).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+
+        let claim = Claim {
+            message_hash: U256::from(7),
+            ..claim
+        };
+        assert_eq!(
+            claim.prove(&witness),
+            Err(ProverError::ConstraintUnsatisfied {
+                constraint: 26,
+                row:        result_row(2),
+            })
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Fibonacci-like sequence with a secret second element.
+//!
+//! The claim is that the `index`-th element of the sequence starting with
+//! `1, secret` is `value`. The trace has two columns holding consecutive
+//! elements of the sequence and is padded to a power of two with further
+//! elements. A [`MultiClaim`] proves several elements of the same sequence
+//! at once.
+//!
+//! With the `json` feature [`Claim`] and [`Witness`] implement serde's
+//! `Serialize` and `Deserialize`. Field elements are hexadecimal strings as
+//! in the AIR description:
+//!
+//! ```json
+//! { "index": 1000, "value": "0x0142c45e5d743d10eae7ebb70f1526c65de7dbcdb65b322b6ddc36a812591e8f" }
+//! { "secret": "0x00000000000000000000000000000000000000000000000000000000cafebabe" }
+//! ```
+use crate::{ClaimBytes, Constraints, RationalExpression, Verifiable};
+#[cfg(feature = "prover")]
+use crate::{Component, Provable, TraceTable};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Claim {
+    pub index: usize,
+    #[cfg_attr(feature = "json", serde(with = "crate::hex_field"))]
+    pub value: FieldElement,
+}
+
+/// Claim on the values of several elements of the sequence.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct MultiClaim {
+    /// Pairs of `(index, value)` in any order.
+    pub values: Vec<(usize, FieldElement)>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Witness {
+    #[cfg_attr(feature = "json", serde(with = "crate::hex_field"))]
+    pub secret: FieldElement,
+}
+
+#[cfg(feature = "json")]
+impl Claim {
+    /// The claim as JSON, see the [module documentation](index.html).
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Claim serialization does not fail")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+#[cfg(feature = "json")]
+impl Witness {
+    /// The witness as JSON, see the [module documentation](index.html).
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Witness serialization does not fail")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+// The smallest power of two trace that contains row `index`.
+fn trace_length(max_index: usize) -> usize {
+    std::cmp::max(max_index + 1, 2).next_power_of_two()
+}
+
+fn expressions(trace_length: usize, values: &[(usize, FieldElement)]) -> Vec<RationalExpression> {
+    use RationalExpression::*;
+
+    // Constraint repetitions
+    let trace_generator = FieldElement::root(trace_length).unwrap();
+    let g = Constant(trace_generator);
+    let on_row = |index| (X - g.pow(index)).inv();
+    let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1.into());
+
+    let mut expressions = vec![
+        (Trace(0, 1) - Trace(1, 0)) * every_row(),
+        (Trace(1, 1) - Trace(0, 0) - Trace(1, 0)) * every_row(),
+        (Trace(0, 0) - 1.into()) * on_row(0),
+    ];
+    expressions.extend(
+        values
+            .iter()
+            .map(|(index, value)| (Trace(0, 0) - value.into()) * on_row(*index)),
+    );
+    expressions
+}
+
+#[cfg(feature = "prover")]
+fn trace(trace_length: usize, witness: &Witness) -> TraceTable {
+    TraceTable::from_recurrence(
+        trace_length,
+        vec![1.into(), witness.secret.clone()],
+        |_, previous| vec![previous[1].clone(), &previous[0] + &previous[1]],
+    )
+}
+
+impl Claim {
+    fn trace_length(&self) -> usize {
+        trace_length(self.index)
+    }
+
+    /// The constraints on a trace table of `trace_length` rows.
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        expressions(self.trace_length(), &[(self.index, self.value.clone())])
+    }
+
+    /// The trace and constraints as a component for building larger
+    /// constraint systems.
+    #[cfg(feature = "prover")]
+    pub fn component(&self, witness: &Witness) -> Component {
+        Component::new(self.trace(witness), self.expressions())
+    }
+}
+
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.index.write_claim_bytes(bytes);
+        self.value.write_claim_bytes(bytes);
+    }
+}
+
+impl Verifiable for Claim {
+    fn constraints(&self) -> Constraints {
+        Constraints::from_expressions(
+            (self.trace_length(), 2),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(feature = "prover")]
+impl Provable<&Witness> for Claim {
+    fn trace(&self, witness: &Witness) -> TraceTable {
+        trace(self.trace_length(), witness)
+    }
+}
+
+impl MultiClaim {
+    fn trace_length(&self) -> usize {
+        trace_length(
+            self.values
+                .iter()
+                .map(|(index, _)| *index)
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    /// The constraints on a trace table long enough for the largest index.
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        expressions(self.trace_length(), &self.values)
+    }
+
+    /// The trace and constraints as a component for building larger
+    /// constraint systems.
+    #[cfg(feature = "prover")]
+    pub fn component(&self, witness: &Witness) -> Component {
+        Component::new(self.trace(witness), self.expressions())
+    }
+}
+
+impl ClaimBytes for MultiClaim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.values.len().write_claim_bytes(bytes);
+        self.values.write_claim_bytes(bytes);
+    }
+}
+
+impl Verifiable for MultiClaim {
+    fn constraints(&self) -> Constraints {
+        Constraints::from_expressions(
+            (self.trace_length(), 2),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(feature = "prover")]
+impl Provable<&Witness> for MultiClaim {
+    fn trace(&self, witness: &Witness) -> TraceTable {
+        trace(self.trace_length(), witness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverError;
+
+    #[test]
+    fn fibonacci_test() {
+        let witness = Witness { secret: 5.into() };
+        // 1, 5, 6, 11, 17, 28
+        let claim = Claim {
+            index: 5,
+            value: 28.into(),
+        };
+        let proof = claim.prove(&witness).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+
+        let claim = Claim {
+            index: 5,
+            value: 29.into(),
+        };
+        assert_eq!(
+            claim.prove(&witness),
+            Err(ProverError::ConstraintUnsatisfied {
+                constraint: 3,
+                row:        5,
+            })
+        );
+    }
+
+    #[test]
+    fn power_of_two_index_test() {
+        // 1, 5, 6, 11, 17, 28, 45, 73, 118
+        let witness = Witness { secret: 5.into() };
+        let claim = Claim {
+            index: 8,
+            value: 118.into(),
+        };
+        assert_eq!(claim.constraints().trace_nrows(), 16);
+        let proof = claim.prove(&witness).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+    }
+
+    #[test]
+    fn multi_claim_test() {
+        let witness = Witness { secret: 5.into() };
+        let claim = MultiClaim {
+            values: vec![(5, 28.into()), (2, 6.into()), (7, 73.into())],
+        };
+        assert_eq!(claim.constraints().trace_nrows(), 8);
+        assert_eq!(claim.expressions().len(), 6);
+        let proof = claim.prove(&witness).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+
+        let claim = MultiClaim {
+            values: vec![(5, 28.into()), (2, 7.into())],
+        };
+        assert_eq!(
+            claim.prove(&witness),
+            Err(ProverError::ConstraintUnsatisfied {
+                constraint: 4,
+                row:        2,
+            })
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_test() {
+        let claim = Claim {
+            index: 5,
+            value: 28.into(),
+        };
+        let json = claim.to_json();
+        assert_eq!(
+            json,
+            "{\"index\":5,\"value\":\"\
+             0x000000000000000000000000000000000000000000000000000000000000001c\"}"
+        );
+        assert_eq!(Claim::from_json(&json).unwrap(), claim);
+
+        let witness = Witness::from_json("{\"secret\":\"0x05\"}").unwrap();
+        assert_eq!(witness, Witness { secret: 5.into() });
+        assert_eq!(Witness::from_json(&witness.to_json()).unwrap(), witness);
+        assert!(Witness::from_json(
+            "{\"secret\":\"0x0800000000000011000000000000000000000000000000000000000000000001\"}"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,415 @@
+//! Keccak-256 of a secret single block message.
+//!
+//! The claim is that the prover knows a message of `length` bytes, at most
+//! 135, whose Keccak-256 hash, as computed by the EVM, is `hash`. The message
+//! fits in a single block, so the hash is one Keccak-f[1600] permutation of
+//! the padded message.
+//!
+//! The trace has one row per round of the permutation and works on bits. Row
+//! `i` holds
+//!
+//! * the 1600 bits of the state at the start of round `i`, lane `x + 5 y` in
+//!   columns `64 (x + 5 y)..64 (x + 5 y + 1)` with the least significant bit
+//!   first,
+//! * the 320 column parities `C[x][z]` of the theta step, and
+//! * the 1600 bits of the state after the theta, rho and pi steps.
+//!
+//! The parities are bits with `sum_y A[x][y][z] - C[x][z]` in `{0, 2, 4}`,
+//! the theta output is a three way exclusive or and the chi and iota steps
+//! give the next row, all of degree at most three. Rho and pi only move bits
+//! around and are part of the column assignment. Row 24 holds the output and
+//! the trace is padded with zeros to 32 rows.
+use crate::{ClaimBytes, Constraints, PeriodicColumn, RationalExpression, Verifiable};
+#[cfg(feature = "prover")]
+use crate::{Component, Provable, TraceTable};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Claim {
+    pub length: usize,
+    pub hash:   [u8; 32],
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Witness {
+    pub message: Vec<u8>,
+}
+
+/// Rate of Keccak-256 in bytes, messages must be shorter than this.
+pub const RATE: usize = 136;
+
+const ROUNDS: usize = 24;
+const TRACE_LENGTH: usize = 32;
+const LANE_BITS: usize = 64;
+const STATE_BITS: usize = 25 * LANE_BITS;
+const PARITY_COLUMNS: usize = STATE_BITS;
+const THETA_COLUMNS: usize = PARITY_COLUMNS + 5 * LANE_BITS;
+const COLUMNS: usize = THETA_COLUMNS + STATE_BITS;
+
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808a,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808b,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008a,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000a,
+    0x0000_0000_8000_808b,
+    0x8000_0000_0000_008b,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800a,
+    0x8000_0000_8000_000a,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+// Rotation of lane `x + 5 y` in the rho step
+const ROTATIONS: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+fn lane(x: usize, y: usize) -> usize {
+    x % 5 + 5 * (y % 5)
+}
+
+// Destination lane of lane `(x, y)` in the pi step
+fn pi(x: usize, y: usize) -> usize {
+    lane(y, 2 * x + 3 * y)
+}
+
+fn state_column(lane: usize, z: usize) -> usize {
+    LANE_BITS * lane + z % LANE_BITS
+}
+
+fn parity_column(x: usize, z: usize) -> usize {
+    PARITY_COLUMNS + LANE_BITS * (x % 5) + z % LANE_BITS
+}
+
+fn theta_column(lane: usize, z: usize) -> usize {
+    THETA_COLUMNS + LANE_BITS * lane + z % LANE_BITS
+}
+
+/// The state of one round: the parities and the theta, rho and pi output.
+struct Round {
+    parities: [u64; 5],
+    theta:    [u64; 25],
+}
+
+// Apply round `index` to `state`.
+fn round(state: &mut [u64; 25], index: usize) -> Round {
+    let mut parities = [0; 5];
+    for (x, parity) in parities.iter_mut().enumerate() {
+        *parity = (0..5).fold(0, |parity, y| parity ^ state[lane(x, y)]);
+    }
+    let mut theta = [0; 25];
+    for x in 0..5 {
+        let d = parities[(x + 4) % 5] ^ parities[(x + 1) % 5].rotate_left(1);
+        for y in 0..5 {
+            theta[pi(x, y)] = (state[lane(x, y)] ^ d).rotate_left(ROTATIONS[lane(x, y)]);
+        }
+    }
+    for x in 0..5 {
+        for y in 0..5 {
+            state[lane(x, y)] =
+                theta[lane(x, y)] ^ (!theta[lane(x + 1, y)] & theta[lane(x + 2, y)]);
+        }
+    }
+    state[0] ^= ROUND_CONSTANTS[index];
+    Round { parities, theta }
+}
+
+/// The Keccak-f[1600] permutation.
+pub fn keccak_f(state: &mut [u64; 25]) {
+    for index in 0..ROUNDS {
+        let _ = round(state, index);
+    }
+}
+
+// The padded message as the initial state.
+fn initial_state(message: &[u8]) -> [u64; 25] {
+    assert!(message.len() < RATE);
+    let mut block = [0_u8; RATE];
+    block[..message.len()].copy_from_slice(message);
+    block[message.len()] ^= 0x01;
+    block[RATE - 1] ^= 0x80;
+    let mut state = [0; 25];
+    for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+        let mut word = [0; 8];
+        word.copy_from_slice(bytes);
+        *lane = u64::from_le_bytes(word);
+    }
+    state
+}
+
+impl Witness {
+    /// The claim on the hash of the message.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the message is not shorter than [`RATE`].
+    pub fn claim(&self) -> Claim {
+        let mut state = initial_state(&self.message);
+        keccak_f(&mut state);
+        let mut hash = [0; 32];
+        for (bytes, lane) in hash.chunks_mut(8).zip(state.iter()) {
+            bytes.copy_from_slice(&lane.to_le_bytes());
+        }
+        Claim {
+            length: self.message.len(),
+            hash,
+        }
+    }
+}
+
+fn bit(word: u64, z: usize) -> FieldElement {
+    FieldElement::from((word >> z) & 1)
+}
+
+impl Claim {
+    /// The constraints on a trace table of `trace_length` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length is not less than [`RATE`].
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+        assert!(self.length < RATE);
+
+        // Constraint repetitions
+        let trace_generator = FieldElement::root(TRACE_LENGTH).unwrap();
+        let g = Constant(trace_generator);
+        let on_row = |index| (X - g.pow(index)).inv();
+        let on_round_rows = || {
+            (ROUNDS..TRACE_LENGTH)
+                .map(|row| X - g.pow(row))
+                .fold(Constant(FieldElement::ONE), |product, factor| {
+                    product * factor
+                })
+                / (X.pow(TRACE_LENGTH) - 1.into())
+        };
+
+        let one = || Constant(FieldElement::ONE);
+        let two = || Constant(2.into());
+        let xor =
+            |a: RationalExpression, b: RationalExpression| a.clone() + b.clone() - two() * a * b;
+        let state = |lane, z| Trace(state_column(lane, z), 0);
+        let theta = |lane, z| Trace(theta_column(lane, z), 0);
+
+        let mut expressions = Vec::new();
+
+        // Theta parities
+        for x in 0..5 {
+            for z in 0..LANE_BITS {
+                let parity = Trace(parity_column(x, z), 0);
+                let sum: RationalExpression = (0..5).map(|y| state(lane(x, y), z)).sum();
+                let even = sum - parity.clone();
+                expressions.push(parity.clone() * (parity - one()) * on_round_rows());
+                expressions.push(
+                    even.clone()
+                        * (even.clone() - two())
+                        * (even - Constant(4.into()))
+                        * on_round_rows(),
+                );
+            }
+        }
+
+        // Theta, rho and pi
+        for x in 0..5 {
+            for y in 0..5 {
+                let rotation = ROTATIONS[lane(x, y)] as usize;
+                for z in 0..LANE_BITS {
+                    let value = xor(
+                        xor(state(lane(x, y), z), Trace(parity_column(x + 4, z), 0)),
+                        Trace(parity_column(x + 1, z + LANE_BITS - 1), 0),
+                    );
+                    expressions.push((theta(pi(x, y), z + rotation) - value) * on_round_rows());
+                }
+            }
+        }
+
+        // Chi and iota
+        for x in 0..5 {
+            for y in 0..5 {
+                for z in 0..LANE_BITS {
+                    let mut value = xor(
+                        theta(lane(x, y), z),
+                        (one() - theta(lane(x + 1, y), z)) * theta(lane(x + 2, y), z),
+                    );
+                    if lane(x, y) == 0 {
+                        let round_constant: Vec<FieldElement> = (0..TRACE_LENGTH)
+                            .map(|row| {
+                                if row < ROUNDS {
+                                    bit(ROUND_CONSTANTS[row], z)
+                                } else {
+                                    FieldElement::ZERO
+                                }
+                            })
+                            .collect();
+                        value = xor(
+                            value,
+                            PeriodicColumn::from_values(&round_constant).expression(TRACE_LENGTH),
+                        );
+                    }
+                    expressions
+                        .push((Trace(state_column(lane(x, y), z), 1) - value) * on_round_rows());
+                }
+            }
+        }
+
+        // The message bits are secret, the padding is public
+        let padding = initial_state(&vec![0; self.length]);
+        for lane in 0..25 {
+            for z in 0..LANE_BITS {
+                let column = state_column(lane, z);
+                if column < 8 * self.length {
+                    expressions.push(state(lane, z) * (state(lane, z) - one()) * on_row(0));
+                } else {
+                    expressions
+                        .push((state(lane, z) - (&bit(padding[lane], z)).into()) * on_row(0));
+                }
+            }
+        }
+
+        // The hash is the first 256 bits of the output
+        for (index, byte) in self.hash.iter().enumerate() {
+            for i in 0..8 {
+                let column = 8 * index + i;
+                let value = FieldElement::from((byte >> i) & 1);
+                expressions.push((Trace(column, 0) - (&value).into()) * on_row(ROUNDS));
+            }
+        }
+        expressions
+    }
+
+    /// The trace and constraints as a component for building larger
+    /// constraint systems.
+    #[cfg(feature = "prover")]
+    pub fn component(&self, witness: &Witness) -> Component {
+        Component::new(self.trace(witness), self.expressions())
+    }
+}
+
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.length.write_claim_bytes(bytes);
+        bytes.extend_from_slice(&self.hash);
+    }
+}
+
+impl Verifiable for Claim {
+    fn constraints(&self) -> Constraints {
+        Constraints::from_expressions(
+            (TRACE_LENGTH, COLUMNS),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(feature = "prover")]
+impl Provable<&Witness> for Claim {
+    fn trace(&self, witness: &Witness) -> TraceTable {
+        let mut trace = TraceTable::new(TRACE_LENGTH, COLUMNS);
+        let mut state = initial_state(&witness.message);
+        for index in 0..ROUNDS {
+            for (lane, word) in state.iter().enumerate() {
+                for z in 0..LANE_BITS {
+                    trace[(index, state_column(lane, z))] = bit(*word, z);
+                }
+            }
+            let Round { parities, theta } = round(&mut state, index);
+            for (x, word) in parities.iter().enumerate() {
+                for z in 0..LANE_BITS {
+                    trace[(index, parity_column(x, z))] = bit(*word, z);
+                }
+            }
+            for (lane, word) in theta.iter().enumerate() {
+                for z in 0..LANE_BITS {
+                    trace[(index, theta_column(lane, z))] = bit(*word, z);
+                }
+            }
+        }
+        for (lane, word) in state.iter().enumerate() {
+            for z in 0..LANE_BITS {
+                trace[(ROUNDS, state_column(lane, z))] = bit(*word, z);
+            }
+        }
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_keccak::Keccak;
+    use zkp_macros_decl::hex;
+
+    fn keccak256(message: &[u8]) -> [u8; 32] {
+        let mut keccak = Keccak::new_keccak256();
+        keccak.update(message);
+        let mut hash = [0; 32];
+        keccak.finalize(&mut hash);
+        hash
+    }
+
+    #[test]
+    fn keccak_f_test() {
+        let mut state = [0; 25];
+        keccak_f(&mut state);
+        assert_eq!(state[0], 0xf125_8f79_40e1_dde7);
+        assert_eq!(state[24], 0xeaf1_ff7b_5cec_a249);
+    }
+
+    #[test]
+    fn claim_test() {
+        let witness = Witness { message: vec![] };
+        assert_eq!(
+            witness.claim().hash,
+            hex!("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+        for length in &[1, 55, 134, 135] {
+            let message: Vec<u8> = (0..*length).map(|i| i as u8).collect();
+            let witness = Witness {
+                message: message.clone(),
+            };
+            assert_eq!(witness.claim().hash, keccak256(&message));
+        }
+    }
+
+    #[test]
+    fn keccak_constraints_test() {
+        let witness = Witness {
+            message: b"OpenZKP".to_vec(),
+        };
+        let claim = witness.claim();
+        let constraints = claim.constraints();
+        let mut trace = claim.trace(&witness);
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+
+        // Flip a bit of the message
+        let bit = state_column(0, 3);
+        trace[(0, bit)] = FieldElement::ONE - &trace[(0, bit)];
+        assert!(constraints.check_trace(&trace).is_err());
+
+        // Claim a message of different length
+        let claim = Claim { length: 8, ..claim };
+        assert!(claim
+            .constraints()
+            .check_trace(&claim.trace(&witness))
+            .is_err());
+    }
+}
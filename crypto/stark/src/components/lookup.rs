@@ -0,0 +1,88 @@
+//! Constraints that values belong to a fixed table.
+//!
+//! A value `v` is in the table `T` if and only if the table polynomial
+//! `P(x) = prod_(t in T) (x - t)` vanishes at `v`. The constraint
+//! `P(v) = 0` has degree `|T|` in the trace, so this is meant for small
+//! tables such as the nibbles `0..16` or the bytes in a byte-wise operation.
+//! Range checks can then use a few wide limbs instead of one row per bit.
+use crate::{polynomial::DensePolynomial, rational_expression::RationalExpression};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+
+/// The monic polynomial with the distinct entries of `table` as roots.
+pub fn table_polynomial(table: &[FieldElement]) -> DensePolynomial {
+    let mut roots: Vec<&FieldElement> = Vec::with_capacity(table.len());
+    for entry in table {
+        if !roots.contains(&entry) {
+            roots.push(entry);
+        }
+    }
+    let mut coefficients = vec![FieldElement::ZERO; (roots.len() + 1).next_power_of_two()];
+    coefficients[0] = FieldElement::ONE;
+    for (degree, root) in roots.into_iter().enumerate() {
+        // Multiply by (x - root)
+        for i in (0..=degree + 1).rev() {
+            let shifted = if i > 0 {
+                coefficients[i - 1].clone()
+            } else {
+                FieldElement::ZERO
+            };
+            coefficients[i] = shifted - &coefficients[i] * root;
+        }
+    }
+    DensePolynomial::new(&coefficients)
+}
+
+/// Constrain `expression` to take a value from `table` on every row of a
+/// trace of `trace_length` rows.
+///
+/// # Panics
+///
+/// Panics if `trace_length` is not a power of two.
+pub fn lookup(
+    expression: RationalExpression,
+    table: &[FieldElement],
+    trace_length: usize,
+) -> RationalExpression {
+    use RationalExpression::*;
+    assert!(trace_length.is_power_of_two());
+    Polynomial(table_polynomial(table), Box::new(expression)) / (X.pow(trace_length) - 1.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prove, verify, Constraints, TraceTable};
+    use quickcheck_macros::quickcheck;
+    use RationalExpression::*;
+
+    #[quickcheck]
+    fn table_polynomial_roots(table: Vec<FieldElement>, x: FieldElement) -> bool {
+        let polynomial = table_polynomial(&table);
+        table.iter().all(|t| polynomial.evaluate(t).is_zero())
+            && (table.contains(&x) || !polynomial.evaluate(&x).is_zero())
+    }
+
+    #[test]
+    fn table_polynomial_duplicates() {
+        let table: Vec<FieldElement> = vec![1.into(), 2.into(), 1.into()];
+        assert_eq!(table_polynomial(&table).degree(), 2);
+    }
+
+    #[test]
+    fn lookup_test() {
+        let table: Vec<FieldElement> = (0..4_usize).map(FieldElement::from).collect();
+        let constraints =
+            Constraints::from_expressions((8, 1), vec![], vec![lookup(Trace(0, 0), &table, 8)])
+                .unwrap();
+        let mut trace = TraceTable::from_rows(8, 1, |i| vec![FieldElement::from(i % 4)]);
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+
+        trace[(5, 0)] = 4.into();
+        let violations = constraints.check_trace(&trace).unwrap_err();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].row, 5);
+    }
+}
@@ -0,0 +1,274 @@
+//! Read-write memory checked with a permutation argument.
+//!
+//! Every row of the execution trace makes one access to memory: it reads or
+//! writes `value` at `address`. The accesses are copied to a second set of
+//! columns sorted by address and then by time, and a [`Permutation`] shows
+//! that both hold the same accesses. In sorted order memory consistency is a
+//! local property of consecutive rows:
+//!
+//! * a read returns the value of the previous access to the same address,
+//! * the first access to an address that is a read returns zero.
+//!
+//! The order is enforced by range checking the gap between consecutive rows,
+//! which is the time difference minus one for the same address and the
+//! address difference minus one otherwise. Memory starts out zero.
+//!
+//! [`Permutation`]: ../permutation/struct.Permutation.html
+use super::{
+    permutation::Permutation,
+    range_check::{Decomposition, RangeCheck},
+};
+#[cfg(feature = "prover")]
+use crate::TraceTable;
+use crate::{RationalExpression, RunningProduct};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+#[cfg(feature = "prover")]
+use zkp_u256::U256;
+
+// Columns relative to `first_column`
+const TIME: usize = 0;
+const ADDRESS: usize = 1;
+const SORTED_TIME: usize = 2;
+const VALUE: usize = 3;
+const WRITE: usize = 4;
+const SAME_ADDRESS: usize = 5;
+const GAP: usize = 6;
+const RANGE_CHECK: usize = 7;
+
+/// A memory access on one row of the execution trace.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Access {
+    pub address: usize,
+    pub value:   FieldElement,
+    pub write:   bool,
+}
+
+/// Constraint that the accesses on all rows are consistent with a memory.
+///
+/// The memory takes [`columns`](#method.columns) columns starting at
+/// `first_column`, which the prover fills with [`fill`](#method.fill). The
+/// gaps between consecutive sorted accesses, in address or in time, are
+/// checked to be less than `2^gap_bits`. This needs to hold for the
+/// trace length and the address range used.
+///
+/// Besides the [`expressions`](#method.expressions) the constraints need the
+/// [`running_product`](#method.running_product).
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Memory {
+    pub gap_bits:      usize,
+    pub decomposition: Decomposition,
+    pub first_column:  usize,
+}
+
+impl Memory {
+    fn range_check(&self) -> RangeCheck {
+        RangeCheck {
+            bits:          self.gap_bits,
+            decomposition: self.decomposition,
+            first_column:  self.first_column + RANGE_CHECK,
+        }
+    }
+
+    fn column(&self, column: usize, offset: isize) -> RationalExpression {
+        RationalExpression::Trace(self.first_column + column, offset)
+    }
+
+    /// The number of columns.
+    pub fn columns(&self) -> usize {
+        RANGE_CHECK + self.range_check().columns()
+    }
+
+    /// Constrain the sorted accesses on a trace of `trace_length` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the conditions of [`RangeCheck::expressions`].
+    ///
+    /// [`RangeCheck::expressions`]: ../range_check/struct.RangeCheck.html#method.expressions
+    pub fn expressions(&self, trace_length: usize) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator);
+        let first_row = || (X - 1.into()).inv();
+        let every_row = || (X.pow(trace_length) - 1.into()).inv();
+        let but_last_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1.into());
+        let one = || Constant(FieldElement::ONE);
+
+        let time = self.column(TIME, 0);
+        let address = self.column(ADDRESS, 0);
+        let next_address = self.column(ADDRESS, 1);
+        let sorted_time = self.column(SORTED_TIME, 0);
+        let next_sorted_time = self.column(SORTED_TIME, 1);
+        let value = self.column(VALUE, 0);
+        let next_value = self.column(VALUE, 1);
+        let write = self.column(WRITE, 0);
+        let next_read = one() - self.column(WRITE, 1);
+        let same = self.column(SAME_ADDRESS, 0);
+        let gap = self.column(GAP, 0);
+
+        let mut expressions = vec![
+            time.clone() * first_row(),
+            (self.column(TIME, 1) - time - 1.into()) * but_last_row(),
+            same.clone() * (same.clone() - 1.into()) * but_last_row(),
+            same.clone() * (next_address.clone() - address.clone()) * but_last_row(),
+            (gap.clone()
+                - same.clone() * (next_sorted_time - sorted_time - 1.into())
+                - (one() - same.clone()) * (next_address - address - 1.into()))
+                * but_last_row(),
+            same.clone()
+                * next_read.clone()
+                * (next_value.clone() - value.clone())
+                * but_last_row(),
+            (one() - same) * next_read * next_value * but_last_row(),
+            (one() - write.clone()) * value * first_row(),
+            write.clone() * (write - 1.into()) * every_row(),
+        ];
+        expressions.extend(self.range_check().expressions(gap, trace_length));
+        expressions
+    }
+
+    /// The running product that shows the sorted accesses are the accesses
+    /// given by `address`, `value` and `write` on the rows of the execution
+    /// trace.
+    ///
+    /// `write` should be one for writes and zero for reads.
+    pub fn running_product(
+        &self,
+        address: RationalExpression,
+        value: RationalExpression,
+        write: RationalExpression,
+    ) -> RunningProduct {
+        Permutation {
+            left:  vec![address, self.column(TIME, 0), value, write],
+            right: vec![
+                self.column(ADDRESS, 0),
+                self.column(SORTED_TIME, 0),
+                self.column(VALUE, 0),
+                self.column(WRITE, 0),
+            ],
+        }
+        .running_product()
+    }
+
+    /// Write the columns for the `accesses` on the rows of `trace`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is not one access per row.
+    #[cfg(feature = "prover")]
+    pub fn fill(&self, trace: &mut TraceTable, accesses: &[Access]) {
+        assert_eq!(accesses.len(), trace.num_rows());
+        let mut sorted: Vec<(usize, &Access)> = accesses.iter().enumerate().collect();
+        sorted.sort_by_key(|(time, access)| (access.address, *time));
+        let range_check = self.range_check();
+        for (row, (time, access)) in sorted.iter().enumerate() {
+            let (same, gap) = match sorted.get(row + 1) {
+                Some((next_time, next)) if next.address == access.address => {
+                    (true, next_time - time - 1)
+                }
+                Some((_, next)) => (false, next.address - access.address - 1),
+                None => (false, 0),
+            };
+            let mut set = |column: usize, value: FieldElement| {
+                trace[(row, self.first_column + column)] = value;
+            };
+            set(TIME, row.into());
+            set(ADDRESS, access.address.into());
+            set(SORTED_TIME, (*time).into());
+            set(VALUE, access.value.clone());
+            set(WRITE, u64::from(access.write).into());
+            set(SAME_ADDRESS, u64::from(same).into());
+            set(GAP, gap.into());
+            range_check.fill(trace, row, &U256::from(gap));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Constraints;
+
+    // Execution columns `address`, `value` and `write`, then the memory
+    fn memory_trace(accesses: &[Access]) -> (Constraints, TraceTable) {
+        use RationalExpression::*;
+        let memory = Memory {
+            gap_bits:      4,
+            decomposition: Decomposition::Bits,
+            first_column:  3,
+        };
+        let columns = 3 + memory.columns();
+        let mut constraints = Constraints::from_expressions(
+            (accesses.len(), columns),
+            vec![],
+            memory.expressions(accesses.len()),
+        )
+        .unwrap();
+        let _ = constraints
+            .add_running_product(memory.running_product(Trace(0, 0), Trace(1, 0), Trace(2, 0)))
+            .unwrap();
+        let mut trace = TraceTable::new(accesses.len(), columns);
+        for (row, access) in accesses.iter().enumerate() {
+            trace[(row, 0)] = access.address.into();
+            trace[(row, 1)] = access.value.clone();
+            trace[(row, 2)] = u64::from(access.write).into();
+        }
+        memory.fill(&mut trace, accesses);
+        (constraints, trace)
+    }
+
+    fn access(address: usize, value: usize, write: bool) -> Access {
+        Access {
+            address,
+            value: value.into(),
+            write,
+        }
+    }
+
+    fn accesses() -> Vec<Access> {
+        vec![
+            access(2, 5, true),
+            access(2, 5, false),
+            access(7, 0, false),
+            access(7, 3, true),
+            access(2, 5, false),
+            access(2, 9, true),
+            access(2, 9, false),
+            access(7, 3, false),
+        ]
+    }
+
+    #[test]
+    fn memory_test() {
+        let (constraints, trace) = memory_trace(&accesses());
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+        let proof = crate::prove(&constraints, &trace).unwrap();
+        assert_eq!(crate::verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn memory_inconsistent_read() {
+        let mut accesses = accesses();
+        accesses[4].value = 9.into();
+        let (constraints, trace) = memory_trace(&accesses);
+        assert!(constraints.check_trace(&trace).is_err());
+
+        // Uninitialized memory is zero
+        let mut accesses = self::accesses();
+        accesses[2].value = 1.into();
+        let (constraints, trace) = memory_trace(&accesses);
+        assert!(constraints.check_trace(&trace).is_err());
+    }
+
+    #[test]
+    fn memory_not_sorted_from_execution() {
+        let (constraints, mut trace) = memory_trace(&accesses());
+        // The execution reads a different value than the sorted columns
+        trace[(4, 1)] = 9.into();
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+        assert!(crate::prove(&constraints, &trace).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+//! Reusable constraint systems and tools to combine them.
+#[cfg(feature = "prover")]
+mod component;
+#[cfg(feature = "ecdsa")]
+pub mod ecdsa;
+pub mod fibonacci;
+pub mod keccak;
+pub mod lookup;
+pub mod memory;
+pub mod permutation;
+pub mod poseidon_merkle;
+pub mod range_check;
+pub mod rescue;
+pub mod uint;
+
+#[cfg(feature = "prover")]
+pub use component::Component;
@@ -0,0 +1,165 @@
+//! Permutation argument between two sets of trace rows.
+//!
+//! The rows of `left` are a permutation of the rows of `right` if the
+//! polynomials `∏ (γ - left_i)` and `∏ (γ - right_i)` are equal. The verifier
+//! checks this at a random point `γ` drawn after the trace is committed, with
+//! a running product of the quotients in the interaction trace. Tuples are
+//! compressed to a single value with powers of a second challenge `α`.
+//!
+//! For a permutation of a single column that is
+//!
+//! ```text
+//! z_0 = 1
+//! z_{i+1} ⋅ (γ - right_i) = z_i ⋅ (γ - left_i)
+//! ```
+//!
+//! where the last row wraps around to the first, so the product of all
+//! quotients is one.
+use crate::{RationalExpression, RunningProduct};
+use std::prelude::v1::*;
+
+/// Constraint that the tuples `left` on all rows are a permutation of the
+/// tuples `right`.
+///
+/// Add it to the constraints with [`Constraints::add_running_product`].
+/// Both sides need the same number of expressions, which can only use the
+/// trace columns.
+///
+/// [`Constraints::add_running_product`]: ../../struct.Constraints.html#method.add_running_product
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Permutation {
+    pub left:  Vec<RationalExpression>,
+    pub right: Vec<RationalExpression>,
+}
+
+impl Permutation {
+    /// The running product that shows the permutation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sides have a different number of expressions or none.
+    pub fn running_product(&self) -> RunningProduct {
+        use RationalExpression::*;
+        assert_eq!(self.left.len(), self.right.len());
+        assert!(!self.left.is_empty());
+        let compress = |tuple: &[RationalExpression]| {
+            let mut result = tuple[tuple.len() - 1].clone();
+            for element in tuple.iter().rev().skip(1) {
+                result = result * Challenge(1) + element.clone();
+            }
+            Challenge(0) - result
+        };
+        RunningProduct::new(compress(&self.left), compress(&self.right))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Constraints, TraceTable};
+    use zkp_primefield::FieldElement;
+
+    fn shuffle(right: &[(u64, u64)]) -> (Constraints, TraceTable) {
+        use RationalExpression::*;
+        let left = [
+            (3, 30),
+            (1, 10),
+            (4, 40),
+            (1, 11),
+            (5, 50),
+            (9, 90),
+            (2, 20),
+            (6, 60),
+        ];
+        let mut constraints = Constraints::from_expressions((8, 4), vec![], vec![]).unwrap();
+        let permutation = Permutation {
+            left:  vec![Trace(0, 0), Trace(1, 0)],
+            right: vec![Trace(2, 0), Trace(3, 0)],
+        };
+        let column = constraints
+            .add_running_product(permutation.running_product())
+            .unwrap();
+        assert_eq!(column, 4);
+        assert_eq!(constraints.num_challenges(), 2);
+        let trace = TraceTable::from_rows(8, 4, |row| {
+            vec![
+                left[row].0.into(),
+                left[row].1.into(),
+                right[row].0.into(),
+                right[row].1.into(),
+            ]
+        });
+        (constraints, trace)
+    }
+
+    #[test]
+    fn permutation_test() {
+        let (constraints, trace) = shuffle(&[
+            (1, 10),
+            (1, 11),
+            (2, 20),
+            (3, 30),
+            (4, 40),
+            (5, 50),
+            (6, 60),
+            (9, 90),
+        ]);
+        let proof = crate::prove(&constraints, &trace).unwrap();
+        assert_eq!(crate::verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn permutation_zero_knowledge() {
+        let (mut constraints, trace) = shuffle(&[
+            (1, 10),
+            (1, 11),
+            (2, 20),
+            (3, 30),
+            (4, 40),
+            (5, 50),
+            (6, 60),
+            (9, 90),
+        ]);
+        constraints.zero_knowledge = true;
+        let proof = crate::prove(&constraints, &trace).unwrap();
+        assert_eq!(crate::verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn permutation_values() {
+        let permutation = Permutation {
+            left:  vec![RationalExpression::Trace(0, 0)],
+            right: vec![RationalExpression::Trace(1, 0)],
+        };
+        let trace = TraceTable::from_rows(4, 2, |row| {
+            vec![FieldElement::from(row), FieldElement::from(3 - row)]
+        });
+        let gamma = FieldElement::from(10);
+        let values = permutation
+            .running_product()
+            .values(&trace, &[gamma.clone()]);
+        assert_eq!(values[0], FieldElement::ONE);
+        let expected = (&gamma - FieldElement::ZERO) / (&gamma - FieldElement::from(3));
+        assert_eq!(values[1], expected);
+        // The product over all rows is one
+        let last = (&gamma - FieldElement::from(3)) / (&gamma - FieldElement::ZERO);
+        assert_eq!(&values[3] * last, FieldElement::ONE);
+    }
+
+    #[test]
+    fn permutation_invalid() {
+        // The columns are permutations on their own, but not the pairs
+        let (constraints, trace) = shuffle(&[
+            (1, 10),
+            (1, 11),
+            (2, 20),
+            (3, 30),
+            (4, 40),
+            (5, 50),
+            (6, 90),
+            (9, 60),
+        ]);
+        assert!(crate::prove(&constraints, &trace).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Merkle path with Poseidon as the compression function.
+//!
+//! The claim is that `leaf` is in the Merkle tree with `root`, where nodes
+//! are `Poseidon::hash_pair(left, right)` of their children. The witness is
+//! the authentication path, see [`Witness::from_leaves`] to build it from the
+//! leaves of a tree.
+//!
+//! Every level of the path takes 128 rows of three columns. Row `0` of a
+//! level holds the permutation input `[left, right, 2]` and row `i + 1` the
+//! state after round `i`, so each round is the degree three relation
+//!
+//! ```text
+//! next = M · S(state + c_i)
+//! ```
+//!
+//! with `S` the full or partial S-box. The round constants and the choice of
+//! S-box are periodic columns. Row `91` holds the permutation output, whose
+//! first element must be one of the inputs of the next level. The remaining
+//! rows of the level are zero.
+//!
+//! Like the Pedersen Merkle example, the levels are padded to a power of two
+//! and the root is checked at the end of the last real level, so paths of
+//! similar length share the constraint layout.
+use crate::{ClaimBytes, Constraints, PeriodicColumn, RationalExpression, Verifiable};
+#[cfg(feature = "prover")]
+use crate::{Component, Provable, TraceTable};
+use std::prelude::v1::*;
+use zkp_hash::Poseidon;
+use zkp_primefield::FieldElement;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Claim {
+    pub path_length: usize,
+    pub root:        FieldElement,
+    pub leaf:        FieldElement,
+}
+
+/// Authentication path from the leaf to the root.
+///
+/// A direction is `true` when the sibling on that level is the left child.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Witness {
+    pub directions: Vec<bool>,
+    pub path:       Vec<FieldElement>,
+}
+
+// Rows per level of the path
+const LEVEL_ROWS: usize = 128;
+
+impl Witness {
+    /// Claim and witness for the leaf at `index` in the Poseidon Merkle tree
+    /// over `leaves`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of leaves is not a power of two greater than one
+    /// or if `index` is out of range.
+    pub fn from_leaves(leaves: &[FieldElement], index: usize) -> (Claim, Self) {
+        assert!(leaves.len() > 1 && leaves.len().is_power_of_two());
+        assert!(index < leaves.len());
+        let mut layer = leaves.to_vec();
+        let mut position = index;
+        let mut directions = Vec::new();
+        let mut path = Vec::new();
+        while layer.len() > 1 {
+            directions.push(position % 2 == 1);
+            path.push(layer[position ^ 1].clone());
+            layer = layer
+                .chunks(2)
+                .map(|pair| Poseidon::hash_pair(&pair[0], &pair[1]))
+                .collect();
+            position /= 2;
+        }
+        let claim = Claim {
+            path_length: path.len(),
+            root:        layer[0].clone(),
+            leaf:        leaves[index].clone(),
+        };
+        (claim, Self { directions, path })
+    }
+}
+
+// Multiply by the MDS matrix, see `Poseidon::mix`.
+fn mix(state: [RationalExpression; 3]) -> [RationalExpression; 3] {
+    let [a, b, c] = state;
+    let sum = a.clone() + b.clone() + c.clone();
+    [
+        sum.clone() + a * 2.into(),
+        sum.clone() - b * 2.into(),
+        sum - c * 3.into(),
+    ]
+}
+
+// Periodic column with `value(round)` on the round rows of each level.
+fn periodic<F: Fn(usize) -> FieldElement>(trace_length: usize, value: F) -> RationalExpression {
+    let values: Vec<FieldElement> = (0..LEVEL_ROWS)
+        .map(|row| {
+            if row < Poseidon::ROUNDS {
+                value(row)
+            } else {
+                FieldElement::ZERO
+            }
+        })
+        .collect();
+    PeriodicColumn::from_values(&values).expression(trace_length)
+}
+
+impl Claim {
+    fn levels(&self) -> usize {
+        self.path_length.next_power_of_two()
+    }
+
+    fn trace_length(&self) -> usize {
+        self.levels() * LEVEL_ROWS
+    }
+
+    /// The constraints on a trace table of `trace_length` rows.
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+
+        // Constraint repetitions
+        let levels = self.levels();
+        let trace_length = self.trace_length();
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator.clone());
+        let level_generator = Constant(trace_generator.pow(levels));
+        let on_row = |index| (X - g.pow(index)).inv();
+        let on_level_row = |index| (X.pow(levels) - level_generator.pow(index)).inv();
+        let on_round_rows = || {
+            (Poseidon::ROUNDS..LEVEL_ROWS)
+                .map(|row| X.pow(levels) - level_generator.pow(row))
+                .fold(Constant(FieldElement::ONE), |product, factor| {
+                    product * factor
+                })
+                / (X.pow(trace_length) - 1.into())
+        };
+        let output_row = Poseidon::ROUNDS;
+        let root_row = (self.path_length - 1) * LEVEL_ROWS + output_row;
+        let on_level_end_rows =
+            || on_level_row(output_row) * (X - g.pow(trace_length - LEVEL_ROWS + output_row));
+
+        // Round function
+        let constants = Poseidon::round_constants();
+        let full = periodic(trace_length, |round| {
+            if Poseidon::is_full_round(round) {
+                FieldElement::ONE
+            } else {
+                FieldElement::ZERO
+            }
+        });
+        let sbox_input = |column: usize| {
+            Trace(column, 0) + periodic(trace_length, |round| constants[round][column].clone())
+        };
+        let cube = |a: RationalExpression| a.clone() * a.clone() * a;
+        let partial_sbox = |column| {
+            let y = sbox_input(column);
+            full.clone() * cube(y.clone()) + (Constant(FieldElement::ONE) - full.clone()) * y
+        };
+        let next = mix([partial_sbox(0), partial_sbox(1), cube(sbox_input(2))]);
+
+        // Offset from a level's output row to the next level's input row
+        #[allow(clippy::cast_possible_wrap)]
+        let to_next = (LEVEL_ROWS - output_row) as isize;
+
+        let mut expressions: Vec<RationalExpression> = next
+            .iter()
+            .enumerate()
+            .map(|(column, next)| (Trace(column, 1) - next.clone()) * on_round_rows())
+            .collect();
+        expressions.extend(vec![
+            (Trace(2, 0) - 2.into()) * on_level_row(0),
+            (Trace(0, 0) - (&self.leaf).into()) * (Trace(1, 0) - (&self.leaf).into()) * on_row(0),
+            (Trace(0, to_next) - Trace(0, 0))
+                * (Trace(1, to_next) - Trace(0, 0))
+                * on_level_end_rows(),
+            (Trace(0, 0) - (&self.root).into()) * on_row(root_row),
+        ]);
+        expressions
+    }
+
+    /// The trace and constraints as a component for building larger
+    /// constraint systems.
+    #[cfg(feature = "prover")]
+    pub fn component(&self, witness: &Witness) -> Component {
+        Component::new(self.trace(witness), self.expressions())
+    }
+}
+
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.path_length.write_claim_bytes(bytes);
+        self.root.write_claim_bytes(bytes);
+        self.leaf.write_claim_bytes(bytes);
+    }
+}
+
+impl Verifiable for Claim {
+    fn constraints(&self) -> Constraints {
+        Constraints::from_expressions(
+            (self.trace_length(), Poseidon::WIDTH),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(feature = "prover")]
+impl Provable<&Witness> for Claim {
+    fn trace(&self, witness: &Witness) -> TraceTable {
+        let mut trace = TraceTable::new(self.trace_length(), Poseidon::WIDTH);
+        let mut node = self.leaf.clone();
+        for level in 0..self.levels() {
+            // Padding levels continue the path with zero siblings
+            let sibling = witness
+                .path
+                .get(level)
+                .cloned()
+                .unwrap_or(FieldElement::ZERO);
+            let mut state = if witness.directions.get(level) == Some(&true) {
+                [sibling, node, FieldElement::from(2)]
+            } else {
+                [node, sibling, FieldElement::from(2)]
+            };
+            let start = level * LEVEL_ROWS;
+            for (round, constants) in Poseidon::round_constants().iter().enumerate() {
+                for (column, element) in state.iter().enumerate() {
+                    trace[(start + round, column)] = element.clone();
+                }
+                for (element, constant) in state.iter_mut().zip(constants.iter()) {
+                    *element += constant;
+                }
+                for (column, element) in state.iter_mut().enumerate() {
+                    if column == Poseidon::WIDTH - 1 || Poseidon::is_full_round(round) {
+                        *element = element.square() * &*element;
+                    }
+                }
+                Poseidon::mix(&mut state);
+            }
+            for (column, element) in state.iter().enumerate() {
+                trace[(start + Poseidon::ROUNDS, column)] = element.clone();
+            }
+            node = state[0].clone();
+        }
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverError;
+
+    fn leaves() -> Vec<FieldElement> {
+        (0..8_usize).map(FieldElement::from).collect()
+    }
+
+    #[test]
+    fn from_leaves_test() {
+        let (claim, witness) = Witness::from_leaves(&leaves(), 6);
+        assert_eq!(witness.directions, vec![false, true, true]);
+        assert_eq!(witness.path[0], 7.into());
+        let left = Poseidon::hash_pair(&0.into(), &1.into());
+        let right = Poseidon::hash_pair(&2.into(), &3.into());
+        assert_eq!(witness.path[2], Poseidon::hash_pair(&left, &right));
+        assert_eq!(claim.leaf, 6.into());
+    }
+
+    #[test]
+    fn poseidon_merkle_test() {
+        let (claim, witness) = Witness::from_leaves(&leaves(), 3);
+        let trace = claim.trace(&witness);
+        assert_eq!(trace.num_rows(), 4 * LEVEL_ROWS);
+        assert_eq!(claim.constraints().check_trace(&trace), Ok(()));
+        let proof = claim.prove(&witness).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+
+        let wrong_root = Claim {
+            root: 1.into(),
+            ..claim
+        };
+        assert_eq!(
+            wrong_root.prove(&witness),
+            Err(ProverError::ConstraintUnsatisfied {
+                constraint: 6,
+                row:        2 * LEVEL_ROWS + Poseidon::ROUNDS,
+            })
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! Range check of a public value.
+//!
+//! The claim is that `value` is in the range `0..2^bits`. The trace has a
+//! single column where row `i` holds `value >> i`, so each row differs from
+//! twice the next row by a single bit, and row `bits` is zero. The remaining
+//! rows up to the next power of two are zero as well.
+//!
+//! When used as a component the value constraint can be replaced by one that
+//! links the first row to another component.
+//!
+//! To range check cells of an existing trace instead, use a [`RangeCheck`],
+//! which adds columns with the decomposition of the value on every row.
+use super::lookup::lookup;
+use crate::{ClaimBytes, Constraints, RationalExpression, Verifiable};
+#[cfg(feature = "prover")]
+use crate::{Component, Provable, TraceTable};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// How a [`RangeCheck`] decomposes the value.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Decomposition {
+    /// One column per bit, each constrained to be zero or one.
+    Bits,
+    /// One column per limb of the given number of bits, each constrained by a
+    /// [`lookup`] in the table of all limb values. The constraint degree is
+    /// the table size, so limbs should be a few bits at most.
+    Limbs(usize),
+}
+
+/// Constraint that an expression is in `0..2^bits` on every row.
+///
+/// The decomposition takes [`columns`](#method.columns) columns starting at
+/// `first_column`, which the prover fills with [`fill`](#method.fill). Rows
+/// that do not hold a value to check should hold a value in range, such as
+/// zero.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RangeCheck {
+    pub bits:          usize,
+    pub decomposition: Decomposition,
+    pub first_column:  usize,
+}
+
+impl RangeCheck {
+    // Bits per column
+    fn limb_bits(&self) -> usize {
+        match self.decomposition {
+            Decomposition::Bits => 1,
+            Decomposition::Limbs(bits) => bits,
+        }
+    }
+
+    /// The number of decomposition columns.
+    pub fn columns(&self) -> usize {
+        (self.bits + self.limb_bits() - 1) / self.limb_bits()
+    }
+
+    /// Constrain `value` on a trace of `trace_length` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is more than 251, as larger ranges include field
+    /// elements with several decompositions, if limbs have zero bits or if
+    /// `trace_length` is not a power of two.
+    pub fn expressions(
+        &self,
+        value: RationalExpression,
+        trace_length: usize,
+    ) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+        assert!(self.bits <= 251);
+        assert!(self.limb_bits() > 0);
+        assert!(trace_length.is_power_of_two());
+        let every_row = || Constant(FieldElement::ONE) / (X.pow(trace_length) - 1.into());
+
+        let limb_bits = self.limb_bits();
+        let mut sum = Constant(FieldElement::ZERO);
+        let mut expressions = Vec::with_capacity(self.columns() + 1);
+        for limb in 0..self.columns() {
+            let column = Trace(self.first_column + limb, 0);
+            let bits = std::cmp::min(limb_bits, self.bits - limb * limb_bits);
+            expressions.push(match self.decomposition {
+                Decomposition::Bits => column.clone() * (column.clone() - 1.into()) * every_row(),
+                Decomposition::Limbs(_) => {
+                    let table: Vec<FieldElement> =
+                        (0..1_usize << bits).map(FieldElement::from).collect();
+                    lookup(column.clone(), &table, trace_length)
+                }
+            });
+            let weight = FieldElement::from(U256::ONE << (limb * limb_bits));
+            sum = sum + column * Constant(weight);
+        }
+        expressions.push((value - sum) * every_row());
+        expressions
+    }
+
+    /// Write the decomposition of `value` to `row` of `trace`.
+    #[cfg(feature = "prover")]
+    pub fn fill(&self, trace: &mut TraceTable, row: usize, value: &U256) {
+        let limb_bits = self.limb_bits();
+        let mask = (U256::ONE << limb_bits) - U256::ONE;
+        for limb in 0..self.columns() {
+            let limb_value = (value.clone() >> (limb * limb_bits)) & &mask;
+            trace[(row, self.first_column + limb)] = FieldElement::from(limb_value);
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Claim {
+    pub bits:  usize,
+    pub value: FieldElement,
+}
+
+impl Claim {
+    fn trace_length(&self) -> usize {
+        (self.bits + 1).next_power_of_two()
+    }
+
+    /// The constraints on a trace table of `trace_length` rows.
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+
+        // Constraint repetitions
+        let trace_length = self.trace_length();
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator);
+        let on_row = |index| (X - g.pow(index)).inv();
+        let every_row = || (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1.into());
+
+        let bit = Trace(0, 0) - Trace(0, 1) * 2.into();
+        vec![
+            bit.clone() * (bit - 1.into()) * every_row(),
+            Trace(0, 0) * on_row(self.bits),
+            (Trace(0, 0) - (&self.value).into()) * on_row(0),
+        ]
+    }
+
+    /// The trace and constraints as a component for building larger
+    /// constraint systems.
+    #[cfg(feature = "prover")]
+    pub fn component(&self) -> Component {
+        Component::new(self.trace(()), self.expressions())
+    }
+}
+
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.bits.write_claim_bytes(bytes);
+        self.value.write_claim_bytes(bytes);
+    }
+}
+
+impl Verifiable for Claim {
+    fn constraints(&self) -> Constraints {
+        Constraints::from_expressions(
+            (self.trace_length(), 1),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(feature = "prover")]
+impl Provable<()> for Claim {
+    fn trace(&self, _witness: ()) -> TraceTable {
+        let value = U256::from(&self.value);
+        TraceTable::from_rows(self.trace_length(), 1, |i| {
+            vec![FieldElement::from(value.clone() >> i)]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverError;
+
+    #[test]
+    fn range_check_test() {
+        let claim = Claim {
+            bits:  8,
+            value: 255.into(),
+        };
+        let proof = claim.prove(()).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+
+        let claim = Claim {
+            bits:  8,
+            value: 256.into(),
+        };
+        assert_eq!(
+            claim.prove(()),
+            Err(ProverError::ConstraintUnsatisfied {
+                constraint: 1,
+                row:        8,
+            })
+        );
+    }
+
+    fn range_check_cells(decomposition: Decomposition) {
+        use RationalExpression::*;
+        let range_check = RangeCheck {
+            bits: 10,
+            decomposition,
+            first_column: 1,
+        };
+        let constraints = Constraints::from_expressions(
+            (8, 1 + range_check.columns()),
+            vec![],
+            range_check.expressions(Trace(0, 0), 8),
+        )
+        .unwrap();
+        let mut trace = TraceTable::new(8, 1 + range_check.columns());
+        for row in 0..8 {
+            let value = U256::from(row as u64 * 146);
+            trace[(row, 0)] = FieldElement::from(value.clone());
+            range_check.fill(&mut trace, row, &value);
+        }
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+        let proof = crate::prove(&constraints, &trace).unwrap();
+        assert_eq!(crate::verify(&constraints, &proof), Ok(()));
+
+        // 1024 does not fit in ten bits
+        let value = U256::from(1024);
+        trace[(3, 0)] = FieldElement::from(value.clone());
+        range_check.fill(&mut trace, 3, &value);
+        assert!(constraints.check_trace(&trace).is_err());
+    }
+
+    #[test]
+    fn range_check_bits() {
+        assert_eq!(
+            RangeCheck {
+                bits:          10,
+                decomposition: Decomposition::Bits,
+                first_column:  0,
+            }
+            .columns(),
+            10
+        );
+        range_check_cells(Decomposition::Bits);
+    }
+
+    #[test]
+    fn range_check_limbs() {
+        assert_eq!(
+            RangeCheck {
+                bits:          10,
+                decomposition: Decomposition::Limbs(4),
+                first_column:  0,
+            }
+            .columns(),
+            3
+        );
+        range_check_cells(Decomposition::Limbs(4));
+    }
+}
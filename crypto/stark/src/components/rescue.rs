@@ -0,0 +1,203 @@
+//! Knowledge of a preimage under the Rescue-Prime hash.
+//!
+//! The claim is that the prover knows `left` and `right` such that
+//! `Rescue::hash_pair(left, right)` is `hash`. The trace has one row per
+//! round of the permutation. Columns `0..3` hold the state at the start of
+//! the round and columns `3..6` the state after the inverse S-box, so both
+//! halves of the round are degree three relations:
+//!
+//! ```text
+//! middle^3   = M · state^3 + c_(2 i)
+//! next state = M · middle  + c_(2 i + 1)
+//! ```
+//!
+//! The row after the last round holds the output and the trace is padded
+//! with zeros to a power of two. This takes 32 rows per hash, compared to
+//! 256 rows for a Pedersen hash.
+use crate::{ClaimBytes, Constraints, PeriodicColumn, RationalExpression, Verifiable};
+#[cfg(feature = "prover")]
+use crate::{Component, Provable, TraceTable};
+use std::prelude::v1::*;
+use zkp_hash::Rescue;
+use zkp_primefield::FieldElement;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Claim {
+    pub hash: FieldElement,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Witness {
+    pub left:  FieldElement,
+    pub right: FieldElement,
+}
+
+const COLUMNS: usize = 2 * Rescue::WIDTH;
+
+fn trace_length() -> usize {
+    (Rescue::ROUNDS + 1).next_power_of_two()
+}
+
+// Multiply by the MDS matrix, see `Rescue::mix`.
+fn mix(state: [RationalExpression; 3]) -> [RationalExpression; 3] {
+    let [a, b, c] = state;
+    let sum = a.clone() + b.clone() + c.clone();
+    [
+        sum.clone() + a * 2.into(),
+        sum.clone() - b * 2.into(),
+        sum - c * 3.into(),
+    ]
+}
+
+// Periodic columns with round constants `2 i + offset` on the round rows `i`.
+fn round_constants(offset: usize) -> Vec<RationalExpression> {
+    let trace_length = trace_length();
+    let constants = Rescue::round_constants();
+    (0..Rescue::WIDTH)
+        .map(|column| {
+            let values: Vec<FieldElement> = (0..trace_length)
+                .map(|row| {
+                    if row < Rescue::ROUNDS {
+                        constants[2 * row + offset][column].clone()
+                    } else {
+                        FieldElement::ZERO
+                    }
+                })
+                .collect();
+            PeriodicColumn::from_values(&values).expression(trace_length)
+        })
+        .collect()
+}
+
+impl Claim {
+    /// The constraints on a trace table of `trace_length` rows.
+    pub fn expressions(&self) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+
+        // Constraint repetitions
+        let trace_length = trace_length();
+        let trace_generator = FieldElement::root(trace_length).unwrap();
+        let g = Constant(trace_generator);
+        let on_row = |index| (X - g.pow(index)).inv();
+        let on_round_rows = || {
+            (Rescue::ROUNDS..trace_length)
+                .map(|row| X - g.pow(row))
+                .fold(Constant(FieldElement::ONE), |product, factor| {
+                    product * factor
+                })
+                / (X.pow(trace_length) - 1.into())
+        };
+
+        let cube = |column| Trace(column, 0) * Trace(column, 0) * Trace(column, 0);
+        let forward = mix([cube(0), cube(1), cube(2)]);
+        let backward = mix([Trace(3, 0), Trace(4, 0), Trace(5, 0)]);
+        let first_constants = round_constants(0);
+        let second_constants = round_constants(1);
+
+        let mut expressions = Vec::with_capacity(COLUMNS + 2);
+        for (column, (forward, constant)) in forward.iter().zip(first_constants).enumerate() {
+            expressions.push(
+                (cube(Rescue::WIDTH + column) - forward.clone() - constant) * on_round_rows(),
+            );
+        }
+        for (column, (backward, constant)) in backward.iter().zip(second_constants).enumerate() {
+            expressions.push((Trace(column, 1) - backward.clone() - constant) * on_round_rows());
+        }
+        expressions.push((Trace(2, 0) - 2.into()) * on_row(0));
+        expressions.push((Trace(0, 0) - (&self.hash).into()) * on_row(Rescue::ROUNDS));
+        expressions
+    }
+
+    /// The trace and constraints as a component for building larger
+    /// constraint systems.
+    #[cfg(feature = "prover")]
+    pub fn component(&self, witness: &Witness) -> Component {
+        Component::new(self.trace(witness), self.expressions())
+    }
+}
+
+impl ClaimBytes for Claim {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.hash.write_claim_bytes(bytes);
+    }
+}
+
+impl Verifiable for Claim {
+    fn constraints(&self) -> Constraints {
+        Constraints::from_expressions(
+            (trace_length(), COLUMNS),
+            self.claim_bytes(),
+            self.expressions(),
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(feature = "prover")]
+impl Provable<&Witness> for Claim {
+    fn trace(&self, witness: &Witness) -> TraceTable {
+        let mut trace = TraceTable::new(trace_length(), COLUMNS);
+        let mut state = [
+            witness.left.clone(),
+            witness.right.clone(),
+            FieldElement::from(2),
+        ];
+        for (round, constants) in Rescue::round_constants().chunks(2).enumerate() {
+            for (column, element) in state.iter().enumerate() {
+                trace[(round, column)] = element.clone();
+            }
+            for element in state.iter_mut() {
+                *element = element.square() * &*element;
+            }
+            Rescue::mix(&mut state);
+            for (element, constant) in state.iter_mut().zip(constants[0].iter()) {
+                *element += constant;
+                *element = Rescue::inverse_sbox(element);
+            }
+            for (column, element) in state.iter().enumerate() {
+                trace[(round, Rescue::WIDTH + column)] = element.clone();
+            }
+            Rescue::mix(&mut state);
+            for (element, constant) in state.iter_mut().zip(constants[1].iter()) {
+                *element += constant;
+            }
+        }
+        for (column, element) in state.iter().enumerate() {
+            trace[(Rescue::ROUNDS, column)] = element.clone();
+        }
+        trace
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProverError;
+
+    #[test]
+    fn rescue_test() {
+        let witness = Witness {
+            left:  3.into(),
+            right: 5.into(),
+        };
+        let claim = Claim {
+            hash: Rescue::hash_pair(&witness.left, &witness.right),
+        };
+        let trace = claim.trace(&witness);
+        assert_eq!(trace.num_rows(), 32);
+        assert_eq!(claim.constraints().check_trace(&trace), Ok(()));
+        let proof = claim.prove(&witness).unwrap();
+        assert_eq!(claim.verify(&proof), Ok(()));
+
+        let claim = Claim { hash: 7.into() };
+        assert_eq!(
+            claim.prove(&witness),
+            Err(ProverError::ConstraintUnsatisfied {
+                constraint: 7,
+                row:        Rescue::ROUNDS,
+            })
+        );
+    }
+}
@@ -0,0 +1,317 @@
+//! Gadgets for unsigned 32 and 64 bit integer arithmetic.
+//!
+//! Binary operations are expensive to express in a 252 bit prime field, so
+//! integers are kept as a [`Word`] of bit columns. The bitwise operations
+//! are then low degree relations between the bits, rotations are free and
+//! the value of a word is the weighted sum of its bits. Addition and
+//! comparison take a single extra column besides the result word.
+//!
+//! All constraints hold on every row of the trace. Rows that are not used
+//! should hold zeros or other valid values, which [`Word::fill`] and the
+//! other `fill` functions produce.
+use crate::RationalExpression;
+#[cfg(feature = "prover")]
+use crate::TraceTable;
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+use zkp_u256::U256;
+
+/// An unsigned integer stored as one bit per column, least significant
+/// first.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Word {
+    pub bits:         usize,
+    pub first_column: usize,
+}
+
+fn every_row(trace_length: usize) -> RationalExpression {
+    use RationalExpression::*;
+    assert!(trace_length.is_power_of_two());
+    Constant(FieldElement::ONE) / (X.pow(trace_length) - 1.into())
+}
+
+fn power_of_two(exponent: usize) -> RationalExpression {
+    RationalExpression::Constant(FieldElement::from(U256::ONE << exponent))
+}
+
+impl Word {
+    /// # Panics
+    ///
+    /// Panics if `bits` is more than 64.
+    pub fn new(bits: usize, first_column: usize) -> Self {
+        assert!(bits <= 64);
+        Self { bits, first_column }
+    }
+
+    pub fn u32(first_column: usize) -> Self {
+        Self::new(32, first_column)
+    }
+
+    pub fn u64(first_column: usize) -> Self {
+        Self::new(64, first_column)
+    }
+
+    /// The number of columns, one per bit.
+    pub fn columns(&self) -> usize {
+        self.bits
+    }
+
+    pub fn bit(&self, index: usize) -> RationalExpression {
+        assert!(index < self.bits);
+        RationalExpression::Trace(self.first_column + index, 0)
+    }
+
+    pub fn value(&self) -> RationalExpression {
+        self.rotate_left(0)
+    }
+
+    /// The value of the word rotated left by `amount` bits.
+    ///
+    /// Rotations only reorder the bits, so this takes no constraints.
+    pub fn rotate_left(&self, amount: usize) -> RationalExpression {
+        (0..self.bits)
+            .map(|index| self.bit(index) * power_of_two((index + amount) % self.bits))
+            .sum()
+    }
+
+    /// Constraints that the columns hold bits.
+    pub fn expressions(&self, trace_length: usize) -> Vec<RationalExpression> {
+        (0..self.bits)
+            .map(|index| self.bit(index) * (self.bit(index) - 1.into()) * every_row(trace_length))
+            .collect()
+    }
+
+    /// Write the bits of `value` to `row` of `trace`.
+    #[cfg(feature = "prover")]
+    pub fn fill(&self, trace: &mut TraceTable, row: usize, value: u64) {
+        for index in 0..self.bits {
+            trace[(row, self.first_column + index)] = FieldElement::from((value >> index) & 1);
+        }
+    }
+}
+
+/// Constraints that `out` is the bitwise exclusive or of `left` and `right`.
+///
+/// All three words must have the same size.
+pub fn xor(left: &Word, right: &Word, out: &Word, trace_length: usize) -> Vec<RationalExpression> {
+    assert!(left.bits == right.bits && left.bits == out.bits);
+    (0..out.bits)
+        .map(|index| {
+            let (a, b) = (left.bit(index), right.bit(index));
+            (out.bit(index) - a.clone() - b.clone() + a * b * 2.into()) * every_row(trace_length)
+        })
+        .collect()
+}
+
+/// Constraints that `out` is the bitwise and of `left` and `right`.
+///
+/// All three words must have the same size.
+pub fn and(left: &Word, right: &Word, out: &Word, trace_length: usize) -> Vec<RationalExpression> {
+    assert!(left.bits == right.bits && left.bits == out.bits);
+    (0..out.bits)
+        .map(|index| {
+            (out.bit(index) - left.bit(index) * right.bit(index)) * every_row(trace_length)
+        })
+        .collect()
+}
+
+/// Constraints that `out` is `left + right` modulo `2^bits` with the
+/// overflow in the `carry` column.
+///
+/// The inputs must be less than `2^bits`, for example the values of words
+/// or their rotations.
+pub fn add(
+    left: RationalExpression,
+    right: RationalExpression,
+    out: &Word,
+    carry: usize,
+    trace_length: usize,
+) -> Vec<RationalExpression> {
+    use RationalExpression::*;
+    vec![
+        Trace(carry, 0) * (Trace(carry, 0) - 1.into()) * every_row(trace_length),
+        (left + right - out.value() - Trace(carry, 0) * power_of_two(out.bits))
+            * every_row(trace_length),
+    ]
+}
+
+/// Write `left + right` and the carry as constrained by [`add`].
+#[cfg(feature = "prover")]
+pub fn fill_add(
+    trace: &mut TraceTable,
+    row: usize,
+    out: &Word,
+    carry: usize,
+    left: u64,
+    right: u64,
+) {
+    let sum = u128::from(left) + u128::from(right);
+    let mask = (1_u128 << out.bits) - 1;
+    #[allow(clippy::cast_possible_truncation)]
+    out.fill(trace, row, (sum & mask) as u64);
+    trace[(row, carry)] = FieldElement::from(sum >> out.bits);
+}
+
+/// Constraints that the `result` column is one if `left < right` and zero
+/// otherwise.
+///
+/// The `difference` word holds `right - left - 1` modulo `2^bits`. The inputs
+/// must be less than `2^bits`.
+pub fn less_than(
+    left: RationalExpression,
+    right: RationalExpression,
+    difference: &Word,
+    result: usize,
+    trace_length: usize,
+) -> Vec<RationalExpression> {
+    use RationalExpression::*;
+    vec![
+        Trace(result, 0) * (Trace(result, 0) - 1.into()) * every_row(trace_length),
+        (right - left - 1.into()
+            + (Constant(FieldElement::ONE) - Trace(result, 0)) * power_of_two(difference.bits)
+            - difference.value())
+            * every_row(trace_length),
+    ]
+}
+
+/// Write the difference and result as constrained by [`less_than`].
+#[cfg(feature = "prover")]
+pub fn fill_less_than(
+    trace: &mut TraceTable,
+    row: usize,
+    difference: &Word,
+    result: usize,
+    left: u64,
+    right: u64,
+) {
+    let mask = (1_u128 << difference.bits) - 1;
+    let value = (u128::from(right) + (1 << difference.bits) - u128::from(left) - 1) & mask;
+    #[allow(clippy::cast_possible_truncation)]
+    difference.fill(trace, row, value as u64);
+    trace[(row, result)] = FieldElement::from(u64::from(left < right));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prove, verify, Constraints};
+
+    // Columns for two inputs and the results of all gadgets
+    struct Layout {
+        left:       Word,
+        right:      Word,
+        xor:        Word,
+        and:        Word,
+        sum:        Word,
+        carry:      usize,
+        difference: Word,
+        less:       usize,
+    }
+
+    impl Layout {
+        fn new(bits: usize) -> Self {
+            Self {
+                left:       Word::new(bits, 0),
+                right:      Word::new(bits, bits),
+                xor:        Word::new(bits, 2 * bits),
+                and:        Word::new(bits, 3 * bits),
+                sum:        Word::new(bits, 4 * bits),
+                carry:      5 * bits,
+                difference: Word::new(bits, 5 * bits + 1),
+                less:       6 * bits + 1,
+            }
+        }
+
+        fn columns(&self) -> usize {
+            self.less + 1
+        }
+
+        fn constraints(&self, trace_length: usize) -> Constraints {
+            let mut expressions = self.left.expressions(trace_length);
+            expressions.extend(self.right.expressions(trace_length));
+            expressions.extend(xor(&self.left, &self.right, &self.xor, trace_length));
+            expressions.extend(and(&self.left, &self.right, &self.and, trace_length));
+            expressions.extend(self.sum.expressions(trace_length));
+            expressions.extend(add(
+                self.left.value(),
+                self.right.rotate_left(8),
+                &self.sum,
+                self.carry,
+                trace_length,
+            ));
+            expressions.extend(self.difference.expressions(trace_length));
+            expressions.extend(less_than(
+                self.left.value(),
+                self.right.value(),
+                &self.difference,
+                self.less,
+                trace_length,
+            ));
+            Constraints::from_expressions((trace_length, self.columns()), vec![], expressions)
+                .unwrap()
+        }
+
+        fn trace(&self, values: &[(u64, u64)]) -> TraceTable {
+            let mut trace = TraceTable::new(values.len(), self.columns());
+            for (row, &(left, right)) in values.iter().enumerate() {
+                #[allow(clippy::cast_possible_truncation)]
+                let rotated = if self.left.bits == 32 {
+                    u64::from((right as u32).rotate_left(8))
+                } else {
+                    right.rotate_left(8)
+                };
+                self.left.fill(&mut trace, row, left);
+                self.right.fill(&mut trace, row, right);
+                self.xor.fill(&mut trace, row, left ^ right);
+                self.and.fill(&mut trace, row, left & right);
+                fill_add(&mut trace, row, &self.sum, self.carry, left, rotated);
+                fill_less_than(&mut trace, row, &self.difference, self.less, left, right);
+            }
+            trace
+        }
+    }
+
+    #[test]
+    fn u32_test() {
+        let layout = Layout::new(32);
+        let values = [
+            (0, 0),
+            (1, 2),
+            (0xffff_ffff, 0xffff_ffff),
+            (0x8000_0000, 0x7fff_ffff),
+            (0x1234_5678, 0x9abc_def0),
+            (5, 5),
+            (0, 0xffff_ffff),
+            (0xdead_beef, 0xcafe_babe),
+        ];
+        let constraints = layout.constraints(values.len());
+        let mut trace = layout.trace(&values);
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+
+        // Claim 5 < 5
+        trace[(5, layout.less)] = FieldElement::ONE;
+        assert!(constraints.check_trace(&trace).is_err());
+    }
+
+    #[test]
+    fn u64_test() {
+        let layout = Layout::new(64);
+        let values = [
+            (0, u64::max_value()),
+            (u64::max_value(), 1),
+            (1 << 63, 1 << 63),
+            (0x0123_4567_89ab_cdef, 0xfedc_ba98_7654_3210),
+        ];
+        let constraints = layout.constraints(values.len());
+        let mut trace = layout.trace(&values);
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+
+        // Drop the carry of `u64::MAX + (1 << 8)`
+        assert_eq!(trace[(1, layout.carry)], FieldElement::ONE);
+        trace[(1, layout.carry)] = FieldElement::ZERO;
+        assert!(constraints.check_trace(&trace).is_err());
+    }
+}
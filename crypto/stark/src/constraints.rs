@@ -1,4 +1,10 @@
-use crate::rational_expression::RationalExpression;
+#[cfg(feature = "prover")]
+use crate::TraceTable;
+use crate::{
+    channel::ChannelLayout, external_commitment::ExternalCommitment, fri::FriParams,
+    public_column::PublicColumn, rational_expression::RationalExpression,
+    running_product::RunningProduct, ClaimBytes,
+};
 use itertools::Itertools;
 use std::{fmt, prelude::v1::*};
 use zkp_primefield::FieldElement;
@@ -6,6 +12,9 @@ use zkp_primefield::FieldElement;
 #[derive(Clone, Debug)]
 pub enum Error {
     InvalidTraceLength,
+    InvalidPublicColumn,
+    InvalidRunningProduct,
+    InvalidExternalCommitment,
 }
 
 impl fmt::Display for Error {
@@ -13,10 +22,43 @@ impl fmt::Display for Error {
         use Error::*;
         match *self {
             InvalidTraceLength => write!(f, "Invalid trace length (must be power of two)"),
+            InvalidPublicColumn => {
+                write!(
+                    f,
+                    "Public column is outside the trace or has more values than rows"
+                )
+            }
+            InvalidRunningProduct => {
+                write!(f, "Running product uses a column that is not in the trace")
+            }
+            InvalidExternalCommitment => {
+                write!(
+                    f,
+                    "External commitment has no columns or all trace columns, or there is one \
+                     already"
+                )
+            }
         }
     }
 }
 
+/// A constraint that does not hold on a row of the trace table.
+///
+/// See [`Constraints::check_trace`].
+#[cfg(feature = "prover")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintViolation {
+    /// Index of the constraint in [`Constraints::expressions`].
+    pub constraint: usize,
+
+    /// Row of the trace table on which the constraint fails.
+    pub row: usize,
+
+    /// Value of the constraint numerator on the row. This is non-zero while
+    /// the denominator is zero.
+    pub value: FieldElement,
+}
+
 /// Constraints for Stark proofs
 ///
 /// Contains the constraint expressions that apply to the trace table in
@@ -36,10 +78,19 @@ pub struct Constraints {
 
     expressions: Vec<RationalExpression>,
 
+    pub(crate) running_products: Vec<RunningProduct>,
+
+    pub(crate) external_commitment: Option<ExternalCommitment>,
+
     /// The blowup factor
     ///
     /// The size of the low-degree-extension domain compared to the trace
     /// domain. Should be a power of two. Recommended values are 16, 32 or 64.
+    ///
+    /// This is the blowup of the committed trace and constraint values and
+    /// of FRI. The constraints themselves are evaluated on the smallest
+    /// extension that fits their [`degree`](#method.degree), which may be
+    /// smaller or larger than this.
     pub blowup: usize,
 
     /// Proof of work difficulty
@@ -60,31 +111,31 @@ pub struct Constraints {
     /// After `fri_layout.sum()` reductions are done, the remaining polynomial
     /// is written explicitly in coefficient form.
     pub fri_layout: Vec<usize>,
-}
 
-impl Constraints {
-    fn default_fri_layout(trace_nrows: usize) -> Vec<usize> {
-        // The binary logarithm of the final layer polynomial degree.
-        const LOG2_TARGET: usize = 8;
-
-        // Number of reductions to reach target degree
-        // TODO: For very small traces we fold to a constant, but this is not
-        // necessarily optimal.
-        let log2_trace = trace_nrows.trailing_zeros() as usize;
-        let num_reductions = if log2_trace > LOG2_TARGET {
-            log2_trace - LOG2_TARGET
-        } else {
-            log2_trace
-        };
+    /// Minimum security level in bits
+    ///
+    /// Verification fails if the conjectured security of the parameters
+    /// above, as computed by `Proof::security_level`, is less than this.
+    /// Defaults to 80 bits.
+    pub min_security_bits: usize,
 
-        // Do as many three reductions as possible
-        let mut fri_layout = vec![3; num_reductions / 3];
-        if num_reductions % 3 != 0 {
-            fri_layout.push(num_reductions % 3);
-        }
-        fri_layout
-    }
+    /// Byte layout of the Fiat-Shamir channel
+    ///
+    /// Defaults to `ChannelLayout::STARKWARE`, which follows StarkWare's
+    /// verifier.
+    pub channel_layout: ChannelLayout,
+
+    /// Make proofs zero-knowledge
+    ///
+    /// The committed trace is blinded with a random row after every row, the
+    /// constraint commitment includes a random polynomial that masks the FRI
+    /// polynomial, and the trace and constraint commitments salt their
+    /// leaves. Proofs are larger and take longer, as the blinded trace has
+    /// twice the rows. Defaults to `false`.
+    pub zero_knowledge: bool,
+}
 
+impl Constraints {
     pub fn from_expressions(
         (trace_nrows, trace_ncolumns): (usize, usize),
         channel_seed: Vec<u8>,
@@ -101,10 +152,15 @@ impl Constraints {
             trace_nrows,
             trace_ncolumns,
             expressions,
+            running_products: Vec::new(),
+            external_commitment: None,
             blowup: 16,
             pow_bits: if cfg!(test) { 12 } else { 20 },
             num_queries: 30,
-            fri_layout: Self::default_fri_layout(trace_nrows),
+            fri_layout: FriParams::default_layout(trace_nrows),
+            min_security_bits: 80,
+            channel_layout: ChannelLayout::STARKWARE,
+            zero_knowledge: false,
         })
     }
 
@@ -112,6 +168,69 @@ impl Constraints {
         &self.channel_seed
     }
 
+    /// Constrain a trace column to the values of `column` and append the
+    /// column to the channel seed.
+    pub fn add_public_column(&mut self, column: &PublicColumn) -> Result<(), Error> {
+        if column.column() >= self.trace_ncolumns || column.values().len() > self.trace_nrows {
+            return Err(Error::InvalidPublicColumn);
+        }
+        self.expressions.push(column.expression(self.trace_nrows));
+        column.write_claim_bytes(&mut self.channel_seed);
+        Ok(())
+    }
+
+    /// Commit to the leading trace columns with `commitment` instead of in
+    /// the proof, and append it to the channel seed.
+    ///
+    /// At least one column has to remain in the proof's own trace
+    /// commitment, and there can be only one external commitment.
+    pub fn add_external_commitment(&mut self, commitment: ExternalCommitment) -> Result<(), Error> {
+        if commitment.columns() == 0
+            || commitment.columns() >= self.trace_ncolumns
+            || self.external_commitment.is_some()
+        {
+            return Err(Error::InvalidExternalCommitment);
+        }
+        commitment.write_claim_bytes(&mut self.channel_seed);
+        self.external_commitment = Some(commitment);
+        Ok(())
+    }
+
+    pub fn external_commitment(&self) -> Option<&ExternalCommitment> {
+        self.external_commitment.as_ref()
+    }
+
+    /// The number of externally committed trace columns.
+    pub(crate) fn external_ncolumns(&self) -> usize {
+        self.external_commitment
+            .as_ref()
+            .map_or(0, ExternalCommitment::columns)
+    }
+
+    /// Add a column with the running product `product` to the interaction
+    /// trace and return its index.
+    ///
+    /// Interaction columns follow the trace columns, so the `k`-th running
+    /// product is column `trace_ncolumns + k`. Its constraints are added to
+    /// the expressions. The numerator and denominator can only use the trace
+    /// columns, as the prover computes the interaction trace from them.
+    pub fn add_running_product(&mut self, product: RunningProduct) -> Result<usize, Error> {
+        let in_trace = |expression: &RationalExpression| {
+            expression
+                .trace_arguments()
+                .iter()
+                .all(|&(column, _)| column < self.trace_ncolumns)
+        };
+        if !in_trace(product.numerator()) || !in_trace(product.denominator()) {
+            return Err(Error::InvalidRunningProduct);
+        }
+        let column = self.trace_ncolumns + self.running_products.len();
+        self.expressions
+            .extend(product.expressions(column, self.trace_nrows));
+        self.running_products.push(product);
+        Ok(column)
+    }
+
     pub fn trace_nrows(&self) -> usize {
         self.trace_nrows
     }
@@ -120,6 +239,28 @@ impl Constraints {
         self.trace_ncolumns
     }
 
+    /// The number of columns of the interaction trace, which is committed
+    /// after the challenges are drawn.
+    pub fn interaction_ncolumns(&self) -> usize {
+        self.running_products.len()
+    }
+
+    pub fn running_products(&self) -> &[RunningProduct] {
+        &self.running_products
+    }
+
+    /// The number of challenges drawn after the trace is committed.
+    ///
+    /// This is one more than the largest challenge index in the
+    /// expressions.
+    pub fn num_challenges(&self) -> usize {
+        self.expressions
+            .iter()
+            .filter_map(|expression| expression.challenges().last().copied())
+            .max()
+            .map_or(0, |index| index + 1)
+    }
+
     pub fn len(&self) -> usize {
         self.expressions.len()
     }
@@ -132,17 +273,153 @@ impl Constraints {
         &self.expressions
     }
 
+    /// Degree of the constraints in the trace polynomials.
+    ///
+    /// The combined constraint polynomial is split in this many parts of
+    /// degree less than `trace_nrows`. Periodic columns and powers of `X` can
+    /// raise the degree of a constraint beyond its degree in the trace, in
+    /// which case the degree is increased so the constraint still fits.
     pub fn degree(&self) -> usize {
         self.expressions
             .iter()
             .map(|c| {
                 let (numerator_degree, denominator_degree) = c.trace_degree();
-                numerator_degree - denominator_degree
+                let (x_numerator_degree, x_denominator_degree) = c.degree(self.trace_nrows() - 1);
+                std::cmp::max(
+                    numerator_degree - denominator_degree,
+                    x_numerator_degree.saturating_sub(x_denominator_degree) / self.trace_nrows()
+                        + 1,
+                )
             })
             .max()
             .expect("no constraints")
     }
 
+    /// The number of committed constraint polynomials.
+    ///
+    /// These are the [`degree`](#method.degree) parts of the combined
+    /// constraint polynomial, followed by the random mask in zero-knowledge
+    /// proofs.
+    pub(crate) fn constraint_ncolumns(&self) -> usize {
+        self.degree() + usize::from(self.zero_knowledge)
+    }
+
+    /// The constraints on the blinded trace of a zero-knowledge proof.
+    ///
+    /// The blinded trace has twice the rows, with the rows of the trace on
+    /// the even rows and random values on the odd rows. Row offsets double
+    /// and `X` is unchanged, so the constraints hold on the even rows. The
+    /// prover does not build this trace: a trace polynomial `P` of degree
+    /// less than `n` is blinded as `P + (X^n - 1) R` with `R` random of
+    /// degree less than `n`, which agrees with `P` on the trace domain. Up to
+    /// `n` values of it outside the trace domain are uniformly random, which
+    /// covers the out of domain values and the queries.
+    ///
+    /// The prover and verifier work on these constraints, on both of which
+    /// [`zero_knowledge`](#structfield.zero_knowledge) stays set.
+    pub(crate) fn blinded(&self) -> Self {
+        use RationalExpression::*;
+        let blind = |expression: &RationalExpression| {
+            expression.substitute(&X, &|column, offset| Trace(column, 2 * offset))
+        };
+        Self {
+            trace_nrows: 2 * self.trace_nrows,
+            expressions: self.expressions.iter().map(blind).collect(),
+            running_products: self
+                .running_products
+                .iter()
+                .map(|product| {
+                    RunningProduct::new(blind(product.numerator()), blind(product.denominator()))
+                })
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Human readable listing of the constraints.
+    ///
+    /// Each constraint is simplified and shown with its degree in the trace
+    /// polynomials, followed by its numerator and denominator. Degrees in `X`
+    /// are upper bounds for a trace of `trace_nrows` rows.
+    pub fn describe(&self) -> String {
+        use fmt::Write;
+        use RationalExpression::*;
+        let mut result = String::new();
+        for (index, expression) in self.expressions.iter().enumerate() {
+            let expression = expression.simplify();
+            let (numerator, denominator) = match &expression {
+                Mul(numerator, denominator) => {
+                    match &**denominator {
+                        Inv(denominator) => ((**numerator).clone(), Some(&**denominator)),
+                        _ => (expression.clone(), None),
+                    }
+                }
+                Inv(denominator) => (Constant(FieldElement::ONE), Some(&**denominator)),
+                _ => (expression.clone(), None),
+            };
+            let (trace_numerator, trace_denominator) = expression.trace_degree();
+            let (x_numerator, x_denominator) = expression.degree(self.trace_nrows - 1);
+            // Writing to a string does not fail
+            let _ = writeln!(
+                result,
+                "Constraint {}: degree {} (X degree {} / {})",
+                index,
+                trace_numerator.saturating_sub(trace_denominator),
+                x_numerator,
+                x_denominator
+            );
+            let _ = writeln!(result, "    numerator:   {}", numerator);
+            if let Some(denominator) = denominator {
+                let _ = writeln!(result, "    denominator: {}", denominator);
+            }
+        }
+        result
+    }
+
+    /// The row offsets at which the trace polynomials are sampled out of
+    /// domain, in sorted order.
+    ///
+    /// These are all the row offsets used by the constraints. Offsets zero
+    /// and one are always included, so constraint systems on two consecutive
+    /// rows all have the same proof layout.
+    pub fn trace_offsets(&self) -> Vec<isize> {
+        let mut offsets = vec![0, 1];
+        for expression in &self.expressions {
+            offsets.extend(expression.trace_arguments().iter().map(|&(_, j)| j));
+        }
+        offsets.sort();
+        offsets.dedup();
+        offsets
+    }
+
+    /// The points `point * g^offset` for each of the `trace_offsets`, where
+    /// `g` is the trace generator.
+    pub(crate) fn trace_offset_points(&self, point: &FieldElement) -> Vec<FieldElement> {
+        let generator = FieldElement::root(self.trace_nrows).expect("No generator for trace.");
+        let generator_inv = generator.inv().expect("Generator is never zero.");
+        self.trace_offsets()
+            .iter()
+            .map(|&offset| {
+                // Sizes are small enough
+                #[allow(clippy::cast_sign_loss)]
+                let exponent = offset.abs() as usize;
+                if offset < 0 {
+                    point * generator_inv.pow(exponent)
+                } else {
+                    point * generator.pow(exponent)
+                }
+            })
+            .collect()
+    }
+
+    /// The parameters of the FRI low degree test on the OODS polynomial.
+    pub fn fri_params(&self) -> FriParams {
+        FriParams {
+            layout: self.fri_layout.clone(),
+            blowup: self.blowup,
+        }
+    }
+
     // TODO: Better explanation with literature references.
     pub fn security_bits(&self) -> usize {
         // Our conservative formula is (1/2^blowup_log)^(queries/2)*(1/2^pow_bits)
@@ -160,8 +437,19 @@ impl Constraints {
         let trace_len_log = self.trace_nrows().trailing_zeros() as usize;
         // First we decommit two proofs for each query [one which is the evaluation
         // domain decommitment and one is the constraints]
-        let mut total_decommitment =
-            self.num_queries * (trace_len_log * self.trace_ncolumns() + trace_len_log);
+        let columns = self.trace_ncolumns() + self.interaction_ncolumns();
+        let mut total_decommitment = self.num_queries * (trace_len_log * columns + trace_len_log);
+        if self.interaction_ncolumns() > 0 {
+            total_decommitment += self.num_queries * trace_len_log;
+        }
+        if self.external_commitment.is_some() {
+            total_decommitment += self.num_queries * trace_len_log;
+        }
+        // The mask polynomial and a salt for each of the trace, interaction
+        // and constraint leaves
+        if self.zero_knowledge {
+            total_decommitment += self.num_queries * 4;
+        }
         // Now we account for the first layer which is 8 elements [assuming the worst
         // case we need to decommit 7 other elements].
         let mut current_size = trace_len_log - 3;
@@ -181,7 +469,74 @@ impl Constraints {
         32 * total_decommitment
     }
 
-    pub(crate) fn combine(&self, constraint_coefficients: &[FieldElement]) -> RationalExpression {
+    /// Check that the trace table satisfies the constraints.
+    ///
+    /// Each constraint is evaluated as a fraction on the trace domain, i.e.
+    /// the un-extended domain where the trace table values are known
+    /// exactly. A row violates a constraint if the denominator vanishes but
+    /// the numerator does not. All violations are reported, ordered by row
+    /// and then by constraint.
+    ///
+    /// This is a debugging aid while developing constraint systems. It is
+    /// much slower than a proof's constraint evaluation, and since it works
+    /// on unreduced fractions, constraints that are sums of several
+    /// fractions with shared zeros may not be fully checked.
+    ///
+    /// Constraints that use challenges or the interaction trace are not
+    /// checked, as they depend on the channel.
+    #[cfg(feature = "prover")]
+    pub fn check_trace(&self, trace: &TraceTable) -> Result<(), Vec<ConstraintViolation>> {
+        let checked: Vec<bool> = self
+            .expressions
+            .iter()
+            .map(|expression| {
+                expression.challenges().is_empty()
+                    && expression
+                        .trace_arguments()
+                        .iter()
+                        .all(|&(column, _)| column < self.trace_ncolumns)
+            })
+            .collect();
+        let trace_length = trace.num_rows();
+        let generator = trace.generator();
+        let mut violations = Vec::new();
+        let mut x = FieldElement::ONE;
+        for row in 0..trace_length {
+            let trace_value = |column: usize, offset: isize| {
+                // Sizes are small enough
+                #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+                let index = (row as isize + offset).rem_euclid(trace_length as isize) as usize;
+                trace[(index, column)].clone()
+            };
+            for (constraint, expression) in self.expressions.iter().enumerate() {
+                if !checked[constraint] {
+                    continue;
+                }
+                let (numerator, denominator) = expression.evaluate_fraction(&x, &trace_value);
+                if denominator.is_zero() && !numerator.is_zero() {
+                    violations.push(ConstraintViolation {
+                        constraint,
+                        row,
+                        value: numerator,
+                    });
+                }
+            }
+            x *= &generator;
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// The sum of the constraints with random coefficients, with the
+    /// challenges replaced by their values.
+    pub(crate) fn combine(
+        &self,
+        constraint_coefficients: &[FieldElement],
+        challenges: &[FieldElement],
+    ) -> RationalExpression {
         use RationalExpression::*;
         assert_eq!(2 * self.len(), constraint_coefficients.len());
         let target_degree = self.degree() * self.trace_nrows() - 1;
@@ -195,7 +550,7 @@ impl Constraints {
                     let adjustment_degree = target_degree + den - num;
                     let adjustment = Constant(coefficient_low.clone())
                         + Constant(coefficient_high.clone()) * X.pow(adjustment_degree);
-                    adjustment * constraint.clone()
+                    adjustment * constraint.with_challenges(challenges)
                 },
             )
             .sum()
@@ -204,13 +559,13 @@ impl Constraints {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
     use crate::{
         prove,
-        traits::tests::{Claim, Witness},
+        traits::tests::{fib_claim, fib_witness, Claim, Witness},
         Provable, Verifiable,
     };
     use zkp_macros_decl::field_element;
-    use zkp_primefield::FieldElement;
     use zkp_u256::U256;
 
     #[test]
@@ -234,4 +589,39 @@ mod tests {
         let actual = prove(&constraints, &public.trace(&private)).unwrap();
         assert!(actual.as_bytes().len() <= constraints.max_proof_size());
     }
+
+    #[test]
+    fn describe_test() {
+        use RationalExpression::*;
+        let constraints = Constraints::from_expressions((8, 2), Vec::new(), vec![
+            (Trace(0, 1) - Trace(1, 0)) / (X - 1.into()),
+        ])
+        .unwrap();
+        assert_eq!(
+            constraints.describe(),
+            "Constraint 0: degree 1 (X degree 7 / 1)\n    numerator:   Trace(0, 1) - Trace(1, \
+             0)\n    denominator: X - 1\n"
+        );
+    }
+
+    #[test]
+    fn check_trace_test() {
+        let private = fib_witness();
+        let public = fib_claim();
+        let constraints = public.constraints();
+        let mut trace = public.trace(&private);
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+
+        // Break the claimed value
+        trace[(1000, 0)] = FieldElement::ZERO;
+        let violations = constraints.check_trace(&trace).unwrap_err();
+        assert_eq!(violations.len(), 3);
+        assert_eq!((violations[0].constraint, violations[0].row), (0, 999));
+        assert_eq!((violations[1].constraint, violations[1].row), (1, 1000));
+        assert_eq!(violations[2], ConstraintViolation {
+            constraint: 3,
+            row:        1000,
+            value:      -&public.value,
+        });
+    }
 }
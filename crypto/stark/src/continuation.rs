@@ -0,0 +1,225 @@
+//! Proofs of long computations split into segments.
+//!
+//! A computation that is too long for a single trace can be cut into
+//! consecutive segments that are proven separately. Each segment claim
+//! exposes the machine state in its first and last row, enforced with
+//! [`boundary_expressions`], and the chain is sound if every proof verifies
+//! and the final state of each segment is the initial state of the next.
+//! The boundary row is thus repeated: it is the last row of one segment and
+//! the first row of the next.
+//!
+//! States are compared by their [`state_digest`], which is also what
+//! [`verify_chain`] returns for the ends of the chain, so a verifier only
+//! needs to know the digests of the overall input and output state.
+use crate::{ClaimBytes, Proof, RationalExpression, Verifiable, VerifierError};
+#[cfg(feature = "std")]
+use std::error;
+use std::{fmt, prelude::v1::*};
+use zkp_hash::{Hash, MaskedKeccak};
+use zkp_primefield::FieldElement;
+
+/// A claim on one segment of a longer computation.
+///
+/// The states must be part of the claim bytes, so the proof of a segment is
+/// bound to its states.
+pub trait Continuation: Verifiable {
+    /// The state in the first row of the segment.
+    fn initial_state(&self) -> Vec<FieldElement>;
+
+    /// The state in the last row of the segment.
+    fn final_state(&self) -> Vec<FieldElement>;
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// There are no segments.
+    Empty,
+    /// The proof of a segment failed to verify.
+    Segment { index: usize, error: VerifierError },
+    /// The initial state of a segment is not the final state of the previous
+    /// one.
+    Link { index: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Error::*;
+        match *self {
+            Empty => write!(f, "The chain has no segments"),
+            Segment { index, error } => write!(f, "Segment {} failed to verify: {}", index, error),
+            Link { index } => {
+                write!(
+                    f,
+                    "Segment {} does not start in the final state of the previous segment",
+                    index
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {}
+
+/// Keccak hash of the claim bytes of a state.
+pub fn state_digest(state: &[FieldElement]) -> Hash {
+    let mut hasher = MaskedKeccak::new();
+    hasher.update(&state.claim_bytes());
+    hasher.hash()
+}
+
+/// Constraints that the trace holds `state` in `columns` on `row`.
+///
+/// # Panics
+///
+/// Panics if `columns` and `state` differ in length or if `trace_length` is
+/// not a power of two.
+pub fn boundary_expressions(
+    columns: &[usize],
+    state: &[FieldElement],
+    row: usize,
+    trace_length: usize,
+) -> Vec<RationalExpression> {
+    use RationalExpression::*;
+    assert_eq!(columns.len(), state.len());
+    let trace_generator = FieldElement::root(trace_length).unwrap();
+    let on_row = (X - Constant(trace_generator.pow(row))).inv();
+    columns
+        .iter()
+        .zip(state)
+        .map(|(column, value)| (Trace(*column, 0) - value.into()) * on_row.clone())
+        .collect()
+}
+
+/// Verify a chain of segment proofs and return the digests of its initial
+/// and final state.
+pub fn verify_chain<C: Continuation>(segments: &[(C, Proof)]) -> Result<(Hash, Hash), Error> {
+    let mut previous: Option<Hash> = None;
+    for (index, (claim, proof)) in segments.iter().enumerate() {
+        claim
+            .verify(proof)
+            .map_err(|error| Error::Segment { index, error })?;
+        if let Some(digest) = previous {
+            if digest != state_digest(&claim.initial_state()) {
+                return Err(Error::Link { index });
+            }
+        }
+        previous = Some(state_digest(&claim.final_state()));
+    }
+    let (first, _) = segments.first().ok_or(Error::Empty)?;
+    Ok((state_digest(&first.initial_state()), previous.unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Constraints, Provable, TraceTable};
+
+    // A segment of the Fibonacci sequence in two columns
+    #[derive(Clone, PartialEq, Debug)]
+    struct Segment {
+        start: [FieldElement; 2],
+        end:   [FieldElement; 2],
+    }
+
+    const LENGTH: usize = 16;
+
+    fn step(state: &[FieldElement; 2]) -> [FieldElement; 2] {
+        [state[1].clone(), &state[0] + &state[1]]
+    }
+
+    impl Segment {
+        fn new(start: [FieldElement; 2]) -> Self {
+            let mut end = start.clone();
+            for _ in 1..LENGTH {
+                end = step(&end);
+            }
+            Self { start, end }
+        }
+    }
+
+    impl ClaimBytes for Segment {
+        fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+            self.start[..].write_claim_bytes(bytes);
+            self.end[..].write_claim_bytes(bytes);
+        }
+    }
+
+    impl Verifiable for Segment {
+        fn constraints(&self) -> Constraints {
+            use RationalExpression::*;
+            let g = Constant(FieldElement::root(LENGTH).unwrap());
+            let on_transition_rows = (X - g.pow(LENGTH - 1)) / (X.pow(LENGTH) - 1.into());
+            let mut expressions = vec![
+                (Trace(0, 1) - Trace(1, 0)) * on_transition_rows.clone(),
+                (Trace(1, 1) - Trace(0, 0) - Trace(1, 0)) * on_transition_rows,
+            ];
+            expressions.extend(boundary_expressions(&[0, 1], &self.start, 0, LENGTH));
+            expressions.extend(boundary_expressions(&[0, 1], &self.end, LENGTH - 1, LENGTH));
+            Constraints::from_expressions((LENGTH, 2), self.claim_bytes(), expressions).unwrap()
+        }
+    }
+
+    impl Provable<()> for Segment {
+        fn trace(&self, _witness: ()) -> TraceTable {
+            let mut trace = TraceTable::new(LENGTH, 2);
+            let mut state = self.start.clone();
+            for row in 0..LENGTH {
+                trace[(row, 0)] = state[0].clone();
+                trace[(row, 1)] = state[1].clone();
+                state = step(&state);
+            }
+            trace
+        }
+    }
+
+    impl Continuation for Segment {
+        fn initial_state(&self) -> Vec<FieldElement> {
+            self.start.to_vec()
+        }
+
+        fn final_state(&self) -> Vec<FieldElement> {
+            self.end.to_vec()
+        }
+    }
+
+    fn chain(start: [FieldElement; 2], length: usize) -> Vec<(Segment, Proof)> {
+        let mut state = start;
+        (0..length)
+            .map(|_| {
+                let segment = Segment::new(state.clone());
+                state = segment.end.clone();
+                let proof = segment.prove(()).unwrap();
+                (segment, proof)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chain_test() {
+        let start = [FieldElement::ONE, FieldElement::ONE];
+        let segments = chain(start.clone(), 3);
+        let end = segments[2].0.end.clone();
+        assert_eq!(
+            verify_chain(&segments),
+            Ok((state_digest(&start), state_digest(&end)))
+        );
+        assert_eq!(verify_chain::<Segment>(&[]), Err(Error::Empty));
+    }
+
+    #[test]
+    fn broken_chain() {
+        let mut segments = chain([FieldElement::ONE, FieldElement::ONE], 3);
+
+        // A valid segment that does not continue the previous one
+        segments[2] = chain([FieldElement::ZERO, FieldElement::ONE], 1).remove(0);
+        assert_eq!(verify_chain(&segments), Err(Error::Link { index: 2 }));
+
+        // A proof of a different segment
+        segments[2].1 = segments[1].1.clone();
+        match verify_chain(&segments) {
+            Err(Error::Segment { index: 2, .. }) => {}
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+}
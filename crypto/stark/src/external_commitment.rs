@@ -0,0 +1,195 @@
+//! Trace columns committed outside of the proof.
+//!
+//! A proof commits to its trace with the Merkle root of the low degree
+//! extension of the trace columns. The leading columns of the trace can
+//! instead be committed to separately, with the root given in the claim.
+//! The prover opens them at the queries against that root, and the verifier
+//! checks the openings and uses the values like those of the other trace
+//! columns. No further constraints are needed: the committed columns are
+//! part of the out of domain check and the low degree test, which ties the
+//! committed values to the constraints of the proof.
+//!
+//! This chains proofs. A proof with its output in the leading columns of its
+//! trace has an [`ExternalCommitment`] to them, and the next proof takes that
+//! commitment in its claim for its input in the same columns. Both traces
+//! need the same length, blowup and hash for the commitments to be equal.
+//!
+//! [`ExternalCommitment`]: struct.ExternalCommitment.html
+use crate::ClaimBytes;
+#[cfg(feature = "prover")]
+use crate::{
+    prover::{lde_commitment, Error as ProverError},
+    TraceTable,
+};
+use std::prelude::v1::*;
+use zkp_hash::Hash;
+#[cfg(feature = "prover")]
+use zkp_hash::Hasher;
+
+/// A commitment to the first `columns` trace columns.
+///
+/// The root is that of the Merkle tree a proof would build over the low
+/// degree extension of just these columns. Add it with
+/// [`Constraints::add_external_commitment`], which also seeds the channel
+/// with it.
+///
+/// Zero-knowledge proofs can not open external commitments, as they commit
+/// to the blinded trace.
+///
+/// [`Constraints::add_external_commitment`]: struct.Constraints.html#method.add_external_commitment
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ExternalCommitment {
+    columns: usize,
+    root:    Hash,
+}
+
+impl ExternalCommitment {
+    pub fn new(columns: usize, root: Hash) -> Self {
+        Self { columns, root }
+    }
+
+    /// Commit to the first `columns` columns of `trace` with hash `H`, as a
+    /// proof with `blowup` would.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the trace has fewer than `columns` columns.
+    #[cfg(feature = "prover")]
+    pub fn commit<H: Hasher>(
+        trace: &TraceTable,
+        columns: usize,
+        blowup: usize,
+    ) -> Result<Self, ProverError> {
+        assert!(columns <= trace.num_columns());
+        let polynomials = trace.interpolate();
+        let commitment = lde_commitment::<H>(&polynomials[..columns], blowup)?;
+        Ok(Self::new(columns, commitment.hash().clone()))
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+}
+
+impl ClaimBytes for ExternalCommitment {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.columns.write_claim_bytes(bytes);
+        bytes.extend_from_slice(self.root.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        traits::tests::{fib_claim, fib_witness},
+        verify, Constraints, ProofStats, Provable, RationalExpression, Verifiable,
+    };
+    use zkp_hash::MaskedKeccak;
+    use zkp_primefield::FieldElement;
+
+    const LENGTH: usize = 16;
+
+    // Doubles the input column into the other column
+    fn double(input: &[FieldElement], input_column: usize) -> (Constraints, TraceTable) {
+        use RationalExpression::*;
+        let output_column = 1 - input_column;
+        let every_row = (X.pow(LENGTH) - 1.into()).inv();
+        let constraints = Constraints::from_expressions((LENGTH, 2), vec![], vec![
+            (Trace(output_column, 0) - Trace(input_column, 0) * 2.into()) * every_row,
+        ])
+        .unwrap();
+        let trace = TraceTable::from_rows(LENGTH, 2, |row| {
+            let mut values = vec![FieldElement::ZERO; 2];
+            values[input_column] = input[row].clone();
+            values[output_column] = &input[row] + &input[row];
+            values
+        });
+        (constraints, trace)
+    }
+
+    fn input() -> Vec<FieldElement> {
+        (0..LENGTH).map(|i| FieldElement::from(i * i + 1)).collect()
+    }
+
+    #[test]
+    fn external_commitment_test() {
+        let (mut constraints, trace) = double(&input(), 0);
+        let commitment =
+            ExternalCommitment::commit::<MaskedKeccak>(&trace, 1, constraints.blowup).unwrap();
+        constraints.add_external_commitment(commitment).unwrap();
+        let proof = crate::prove(&constraints, &trace).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+        let stats = ProofStats::from_proof(&proof, &constraints).unwrap();
+        assert_eq!(stats.size, proof.as_bytes().len());
+        assert!(stats.external_decommitment.total() > 0);
+
+        // The proof is bound to the committed values
+        let mut changed = input();
+        changed[3] += FieldElement::ONE;
+        let (mut other, changed) = double(&changed, 0);
+        let commitment =
+            ExternalCommitment::commit::<MaskedKeccak>(&changed, 1, other.blowup).unwrap();
+        other.add_external_commitment(commitment).unwrap();
+        assert!(verify(&other, &proof).is_err());
+        assert_eq!(
+            crate::prove(&other, &trace),
+            Err(ProverError::ExternalCommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn external_commitment_chain() {
+        // The output of the first proof is the input of the second, both in
+        // the first column
+        let (mut first, first_trace) = double(&input(), 1);
+        let output: Vec<FieldElement> = (0..LENGTH)
+            .map(|row| first_trace[(row, 0)].clone())
+            .collect();
+        let commitment =
+            ExternalCommitment::commit::<MaskedKeccak>(&first_trace, 1, first.blowup).unwrap();
+        first.add_external_commitment(commitment.clone()).unwrap();
+        let (mut second, second_trace) = double(&output, 0);
+        second.add_external_commitment(commitment).unwrap();
+
+        let first_proof = crate::prove(&first, &first_trace).unwrap();
+        let second_proof = crate::prove(&second, &second_trace).unwrap();
+        assert_eq!(verify(&first, &first_proof), Ok(()));
+        assert_eq!(verify(&second, &second_proof), Ok(()));
+
+        // The second proof can not use another input
+        let (_, other_trace) = double(&input(), 0);
+        assert_eq!(
+            crate::prove(&second, &other_trace),
+            Err(ProverError::ExternalCommitmentMismatch)
+        );
+    }
+
+    #[test]
+    fn external_commitment_invalid() {
+        let claim = fib_claim();
+        let trace = claim.trace(&fib_witness());
+        let mut constraints = claim.constraints();
+        let commitment = ExternalCommitment::commit::<MaskedKeccak>(&trace, 2, 16).unwrap();
+        assert!(constraints.add_external_commitment(commitment).is_err());
+        let commitment = ExternalCommitment::commit::<MaskedKeccak>(&trace, 1, 16).unwrap();
+        constraints
+            .add_external_commitment(commitment.clone())
+            .unwrap();
+        assert!(constraints.add_external_commitment(commitment).is_err());
+
+        // The commitment depends on the blowup
+        constraints.blowup = 32;
+        assert_eq!(
+            crate::prove(&constraints, &trace),
+            Err(ProverError::ExternalCommitmentMismatch)
+        );
+        let proof = claim.prove(&fib_witness()).unwrap();
+        assert!(verify(&constraints, &proof).is_err());
+    }
+}
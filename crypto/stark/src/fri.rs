@@ -0,0 +1,650 @@
+// False positives on the Latex math.
+#![allow(clippy::doc_markdown)]
+//! FRI low degree test.
+//!
+//! Proves that a vector of evaluations is close to the evaluations of a
+//! polynomial of degree less than $n / \text{blowup}$ on a subgroup of size
+//! $n$ (or a coset of one). The evaluations are expected in bit-reversed
+//! order, as produced by [`DensePolynomial::low_degree_extension`].
+//!
+//! The test consists of two phases that are interleaved with other protocol
+//! steps. In the commit phase [`FriProver::commit`] writes layer commitments
+//! and the final layer to the channel, which [`FriVerifier::read_commitments`]
+//! reads. After the verifier's query indices are drawn, [`FriProver::decommit`]
+//! writes the decommitments which [`FriVerifier::verify`] checks against the
+//! queried values.
+//!
+//! [`DensePolynomial::low_degree_extension`]: crate::DensePolynomial::low_degree_extension
+use crate::{
+    channel::{Label, RandomGenerator, Replayable, Transcript, VerifierChannel},
+    polynomial::DensePolynomial,
+    verifier::Error,
+};
+#[cfg(feature = "prover")]
+use crate::{
+    channel::{ProverChannel, Writable},
+    metrics::Phases,
+    prover::{notify, Error as ProverError},
+    prover_callback::{ProverCallback, ProverEvent},
+};
+#[cfg(feature = "prover")]
+use itertools::Itertools;
+use std::{collections::BTreeMap, prelude::v1::*};
+#[cfg(feature = "prover")]
+use zkp_hash::Hashable;
+use zkp_hash::{Hash, Hasher};
+use zkp_merkle_tree::{Commitment, Proof as MerkleProof};
+#[cfg(feature = "prover")]
+use zkp_merkle_tree::{Tree, VectorCommitment};
+#[cfg(feature = "prover")]
+use zkp_mmap_vec::{MmapVec, Storage};
+#[cfg(feature = "prover")]
+use zkp_primefield::fft::{ifft_permuted, permute};
+use zkp_primefield::{fft::permute_index, FieldElement};
+#[cfg(feature = "prover")]
+use zkp_u256::U256;
+
+/// Parameters of the FRI low degree test.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FriParams {
+    /// Number of times the domain is halved between committed layers. Each
+    /// value must be between one and four.
+    pub layout: Vec<usize>,
+    /// The ratio of the domain size to the degree bound.
+    pub blowup: usize,
+}
+
+impl FriParams {
+    /// A layout folding a polynomial of degree less than `degree_bound` to
+    /// a small final layer, mostly in steps of three.
+    pub fn default_layout(degree_bound: usize) -> Vec<usize> {
+        // The binary logarithm of the final layer polynomial degree.
+        const LOG2_TARGET: usize = 8;
+
+        // Number of reductions to reach target degree
+        // TODO: For very small traces we fold to a constant, but this is not
+        // necessarily optimal.
+        let log2_degree = degree_bound.trailing_zeros() as usize;
+        let num_reductions = if log2_degree > LOG2_TARGET {
+            log2_degree - LOG2_TARGET
+        } else {
+            log2_degree
+        };
+
+        // Do as many three reductions as possible
+        let mut layout = vec![3; num_reductions / 3];
+        if num_reductions % 3 != 0 {
+            layout.push(num_reductions % 3);
+        }
+        layout
+    }
+
+    /// Whether the parameters can fold a domain of `domain_size`.
+    ///
+    /// The layout must be non-empty, every value between one and four and
+    /// together they can fold at most the whole domain.
+    pub(crate) fn is_valid(&self, domain_size: usize) -> bool {
+        domain_size.is_power_of_two()
+            && self.blowup.is_power_of_two()
+            && !self.layout.is_empty()
+            && self.layout.iter().all(|&n| (1..=4).contains(&n))
+            && self.layout.iter().sum::<usize>() <= domain_size.trailing_zeros() as usize
+    }
+}
+
+#[cfg(feature = "prover")]
+#[derive(Clone, Debug)]
+struct FriLeaves {
+    coset_size: usize,
+    layer:      MmapVec<FieldElement>,
+}
+
+#[cfg(feature = "prover")]
+type FriTree<H> = Tree<FriLeaves, H>;
+
+// Merkle tree for FRI layers with coset size
+#[cfg(feature = "prover")]
+impl VectorCommitment for FriLeaves {
+    type Leaf = Vec<U256>;
+
+    fn len(&self) -> usize {
+        debug_assert_eq!(self.layer.len() % self.coset_size, 0);
+        self.layer.len() / self.coset_size
+    }
+
+    fn leaf(&self, index: usize) -> Self::Leaf {
+        let mut internal_leaf = Vec::with_capacity(self.coset_size);
+        for j in 0..self.coset_size {
+            internal_leaf.push(
+                self.layer[(index * self.coset_size + j)]
+                    .as_montgomery()
+                    .clone(),
+            );
+        }
+        internal_leaf
+    }
+
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        if self.coset_size == 1 {
+            // For a single element, return its hash.
+            self.layer[index].hash()
+        } else {
+            // Combine the element hashes.
+            let hashes: Vec<Hash> = (0..self.coset_size)
+                .map(|j| self.layer[(index * self.coset_size + j)].hash())
+                .collect();
+            H::hash_many(&hashes)
+        }
+    }
+}
+
+/// Prover side of the FRI low degree test, holding the committed layers.
+#[cfg(feature = "prover")]
+#[derive(Debug)]
+pub struct FriProver<H: Hasher> {
+    trees: Vec<FriTree<H>>,
+}
+
+#[cfg(feature = "prover")]
+impl<H: Hasher> FriProver<H> {
+    /// Commit to the layers of FRI and write the final layer coefficients.
+    ///
+    /// Returns `Error::InvalidFriParams` if `params` can not fold the
+    /// evaluations, see [`FriParams::layout`].
+    ///
+    /// [`FriParams::layout`]: struct.FriParams.html#structfield.layout
+    pub fn commit<T: Transcript>(
+        evaluations: MmapVec<FieldElement>,
+        params: &FriParams,
+        channel: &mut ProverChannel<T>,
+    ) -> Result<Self, ProverError> {
+        Self::commit_with_callback(
+            evaluations,
+            params,
+            Storage::default(),
+            channel,
+            &(),
+            &mut Phases::prove(),
+        )
+    }
+
+    pub(crate) fn commit_with_callback<T: Transcript, C: ProverCallback + ?Sized>(
+        first_layer: MmapVec<FieldElement>,
+        params: &FriParams,
+        storage: Storage,
+        proof: &mut ProverChannel<T>,
+        callback: &C,
+        phases: &mut Phases,
+    ) -> Result<Self, ProverError> {
+        if !params.is_valid(first_layer.len()) {
+            return Err(ProverError::InvalidFriParams);
+        }
+        let fri_layout = &params.layout;
+        let mut fri_trees: Vec<FriTree<H>> = Vec::with_capacity(fri_layout.len());
+
+        // Compute 1/x for the fri layer. We only compute the even coordinates.
+        // OPT: Can these be efficiently computed on the fly?
+        let x_inv = {
+            let n = first_layer.len();
+            let root_inv = FieldElement::root(n)
+                .ok_or(ProverError::RootUnavailable)?
+                .inv()
+                .unwrap();
+            let mut x_inv = MmapVec::with_capacity_in(n / 2, storage);
+            let mut accumulator = FieldElement::ONE;
+            for _ in 0..n / 2 {
+                x_inv.push(accumulator.clone());
+                accumulator *= &root_inv;
+            }
+            permute(&mut x_inv);
+            x_inv
+        };
+
+        let mut next_layer = first_layer;
+        for (i, &n_reductions) in fri_layout.iter().enumerate() {
+            notify(callback, phases, ProverEvent::FriLayer {
+                layer:  i,
+                layers: fri_layout.len(),
+            })?;
+
+            // Allocate next and swap ownership
+            let mut layer =
+                MmapVec::with_capacity_in(next_layer.len() / (1 << n_reductions), storage);
+            std::mem::swap(&mut layer, &mut next_layer);
+
+            // Create tree from layer
+            // FRI layout values are small.
+            #[allow(clippy::cast_possible_truncation)]
+            let coset_size = 2_usize.pow(n_reductions as u32);
+            let tree = FriTree::from_leaves_in(FriLeaves { coset_size, layer }, 1, storage)?;
+            fri_trees.push(tree);
+            let tree = fri_trees.last().unwrap();
+            let layer = &tree.leaves().layer;
+
+            // Write commitment and pull coefficient
+            proof.label(Label::FriLayer(i));
+            proof.write(tree.commitment());
+            let coefficient = proof.get_random();
+
+            // Fold layer up to three times
+            // TODO: Capture the pattern in a macro and DRY.
+            // OPT: Parallelization
+            // OPT: The structure in x_inv should allow faster methods,
+            // like in a radix-4 and radix-8 fft.
+            let layer = layer.iter();
+            match n_reductions {
+                1 => {
+                    next_layer.extend(
+                        layer
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1)),
+                    )
+                }
+                2 => {
+                    let coefficient_2 = coefficient.pow(2);
+                    next_layer.extend(
+                        layer
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| {
+                                (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1)
+                            }),
+                    )
+                }
+                3 => {
+                    let coefficient_2 = coefficient.square();
+                    let coefficient_4 = coefficient_2.square();
+                    next_layer.extend(
+                        layer
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| {
+                                (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1)
+                            })
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| {
+                                (&p0 + &p1) + &coefficient_4 * x_inv * (p0 - p1)
+                            }),
+                    )
+                }
+                // TODO: Is there a use case for 4 layer folds?
+                4 => {
+                    let coefficient_2 = coefficient.square();
+                    let coefficient_4 = coefficient_2.square();
+                    let coefficient_8 = coefficient_4.square();
+                    next_layer.extend(
+                        layer
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| {
+                                (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1)
+                            })
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| {
+                                (&p0 + &p1) + &coefficient_4 * x_inv * (p0 - p1)
+                            })
+                            .tuples()
+                            .zip(x_inv.iter())
+                            .map(|((p0, p1), x_inv)| {
+                                (&p0 + &p1) + &coefficient_8 * x_inv * (p0 - p1)
+                            }),
+                    )
+                }
+                // Checked by `FriParams::is_valid`
+                _ => unreachable!(),
+            };
+        }
+
+        // Write the final layer coefficients
+        let n_coefficients = next_layer.len() / params.blowup;
+        let points = &mut next_layer[0..n_coefficients];
+        permute(points);
+        ifft_permuted(points);
+        permute(points);
+        proof.label(Label::FriLastLayer);
+        proof.write(&*points);
+
+        Ok(Self { trees: fri_trees })
+    }
+
+    /// The commitments to the layers, in the order they were written.
+    pub fn commitments(&self) -> Vec<Commitment> {
+        self.trees
+            .iter()
+            .map(|tree| tree.commitment().clone())
+            .collect()
+    }
+
+    /// Write the decommitments for the sorted first layer `queries`.
+    pub fn decommit<T: Transcript>(
+        &self,
+        queries: &[usize],
+        proof: &mut ProverChannel<T>,
+    ) -> Result<(), ProverError> {
+        let mut previous_indices: Vec<usize> = queries.to_vec();
+
+        for tree in &self.trees {
+            let coset_size = tree.leaves().coset_size;
+
+            let new_indices: Vec<usize> = previous_indices
+                .iter()
+                .map(|x| x / coset_size)
+                .dedup()
+                .collect();
+
+            for i in &new_indices {
+                // TODO: Write entire tree.leaf(i)
+                for j in 0..coset_size {
+                    let n = i * coset_size + j;
+                    match previous_indices.binary_search(&n) {
+                        Ok(_) => (),
+                        _ => proof.write(&tree.leaves().layer[n]),
+                    };
+                }
+            }
+            proof.write(&tree.open(&new_indices)?);
+            previous_indices = new_indices;
+        }
+        Ok(())
+    }
+}
+
+/// Verifier side of the FRI low degree test, holding the commit phase.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FriVerifier {
+    params:      FriParams,
+    domain_size: usize,
+    commitments: Vec<Commitment>,
+    eval_points: Vec<FieldElement>,
+    last_layer:  Vec<FieldElement>,
+}
+
+impl FriVerifier {
+    /// Read the layer commitments and final layer for evaluations on a domain
+    /// of `domain_size`.
+    pub fn read_commitments<T: Transcript>(
+        params: &FriParams,
+        domain_size: usize,
+        channel: &mut VerifierChannel<T>,
+    ) -> Result<Self, Error> {
+        if !params.is_valid(domain_size) {
+            return Err(Error::UnsupportedParameters);
+        }
+        let mut commitments: Vec<Commitment> = Vec::with_capacity(params.layout.len() + 1);
+        let mut eval_points: Vec<FieldElement> = Vec::with_capacity(params.layout.len() + 1);
+        let mut fri_size = domain_size >> params.layout[0];
+        // Get first fri root:
+        channel.label(Label::FriLayer(0));
+        commitments.push(Commitment::from_size_hash(
+            fri_size,
+            &Replayable::<Hash>::replay(channel),
+        )?);
+        // Get fri roots and eval points from the channel random
+        for (layer, &x) in params.layout.iter().enumerate().skip(1) {
+            fri_size >>= x;
+            // TODO: When is x equal to zero?
+            let eval_point = if x == 0 {
+                FieldElement::ONE
+            } else {
+                channel.get_random()
+            };
+            eval_points.push(eval_point);
+            channel.label(Label::FriLayer(layer));
+            commitments.push(Commitment::from_size_hash(
+                fri_size,
+                &Replayable::<Hash>::replay(channel),
+            )?);
+        }
+        // Gets the last layer and the polynomial coefficients
+        eval_points.push(channel.get_random());
+        channel.label(Label::FriLastLayer);
+        let last_layer = Replayable::<FieldElement>::replay_many(channel, fri_size / params.blowup);
+        Ok(Self {
+            params: params.clone(),
+            domain_size,
+            commitments,
+            eval_points,
+            last_layer,
+        })
+    }
+
+    /// Read the decommitments and check them against the values of the first
+    /// layer at the sorted query indices.
+    pub fn verify<H: Hasher, T: Transcript>(
+        &self,
+        queries: &[(usize, FieldElement)],
+        channel: &mut VerifierChannel<T>,
+    ) -> Result<(), Error> {
+        let root = FieldElement::root(self.domain_size).ok_or(Error::RootUnavailable)?;
+        let coset_sizes = self
+            .params
+            .layout
+            .iter()
+            .map(|k| 1_usize << k)
+            .collect::<Vec<_>>();
+        let mut previous_indices: Vec<usize> = queries.iter().map(|(index, _)| *index).collect();
+        let mut fri_indices: Vec<usize> = previous_indices
+            .iter()
+            .map(|x| x / coset_sizes[0])
+            .collect();
+
+        // Folded fri values from the previous layer
+        let mut fri_folds: BTreeMap<usize, FieldElement> = BTreeMap::new();
+
+        let mut step = 1;
+        let mut len = self.domain_size;
+        for (k, commitment) in self.commitments.iter().enumerate() {
+            let mut fri_layer_values = Vec::new();
+
+            fri_indices.dedup();
+            for i in &fri_indices {
+                let mut coset: Vec<FieldElement> = Vec::new();
+                for j in 0..coset_sizes[k] {
+                    let n = i * coset_sizes[k] + j;
+                    if let Ok(z) = previous_indices.binary_search(&n) {
+                        if k > 0 {
+                            coset.push(match fri_folds.get(&n) {
+                                Some(x) => x.clone(),
+                                None => return Err(Error::HashMapFailure),
+                            });
+                        } else {
+                            coset.push(queries[z].1.clone());
+                        }
+                    } else {
+                        coset.push(Replayable::<FieldElement>::replay(channel));
+                    }
+                }
+                fri_layer_values.push((*i, coset));
+            }
+            // Fold and record foldings
+            let mut layer_folds = BTreeMap::new();
+            for (i, coset) in &fri_layer_values {
+                let _old_value = layer_folds.insert(
+                    *i,
+                    fri_fold(
+                        coset.as_slice(),
+                        &self.eval_points[k],
+                        step,
+                        (coset_sizes[k] / 2) * i,
+                        len,
+                        &root,
+                    ),
+                );
+            }
+
+            let merkle_proof_length = commitment.proof_size(&fri_indices)?;
+            let merkle_hashes = Replayable::<Hash>::replay_many(channel, merkle_proof_length);
+            let merkle_proof = MerkleProof::from_hashes(commitment, &fri_indices, &merkle_hashes)?;
+            fri_folds = layer_folds;
+
+            for _ in 0..self.params.layout[k] {
+                step *= 2;
+            }
+            len /= coset_sizes[k];
+
+            // Note - we could express this a merkle error instead but this adds specificity
+            if merkle_proof.verify_with::<H, _>(&fri_layer_values).is_err() {
+                return Err(Error::InvalidFriCommitment);
+            };
+
+            previous_indices = fri_indices.clone();
+            if k + 1 < self.params.layout.len() {
+                fri_indices = fri_indices
+                    .iter()
+                    .map(|ind| ind / coset_sizes[k + 1])
+                    .collect();
+            }
+        }
+
+        // Checks that the calculated fri folded queries are the points interpolated by
+        // the decommited polynomial.
+        let interp_root = match FieldElement::root(len) {
+            Some(x) => x,
+            None => return Err(Error::RootUnavailable),
+        };
+        let last_layer = DensePolynomial::new(&self.last_layer);
+        for key in &previous_indices {
+            let calculated = fri_folds[key].clone();
+            let x_pow = interp_root.pow(permute_index(len, *key));
+            let committed = last_layer.evaluate(&x_pow);
+
+            if committed != calculated {
+                return Err(Error::OodsCalculationFailure);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fri_fold(
+    coset: &[FieldElement],
+    eval_point: &FieldElement,
+    mut step: usize,
+    mut index: usize,
+    mut len: usize,
+    root: &FieldElement,
+) -> FieldElement {
+    let mut mutable_eval_copy = eval_point.clone();
+    let mut coset_full: Vec<FieldElement> = coset.to_vec();
+    while coset_full.len() > 1 {
+        let mut next_coset = Vec::with_capacity(coset.len() / 2);
+
+        for (k, pair) in coset_full.chunks(2).enumerate() {
+            let x = root.pow(permute_index(len / 2, index + k) * step);
+            next_coset.push(fri_single_fold(&pair[0], &pair[1], &x, &mutable_eval_copy));
+        }
+        len /= 2;
+        index /= 2;
+        step *= 2;
+        mutable_eval_copy = mutable_eval_copy.square();
+        coset_full = next_coset;
+    }
+    coset_full[0].clone()
+}
+
+fn fri_single_fold(
+    poly_at_x: &FieldElement,
+    poly_at_neg_x: &FieldElement,
+    x: &FieldElement,
+    eval_point: &FieldElement,
+) -> FieldElement {
+    (poly_at_x + poly_at_neg_x) + eval_point / x * (poly_at_x - poly_at_neg_x)
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::HashChain;
+    use zkp_hash::MaskedKeccak;
+
+    fn prove_and_verify(
+        evaluations: MmapVec<FieldElement>,
+        queries: &[usize],
+    ) -> Result<(), Error> {
+        let params = FriParams {
+            layout: vec![2, 1],
+            blowup: 4,
+        };
+        let domain_size = evaluations.len();
+        let values: Vec<(usize, FieldElement)> = queries
+            .iter()
+            .map(|&index| (index, evaluations[index].clone()))
+            .collect();
+
+        let mut prover_channel = ProverChannel::<HashChain<MaskedKeccak>>::new();
+        prover_channel.initialize(b"fri test");
+        let fri =
+            FriProver::<MaskedKeccak>::commit(evaluations, &params, &mut prover_channel).unwrap();
+        fri.decommit(queries, &mut prover_channel).unwrap();
+
+        let mut verifier_channel =
+            VerifierChannel::<HashChain<MaskedKeccak>>::new(prover_channel.proof);
+        verifier_channel.initialize(b"fri test");
+        let fri = FriVerifier::read_commitments(&params, domain_size, &mut verifier_channel)?;
+        assert_eq!(fri.commitments.len(), 2);
+        fri.verify::<MaskedKeccak, _>(&values, &mut verifier_channel)?;
+        assert!(verifier_channel.at_end());
+        Ok(())
+    }
+
+    #[test]
+    fn test_low_degree() {
+        let polynomial = DensePolynomial::new(
+            &(0..16_u64)
+                .map(|i| FieldElement::from(i * i + 7))
+                .collect::<Vec<_>>(),
+        );
+        let evaluations = polynomial.low_degree_extension(4);
+        assert_eq!(evaluations.len(), 64);
+        assert_eq!(prove_and_verify(evaluations, &[0, 5, 6, 33, 63]), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        let evaluations = DensePolynomial::new(&[FieldElement::ONE; 16]).low_degree_extension(4);
+        for layout in &[vec![], vec![0], vec![5], vec![1, 0], vec![4, 3]] {
+            let params = FriParams {
+                layout: layout.clone(),
+                blowup: 4,
+            };
+            let mut channel = ProverChannel::<HashChain<MaskedKeccak>>::new();
+            assert_eq!(
+                FriProver::<MaskedKeccak>::commit(evaluations.clone(), &params, &mut channel)
+                    .unwrap_err(),
+                ProverError::InvalidFriParams
+            );
+            let mut channel = VerifierChannel::<HashChain<MaskedKeccak>>::new(channel.proof);
+            assert_eq!(
+                FriVerifier::read_commitments(&params, evaluations.len(), &mut channel)
+                    .unwrap_err(),
+                Error::UnsupportedParameters
+            );
+        }
+    }
+
+    #[test]
+    fn test_high_degree() {
+        let mut evaluations = MmapVec::with_capacity(64);
+        evaluations.extend((0..64_u64).map(|i| FieldElement::from(i * i * i + 1)));
+        let queries: Vec<usize> = (0..64).collect();
+        assert_eq!(
+            prove_and_verify(evaluations, &queries),
+            Err(Error::OodsCalculationFailure)
+        );
+    }
+}
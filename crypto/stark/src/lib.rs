@@ -37,13 +37,33 @@
 )]
 #![cfg_attr(feature = "std", warn(missing_debug_implementations,))]
 
+#[cfg(feature = "json")]
+mod air;
+#[cfg(feature = "std")]
+mod annotations;
+#[cfg(feature = "async")]
+mod asynchronous;
 mod channel;
+mod claim_bytes;
+pub mod components;
 mod constraints;
+mod continuation;
+mod external_commitment;
+mod fri;
+mod metrics;
+mod parallelism;
+mod pcs;
+mod periodic_column;
 mod polynomial;
 mod proof;
 mod proof_of_work;
+mod proof_params;
+mod proof_stats;
+mod public_column;
 mod rational_expression;
+mod running_product;
 mod traits;
+mod transcript_logger;
 mod verifier;
 
 // Optional prover functionality. Note that prover requires std.
@@ -51,8 +71,20 @@ mod verifier;
 #[cfg(feature = "prover")]
 mod algebraic_dag;
 #[cfg(feature = "prover")]
+// The backend is only public with the `gpu` feature
+#[cfg_attr(not(feature = "gpu"), allow(unreachable_pub))]
+mod backend;
+#[cfg(feature = "prover")]
 mod prover;
 #[cfg(feature = "prover")]
+mod prover_callback;
+#[cfg(feature = "prover")]
+mod prover_options;
+#[cfg(all(feature = "prover", feature = "quickcheck"))]
+pub mod testing;
+#[cfg(feature = "prover")]
+mod trace_provider;
+#[cfg(feature = "prover")]
 mod trace_table;
 
 // TODO: Have unconditional Debug trait on all types
@@ -65,21 +97,101 @@ extern crate no_std_compat as std;
 // Re-exports dependencies that are part of the public interface
 pub use zkp_primefield as primefield;
 
+// Allocation types for the `StarkClaim` derive, which can not assume `std`.
+#[doc(hidden)]
+pub use std::prelude::v1 as prelude;
+
 // Exports for verifier
+#[cfg(feature = "json")]
+pub use air::{hex_field, Error as AirError};
+#[cfg(feature = "std")]
+pub use annotations::{check_annotations, Error as AnnotationError};
+pub use channel::{
+    ChannelLayout, CounterLayout, FieldEncoding, HashChain, Label, ProverChannel, RandomGenerator,
+    Replayable, Transcript, VerifierChannel, Writable, PROTOCOL_VERSION,
+};
+pub use claim_bytes::ClaimBytes;
 pub use constraints::{Constraints, Error as ConstraintError};
+pub use continuation::{
+    boundary_expressions, state_digest, verify_chain, Continuation, Error as ContinuationError,
+};
+pub use external_commitment::ExternalCommitment;
+pub use fri::{FriParams, FriVerifier};
+#[cfg(feature = "metrics")]
+pub use metrics::Counters;
+pub use parallelism::Parallelism;
+pub use pcs::{EvaluationProof, FriPcs};
+pub use periodic_column::PeriodicColumn;
 pub use polynomial::DensePolynomial;
 pub use proof::Proof;
+pub use proof_params::ProofParams;
+pub use proof_stats::{DecommitmentStats, ProofStats};
+pub use public_column::PublicColumn;
 pub use rational_expression::RationalExpression;
+pub use running_product::RunningProduct;
 pub use traits::Verifiable;
-pub use verifier::{verify, Error as VerifierError};
+
+// Fixtures for the tests of dependent crates
+#[cfg(feature = "test")]
+pub use traits::tests::{fib_claim, fib_witness};
+pub use transcript_logger::{TranscriptEntry, TranscriptLogger};
+pub use verifier::{
+    verify, verify_batch, verify_logged, verify_with, verify_with_transcript,
+    Error as VerifierError, MAX_DOMAIN_SIZE, MAX_FRI_LAYERS, MAX_PROOF_SIZE, MAX_QUERIES,
+    MAX_TRACE_LENGTH,
+};
 
 // Exports for prover
+#[cfg(feature = "async")]
+pub use asynchronous::{prove_async, verify_async};
+#[cfg(feature = "gpu")]
+pub use backend::{Backend, CpuBackend};
+#[cfg(feature = "prover")]
+pub use components::Component;
+#[cfg(feature = "prover")]
+pub use constraints::ConstraintViolation;
 #[cfg(feature = "prover")]
-pub use prover::{prove, Error as ProverError};
+pub use fri::FriProver;
+#[cfg(feature = "gpu")]
+pub use prover::prove_with_backend;
 #[cfg(feature = "prover")]
-pub use trace_table::TraceTable;
+pub use prover::{
+    prove, prove_logged, prove_streaming, prove_with, prove_with_callback, prove_with_options,
+    prove_with_transcript, Error as ProverError,
+};
+#[cfg(feature = "prover")]
+pub use prover_callback::{CancellationToken, ProverCallback, ProverEvent};
+#[cfg(feature = "prover")]
+pub use prover_options::ProverOptions;
+#[cfg(feature = "prover")]
+pub use trace_provider::TraceProvider;
+#[cfg(feature = "prover")]
+pub use trace_table::{TraceLayout, TraceTable};
 #[cfg(feature = "prover")]
 pub use traits::Provable;
+#[cfg(feature = "prover")]
+pub use zkp_mmap_vec::Storage;
+
+// Proofs are made and checked on worker threads, for example by
+// `prove_async`. Fail to compile if a public type stops being `Send + Sync`.
+#[allow(dead_code)]
+fn assert_send_sync() {
+    fn check<T: Send + Sync>() {}
+    check::<Proof>();
+    check::<Constraints>();
+    check::<RationalExpression>();
+    check::<ProofParams>();
+    check::<ProverChannel>();
+    check::<VerifierChannel>();
+    check::<VerifierError>();
+    #[cfg(feature = "prover")]
+    {
+        check::<TraceTable>();
+        check::<Component>();
+        check::<ProverError>();
+        check::<CancellationToken>();
+    }
+}
 
 #[cfg(test)]
 mod tests {
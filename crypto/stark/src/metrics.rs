@@ -0,0 +1,225 @@
+//! `tracing` spans for the phases of the prover and verifier.
+//!
+//! With the `metrics` feature the prover runs in a `prove` span and the
+//! verifier in a `verify` span. Inside these, each phase gets a `phase` span
+//! with the phase in the `name` field, the FRI layer in the `layer` field,
+//! and the number of hashes computed during the phase in the `hashes` field.
+//! The hash count is recorded when the phase ends. Attach a subscriber to
+//! collect timings, for example to export them to Prometheus.
+//!
+//! Without the feature [`Phases`] does nothing and compiles away.
+#[cfg(feature = "metrics")]
+use tracing::{field::Empty, info_span, span::EnteredSpan, Span};
+
+/// Snapshot of the global operation counters.
+///
+/// The counters are shared by all threads, so concurrent proofs are included
+/// in each others counts. Field operations are not counted, as a counter in
+/// every multiplication would slow down the prover.
+#[cfg(feature = "metrics")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counters {
+    /// Calls to `Hasher` functions, see `zkp_hash::metrics`.
+    pub hashes: u64,
+}
+
+#[cfg(feature = "metrics")]
+impl Counters {
+    pub fn now() -> Self {
+        Self {
+            hashes: zkp_hash::metrics::hash_count(),
+        }
+    }
+
+    /// The operations since `earlier`.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            hashes: self.hashes - earlier.hashes,
+        }
+    }
+}
+
+/// The span of the running prover or verifier and of its current phase.
+///
+/// Starting a phase ends the previous one. The last phase ends when the
+/// value is dropped.
+#[cfg(feature = "metrics")]
+pub(crate) struct Phases {
+    current: Option<(EnteredSpan, Counters)>,
+    _root:   EnteredSpan,
+}
+
+#[cfg(not(feature = "metrics"))]
+pub(crate) struct Phases;
+
+#[cfg(feature = "metrics")]
+impl Phases {
+    pub(crate) fn prove() -> Self {
+        Self::new(info_span!("prove"))
+    }
+
+    pub(crate) fn verify() -> Self {
+        Self::new(info_span!("verify"))
+    }
+
+    fn new(root: Span) -> Self {
+        Self {
+            current: None,
+            _root:   root.entered(),
+        }
+    }
+
+    pub(crate) fn start(&mut self, name: &'static str, layer: Option<usize>) {
+        self.end();
+        let span = info_span!("phase", name, layer, hashes = Empty).entered();
+        self.current = Some((span, Counters::now()));
+    }
+
+    fn end(&mut self) {
+        if let Some((span, start)) = self.current.take() {
+            let _ = span.record("hashes", Counters::now().since(&start).hashes);
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+impl Drop for Phases {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+impl Phases {
+    pub(crate) fn prove() -> Self {
+        Self
+    }
+
+    pub(crate) fn verify() -> Self {
+        Self
+    }
+
+    #[allow(clippy::unused_self)]
+    pub(crate) fn start(&mut self, _name: &'static str, _layer: Option<usize>) {}
+}
+
+#[cfg(all(test, feature = "metrics", feature = "prover"))]
+mod tests {
+    use crate::{
+        prove,
+        traits::tests::{fib_claim, fib_witness},
+        verify, Provable, Verifiable,
+    };
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        subscriber::with_default,
+        Event, Metadata, Subscriber,
+    };
+
+    /// Subscriber that records the name and hash count of each span, by id.
+    ///
+    /// Phase spans are recorded by the phase name.
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<(String, u64)>>>);
+
+    struct Visitor<'a>(&'a mut (String, u64));
+
+    impl Visit for Visitor<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "name" {
+                value.clone_into(&mut (self.0).0);
+            }
+        }
+
+        fn record_u64(&mut self, field: &Field, value: u64) {
+            if field.name() == "hashes" {
+                (self.0).1 = value;
+            }
+        }
+
+        fn record_debug(&mut self, _field: &Field, _value: &dyn fmt::Debug) {}
+    }
+
+    impl Recorder {
+        fn spans(&self) -> Vec<(String, u64)> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            let mut entry = (span.metadata().name().to_owned(), 0);
+            span.record(&mut Visitor(&mut entry));
+            spans.push(entry);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            #[allow(clippy::cast_possible_truncation)]
+            let index = span.into_u64() as usize - 1;
+            values.record(&mut Visitor(&mut spans[index]));
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn phases_test() {
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&fib_witness());
+
+        let recorder = Recorder::default();
+        let proof = with_default(recorder.clone(), || prove(&constraints, &trace)).unwrap();
+        let spans = recorder.spans();
+        let names: Vec<&str> = spans.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names[..4], [
+            "prove",
+            "trace_lde",
+            "constraint_evaluation",
+            "constraint_lde"
+        ]);
+        assert_eq!(
+            names.iter().filter(|&&name| name == "fri_layer").count(),
+            constraints.fri_layout.len()
+        );
+        // The trace LDE phase includes the trace commitment
+        assert!(spans[1].1 > 0);
+        // The prover verifies the proof in its last phase
+        assert_eq!(names[names.len() - 7..], [
+            "verify",
+            "verify",
+            "commitments",
+            "proof_of_work",
+            "decommitments",
+            "fri",
+            "oods"
+        ]);
+
+        let recorder = Recorder::default();
+        with_default(recorder.clone(), || verify(&constraints, &proof)).unwrap();
+        let spans = recorder.spans();
+        assert_eq!(spans.len(), 6);
+        assert_eq!(spans[0].0, "verify");
+        // Decommitments are checked against the Merkle roots
+        assert_eq!(spans[3].0, "decommitments");
+        assert!(spans[3].1 > 0);
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(feature = "std")]
+use std::{cell::Cell, sync::Arc};
+
+#[cfg(feature = "std")]
+thread_local! {
+    // Set on threads running a `Parallelism::Sequential` job
+    static SEQUENTIAL: Cell<bool> = Cell::new(false);
+}
+
+/// Where the prover runs its parallel work.
+///
+/// All parallel stages of the prover run inside [`install`], so the choice
+/// applies to the whole proof without touching the global Rayon pool. Set
+/// it with [`ProverOptions::with_parallelism`].
+///
+/// [`install`]: #method.install
+/// [`ProverOptions::with_parallelism`]: struct.ProverOptions.html#method.with_parallelism
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Parallelism {
+    /// Use the current Rayon pool, which is the global pool unless called
+    /// from inside another pool.
+    Global,
+    /// Run everything on the calling thread, without Rayon.
+    Sequential,
+    /// Use the given Rayon pool.
+    #[cfg(feature = "std")]
+    Pool(Arc<rayon::ThreadPool>),
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Self::Global
+    }
+}
+
+impl PartialEq for Parallelism {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Global, Self::Global) | (Self::Sequential, Self::Sequential) => true,
+            #[cfg(feature = "std")]
+            (Self::Pool(a), Self::Pool(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Parallelism {}
+
+impl Parallelism {
+    /// Run `job` with this parallelism.
+    ///
+    /// `Sequential` runs the job directly on the calling thread. The parallel
+    /// stages of the prover and the Merkle tree construction detect this and
+    /// fall back to plain iterators.
+    #[cfg(feature = "std")]
+    pub fn install<R, F>(&self, job: F) -> R
+    where
+        R: Send,
+        F: FnOnce() -> R + Send,
+    {
+        // Restores the previous value, also when `job` panics
+        struct Restore(bool);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                SEQUENTIAL.with(|sequential| sequential.set(self.0));
+            }
+        }
+        match self {
+            Self::Global => job(),
+            Self::Sequential => {
+                let _restore = Restore(SEQUENTIAL.with(|sequential| sequential.replace(true)));
+                // Merkle trees are only built by the prover
+                #[cfg(feature = "prover")]
+                let result = zkp_merkle_tree::sequential(job);
+                #[cfg(not(feature = "prover"))]
+                let result = job();
+                result
+            }
+            Self::Pool(pool) => pool.install(job),
+        }
+    }
+
+    /// Run `job`. Without `std` there are no threads.
+    #[cfg(not(feature = "std"))]
+    pub fn install<R, F>(&self, job: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        job()
+    }
+}
+
+/// Whether the calling thread runs a [`Parallelism::Sequential`] job.
+///
+/// [`Parallelism::Sequential`]: enum.Parallelism.html#variant.Sequential
+#[cfg(feature = "std")]
+pub(crate) fn is_sequential() -> bool {
+    SEQUENTIAL.with(Cell::get)
+}
+
+#[cfg(feature = "std")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn install_test() {
+        assert!(Parallelism::Sequential.install(is_sequential));
+        assert!(!is_sequential());
+        assert!(!Parallelism::Global.install(is_sequential));
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(3)
+                .build()
+                .unwrap(),
+        );
+        let parallelism = Parallelism::Pool(pool.clone());
+        assert_eq!(parallelism.install(rayon::current_num_threads), 3);
+        assert_eq!(parallelism, Parallelism::Pool(pool));
+        assert_ne!(parallelism, Parallelism::Global);
+    }
+}
@@ -0,0 +1,307 @@
+// False positives on the Latex math.
+#![allow(clippy::doc_markdown)]
+//! FRI based polynomial commitment scheme.
+//!
+//! A polynomial $P$ with $n$ coefficients is committed to by a Merkle tree
+//! over its low degree extension to $n \cdot \text{blowup}$ points. To open
+//! it at a point $z$ to a value $v$, the prover runs the FRI low degree test
+//! on the quotient $(P(X) - v) / (X - z)$. The verifier computes the quotient
+//! at the query points from decommitted values of $P$. If $P(z) \neq v$ the
+//! quotient is not a polynomial and the low degree test fails.
+use crate::{
+    channel::{HashChain, Replayable, VerifierChannel},
+    fri::{FriParams, FriVerifier},
+    verifier::{self, Error},
+};
+#[cfg(feature = "prover")]
+use crate::{
+    channel::{ProverChannel, Writable},
+    fri::FriProver,
+    polynomial::DensePolynomial,
+    prover::Error as ProverError,
+};
+use std::{marker::PhantomData, prelude::v1::*};
+use zkp_hash::{Hash, Hasher, MaskedKeccak};
+use zkp_merkle_tree::{Commitment, Proof as MerkleProof};
+#[cfg(feature = "prover")]
+use zkp_merkle_tree::{CommitmentScheme, MerkleScheme};
+#[cfg(feature = "prover")]
+use zkp_mmap_vec::MmapVec;
+use zkp_primefield::{fft::permute_index, FieldElement};
+use zkp_u256::U256;
+
+#[cfg(feature = "prover")]
+type LdeCommitment<H> = MerkleScheme<MmapVec<FieldElement>, H>;
+
+/// Polynomial commitments using Merkle trees and FRI, hashed with `H`.
+///
+/// The commitment is the Merkle root of the low degree extension. Its size
+/// determines the number of coefficients the polynomial may have, so the
+/// verifier does not need to know it in advance.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FriPcs<H: Hasher = MaskedKeccak> {
+    /// The ratio of the committed domain size to the number of coefficients.
+    pub blowup:      usize,
+    /// The number of FRI queries in an opening proof.
+    pub num_queries: usize,
+    hasher:          PhantomData<H>,
+}
+
+/// Proof that a committed polynomial has a certain value at a point.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EvaluationProof(Vec<u8>);
+
+impl EvaluationProof {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<H: Hasher> Default for FriPcs<H> {
+    fn default() -> Self {
+        Self::new(16, 30)
+    }
+}
+
+impl<H: Hasher> FriPcs<H> {
+    pub fn new(blowup: usize, num_queries: usize) -> Self {
+        assert!(blowup.is_power_of_two());
+        Self {
+            blowup,
+            num_queries,
+            hasher: PhantomData,
+        }
+    }
+
+    fn fri_params(&self, domain_size: usize) -> FriParams {
+        FriParams {
+            layout: FriParams::default_layout(domain_size / self.blowup),
+            blowup: self.blowup,
+        }
+    }
+
+    // The channel is seeded with the claim so the proof is bound to it.
+    fn seed(commitment: &Commitment, point: &FieldElement, value: &FieldElement) -> Vec<u8> {
+        [
+            commitment.hash().as_bytes(),
+            &U256::from(point).to_bytes_be()[..],
+            &U256::from(value).to_bytes_be()[..],
+        ]
+        .concat()
+    }
+
+    /// Commit to `polynomial`.
+    #[cfg(feature = "prover")]
+    pub fn commit(&self, polynomial: &DensePolynomial) -> Result<Commitment, ProverError> {
+        let (commitment, _) = LdeCommitment::<H>::commit(self.extend(polynomial))?;
+        Ok(commitment)
+    }
+
+    /// Prove the value of `polynomial` at `point`.
+    ///
+    /// The value itself is not part of the proof, it can be computed with
+    /// [`DensePolynomial::evaluate`].
+    #[cfg(feature = "prover")]
+    pub fn open(
+        &self,
+        polynomial: &DensePolynomial,
+        point: &FieldElement,
+    ) -> Result<EvaluationProof, ProverError> {
+        self.open_with_value(polynomial, point, &polynomial.evaluate(point))
+    }
+
+    // The quotient is computed for the true value, so a wrong `value` only
+    // changes the channel seed. This lets tests produce false proofs.
+    #[cfg(feature = "prover")]
+    fn open_with_value(
+        &self,
+        polynomial: &DensePolynomial,
+        point: &FieldElement,
+        value: &FieldElement,
+    ) -> Result<EvaluationProof, ProverError> {
+        let polynomial = Self::pad(polynomial);
+        let (commitment, tree) = LdeCommitment::<H>::commit(self.extend(&polynomial))?;
+        let domain_size = commitment.size();
+
+        // Quotient (P(X) - P(z)) / (X - z)
+        let mut quotient = DensePolynomial::zeros(polynomial.len());
+        polynomial.divide_out_point_into(point, &FieldElement::ONE, &mut quotient);
+
+        let mut channel = ProverChannel::<HashChain<H>>::new();
+        channel.initialize(&Self::seed(&commitment, point, value));
+        let fri = FriProver::<H>::commit(
+            quotient.low_degree_extension(self.blowup),
+            &self.fri_params(domain_size),
+            &mut channel,
+        )?;
+
+        let queries =
+            verifier::get_indices(self.num_queries, domain_size.trailing_zeros(), &mut channel);
+        for &index in &queries {
+            channel.write(&tree.leaves()[index]);
+        }
+        channel.write(&LdeCommitment::<H>::batch_open(&tree, &queries)?);
+        fri.decommit(&queries, &mut channel)?;
+        Ok(EvaluationProof(channel.proof))
+    }
+
+    /// Check that the polynomial committed to by `commitment` has `value` at
+    /// `point`.
+    ///
+    /// Points in the committed coset are rejected with
+    /// `InvalidEvaluationPoint`, the quotient is not defined there.
+    pub fn verify(
+        &self,
+        commitment: &Commitment,
+        point: &FieldElement,
+        value: &FieldElement,
+        proof: &EvaluationProof,
+    ) -> Result<(), Error> {
+        let domain_size = commitment.size();
+        if !domain_size.is_power_of_two() || domain_size < 2 * self.blowup {
+            return Err(Error::InvalidLDECommitment);
+        }
+        let root = FieldElement::root(domain_size).ok_or(Error::RootUnavailable)?;
+        let shifted = point / &FieldElement::GENERATOR;
+        if shifted.pow(domain_size) == FieldElement::ONE {
+            return Err(Error::InvalidEvaluationPoint);
+        }
+        let mut channel = VerifierChannel::<HashChain<H>>::new(proof.0.clone());
+        channel.initialize(&Self::seed(commitment, point, value));
+        let fri = FriVerifier::read_commitments(
+            &self.fri_params(domain_size),
+            domain_size,
+            &mut channel,
+        )?;
+
+        let queries =
+            verifier::get_indices(self.num_queries, domain_size.trailing_zeros(), &mut channel);
+        let values: Vec<(usize, FieldElement)> = queries
+            .iter()
+            .map(|&index| (index, Replayable::<FieldElement>::replay(&mut channel)))
+            .collect();
+        let proof_length = commitment.proof_size(&queries)?;
+        let hashes = Replayable::<Hash>::replay_many(&mut channel, proof_length);
+        let merkle_proof = MerkleProof::from_hashes(commitment, &queries, &hashes)?;
+        if merkle_proof.verify_with::<H, _>(&values).is_err() {
+            return Err(Error::InvalidLDECommitment);
+        }
+
+        // Compute the quotient on the queried points of the LDE coset
+        let mut quotient_values = Vec::with_capacity(values.len());
+        for (index, polynomial_value) in values {
+            let x = FieldElement::GENERATOR * root.pow(permute_index(domain_size, index));
+            let denominator = (x - point).inv().ok_or(Error::InvalidEvaluationPoint)?;
+            quotient_values.push((index, (polynomial_value - value) * denominator));
+        }
+        fri.verify::<H, _>(&quotient_values, &mut channel)?;
+        if !channel.at_end() {
+            return Err(Error::ProofTooLong);
+        }
+        Ok(())
+    }
+
+    // FRI needs at least one reduction, so constants are committed to as
+    // linear polynomials.
+    #[cfg(feature = "prover")]
+    fn pad(polynomial: &DensePolynomial) -> DensePolynomial {
+        if polynomial.len() > 1 {
+            polynomial.clone()
+        } else {
+            DensePolynomial::new(&[polynomial.coefficients()[0].clone(), FieldElement::ZERO])
+        }
+    }
+
+    #[cfg(feature = "prover")]
+    fn extend(&self, polynomial: &DensePolynomial) -> MmapVec<FieldElement> {
+        Self::pad(polynomial).low_degree_extension(self.blowup)
+    }
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polynomial() -> DensePolynomial {
+        DensePolynomial::new(
+            &(0..32_u64)
+                .map(|i| FieldElement::from(3 * i * i + 5))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn test_open_verify() {
+        let pcs = FriPcs::<MaskedKeccak>::new(4, 10);
+        let polynomial = polynomial();
+        let point = FieldElement::from(12345_u64);
+        let value = polynomial.evaluate(&point);
+        let commitment = pcs.commit(&polynomial).unwrap();
+        assert_eq!(commitment.size(), 128);
+        let proof = pcs.open(&polynomial, &point).unwrap();
+        assert_eq!(pcs.verify(&commitment, &point, &value, &proof), Ok(()));
+    }
+
+    #[test]
+    fn test_false_value() {
+        let pcs = FriPcs::<MaskedKeccak>::new(4, 10);
+        let polynomial = polynomial();
+        let point = FieldElement::from(12345_u64);
+        let value = polynomial.evaluate(&point) + FieldElement::ONE;
+        let commitment = pcs.commit(&polynomial).unwrap();
+        let proof = pcs.open_with_value(&polynomial, &point, &value).unwrap();
+        assert_eq!(
+            pcs.verify(&commitment, &point, &value, &proof),
+            Err(Error::InvalidFriCommitment)
+        );
+    }
+
+    #[test]
+    fn test_tampered_proof() {
+        let pcs = FriPcs::<MaskedKeccak>::new(4, 10);
+        let polynomial = polynomial();
+        let point = FieldElement::from(12345_u64);
+        let value = polynomial.evaluate(&point);
+        let commitment = pcs.commit(&polynomial).unwrap();
+        let mut bytes = pcs.open(&polynomial, &point).unwrap().as_bytes().to_vec();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let proof = EvaluationProof::from_bytes(bytes);
+        assert!(pcs.verify(&commitment, &point, &value, &proof).is_err());
+    }
+
+    #[test]
+    fn test_constant() {
+        let pcs = FriPcs::<MaskedKeccak>::new(4, 10);
+        let polynomial = DensePolynomial::new(&[FieldElement::from(7_u64)]);
+        let point = FieldElement::from(12345_u64);
+        let commitment = pcs.commit(&polynomial).unwrap();
+        let proof = pcs.open(&polynomial, &point).unwrap();
+        assert_eq!(
+            pcs.verify(&commitment, &point, &FieldElement::from(7_u64), &proof),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_point_in_coset() {
+        let pcs = FriPcs::<MaskedKeccak>::new(4, 10);
+        let polynomial = polynomial();
+        let commitment = pcs.commit(&polynomial).unwrap();
+        let root = FieldElement::root(commitment.size()).unwrap();
+        let point = FieldElement::GENERATOR * root.pow(5_usize);
+        let value = polynomial.evaluate(&point);
+        let proof = pcs.open(&polynomial, &point).unwrap();
+        assert_eq!(
+            pcs.verify(&commitment, &point, &value, &proof),
+            Err(Error::InvalidEvaluationPoint)
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{polynomial::DensePolynomial, rational_expression::RationalExpression};
+use std::prelude::v1::*;
+use zkp_primefield::{fft::ifft, FieldElement};
+
+/// A column of values that repeats with a fixed period.
+///
+/// Periodic columns are known to both prover and verifier and do not need to
+/// be committed to. They are typically used for round constants and lookup
+/// tables. The column is stored as the polynomial `P` of degree less than the
+/// period `p` such that `P(ω_p^i)` is the `i`-th value, with `ω_p` the `p`-th
+/// root of unity. On a trace of length `n` the value at row `r` is
+/// `P(x^(n / p))` with `x = ω_n^r`.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PeriodicColumn {
+    polynomial: DensePolynomial,
+}
+
+impl PeriodicColumn {
+    /// Creates a periodic column from the coefficients of its polynomial.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of coefficients is not a power of two.
+    pub fn from_coefficients(coefficients: &[FieldElement]) -> Self {
+        Self {
+            polynomial: DensePolynomial::new(coefficients),
+        }
+    }
+
+    /// Creates a periodic column from the values in one period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of values is not a power of two.
+    pub fn from_values(values: &[FieldElement]) -> Self {
+        assert!(values.len().is_power_of_two());
+        Self::from_coefficients(&ifft(values))
+    }
+
+    pub fn period(&self) -> usize {
+        self.polynomial.len()
+    }
+
+    pub fn polynomial(&self) -> &DensePolynomial {
+        &self.polynomial
+    }
+
+    /// The value of the column on the given row.
+    pub fn value(&self, row: usize) -> FieldElement {
+        let root = FieldElement::root(self.period()).expect("Period is a power of two.");
+        self.polynomial.evaluate(&root.pow(row % self.period()))
+    }
+
+    /// The values of the column in one period.
+    pub fn values(&self) -> Vec<FieldElement> {
+        (0..self.period()).map(|row| self.value(row)).collect()
+    }
+
+    /// The column as an expression in a trace of the given length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the period does not divide the trace length.
+    pub fn expression(&self, trace_length: usize) -> RationalExpression {
+        use RationalExpression::*;
+        assert_eq!(trace_length % self.period(), 0);
+        Polynomial(
+            self.polynomial.clone(),
+            Box::new(X.pow(trace_length / self.period())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    #[test]
+    fn from_values_test() {
+        let values: Vec<FieldElement> = (0..8).map(|i| FieldElement::from(i * i + 3)).collect();
+        let column = PeriodicColumn::from_values(&values);
+        assert_eq!(column.period(), 8);
+        assert_eq!(column.values(), values);
+        assert_eq!(column.value(13), values[5]);
+    }
+
+    #[quickcheck]
+    fn expression_repeats(values: Vec<FieldElement>, log_repetitions: u8) {
+        let mut values = values;
+        values.resize(values.len().next_power_of_two(), FieldElement::ZERO);
+        let column = PeriodicColumn::from_values(&values);
+        let trace_length = values.len() << (log_repetitions % 4);
+        let expression = column.expression(trace_length);
+        let generator = FieldElement::root(trace_length).unwrap();
+        let no_trace = |_, _| panic!("Periodic columns do not depend on the trace");
+        for row in 0..trace_length {
+            assert_eq!(
+                expression.evaluate(&generator.pow(row), &no_trace),
+                values[row % values.len()]
+            );
+        }
+    }
+}
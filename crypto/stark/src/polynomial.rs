@@ -1,10 +1,14 @@
 // TODO: Naming?
 #![allow(clippy::module_name_repetitions)]
 #[cfg(feature = "std")]
+use crate::parallelism::is_sequential;
+#[cfg(feature = "std")]
 use rayon::prelude::*;
 use std::prelude::v1::*;
 use zkp_mmap_vec::MmapVec;
 #[cfg(feature = "std")]
+use zkp_mmap_vec::Storage;
+#[cfg(feature = "std")]
 use zkp_primefield::fft::{fft_cofactor_permuted_out, permute_index};
 use zkp_primefield::FieldElement;
 
@@ -74,27 +78,80 @@ impl DensePolynomial {
         result
     }
 
+    /// The largest power of two `k` such that only every `k`-th coefficient
+    /// is non-zero, i.e. the polynomial is `Q(X^k)` for some `Q`.
+    ///
+    /// The evaluations of such a polynomial on a domain of size `len` repeat
+    /// with period `len / k`. Constant and periodic trace columns have this
+    /// form.
+    pub fn stride(&self) -> usize {
+        self.0
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, coefficient)| !coefficient.is_zero())
+            .map(|(index, _)| 1 << index.trailing_zeros())
+            .min()
+            .unwrap_or_else(|| self.len())
+    }
+
+    /// Evaluate on `blowup` cosets of the domain of size `len`, in permuted
+    /// order.
+    ///
+    /// For a polynomial `Q(X^k)`, see [`stride`], each coset is computed with
+    /// an FFT of size `len / k`. In permuted order the evaluations of `Q`
+    /// appear as consecutive blocks of `k` equal values.
+    ///
+    /// [`stride`]: #method.stride
     #[cfg(feature = "std")]
     pub fn low_degree_extension(&self, blowup: usize) -> MmapVec<FieldElement> {
+        self.low_degree_extension_in(blowup, Storage::default())
+    }
+
+    /// Like [`low_degree_extension`] with the result kept as selected by
+    /// `storage`.
+    ///
+    /// [`low_degree_extension`]: #method.low_degree_extension
+    #[cfg(feature = "std")]
+    pub fn low_degree_extension_in(
+        &self,
+        blowup: usize,
+        storage: Storage,
+    ) -> MmapVec<FieldElement> {
         // TODO: shift polynomial by FieldElement::GENERATOR outside of this function.
         const SHIFT_FACTOR: FieldElement = FieldElement::GENERATOR;
         let length = self.len() * blowup;
         let generator =
             FieldElement::root(length).expect("No generator for extended_domain_length.");
+        let stride = self.stride();
+        let sparse: Vec<FieldElement> = self.0.iter().step_by(stride).cloned().collect();
 
         // FieldElement is safe to initialize zero (which maps to zero)
         #[allow(unsafe_code)]
-        let mut result: MmapVec<FieldElement> = unsafe { MmapVec::zero_initialized(length) };
+        let mut result: MmapVec<FieldElement> =
+            unsafe { MmapVec::zero_initialized_in(length, storage) };
 
         // Compute cosets in parallel
-        result
-            .as_mut_slice()
-            .par_chunks_mut(self.len())
-            .enumerate()
-            .for_each(|(i, slice)| {
-                let cofactor = &SHIFT_FACTOR * generator.pow(permute_index(blowup, i));
+        let coset = |(i, slice): (usize, &mut [FieldElement])| {
+            let cofactor = &SHIFT_FACTOR * generator.pow(permute_index(blowup, i));
+            if stride == 1 {
                 fft_cofactor_permuted_out(&cofactor, &self.coefficients(), slice);
-            });
+            } else {
+                let mut values = vec![FieldElement::ZERO; sparse.len()];
+                fft_cofactor_permuted_out(&cofactor.pow(stride), &sparse, &mut values);
+                for (block, value) in slice.chunks_mut(stride).zip(values) {
+                    for element in block {
+                        *element = value.clone();
+                    }
+                }
+            }
+        };
+        let slice = result.as_mut_slice();
+        if is_sequential() {
+            slice.chunks_mut(self.len()).enumerate().for_each(coset);
+        } else {
+            slice.par_chunks_mut(self.len()).enumerate().for_each(coset);
+        }
         result
     }
 
@@ -112,9 +169,9 @@ impl DensePolynomial {
     }
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 use quickcheck::{Arbitrary, Gen};
-#[cfg(test)]
+#[cfg(any(test, feature = "quickcheck"))]
 impl Arbitrary for DensePolynomial {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         let mut coefficients = Vec::<FieldElement>::arbitrary(g);
@@ -146,4 +203,35 @@ mod tests {
         let p = dense_polynomial(&[1, 0, 0, 2]);
         assert_eq!(p.evaluate(&FieldElement::from(2)), FieldElement::from(17));
     }
+
+    #[test]
+    fn stride_test() {
+        assert_eq!(dense_polynomial(&[1, 0, 0, 2]).stride(), 1);
+        assert_eq!(dense_polynomial(&[1, 0, 3, 0, 0, 0, 2, 0]).stride(), 2);
+        assert_eq!(dense_polynomial(&[0, 0, 0, 0, 5, 0, 0, 0]).stride(), 4);
+        assert_eq!(dense_polynomial(&[7, 0, 0, 0]).stride(), 4);
+        assert_eq!(dense_polynomial(&[0]).stride(), 1);
+    }
+
+    #[test]
+    fn sparse_low_degree_extension() {
+        let blowup = 4;
+        for coefficients in &[
+            [3, 1, 0, 0, 0, 0, 0, 2],
+            [3, 0, 0, 0, 0, 0, 0, 0],
+            [3, 0, 0, 0, 1, 0, 0, 0],
+            [3, 0, 2, 0, 1, 0, 5, 0],
+        ] {
+            let p = dense_polynomial(coefficients);
+            let lde = p.low_degree_extension(blowup);
+            let generator = FieldElement::root(p.len() * blowup).unwrap();
+            for (i, value) in lde.iter().enumerate() {
+                let (coset, index) = (i / p.len(), i % p.len());
+                let x = FieldElement::GENERATOR
+                    * generator.pow(permute_index(blowup, coset))
+                    * generator.pow(blowup * permute_index(p.len(), index));
+                assert_eq!(value, &p.evaluate(&x));
+            }
+        }
+    }
 }
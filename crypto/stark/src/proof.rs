@@ -1,14 +1,87 @@
+use crate::{constraints::Constraints, proof_params::ProofParams};
 use std::prelude::v1::*;
+use zkp_hash::{Hasher, MaskedKeccak};
 
+/// A Stark proof.
+///
+/// The proof consists of the channel transcript and the identifier of the
+/// [`Hasher`] used for the channel and Merkle commitments. The serialized form
+/// from [`Proof::to_bytes`] starts with a one byte header containing the
+/// identifier, followed by the transcript.
 #[derive(Clone, Debug, PartialEq)]
-pub struct Proof(Vec<u8>);
+pub struct Proof {
+    hash_id:    u8,
+    transcript: Vec<u8>,
+}
 
 impl Proof {
+    /// A proof from a transcript using Keccak.
     pub fn from_bytes(bytes: Vec<u8>) -> Self {
-        Self(bytes)
+        Self::from_transcript::<MaskedKeccak>(bytes)
+    }
+
+    /// A proof from a transcript using the hash `H`.
+    pub fn from_transcript<H: Hasher>(transcript: Vec<u8>) -> Self {
+        Self {
+            hash_id: H::ID,
+            transcript,
+        }
+    }
+
+    /// Parse the serialized form produced by [`Proof::to_bytes`].
+    ///
+    /// Returns `None` if there is no header.
+    pub fn from_bytes_with_header(bytes: &[u8]) -> Option<Self> {
+        let (&hash_id, transcript) = bytes.split_first()?;
+        Some(Self {
+            hash_id,
+            transcript: transcript.to_vec(),
+        })
+    }
+
+    /// The identifier of the hash function used, see [`Hasher::ID`].
+    pub fn hash_id(&self) -> u8 {
+        self.hash_id
     }
 
+    /// The conjectured security in bits of the proof when verified against
+    /// `constraints`.
+    ///
+    /// This depends on the blowup, number of queries, proof of work
+    /// difficulty and the size of the field, see
+    /// [`ProofParams::conjectured_security`].
+    pub fn security_level(&self, constraints: &Constraints) -> usize {
+        ProofParams::from_constraints(constraints).conjectured_security(constraints.trace_nrows())
+    }
+
+    /// The channel transcript (without header).
     pub fn as_bytes(&self) -> &[u8] {
-        &self.0
+        &self.transcript
+    }
+
+    /// The serialized proof, header followed by transcript.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.transcript.len());
+        bytes.push(self.hash_id);
+        bytes.extend_from_slice(&self.transcript);
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkp_hash::Poseidon;
+
+    #[test]
+    fn header_roundtrip() {
+        let proof = Proof::from_transcript::<Poseidon>(vec![1, 2, 3]);
+        assert_eq!(proof.to_bytes(), vec![Poseidon::ID, 1, 2, 3]);
+        assert_eq!(
+            Proof::from_bytes_with_header(&proof.to_bytes()),
+            Some(proof)
+        );
+        assert_eq!(Proof::from_bytes_with_header(&[]), None);
+        assert_eq!(Proof::from_bytes(vec![]).hash_id(), MaskedKeccak::ID);
     }
 }
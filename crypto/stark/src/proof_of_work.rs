@@ -1,4 +1,6 @@
 #[cfg(all(feature = "std", feature = "prover"))]
+use crate::parallelism::is_sequential;
+#[cfg(all(feature = "std", feature = "prover"))]
 use log::info;
 #[cfg(all(feature = "std", feature = "prover"))]
 use rayon::prelude::*;
@@ -61,6 +63,10 @@ impl Challenge {
 impl Challenge {
     #[cfg(not(feature = "std"))]
     pub(crate) fn solve(&self) -> Response {
+        self.solve_sequential()
+    }
+
+    fn solve_sequential(&self) -> Response {
         // We assume a nonce exists and will be found in reasonable time.
         info!(
             "Solving {} bit proof of work single-threaded.",
@@ -75,6 +81,9 @@ impl Challenge {
 
     #[cfg(feature = "std")]
     pub(crate) fn solve(&self) -> Response {
+        if is_sequential() {
+            return self.solve_sequential();
+        }
         let num_threads = rayon::current_num_threads();
         info!(
             "Solving {} bit proof of work with {} threads.",
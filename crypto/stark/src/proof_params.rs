@@ -0,0 +1,266 @@
+use crate::constraints::Constraints;
+use std::{cmp::min, prelude::v1::*};
+
+/// Number of bits in the field modulus, rounded down.
+const FIELD_BITS: usize = 251;
+
+/// Maximal proof of work difficulty suggested by [`ProofParams::for_security`].
+const MAX_POW_BITS: usize = 20;
+
+/// Maximal blowup suggested by [`ProofParams::for_security`].
+const MAX_BLOWUP: usize = 64;
+
+/// Number of bytes in a hash or field element in the proof.
+const ELEMENT_SIZE: usize = 32;
+
+/// Number of bytes in the proof of work nonce.
+const NONCE_SIZE: usize = 8;
+
+/// The parameters of a proof that trade off proof size, prover time and
+/// security.
+///
+/// These are the tunable fields of [`Constraints`].
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ProofParams {
+    pub blowup:      usize,
+    pub pow_bits:    usize,
+    pub num_queries: usize,
+    pub fri_layout:  Vec<usize>,
+
+    /// Hide the trace, see [`Constraints::zero_knowledge`].
+    ///
+    /// [`Constraints::zero_knowledge`]: struct.Constraints.html#structfield.zero_knowledge
+    pub zero_knowledge: bool,
+}
+
+impl ProofParams {
+    pub fn from_constraints(constraints: &Constraints) -> Self {
+        Self {
+            blowup:         constraints.blowup,
+            pow_bits:       constraints.pow_bits,
+            num_queries:    constraints.num_queries,
+            fri_layout:     constraints.fri_layout.clone(),
+            zero_knowledge: constraints.zero_knowledge,
+        }
+    }
+
+    /// Set the parameters of `constraints`.
+    pub fn apply(&self, constraints: &mut Constraints) {
+        constraints.blowup = self.blowup;
+        constraints.pow_bits = self.pow_bits;
+        constraints.num_queries = self.num_queries;
+        constraints.fri_layout = self.fri_layout.clone();
+        constraints.zero_knowledge = self.zero_knowledge;
+    }
+
+    /// Conjectured security in bits for a trace of `trace_len` rows.
+    ///
+    /// Each query is conjectured to contribute $\log_2$ of the blowup in
+    /// bits, and proof of work adds its difficulty. The result is limited by
+    /// the size of the field relative to the evaluation domain.
+    pub fn conjectured_security(&self, trace_len: usize) -> usize {
+        min(
+            self.num_queries * log2(self.blowup) + self.pow_bits,
+            self.field_security(trace_len),
+        )
+    }
+
+    /// Provable security in bits for a trace of `trace_len` rows.
+    ///
+    /// Up to the Johnson bound each query contributes half of $\log_2$ of
+    /// the blowup in bits.
+    pub fn provable_security(&self, trace_len: usize) -> usize {
+        min(
+            self.num_queries * log2(self.blowup) / 2 + self.pow_bits,
+            self.field_security(trace_len),
+        )
+    }
+
+    /// Expected size in bytes of a proof for `constraints` on a trace of
+    /// `trace_len` rows.
+    ///
+    /// This counts commitments, out of domain values, the final FRI layer and
+    /// all decommitted values exactly. For the Merkle decommitments it
+    /// assumes the queries are spread out, so that paths only merge where
+    /// the layers have fewer nodes than there are queries.
+    pub fn estimate_size(&self, constraints: &Constraints, trace_len: usize) -> usize {
+        // Zero-knowledge proofs are about the blinded trace
+        let blinded;
+        let (constraints, trace_len) = if self.zero_knowledge {
+            blinded = constraints.blinded();
+            (&blinded, 2 * trace_len)
+        } else {
+            (constraints, trace_len)
+        };
+        let lde_depth = log2(trace_len * self.blowup);
+        let trace_columns = constraints.trace_ncolumns();
+        let external_columns = constraints.external_ncolumns();
+        let interaction_columns = constraints.interaction_ncolumns();
+        let columns = trace_columns + interaction_columns;
+        let constraint_polynomials = constraints.degree() + usize::from(self.zero_knowledge);
+        // A salt for each opened leaf
+        let salt = usize::from(self.zero_knowledge);
+        let queries = min(self.num_queries, 1 << lde_depth);
+
+        // Commitments, out of domain values and the final layer
+        let reductions: usize = self.fri_layout.iter().sum();
+        let mut elements = 2 + self.fri_layout.len();
+        elements += columns * constraints.trace_offsets().len() + constraint_polynomials;
+        elements += trace_len >> reductions;
+
+        // External, trace, interaction and constraint decommitments. The
+        // external root is part of the claim.
+        if external_columns > 0 {
+            elements += queries * external_columns + merkle_proof_size(lde_depth, queries);
+        }
+        elements += queries * (trace_columns - external_columns + salt)
+            + merkle_proof_size(lde_depth, queries);
+        if interaction_columns > 0 {
+            elements += 1;
+            elements +=
+                queries * (interaction_columns + salt) + merkle_proof_size(lde_depth, queries);
+        }
+        elements +=
+            queries * (constraint_polynomials + salt) + merkle_proof_size(lde_depth, queries);
+
+        // FRI decommitments. The values at the previous indices are known to
+        // the verifier.
+        let mut depth = lde_depth;
+        let mut indices = queries;
+        for &n_reductions in &self.fri_layout {
+            depth -= n_reductions;
+            let cosets = min(indices, 1 << depth);
+            elements += (cosets << n_reductions) - indices;
+            elements += merkle_proof_size(depth, cosets);
+            indices = cosets;
+        }
+
+        ELEMENT_SIZE * elements + NONCE_SIZE
+    }
+
+    /// Smallest parameters that achieve `bits` of conjectured security with
+    /// a proof of at most `max_size` bytes, if any.
+    ///
+    /// The search keeps the FRI layout of `constraints` and considers blowups
+    /// from 2 up to 64 and proof of work up to 20 bits. The blowup does not
+    /// need to cover the constraint degree, the prover evaluates constraints
+    /// on a separate domain.
+    pub fn for_security(constraints: &Constraints, bits: usize, max_size: usize) -> Option<Self> {
+        let trace_len = constraints.trace_nrows();
+        let mut best: Option<(usize, Self)> = None;
+        let mut blowup = 2;
+        while blowup <= MAX_BLOWUP {
+            for pow_bits in 0..=min(MAX_POW_BITS, bits) {
+                let bits_per_query = log2(blowup);
+                let num_queries = (bits - pow_bits + bits_per_query - 1) / bits_per_query;
+                let params = Self {
+                    blowup,
+                    pow_bits,
+                    num_queries,
+                    fri_layout: constraints.fri_layout.clone(),
+                    zero_knowledge: constraints.zero_knowledge,
+                };
+                if params.conjectured_security(trace_len) < bits {
+                    continue;
+                }
+                let size = params.estimate_size(constraints, trace_len);
+                if size <= max_size && best.as_ref().map_or(true, |(best, _)| size < *best) {
+                    best = Some((size, params));
+                }
+            }
+            blowup *= 2;
+        }
+        best.map(|(_, params)| params)
+    }
+
+    fn field_security(&self, trace_len: usize) -> usize {
+        FIELD_BITS.saturating_sub(log2(trace_len * self.blowup))
+    }
+}
+
+fn log2(value: usize) -> usize {
+    value.trailing_zeros() as usize
+}
+
+// Number of sibling hashes in a multiproof for `queries` spread out leaves in
+// a tree of depth `depth`.
+fn merkle_proof_size(depth: usize, queries: usize) -> usize {
+    let occupied = |height: usize| min(queries, 1 << (depth - height));
+    (0..depth)
+        .map(|height| 2 * occupied(height + 1) - occupied(height))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{traits::tests::fib_claim, Verifiable};
+
+    fn constraints() -> Constraints {
+        let claim = fib_claim();
+        claim.constraints()
+    }
+
+    #[test]
+    fn merkle_proof_size_test() {
+        assert_eq!(merkle_proof_size(3, 1), 3);
+        assert_eq!(merkle_proof_size(3, 8), 0);
+        assert_eq!(merkle_proof_size(10, 4), 4 * 8);
+    }
+
+    #[test]
+    fn security_test() {
+        let mut params = ProofParams::from_constraints(&constraints());
+        params.blowup = 16;
+        params.num_queries = 20;
+        params.pow_bits = 12;
+        assert_eq!(params.conjectured_security(1024), 92);
+        assert_eq!(params.provable_security(1024), 52);
+        params.num_queries = 100;
+        assert_eq!(params.conjectured_security(1024), FIELD_BITS - 14);
+    }
+
+    #[test]
+    fn for_security_test() {
+        let constraints = constraints();
+        let trace_len = constraints.trace_nrows();
+        let params = ProofParams::for_security(&constraints, 80, 1_000_000).unwrap();
+        assert!(params.conjectured_security(trace_len) >= 80);
+        let size = params.estimate_size(&constraints, trace_len);
+        for blowup in &[2, 4, 8, 16, 32] {
+            let mut other = params.clone();
+            other.blowup = *blowup;
+            other.num_queries = (80 - other.pow_bits + log2(*blowup) - 1) / log2(*blowup);
+            assert!(other.estimate_size(&constraints, trace_len) >= size);
+        }
+        assert_eq!(ProofParams::for_security(&constraints, 80, 1000), None);
+    }
+
+    #[cfg(feature = "prover")]
+    #[test]
+    fn estimate_size_test() {
+        use crate::{prove, traits::tests::fib_witness, Provable};
+
+        let claim = fib_claim();
+        let witness = fib_witness();
+        let mut constraints = claim.constraints();
+        constraints.pow_bits = 0;
+        let params = ProofParams::from_constraints(&constraints);
+        let actual = prove(&constraints, &claim.trace(&witness))
+            .unwrap()
+            .as_bytes()
+            .len();
+        let estimate = params.estimate_size(&constraints, constraints.trace_nrows());
+        assert!(estimate * 10 >= actual * 9 && estimate * 10 <= actual * 11);
+
+        constraints.zero_knowledge = true;
+        let params = ProofParams::from_constraints(&constraints);
+        let actual = prove(&constraints, &claim.trace(&witness))
+            .unwrap()
+            .as_bytes()
+            .len();
+        let estimate = params.estimate_size(&constraints, constraints.trace_nrows());
+        assert!(estimate * 10 >= actual * 9 && estimate * 10 <= actual * 11);
+    }
+}
@@ -0,0 +1,366 @@
+use crate::{
+    channel::{HashChain, Label, RandomGenerator, Replayable, VerifierChannel},
+    constraints::Constraints,
+    proof_of_work,
+    proof_params::ProofParams,
+    verifier::{get_indices, Error},
+    Proof,
+};
+use std::{fmt, prelude::v1::*};
+#[cfg(feature = "pedersen")]
+use zkp_elliptic_curve_crypto::Pedersen;
+#[cfg(feature = "blake3")]
+use zkp_hash::Blake3;
+use zkp_hash::{Blake2s, Hash, Hasher, MaskedKeccak, Poseidon, Rescue};
+use zkp_merkle_tree::Commitment;
+use zkp_primefield::FieldElement;
+
+/// Number of bytes in a hash or field element in the proof.
+const ELEMENT_SIZE: usize = 32;
+
+/// Number of bytes in the proof of work nonce.
+const NONCE_SIZE: usize = 8;
+
+/// Size in bytes of the decommitment of a Merkle tree.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct DecommitmentStats {
+    /// The revealed leaf values, and their salts in zero-knowledge proofs.
+    pub values: usize,
+    /// The Merkle multiproof.
+    pub hashes: usize,
+}
+
+/// Breakdown of the size of a proof.
+///
+/// All sizes are in bytes of the transcript, so they add up to the length of
+/// [`Proof::as_bytes`].
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ProofStats {
+    pub hash_id:                  u8,
+    pub params:                   ProofParams,
+    pub size:                     usize,
+    /// Merkle roots of the trace, constraint and FRI layer commitments.
+    pub commitments:              usize,
+    /// Out of domain values of the trace and constraint polynomials.
+    pub oods_values:              usize,
+    /// Coefficients of the final FRI layer.
+    pub last_layer:               usize,
+    pub pow_nonce:                usize,
+    /// Zero without an external commitment.
+    pub external_decommitment:    DecommitmentStats,
+    pub trace_decommitment:       DecommitmentStats,
+    /// Zero without an interaction trace.
+    pub interaction_decommitment: DecommitmentStats,
+    pub constraint_decommitment:  DecommitmentStats,
+    pub fri_decommitments:        Vec<DecommitmentStats>,
+    /// The sorted query indices in the evaluation domain.
+    pub queries:                  Vec<usize>,
+}
+
+impl DecommitmentStats {
+    pub fn total(&self) -> usize {
+        self.values + self.hashes
+    }
+}
+
+impl ProofStats {
+    /// Statistics of `proof` for the claim described by `constraints`.
+    ///
+    /// The layout of the proof depends on the claim, so the constraints are
+    /// required. The query indices are recomputed from the transcript, which
+    /// is otherwise not checked.
+    pub fn from_proof(proof: &Proof, constraints: &Constraints) -> Result<Self, Error> {
+        match proof.hash_id() {
+            MaskedKeccak::ID => Self::from_proof_with::<MaskedKeccak>(proof, constraints),
+            Poseidon::ID => Self::from_proof_with::<Poseidon>(proof, constraints),
+            Blake2s::ID => Self::from_proof_with::<Blake2s>(proof, constraints),
+            #[cfg(feature = "blake3")]
+            Blake3::ID => Self::from_proof_with::<Blake3>(proof, constraints),
+            Rescue::ID => Self::from_proof_with::<Rescue>(proof, constraints),
+            #[cfg(feature = "pedersen")]
+            Pedersen::ID => Self::from_proof_with::<Pedersen>(proof, constraints),
+            _ => Err(Error::UnsupportedHash),
+        }
+    }
+
+    fn from_proof_with<H: Hasher>(proof: &Proof, constraints: &Constraints) -> Result<Self, Error> {
+        // Zero-knowledge proofs are about the blinded trace
+        let blinded;
+        let constraints = if constraints.zero_knowledge {
+            blinded = constraints.blinded();
+            &blinded
+        } else {
+            constraints
+        };
+        let eval_domain_size = constraints.trace_nrows() * constraints.blowup;
+        let interaction_cols = constraints.interaction_ncolumns();
+        let trace_values_len =
+            constraints.trace_offsets().len() * (constraints.trace_ncolumns() + interaction_cols);
+        let constraint_cols = constraints.constraint_ncolumns();
+        let salt = usize::from(constraints.zero_knowledge);
+        let mut fri_sizes = Vec::with_capacity(constraints.fri_layout.len());
+        let mut fri_size = eval_domain_size;
+        for &reductions in &constraints.fri_layout {
+            fri_size >>= reductions;
+            fri_sizes.push(fri_size);
+        }
+
+        let interaction_commitments = if interaction_cols > 0 { 1 } else { 0 };
+        let commitments =
+            ELEMENT_SIZE * (2 + interaction_commitments + constraints.fri_layout.len());
+        let oods_values = ELEMENT_SIZE * (trace_values_len + constraint_cols);
+        let last_layer = ELEMENT_SIZE * (fri_size / constraints.blowup);
+        if proof.as_bytes().len() < commitments + oods_values + last_layer + NONCE_SIZE {
+            return Err(Error::ProofTooLong);
+        }
+
+        // Replay the transcript up to the queries, following the verifier.
+        let mut channel = VerifierChannel::<HashChain<H>>::new(proof.as_bytes().to_vec());
+        channel.coin.layout = constraints.channel_layout;
+        channel.initialize(constraints.channel_seed());
+        channel.label(Label::TraceCommitment);
+        let lde_root: Hash = channel.replay();
+        if constraints.num_challenges() > 0 {
+            channel.label(Label::Challenges);
+            for _ in 0..constraints.num_challenges() {
+                let _: FieldElement = channel.get_random();
+            }
+        }
+        let interaction_root: Option<Hash> = if interaction_cols > 0 {
+            channel.label(Label::InteractionCommitment);
+            Some(channel.replay())
+        } else {
+            None
+        };
+        channel.label(Label::ConstraintCoefficients);
+        for _ in 0..2 * constraints.len() {
+            let _: FieldElement = channel.get_random();
+        }
+        channel.label(Label::ConstraintCommitment);
+        let constraint_root: Hash = channel.replay();
+        channel.label(Label::OodsPoint);
+        let _: FieldElement = channel.get_random();
+        channel.label(Label::OodsValues);
+        for _ in 0..trace_values_len + constraint_cols {
+            let _: FieldElement = channel.replay();
+        }
+        channel.label(Label::OodsCoefficients);
+        for _ in 0..trace_values_len + constraint_cols {
+            let _: FieldElement = channel.get_random();
+        }
+        channel.label(Label::FriLayer(0));
+        let mut fri_roots: Vec<Hash> = vec![channel.replay()];
+        for (layer, &reductions) in constraints.fri_layout.iter().enumerate().skip(1) {
+            if reductions != 0 {
+                let _: FieldElement = channel.get_random();
+            }
+            channel.label(Label::FriLayer(layer));
+            fri_roots.push(channel.replay());
+        }
+        let _: FieldElement = channel.get_random();
+        channel.label(Label::FriLastLayer);
+        let _: Vec<FieldElement> = channel.replay_many(fri_size / constraints.blowup);
+        channel.label(Label::ProofOfWork);
+        let _: proof_of_work::ChallengeSeed = channel.get_random();
+        let _: proof_of_work::Response = channel.replay();
+        channel.label(Label::QueryIndices);
+        let queries = get_indices(
+            constraints.num_queries,
+            eval_domain_size.trailing_zeros(),
+            &mut channel,
+        );
+
+        let external_cols = constraints.external_ncolumns();
+        let external_decommitment = match constraints.external_commitment() {
+            Some(external) => {
+                DecommitmentStats {
+                    values: ELEMENT_SIZE * queries.len() * external_cols,
+                    hashes: ELEMENT_SIZE
+                        * Commitment::from_size_hash(eval_domain_size, external.root())?
+                            .proof_size(&queries)?,
+                }
+            }
+            None => DecommitmentStats::default(),
+        };
+        let trace_decommitment = DecommitmentStats {
+            values: ELEMENT_SIZE
+                * queries.len()
+                * (constraints.trace_ncolumns() - external_cols + salt),
+            hashes: ELEMENT_SIZE
+                * Commitment::from_size_hash(eval_domain_size, &lde_root)?.proof_size(&queries)?,
+        };
+        let interaction_decommitment = match &interaction_root {
+            Some(root) => {
+                DecommitmentStats {
+                    values: ELEMENT_SIZE * queries.len() * (interaction_cols + salt),
+                    hashes: ELEMENT_SIZE
+                        * Commitment::from_size_hash(eval_domain_size, root)?
+                            .proof_size(&queries)?,
+                }
+            }
+            None => DecommitmentStats::default(),
+        };
+        let constraint_decommitment = DecommitmentStats {
+            values: ELEMENT_SIZE * queries.len() * (constraint_cols + salt),
+            hashes: ELEMENT_SIZE
+                * Commitment::from_size_hash(eval_domain_size, &constraint_root)?
+                    .proof_size(&queries)?,
+        };
+
+        // Values at indices of the previous layer are computed by the verifier
+        let mut fri_decommitments = Vec::with_capacity(fri_roots.len());
+        let mut previous_indices = queries.clone();
+        for ((&reductions, size), root) in
+            constraints.fri_layout.iter().zip(fri_sizes).zip(&fri_roots)
+        {
+            let coset_size = 1_usize << reductions;
+            let mut indices: Vec<usize> = previous_indices.iter().map(|i| i / coset_size).collect();
+            indices.dedup();
+            let revealed = indices
+                .iter()
+                .flat_map(|i| i * coset_size..(i + 1) * coset_size)
+                .filter(|n| previous_indices.binary_search(n).is_err())
+                .count();
+            fri_decommitments.push(DecommitmentStats {
+                values: ELEMENT_SIZE * revealed,
+                hashes: ELEMENT_SIZE
+                    * Commitment::from_size_hash(size, root)?.proof_size(&indices)?,
+            });
+            previous_indices = indices;
+        }
+
+        let stats = Self {
+            hash_id: proof.hash_id(),
+            params: ProofParams::from_constraints(constraints),
+            size: proof.as_bytes().len(),
+            commitments,
+            oods_values,
+            last_layer,
+            pow_nonce: NONCE_SIZE,
+            external_decommitment,
+            trace_decommitment,
+            interaction_decommitment,
+            constraint_decommitment,
+            fri_decommitments,
+            queries,
+        };
+        if stats.accounted() != stats.size {
+            return Err(Error::ProofTooLong);
+        }
+        Ok(stats)
+    }
+
+    /// The total size of all decommitments.
+    pub fn decommitments(&self) -> usize {
+        self.external_decommitment.total()
+            + self.trace_decommitment.total()
+            + self.interaction_decommitment.total()
+            + self.constraint_decommitment.total()
+            + self
+                .fri_decommitments
+                .iter()
+                .map(DecommitmentStats::total)
+                .sum::<usize>()
+    }
+
+    fn accounted(&self) -> usize {
+        self.commitments
+            + self.oods_values
+            + self.last_layer
+            + self.pow_nonce
+            + self.decommitments()
+    }
+}
+
+// Percentages do not need full precision
+#[allow(clippy::cast_precision_loss)]
+impl fmt::Display for ProofStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |part: usize| 100.0 * part as f64 / self.size as f64;
+        let mut line = |name: &str, part: usize| {
+            writeln!(f, "{:<24}{:>10} {:>6.1}%", name, part, percent(part))
+        };
+        line("commitments", self.commitments)?;
+        line("oods values", self.oods_values)?;
+        line("last layer", self.last_layer)?;
+        line("proof of work", self.pow_nonce)?;
+        if self.external_decommitment.total() > 0 {
+            line("external values", self.external_decommitment.values)?;
+            line("external hashes", self.external_decommitment.hashes)?;
+        }
+        line("trace values", self.trace_decommitment.values)?;
+        line("trace hashes", self.trace_decommitment.hashes)?;
+        if self.interaction_decommitment.total() > 0 {
+            line("interaction values", self.interaction_decommitment.values)?;
+            line("interaction hashes", self.interaction_decommitment.hashes)?;
+        }
+        line("constraint values", self.constraint_decommitment.values)?;
+        line("constraint hashes", self.constraint_decommitment.hashes)?;
+        for (i, layer) in self.fri_decommitments.iter().enumerate() {
+            line(&format!("fri layer {} values", i), layer.values)?;
+            line(&format!("fri layer {} hashes", i), layer.hashes)?;
+        }
+        write!(
+            f,
+            "total {} bytes, blowup {}, {} queries, {} bits of proof of work, fri layout {:?}",
+            self.size,
+            self.params.blowup,
+            self.params.num_queries,
+            self.params.pow_bits,
+            self.params.fri_layout
+        )
+    }
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prove, prove_with,
+        traits::tests::{fib_claim, fib_witness},
+        Provable, Verifiable,
+    };
+
+    #[test]
+    fn proof_stats_test() {
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let proof = prove(&constraints, &claim.trace(&fib_witness())).unwrap();
+        let stats = ProofStats::from_proof(&proof, &constraints).unwrap();
+        assert_eq!(stats.size, proof.as_bytes().len());
+        // Duplicate query indices are removed
+        assert!(stats.queries.len() <= constraints.num_queries);
+        assert!(stats.queries.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(stats.fri_decommitments.len(), constraints.fri_layout.len());
+        assert_eq!(stats.pow_nonce, 8);
+        assert_eq!(
+            stats.trace_decommitment.values,
+            32 * constraints.num_queries * constraints.trace_ncolumns()
+        );
+        let estimate = stats
+            .params
+            .estimate_size(&constraints, constraints.trace_nrows());
+        assert!(estimate * 10 >= stats.size * 9 && estimate * 10 <= stats.size * 11);
+    }
+
+    #[test]
+    fn proof_stats_hash_test() {
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let proof = prove_with::<Poseidon>(&constraints, &claim.trace(&fib_witness())).unwrap();
+        let stats = ProofStats::from_proof(&proof, &constraints).unwrap();
+        assert_eq!(stats.hash_id, Poseidon::ID);
+        assert_eq!(stats.size, proof.as_bytes().len());
+
+        let mut truncated = proof.to_bytes();
+        let _ = truncated.pop();
+        let truncated = Proof::from_bytes_with_header(&truncated).unwrap();
+        assert_eq!(
+            ProofStats::from_proof(&truncated, &constraints),
+            Err(Error::ProofTooLong)
+        );
+    }
+}
@@ -1,19 +1,32 @@
 use crate::{
     algebraic_dag::AlgebraicGraph,
-    channel::{ProverChannel, RandomGenerator, Writable},
+    backend::{self, Backend, CpuBackend},
+    channel::{HashChain, Label, ProverChannel, RandomGenerator, Transcript, Writable},
     constraints::Constraints,
+    fri::FriProver,
+    metrics::Phases,
+    parallelism::is_sequential,
     polynomial::DensePolynomial,
-    proof_of_work, verify, Proof, TraceTable, VerifierError,
+    proof_of_work,
+    prover_callback::{ProverCallback, ProverEvent},
+    prover_options::ProverOptions,
+    trace_provider::{self, TraceProvider},
+    transcript_logger::TranscriptLogger,
+    verifier::{get_indices, verify_with_transcript},
+    Proof, TraceLayout, TraceTable, VerifierError,
 };
-use itertools::Itertools;
 use log::info;
+use rand::Rng;
 use rayon::prelude::*;
 use std::{fmt, prelude::v1::*, vec};
-use zkp_hash::{Hash, Hashable, MaskedKeccak};
-use zkp_merkle_tree::{Error as MerkleError, Tree, VectorCommitment};
-use zkp_mmap_vec::MmapVec;
+use zkp_hash::{Hash, Hashable, Hasher, MaskedKeccak};
+use zkp_merkle_tree::{
+    Commitment, CommitmentScheme, Error as MerkleError, MerkleScheme, Salted, SaltedVec, Tree,
+    VectorCommitment,
+};
+use zkp_mmap_vec::{MmapVec, Storage};
 use zkp_primefield::{
-    fft::{ifft_permuted, permute, permute_index},
+    fft::{fft, ifft_permuted, permute, permute_index},
     geometric_series::geometric_series,
     FieldElement,
 };
@@ -24,8 +37,27 @@ type Result<T> = std::result::Result<T, Error>;
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Error {
     RootUnavailable,
+    InvalidTraceLength,
+    TraceLengthMismatch,
+    TraceWidthMismatch,
+    /// Constraint `constraint` does not hold on trace table row `row`.
+    ConstraintUnsatisfied {
+        constraint: usize,
+        row:        usize,
+    },
+    /// The combined constraint polynomial is not of the expected degree, but
+    /// no individual violated constraint could be located.
+    ConstraintDegreeMismatch,
     MerkleFailed(MerkleError),
     VerificationFailed(VerifierError),
+    /// The proof was cancelled through `ProverCallback::is_cancelled`.
+    Cancelled,
+    /// The FRI layout can not fold the evaluation domain, see
+    /// `FriParams::layout`.
+    InvalidFriParams,
+    /// The leading trace columns do not match the external commitment, see
+    /// `Constraints::add_external_commitment`.
+    ExternalCommitmentMismatch,
 }
 
 impl fmt::Display for Error {
@@ -33,8 +65,45 @@ impl fmt::Display for Error {
         use Error::*;
         match *self {
             RootUnavailable => write!(f, "The prime field doesn't have a root of this order"),
+            InvalidTraceLength => write!(f, "Invalid trace length (must be power of two)"),
+            TraceLengthMismatch => {
+                write!(
+                    f,
+                    "The trace table length doesn't match the constraint system"
+                )
+            }
+            TraceWidthMismatch => {
+                write!(
+                    f,
+                    "The number of trace table columns doesn't match the constraint system"
+                )
+            }
+            ConstraintUnsatisfied { constraint, row } => {
+                write!(
+                    f,
+                    "Constraint {} is not satisfied on row {}",
+                    constraint, row
+                )
+            }
+            ConstraintDegreeMismatch => {
+                write!(
+                    f,
+                    "The combined constraint polynomial has a higher degree than expected"
+                )
+            }
             MerkleFailed(ref e) => std::fmt::Display::fmt(e, f),
             VerificationFailed(ref e) => std::fmt::Display::fmt(e, f),
+            Cancelled => write!(f, "The proof was cancelled"),
+            InvalidFriParams => {
+                write!(
+                    f,
+                    "The FRI layout values must be between one and four and fold at most the \
+                     evaluation domain"
+                )
+            }
+            ExternalCommitmentMismatch => {
+                write!(f, "The trace doesn't match the external commitment")
+            }
         }
     }
 }
@@ -73,65 +142,192 @@ impl VectorCommitment for PolyLDE {
         ret
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        if self.0.len() == 1 {
-            // For a single element, return its hash.
-            self.0[0][index].hash()
-        } else {
-            // Concatenate the element hashes and hash the result.
-            let mut hasher = MaskedKeccak::new();
-            for value in &self.0 {
-                hasher.update(value[index].hash().as_bytes());
-            }
-            hasher.hash()
-        }
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        backend::leaf_hash::<H>(&self.0, index)
     }
 }
 
+/// LDE with leaf hashes computed by a [`Backend`].
 #[derive(Clone, Debug)]
-struct FriLeaves {
-    coset_size: usize,
-    layer:      MmapVec<FieldElement>,
+struct HashedLDE {
+    lde:    PolyLDE,
+    hashes: Vec<Hash>,
 }
 
-type FriTree = Tree<FriLeaves>;
+impl HashedLDE {
+    fn new<H: Hasher, B: Backend>(
+        backend: &B,
+        polynomials: &[DensePolynomial],
+        blowup: usize,
+        storage: Storage,
+    ) -> Self {
+        let lde = PolyLDE(backend.low_degree_extensions(polynomials, blowup, storage));
+        let hashes = backend.leaf_hashes::<H>(&lde.0);
+        Self { lde, hashes }
+    }
+}
 
-// Merkle tree for FRI layers with coset size
-impl VectorCommitment for FriLeaves {
+impl VectorCommitment for HashedLDE {
     type Leaf = Vec<U256>;
 
     fn len(&self) -> usize {
-        debug_assert_eq!(self.layer.len() % self.coset_size, 0);
-        self.layer.len() / self.coset_size
+        self.lde.len()
     }
 
     fn leaf(&self, index: usize) -> Self::Leaf {
-        let mut internal_leaf = Vec::with_capacity(self.coset_size);
-        for j in 0..self.coset_size {
-            internal_leaf.push(
-                self.layer[(index * self.coset_size + j)]
-                    .as_montgomery()
-                    .clone(),
-            );
+        self.lde.leaf(index)
+    }
+
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        self.hashes[index].clone()
+    }
+}
+
+/// The leaves of an LDE commitment, salted in zero-knowledge proofs.
+#[derive(Debug)]
+enum LdeLeaves {
+    Plain(HashedLDE),
+    Salted(SaltedVec<HashedLDE>),
+}
+
+impl LdeLeaves {
+    fn lde(&self) -> &PolyLDE {
+        match self {
+            Self::Plain(lde) => &lde.lde,
+            Self::Salted(salted) => &salted.inner().lde,
         }
-        internal_leaf
     }
 
-    fn leaf_hash(&self, index: usize) -> Hash {
-        if self.coset_size == 1 {
-            // For a single element, return its hash.
-            self.layer[index].hash()
-        } else {
-            // Concatenate the element hashes and hash the result.
-            let mut hasher = MaskedKeccak::new();
-            for j in 0..self.coset_size {
-                hasher.update(self.layer[(index * self.coset_size + j)].hash().as_bytes());
-            }
-            hasher.hash()
+    fn salt(&self, index: usize) -> Option<&Hash> {
+        match self {
+            Self::Plain(_) => None,
+            Self::Salted(salted) => Some(&salted.salts()[index]),
+        }
+    }
+}
+
+/// A leaf of [`LdeLeaves`].
+enum LdeLeaf {
+    Plain(Vec<U256>),
+    Salted(Salted<Vec<U256>>),
+}
+
+impl Hashable for LdeLeaf {
+    fn hash(&self) -> Hash {
+        self.hash_with::<MaskedKeccak>()
+    }
+
+    fn hash_with<H: Hasher>(&self) -> Hash {
+        match self {
+            Self::Plain(leaf) => leaf.hash_with::<H>(),
+            Self::Salted(leaf) => leaf.hash_with::<H>(),
+        }
+    }
+}
+
+impl VectorCommitment for LdeLeaves {
+    type Leaf = LdeLeaf;
+
+    fn len(&self) -> usize {
+        self.lde().len()
+    }
+
+    fn leaf(&self, index: usize) -> Self::Leaf {
+        match self {
+            Self::Plain(lde) => LdeLeaf::Plain(lde.leaf(index)),
+            Self::Salted(salted) => LdeLeaf::Salted(salted.leaf(index)),
+        }
+    }
+
+    fn leaf_hash<H: Hasher>(&self, index: usize) -> Hash {
+        match self {
+            Self::Plain(lde) => lde.leaf_hash::<H>(index),
+            Self::Salted(salted) => salted.leaf_hash::<H>(index),
         }
     }
 }
 
+/// Commitment scheme for the trace and constraint LDEs.
+///
+/// The prover opens the commitments through the [`CommitmentScheme`]
+/// interface, so the scheme can be swapped out here together with
+/// [`commit_lde`]. The channel and verifier still expect Merkle commitments.
+type LdeCommitment<H> = MerkleScheme<LdeLeaves, H>;
+
+/// Commit to `lde` with the Merkle tree layers kept as selected by `storage`.
+///
+/// With `salted` every leaf gets a random salt, see [`SaltedVec`].
+fn commit_lde<H: Hasher>(
+    lde: HashedLDE,
+    salted: bool,
+    storage: Storage,
+) -> Result<(
+    Commitment,
+    <LdeCommitment<H> as CommitmentScheme>::Decommitment,
+)> {
+    let leaves = if salted {
+        LdeLeaves::Salted(SaltedVec::from_seed::<H>(lde, &rand::thread_rng().gen()))
+    } else {
+        LdeLeaves::Plain(lde)
+    };
+    let tree = Tree::<_, H>::from_leaves_in(leaves, 1, storage)?;
+    Ok((tree.commitment().clone(), tree))
+}
+
+/// The commitment a proof with `blowup` makes to trace columns with
+/// polynomials `polynomials`.
+pub(crate) fn lde_commitment<H: Hasher>(
+    polynomials: &[DensePolynomial],
+    blowup: usize,
+) -> Result<Commitment> {
+    let storage = ProverOptions::default().storage;
+    let lde = HashedLDE::new::<H, _>(&CpuBackend, polynomials, blowup, storage);
+    let (commitment, _) = commit_lde::<H>(lde, false, storage)?;
+    Ok(commitment)
+}
+
+/// Write the values of the leaves at `indices`, then their salts if they
+/// are salted, then the Merkle proof.
+fn decommit_lde<H: Hasher, T: Transcript>(
+    proof: &mut ProverChannel<T>,
+    tree: &<LdeCommitment<H> as CommitmentScheme>::Decommitment,
+    indices: &[usize],
+) -> Result<()> {
+    for &index in indices {
+        proof.write(tree.leaves().lde().leaf(index));
+    }
+    for salt in indices
+        .iter()
+        .filter_map(|&index| tree.leaves().salt(index))
+    {
+        proof.write(salt);
+    }
+    proof.write(&LdeCommitment::<H>::batch_open(tree, indices)?);
+    Ok(())
+}
+
+/// Blind polynomials of degree less than `n` as `P + (X^n - 1) R` with `R`
+/// random of degree less than `n`, see [`Constraints::blinded`].
+fn blind(polynomials: &[DensePolynomial]) -> Vec<DensePolynomial> {
+    let mut rng = rand::thread_rng();
+    polynomials
+        .iter()
+        .map(|polynomial| {
+            let random: Vec<FieldElement> = (0..polynomial.len()).map(|_| rng.gen()).collect();
+            let mut coefficients = Vec::with_capacity(2 * polynomial.len());
+            coefficients.extend(
+                polynomial
+                    .coefficients()
+                    .iter()
+                    .zip(&random)
+                    .map(|(coefficient, random)| coefficient - random),
+            );
+            coefficients.extend(random);
+            DensePolynomial::new(&coefficients)
+        })
+        .collect()
+}
+
 // False positives on the Latex math.
 #[allow(clippy::doc_markdown)]
 /// # Produce a Stark proof.
@@ -173,6 +369,14 @@ impl VectorCommitment for FriLeaves {
 ///
 /// <!-- TODO: The indices should be bit-reversed. -->
 ///
+/// If the constraints have [running products], the challenges are drawn next
+/// and the prover computes the interaction trace from the trace and the
+/// challenges. It is extended and committed in the same way. From here on
+/// the interaction columns are treated as trace columns following the
+/// others, with their own commitment and decommitments.
+///
+/// [running products]: struct.Constraints.html#method.add_running_product
+///
 /// ### Step 2: Constraint commitment
 ///
 /// For each constraint, two random value $\alpha_i$ and $\beta_i$ are drawn
@@ -308,6 +512,7 @@ impl VectorCommitment for FriLeaves {
 /// $$
 ///
 /// A merkle proof is provided linking these values to the earlier commitment.
+/// The values of the interaction trace follow with their own merkle proof.
 ///
 /// Similarly, the combined constraint polynomial values are written to the
 /// channel:
@@ -340,39 +545,270 @@ impl VectorCommitment for FriLeaves {
 ///
 /// with merkle proofs to that layer. This process is repeated for all FRI layer
 /// commitments.
-// TODO: Simplify
-#[allow(clippy::cognitive_complexity)]
-// TODO: Split up
-#[allow(clippy::too_many_lines)]
+///
+/// ## Randomness
+///
+/// All random values above are drawn from the channel, which is seeded with
+/// the claim and the prover's messages, so they are determined by the claim
+/// and the trace. The proof of work solution is the smallest valid nonce,
+/// independent of the number of threads used to find it. No other step of
+/// the prover uses randomness, so the resulting proof is reproducible.
+///
+/// ## Zero knowledge
+///
+/// Unless [`Constraints::zero_knowledge`] is set, the proof reveals trace
+/// values at the queries and the out of domain point. With it the prover
+/// commits to a blinded trace of twice the rows, adds a random polynomial to
+/// the constraint commitment and salts all leaves of the trace and
+/// constraint commitments. The salts follow the values of the opened leaves.
+/// The random values come from `rand::thread_rng`, so zero-knowledge proofs
+/// are not reproducible.
+///
+/// [`Constraints::zero_knowledge`]: struct.Constraints.html#structfield.zero_knowledge
 pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
+    prove_with::<MaskedKeccak>(constraints, trace)
+}
+
+/// Produce a Stark proof with Merkle commitments hashed with `H`.
+///
+/// The proof verifies with [`verify_with`] using the same `H`. See [`prove`]
+/// for details.
+///
+/// [`verify_with`]: fn.verify_with.html
+pub fn prove_with<H: Hasher>(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
+    prove_with_transcript::<H, HashChain<H>>(constraints, trace)
+}
+
+/// Produce a Stark proof with hash `H`, drawing the Fiat-Shamir randomness
+/// from the transcript `T`.
+///
+/// The proof verifies with [`verify_with_transcript`] using the same `H` and
+/// `T`. See [`Transcript`].
+///
+/// [`Transcript`]: trait.Transcript.html
+pub fn prove_with_transcript<H: Hasher, T: Transcript>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+) -> Result<Proof> {
+    prove_channel::<H, T, _, _>(
+        constraints,
+        trace,
+        &ProverOptions::default(),
+        &CpuBackend,
+        &(),
+        &mut ProverChannel::<T>::new(),
+    )
+}
+
+/// Produce a Stark proof with hash `H` using the prover settings in
+/// `options`.
+///
+/// The options do not affect the proof, which is the same as for
+/// [`prove_with`] unless it is zero-knowledge.
+pub fn prove_with_options<H: Hasher>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    options: &ProverOptions,
+) -> Result<Proof> {
+    options.parallelism.install(|| {
+        prove_channel::<H, _, _, _>(
+            constraints,
+            trace,
+            options,
+            &CpuBackend,
+            &(),
+            &mut ProverChannel::<HashChain<H>>::new(),
+        )
+    })
+}
+
+/// Produce a Stark proof with hash `H`, running the low degree extensions and
+/// leaf hashing on `backend`.
+///
+/// The proof is the same as for [`prove_with`], provided the backend computes
+/// the same results as [`CpuBackend`].
+#[cfg(feature = "gpu")]
+pub fn prove_with_backend<H: Hasher, B: Backend>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    backend: &B,
+) -> Result<Proof> {
+    prove_channel::<H, _, _, _>(
+        constraints,
+        trace,
+        &ProverOptions::default(),
+        backend,
+        &(),
+        &mut ProverChannel::<HashChain<H>>::new(),
+    )
+}
+
+/// Produce a Stark proof with hash `H`, reporting progress to `callback`.
+///
+/// Returns `Error::Cancelled` if the callback cancels the proof.
+pub fn prove_with_callback<H: Hasher>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    callback: &dyn ProverCallback,
+) -> Result<Proof> {
+    prove_channel::<H, _, _, _>(
+        constraints,
+        trace,
+        &ProverOptions::default(),
+        &CpuBackend,
+        callback,
+        &mut ProverChannel::<HashChain<H>>::new(),
+    )
+}
+
+/// Produce a Stark proof with hash `H` and append all channel interactions to
+/// `logger`.
+pub fn prove_logged<H: Hasher>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    logger: &mut TranscriptLogger,
+) -> Result<Proof> {
+    let mut channel = ProverChannel::<HashChain<H>>::new();
+    channel.coin.logger = Some(std::mem::take(logger));
+    let result = prove_channel::<H, _, _, _>(
+        constraints,
+        trace,
+        &ProverOptions::default(),
+        &CpuBackend,
+        &(),
+        &mut channel,
+    );
+    *logger = channel.coin.logger.take().unwrap_or_default();
+    result
+}
+
+/// Produce a Stark proof with hash `H` for a trace that is generated while
+/// proving.
+///
+/// The proof is the same as for [`prove_with`] on a [`TraceTable`] with the
+/// rows of `provider`, see [`TraceProvider`]. Since the trace is not kept,
+/// a trace that does not satisfy the constraints results in
+/// `Error::VerificationFailed` instead of `Error::ConstraintUnsatisfied`.
+///
+/// [`TraceTable`]: struct.TraceTable.html
+/// [`TraceProvider`]: trait.TraceProvider.html
+pub fn prove_streaming<H: Hasher, P: TraceProvider + Send>(
+    constraints: &Constraints,
+    provider: &mut P,
+) -> Result<Proof> {
+    check_dimensions(constraints, provider.num_rows(), provider.num_columns())?;
+    let mut phases = Phases::prove();
+    notify(&(), &mut phases, ProverEvent::TraceLde)?;
+    let trace_polynomials = trace_provider::interpolate(provider);
+    prove_polynomials::<H, _, _, _>(
+        constraints,
+        trace_polynomials,
+        &ProverOptions::default(),
+        &CpuBackend,
+        &(),
+        &mut phases,
+        &mut ProverChannel::<HashChain<H>>::new(),
+    )
+}
+
+fn check_dimensions(constraints: &Constraints, num_rows: usize, num_columns: usize) -> Result<()> {
     // TODO: Verify input
-    //  * Constraint trace length matches trace table length
     //  * Fri layout is less than trace length * blowup
     //  * Trace(_, _) items in constraint are valid.
-    //  * Trace table satisfies constraints (expensive check, should be optional)
+    if !num_rows.is_power_of_two() {
+        return Err(Error::InvalidTraceLength);
+    }
+    if num_rows != constraints.trace_nrows() {
+        return Err(Error::TraceLengthMismatch);
+    }
+    if num_columns != constraints.trace_ncolumns() {
+        return Err(Error::TraceWidthMismatch);
+    }
+    Ok(())
+}
+
+fn prove_channel<H: Hasher, T: Transcript, B: Backend, C: ProverCallback + ?Sized>(
+    constraints: &Constraints,
+    trace: &TraceTable,
+    options: &ProverOptions,
+    backend: &B,
+    callback: &C,
+    proof: &mut ProverChannel<T>,
+) -> Result<Proof> {
+    check_dimensions(constraints, trace.num_rows(), trace.num_columns())?;
+    let mut phases = Phases::prove();
+    notify(callback, &mut phases, ProverEvent::TraceLde)?;
+    let trace_polynomials = trace.interpolate();
+    prove_polynomials::<H, T, B, C>(
+        constraints,
+        trace_polynomials,
+        options,
+        backend,
+        callback,
+        &mut phases,
+        proof,
+    )
+    .map_err(|err| {
+        match err {
+            Error::ConstraintDegreeMismatch | Error::VerificationFailed(_) => {
+                unsatisfied_error(constraints, trace, err)
+            }
+            err => err,
+        }
+    })
+}
 
+// TODO: Simplify
+#[allow(clippy::cognitive_complexity)]
+// TODO: Split up
+#[allow(clippy::too_many_lines)]
+fn prove_polynomials<H: Hasher, T: Transcript, B: Backend, C: ProverCallback + ?Sized>(
+    constraints: &Constraints,
+    mut trace_polynomials: Vec<DensePolynomial>,
+    options: &ProverOptions,
+    backend: &B,
+    callback: &C,
+    phases: &mut Phases,
+    proof: &mut ProverChannel<T>,
+) -> Result<Proof> {
+    // Zero-knowledge proofs are about the blinded trace
+    let original = constraints;
+    let blinded;
+    let constraints = if original.zero_knowledge {
+        blinded = original.blinded();
+        &blinded
+    } else {
+        original
+    };
+    let zero_knowledge = constraints.zero_knowledge;
+    let unblinded = if zero_knowledge {
+        let blinded = blind(&trace_polynomials);
+        Some(std::mem::replace(&mut trace_polynomials, blinded))
+    } else {
+        None
+    };
+    let trace_length = constraints.trace_nrows();
     info!("Starting Stark proof.");
     info!("Proof constraints: {:?}", constraints);
     // TODO: Use a proper size human formating function
     #[allow(clippy::cast_precision_loss)]
-    let size_mb = (trace.num_rows() * trace.num_columns() * 32) as f64 / 1_000_000_f64;
+    let size_mb = (trace_length * trace_polynomials.len() * 32) as f64 / 1_000_000_f64;
     info!(
         "Trace table {} rows {} columns ({} MB)",
-        trace.num_rows(),
-        trace.num_columns(),
+        trace_length,
+        trace_polynomials.len(),
         size_mb
     );
     info!("{} constraints", constraints.len(),);
 
     info!("Initialize channel with claim.");
-    let mut proof = ProverChannel::new();
+    proof.coin.layout = constraints.channel_layout;
     proof.initialize(constraints.channel_seed());
 
     // 1. Trace commitment.
 
     // Compute the low degree extension of the trace table.
     info!("Compute the low degree extension of the trace table.");
-    let trace_polynomials = trace.interpolate();
     info!(
         "Trace degrees: {:?}",
         trace_polynomials
@@ -380,23 +816,67 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
             .map(DensePolynomial::degree)
             .collect::<Vec<_>>()
     );
-    let trace_lde = PolyLDE(
-        trace_polynomials
-            .par_iter()
-            .map(|p| p.low_degree_extension(constraints.blowup))
-            .collect::<Vec<_>>(),
-    );
+    let storage = options.storage;
+    let (external_polynomials, own_polynomials) =
+        trace_polynomials.split_at(constraints.external_ncolumns());
+    let e_tree = match constraints.external_commitment() {
+        Some(external) => {
+            info!("Open the externally committed columns.");
+            let lde =
+                HashedLDE::new::<H, _>(backend, external_polynomials, constraints.blowup, storage);
+            let (commitment, e_tree) = commit_lde::<H>(lde, false, storage)?;
+            if commitment.hash() != external.root() {
+                return Err(Error::ExternalCommitmentMismatch);
+            }
+            Some(e_tree)
+        }
+        None => None,
+    };
+    let trace_lde = HashedLDE::new::<H, _>(backend, own_polynomials, constraints.blowup, storage);
 
     // Construct a merkle tree over the LDE trace
     // and write the root to the channel.
     info!("Construct a merkle tree over the LDE trace and write the root to the channel.");
-    let (commitment, tree) = trace_lde.commit()?;
+    let (commitment, tree) = commit_lde::<H>(trace_lde, zero_knowledge, storage)?;
+    proof.label(Label::TraceCommitment);
     proof.write(&commitment);
 
+    // Draw the challenges and commit to the interaction trace. The
+    // interaction polynomials follow the trace polynomials from here on.
+    let mut challenges = Vec::with_capacity(constraints.num_challenges());
+    if constraints.num_challenges() > 0 {
+        proof.label(Label::Challenges);
+        for _ in 0..constraints.num_challenges() {
+            challenges.push(proof.get_random());
+        }
+    }
+    let i_tree = if constraints.interaction_ncolumns() > 0 {
+        info!("Compute the interaction trace and write its root to the channel.");
+        let polynomials = interaction_polynomials(
+            original,
+            unblinded.as_ref().unwrap_or(&trace_polynomials),
+            &challenges,
+        );
+        let polynomials = if zero_knowledge {
+            blind(&polynomials)
+        } else {
+            polynomials
+        };
+        let lde = HashedLDE::new::<H, _>(backend, &polynomials, constraints.blowup, storage);
+        let (commitment, i_tree) = commit_lde::<H>(lde, zero_knowledge, storage)?;
+        proof.label(Label::InteractionCommitment);
+        proof.write(&commitment);
+        trace_polynomials.extend(polynomials);
+        Some(i_tree)
+    } else {
+        None
+    };
+
     // 2. Constraint commitment
 
     // Read constraint coefficients from the channel.
     info!("Read constraint coefficients from the channel.");
+    proof.label(Label::ConstraintCoefficients);
     let mut constraint_coefficients = Vec::with_capacity(2 * constraints.len());
     for _ in 0..constraints.len() {
         constraint_coefficients.push(proof.get_random());
@@ -404,12 +884,26 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
     }
 
     info!("Compute constraint polynomials.");
-    let constraint_polynomials = get_constraint_polynomials(
-        &tree.leaves(),
+    notify(callback, phases, ProverEvent::ConstraintEvaluation)?;
+    let mut trace_ldes: Vec<_> = e_tree.iter().map(|e_tree| e_tree.leaves().lde()).collect();
+    trace_ldes.push(tree.leaves().lde());
+    trace_ldes.extend(i_tree.iter().map(|i_tree| i_tree.leaves().lde()));
+    let trace_coset = constraint_trace_coset(
+        backend,
+        &trace_ldes,
+        &trace_polynomials,
+        constraints,
+        storage,
+    );
+    let mut constraint_polynomials = get_constraint_polynomials(
+        &trace_coset,
         &constraints,
         &constraint_coefficients,
-        trace.num_rows(),
-    );
+        &challenges,
+        trace_length,
+        storage,
+    )
+    .ok_or(Error::ConstraintDegreeMismatch)?;
     info!(
         "Constraint degrees: {:?}",
         constraint_polynomials
@@ -417,40 +911,59 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
             .map(DensePolynomial::degree)
             .collect::<Vec<_>>()
     );
+    if zero_knowledge {
+        // A random mask makes the out of domain values and the FRI
+        // polynomial independent of the trace.
+        let mut rng = rand::thread_rng();
+        let mask: Vec<FieldElement> = (0..trace_length).map(|_| rng.gen()).collect();
+        constraint_polynomials.push(DensePolynomial::new(&mask));
+    }
 
     // OPT: It may be faster to compute the constraint LDE from the trace LDE,
     // instead of using an FFT.
     info!("Compute the low degree extension of constraint polynomials.");
-    let constraint_lde = PolyLDE(
-        constraint_polynomials
-            .par_iter()
-            .map(|p| p.low_degree_extension(constraints.blowup))
-            .collect::<Vec<_>>(),
+    notify(callback, phases, ProverEvent::ConstraintLde)?;
+    let constraint_lde = HashedLDE::new::<H, _>(
+        backend,
+        &constraint_polynomials,
+        constraints.blowup,
+        storage,
     );
     // Construct a merkle tree over the LDE combined constraints
     // and write the root to the channel.
     info!("Compute the merkle tree over the LDE constraint polynomials.");
-    let (commitment, c_tree) = constraint_lde.commit()?;
+    let (commitment, c_tree) = commit_lde::<H>(constraint_lde, zero_knowledge, storage)?;
+    proof.label(Label::ConstraintCommitment);
     proof.write(&commitment);
 
     // 3. Out of domain sampling
     info!("Divide out OODS point and combine polynomials.");
-    let oods_polynomial = oods_combine(&mut proof, &trace_polynomials, &constraint_polynomials);
+    notify(callback, phases, ProverEvent::Oods)?;
+    let oods_polynomial = oods_combine(
+        proof,
+        &trace_polynomials,
+        constraints,
+        &constraint_polynomials,
+    );
     info!("Oods poly degree: {}", oods_polynomial.degree());
 
     // 4. FRI layers with trees
     info!("LDE extension of final polynomial.");
-    let first_fri_layer = oods_polynomial.low_degree_extension(constraints.blowup);
+    let first_fri_layer = oods_polynomial.low_degree_extension_in(constraints.blowup, storage);
     info!("Fri layers.");
-    let fri_trees = perform_fri_layering(
+    let fri = FriProver::<H>::commit_with_callback(
         first_fri_layer,
-        &mut proof,
-        &constraints.fri_layout,
-        constraints.blowup,
+        &constraints.fri_params(),
+        storage,
+        proof,
+        callback,
+        phases,
     )?;
 
     // 5. Proof of work
     info!("Proof of work.");
+    notify(callback, phases, ProverEvent::ProofOfWork)?;
+    proof.label(Label::ProofOfWork);
     let pow_seed: proof_of_work::ChallengeSeed = proof.get_random();
     let pow_challenge = pow_seed.with_difficulty(constraints.pow_bits);
     let pow_response = pow_challenge.solve();
@@ -462,77 +975,150 @@ pub fn prove(constraints: &Constraints, trace: &TraceTable) -> Result<Proof> {
 
     // Fetch query indices from channel.
     info!("Fetch query indices from channel.");
-    let eval_domain_size = trace.num_rows() * constraints.blowup;
+    notify(callback, phases, ProverEvent::Queries)?;
+    let eval_domain_size = trace_length * constraints.blowup;
+    proof.label(Label::QueryIndices);
     let query_indices = get_indices(
         constraints.num_queries,
         64 - eval_domain_size.leading_zeros() - 1,
-        &mut proof,
+        proof,
     );
     info!("Query indices: {:?}", query_indices);
 
     // Decommit the trace table values.
     info!("Decommit the trace table values.");
-    for &index in &query_indices {
-        proof.write(tree.leaf(index));
+    proof.label(Label::Decommitments);
+    if let Some(e_tree) = &e_tree {
+        decommit_lde::<H, _>(proof, e_tree, &query_indices)?;
+    }
+    decommit_lde::<H, _>(proof, &tree, &query_indices)?;
+    if let Some(i_tree) = &i_tree {
+        decommit_lde::<H, _>(proof, i_tree, &query_indices)?;
     }
-    proof.write(&tree.open(&query_indices)?);
 
     // Decommit the constraint values
     info!("Decommit the constraint values.");
-    for &index in &query_indices {
-        proof.write(c_tree.leaf(index));
-    }
-    proof.write(&c_tree.open(&query_indices)?);
+    decommit_lde::<H, _>(proof, &c_tree, &query_indices)?;
 
     // Decommit the FRI layer values
     info!("Decommit the FRI layer values.");
-    decommit_fri_layers_and_trees(fri_trees.as_slice(), query_indices.as_slice(), &mut proof)?;
+    fri.decommit(&query_indices, proof)?;
 
     // Verify proof
     info!("Verify proof.");
+    notify(callback, phases, ProverEvent::Verify)?;
     // TODO: Rename channel / transcript object
-    let proof = Proof::from_bytes(proof.proof);
-    verify(constraints, &proof)?;
+    let proof = Proof::from_transcript::<H>(std::mem::take(&mut proof.proof));
+    verify_with_transcript::<H, T>(original, &proof).map_err(Error::VerificationFailed)?;
     Ok(proof)
 }
 
-fn extract_trace_coset(trace_lde: &PolyLDE, size: usize) -> TraceTable {
-    let trace_lde: &[MmapVec<FieldElement>] = &trace_lde.0;
+/// Report `event` unless the proof is cancelled, and start its phase.
+pub(crate) fn notify<C: ProverCallback + ?Sized>(
+    callback: &C,
+    phases: &mut Phases,
+    event: ProverEvent,
+) -> Result<()> {
+    if callback.is_cancelled() {
+        return Err(Error::Cancelled);
+    }
+    callback.on_event(event);
+    let layer = match event {
+        ProverEvent::FriLayer { layer, .. } => Some(layer),
+        _ => None,
+    };
+    phases.start(event.name(), layer);
+    Ok(())
+}
+
+/// Diagnose a failed proof.
+///
+/// The most likely reason for a proof to fail is a trace table that does not
+/// satisfy the constraints. Since checking this is expensive, it is only done
+/// after a failure. If no unsatisfied constraint is found, `fallback` is
+/// returned.
+fn unsatisfied_error(constraints: &Constraints, trace: &TraceTable, fallback: Error) -> Error {
+    match constraints.check_trace(trace) {
+        Ok(()) => fallback,
+        Err(violations) => {
+            Error::ConstraintUnsatisfied {
+                constraint: violations[0].constraint,
+                row:        violations[0].row,
+            }
+        }
+    }
+}
+
+/// The trace on the domain the constraints are evaluated on.
+///
+/// The constraints are evaluated on the smallest extension of the trace
+/// domain that fits their degree, independent of the blowup of the
+/// commitments. If this domain is part of the trace LDE we take it from
+/// there, otherwise we extend the trace polynomials separately. This way the
+/// blowup only needs to be large enough for FRI, not for the constraints.
+///
+/// The columns of `trace_ldes` follow each other, like the trace and the
+/// interaction trace.
+fn constraint_trace_coset<B: Backend>(
+    backend: &B,
+    trace_ldes: &[&PolyLDE],
+    trace_polynomials: &[DensePolynomial],
+    constraints: &Constraints,
+    storage: Storage,
+) -> TraceTable {
+    let eval_degree = constraints.degree().next_power_of_two();
+    let coset_size = constraints.trace_nrows() * eval_degree;
+    if eval_degree <= constraints.blowup {
+        let columns: Vec<_> = trace_ldes.iter().flat_map(|lde| &lde.0).collect();
+        extract_trace_coset(&columns, coset_size, storage)
+    } else {
+        info!("Extend the trace for constraint evaluation.");
+        let lde = backend.low_degree_extensions(trace_polynomials, eval_degree, storage);
+        extract_trace_coset(&lde.iter().collect::<Vec<_>>(), coset_size, storage)
+    }
+}
+
+fn extract_trace_coset(
+    trace_lde: &[&MmapVec<FieldElement>],
+    size: usize,
+    storage: Storage,
+) -> TraceTable {
     let lde_size = trace_lde[0].len();
-    let mut trace_coset = TraceTable::new(size, trace_lde.len());
-    // OPT: Benchmark with flipped order of loops
-    for i in 0..trace_coset.num_rows() {
-        for j in 0..trace_coset.num_columns() {
-            let lde = &trace_lde[j];
+    let mut trace_coset =
+        TraceTable::with_layout_in(size, trace_lde.len(), TraceLayout::ColumnMajor, storage);
+    let extract = |(column, lde): (&mut [FieldElement], &&MmapVec<FieldElement>)| {
+        for (i, value) in column.iter_mut().enumerate() {
             let index = i * lde_size / size;
             let index = permute_index(lde.len(), index);
-            trace_coset[(i, j)] = lde[index].clone();
+            *value = lde[index].clone();
+        }
+    };
+    if is_sequential() {
+        for (j, lde) in trace_lde.iter().enumerate() {
+            extract((trace_coset.column_mut(j), lde));
         }
+    } else {
+        trace_coset
+            .par_columns_mut()
+            .zip(trace_lde.par_iter())
+            .for_each(extract);
     }
     trace_coset
 }
 
-fn get_indices(num: usize, bits: u32, proof: &mut ProverChannel) -> Vec<usize> {
-    let mut query_indices = Vec::with_capacity(num + 3);
-    while query_indices.len() < num {
-        let val: U256 = proof.get_random();
-        let mask = 2_usize.pow(bits) - 1;
-        query_indices.push((val.clone() >> (0x100 - 0x040)).as_usize() & mask);
-        query_indices.push((val.clone() >> (0x100 - 0x080)).as_usize() & mask);
-        query_indices.push((val.clone() >> (0x100 - 0x0C0)).as_usize() & mask);
-        query_indices.push(val.as_usize() & mask);
-    }
-    query_indices.truncate(num);
-    (&mut query_indices).sort_unstable();
-    query_indices
-}
-
+/// Compute the combined constraint polynomials.
+///
+/// Returns `None` if the combined constraint polynomial is not of the expected
+/// degree, which happens when the trace table does not satisfy the
+/// constraints.
 fn get_constraint_polynomials(
-    trace_lde: &PolyLDE,
+    trace_coset: &TraceTable,
     constraints: &Constraints,
     constraint_coefficients: &[FieldElement],
+    challenges: &[FieldElement],
     trace_length: usize,
-) -> Vec<DensePolynomial> {
+    storage: Storage,
+) -> Option<Vec<DensePolynomial>> {
     // OPT: Better parallelization strategies. Probably the best would be to
     // split to domain up in smaller cosets and solve their expressions
     // independently. This will make all periods and therefore lookup tables
@@ -543,12 +1129,10 @@ fn get_constraint_polynomials(
     let constraint_degree = constraints.degree();
     let eval_degree = constraint_degree.next_power_of_two();
     let coset_size = trace_length * eval_degree;
-
-    info!("Compute offset trace table");
-    let trace_coset = extract_trace_coset(trace_lde, coset_size);
+    assert_eq!(trace_coset.num_rows(), coset_size);
 
     info!("Combine rational expressions");
-    let combined_constraints = constraints.combine(constraint_coefficients);
+    let combined_constraints = constraints.combine(constraint_coefficients, challenges);
     let mut dag = AlgebraicGraph::new(
         &FieldElement::GENERATOR,
         trace_coset.num_rows(),
@@ -558,25 +1142,32 @@ fn get_constraint_polynomials(
     dag.lookup_tables();
     // TODO: Track and use result reference.
     let _ = dag.tree_shake(result);
+    #[cfg(feature = "codegen")]
+    let mut dag = dag.compile();
     dag.init(0);
 
     // Evaluate on the coset trace table
     info!("Evaluate on the coset trace table");
-    let mut result: MmapVec<FieldElement> = MmapVec::with_capacity(coset_size);
+    let mut result: MmapVec<FieldElement> = MmapVec::with_capacity_in(coset_size, storage);
     result.resize(coset_size, FieldElement::ZERO);
     let values = &mut result;
-    values
-        .par_chunks_mut(CHUNK_SIZE)
-        .enumerate()
-        .for_each(|(mut i, chunk)| {
-            i *= CHUNK_SIZE;
-            let mut dag = dag.clone();
-            dag.init(i);
-            for value in chunk {
-                *value = dag.next(&trace_coset);
-                i += 1;
-            }
-        });
+    let evaluate = |(mut i, chunk): (usize, &mut [FieldElement])| {
+        i *= CHUNK_SIZE;
+        let mut dag = dag.clone();
+        dag.init(i);
+        for value in chunk {
+            *value = dag.next(&trace_coset);
+            i += 1;
+        }
+    };
+    if is_sequential() {
+        values.chunks_mut(CHUNK_SIZE).enumerate().for_each(evaluate);
+    } else {
+        values
+            .par_chunks_mut(CHUNK_SIZE)
+            .enumerate()
+            .for_each(evaluate);
+    }
 
     info!("Convert from values to coefficients");
     ifft_permuted(values);
@@ -591,68 +1182,100 @@ fn get_constraint_polynomials(
 
     // Convert to even and odd coefficient polynomials
     let mut constraint_polynomials: Vec<MmapVec<FieldElement>> =
-        vec![MmapVec::with_capacity(trace_length); constraint_degree];
+        vec![MmapVec::with_capacity_in(trace_length, storage); constraint_degree];
     let (coefficients, zeros) = values.split_at(constraint_degree * trace_length);
-    assert!(zeros.iter().all(|z| z == &FieldElement::ZERO));
+    if !zeros.iter().all(|z| z == &FieldElement::ZERO) {
+        return None;
+    }
     for chunk in coefficients.chunks_exact(constraint_degree) {
         for (i, coefficient) in chunk.iter().enumerate() {
             constraint_polynomials[i].push(coefficient.clone());
         }
     }
-    constraint_polynomials
-        .into_iter()
-        .map(DensePolynomial::from_mmap_vec)
-        .collect()
+    Some(
+        constraint_polynomials
+            .into_iter()
+            .map(DensePolynomial::from_mmap_vec)
+            .collect(),
+    )
 }
 
-fn oods_combine(
-    proof: &mut ProverChannel,
+/// Interpolate the interaction trace of the running products on the trace
+/// with the given challenges.
+fn interaction_polynomials(
+    constraints: &Constraints,
+    trace_polynomials: &[DensePolynomial],
+    challenges: &[FieldElement],
+) -> Vec<DensePolynomial> {
+    let trace_length = constraints.trace_nrows();
+    let mut trace = TraceTable::with_layout(
+        trace_length,
+        trace_polynomials.len(),
+        TraceLayout::ColumnMajor,
+    );
+    for (j, polynomial) in trace_polynomials.iter().enumerate() {
+        trace
+            .column_mut(j)
+            .clone_from_slice(&fft(polynomial.coefficients()));
+    }
+    let mut interaction = TraceTable::with_layout(
+        trace_length,
+        constraints.interaction_ncolumns(),
+        TraceLayout::ColumnMajor,
+    );
+    for (j, product) in constraints.running_products().iter().enumerate() {
+        interaction
+            .column_mut(j)
+            .clone_from_slice(&product.values(&trace, challenges));
+    }
+    interaction.interpolate()
+}
+
+fn oods_combine<T: Transcript>(
+    proof: &mut ProverChannel<T>,
     trace_polynomials: &[DensePolynomial],
+    constraints: &Constraints,
     constraint_polynomials: &[DensePolynomial],
 ) -> DensePolynomial {
     // Fetch the oods sampling point
     let trace_length = trace_polynomials[0].len();
+    proof.label(Label::OodsPoint);
     let oods_point: FieldElement = proof.get_random();
-    let g = FieldElement::root(trace_length).expect("No root for trace polynomial length.");
-    let oods_point_g = &oods_point * &g;
-    let oods_point_pow = oods_point.pow(constraint_polynomials.len());
+    let oods_points = constraints.trace_offset_points(&oods_point);
+    let oods_point_pow = oods_point.pow(constraints.degree());
 
     // Write point evaluations to proof
     // OPT: Parallelization
+    proof.label(Label::OodsValues);
     for trace_polynomial in trace_polynomials {
-        proof.write(&trace_polynomial.evaluate(&oods_point));
-        proof.write(&trace_polynomial.evaluate(&oods_point_g));
+        for point in &oods_points {
+            proof.write(&trace_polynomial.evaluate(point));
+        }
     }
     for constraint_polynomial in constraint_polynomials {
         proof.write(&constraint_polynomial.evaluate(&oods_point_pow));
     }
 
     // Read coefficients
-    let n_coefficients = 2 * trace_polynomials.len() + constraint_polynomials.len();
+    let n_coefficients = oods_points.len() * trace_polynomials.len() + constraint_polynomials.len();
     let mut oods_coefficients: Vec<FieldElement> = Vec::with_capacity(n_coefficients);
+    proof.label(Label::OodsCoefficients);
     for _ in 0..n_coefficients {
         oods_coefficients.push(proof.get_random());
     }
     let (trace_coefficients, constraint_coefficients) =
-        oods_coefficients.split_at(2 * trace_polynomials.len());
+        oods_coefficients.split_at(oods_points.len() * trace_polynomials.len());
 
     // Divide out points and linear sum the polynomials
     // OPT: Parallelization
     let mut combined_polynomial = DensePolynomial::zeros(trace_length);
-    for (trace_polynomial, (coefficient_0, coefficient_1)) in trace_polynomials
+    for (trace_polynomial, coefficients) in trace_polynomials
         .iter()
-        .zip(trace_coefficients.iter().tuples())
+        .zip(trace_coefficients.chunks(oods_points.len()))
     {
-        trace_polynomial.divide_out_point_into(
-            &oods_point,
-            coefficient_0,
-            &mut combined_polynomial,
-        );
-        trace_polynomial.divide_out_point_into(
-            &oods_point_g,
-            coefficient_1,
-            &mut combined_polynomial,
-        );
+        for (point, coefficient) in oods_points.iter().zip(coefficients) {
+            trace_polynomial.divide_out_point_into(point, coefficient, &mut combined_polynomial);
+        }
     }
     for (constraint_polynomial, coefficient) in constraint_polynomials
         .iter()
@@ -667,170 +1290,20 @@ fn oods_combine(
     combined_polynomial
 }
 
-fn perform_fri_layering(
-    first_layer: MmapVec<FieldElement>,
-    proof: &mut ProverChannel,
-    fri_layout: &[usize],
-    blowup: usize,
-) -> Result<Vec<FriTree>> {
-    let mut fri_trees: Vec<FriTree> = Vec::with_capacity(fri_layout.len());
-
-    // Compute 1/x for the fri layer. We only compute the even coordinates.
-    // OPT: Can these be efficiently computed on the fly?
-    let x_inv = {
-        let n = first_layer.len();
-        let root_inv = FieldElement::root(n)
-            .ok_or(Error::RootUnavailable)?
-            .inv()
-            .unwrap();
-        let mut x_inv = MmapVec::with_capacity(n / 2);
-        let mut accumulator = FieldElement::ONE;
-        for _ in 0..n / 2 {
-            x_inv.push(accumulator.clone());
-            accumulator *= &root_inv;
-        }
-        permute(&mut x_inv);
-        x_inv
-    };
-
-    let mut next_layer = first_layer;
-    for &n_reductions in fri_layout {
-        // Allocate next and swap ownership
-        let mut layer = MmapVec::with_capacity(next_layer.len() / (1 << n_reductions));
-        std::mem::swap(&mut layer, &mut next_layer);
-
-        // Create tree from layer
-        // FRI layout values are small.
-        #[allow(clippy::cast_possible_truncation)]
-        let coset_size = 2_usize.pow(n_reductions as u32);
-        let tree = FriTree::from_leaves(FriLeaves { coset_size, layer })?;
-        fri_trees.push(tree);
-        let tree = fri_trees.last().unwrap();
-        let layer = &tree.leaves().layer;
-
-        // Write commitment and pull coefficient
-        proof.write(tree.commitment());
-        let coefficient = proof.get_random();
-
-        // Fold layer up to three times
-        // TODO: Capture the pattern in a macro and DRY.
-        // OPT: Parallelization
-        // OPT: The structure in x_inv should allow faster methods,
-        // like in a radix-4 and radix-8 fft.
-        let layer = layer.iter();
-        match n_reductions {
-            1 => {
-                next_layer.extend(
-                    layer
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1)),
-                )
-            }
-            2 => {
-                let coefficient_2 = coefficient.pow(2);
-                next_layer.extend(
-                    layer
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1)),
-                )
-            }
-            3 => {
-                let coefficient_2 = coefficient.square();
-                let coefficient_4 = coefficient_2.square();
-                next_layer.extend(
-                    layer
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1))
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_4 * x_inv * (p0 - p1)),
-                )
-            }
-            // TODO: Is there a use case for 4 layer folds?
-            4 => {
-                let coefficient_2 = coefficient.square();
-                let coefficient_4 = coefficient_2.square();
-                let coefficient_8 = coefficient_4.square();
-                next_layer.extend(
-                    layer
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (p0 + p1) + &coefficient * x_inv * (p0 - p1))
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_2 * x_inv * (p0 - p1))
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_4 * x_inv * (p0 - p1))
-                        .tuples()
-                        .zip(x_inv.iter())
-                        .map(|((p0, p1), x_inv)| (&p0 + &p1) + &coefficient_8 * x_inv * (p0 - p1)),
-                )
-            }
-            _ => unimplemented!(),
-        };
-    }
-
-    // Write the final layer coefficients
-    let n_coefficients = next_layer.len() / blowup;
-    let points = &mut next_layer[0..n_coefficients];
-    permute(points);
-    ifft_permuted(points);
-    permute(points);
-    proof.write(&*points);
-
-    Ok(fri_trees)
-}
-
-fn decommit_fri_layers_and_trees(
-    fri_trees: &[FriTree],
-    query_indices: &[usize],
-    proof: &mut ProverChannel,
-) -> Result<()> {
-    let mut previous_indices: Vec<usize> = query_indices.to_vec();
-
-    for tree in fri_trees {
-        let coset_size = tree.leaves().coset_size;
-
-        let new_indices: Vec<usize> = previous_indices
-            .iter()
-            .map(|x| x / coset_size)
-            .dedup()
-            .collect();
-
-        for i in &new_indices {
-            // TODO: Write entire tree.leaf(i)
-            for j in 0..coset_size {
-                let n = i * coset_size + j;
-                match previous_indices.binary_search(&n) {
-                    Ok(_) => (),
-                    _ => proof.write(&tree.leaves().layer[n]),
-                };
-            }
-        }
-        proof.write(&tree.open(&new_indices)?);
-        previous_indices = new_indices;
-    }
-    Ok(())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        traits::tests::{Claim, Witness},
-        verify, Provable, Verifiable,
+        channel::tests::Ratchet,
+        traits::tests::{fib_claim, fib_witness, Claim, Witness},
+        verify, verify_logged, verify_with, ChannelLayout, CounterLayout, FieldEncoding,
+        Parallelism, ProofStats, Provable, Verifiable,
     };
     use tiny_keccak::sha3_256;
+    use zkp_elliptic_curve_crypto::Pedersen;
+    #[cfg(feature = "blake3")]
+    use zkp_hash::Blake3;
+    use zkp_hash::Poseidon;
     use zkp_macros_decl::{field_element, hex, u256h};
     use zkp_primefield::{fft::permute_index, geometric_series::geometric_series};
     use zkp_u256::U256;
@@ -881,15 +1354,8 @@ mod tests {
 
     #[test]
     fn fib_test_1024_python_witness() {
-        let witness = Witness {
-            secret: field_element!("cafebabe"),
-        };
-        let claim = Claim {
-            index: 1000,
-            value: field_element!(
-                "0142c45e5d743d10eae7ebb70f1526c65de7dbcdb65b322b6ddc36a812591e8f"
-            ),
-        };
+        let witness = fib_witness();
+        let claim = fib_claim();
 
         let mut constraints = claim.constraints();
         let trace = claim.trace(&witness);
@@ -952,6 +1418,331 @@ mod tests {
         verify(&constraints, &actual).unwrap();
     }
 
+    #[test]
+    fn fib_test_poseidon() {
+        let secret = field_element!("0f00dbabe0cafebabe");
+        let (value, _) = (0..100).fold((FieldElement::ONE, secret.clone()), |(a, b), _| {
+            let next = a + &b;
+            (b, next)
+        });
+        let witness = Witness { secret };
+        let claim = Claim { index: 100, value };
+
+        let mut constraints = claim.constraints();
+        constraints.blowup = 16;
+        constraints.pow_bits = 0;
+        constraints.num_queries = 20;
+        constraints.fri_layout = vec![3, 2];
+        let trace = claim.trace(&witness);
+        let actual = prove_with::<Poseidon>(&constraints, &trace).unwrap();
+        assert_eq!(actual.hash_id(), Poseidon::ID);
+        verify_with::<Poseidon>(&constraints, &actual).unwrap();
+        verify(&constraints, &actual).unwrap();
+        assert_eq!(
+            verify_with::<MaskedKeccak>(&constraints, &actual),
+            Err(VerifierError::HashMismatch)
+        );
+        let relabeled = Proof::from_transcript::<MaskedKeccak>(actual.as_bytes().to_vec());
+        assert_eq!(
+            verify(&constraints, &relabeled),
+            Err(VerifierError::InvalidLDECommitment)
+        );
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn fib_test_blake3() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&witness);
+        let actual = prove_with::<Blake3>(&constraints, &trace).unwrap();
+        assert_eq!(actual.hash_id(), Blake3::ID);
+        verify(&constraints, &actual).unwrap();
+        assert_eq!(
+            verify_with::<Poseidon>(&constraints, &actual),
+            Err(VerifierError::HashMismatch)
+        );
+    }
+
+    #[test]
+    fn fib_test_storage() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&witness);
+        let expected = prove(&constraints, &trace).unwrap();
+        for &storage in &[Storage::MEMORY, Storage::with_threshold(1 << 12)] {
+            let options = ProverOptions::default().with_storage(storage);
+            let actual = prove_with_options::<MaskedKeccak>(&constraints, &trace, &options);
+            assert_eq!(actual.unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn fib_test_pedersen() {
+        let secret = field_element!("cafebabe");
+        let (value, _) = (0..10).fold((FieldElement::ONE, secret.clone()), |(a, b), _| {
+            let next = a + &b;
+            (b, next)
+        });
+        let witness = Witness { secret };
+        let claim = Claim { index: 10, value };
+
+        let mut constraints = claim.constraints();
+        constraints.blowup = 16;
+        constraints.pow_bits = 0;
+        constraints.num_queries = 20;
+        constraints.fri_layout = vec![2];
+        let trace = claim.trace(&witness);
+        let actual = prove_with::<Pedersen>(&constraints, &trace).unwrap();
+        assert_eq!(actual.hash_id(), Pedersen::ID);
+        verify(&constraints, &actual).unwrap();
+    }
+
+    #[test]
+    fn fib_test_security_level() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let mut constraints = claim.constraints();
+        constraints.blowup = 16;
+        constraints.pow_bits = 0;
+        constraints.num_queries = 20;
+        let actual = prove(&constraints, &claim.trace(&witness)).unwrap();
+        assert_eq!(actual.security_level(&constraints), 80);
+
+        constraints.min_security_bits = 100;
+        assert_eq!(
+            verify(&constraints, &actual),
+            Err(VerifierError::InsufficientSecurity)
+        );
+    }
+
+    #[test]
+    fn fib_test_deterministic() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&witness);
+        let prove_on = |threads| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| prove(&constraints, &trace).unwrap())
+        };
+        let proof = prove_on(1);
+        assert_eq!(prove_on(4), proof);
+        assert!(verify(&constraints, &proof).is_ok());
+
+        let options = ProverOptions::default().with_parallelism(Parallelism::Sequential);
+        assert_eq!(
+            prove_with_options::<MaskedKeccak>(&constraints, &trace, &options).unwrap(),
+            proof
+        );
+    }
+
+    #[test]
+    fn fib_test_channel_layout() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let trace = claim.trace(&witness);
+        let mut constraints = claim.constraints();
+        assert_eq!(constraints.channel_layout, ChannelLayout::STARKWARE);
+        let default = prove(&constraints, &trace).unwrap();
+
+        constraints.channel_layout = ChannelLayout {
+            counter:        CounterLayout::Compact,
+            field_encoding: FieldEncoding::Standard,
+            labels:         false,
+        };
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_ne!(proof, default);
+        assert!(verify(&constraints, &proof).is_ok());
+        assert!(verify(&constraints, &default).is_err());
+
+        constraints.channel_layout = ChannelLayout::LABELED;
+        let labeled = prove(&constraints, &trace).unwrap();
+        assert_ne!(labeled, default);
+        assert!(verify(&constraints, &labeled).is_ok());
+        assert!(verify(&constraints, &default).is_err());
+        let stats = ProofStats::from_proof(&labeled, &constraints).unwrap();
+        assert_eq!(stats.size, labeled.as_bytes().len());
+    }
+
+    // Fibonacci rows computed as they are requested
+    struct FibonacciRows {
+        next:   [FieldElement; 2],
+        length: usize,
+    }
+
+    impl TraceProvider for FibonacciRows {
+        fn num_rows(&self) -> usize {
+            self.length
+        }
+
+        fn num_columns(&self) -> usize {
+            2
+        }
+
+        fn segment_length(&self) -> usize {
+            64
+        }
+
+        fn fill_segment(&mut self, _start: usize, segment: &mut TraceTable) {
+            for row in 0..segment.num_rows() {
+                segment.set_row(row, &self.next);
+                self.next = [self.next[1].clone(), &self.next[0] + &self.next[1]];
+            }
+        }
+    }
+
+    #[test]
+    fn fib_test_streaming() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let mut rows = FibonacciRows {
+            next:   [FieldElement::ONE, witness.secret.clone()],
+            length: 1024,
+        };
+        let proof = prove_streaming::<MaskedKeccak, _>(&constraints, &mut rows).unwrap();
+        assert_eq!(proof, prove(&constraints, &claim.trace(&witness)).unwrap());
+
+        let mut wrong = FibonacciRows {
+            next:   [FieldElement::ONE, FieldElement::ONE],
+            length: 1024,
+        };
+        assert!(prove_streaming::<MaskedKeccak, _>(&constraints, &mut wrong).is_err());
+    }
+
+    #[test]
+    fn fib_test_custom_transcript() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&witness);
+        let default = prove(&constraints, &trace).unwrap();
+        let proof = prove_with_transcript::<MaskedKeccak, Ratchet>(&constraints, &trace).unwrap();
+        assert_ne!(proof, default);
+        assert!(verify_with_transcript::<MaskedKeccak, Ratchet>(&constraints, &proof).is_ok());
+        assert!(verify_with_transcript::<MaskedKeccak, Ratchet>(&constraints, &default).is_err());
+        assert!(verify(&constraints, &proof).is_err());
+    }
+
+    #[test]
+    fn fib_test_transcript() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let mut prover_log = TranscriptLogger::new();
+        let proof =
+            prove_logged::<MaskedKeccak>(&constraints, &claim.trace(&witness), &mut prover_log)
+                .unwrap();
+        let mut verifier_log = TranscriptLogger::new();
+        verify_logged::<MaskedKeccak>(&constraints, &proof, &mut verifier_log).unwrap();
+        assert_eq!(prover_log.entries()[0].label, "seed");
+        assert_eq!(prover_log.first_mismatch(&verifier_log), None);
+
+        let writes: Vec<u8> = prover_log
+            .entries()
+            .iter()
+            .filter(|entry| entry.label == "write")
+            .flat_map(|entry| entry.bytes.clone())
+            .collect();
+        assert_eq!(writes.as_slice(), proof.as_bytes());
+
+        // A modified proof diverges at the first write after the change
+        let mut bytes = proof.as_bytes().to_vec();
+        bytes[40] ^= 1;
+        let mut tampered_log = TranscriptLogger::new();
+        let _ = verify_logged::<MaskedKeccak>(
+            &constraints,
+            &Proof::from_bytes(bytes),
+            &mut tampered_log,
+        );
+        let mismatch = prover_log.first_mismatch(&tampered_log).unwrap();
+        assert_eq!(prover_log.entries()[mismatch].label, "write");
+        assert_eq!(
+            tampered_log.entries()[..mismatch],
+            prover_log.entries()[..mismatch]
+        );
+    }
+
+    #[test]
+    fn constraint_degree_above_blowup() {
+        use crate::{components::lookup::lookup, RationalExpression::Trace};
+        let table: Vec<FieldElement> = (0..8_usize).map(FieldElement::from).collect();
+        let mut constraints =
+            Constraints::from_expressions((16, 1), vec![], vec![lookup(Trace(0, 0), &table, 16)])
+                .unwrap();
+        constraints.blowup = 2;
+        constraints.min_security_bits = 0;
+        assert!(constraints.degree() > constraints.blowup);
+        let trace = TraceTable::from_rows(16, 1, |i| vec![FieldElement::from(i % 8)]);
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+    }
+
+    #[test]
+    fn fib_test_invalid_witness() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+
+        let mut trace = claim.trace(&witness);
+        trace[(500, 1)] += FieldElement::ONE;
+        assert_eq!(
+            prove(&constraints, &trace),
+            Err(Error::ConstraintUnsatisfied {
+                constraint: 1,
+                row:        499,
+            })
+        );
+
+        let trace = TraceTable::new(512, 2);
+        assert_eq!(prove(&constraints, &trace), Err(Error::TraceLengthMismatch));
+        let trace = TraceTable::new(1024, 3);
+        assert_eq!(prove(&constraints, &trace), Err(Error::TraceWidthMismatch));
+    }
+
+    #[test]
+    fn fib_test_zero_knowledge() {
+        let witness = fib_witness();
+        let claim = fib_claim();
+        let mut constraints = claim.constraints();
+        constraints.zero_knowledge = true;
+        let trace = claim.trace(&witness);
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+        let stats = ProofStats::from_proof(&proof, &constraints).unwrap();
+        assert_eq!(stats.size, proof.as_bytes().len());
+
+        // The blinding is random
+        let other = prove(&constraints, &trace).unwrap();
+        assert_ne!(other, proof);
+        assert_eq!(verify(&constraints, &other), Ok(()));
+
+        constraints.zero_knowledge = false;
+        assert!(verify(&constraints, &proof).is_err());
+    }
+
+    #[test]
+    fn blind_test() {
+        let trace = fib_claim().trace(&fib_witness());
+        let polynomials = trace.interpolate();
+        let blinded = blind(&polynomials);
+        let root = FieldElement::root(trace.num_rows()).unwrap();
+        for (polynomial, blinded) in polynomials.iter().zip(&blinded) {
+            assert_eq!(blinded.len(), 2 * polynomial.len());
+            for x in geometric_series(&FieldElement::ONE, &root).take(10) {
+                assert_eq!(blinded.evaluate(&x), polynomial.evaluate(&x));
+            }
+            let x = FieldElement::GENERATOR;
+            assert_ne!(blinded.evaluate(&x), polynomial.evaluate(&x));
+        }
+    }
+
     // TODO: What are we actually testing here? Should we add these as debug_assert
     // to the main implementation? Should we break up the implementation so we
     // can test the individual steps?
@@ -963,17 +1754,8 @@ mod tests {
     fn fib_proof_test() {
         crate::tests::init();
 
-        let claim = Claim {
-            index: 1000,
-            value: FieldElement::from(u256h!(
-                "0142c45e5d743d10eae7ebb70f1526c65de7dbcdb65b322b6ddc36a812591e8f"
-            )),
-        };
-        let witness = Witness {
-            secret: FieldElement::from(u256h!(
-                "00000000000000000000000000000000000000000000000000000000cafebabe"
-            )),
-        };
+        let claim = fib_claim();
+        let witness = fib_witness();
         let mut constraints = claim.constraints();
         constraints.blowup = 16;
         constraints.pow_bits = 12;
@@ -1034,17 +1816,17 @@ mod tests {
         let mut proof_seed = [(claim.index as u64).to_be_bytes()].concat();
         proof_seed.extend_from_slice(&claim.value.as_montgomery().to_bytes_be());
 
-        let mut proof = ProverChannel::new();
+        let mut proof: ProverChannel = ProverChannel::new();
         proof.initialize(&proof_seed.as_slice());
         // Checks that the channel is inited properly
         assert_eq!(
-            proof.coin.digest,
+            proof.coin.digest(),
             hex!("c891a11ddbc6c425fad523a7a4aeafa505d7aa1638cfffbd5b747100bc69e367")
         );
         proof.write(tree.commitment());
         // Checks that the channel allows writing of [u8; 32] properly
         assert_eq!(
-            proof.coin.digest,
+            proof.coin.digest(),
             hex!("b7d80385fa0c8879473cdf987ea7970bb807aec78bb91af39a1504d965ad8e92")
         );
 
@@ -1061,12 +1843,20 @@ mod tests {
             constraint_coefficients.push(proof.get_random());
         }
 
+        let trace_coset = extract_trace_coset(
+            &tree.leaves().0.iter().collect::<Vec<_>>(),
+            trace.num_rows() * constraints.degree().next_power_of_two(),
+            Storage::default(),
+        );
         let constraint_polynomials = get_constraint_polynomials(
-            &tree.leaves(),
+            &trace_coset,
             &constraints,
             &constraint_coefficients,
+            &[],
             trace.num_rows(),
-        );
+            Storage::default(),
+        )
+        .unwrap();
         assert_eq!(constraint_polynomials.len(), 1);
         assert_eq!(constraint_polynomials[0].len(), 1024);
         let CC = PolyLDE(
@@ -1090,11 +1880,11 @@ mod tests {
         );
         proof.write(&commitment);
 
-        let CO = oods_combine(&mut proof, &TPn, &constraint_polynomials);
+        let CO = oods_combine(&mut proof, &TPn, &constraints, &constraint_polynomials);
         // Checks that our get out of domain function call has written the right values
         // to the proof
         assert_eq!(
-            hex::encode(proof.coin.digest),
+            hex::encode(proof.coin.digest()),
             "c1b7a613149f857c524a724ebb54121352b9e720bf794ecebf2d78ee4e3f938b"
         );
 
@@ -1105,27 +1895,27 @@ mod tests {
             field_element!("03c6b730c58b55f44bbf3cb7ea82b2e6a0a8b23558e908b5466dfe42e821ee96")
         );
 
-        let fri_trees = perform_fri_layering(
+        let fri = FriProver::<MaskedKeccak>::commit(
             CO.low_degree_extension(constraints.blowup),
+            &constraints.fri_params(),
             &mut proof,
-            &constraints.fri_layout,
-            constraints.blowup,
         )
         .unwrap();
+        let fri_commitments = fri.commitments();
 
         // Checks that the first fri merkle tree root is right
         assert_eq!(
-            hex::encode(fri_trees[0].commitment().hash().as_bytes()),
+            hex::encode(fri_commitments[0].hash().as_bytes()),
             "620a934880b6c7d893acf17a21cc9c10058a7add000000000000000000000000"
         );
         // Checks that the second fri merkle tree root is right
         assert_eq!(
-            hex::encode(fri_trees[1].commitment().hash().as_bytes()),
+            hex::encode(fri_commitments[1].hash().as_bytes()),
             "effd58adf9f2dac6bfd338772d0d7750c0c6f8b2000000000000000000000000"
         );
         // Checks that the fri layering function decommited the right values.
         assert_eq!(
-            hex::encode(proof.coin.digest),
+            hex::encode(proof.coin.digest()),
             "3c6cecef72873e7d73933e73279d36ca77c5a0c7497311eba735722549238334"
         );
 
@@ -1154,7 +1944,7 @@ mod tests {
 
         // Checks that our first decommitment is successful
         assert_eq!(
-            hex::encode(proof.coin.digest),
+            hex::encode(proof.coin.digest()),
             "c0bf8d8ba4d15bd0e73892e3d6e90bd4f477f9135a7be39ba7e9471e6ac68a44"
         );
 
@@ -1166,15 +1956,14 @@ mod tests {
 
         // Checks that our second decommitment is successful
         assert_eq!(
-            hex::encode(proof.coin.digest),
+            hex::encode(proof.coin.digest()),
             "f2d3e6593dc23fa32655040ad5023739e15fff1d645bb809467cfccb676d6343"
         );
 
-        decommit_fri_layers_and_trees(fri_trees.as_slice(), query_indices.as_slice(), &mut proof)
-            .unwrap();
+        fri.decommit(&query_indices, &mut proof).unwrap();
         // Checks that our fri decommitment is successful
         assert_eq!(
-            hex::encode(proof.coin.digest),
+            hex::encode(proof.coin.digest()),
             "fcf1924f84656e5068ab9cbd44ae084b235bb990eefc0fd0183c77d5645e830e"
         );
     }
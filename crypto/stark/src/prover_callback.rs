@@ -0,0 +1,170 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Phase of the prover that is about to start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProverEvent {
+    /// Interpolate the trace and commit to its low degree extension,
+    /// followed by the interaction trace if there is one.
+    TraceLde,
+    /// Evaluate the combined constraint polynomial.
+    ConstraintEvaluation,
+    /// Commit to the low degree extension of the constraint polynomials.
+    ConstraintLde,
+    /// Combine the polynomials at the out of domain sampling point.
+    Oods,
+    /// Commit to FRI layer `layer` out of `layers`.
+    FriLayer { layer: usize, layers: usize },
+    /// Solve the proof of work.
+    ProofOfWork,
+    /// Write the query decommitments.
+    Queries,
+    /// Verify the finished proof.
+    Verify,
+}
+
+impl ProverEvent {
+    /// Name of the phase in `snake_case`, without the FRI layer number.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::TraceLde => "trace_lde",
+            Self::ConstraintEvaluation => "constraint_evaluation",
+            Self::ConstraintLde => "constraint_lde",
+            Self::Oods => "oods",
+            Self::FriLayer { .. } => "fri_layer",
+            Self::ProofOfWork => "proof_of_work",
+            Self::Queries => "queries",
+            Self::Verify => "verify",
+        }
+    }
+
+    /// Rough estimate of the proof completed when the phase starts, in
+    /// percent.
+    pub fn percent(&self) -> usize {
+        match *self {
+            Self::TraceLde => 0,
+            Self::ConstraintEvaluation => 20,
+            Self::ConstraintLde => 40,
+            Self::Oods => 55,
+            Self::FriLayer { layer, layers } => 60 + 25 * layer / layers,
+            Self::ProofOfWork => 85,
+            Self::Queries => 90,
+            Self::Verify => 95,
+        }
+    }
+}
+
+/// Hooks called by the prover between phases.
+///
+/// Use with [`prove_with_callback`]. The prover checks [`is_cancelled`]
+/// before each phase and stops with `ProverError::Cancelled` if it returns
+/// `true`. Phases are not interrupted, so cancellation takes effect at the
+/// start of the next phase.
+///
+/// [`prove_with_callback`]: fn.prove_with_callback.html
+/// [`is_cancelled`]: #method.is_cancelled
+pub trait ProverCallback: Sync {
+    fn on_event(&self, _event: ProverEvent) {}
+
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// No hooks.
+impl ProverCallback for () {}
+
+/// Shared flag to cancel a running proof from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl ProverCallback for CancellationToken {
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prove, prove_with_callback,
+        traits::tests::{fib_claim, fib_witness},
+        Provable, ProverError, Verifiable,
+    };
+    use std::{prelude::v1::*, sync::Mutex};
+    use zkp_hash::MaskedKeccak;
+
+    #[derive(Default)]
+    struct Recorder {
+        events:       Mutex<Vec<ProverEvent>>,
+        cancel_after: Option<usize>,
+    }
+
+    impl ProverCallback for Recorder {
+        fn on_event(&self, event: ProverEvent) {
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn is_cancelled(&self) -> bool {
+            self.cancel_after
+                .map_or(false, |n| self.events.lock().unwrap().len() >= n)
+        }
+    }
+
+    #[test]
+    fn events_test() {
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&fib_witness());
+        let recorder = Recorder::default();
+        let proof = prove_with_callback::<MaskedKeccak>(&constraints, &trace, &recorder).unwrap();
+        assert_eq!(proof, prove(&constraints, &trace).unwrap());
+
+        let events = recorder.events.into_inner().unwrap();
+        assert_eq!(events.len(), 7 + constraints.fri_layout.len());
+        assert_eq!(events.first(), Some(&ProverEvent::TraceLde));
+        assert_eq!(events.last(), Some(&ProverEvent::Verify));
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].percent() < pair[1].percent()));
+    }
+
+    #[test]
+    fn cancel_test() {
+        let claim = fib_claim();
+        let constraints = claim.constraints();
+        let trace = claim.trace(&fib_witness());
+        let recorder = Recorder {
+            cancel_after: Some(2),
+            ..Recorder::default()
+        };
+        assert_eq!(
+            prove_with_callback::<MaskedKeccak>(&constraints, &trace, &recorder),
+            Err(ProverError::Cancelled)
+        );
+        assert_eq!(recorder.events.into_inner().unwrap(), vec![
+            ProverEvent::TraceLde,
+            ProverEvent::ConstraintEvaluation
+        ]);
+
+        let token = CancellationToken::new();
+        token.clone().cancel();
+        assert_eq!(
+            prove_with_callback::<MaskedKeccak>(&constraints, &trace, &token),
+            Err(ProverError::Cancelled)
+        );
+    }
+}
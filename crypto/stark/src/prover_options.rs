@@ -0,0 +1,37 @@
+use crate::parallelism::Parallelism;
+use zkp_mmap_vec::Storage;
+
+/// Settings of the prover that do not change the proof.
+///
+/// Unlike the parameters in [`Constraints`], these are not seen by the
+/// verifier. Pass them to [`prove_with_options`].
+///
+/// [`Constraints`]: struct.Constraints.html
+/// [`prove_with_options`]: fn.prove_with_options.html
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct ProverOptions {
+    /// Storage for the trace and constraint low degree extensions, the
+    /// constraint evaluations, the FRI layers and the Merkle tree layers.
+    ///
+    /// Defaults to temporary files for everything.
+    pub storage: Storage,
+
+    /// Threads used by the prover
+    ///
+    /// Defaults to `Parallelism::Global`, the current Rayon pool.
+    pub parallelism: Parallelism,
+}
+
+impl ProverOptions {
+    /// Keep the large prover intermediates as selected by `storage`.
+    pub fn with_storage(mut self, storage: Storage) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Run the prover with `parallelism` instead of the current Rayon pool.
+    pub fn with_parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+}
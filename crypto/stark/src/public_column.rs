@@ -0,0 +1,137 @@
+#[cfg(feature = "prover")]
+use crate::TraceTable;
+use crate::{ClaimBytes, PeriodicColumn, RationalExpression};
+use std::prelude::v1::*;
+use zkp_hash::{Hash, MaskedKeccak};
+use zkp_primefield::FieldElement;
+
+/// A trace column whose values are part of the claim.
+///
+/// The values fill the column from the first row, the remaining rows are
+/// zero. Instead of a boundary constraint for each value, a single constraint
+/// equates the column to the polynomial interpolating the values, which the
+/// verifier evaluates once. Add it with [`Constraints::add_public_column`],
+/// which also seeds the channel with the [`digest`] of the values.
+///
+/// [`Constraints::add_public_column`]: struct.Constraints.html#method.add_public_column
+/// [`digest`]: #method.digest
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PublicColumn {
+    column: usize,
+    values: Vec<FieldElement>,
+}
+
+impl PublicColumn {
+    pub fn new(column: usize, values: Vec<FieldElement>) -> Self {
+        Self { column, values }
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn values(&self) -> &[FieldElement] {
+        &self.values
+    }
+
+    /// Keccak hash of the claim bytes of the values.
+    pub fn digest(&self) -> Hash {
+        let mut hasher = MaskedKeccak::new();
+        hasher.update(&self.values.claim_bytes());
+        hasher.hash()
+    }
+
+    /// The constraint that the column holds the values in a trace of the
+    /// given length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `trace_length` is not a power of two or less than the number
+    /// of values.
+    pub fn expression(&self, trace_length: usize) -> RationalExpression {
+        use RationalExpression::*;
+        assert!(self.values.len() <= trace_length);
+        let mut values = self.values.clone();
+        values.resize(trace_length, FieldElement::ZERO);
+        let every_row = (X.pow(trace_length) - 1.into()).inv();
+        (Trace(self.column, 0) - PeriodicColumn::from_values(&values).expression(trace_length))
+            * every_row
+    }
+
+    /// Write the values and zeros for the remaining rows to the column.
+    #[cfg(feature = "prover")]
+    pub fn fill(&self, trace: &mut TraceTable) {
+        for row in 0..trace.num_rows() {
+            trace[(row, self.column)] = self.values.get(row).cloned().unwrap_or(FieldElement::ZERO);
+        }
+    }
+}
+
+// The column, the number of values and their digest, so the channel seed
+// stays short for long columns.
+impl ClaimBytes for PublicColumn {
+    fn write_claim_bytes(&self, bytes: &mut Vec<u8>) {
+        self.column.write_claim_bytes(bytes);
+        self.values.len().write_claim_bytes(bytes);
+        bytes.extend_from_slice(self.digest().as_bytes());
+    }
+}
+
+#[cfg(feature = "prover")]
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prove, verify, Constraints};
+
+    fn values() -> Vec<FieldElement> {
+        (0..100_usize)
+            .map(|i| FieldElement::from(i * i + 3))
+            .collect()
+    }
+
+    // Column 1 is the public column, column 0 is its running sum.
+    fn public_constraints(public: &PublicColumn) -> Constraints {
+        use RationalExpression::*;
+        let trace_length = 128;
+        let g = Constant(FieldElement::root(trace_length).unwrap());
+        let every_row = (X - g.pow(trace_length - 1)) / (X.pow(trace_length) - 1.into());
+        let mut constraints =
+            Constraints::from_expressions((trace_length, 2), b"public column test".to_vec(), vec![
+                (Trace(0, 1) - Trace(0, 0) - Trace(1, 1)) * every_row,
+            ])
+            .unwrap();
+        constraints.add_public_column(public).unwrap();
+        constraints
+    }
+
+    fn trace(public: &PublicColumn) -> TraceTable {
+        let mut trace = TraceTable::new(128, 2);
+        public.fill(&mut trace);
+        for row in 1..128 {
+            trace[(row, 0)] = &trace[(row - 1, 0)] + &trace[(row, 1)];
+        }
+        trace
+    }
+
+    #[test]
+    fn public_column_test() {
+        let public = PublicColumn::new(1, values());
+        let constraints = public_constraints(&public);
+        let mut trace = trace(&public);
+        assert_eq!(constraints.check_trace(&trace), Ok(()));
+        let proof = prove(&constraints, &trace).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+
+        // Changing a value changes the seed, so the proof does not transfer
+        let mut other = values();
+        other[50] += &FieldElement::ONE;
+        let other = PublicColumn::new(1, other);
+        assert_ne!(other.digest(), public.digest());
+        assert!(verify(&public_constraints(&other), &proof).is_err());
+
+        // A trace that deviates from the values fails the constraint
+        trace[(120, 1)] = FieldElement::ONE;
+        assert!(constraints.check_trace(&trace).is_err());
+    }
+}
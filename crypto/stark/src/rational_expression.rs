@@ -1,18 +1,29 @@
 use crate::polynomial::DensePolynomial;
 use std::{
+    fmt,
     iter::Sum,
     ops::{Add, Div, Mul, Sub},
     prelude::v1::*,
 };
 use zkp_primefield::FieldElement;
+use zkp_u256::U256;
 
 // TODO: Rename to algebraic expression
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub enum RationalExpression {
     X,
     Constant(FieldElement),
     Trace(usize, isize),
+    /// A random value drawn from the channel after the trace is committed.
+    ///
+    /// Constraints on the interaction trace use challenges, see
+    /// [`Constraints::add_running_product`]. They are replaced by their
+    /// values with [`with_challenges`](#method.with_challenges) before the
+    /// expression is evaluated.
+    ///
+    /// [`Constraints::add_running_product`]: struct.Constraints.html#method.add_running_product
+    Challenge(usize),
     Polynomial(DensePolynomial, Box<RationalExpression>),
     Add(Box<RationalExpression>, Box<RationalExpression>),
     Neg(Box<RationalExpression>),
@@ -99,7 +110,8 @@ impl Sum<RationalExpression> for RationalExpression {
 impl RationalExpression {
     /// Numerator and denominator degree of the expression in X.
     ///
-    /// Calculates an upper bound. Cancelations may occur.
+    /// Calculates an upper bound. Cancelations may occur, calling
+    /// [`simplify`](#method.simplify) first gives a tighter bound.
     // Note: We can have trace polynomials of different degree here if we want.
     pub fn degree(&self, trace_degree: usize) -> (usize, usize) {
         self.degree_impl(1, trace_degree)
@@ -114,7 +126,7 @@ impl RationalExpression {
         use RationalExpression::*;
         match self {
             X => (x_degree, 0),
-            Constant(_) => (0, 0),
+            Constant(_) | Challenge(_) => (0, 0),
             Trace(..) => (trace_degree, 0),
             Polynomial(p, a) => {
                 let (n, d) = a.degree_impl(x_degree, trace_degree);
@@ -123,9 +135,8 @@ impl RationalExpression {
             Add(a, b) => {
                 let (an, ad) = a.degree_impl(x_degree, trace_degree);
                 let (bn, bd) = b.degree_impl(x_degree, trace_degree);
-                assert!(ad == 0); // TODO: Can we handle this better?
-                assert!(bd == 0);
-                (std::cmp::max(an, bn), 0)
+                // a / c + b / d = (a d + b c) / (c d)
+                (std::cmp::max(an + bd, bn + ad), ad + bd)
             }
             Neg(a) => a.degree_impl(x_degree, trace_degree),
             Mul(a, b) => {
@@ -144,6 +155,99 @@ impl RationalExpression {
         }
     }
 
+    /// All distinct `(column, row offset)` pairs referenced by the
+    /// expression, in sorted order.
+    pub fn trace_arguments(&self) -> Vec<(usize, isize)> {
+        let mut arguments = Vec::new();
+        self.trace_arguments_impl(&mut arguments);
+        arguments.sort();
+        arguments.dedup();
+        arguments
+    }
+
+    fn trace_arguments_impl(&self, arguments: &mut Vec<(usize, isize)>) {
+        use RationalExpression::*;
+        match self {
+            X | Constant(_) | Challenge(_) => {}
+            &Trace(i, j) => arguments.push((i, j)),
+            Polynomial(_, a) | Neg(a) | Inv(a) | Exp(a, _) => a.trace_arguments_impl(arguments),
+            Add(a, b) | Mul(a, b) => {
+                a.trace_arguments_impl(arguments);
+                b.trace_arguments_impl(arguments);
+            }
+        }
+    }
+
+    /// All distinct challenge indices used by the expression, in sorted
+    /// order.
+    pub fn challenges(&self) -> Vec<usize> {
+        let mut challenges = Vec::new();
+        self.challenges_impl(&mut challenges);
+        challenges.sort_unstable();
+        challenges.dedup();
+        challenges
+    }
+
+    fn challenges_impl(&self, challenges: &mut Vec<usize>) {
+        use RationalExpression::*;
+        match self {
+            X | Constant(_) | Trace(..) => {}
+            &Challenge(i) => challenges.push(i),
+            Polynomial(_, a) | Neg(a) | Inv(a) | Exp(a, _) => a.challenges_impl(challenges),
+            Add(a, b) | Mul(a, b) => {
+                a.challenges_impl(challenges);
+                b.challenges_impl(challenges);
+            }
+        }
+    }
+
+    /// Replace each `Challenge(i)` by the constant `challenges[i]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a challenge index is out of range.
+    pub fn with_challenges(&self, challenges: &[FieldElement]) -> Self {
+        use RationalExpression::*;
+        let recurse = |a: &Self| Box::new(a.with_challenges(challenges));
+        match self {
+            X | Constant(_) | Trace(..) => self.clone(),
+            &Challenge(i) => Constant(challenges[i].clone()),
+            Polynomial(p, a) => Polynomial(p.clone(), recurse(a)),
+            Add(a, b) => Add(recurse(a), recurse(b)),
+            Neg(a) => Neg(recurse(a)),
+            Mul(a, b) => Mul(recurse(a), recurse(b)),
+            Inv(a) => Inv(recurse(a)),
+            Exp(a, e) => Exp(recurse(a), *e),
+        }
+    }
+
+    /// Replace `X` and `Trace(i, j)` by other expressions.
+    ///
+    /// This is the symbolic counterpart of `evaluate`. It can be used to
+    /// relabel trace columns or change the evaluation domain.
+    pub fn substitute(&self, x: &Self, trace: &dyn Fn(usize, isize) -> Self) -> Self {
+        use RationalExpression::*;
+        match self {
+            X => x.clone(),
+            Constant(c) => Constant(c.clone()),
+            &Trace(i, j) => trace(i, j),
+            &Challenge(i) => Challenge(i),
+            Polynomial(p, a) => Polynomial(p.clone(), Box::new(a.substitute(x, trace))),
+            Add(a, b) => a.substitute(x, trace) + b.substitute(x, trace),
+            Neg(a) => Neg(Box::new(a.substitute(x, trace))),
+            Mul(a, b) => a.substitute(x, trace) * b.substitute(x, trace),
+            Inv(a) => Inv(Box::new(a.substitute(x, trace))),
+            Exp(a, e) => Exp(Box::new(a.substitute(x, trace)), *e),
+        }
+    }
+
+    /// Evaluate the expression at `x` with `trace(i, j)` the value of
+    /// `Trace(i, j)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics on a division by zero and on challenges, which need to be
+    /// replaced first with [`with_challenges`](#method.with_challenges).
     pub fn evaluate(
         &self,
         x: &FieldElement,
@@ -154,6 +258,7 @@ impl RationalExpression {
             X => x.clone(),
             Constant(c) => c.clone(),
             &Trace(i, j) => trace(i, j),
+            Challenge(_) => panic!("Challenges must be replaced before evaluation"),
             Polynomial(p, a) => p.evaluate(&a.evaluate(x, trace)),
             Add(a, b) => a.evaluate(x, trace) + b.evaluate(x, trace),
             Neg(a) => -&a.evaluate(x, trace),
@@ -162,4 +267,453 @@ impl RationalExpression {
             Exp(a, e) => a.evaluate(x, trace).pow(*e),
         }
     }
+
+    /// Evaluate the expression as a (numerator, denominator) pair.
+    ///
+    /// Unlike `evaluate` this does not perform any divisions, so it can be
+    /// used on points where the denominator vanishes. The result is not
+    /// reduced, common factors in numerator and denominator are retained.
+    ///
+    /// # Panics
+    ///
+    /// Panics on challenges, like `evaluate`.
+    pub fn evaluate_fraction(
+        &self,
+        x: &FieldElement,
+        trace: &dyn Fn(usize, isize) -> FieldElement,
+    ) -> (FieldElement, FieldElement) {
+        use RationalExpression::*;
+        match self {
+            X => (x.clone(), FieldElement::ONE),
+            Constant(c) => (c.clone(), FieldElement::ONE),
+            &Trace(i, j) => (trace(i, j), FieldElement::ONE),
+            Challenge(_) => panic!("Challenges must be replaced before evaluation"),
+            Polynomial(p, a) => {
+                let (an, ad) = a.evaluate_fraction(x, trace);
+                // Horner's rule with the fraction `an / ad`
+                let mut numerator = FieldElement::ZERO;
+                let mut denominator = FieldElement::ONE;
+                for coefficient in p.coefficients().iter().rev() {
+                    numerator = numerator * &an + coefficient * &denominator * &ad;
+                    denominator *= &ad;
+                }
+                (numerator, denominator)
+            }
+            Add(a, b) => {
+                let (an, ad) = a.evaluate_fraction(x, trace);
+                let (bn, bd) = b.evaluate_fraction(x, trace);
+                (&an * &bd + &bn * &ad, ad * bd)
+            }
+            Neg(a) => {
+                let (n, d) = a.evaluate_fraction(x, trace);
+                (-&n, d)
+            }
+            Mul(a, b) => {
+                let (an, ad) = a.evaluate_fraction(x, trace);
+                let (bn, bd) = b.evaluate_fraction(x, trace);
+                (an * bn, ad * bd)
+            }
+            Inv(a) => {
+                let (n, d) = a.evaluate_fraction(x, trace);
+                (d, n)
+            }
+            Exp(a, e) => {
+                let (n, d) = a.evaluate_fraction(x, trace);
+                (n.pow(*e), d.pow(*e))
+            }
+        }
+    }
+}
+
+// Operator precedences, higher binds tighter.
+const SUM: usize = 0;
+const PRODUCT: usize = 1;
+const UNARY: usize = 2;
+const ATOM: usize = 3;
+
+// Splits a constant in sign and magnitude. Field elements close to the
+// modulus are shown as small negative numbers.
+fn constant_parts(value: &FieldElement) -> (bool, U256) {
+    let n = U256::from(value);
+    let negated = U256::from(-value);
+    if n.bits() > 64 && negated.bits() <= 64 {
+        (true, negated)
+    } else {
+        (false, n)
+    }
+}
+
+// Magnitudes that do not fit a `u64` are written in hexadecimal.
+fn fmt_magnitude(f: &mut fmt::Formatter<'_>, n: &U256) -> fmt::Result {
+    if n.bits() <= 64 {
+        write!(f, "{}", n.c0)
+    } else {
+        write!(f, "0x{:016x}{:016x}{:016x}{:016x}", n.c3, n.c2, n.c1, n.c0)
+    }
+}
+
+/// Renders a [`RationalExpression`] as LaTeX math.
+///
+/// Created by [`RationalExpression::to_latex`].
+#[cfg_attr(feature = "std", derive(Debug))]
+struct Latex<'a>(&'a RationalExpression);
+
+impl fmt::Display for Latex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_with(f, true, SUM)
+    }
+}
+
+impl fmt::Display for RationalExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with(f, false, SUM)
+    }
+}
+
+impl RationalExpression {
+    /// The expression as LaTeX math, without the surrounding `$`.
+    ///
+    /// `Trace(i, j)` is written as `T_{i,j}`, `Challenge(i)` as `\gamma_{i}`
+    /// and a polynomial of degree `d` as `p_{d}`.
+    pub fn to_latex(&self) -> String {
+        use fmt::Write;
+        let mut result = String::new();
+        // Writing to a string does not fail
+        let _ = write!(result, "{}", Latex(self));
+        result
+    }
+
+    fn precedence(&self) -> usize {
+        use RationalExpression::*;
+        match self {
+            Constant(c) if constant_parts(c).0 => UNARY,
+            X | Constant(_) | Trace(..) | Challenge(_) | Polynomial(..) => ATOM,
+            Add(..) => SUM,
+            Mul(..) | Inv(_) => PRODUCT,
+            Neg(_) | Exp(..) => UNARY,
+        }
+    }
+
+    fn fmt_with(&self, f: &mut fmt::Formatter<'_>, latex: bool, precedence: usize) -> fmt::Result {
+        use RationalExpression::*;
+        let parenthesize = self.precedence() < precedence;
+        if parenthesize {
+            write!(f, "{}", if latex { "\\left(" } else { "(" })?;
+        }
+        match self {
+            X => write!(f, "{}", if latex { "x" } else { "X" })?,
+            Constant(c) => {
+                let (negative, magnitude) = constant_parts(c);
+                if negative {
+                    write!(f, "-")?;
+                }
+                fmt_magnitude(f, &magnitude)?
+            }
+            Trace(i, j) if latex => write!(f, "T_{{{},{}}}", i, j)?,
+            Trace(i, j) => write!(f, "Trace({}, {})", i, j)?,
+            Challenge(i) if latex => write!(f, "\\gamma_{{{}}}", i)?,
+            Challenge(i) => write!(f, "Challenge({})", i)?,
+            Polynomial(p, a) => {
+                if latex {
+                    write!(f, "p_{{{}}}\\left(", p.degree())?;
+                } else {
+                    write!(f, "p{}(", p.degree())?;
+                }
+                a.fmt_with(f, latex, SUM)?;
+                write!(f, "{}", if latex { "\\right)" } else { ")" })?
+            }
+            Add(a, b) => {
+                a.fmt_with(f, latex, SUM)?;
+                match &**b {
+                    Neg(b) => {
+                        write!(f, " - ")?;
+                        b.fmt_with(f, latex, PRODUCT)?
+                    }
+                    Constant(c) if constant_parts(c).0 => {
+                        write!(f, " - ")?;
+                        fmt_magnitude(f, &constant_parts(c).1)?
+                    }
+                    b => {
+                        write!(f, " + ")?;
+                        b.fmt_with(f, latex, PRODUCT)?
+                    }
+                }
+            }
+            Neg(a) => {
+                write!(f, "-")?;
+                a.fmt_with(f, latex, UNARY)?
+            }
+            Mul(a, b) => {
+                match &**b {
+                    Inv(b) if latex => {
+                        write!(f, "\\frac{{")?;
+                        a.fmt_with(f, latex, SUM)?;
+                        write!(f, "}}{{")?;
+                        b.fmt_with(f, latex, SUM)?;
+                        write!(f, "}}")?
+                    }
+                    Inv(b) => {
+                        a.fmt_with(f, latex, PRODUCT)?;
+                        write!(f, " / ")?;
+                        b.fmt_with(f, latex, UNARY)?
+                    }
+                    b => {
+                        a.fmt_with(f, latex, PRODUCT)?;
+                        write!(f, "{}", if latex { " \\cdot " } else { " * " })?;
+                        b.fmt_with(f, latex, UNARY)?
+                    }
+                }
+            }
+            Inv(a) => {
+                if latex {
+                    write!(f, "\\frac{{1}}{{")?;
+                    a.fmt_with(f, latex, SUM)?;
+                    write!(f, "}}")?
+                } else {
+                    write!(f, "1 / ")?;
+                    a.fmt_with(f, latex, UNARY)?
+                }
+            }
+            Exp(a, e) => {
+                a.fmt_with(f, latex, ATOM)?;
+                if latex {
+                    write!(f, "^{{{}}}", e)?
+                } else {
+                    write!(f, "^{}", e)?
+                }
+            }
+        }
+        if parenthesize {
+            write!(f, "{}", if latex { "\\right)" } else { ")" })?;
+        }
+        Ok(())
+    }
+}
+
+// Product of factors with their numerator and denominator multiplicities.
+struct Product {
+    coefficient: FieldElement,
+    factors:     Vec<(RationalExpression, usize, usize)>,
+}
+
+impl Product {
+    fn new() -> Self {
+        Self {
+            coefficient: FieldElement::ONE,
+            factors:     Vec::new(),
+        }
+    }
+
+    fn collect(&mut self, expression: &RationalExpression, numerator: usize, denominator: usize) {
+        use RationalExpression::*;
+        match expression {
+            Mul(a, b) => {
+                self.collect(a, numerator, denominator);
+                self.collect(b, numerator, denominator);
+            }
+            Inv(a) => self.collect(a, denominator, numerator),
+            Exp(a, e) => self.collect(a, e * numerator, e * denominator),
+            Neg(a) => {
+                if (numerator + denominator) % 2 == 1 {
+                    self.coefficient = -&self.coefficient;
+                }
+                self.collect(a, numerator, denominator)
+            }
+            Constant(c) if denominator == 0 || !c.is_zero() => {
+                self.coefficient *= c.pow(numerator);
+                if denominator > 0 {
+                    self.coefficient *= c.inv().unwrap().pow(denominator);
+                }
+            }
+            _ => {
+                if let Some(entry) = self.factors.iter_mut().find(|(f, ..)| f == expression) {
+                    entry.1 += numerator;
+                    entry.2 += denominator;
+                } else {
+                    self.factors
+                        .push((expression.clone(), numerator, denominator));
+                }
+            }
+        }
+    }
+
+    fn into_expression(self) -> RationalExpression {
+        use RationalExpression::*;
+        fn product(
+            factors: impl Iterator<Item = RationalExpression>,
+        ) -> Option<RationalExpression> {
+            factors.fold(None, |acc, factor| {
+                Some(match acc {
+                    Some(acc) => acc * factor,
+                    None => factor,
+                })
+            })
+        }
+        fn power(factor: &RationalExpression, exponent: usize) -> RationalExpression {
+            if exponent == 1 {
+                factor.clone()
+            } else {
+                factor.pow(exponent)
+            }
+        }
+        if self.coefficient.is_zero() {
+            return Constant(FieldElement::ZERO);
+        }
+        let numerator = product(self.factors.iter().filter_map(|(factor, n, d)| {
+            if n > d {
+                Some(power(factor, n - d))
+            } else {
+                None
+            }
+        }));
+        let denominator = product(self.factors.iter().filter_map(|(factor, n, d)| {
+            if d > n {
+                Some(power(factor, d - n))
+            } else {
+                None
+            }
+        }));
+        let numerator = match (self.coefficient == FieldElement::ONE, numerator) {
+            (true, Some(numerator)) => numerator,
+            (_, None) => Constant(self.coefficient),
+            (false, Some(numerator)) if self.coefficient == -&FieldElement::ONE => numerator.neg(),
+            (false, Some(numerator)) => Constant(self.coefficient) * numerator,
+        };
+        match denominator {
+            Some(denominator) => numerator * denominator.inv(),
+            None => numerator,
+        }
+    }
+}
+
+impl RationalExpression {
+    /// Algebraically simplify the expression.
+    ///
+    /// Constant subexpressions are folded. Products are normalized to a
+    /// single numerator and a single denominator, so vanishing polynomials
+    /// dividing the same constraint are combined. Identical factors in the
+    /// numerator and denominator cancel.
+    ///
+    /// The result is equal as a rational function. Cancellation removes
+    /// poles, so the result may be defined on more points than the input.
+    pub fn simplify(&self) -> Self {
+        use RationalExpression::*;
+        match self {
+            X | Constant(_) | Trace(..) | Challenge(_) => self.clone(),
+            Polynomial(p, a) => {
+                match a.simplify() {
+                    Constant(c) => Constant(p.evaluate(&c)),
+                    a => Polynomial(p.clone(), Box::new(a)),
+                }
+            }
+            Add(a, b) => {
+                match (a.simplify(), b.simplify()) {
+                    (Constant(a), Constant(b)) => Constant(a + b),
+                    (Constant(a), b) if a.is_zero() => b,
+                    (a, Constant(b)) if b.is_zero() => a,
+                    (a, b) => a + b,
+                }
+            }
+            Neg(a) => {
+                match a.simplify() {
+                    Constant(a) => Constant(-&a),
+                    Neg(a) => *a,
+                    a => a.neg(),
+                }
+            }
+            Mul(..) | Inv(_) | Exp(..) => {
+                let mut product = Product::new();
+                product.collect(self, 1, 0);
+                // Simplify factors after collecting, so identical factors
+                // are merged before they are simplified.
+                let mut simplified = Product::new();
+                simplified.coefficient = product.coefficient;
+                for (factor, numerator, denominator) in product.factors {
+                    simplified.collect(&factor.simplify(), numerator, denominator);
+                }
+                simplified.into_expression()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+    use RationalExpression::*;
+
+    fn trace(i: usize, j: isize) -> FieldElement {
+        FieldElement::from(7 * i + 1) * FieldElement::from(j + 11)
+    }
+
+    fn example() -> RationalExpression {
+        let vanishing = X.pow(16) - 1.into();
+        (Trace(0, 1) - Trace(1, 0) * 2.into()) * (X - 3.into()) / vanishing.clone()
+            + (Trace(1, 1) - Trace(0, 0).pow(2)) * (X - 3.into()) / (X - 5.into()) / vanishing
+    }
+
+    #[test]
+    fn test_constant_folding() {
+        let expression = (RationalExpression::from(2) + 3.into()) * 4.into()
+            - RationalExpression::from(6) / 3.into();
+        assert_eq!(expression.simplify(), Constant(18.into()));
+    }
+
+    #[test]
+    fn test_identities() {
+        assert_eq!((X + 0.into()).simplify(), X);
+        assert_eq!((X * 1.into()).simplify(), X);
+        assert_eq!((X * 0.into()).simplify(), Constant(0.into()));
+        assert_eq!(X.neg().neg().simplify(), X);
+        assert_eq!(X.pow(0).simplify(), Constant(1.into()));
+    }
+
+    #[test]
+    fn test_combine_denominators() {
+        let expression = Trace(0, 0) / (X - 1.into()) / (X.pow(8) - 1.into());
+        assert_eq!(
+            expression.simplify(),
+            Trace(0, 0) * ((X - 1.into()).simplify() * (X.pow(8) - 1.into()).simplify()).inv()
+        );
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let divisor = X.pow(16) - 1.into();
+        let expression = Trace(0, 0) * divisor.clone() * X.pow(2) / divisor / X;
+        assert_eq!(expression.simplify(), Trace(0, 0) * X);
+        assert_eq!(expression.degree(15), (16 + 15 + 2, 17));
+        assert_eq!(expression.simplify().degree(15), (16, 0));
+    }
+
+    #[test]
+    fn test_degree_of_sum() {
+        // Adding fractions used to panic
+        assert_eq!(example().degree(15), (16 + 30 + 1, 16 + 16 + 1));
+    }
+
+    #[test]
+    fn test_display() {
+        let expression = (Trace(0, 1) - Trace(1, 0) * 2.into()).pow(2) / (X - 1.into())
+            + X.neg() * (Trace(0, 0) + 3.into());
+        assert_eq!(
+            expression.to_string(),
+            "(Trace(0, 1) - Trace(1, 0) * 2)^2 / (X - 1) + -X * (Trace(0, 0) + 3)"
+        );
+        assert_eq!(
+            expression.to_latex(),
+            "\\frac{\\left(T_{0,1} - T_{1,0} \\cdot 2\\right)^{2}}{x - 1} + -x \\cdot \
+             \\left(T_{0,0} + 3\\right)"
+        );
+        assert_eq!(RationalExpression::from(-5).to_string(), "-5");
+    }
+
+    #[quickcheck]
+    fn simplify_preserves_value(x: FieldElement) -> bool {
+        let expression = example();
+        let (n, d) = expression.evaluate_fraction(&x, &trace);
+        let (sn, sd) = expression.simplify().evaluate_fraction(&x, &trace);
+        n * sd == sn * d
+    }
 }
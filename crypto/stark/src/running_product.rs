@@ -0,0 +1,105 @@
+use crate::RationalExpression;
+#[cfg(feature = "prover")]
+use crate::{parallelism::is_sequential, TraceTable};
+#[cfg(feature = "prover")]
+use rayon::prelude::*;
+use std::prelude::v1::*;
+#[cfg(feature = "prover")]
+use zkp_primefield::FieldElement;
+
+/// A column of the interaction trace with the running product of
+/// `numerator / denominator` over the rows.
+///
+/// The numerator and denominator are expressions in the trace columns and
+/// the [challenges]. The column starts at one on the first row and each next
+/// row is the previous one times the quotient on that row. The column is
+/// cyclic, so the product of the quotients over all rows has to be one. This
+/// is how multiset equality is shown, see [`Permutation`].
+///
+/// Add it with [`Constraints::add_running_product`]. The challenges are
+/// drawn after the trace is committed, then the prover computes the column
+/// and commits to it.
+///
+/// [challenges]: enum.RationalExpression.html#variant.Challenge
+/// [`Permutation`]: components/permutation/struct.Permutation.html
+/// [`Constraints::add_running_product`]: struct.Constraints.html#method.add_running_product
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RunningProduct {
+    numerator:   RationalExpression,
+    denominator: RationalExpression,
+}
+
+impl RunningProduct {
+    pub fn new(numerator: RationalExpression, denominator: RationalExpression) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    pub fn numerator(&self) -> &RationalExpression {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &RationalExpression {
+        &self.denominator
+    }
+
+    /// The constraints that `column` holds the running product in a trace
+    /// of `trace_length` rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `trace_length` is not a power of two.
+    pub fn expressions(&self, column: usize, trace_length: usize) -> Vec<RationalExpression> {
+        use RationalExpression::*;
+        assert!(trace_length.is_power_of_two());
+        let every_row = (X.pow(trace_length) - 1.into()).inv();
+        vec![
+            (Trace(column, 0) - 1.into()) / (X - 1.into()),
+            (Trace(column, 1) * self.denominator.clone()
+                - Trace(column, 0) * self.numerator.clone())
+                * every_row,
+        ]
+    }
+
+    /// The values of the column on `trace` for the given challenges.
+    ///
+    /// From the first row where the denominator vanishes on, the values do
+    /// not satisfy the constraints.
+    #[cfg(feature = "prover")]
+    pub(crate) fn values(
+        &self,
+        trace: &TraceTable,
+        challenges: &[FieldElement],
+    ) -> Vec<FieldElement> {
+        let numerator = self.numerator.with_challenges(challenges);
+        let denominator = self.denominator.with_challenges(challenges);
+        let trace_length = trace.num_rows();
+        let generator = trace.generator();
+        let quotient = |row: usize| {
+            let x = generator.pow(row);
+            let trace_value = |column: usize, offset: isize| {
+                // Sizes are small enough
+                #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+                let index = (row as isize + offset).rem_euclid(trace_length as isize) as usize;
+                trace[(index, column)].clone()
+            };
+            let denominator = denominator.evaluate(&x, &trace_value);
+            numerator.evaluate(&x, &trace_value) * denominator.inv().unwrap_or(FieldElement::ZERO)
+        };
+        let quotients: Vec<FieldElement> = if is_sequential() {
+            (0..trace_length).map(quotient).collect()
+        } else {
+            (0..trace_length).into_par_iter().map(quotient).collect()
+        };
+        let mut product = FieldElement::ONE;
+        let mut values = Vec::with_capacity(trace_length);
+        for quotient in quotients {
+            values.push(product.clone());
+            product *= quotient;
+        }
+        values
+    }
+}
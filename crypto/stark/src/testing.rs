@@ -0,0 +1,109 @@
+//! Random inputs for property tests of constraint systems.
+//!
+//! Enabled by the `test` feature, which also provides the `Arbitrary`
+//! implementations for `U256`, `FieldElement`, curve points and
+//! `DensePolynomial`. Downstream crates can use these to property test their
+//! own components against the prover and verifier.
+use crate::{Constraints, RationalExpression, TraceTable};
+use quickcheck::{Arbitrary, Gen};
+use std::prelude::v1::*;
+use zkp_primefield::FieldElement;
+
+/// Random trace tables of up to 16 rows and 4 columns.
+impl Arbitrary for TraceTable {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let trace_length = 1 << (usize::arbitrary(g) % 5);
+        let num_columns = 1 + usize::arbitrary(g) % 4;
+        let mut trace = Self::new(trace_length, num_columns);
+        for i in 0..trace_length {
+            for j in 0..num_columns {
+                trace[(i, j)] = FieldElement::arbitrary(g);
+            }
+        }
+        trace
+    }
+}
+
+/// A small random constraint system and a trace that satisfies it.
+///
+/// Every column follows an affine recurrence in the previous row, with random
+/// coefficients and a random first row. The constraints are the transition
+/// and the first row boundary for each column.
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct RandomAir {
+    pub constraints: Constraints,
+    pub trace:       TraceTable,
+}
+
+impl Arbitrary for RandomAir {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        use RationalExpression::*;
+        let trace_length = 1 << (3 + usize::arbitrary(g) % 3);
+        let num_columns = 1 + usize::arbitrary(g) % 3;
+        let mut random = |n: usize| (0..n).map(|_| FieldElement::arbitrary(g)).collect();
+        let first_row: Vec<FieldElement> = random(num_columns);
+        let coefficients: Vec<Vec<FieldElement>> =
+            (0..num_columns).map(|_| random(num_columns)).collect();
+        let constants: Vec<FieldElement> = random(num_columns);
+
+        let trace = TraceTable::from_recurrence(trace_length, first_row.clone(), |_, previous| {
+            coefficients
+                .iter()
+                .zip(&constants)
+                .map(|(row, constant)| {
+                    row.iter()
+                        .zip(previous)
+                        .fold(constant.clone(), |sum, (c, v)| sum + c * v)
+                })
+                .collect()
+        });
+
+        let generator = FieldElement::root(trace_length).unwrap();
+        let every_row =
+            || (X - Constant(generator.pow(trace_length - 1))) / (X.pow(trace_length) - 1.into());
+        let mut expressions = Vec::with_capacity(2 * num_columns);
+        for (j, (row, constant)) in coefficients.iter().zip(&constants).enumerate() {
+            let next = row
+                .iter()
+                .enumerate()
+                .fold(Constant(constant.clone()), |sum, (k, c)| {
+                    sum + Constant(c.clone()) * Trace(k, 0)
+                });
+            expressions.push((Trace(j, 1) - next) * every_row());
+        }
+        for (j, value) in first_row.iter().enumerate() {
+            expressions.push((Trace(j, 0) - Constant(value.clone())) / (X - 1.into()));
+        }
+        let constraints = Constraints::from_expressions(
+            (trace_length, num_columns),
+            b"random air".to_vec(),
+            expressions,
+        )
+        .unwrap();
+        Self { constraints, trace }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prove, verify};
+    use quickcheck::StdThreadGen;
+    use quickcheck_macros::quickcheck;
+
+    #[quickcheck]
+    fn random_air_is_satisfied(air: RandomAir) -> bool {
+        air.constraints.check_trace(&air.trace).is_ok()
+    }
+
+    #[test]
+    fn random_air_proves() {
+        let mut g = StdThreadGen::new(10);
+        for _ in 0..3 {
+            let air = RandomAir::arbitrary(&mut g);
+            let proof = prove(&air.constraints, &air.trace).unwrap();
+            assert_eq!(verify(&air.constraints, &proof), Ok(()));
+        }
+    }
+}
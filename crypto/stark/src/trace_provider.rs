@@ -0,0 +1,99 @@
+use crate::{parallelism::is_sequential, polynomial::DensePolynomial, TraceTable};
+use rayon::prelude::*;
+use std::{cmp::min, prelude::v1::*};
+use zkp_mmap_vec::MmapVec;
+use zkp_primefield::{
+    fft::{ifft_permuted, permute},
+    FieldElement,
+};
+
+/// Source of trace rows that are generated while proving.
+///
+/// The prover reads the rows segment by segment, in order, and copies them
+/// into the buffers it interpolates in place. The trace is thus held in
+/// memory once, instead of once as a [`TraceTable`] and once as polynomials.
+/// Interpolation needs complete columns, so the rows are not discarded
+/// before the whole trace is read.
+///
+/// [`TraceTable`]: struct.TraceTable.html
+pub trait TraceProvider {
+    fn num_rows(&self) -> usize;
+
+    fn num_columns(&self) -> usize;
+
+    /// Number of rows requested at a time.
+    fn segment_length(&self) -> usize {
+        1 << 12
+    }
+
+    /// Write the rows starting at `start` into `segment`.
+    ///
+    /// Segments are requested in order and cover the trace. The last one may
+    /// reach past the end of the trace, the rows beyond it are ignored.
+    fn fill_segment(&mut self, start: usize, segment: &mut TraceTable);
+}
+
+/// Read the rows of `provider` and interpolate the columns.
+pub(crate) fn interpolate<P: TraceProvider + ?Sized>(provider: &mut P) -> Vec<DensePolynomial> {
+    let num_rows = provider.num_rows();
+    let mut columns: Vec<MmapVec<FieldElement>> = (0..provider.num_columns())
+        .map(|_| MmapVec::with_capacity(num_rows))
+        .collect();
+    let segment_length = min(provider.segment_length(), num_rows);
+    let mut segment = TraceTable::new(segment_length, provider.num_columns());
+    let mut start = 0;
+    while start < num_rows {
+        provider.fill_segment(start, &mut segment);
+        let length = min(segment_length, num_rows - start);
+        for (j, column) in columns.iter_mut().enumerate() {
+            column.extend(segment.iter_column(j).take(length));
+        }
+        start += length;
+    }
+    let interpolate = |mut column: MmapVec<FieldElement>| {
+        ifft_permuted(&mut column);
+        permute(&mut column);
+        DensePolynomial::from_mmap_vec(column)
+    };
+    if is_sequential() {
+        columns.into_iter().map(interpolate).collect()
+    } else {
+        columns.into_par_iter().map(interpolate).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rows of `trace`, three at a time
+    struct Segments<'a>(&'a TraceTable);
+
+    impl TraceProvider for Segments<'_> {
+        fn num_rows(&self) -> usize {
+            self.0.num_rows()
+        }
+
+        fn num_columns(&self) -> usize {
+            self.0.num_columns()
+        }
+
+        fn segment_length(&self) -> usize {
+            3
+        }
+
+        fn fill_segment(&mut self, start: usize, segment: &mut TraceTable) {
+            for i in 0..segment.num_rows() {
+                if start + i < self.0.num_rows() {
+                    segment.set_row(i, &self.0.row(start + i));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn interpolate_test() {
+        let trace = TraceTable::from_rows(16, 2, |i| vec![i.into(), (i * i).into()]);
+        assert_eq!(interpolate(&mut Segments(&trace)), trace.interpolate());
+    }
+}
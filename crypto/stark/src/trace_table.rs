@@ -1,36 +1,157 @@
-use crate::polynomial::DensePolynomial;
+use crate::{parallelism::is_sequential, polynomial::DensePolynomial};
 use rayon::prelude::*;
 use std::{
     ops::{Index, IndexMut},
     prelude::v1::*,
 };
-use zkp_mmap_vec::MmapVec;
+use zkp_mmap_vec::{MmapVec, Storage};
 use zkp_primefield::{
     fft::{ifft_permuted, permute},
     FieldElement,
 };
 
+/// Order in which the values of a [`TraceTable`] are stored.
+///
+/// [`TraceTable`]: struct.TraceTable.html
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TraceLayout {
+    /// Rows are contiguous in memory and can be borrowed as slices.
+    RowMajor,
+    /// Columns are contiguous in memory and can be borrowed as slices. This
+    /// matches the interpolation and low degree extension, which work per
+    /// column, and avoids strided reads for wide traces.
+    ColumnMajor,
+}
+
+/// A table of field elements indexed by `(row, column)`.
+///
+/// Tables are row-major unless constructed with [`with_layout`]. Rows of a
+/// row-major table are available as slices through `trace[i]`, columns of a
+/// column-major table through [`column`] and [`columns`]. The prover reads
+/// either layout.
+///
+/// [`with_layout`]: #method.with_layout
+/// [`column`]: #method.column
+/// [`columns`]: #method.columns
+#[derive(Clone)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct TraceTable {
     trace_length: usize,
     num_columns:  usize,
+    layout:       TraceLayout,
     values:       MmapVec<FieldElement>,
 }
 
 impl TraceTable {
-    /// Constructs a zero-initialized trace table of the given size.
+    /// Constructs a zero-initialized row-major trace table of the given size.
     pub fn new(trace_length: usize, num_columns: usize) -> Self {
-        let mut values: MmapVec<FieldElement> = MmapVec::with_capacity(trace_length * num_columns);
-        for _ in 0..(trace_length * num_columns) {
-            values.push(FieldElement::ZERO);
-        }
+        Self::with_layout(trace_length, num_columns, TraceLayout::RowMajor)
+    }
+
+    /// Constructs a zero-initialized trace table with the given layout.
+    pub fn with_layout(trace_length: usize, num_columns: usize, layout: TraceLayout) -> Self {
+        Self::with_layout_in(trace_length, num_columns, layout, Storage::default())
+    }
+
+    /// Like [`with_layout`] with the values kept as selected by `storage`.
+    ///
+    /// [`with_layout`]: #method.with_layout
+    pub fn with_layout_in(
+        trace_length: usize,
+        num_columns: usize,
+        layout: TraceLayout,
+        storage: Storage,
+    ) -> Self {
+        let size = trace_length * num_columns;
+        let mut values: MmapVec<FieldElement> = MmapVec::with_capacity_in(size, storage);
+        values.resize(size, FieldElement::ZERO);
         Self {
             trace_length,
             num_columns,
+            layout,
             values,
         }
     }
 
+    pub fn layout(&self) -> TraceLayout {
+        self.layout
+    }
+
+    /// Convert the table to `layout`, transposing the values if needed.
+    pub fn into_layout(self, layout: TraceLayout) -> Self {
+        if self.layout == layout {
+            return self;
+        }
+        let mut result = Self::with_layout(self.trace_length, self.num_columns, layout);
+        for i in 0..self.trace_length {
+            for j in 0..self.num_columns {
+                result[(i, j)] = self[(i, j)].clone();
+            }
+        }
+        result
+    }
+
+    fn offset(&self, i: usize, j: usize) -> usize {
+        assert!(i < self.trace_length);
+        assert!(j < self.num_columns);
+        match self.layout {
+            TraceLayout::RowMajor => i * self.num_columns + j,
+            TraceLayout::ColumnMajor => j * self.trace_length + i,
+        }
+    }
+
+    fn assert_column_major(&self) {
+        assert_eq!(
+            self.layout,
+            TraceLayout::ColumnMajor,
+            "Column slices require a column-major trace table"
+        );
+    }
+
+    /// Constructs a trace table from independently computed rows.
+    ///
+    /// The closure is called once for each row index, possibly in parallel,
+    /// and must return `num_columns` values.
+    pub fn from_rows<F>(trace_length: usize, num_columns: usize, row: F) -> Self
+    where
+        F: Fn(usize) -> Vec<FieldElement> + Sync,
+    {
+        let rows = (0..trace_length)
+            .into_par_iter()
+            .map(|i| row(i))
+            .collect::<Vec<_>>();
+        let mut trace = Self::new(trace_length, num_columns);
+        for (i, row) in rows.iter().enumerate() {
+            trace.set_row(i, row);
+        }
+        trace
+    }
+
+    /// Constructs a trace table where each row is computed from the previous.
+    ///
+    /// The closure is called sequentially with the index of the row to compute
+    /// and the values of the row before it. The number of columns is taken
+    /// from `first_row`.
+    pub fn from_recurrence<F>(
+        trace_length: usize,
+        first_row: Vec<FieldElement>,
+        mut next: F,
+    ) -> Self
+    where
+        F: FnMut(usize, &[FieldElement]) -> Vec<FieldElement>,
+    {
+        let mut trace = Self::new(trace_length, first_row.len());
+        let mut row = first_row;
+        for i in 0..trace_length {
+            if i > 0 {
+                row = next(i, &row);
+            }
+            trace.set_row(i, &row);
+        }
+        trace
+    }
+
     pub fn num_rows(&self) -> usize {
         self.trace_length
     }
@@ -44,49 +165,176 @@ impl TraceTable {
     }
 
     pub fn iter_row(&self, i: usize) -> impl Iterator<Item = &FieldElement> {
-        // Delegate to Index<usize> which returns a row slice.
-        self[i].iter()
+        let start = self.offset(i, 0);
+        match self.layout {
+            TraceLayout::RowMajor => {
+                self.values[start..start + self.num_columns]
+                    .iter()
+                    .step_by(1)
+            }
+            TraceLayout::ColumnMajor => self.values[start..].iter().step_by(self.trace_length),
+        }
     }
 
     pub fn iter_column(&self, j: usize) -> impl Iterator<Item = &FieldElement> {
-        self.values[j..].iter().step_by(self.num_columns)
+        let start = self.offset(0, j);
+        match self.layout {
+            TraceLayout::RowMajor => self.values[start..].iter().step_by(self.num_columns),
+            TraceLayout::ColumnMajor => {
+                self.values[start..start + self.trace_length]
+                    .iter()
+                    .step_by(1)
+            }
+        }
+    }
+
+    /// Returns the j-th column as a slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is not column-major.
+    pub fn column(&self, j: usize) -> &[FieldElement] {
+        self.assert_column_major();
+        assert!(j < self.num_columns);
+        &self.values[j * self.trace_length..(j + 1) * self.trace_length]
+    }
+
+    /// Returns the j-th column as a mutable slice.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is not column-major.
+    pub fn column_mut(&mut self, j: usize) -> &mut [FieldElement] {
+        self.assert_column_major();
+        assert!(j < self.num_columns);
+        &mut self.values[j * self.trace_length..(j + 1) * self.trace_length]
+    }
+
+    /// Iterate over the columns as slices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is not column-major.
+    pub fn columns(&self) -> impl Iterator<Item = &[FieldElement]> {
+        self.assert_column_major();
+        // `chunks` panics on zero sized chunks
+        self.values.chunks(std::cmp::max(self.trace_length, 1))
+    }
+
+    /// Iterate over the columns as slices in parallel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is not column-major.
+    pub fn par_columns(&self) -> impl IndexedParallelIterator<Item = &[FieldElement]> {
+        self.assert_column_major();
+        self.values.par_chunks(std::cmp::max(self.trace_length, 1))
+    }
+
+    /// Iterate over the columns as mutable slices in parallel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the table is not column-major.
+    pub fn par_columns_mut(&mut self) -> impl IndexedParallelIterator<Item = &mut [FieldElement]> {
+        self.assert_column_major();
+        self.values
+            .par_chunks_mut(std::cmp::max(self.trace_length, 1))
+    }
+
+    /// Copies the i-th row into a vector.
+    pub fn row(&self, i: usize) -> Vec<FieldElement> {
+        self.iter_row(i).cloned().collect()
+    }
+
+    /// Overwrites the i-th row with the given values.
+    pub fn set_row(&mut self, i: usize, values: &[FieldElement]) {
+        assert_eq!(values.len(), self.num_columns);
+        for (j, value) in values.iter().enumerate() {
+            self[(i, j)] = value.clone();
+        }
     }
 
     /// Extract the j-th column as a vector
     ///
     /// It allocates a potentially large new vector. Where possible, use
-    /// the index accessors or the column iterator instead. It is unfortunately
-    /// not possible to get a slice of a column (since the representation is
-    /// row first.)
-    // TODO: Use strides
+    /// `column` on a column-major table instead.
     pub fn column_to_mmapvec(&self, j: usize) -> MmapVec<FieldElement> {
         let mut result: MmapVec<FieldElement> = MmapVec::with_capacity(self.trace_length);
-        for v in self.iter_column(j) {
-            result.push(v.clone());
-        }
+        result.extend(self.iter_column(j));
         result
     }
 
+    /// The smallest period of the j-th column.
+    ///
+    /// The period is a power of two, the trace length for columns without a
+    /// shorter period. Constant columns have period one.
+    ///
+    /// Periodic columns are cheaper to interpolate and extend, but their
+    /// Merkle leaves still cost the same as any other column. If the verifier
+    /// knows the values, they should be a [`PeriodicColumn`] in the
+    /// constraints instead, which removes them from the trace.
+    ///
+    /// [`PeriodicColumn`]: struct.PeriodicColumn.html
+    pub fn column_period(&self, j: usize) -> usize {
+        let mut period = 1;
+        while period < self.trace_length
+            && !self
+                .iter_column(j)
+                .skip(period)
+                .zip(self.iter_column(j))
+                .all(|(value, start)| value == start)
+        {
+            period *= 2;
+        }
+        period
+    }
+
+    /// Interpolate the columns.
+    ///
+    /// A column with period `p` only needs an inverse FFT of size `p`, see
+    /// [`column_period`].
+    ///
+    /// [`column_period`]: #method.column_period
     pub fn interpolate(&self) -> Vec<DensePolynomial> {
-        (0..self.num_columns())
-            .into_par_iter()
-            // OPT: Use and FFT that can transform the entire table in one pass,
-            // working on whole rows at a time. That is, it is vectorized over rows.
-            // OPT: Use an in-place FFT. We don't need the trace table after this,
-            // so it can be replaced by a matrix of coefficients.
-            .map(|j| {
-                // Copy column to vec
-                let mut vec = MmapVec::with_capacity(self.num_rows());
-                for v in self.iter_column(j) {
-                    vec.push(v.clone());
-                }
-
-                // Transform to coefficients
-                ifft_permuted(&mut vec);
-                permute(&mut vec);
-                DensePolynomial::from_mmap_vec(vec)
-            })
-            .collect::<Vec<DensePolynomial>>()
+        // OPT: Use an in-place FFT. We don't need the trace table after this,
+        // so it can be replaced by a matrix of coefficients.
+        let interpolate_column = |j: usize| {
+            let period = self.column_period(j);
+
+            // Copy one period of the column to vec, transposing row-major
+            // tables on the fly.
+            let mut vec = MmapVec::with_capacity(period);
+            match self.layout {
+                TraceLayout::RowMajor => vec.extend(self.iter_column(j).take(period)),
+                TraceLayout::ColumnMajor => vec.extend_from_slice(&self.column(j)[..period]),
+            }
+
+            // Transform to coefficients
+            ifft_permuted(&mut vec);
+            permute(&mut vec);
+            if period == self.trace_length {
+                return DensePolynomial::from_mmap_vec(vec);
+            }
+
+            // The column is Q(X^(trace_length / period)) where Q interpolates
+            // one period on the subgroup of that size.
+            let stride = self.trace_length / period;
+            let mut coefficients = MmapVec::with_capacity(self.trace_length);
+            coefficients.resize(self.trace_length, FieldElement::ZERO);
+            for (i, coefficient) in vec.iter().enumerate() {
+                coefficients[i * stride] = coefficient.clone();
+            }
+            DensePolynomial::from_mmap_vec(coefficients)
+        };
+        if is_sequential() {
+            (0..self.num_columns).map(interpolate_column).collect()
+        } else {
+            (0..self.num_columns)
+                .into_par_iter()
+                .map(interpolate_column)
+                .collect()
+        }
     }
 }
 
@@ -95,35 +343,135 @@ impl Index<(usize, usize)> for TraceTable {
     type Output = FieldElement;
 
     fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
-        assert!(i < self.trace_length);
-        assert!(j < self.num_columns);
-        &self.values[i * self.num_columns + j]
+        &self.values[self.offset(i, j)]
     }
 }
 
 /// Returns a mutable field
 impl IndexMut<(usize, usize)> for TraceTable {
     fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Self::Output {
-        assert!(i < self.trace_length);
-        assert!(j < self.num_columns);
-        &mut self.values[i * self.num_columns + j]
+        let offset = self.offset(i, j);
+        &mut self.values[offset]
     }
 }
 
 /// Returns a row as a slice
+///
+/// # Panics
+///
+/// Panics if the table is not row-major.
 impl Index<usize> for TraceTable {
     type Output = [FieldElement];
 
     fn index(&self, i: usize) -> &[FieldElement] {
+        assert_eq!(self.layout, TraceLayout::RowMajor);
         assert!(i < self.trace_length);
         &self.values[i * self.num_columns..(i + 1) * self.num_columns]
     }
 }
 
 /// Returns a mutable row as a slice
+///
+/// # Panics
+///
+/// Panics if the table is not row-major.
 impl IndexMut<usize> for TraceTable {
     fn index_mut(&mut self, i: usize) -> &mut [FieldElement] {
+        assert_eq!(self.layout, TraceLayout::RowMajor);
         assert!(i < self.trace_length);
         &mut self.values[i * self.num_columns..(i + 1) * self.num_columns]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_major_test() {
+        let mut trace = TraceTable::with_layout(4, 3, TraceLayout::ColumnMajor);
+        for i in 0..4 {
+            for j in 0..3 {
+                trace[(i, j)] = FieldElement::from(10 * i + j);
+            }
+        }
+        assert_eq!(trace.column(1), &[
+            1.into(),
+            11.into(),
+            21.into(),
+            31.into()
+        ]);
+        assert_eq!(trace.row(2), vec![20.into(), 21.into(), 22.into()]);
+        assert_eq!(trace.columns().count(), 3);
+
+        trace.column_mut(2)[3] = 5.into();
+        assert_eq!(trace[(3, 2)], 5.into());
+        trace.set_row(0, &[7.into(), 8.into(), 9.into()]);
+        assert_eq!(trace.column(2)[0], 9.into());
+
+        trace
+            .par_columns_mut()
+            .for_each(|column| column[1] = FieldElement::ZERO);
+        assert_eq!(trace.row(1), vec![FieldElement::ZERO; 3]);
+    }
+
+    #[test]
+    fn row_major_test() {
+        let mut trace = TraceTable::new(4, 3);
+        assert_eq!(trace.layout(), TraceLayout::RowMajor);
+        trace[2][1] = 5.into();
+        trace[(3, 2)] = 7.into();
+        assert_eq!(trace[(2, 1)], 5.into());
+        assert_eq!(trace[3], [0.into(), 0.into(), 7.into()]);
+        assert_eq!(trace.iter_column(1).cloned().collect::<Vec<_>>(), vec![
+            0.into(),
+            0.into(),
+            5.into(),
+            0.into()
+        ]);
+
+        let transposed = trace.clone().into_layout(TraceLayout::ColumnMajor);
+        assert_eq!(transposed.column(2), &[
+            0.into(),
+            0.into(),
+            0.into(),
+            7.into()
+        ]);
+        assert_eq!(transposed.row(2), trace.row(2));
+        assert_eq!(transposed.interpolate(), trace.interpolate());
+    }
+
+    #[test]
+    fn periodic_column_test() {
+        let trace = TraceTable::from_rows(16, 4, |i| {
+            vec![7.into(), (i % 4).into(), (i % 8 / 7).into(), (i * i).into()]
+        });
+        let periods: Vec<usize> = (0..4).map(|j| trace.column_period(j)).collect();
+        assert_eq!(periods, vec![1, 4, 8, 16]);
+
+        for (j, polynomial) in trace.interpolate().iter().enumerate() {
+            let mut coefficients: Vec<_> = trace.iter_column(j).cloned().collect();
+            ifft_permuted(&mut coefficients);
+            permute(&mut coefficients);
+            assert_eq!(polynomial.coefficients(), coefficients.as_slice());
+            assert_eq!(polynomial.stride(), 16 / periods[j]);
+        }
+    }
+
+    #[test]
+    fn from_rows_test() {
+        let trace = TraceTable::from_rows(8, 2, |i| vec![i.into(), (i * i).into()]);
+        assert_eq!(trace.num_columns(), 2);
+        assert_eq!(trace[(5, 0)], 5.into());
+        assert_eq!(trace[(7, 1)], 49.into());
+    }
+
+    #[test]
+    fn from_recurrence_test() {
+        let trace = TraceTable::from_recurrence(8, vec![1.into(), 1.into()], |_, previous| {
+            vec![previous[1].clone(), &previous[0] + &previous[1]]
+        });
+        assert_eq!(trace.row(0), vec![1.into(), 1.into()]);
+        assert_eq!(trace.row(7), vec![21.into(), 34.into()]);
+    }
+}
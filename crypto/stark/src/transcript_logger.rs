@@ -0,0 +1,122 @@
+use std::{fmt::Write, prelude::v1::*};
+
+/// An interaction with the channel.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TranscriptEntry {
+    /// One of `seed`, `label`, `write` or `random`. Writes are messages sent by
+    /// the prover or read by the verifier.
+    pub label: &'static str,
+    /// The seed, the message or the random bytes drawn.
+    pub bytes: Vec<u8>,
+    /// The channel digest after the interaction.
+    pub state: [u8; 32],
+}
+
+/// Record of all Fiat-Shamir interactions of a prover or verifier.
+///
+/// Use with [`prove_logged`] and [`verify_logged`]. The prover and the
+/// verifier of a valid proof produce the same transcript, so comparing the
+/// transcript of another implementation against one of these pinpoints the
+/// first interaction where they diverge.
+///
+/// [`prove_logged`]: fn.prove_logged.html
+/// [`verify_logged`]: fn.verify_logged.html
+#[derive(Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct TranscriptLogger {
+    entries: Vec<TranscriptEntry>,
+}
+
+impl TranscriptLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Index of the first entry that differs from `other`, or `None` if the
+    /// transcripts are the same.
+    pub fn first_mismatch(&self, other: &Self) -> Option<usize> {
+        let common = self
+            .entries
+            .iter()
+            .zip(&other.entries)
+            .position(|(a, b)| a != b);
+        common.or_else(|| {
+            if self.entries.len() == other.entries.len() {
+                None
+            } else {
+                Some(std::cmp::min(self.entries.len(), other.entries.len()))
+            }
+        })
+    }
+
+    /// The transcript as a JSON array of objects with `label`, `bytes` and
+    /// `state` fields. The byte strings are `0x` prefixed hexadecimal.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, entry) in self.entries.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("\n  {\"label\": \"");
+            json.push_str(entry.label);
+            json.push_str("\", \"bytes\": \"");
+            write_hex(&mut json, &entry.bytes);
+            json.push_str("\", \"state\": \"");
+            write_hex(&mut json, &entry.state);
+            json.push_str("\"}");
+        }
+        json.push_str("\n]\n");
+        json
+    }
+
+    pub(crate) fn log(&mut self, label: &'static str, bytes: &[u8], state: &[u8; 32]) {
+        self.entries.push(TranscriptEntry {
+            label,
+            bytes: bytes.to_vec(),
+            state: *state,
+        });
+    }
+}
+
+fn write_hex(string: &mut String, bytes: &[u8]) {
+    string.push_str("0x");
+    for byte in bytes {
+        // Writing to a `String` can not fail
+        let _ = write!(string, "{:02x}", byte);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_json_test() {
+        let mut logger = TranscriptLogger::new();
+        assert_eq!(logger.to_json(), "[\n]\n");
+        logger.log("write", &[1, 171], &[0; 32]);
+        assert_eq!(
+            logger.to_json(),
+            "[\n  {\"label\": \"write\", \"bytes\": \"0x01ab\", \"state\": \
+             \"0x0000000000000000000000000000000000000000000000000000000000000000\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn first_mismatch_test() {
+        let mut a = TranscriptLogger::new();
+        a.log("seed", &[1], &[1; 32]);
+        a.log("write", &[2], &[2; 32]);
+        let mut b = a.clone();
+        assert_eq!(a.first_mismatch(&b), None);
+        b.log("random", &[3], &[2; 32]);
+        assert_eq!(a.first_mismatch(&b), Some(2));
+        b.entries[1].state = [3; 32];
+        assert_eq!(b.first_mismatch(&a), Some(1));
+    }
+}
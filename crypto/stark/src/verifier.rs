@@ -1,12 +1,21 @@
 use crate::{
-    channel::*, constraints::Constraints, polynomial::DensePolynomial, proof_of_work, Proof,
+    channel::*, constraints::Constraints, fri::FriVerifier, metrics::Phases, proof_of_work,
+    transcript_logger::TranscriptLogger, ClaimBytes, Proof, Verifiable,
 };
 #[cfg(feature = "std")]
+use rayon::prelude::*;
+#[cfg(feature = "std")]
 use std::error;
-use std::{collections::BTreeMap, convert::TryInto, fmt, prelude::v1::*};
-use zkp_hash::Hash;
-use zkp_merkle_tree::{Commitment, Error as MerkleError, Proof as MerkleProof};
-use zkp_primefield::{fft, geometric_series::root_series, FieldElement};
+use std::{collections::BTreeMap, fmt, prelude::v1::*};
+#[cfg(feature = "pedersen")]
+use zkp_elliptic_curve_crypto::Pedersen;
+#[cfg(feature = "blake3")]
+use zkp_hash::Blake3;
+use zkp_hash::{Blake2s, Hash, Hashable, Hasher, MaskedKeccak, Poseidon, Rescue};
+use zkp_merkle_tree::{
+    Commitment, Error as MerkleError, Proof as MerkleProof, Salted, VerifiedNodes,
+};
+use zkp_primefield::{fft, FieldElement};
 use zkp_u256::U256;
 
 type Result<T> = std::result::Result<T, Error>;
@@ -18,6 +27,8 @@ pub enum Error {
     RootUnavailable,
     InvalidPoW,
     InvalidLDECommitment,
+    InvalidInteractionCommitment,
+    InvalidExternalCommitment,
     InvalidConstraintCommitment,
     InvalidFriCommitment,
     HashMapFailure,
@@ -25,9 +36,38 @@ pub enum Error {
     OodsCalculationFailure,
     OodsMismatch,
     FriCalculationFailure,
+    /// The proof was made with a different hash than the one requested.
+    HashMismatch,
+    /// The proof header names a hash that is not supported.
+    UnsupportedHash,
+    /// The proof parameters are below the minimum security level.
+    InsufficientSecurity,
+    /// The opening point of a polynomial commitment is in the evaluation
+    /// domain.
+    InvalidEvaluationPoint,
+    /// The proof ended before the verifier read everything it needs.
+    ProofTooShort,
+    /// The proof or constraint parameters are malformed or exceed the
+    /// verifier limits, see [`MAX_TRACE_LENGTH`] and friends.
+    UnsupportedParameters,
     Merkle(MerkleError),
 }
 
+/// Largest proof transcript in bytes the verifier accepts.
+pub const MAX_PROOF_SIZE: usize = 1 << 26;
+
+/// Largest trace length the verifier accepts.
+pub const MAX_TRACE_LENGTH: usize = 1 << 26;
+
+/// Largest evaluation domain (trace length times blowup) the verifier accepts.
+pub const MAX_DOMAIN_SIZE: usize = 1 << 30;
+
+/// Largest number of FRI layers the verifier accepts.
+pub const MAX_FRI_LAYERS: usize = 32;
+
+/// Largest number of queries the verifier accepts.
+pub const MAX_QUERIES: usize = 1024;
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use Error::*;
@@ -35,6 +75,12 @@ impl fmt::Display for Error {
             RootUnavailable => write!(f, "The prime field doesn't have a root of this order"),
             InvalidPoW => write!(f, "The suggested proof of work failed to verify"),
             InvalidLDECommitment => write!(f, "The LDE merkle proof is incorrect"),
+            InvalidInteractionCommitment => {
+                write!(f, "The interaction trace merkle proof is incorrect")
+            }
+            InvalidExternalCommitment => {
+                write!(f, "The external commitment merkle proof is incorrect")
+            }
             InvalidConstraintCommitment => write!(f, "The constraint merkle proof is incorrect"),
             InvalidFriCommitment => write!(f, "A FRI layer commitment is incorrect"),
             HashMapFailure => {
@@ -57,6 +103,27 @@ impl fmt::Display for Error {
                 )
             }
             OodsMismatch => write!(f, "Calculated oods value doesn't match the committed one"),
+            HashMismatch => write!(f, "The proof was made with a different hash function"),
+            UnsupportedHash => write!(f, "The proof uses an unsupported hash function"),
+            InsufficientSecurity => {
+                write!(
+                    f,
+                    "The proof parameters are below the minimum security level"
+                )
+            }
+            InvalidEvaluationPoint => {
+                write!(
+                    f,
+                    "The evaluation point is in the polynomial commitment domain"
+                )
+            }
+            ProofTooShort => write!(f, "The proof ended unexpectedly"),
+            UnsupportedParameters => {
+                write!(
+                    f,
+                    "The proof parameters are malformed or exceed the verifier limits"
+                )
+            }
             // This is a wrapper, so defer to the underlying types' implementation of `fmt`.
             Merkle(ref e) => std::fmt::Display::fmt(e, f),
         }
@@ -79,11 +146,17 @@ impl From<MerkleError> for Error {
 /// A `ConstraintSystem` which captures the claim that is made.
 /// A `ProofParams` object which configures the proof.
 ///
+/// The hash is taken from the proof header. The hashes in `zkp-hash` are
+/// always supported, except Blake3 which requires the `blake3` feature. The
+/// Pedersen hash requires the `pedersen` feature.
+///
 /// ## Verification process
 ///
 /// ### Step 1: Read all commitments and draw random values
 ///
 /// * Read the trace polynomial commitment commitment.
+/// * If there is an interaction trace, draw the challenges and read the
+/// interaction trace commitment.
 /// * Draw the constraint combination coefficients $\alpha_i$ and $\beta_i$.
 /// * Read the combined constraint polynomial commitment.
 /// * Draw the deep point $z$.
@@ -106,12 +179,19 @@ impl From<MerkleError> for Error {
 /// ### Step 3: Read query decommitments
 ///
 /// * Draw query indices
+/// * Read evaluations and verify merkle decommitments for the externally
+/// committed trace columns, if any, against the root from the claim
 /// * Read evaluations of trace polynomial
 /// $T_0(x_0), T_1(x_0), \dots, T_0(x_1), T_1(x_1), \dots$
 /// * Read and verify merkle decommitments for trace polynomial
+/// * Read evaluations and verify merkle decommitments for the interaction
+/// trace, if any
 /// * Read evaluations of the combined constraint polynomial
 /// $A_0(x_0), A_1(x_0), \dots, A_0(x_1), A_1(x_1), \dots$
 /// * Read and verify merkle decommitments for combined constraint polynomial
+/// * In zero-knowledge proofs the leaves are salted, and the salts of the
+/// queried leaves follow their evaluations. The constraint commitment ends
+/// with a random mask polynomial that is not part of $C'(z)$.
 ///
 /// ### Step 4: FRI decommitments and final layer verification
 ///
@@ -167,255 +247,484 @@ impl From<MerkleError> for Error {
 /// * Evaluate the final layer
 ///
 /// <!-- TODO: ellaborate FRI verification -->
+pub fn verify(constraints: &Constraints, proof: &Proof) -> Result<()> {
+    verify_reusing(constraints, proof, None)
+}
+
+// Like `verify`, but take the Merkle nodes authenticated by earlier proofs
+// from `verified` and add the ones of this proof, see `verify_batch`.
+fn verify_reusing(
+    constraints: &Constraints,
+    proof: &Proof,
+    verified: Option<&VerifiedNodes>,
+) -> Result<()> {
+    match proof.hash_id() {
+        MaskedKeccak::ID => verify_nodes::<MaskedKeccak>(constraints, proof, verified),
+        Poseidon::ID => verify_nodes::<Poseidon>(constraints, proof, verified),
+        Blake2s::ID => verify_nodes::<Blake2s>(constraints, proof, verified),
+        #[cfg(feature = "blake3")]
+        Blake3::ID => verify_nodes::<Blake3>(constraints, proof, verified),
+        Rescue::ID => verify_nodes::<Rescue>(constraints, proof, verified),
+        #[cfg(feature = "pedersen")]
+        Pedersen::ID => verify_nodes::<Pedersen>(constraints, proof, verified),
+        _ => Err(Error::UnsupportedHash),
+    }
+}
+
+fn verify_nodes<H: Hasher>(
+    constraints: &Constraints,
+    proof: &Proof,
+    verified: Option<&VerifiedNodes>,
+) -> Result<()> {
+    let mut channel = VerifierChannel::<HashChain<H>>::new(proof.as_bytes().to_vec());
+    verify_channel::<H, _>(constraints, proof, &mut channel, verified)
+}
+
+/// Verify a proof made with the hash `H`.
+///
+/// Unlike [`verify`], this also works for hashes that are not built into
+/// `zkp-hash`.
+pub fn verify_with<H: Hasher>(constraints: &Constraints, proof: &Proof) -> Result<()> {
+    verify_with_transcript::<H, HashChain<H>>(constraints, proof)
+}
+
+/// Verify a proof made with the hash `H` and the transcript `T`.
+///
+/// The proof records the hash but not the transcript, so both sides must
+/// agree on it. See [`Transcript`].
+///
+/// [`Transcript`]: trait.Transcript.html
+pub fn verify_with_transcript<H: Hasher, T: Transcript>(
+    constraints: &Constraints,
+    proof: &Proof,
+) -> Result<()> {
+    let mut channel = VerifierChannel::<T>::new(proof.as_bytes().to_vec());
+    verify_channel::<H, T>(constraints, proof, &mut channel, None)
+}
+
+/// Verify a proof made with the hash `H` and append all channel interactions
+/// to `logger`.
+pub fn verify_logged<H: Hasher>(
+    constraints: &Constraints,
+    proof: &Proof,
+    logger: &mut TranscriptLogger,
+) -> Result<()> {
+    let mut channel = VerifierChannel::<HashChain<H>>::new(proof.as_bytes().to_vec());
+    channel.coin.logger = Some(std::mem::take(logger));
+    let result = verify_channel::<H, _>(constraints, proof, &mut channel, None);
+    *logger = channel.coin.logger.take().unwrap_or_default();
+    result
+}
+
+/// Verify many claims and proofs and return the result for each of them.
+///
+/// The result for each entry is that of [`Verifiable::verify`]. Entries with
+/// the same claim bytes and proof are verified once. The other entries are
+/// verified in parallel, or sequentially without `std`.
+///
+/// Entries share the Merkle nodes of the trace and constraint commitments
+/// they authenticate, see [`VerifiedNodes`]. When proofs commit to the same
+/// tree, for example proofs of different claims about the same trace, the
+/// decommitment paths through verified nodes are not hashed again. FRI layers
+/// depend on all earlier challenges and are not shared.
+///
+/// [`Verifiable::verify`]: trait.Verifiable.html#method.verify
+/// [`VerifiedNodes`]: ../zkp_merkle_tree/struct.VerifiedNodes.html
+pub fn verify_batch<V>(batch: &[(V, Proof)]) -> Vec<Result<()>>
+where
+    V: Verifiable + ClaimBytes + Sync,
+{
+    // Index of the first occurrence of each entry, by the digest of its
+    // claim bytes and proof. The length separates the claim from the proof.
+    let mut seen = BTreeMap::new();
+    let first: Vec<usize> = batch
+        .iter()
+        .enumerate()
+        .map(|(index, (claim, proof))| {
+            let claim = claim.claim_bytes();
+            let digest = Blake2s::digest(&[&claim.len().claim_bytes(), &claim, proof.as_bytes()]);
+            *seen.entry(digest).or_insert(index)
+        })
+        .collect();
+    let unique: Vec<usize> = (0..batch.len())
+        .filter(|&index| first[index] == index)
+        .collect();
+    let verified = VerifiedNodes::new();
+    let verify_entry = |&index: &usize| {
+        let (claim, proof) = &batch[index];
+        let result = verify_reusing(&claim.constraints(), proof, Some(&verified));
+        (index, result)
+    };
+    #[cfg(feature = "std")]
+    let results: Vec<(usize, Result<()>)> = unique.par_iter().map(verify_entry).collect();
+    #[cfg(not(feature = "std"))]
+    let results: Vec<(usize, Result<()>)> = unique.iter().map(verify_entry).collect();
+    first
+        .iter()
+        .map(|index| {
+            let position = unique.binary_search(index).unwrap();
+            results[position].1
+        })
+        .collect()
+}
+
+// Reject parameters that would make the verifier allocate or compute
+// unreasonable amounts before it gets to reject the proof. Constraints can
+// come from untrusted sources too, see `Constraints::from_json`.
+fn check_limits(constraints: &Constraints, proof: &Proof) -> Result<()> {
+    if proof.as_bytes().len() > MAX_PROOF_SIZE {
+        return Err(Error::ProofTooLong);
+    }
+    let trace_length = constraints.trace_nrows();
+    let domain_size = trace_length
+        .checked_mul(constraints.blowup)
+        .ok_or(Error::UnsupportedParameters)?;
+    if trace_length > MAX_TRACE_LENGTH
+        || !constraints.blowup.is_power_of_two()
+        || domain_size > MAX_DOMAIN_SIZE
+        || constraints.num_queries > MAX_QUERIES
+        || constraints.fri_layout.is_empty()
+        || constraints.fri_layout.len() > MAX_FRI_LAYERS
+        || constraints.fri_layout.iter().sum::<usize>() > domain_size.trailing_zeros() as usize
+    {
+        return Err(Error::UnsupportedParameters);
+    }
+    Ok(())
+}
+
+fn verify_channel<H: Hasher, T: Transcript>(
+    constraints: &Constraints,
+    proof: &Proof,
+    channel: &mut VerifierChannel<T>,
+    verified: Option<&VerifiedNodes>,
+) -> Result<()> {
+    if proof.hash_id() != H::ID {
+        return Err(Error::HashMismatch);
+    }
+    if proof.security_level(constraints) < constraints.min_security_bits {
+        return Err(Error::InsufficientSecurity);
+    }
+    // Zero-knowledge proofs are about the blinded trace
+    let blinded;
+    let constraints = if constraints.zero_knowledge {
+        blinded = constraints.blinded();
+        &blinded
+    } else {
+        constraints
+    };
+    check_limits(constraints, proof)?;
+    let result = replay_channel::<H, T>(constraints, channel, verified);
+    // Whatever failed, the root cause is a truncated proof
+    if channel.is_exhausted() {
+        return Err(Error::ProofTooShort);
+    }
+    result
+}
+
 // TODO: Refactor into smaller function
 #[allow(clippy::too_many_lines)]
-pub fn verify(constraints: &Constraints, proof: &Proof) -> Result<()> {
-    let proof = proof.as_bytes();
+fn replay_channel<H: Hasher, T: Transcript>(
+    constraints: &Constraints,
+    channel: &mut VerifierChannel<T>,
+    verified: Option<&VerifiedNodes>,
+) -> Result<()> {
     let trace_length = constraints.trace_nrows();
     let trace_cols = constraints.trace_ncolumns();
+    let external_cols = constraints.external_ncolumns();
+    let interaction_cols = constraints.interaction_ncolumns();
+    let trace_values_len = constraints.trace_offsets().len() * (trace_cols + interaction_cols);
     let eval_domain_size = trace_length * constraints.blowup;
-    let eval_x = root_series(eval_domain_size).collect::<Vec<_>>();
+    let eval_root = FieldElement::root(eval_domain_size).ok_or(Error::RootUnavailable)?;
+
+    let external_commitment = match constraints.external_commitment() {
+        Some(external) => {
+            Some(Commitment::from_size_hash(
+                eval_domain_size,
+                external.root(),
+            )?)
+        }
+        None => None,
+    };
 
-    let mut channel = VerifierChannel::new(proof.to_vec());
+    let mut phases = Phases::verify();
+    phases.start("commitments", None);
+    channel.coin.layout = constraints.channel_layout;
     channel.initialize(constraints.channel_seed());
 
     // Get the low degree root commitment, and constraint root commitment
     // TODO: Make it work as channel.read()
-    let low_degree_extension_root = Replayable::<Hash>::replay(&mut channel);
+    channel.label(Label::TraceCommitment);
+    let low_degree_extension_root = Replayable::<Hash>::replay(channel);
     let lde_commitment = Commitment::from_size_hash(eval_domain_size, &low_degree_extension_root)?;
+    let mut challenges: Vec<FieldElement> = Vec::with_capacity(constraints.num_challenges());
+    if constraints.num_challenges() > 0 {
+        channel.label(Label::Challenges);
+        for _ in 0..constraints.num_challenges() {
+            challenges.push(channel.get_random());
+        }
+    }
+    let interaction_commitment = if interaction_cols > 0 {
+        channel.label(Label::InteractionCommitment);
+        let root = Replayable::<Hash>::replay(channel);
+        Some(Commitment::from_size_hash(eval_domain_size, &root)?)
+    } else {
+        None
+    };
+    channel.label(Label::ConstraintCoefficients);
     let mut constraint_coefficients: Vec<FieldElement> = Vec::with_capacity(2 * constraints.len());
     for _ in 0..constraints.len() {
         constraint_coefficients.push(channel.get_random());
         constraint_coefficients.push(channel.get_random());
     }
-    let constraint_evaluated_root = Replayable::<Hash>::replay(&mut channel);
+    channel.label(Label::ConstraintCommitment);
+    let constraint_evaluated_root = Replayable::<Hash>::replay(channel);
     let constraint_commitment =
         Commitment::from_size_hash(eval_domain_size, &constraint_evaluated_root)?;
 
     // Get the oods information from the proof and random
+    channel.label(Label::OodsPoint);
     let oods_point: FieldElement = channel.get_random();
-    let mut oods_values: Vec<FieldElement> = Vec::with_capacity(2 * trace_cols + 1);
+    let oods_points = constraints.trace_offset_points(&oods_point);
     let constraints_trace_degree = constraints.degree();
-    for _ in 0..(2 * trace_cols + constraints_trace_degree) {
-        oods_values.push(Replayable::<FieldElement>::replay(&mut channel));
+    let constraint_cols = constraints.constraint_ncolumns();
+    let constraint_point = oods_point.pow(constraints_trace_degree);
+    let mut oods_values: Vec<FieldElement> = Vec::with_capacity(trace_values_len + constraint_cols);
+    channel.label(Label::OodsValues);
+    for _ in 0..(trace_values_len + constraint_cols) {
+        oods_values.push(Replayable::<FieldElement>::replay(channel));
     }
-    let mut oods_coefficients: Vec<FieldElement> = Vec::with_capacity(2 * trace_cols + 1);
-    for _ in 0..2 * trace_cols + constraints_trace_degree {
+    let mut oods_coefficients: Vec<FieldElement> =
+        Vec::with_capacity(trace_values_len + constraint_cols);
+    channel.label(Label::OodsCoefficients);
+    for _ in 0..trace_values_len + constraint_cols {
         oods_coefficients.push(channel.get_random());
     }
 
-    let mut fri_commitments: Vec<Commitment> = Vec::with_capacity(constraints.fri_layout.len() + 1);
-    let mut eval_points: Vec<FieldElement> = Vec::with_capacity(constraints.fri_layout.len() + 1);
-    let mut fri_size = eval_domain_size >> constraints.fri_layout[0];
-    // Get first fri root:
-    fri_commitments.push(Commitment::from_size_hash(
-        fri_size,
-        &Replayable::<Hash>::replay(&mut channel),
-    )?);
-    // Get fri roots and eval points from the channel random
-    for &x in constraints.fri_layout.iter().skip(1) {
-        fri_size >>= x;
-        // TODO: When is x equal to zero?
-        let eval_point = if x == 0 {
-            FieldElement::ONE
-        } else {
-            channel.get_random()
-        };
-        eval_points.push(eval_point);
-        fri_commitments.push(Commitment::from_size_hash(
-            fri_size,
-            &Replayable::<Hash>::replay(&mut channel),
-        )?);
-    }
-    // Gets the last layer and the polynomial coefficients
-    eval_points.push(channel.get_random());
-    let last_layer_coefficient: Vec<FieldElement> =
-        Replayable::<FieldElement>::replay_many(&mut channel, fri_size / constraints.blowup);
+    let fri_params = constraints.fri_params();
+    let fri = FriVerifier::read_commitments(&fri_params, eval_domain_size, channel)?;
 
     // Gets the proof of work from the proof.
+    phases.start("proof_of_work", None);
+    channel.label(Label::ProofOfWork);
     let pow_seed: proof_of_work::ChallengeSeed = channel.get_random();
     let pow_challenge = pow_seed.with_difficulty(constraints.pow_bits);
-    let pow_response = Replayable::<proof_of_work::Response>::replay(&mut channel);
+    let pow_response = Replayable::<proof_of_work::Response>::replay(channel);
     if !pow_challenge.verify(pow_response) {
         return Err(Error::InvalidPoW);
     }
 
     // Gets queries from channel
+    channel.label(Label::QueryIndices);
     let queries = get_indices(
         constraints.num_queries,
         eval_domain_size.trailing_zeros(),
-        &mut channel,
+        channel,
     );
 
     // Get values and check decommitment of low degree extension
-    let lde_values: Vec<(usize, Vec<U256>)> = queries
-        .iter()
-        .map(|&index| {
-            let held = Replayable::<U256>::replay_many(&mut channel, trace_cols);
-            (index, held)
-        })
-        .collect();
+    phases.start("decommitments", None);
+    channel.label(Label::Decommitments);
+    let salted = constraints.zero_knowledge;
+
+    // The externally committed columns lead the trace values of a query
+    let external_values = match &external_commitment {
+        Some(commitment) => {
+            let values = read_leaves(channel, &queries, external_cols);
+            let proof_length = commitment.proof_size(&queries)?;
+            let hashes = Replayable::<Hash>::replay_many(channel, proof_length);
+            let proof = MerkleProof::from_hashes(commitment, &queries, &hashes)?;
+            if check_decommitment::<H, _>(&proof, &values, &[], verified).is_err() {
+                return Err(Error::InvalidExternalCommitment);
+            }
+            values
+        }
+        None => queries.iter().map(|&index| (index, Vec::new())).collect(),
+    };
+    let lde_values = read_leaves(channel, &queries, trace_cols - external_cols);
+    let lde_salts = read_salts(channel, &queries, salted);
     let lde_proof_length = lde_commitment.proof_size(&queries)?;
-    let lde_hashes = Replayable::<Hash>::replay_many(&mut channel, lde_proof_length);
+    let lde_hashes = Replayable::<Hash>::replay_many(channel, lde_proof_length);
     let lde_proof = MerkleProof::from_hashes(&lde_commitment, &queries, &lde_hashes)?;
     // Note - we could express this a merkle error instead but this adds specificity
-    if lde_proof.verify(&lde_values).is_err() {
+    if check_decommitment::<H, _>(&lde_proof, &lde_values, &lde_salts, verified).is_err() {
         return Err(Error::InvalidLDECommitment);
     }
+    let mut lde_values: Vec<_> = external_values
+        .into_iter()
+        .zip(lde_values)
+        .map(|((index, mut values), (_, own))| {
+            values.extend(own);
+            (index, values)
+        })
+        .collect();
+
+    // The interaction values follow the trace values of the same query
+    if let Some(commitment) = &interaction_commitment {
+        let values = read_leaves(channel, &queries, interaction_cols);
+        let salts = read_salts(channel, &queries, salted);
+        let proof_length = commitment.proof_size(&queries)?;
+        let hashes = Replayable::<Hash>::replay_many(channel, proof_length);
+        let proof = MerkleProof::from_hashes(commitment, &queries, &hashes)?;
+        if check_decommitment::<H, _>(&proof, &values, &salts, verified).is_err() {
+            return Err(Error::InvalidInteractionCommitment);
+        }
+        for ((_, lde_values), (_, values)) in lde_values.iter_mut().zip(values) {
+            lde_values.extend(values);
+        }
+    }
 
     // Gets the values and checks the constraint decommitment
     let mut constraint_values = Vec::with_capacity(queries.len());
     for query_index in &queries {
         constraint_values.push((
             *query_index,
-            Replayable::<FieldElement>::replay_many(&mut channel, constraints_trace_degree),
+            Replayable::<FieldElement>::replay_many(channel, constraint_cols),
         ));
     }
+    let constraint_salts = read_salts(channel, &queries, salted);
     let constraint_proof_length = constraint_commitment.proof_size(&queries)?;
     let constraint_hashes: Vec<Hash> =
-        Replayable::<Hash>::replay_many(&mut channel, constraint_proof_length);
+        Replayable::<Hash>::replay_many(channel, constraint_proof_length);
     let constraint_proof =
         MerkleProof::from_hashes(&constraint_commitment, &queries, &constraint_hashes)?;
     // Note - we could express this a merkle error instead but this adds specificity
-    if constraint_proof.verify(&constraint_values).is_err() {
+    if check_decommitment::<H, _>(
+        &constraint_proof,
+        &constraint_values,
+        &constraint_salts,
+        verified,
+    )
+    .is_err()
+    {
         return Err(Error::InvalidConstraintCommitment);
     }
 
-    let coset_sizes = constraints
-        .fri_layout
-        .iter()
-        .map(|k| 1_usize << k)
-        .collect::<Vec<_>>();
-    let mut fri_indices: Vec<usize> = queries
-        .to_vec()
-        .iter()
-        .map(|x| x / coset_sizes[0])
-        .collect();
-
-    // Folded fri values from the previous layer
-    let mut fri_folds: BTreeMap<usize, FieldElement> = BTreeMap::new();
-
-    let mut previous_indices = queries.to_vec().clone();
-    let mut step = 1;
-    let mut len = eval_domain_size;
-    for (k, commitment) in fri_commitments.iter().enumerate() {
-        let mut fri_layer_values = Vec::new();
-
-        fri_indices.dedup();
-        for i in &fri_indices {
-            let mut coset: Vec<FieldElement> = Vec::new();
-            for j in 0..coset_sizes[k] {
-                let n = i * coset_sizes[k] + j;
-                if let Ok(z) = previous_indices.binary_search(&n) {
-                    if k > 0 {
-                        coset.push(match fri_folds.get(&n) {
-                            Some(x) => x.clone(),
-                            None => return Err(Error::HashMapFailure),
-                        });
-                    } else {
-                        let z_reverse = fft::permute_index(eval_domain_size, queries[z]);
-                        coset.push(out_of_domain_element(
-                            lde_values[z].1.as_slice(),
-                            &constraint_values[z].1,
-                            &eval_x[z_reverse],
-                            &oods_point,
-                            oods_values.as_slice(),
-                            oods_coefficients.as_slice(),
-                            eval_domain_size,
-                            constraints.blowup,
-                        )?);
-                    }
-                } else {
-                    coset.push(Replayable::<FieldElement>::replay(&mut channel));
-                }
-            }
-            fri_layer_values.push((*i, coset));
-        }
-        // Fold and record foldings
-        let mut layer_folds = BTreeMap::new();
-        for (i, coset) in &fri_layer_values {
-            let _old_value = layer_folds.insert(
-                *i,
-                fri_fold(
-                    coset.as_slice(),
-                    &eval_points[k],
-                    step,
-                    (coset_sizes[k] / 2) * i,
-                    len,
-                    eval_x.as_slice(),
-                ),
-            );
-        }
-
-        let merkle_proof_length = commitment.proof_size(&fri_indices)?;
-        let merkle_hashes = Replayable::<Hash>::replay_many(&mut channel, merkle_proof_length);
-        let merkle_proof = MerkleProof::from_hashes(commitment, &fri_indices, &merkle_hashes)?;
-        fri_folds = layer_folds;
-
-        for _ in 0..constraints.fri_layout[k] {
-            step *= 2;
-        }
-        len /= coset_sizes[k];
-
-        // Note - we could express this a merkle error instead but this adds specificity
-        if merkle_proof.verify(&fri_layer_values).is_err() {
-            return Err(Error::InvalidFriCommitment);
-        };
-
-        previous_indices = fri_indices.clone();
-        if k + 1 < constraints.fri_layout.len() {
-            fri_indices = fri_indices
-                .iter()
-                .map(|ind| ind / coset_sizes[k + 1])
-                .collect();
-        }
+    // Compute the first FRI layer values at the queries
+    let mut fri_values = Vec::with_capacity(queries.len());
+    for (z, &index) in queries.iter().enumerate() {
+        let z_reverse = fft::permute_index(eval_domain_size, index);
+        fri_values.push((
+            index,
+            out_of_domain_element(
+                lde_values[z].1.as_slice(),
+                &constraint_values[z].1,
+                &eval_root.pow(z_reverse),
+                &constraint_point,
+                &oods_points,
+                oods_values.as_slice(),
+                oods_coefficients.as_slice(),
+            )?,
+        ));
     }
+
+    // Check the FRI decommitments and the final layer
+    phases.start("fri", None);
+    fri.verify::<H, _>(&fri_values, channel)?;
     if !channel.at_end() {
         return Err(Error::ProofTooLong);
     }
 
-    // Checks that the calculated fri folded queries are the points interpolated by
-    // the decommited polynomial.
-    let interp_root = match FieldElement::root(len) {
-        Some(x) => x,
-        None => return Err(Error::RootUnavailable),
-    };
-    for key in &previous_indices {
-        let calculated = fri_folds[key].clone();
-        let x_pow = interp_root.pow(fft::permute_index(len, *key));
-        let committed = DensePolynomial::new(&last_layer_coefficient).evaluate(&x_pow);
-
-        if committed != calculated.clone() {
-            return Err(Error::OodsCalculationFailure);
-        }
-    }
-
-    let (trace_values, constraint_values) = oods_values.split_at(2 * trace_cols);
+    phases.start("oods", None);
+    let (trace_values, constraint_values) = oods_values.split_at(trace_values_len);
     if oods_value_from_trace_values(
         &constraints,
         &constraint_coefficients,
+        &challenges,
         &trace_values,
         &oods_point,
-    ) != oods_value_from_constraint_values(&constraint_values, &oods_point)
-    {
+    ) != oods_value_from_constraint_values(
+        &constraint_values[..constraints_trace_degree],
+        &oods_point,
+    ) {
         return Err(Error::OodsMismatch);
     }
     Ok(())
 }
 
+// Read the leaves with `columns` values at each of the `queries`.
+fn read_leaves<T: Transcript>(
+    channel: &mut VerifierChannel<T>,
+    queries: &[usize],
+    columns: usize,
+) -> Vec<(usize, Vec<U256>)> {
+    queries
+        .iter()
+        .map(|&index| {
+            let held = (0..columns)
+                .map(|_| {
+                    Replayable::<FieldElement>::replay(channel)
+                        .as_montgomery()
+                        .clone()
+                })
+                .collect();
+            (index, held)
+        })
+        .collect()
+}
+
+// Read the salts of the leaves at the `queries`, if the leaves are `salted`.
+fn read_salts<T: Transcript>(
+    channel: &mut VerifierChannel<T>,
+    queries: &[usize],
+    salted: bool,
+) -> Vec<Hash> {
+    if salted {
+        Replayable::<Hash>::replay_many(channel, queries.len())
+    } else {
+        Vec::new()
+    }
+}
+
+// Check the `leaves`, salted with `salts` unless there are none.
+fn check_decommitment<H: Hasher, Leaf: Hashable + Clone>(
+    proof: &MerkleProof,
+    leaves: &[(usize, Leaf)],
+    salts: &[Hash],
+    verified: Option<&VerifiedNodes>,
+) -> std::result::Result<(), MerkleError> {
+    if salts.is_empty() {
+        return verify_leaves::<H, _>(proof, leaves, verified);
+    }
+    let leaves: Vec<_> = leaves
+        .iter()
+        .zip(salts)
+        .map(|((index, value), salt)| {
+            (*index, Salted {
+                salt:  salt.clone(),
+                value: value.clone(),
+            })
+        })
+        .collect();
+    verify_leaves::<H, _>(proof, &leaves, verified)
+}
+
+fn verify_leaves<H: Hasher, Leaf: Hashable>(
+    proof: &MerkleProof,
+    leaves: &[(usize, Leaf)],
+    verified: Option<&VerifiedNodes>,
+) -> std::result::Result<(), MerkleError> {
+    match verified {
+        Some(verified) => proof.verify_cached_with::<H, _>(leaves, verified),
+        None => proof.verify_with::<H, _>(leaves),
+    }
+}
+
 fn oods_value_from_trace_values(
     constraints: &Constraints,
     coefficients: &[FieldElement],
+    challenges: &[FieldElement],
     trace_values: &[FieldElement],
     oods_point: &FieldElement,
 ) -> FieldElement {
+    let trace_offsets = constraints.trace_offsets();
     let trace = |i: usize, j: isize| {
-        let j: usize = j.try_into().unwrap();
-        assert!(j == 0 || j == 1);
-        trace_values[2 * i + j].clone()
+        let j = trace_offsets
+            .binary_search(&j)
+            .expect("Constraints use a row offset that was not sampled.");
+        trace_values[trace_offsets.len() * i + j].clone()
     };
     constraints
-        .combine(coefficients)
+        .combine(coefficients, challenges)
         .evaluate(oods_point, &trace)
 }
 
@@ -432,91 +741,67 @@ fn oods_value_from_constraint_values(
     result
 }
 
-// TODO: Clean up
+/// Draw the query indices for an evaluation domain of `2^bits` elements.
+///
+/// Every random `U256` from the channel yields four indices: the low `bits`
+/// bits of each of its 64 bit words, most significant word first. Exactly
+/// `num` indices are drawn, the rest of the last `U256` is discarded. The
+/// indices are then sorted in increasing order and duplicates are removed,
+/// so there can be fewer than `num` queries. All decommitments are written
+/// once per remaining index in this order. The verifier reads them the same
+/// way, so a proof that decommits duplicates or another order is rejected.
+///
+/// This matches the query selection of StarkWare's verifier.
 #[allow(clippy::cast_possible_truncation)]
-fn get_indices(num: usize, bits: u32, proof: &mut VerifierChannel) -> Vec<usize> {
+pub(crate) fn get_indices<R: RandomGenerator<U256>>(
+    num: usize,
+    bits: u32,
+    channel: &mut R,
+) -> Vec<usize> {
+    let mask = (1_u64 << bits) - 1;
     let mut query_indices = Vec::with_capacity(num + 3);
     while query_indices.len() < num {
-        let val: U256 = proof.get_random();
-        query_indices.push(((val.clone() >> (0x100 - 0x040)).c0 & (2_u64.pow(bits) - 1)) as usize);
-        query_indices.push(((val.clone() >> (0x100 - 0x080)).c0 & (2_u64.pow(bits) - 1)) as usize);
-        query_indices.push(((val.clone() >> (0x100 - 0x0C0)).c0 & (2_u64.pow(bits) - 1)) as usize);
-        query_indices.push((val.c0 & (2_u64.pow(bits) - 1)) as usize);
+        let val: U256 = channel.get_random();
+        query_indices.push(((val.clone() >> (0x100 - 0x040)).c0 & mask) as usize);
+        query_indices.push(((val.clone() >> (0x100 - 0x080)).c0 & mask) as usize);
+        query_indices.push(((val.clone() >> (0x100 - 0x0C0)).c0 & mask) as usize);
+        query_indices.push((val.c0 & mask) as usize);
     }
     query_indices.truncate(num);
-    (&mut query_indices).sort_unstable();
+    query_indices.sort_unstable();
+    query_indices.dedup();
     query_indices
 }
 
-fn fri_fold(
-    coset: &[FieldElement],
-    eval_point: &FieldElement,
-    mut step: usize,
-    mut index: usize,
-    mut len: usize,
-    eval_x: &[FieldElement],
-) -> FieldElement {
-    let mut mutable_eval_copy = eval_point.clone();
-    let mut coset_full: Vec<FieldElement> = coset.to_vec();
-    while coset_full.len() > 1 {
-        let mut next_coset = Vec::with_capacity(coset.len() / 2);
-
-        for (k, pair) in coset_full.chunks(2).enumerate() {
-            let x = &eval_x[fft::permute_index(len / 2, index + k) * step];
-            next_coset.push(fri_single_fold(&pair[0], &pair[1], x, &mutable_eval_copy));
-        }
-        len /= 2;
-        index /= 2;
-        step *= 2;
-        mutable_eval_copy = mutable_eval_copy.square();
-        coset_full = next_coset;
-    }
-    coset_full[0].clone()
-}
-
-fn fri_single_fold(
-    poly_at_x: &FieldElement,
-    poly_at_neg_x: &FieldElement,
-    x: &FieldElement,
-    eval_point: &FieldElement,
-) -> FieldElement {
-    (poly_at_x + poly_at_neg_x) + eval_point / x * (poly_at_x - poly_at_neg_x)
-}
-
 // TODO - Make sure this is general
 #[allow(clippy::too_many_arguments)]
 fn out_of_domain_element(
     poly_points_u: &[U256],
     constraint_oods_values: &[FieldElement],
     x_cord: &FieldElement,
-    oods_point: &FieldElement,
+    constraint_point: &FieldElement,
+    oods_points: &[FieldElement],
     oods_values: &[FieldElement],
     oods_coefficients: &[FieldElement],
-    eval_domain_size: usize,
-    blowup: usize,
 ) -> Result<FieldElement> {
     let poly_points: Vec<FieldElement> = poly_points_u
         .iter()
         .map(|i| FieldElement::from_montgomery(i.clone()))
         .collect();
     let x_transform = x_cord * FieldElement::GENERATOR;
-    let omega = match FieldElement::root(eval_domain_size) {
-        Some(x) => x,
-        None => return Err(Error::RootUnavailable),
-    };
-    let g = omega.pow(blowup);
+    let trace_values_len = oods_points.len() * poly_points.len();
     let mut r = FieldElement::ZERO;
 
-    for x in 0..poly_points.len() {
-        r += &oods_coefficients[2 * x] * (&poly_points[x] - &oods_values[2 * x])
-            / (&x_transform - oods_point);
-        r += &oods_coefficients[2 * x + 1] * (&poly_points[x] - &oods_values[2 * x + 1])
-            / (&x_transform - &g * oods_point);
+    for (x, poly_point) in poly_points.iter().enumerate() {
+        for (j, point) in oods_points.iter().enumerate() {
+            let k = x * oods_points.len() + j;
+            r += &oods_coefficients[k] * (poly_point - &oods_values[k]) / (&x_transform - point);
+        }
     }
     for (i, constraint_oods_value) in constraint_oods_values.iter().enumerate() {
-        r += &oods_coefficients[2 * poly_points.len() + i]
-            * (constraint_oods_value - &oods_values[poly_points.len() * 2 + i])
-            / (&x_transform - oods_point.pow(constraint_oods_values.len()));
+        r += &oods_coefficients[trace_values_len + i]
+            * (constraint_oods_value - &oods_values[trace_values_len + i])
+            / (&x_transform - constraint_point);
     }
     Ok(r)
 }
@@ -536,28 +821,145 @@ mod tests {
     use super::*;
     use crate::{
         prove,
-        traits::tests::{Claim, Witness},
-        Provable, Verifiable,
+        traits::tests::{fib_claim, fib_witness, Claim, Witness},
+        ProofStats, Provable, Verifiable,
     };
-    use zkp_macros_decl::u256h;
 
     #[test]
     fn verifier_fib_test() {
-        let public = Claim {
-            index: 1000,
-            value: FieldElement::from(u256h!(
-                "0142c45e5d743d10eae7ebb70f1526c65de7dbcdb65b322b6ddc36a812591e8f"
-            )),
-        };
-        let private = Witness {
-            secret: FieldElement::from(u256h!(
-                "00000000000000000000000000000000000000000000000000000000cafebabe"
-            )),
-        };
+        let public = fib_claim();
+        let private = fib_witness();
         let constraints = public.constraints();
         let trace = public.trace(&private);
         let actual = prove(&constraints, &trace).unwrap();
 
         assert!(verify(&constraints, &actual).is_ok());
     }
+
+    fn fib_proof() -> (Constraints, Proof) {
+        let public = fib_claim();
+        let private = fib_witness();
+        let constraints = public.constraints();
+        let proof = prove(&constraints, &public.trace(&private)).unwrap();
+        (constraints, proof)
+    }
+
+    #[test]
+    fn truncated_proof_test() {
+        let (constraints, proof) = fib_proof();
+        for &length in &[0, 31, 100, proof.as_bytes().len() - 1] {
+            let truncated = Proof::from_bytes(proof.as_bytes()[..length].to_vec());
+            assert_eq!(verify(&constraints, &truncated), Err(Error::ProofTooShort));
+        }
+    }
+
+    #[test]
+    fn limits_test() {
+        let (constraints, proof) = fib_proof();
+
+        let mut padded = proof.as_bytes().to_vec();
+        padded.resize(MAX_PROOF_SIZE + 1, 0);
+        let padded = Proof::from_bytes(padded);
+        assert_eq!(verify(&constraints, &padded), Err(Error::ProofTooLong));
+
+        let mut large = constraints.clone();
+        large.num_queries = MAX_QUERIES + 1;
+        assert_eq!(verify(&large, &proof), Err(Error::UnsupportedParameters));
+
+        let mut large = constraints.clone();
+        large.blowup = MAX_DOMAIN_SIZE;
+        assert_eq!(verify(&large, &proof), Err(Error::UnsupportedParameters));
+
+        let mut large = constraints;
+        large.fri_layout = vec![1; MAX_FRI_LAYERS + 1];
+        assert_eq!(verify(&large, &proof), Err(Error::UnsupportedParameters));
+    }
+
+    #[test]
+    fn get_indices_test() {
+        let mut channel = VerifierChannel::<HashChain<MaskedKeccak>>::new(vec![]);
+        channel.initialize(b"queries");
+        // Twenty indices in a domain of four must repeat
+        let indices = get_indices(20, 2, &mut channel);
+        assert!(indices.len() <= 4);
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(indices.iter().all(|&index| index < 4));
+    }
+
+    #[test]
+    fn colliding_queries_test() {
+        let witness = fib_witness();
+        let mut claim = Claim {
+            index: 10,
+            value: FieldElement::ZERO,
+        };
+        claim.value = claim.trace(&witness)[(10, 0)].clone();
+        // A hundred queries in an evaluation domain of 256 collide
+        let mut constraints = claim.constraints();
+        constraints.num_queries = 100;
+        let proof = prove(&constraints, &claim.trace(&witness)).unwrap();
+        assert_eq!(verify(&constraints, &proof), Ok(()));
+
+        // The statistics only account for every byte of the proof if each
+        // distinct index is decommitted once
+        let stats = ProofStats::from_proof(&proof, &constraints).unwrap();
+        assert!(stats.queries.len() < constraints.num_queries);
+    }
+
+    #[test]
+    fn verify_batch_test() {
+        let witness = Witness {
+            secret: 0x0cafe.into(),
+        };
+        let entry = |index: usize| {
+            let mut claim = Claim {
+                index,
+                value: FieldElement::ZERO,
+            };
+            claim.value = claim.trace(&witness)[(index, 0)].clone();
+            let proof = claim.prove(&witness).unwrap();
+            (claim, proof)
+        };
+        let mut batch = vec![entry(10), entry(20), entry(10)];
+        let (claim, proof) = entry(30);
+        batch.push((
+            Claim {
+                value: FieldElement::ONE,
+                ..claim
+            },
+            proof,
+        ));
+        // A valid proof paired with another claim
+        batch.push((batch[1].0.clone(), batch[0].1.clone()));
+        let results = verify_batch(&batch);
+        assert_eq!(&results[..3], &[Ok(()), Ok(()), Ok(())]);
+        assert!(results[3].is_err());
+        assert!(results[4].is_err());
+        assert_eq!(verify_batch::<Claim>(&[]), vec![]);
+    }
+
+    #[test]
+    fn verify_batch_shared_trace_test() {
+        // Claims about rows 20 and 30 are proven on the same trace
+        let witness = fib_witness();
+        let entry = |index: usize| {
+            let mut claim = Claim {
+                index,
+                value: FieldElement::ZERO,
+            };
+            claim.value = claim.trace(&witness)[(index, 0)].clone();
+            let proof = claim.prove(&witness).unwrap();
+            (claim.constraints(), proof)
+        };
+        let (first, second) = (entry(20), entry(30));
+        let nodes = |entries: &[&(Constraints, Proof)]| {
+            let verified = VerifiedNodes::new();
+            for (constraints, proof) in entries {
+                assert_eq!(verify_reusing(constraints, proof, Some(&verified)), Ok(()));
+            }
+            verified.len()
+        };
+        // The second proof finds part of its trace tree verified
+        assert!(nodes(&[&first, &second]) < nodes(&[&first]) + nodes(&[&second]));
+    }
 }
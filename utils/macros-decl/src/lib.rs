@@ -45,14 +45,44 @@ use proc_macro_hack::proc_macro_hack;
 #[proc_macro_hack]
 pub use zkp_macros_impl::hex;
 
-/// U256 hexadecimal literal
+/// U256 literal
+///
+/// Accepts a hexadecimal string like `"ff"` or `"0xff"`, an integer literal
+/// like `255` or `0xff` of any size that fits 256 bits, or an expression
+/// combining these with parentheses and `+`, `-` and `*`. It is an error for
+/// the value to be negative or exceed 256 bits.
 ///
 /// (Documentation goes here on the re-export, not in the other crate.)
 #[proc_macro_hack]
 pub use zkp_macros_impl::u256h;
 
-/// FieldElement hexadecimal literal
+/// FieldElement literal
+///
+/// Accepts the same inputs as `u256h!`, but evaluates them modulo the field
+/// prime. In particular `field_element!(-1)` is `p - 1`.
 ///
 /// (Documentation goes here on the re-export, not in the other crate.)
 #[proc_macro_hack]
 pub use zkp_macros_impl::field_element;
+
+/// Derive `ClaimBytes`, `Verifiable` and `Provable` for a claim struct.
+///
+/// ```ignore
+/// #[derive(StarkClaim)]
+/// #[stark(constraints = "get_constraints", trace = "get_trace", witness = "Witness")]
+/// struct Claim {
+///     path_length: usize,
+///     root:        FieldElement,
+/// }
+/// ```
+///
+/// All fields are encoded in declaration order to form the `ClaimBytes`
+/// channel seed, and `Vec<u8>: From<&Claim>` is implemented using it. A
+/// different encoding order can be given as `order = "root, path_length"`.
+/// `Verifiable::constraints` calls `get_constraints(&claim)` and
+/// `Provable<&Witness>::trace` calls `get_trace(&claim, &witness)`, the
+/// latter only with the `prover` feature of the deriving crate. Both
+/// attributes are optional, `trace` requires `witness`.
+///
+/// (Documentation goes here on the re-export, not in the other crate.)
+pub use zkp_macros_impl::StarkClaim;
@@ -54,3 +54,8 @@ pub fn u256h(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
 pub fn field_element(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     zkp_macros_lib::field_element(input.into()).into()
 }
+
+#[proc_macro_derive(StarkClaim, attributes(stark))]
+pub fn stark_claim(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    zkp_macros_lib::stark_claim(input.into()).into()
+}
@@ -0,0 +1,251 @@
+use proc_macro2::TokenStream;
+use syn::{BinOp, Expr, Lit, UnOp};
+
+/// 256-bit number as little-endian 64-bit limbs.
+pub(crate) type Limbs = [u64; 4];
+
+/// The arithmetic used to evaluate an expression.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Domain {
+    /// Numbers in `0..2^256`, over- and underflow are errors.
+    Integer,
+    /// Numbers modulo the given modulus.
+    Modular(Limbs),
+}
+
+/// Evaluate a constant expression.
+///
+/// Accepts hexadecimal strings (with optional `0x` prefix and any number of
+/// digits up to 64), integer literals in any base and of any size that fits
+/// 256 bits, and combinations of these using parentheses, unary `-` and
+/// binary `+`, `-` and `*`.
+pub(crate) fn evaluate(input: TokenStream, domain: Domain) -> syn::Result<Limbs> {
+    let expr: Expr = syn::parse2(input)?;
+    domain.evaluate(&expr)
+}
+
+impl Domain {
+    fn evaluate(self, expr: &Expr) -> syn::Result<Limbs> {
+        match expr {
+            Expr::Lit(expr_lit) => self.reduce(parse_literal(&expr_lit.lit)?),
+            Expr::Paren(paren) => self.evaluate(&paren.expr),
+            Expr::Group(group) => self.evaluate(&group.expr),
+            Expr::Unary(unary) => {
+                match unary.op {
+                    UnOp::Neg(_) => {
+                        let value = self.evaluate(&unary.expr)?;
+                        self.sub(&[0; 4], &value)
+                    }
+                    _ => Err(syn::Error::new_spanned(expr, "Unsupported operator")),
+                }
+            }
+            Expr::Binary(binary) => {
+                let left = self.evaluate(&binary.left)?;
+                let right = self.evaluate(&binary.right)?;
+                match binary.op {
+                    BinOp::Add(_) => self.add(&left, &right),
+                    BinOp::Sub(_) => self.sub(&left, &right),
+                    BinOp::Mul(_) => self.mul(&left, &right),
+                    _ => Err(syn::Error::new_spanned(expr, "Unsupported operator")),
+                }
+            }
+            _ => {
+                Err(syn::Error::new_spanned(
+                    expr,
+                    "Expected hexadecimal string, integer literal or arithmetic expression",
+                ))
+            }
+        }
+    }
+
+    fn reduce(self, value: Limbs) -> syn::Result<Limbs> {
+        match self {
+            Self::Integer => Ok(value),
+            Self::Modular(modulus) => {
+                let mut value = value;
+                while !less_than(&value, &modulus) {
+                    value = sub(&value, &modulus).0;
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    fn add(self, left: &Limbs, right: &Limbs) -> syn::Result<Limbs> {
+        let (sum, carry) = add(left, right);
+        match self {
+            Self::Integer if carry => Err(overflow()),
+            Self::Integer => Ok(sum),
+            Self::Modular(modulus) => {
+                if carry || !less_than(&sum, &modulus) {
+                    Ok(sub(&sum, &modulus).0)
+                } else {
+                    Ok(sum)
+                }
+            }
+        }
+    }
+
+    fn sub(self, left: &Limbs, right: &Limbs) -> syn::Result<Limbs> {
+        let (difference, borrow) = sub(left, right);
+        match self {
+            Self::Integer if borrow => {
+                Err(syn::Error::new(
+                    proc_macro2::Span::call_site(),
+                    "Value is negative",
+                ))
+            }
+            Self::Integer => Ok(difference),
+            Self::Modular(modulus) => {
+                if borrow {
+                    Ok(add(&difference, &modulus).0)
+                } else {
+                    Ok(difference)
+                }
+            }
+        }
+    }
+
+    fn mul(self, left: &Limbs, right: &Limbs) -> syn::Result<Limbs> {
+        match self {
+            Self::Integer => mul(left, right).ok_or_else(overflow),
+            Self::Modular(_) => {
+                // Double-and-add, speed is not a concern at compile time.
+                let mut result = [0; 4];
+                for i in (0..256).rev() {
+                    result = self.add(&result, &result)?;
+                    if (right[i / 64] >> (i % 64)) & 1 == 1 {
+                        result = self.add(&result, left)?;
+                    }
+                }
+                Ok(result)
+            }
+        }
+    }
+}
+
+fn overflow() -> syn::Error {
+    syn::Error::new(
+        proc_macro2::Span::call_site(),
+        "Value does not fit 256 bits",
+    )
+}
+
+fn parse_literal(literal: &Lit) -> syn::Result<Limbs> {
+    match literal {
+        Lit::Str(string) => parse_hex(&string.value()),
+        Lit::Int(integer) => {
+            if !integer.suffix().is_empty() {
+                return Err(syn::Error::new_spanned(
+                    integer,
+                    "Integer literal must not have a suffix",
+                ));
+            }
+            // Syn converts literals in any base to decimal, without
+            // limiting their size.
+            parse_decimal(integer.base10_digits())
+        }
+        _ => {
+            Err(syn::Error::new_spanned(
+                literal,
+                "Expected hexadecimal string or integer literal",
+            ))
+        }
+    }
+}
+
+fn parse_hex(string: &str) -> syn::Result<Limbs> {
+    let digits = if string.starts_with("0x") {
+        &string[2..]
+    } else {
+        string
+    };
+    if digits.len() > 64 {
+        return Err(overflow());
+    }
+    let mut result = [0; 4];
+    for character in digits.chars() {
+        let digit = character.to_digit(16).ok_or_else(|| {
+            syn::Error::new(
+                proc_macro2::Span::call_site(),
+                format!("Invalid hexadecimal digit '{}'", character),
+            )
+        })?;
+        result = [
+            (result[0] << 4) | u64::from(digit),
+            (result[1] << 4) | (result[0] >> 60),
+            (result[2] << 4) | (result[1] >> 60),
+            (result[3] << 4) | (result[2] >> 60),
+        ];
+    }
+    Ok(result)
+}
+
+fn parse_decimal(digits: &str) -> syn::Result<Limbs> {
+    let mut result = [0; 4];
+    for character in digits.chars() {
+        // Syn guarantees decimal digits only.
+        let digit = u64::from(character.to_digit(10).unwrap());
+        result = mul(&result, &[10, 0, 0, 0]).ok_or_else(overflow)?;
+        let (sum, carry) = add(&result, &[digit, 0, 0, 0]);
+        if carry {
+            return Err(overflow());
+        }
+        result = sum;
+    }
+    Ok(result)
+}
+
+fn less_than(left: &Limbs, right: &Limbs) -> bool {
+    (left[3], left[2], left[1], left[0]) < (right[3], right[2], right[1], right[0])
+}
+
+fn add(left: &Limbs, right: &Limbs) -> (Limbs, bool) {
+    let mut result = [0; 4];
+    let mut carry = false;
+    for i in 0..4 {
+        let (sum, carry_1) = left[i].overflowing_add(right[i]);
+        let (sum, carry_2) = sum.overflowing_add(u64::from(carry));
+        result[i] = sum;
+        carry = carry_1 || carry_2;
+    }
+    (result, carry)
+}
+
+fn sub(left: &Limbs, right: &Limbs) -> (Limbs, bool) {
+    let mut result = [0; 4];
+    let mut borrow = false;
+    for i in 0..4 {
+        let (difference, borrow_1) = left[i].overflowing_sub(right[i]);
+        let (difference, borrow_2) = difference.overflowing_sub(u64::from(borrow));
+        result[i] = difference;
+        borrow = borrow_1 || borrow_2;
+    }
+    (result, borrow)
+}
+
+/// Multiplication, `None` if the result exceeds 256 bits.
+fn mul(left: &Limbs, right: &Limbs) -> Option<Limbs> {
+    let mut result = [0_u64; 8];
+    for i in 0..4 {
+        let mut carry = 0_u128;
+        for j in 0..4 {
+            let product =
+                u128::from(left[i]) * u128::from(right[j]) + u128::from(result[i + j]) + carry;
+            // We want truncation here
+            #[allow(clippy::cast_possible_truncation)]
+            let low = product as u64;
+            result[i + j] = low;
+            carry = product >> 64;
+        }
+        // We want truncation here
+        #[allow(clippy::cast_possible_truncation)]
+        let high = carry as u64;
+        result[i + 4] = high;
+    }
+    if result[4..].iter().all(|limb| *limb == 0) {
+        Some([result[0], result[1], result[2], result[3]])
+    } else {
+        None
+    }
+}
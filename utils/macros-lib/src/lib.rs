@@ -37,6 +37,13 @@
 )]
 #![cfg_attr(feature = "std", warn(missing_debug_implementations,))]
 
+mod expression;
+mod stark_claim;
+
+pub use stark_claim::stark_claim;
+
+use expression::{evaluate, Domain};
+
 use proc_macro2::{Literal, Span, TokenStream};
 use quote::quote;
 use syn::{Expr, Lit};
@@ -75,26 +82,10 @@ fn parse_hex(input: TokenStream) -> syn::Result<Vec<u8>> {
     })
 }
 
-fn bytes_to_limbs(bytes: &[u8]) -> syn::Result<[u64; 4]> {
-    if bytes.len() > 32 {
-        return Err(syn::Error::new(
-            Span::call_site(),
-            "expected up to 32 bytes",
-        ));
-    }
-    let mut result = [0_u64; 4];
-    let mut iter = bytes.iter().rev();
-    for i in 0..4 {
-        for j in 0..8 {
-            if let Some(byte) = iter.next() {
-                result[i] |= u64::from(*byte) << (8 * j);
-            } else {
-                return Ok(result);
-            }
-        }
-    }
-    Ok(result)
-}
+/// The prime modulus of `FieldElement`, copied from `primefield`.
+// We don't mind large number literals here.
+#[allow(clippy::unreadable_literal)]
+const FIELD_MODULUS: [u64; 4] = [1, 0, 0, 576460752303423505];
 
 // This function and constants are taken from primefield::montgomery
 // TODO: Drop this in favour of a `const fn` call.
@@ -198,9 +189,7 @@ pub fn hex(input: TokenStream) -> TokenStream {
 
 pub fn u256h(input: TokenStream) -> TokenStream {
     (|| {
-        // TODO: Also accept integer literals
-        let bytes = parse_hex(input)?;
-        let limbs = bytes_to_limbs(bytes.as_slice())?;
+        let limbs = evaluate(input, Domain::Integer)?;
         let c0 = Literal::u64_suffixed(limbs[0]);
         let c1 = Literal::u64_suffixed(limbs[1]);
         let c2 = Literal::u64_suffixed(limbs[2]);
@@ -216,9 +205,7 @@ pub fn u256h(input: TokenStream) -> TokenStream {
 
 pub fn field_element(input: TokenStream) -> TokenStream {
     (|| {
-        // TODO: Also accept integer literals
-        let bytes = parse_hex(input)?;
-        let limbs = bytes_to_limbs(bytes.as_slice())?;
+        let limbs = evaluate(input, Domain::Modular(FIELD_MODULUS))?;
         let (c0, c1, c2, c3) = montgomery_convert((limbs[0], limbs[1], limbs[2], limbs[3]));
         let c0 = Literal::u64_suffixed(c0);
         let c1 = Literal::u64_suffixed(c1);
@@ -309,4 +296,72 @@ mod test {
             .to_string()
         );
     }
+
+    #[test]
+    fn u256h_expressions() {
+        let expected = u256h(quote! {"ff"}).to_string();
+        assert_eq!(u256h(quote! {255}).to_string(), expected);
+        assert_eq!(u256h(quote! {0xff}).to_string(), expected);
+        assert_eq!(u256h(quote! {"0xff"}).to_string(), expected);
+        assert_eq!(u256h(quote! {"0fF"}).to_string(), expected);
+        assert_eq!(u256h(quote! {(3 + 2) * 51}).to_string(), expected);
+        assert_eq!(u256h(quote! {256 - 1}).to_string(), expected);
+        assert_eq!(
+            u256h(quote! {
+                115792089237316195423570985008687907853269984665640564039457584007913129639935
+            })
+            .to_string(),
+            u256h(quote! {"ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"})
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn u256h_negative() {
+        assert_eq!(
+            u256h(quote! {-1}).to_string(),
+            quote! {compile_error ! { "Value is negative" }}.to_string()
+        );
+        assert_eq!(
+            u256h(quote! {
+                115792089237316195423570985008687907853269984665640564039457584007913129639936
+            })
+            .to_string(),
+            quote! {compile_error ! { "Value does not fit 256 bits" }}.to_string()
+        );
+        assert_eq!(
+            u256h(quote! {1u64}).to_string(),
+            quote! {compile_error ! { "Integer literal must not have a suffix" }}.to_string()
+        );
+        assert_eq!(
+            u256h(quote! {1 / 2}).to_string(),
+            quote! {compile_error ! { "Unsupported operator" }}.to_string()
+        );
+    }
+
+    #[test]
+    fn field_element_expressions() {
+        assert_eq!(
+            field_element(quote! {-1}).to_string(),
+            field_element(quote! {
+                "0800000000000011000000000000000000000000000000000000000000000000"
+            })
+            .to_string()
+        );
+        assert_eq!(
+            field_element(quote! {1}).to_string(),
+            field_element(quote! {"01"}).to_string()
+        );
+        assert_eq!(
+            field_element(quote! {-3 * -5 + 2}).to_string(),
+            field_element(quote! {17}).to_string()
+        );
+        assert_eq!(
+            field_element(
+                quote! {0x0800000000000011000000000000000000000000000000000000000000000003}
+            )
+            .to_string(),
+            field_element(quote! {2}).to_string()
+        );
+    }
 }
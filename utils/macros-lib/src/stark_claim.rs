@@ -0,0 +1,280 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{
+    Data, DeriveInput, Fields, Ident, Index, Lit, Meta, MetaNameValue, NestedMeta, Path, Type,
+};
+
+#[derive(Default)]
+struct Attributes {
+    constraints: Option<Path>,
+    trace:       Option<Path>,
+    witness:     Option<Type>,
+    order:       Option<Vec<Ident>>,
+}
+
+fn parse_attributes(input: &DeriveInput) -> syn::Result<Attributes> {
+    let mut result = Attributes::default();
+    for attribute in input.attrs.iter().filter(|a| a.path.is_ident("stark")) {
+        let list = match attribute.parse_meta()? {
+            Meta::List(list) => list,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    attribute,
+                    "Expected #[stark(key = \"value\", ...)]",
+                ))
+            }
+        };
+        for nested in list.nested {
+            let (path, value) = match nested {
+                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                    path,
+                    lit: Lit::Str(value),
+                    ..
+                })) => (path, value),
+                other => return Err(syn::Error::new_spanned(other, "Expected key = \"value\"")),
+            };
+            if path.is_ident("constraints") {
+                result.constraints = Some(value.parse()?);
+            } else if path.is_ident("trace") {
+                result.trace = Some(value.parse()?);
+            } else if path.is_ident("witness") {
+                result.witness = Some(value.parse()?);
+            } else if path.is_ident("order") {
+                result.order = Some(
+                    value
+                        .value()
+                        .split(',')
+                        .map(|name| syn::parse_str(name.trim()))
+                        .collect::<syn::Result<_>>()?,
+                );
+            } else {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "Expected one of `constraints`, `trace`, `witness` or `order`",
+                ));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Writes every field of the struct in declaration order, or in the order
+/// given by the `order` attribute.
+fn claim_bytes_body(input: &DeriveInput, order: Option<&[Ident]>) -> syn::Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "StarkClaim can only be derived for structs",
+            ))
+        }
+    };
+    let writes = match fields {
+        Fields::Named(fields) => {
+            let mut names: Vec<&Ident> = fields
+                .named
+                .iter()
+                .filter_map(|field| field.ident.as_ref())
+                .collect();
+            if let Some(order) = order {
+                let mut sorted = names.clone();
+                let mut sorted_order: Vec<&Ident> = order.iter().collect();
+                sorted.sort();
+                sorted_order.sort();
+                if sorted != sorted_order {
+                    return Err(syn::Error::new(
+                        Span::call_site(),
+                        "`order` must list every field exactly once",
+                    ));
+                }
+                names = order.iter().collect();
+            }
+            names
+                .into_iter()
+                .map(|name| {
+                    quote! { ::zkp_stark::ClaimBytes::write_claim_bytes(&self.#name, bytes); }
+                })
+                .collect::<Vec<_>>()
+        }
+        Fields::Unnamed(_) | Fields::Unit if order.is_some() => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`order` requires named fields",
+            ))
+        }
+        Fields::Unnamed(fields) => {
+            (0..fields.unnamed.len())
+                .map(|index| {
+                    let index = Index::from(index);
+                    quote! { ::zkp_stark::ClaimBytes::write_claim_bytes(&self.#index, bytes); }
+                })
+                .collect::<Vec<_>>()
+        }
+        Fields::Unit => vec![quote! { let _ = bytes; }],
+    };
+    Ok(quote! { #(#writes)* })
+}
+
+fn derive(input: TokenStream) -> syn::Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let attributes = parse_attributes(&input)?;
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let claim_bytes = claim_bytes_body(&input, attributes.order.as_deref())?;
+    let mut result = quote! {
+        impl #impl_generics ::zkp_stark::ClaimBytes for #name #type_generics #where_clause {
+            fn write_claim_bytes(&self, bytes: &mut ::zkp_stark::prelude::Vec<u8>) {
+                #claim_bytes
+            }
+        }
+
+        impl #impl_generics ::core::convert::From<&#name #type_generics>
+            for ::zkp_stark::prelude::Vec<u8> #where_clause
+        {
+            fn from(claim: &#name #type_generics) -> Self {
+                ::zkp_stark::ClaimBytes::claim_bytes(claim)
+            }
+        }
+    };
+
+    if let Some(constraints) = &attributes.constraints {
+        result.extend(quote! {
+            impl #impl_generics ::zkp_stark::Verifiable for #name #type_generics #where_clause {
+                fn constraints(&self) -> ::zkp_stark::Constraints {
+                    #constraints(self)
+                }
+            }
+        });
+    }
+
+    match (&attributes.trace, &attributes.witness) {
+        (Some(trace), Some(witness)) => {
+            result.extend(quote! {
+                #[cfg(feature = "prover")]
+                impl #impl_generics ::zkp_stark::Provable<&#witness>
+                    for #name #type_generics #where_clause
+                {
+                    fn trace(&self, witness: &#witness) -> ::zkp_stark::TraceTable {
+                        #trace(self, witness)
+                    }
+                }
+            });
+        }
+        (None, None) => {}
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "`trace` and `witness` must be given together",
+            ))
+        }
+    }
+    Ok(result)
+}
+
+/// Derive `ClaimBytes`, `Verifiable` and `Provable` for a claim struct.
+///
+/// The `ClaimBytes` encoding writes all fields in declaration order, unless
+/// an `order = "a, b"` attribute lists them in another order. The other two
+/// traits are implemented by delegating to the functions named in the `stark`
+/// attribute. The `Provable` implementation is only compiled with the
+/// `prover` feature of the deriving crate.
+///
+/// ```ignore
+/// #[derive(StarkClaim)]
+/// #[stark(constraints = "get_constraints", trace = "get_trace", witness = "Witness")]
+/// struct Claim { .. }
+///
+/// fn get_constraints(claim: &Claim) -> Constraints { .. }
+/// fn get_trace(claim: &Claim, witness: &Witness) -> TraceTable { .. }
+/// ```
+pub fn stark_claim(input: TokenStream) -> TokenStream {
+    derive(input).unwrap_or_else(|err: syn::Error| err.to_compile_error())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stark_claim_bytes_only() {
+        assert_eq!(
+            stark_claim(quote! { struct Claim { index: usize, value: FieldElement } }).to_string(),
+            quote! {
+                impl ::zkp_stark::ClaimBytes for Claim {
+                    fn write_claim_bytes(&self, bytes: &mut ::zkp_stark::prelude::Vec<u8>) {
+                        ::zkp_stark::ClaimBytes::write_claim_bytes(&self.index, bytes);
+                        ::zkp_stark::ClaimBytes::write_claim_bytes(&self.value, bytes);
+                    }
+                }
+
+                impl ::core::convert::From<&Claim> for ::zkp_stark::prelude::Vec<u8> {
+                    fn from(claim: &Claim) -> Self {
+                        ::zkp_stark::ClaimBytes::claim_bytes(claim)
+                    }
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn stark_claim_provable() {
+        let result = stark_claim(quote! {
+            #[stark(constraints = "constraints", trace = "trace", witness = "Witness")]
+            struct Claim(FieldElement);
+        })
+        .to_string();
+        assert!(result.contains(
+            &quote! {
+                #[cfg(feature = "prover")]
+                impl ::zkp_stark::Provable<&Witness> for Claim
+            }
+            .to_string()
+        ));
+        assert!(result.contains(&quote! { trace(self, witness) }.to_string()));
+        assert!(result.contains(&quote! { constraints(self) }.to_string()));
+        assert!(result.contains(&quote! { write_claim_bytes(&self.0, bytes) }.to_string()));
+    }
+
+    #[test]
+    fn stark_claim_order() {
+        let result = stark_claim(quote! {
+            #[stark(order = "b, a")]
+            struct Claim { a: usize, b: usize }
+        })
+        .to_string();
+        assert!(result.contains(
+            &quote! {
+                ::zkp_stark::ClaimBytes::write_claim_bytes(&self.b, bytes);
+                ::zkp_stark::ClaimBytes::write_claim_bytes(&self.a, bytes);
+            }
+            .to_string()
+        ));
+    }
+
+    #[test]
+    fn stark_claim_negative() {
+        assert_eq!(
+            stark_claim(quote! { enum Claim {} }).to_string(),
+            quote! {compile_error ! { "StarkClaim can only be derived for structs" }}.to_string()
+        );
+        assert_eq!(
+            stark_claim(quote! {
+                #[stark(trace = "trace")]
+                struct Claim;
+            })
+            .to_string(),
+            quote! {compile_error ! { "`trace` and `witness` must be given together" }}.to_string()
+        );
+        assert_eq!(
+            stark_claim(quote! {
+                #[stark(order = "a")]
+                struct Claim { a: usize, b: usize }
+            })
+            .to_string(),
+            quote! {compile_error ! { "`order` must list every field exactly once" }}.to_string()
+        );
+    }
+}
@@ -41,7 +41,7 @@
 mod mmap_vec;
 
 #[cfg(feature = "std")]
-pub use crate::mmap_vec::MmapVec;
+pub use crate::mmap_vec::{MmapVec, Storage};
 
 // In no std mode, substitute no_std_compat
 #[cfg(not(feature = "std"))]
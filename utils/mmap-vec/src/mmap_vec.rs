@@ -8,36 +8,80 @@ use std::{
     mem::size_of,
     ops::{Deref, DerefMut},
     prelude::v1::*,
-    slice,
+    ptr, slice,
 };
 use tempfile::tempfile;
 
-// TODO: Variant of MmapVec where it switched between Vec and Mmap after
-//       a treshold size.
+/// Selects heap or temporary file storage for new [`MmapVec`] allocations.
+///
+/// Allocations of at least `threshold` bytes are backed by a temporary file,
+/// smaller ones are kept on the heap. The default backs all allocations by
+/// files.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Storage {
+    pub threshold: usize,
+}
+
+impl Storage {
+    /// Back all allocations by temporary files.
+    pub const FILE: Self = Self { threshold: 0 };
+    /// Keep all allocations on the heap.
+    pub const MEMORY: Self = Self {
+        threshold: usize::max_value(),
+    };
+
+    /// Back allocations of at least `bytes` bytes by temporary files.
+    pub fn with_threshold(bytes: usize) -> Self {
+        Self { threshold: bytes }
+    }
+}
+
+#[derive(Debug)]
+enum Backing<T> {
+    // The `Vec` is zero initialized up to capacity but always has length zero,
+    // so it only manages the allocation.
+    Memory(Vec<T>),
+    File(MmapMut),
+}
 
 #[derive(Debug)] // TODO: Custom implementation
 pub struct MmapVec<T: Clone> {
-    mmap:     MmapMut,
+    backing:  Backing<T>,
     length:   usize,
     capacity: usize,
     _t:       PhantomData<T>,
 }
 
 impl<T: Clone> MmapVec<T> {
+    /// An empty vector with room for `capacity` values in a temporary file.
     pub fn with_capacity(capacity: usize) -> Self {
-        // From https://docs.rs/tempfile/3.1.0/tempfile/: tempfile() relies on
-        // the OS to remove the temporary file once the last handle is closed.
-        let file = tempfile().expect("cannot create temporary file");
-        // TODO: Round up to nearest 4KB
-        // Note: mmaped files can not be empty, so we use at leas one byte.
-        let size = max(1, capacity * size_of::<T>());
-        info!("Allocating {} MB in temp file", size / 1_000_000);
-        file.set_len(size as u64)
-            .expect("cannot set mmap file length");
-        let mmap = unsafe { MmapOptions::new().len(size).map_mut(&file) }
-            .expect("cannot access memory mapped file");
+        Self::with_capacity_in(capacity, Storage::default())
+    }
+
+    /// An empty vector with room for `capacity` values kept as selected by
+    /// `storage`.
+    pub fn with_capacity_in(capacity: usize, storage: Storage) -> Self {
+        let bytes = capacity * size_of::<T>();
+        let backing = if bytes < storage.threshold {
+            let mut vec = Vec::with_capacity(capacity);
+            unsafe { ptr::write_bytes(vec.as_mut_ptr(), 0, capacity) };
+            Backing::Memory(vec)
+        } else {
+            // From https://docs.rs/tempfile/3.1.0/tempfile/: tempfile() relies on
+            // the OS to remove the temporary file once the last handle is closed.
+            let file = tempfile().expect("cannot create temporary file");
+            // TODO: Round up to nearest 4KB
+            // Note: mmaped files can not be empty, so we use at leas one byte.
+            let size = max(1, bytes);
+            info!("Allocating {} MB in temp file", size / 1_000_000);
+            file.set_len(size as u64)
+                .expect("cannot set mmap file length");
+            let mmap = unsafe { MmapOptions::new().len(size).map_mut(&file) }
+                .expect("cannot access memory mapped file");
+            Backing::File(mmap)
+        };
         Self {
-            mmap,
+            backing,
             length: 0,
             capacity,
             _t: PhantomData,
@@ -47,11 +91,25 @@ impl<T: Clone> MmapVec<T> {
     // TODO: Maybe we should do something like a Zeroed trait?
     // See https://github.com/rust-lang/rfcs/issues/2626
     pub unsafe fn zero_initialized(len: usize) -> Self {
-        let mut result = Self::with_capacity(len);
+        Self::zero_initialized_in(len, Storage::default())
+    }
+
+    /// Like [`zero_initialized`](#method.zero_initialized) with the storage
+    /// selected by `storage`.
+    pub unsafe fn zero_initialized_in(len: usize, storage: Storage) -> Self {
+        let mut result = Self::with_capacity_in(len, storage);
         result.length = len;
         result
     }
 
+    /// Whether the values are stored in a temporary file.
+    pub fn is_file_backed(&self) -> bool {
+        match self.backing {
+            Backing::Memory(_) => false,
+            Backing::File(_) => true,
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.length == 0
     }
@@ -110,7 +168,12 @@ impl<T: Clone + PartialEq> PartialEq for MmapVec<T> {
 
 impl<T: Clone> Clone for MmapVec<T> {
     fn clone(&self) -> Self {
-        let mut clone = Self::with_capacity(self.capacity);
+        let storage = if self.is_file_backed() {
+            Storage::FILE
+        } else {
+            Storage::MEMORY
+        };
+        let mut clone = Self::with_capacity_in(self.capacity, storage);
         clone.extend(self.iter());
         clone
     }
@@ -140,14 +203,22 @@ impl<T: Clone> Deref for MmapVec<T> {
 
     #[inline]
     fn deref(&self) -> &[T] {
-        unsafe { slice::from_raw_parts(self.mmap.as_ptr() as *const T, self.length) }
+        let pointer = match &self.backing {
+            Backing::Memory(vec) => vec.as_ptr(),
+            Backing::File(mmap) => mmap.as_ptr() as *const T,
+        };
+        unsafe { slice::from_raw_parts(pointer, self.length) }
     }
 }
 
 impl<T: Clone> DerefMut for MmapVec<T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut [T] {
-        unsafe { slice::from_raw_parts_mut(self.mmap.as_mut_ptr() as *mut T, self.length) }
+        let pointer = match &mut self.backing {
+            Backing::Memory(vec) => vec.as_mut_ptr(),
+            Backing::File(mmap) => mmap.as_mut_ptr() as *mut T,
+        };
+        unsafe { slice::from_raw_parts_mut(pointer, self.length) }
     }
 }
 
@@ -198,6 +269,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_threshold() {
+        let storage = Storage::with_threshold(1000);
+        let mut small: MmapVec<u64> = MmapVec::with_capacity_in(10, storage);
+        let large: MmapVec<u64> = MmapVec::with_capacity_in(1000, storage);
+        let zeros = unsafe { MmapVec::<u64>::zero_initialized_in(5, storage) };
+        assert!(!small.is_file_backed());
+        assert!(large.is_file_backed());
+        assert!(!zeros.is_file_backed());
+        assert!(MmapVec::<u64>::with_capacity(1).is_file_backed());
+        assert!(!MmapVec::<u64>::with_capacity_in(1000, Storage::MEMORY).is_file_backed());
+        assert_eq!(zeros.as_slice(), &[0; 5]);
+        small.extend(0..10);
+        assert_eq!(small.as_slice(), &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(!small.clone().is_file_backed());
+        assert!(large.clone().is_file_backed());
+    }
+
     #[test]
     #[should_panic]
     fn test_cannot_index_beyond_end() {